@@ -1,10 +1,13 @@
-use std::path::Path;
+use std::{collections::BTreeMap, path::Path};
 
+use jot_core::NoteLimits;
 use serde::Serialize;
 
 use crate::{
     args::ConfigArgs,
-    profile::{self, Profile},
+    error::{CliError, ErrorKind},
+    profile::{self, DashboardConfig, HooksConfig, NoteTemplate, Profile, SavedSearch},
+    utils::retention::RetentionPolicy,
 };
 
 #[derive(Debug, Serialize)]
@@ -14,11 +17,37 @@ pub struct AppConfig {
     pub db_path: String,
     pub profile_exists: bool,
     pub default_tags: Vec<String>,
+    pub ephemeral: bool,
+    pub note_limits: NoteLimits,
+    pub retention: Vec<RetentionPolicy>,
+    pub inbox_on_down: bool,
+    pub suggest_tags: bool,
+    pub capture_context: bool,
+    pub encrypt: bool,
+    pub shell_aliases: BTreeMap<String, String>,
+    pub server_url: Option<String>,
+    pub server_cert_sha256: Option<String>,
+    pub server_cert_url: Option<String>,
+    pub timezone: Option<String>,
+    pub date_format: Option<String>,
+    pub week_start: Option<String>,
+    pub journal_dir: Option<String>,
+    pub usage_history: bool,
+    pub plain_structure: bool,
+    pub hooks: HooksConfig,
+    pub confirm_delete: bool,
+    pub confirm_dedupe: bool,
+    pub confirm_expire: bool,
+    pub confirm_prune: bool,
+    pub searches: BTreeMap<String, SavedSearch>,
+    pub templates: BTreeMap<String, NoteTemplate>,
+    pub dashboard: DashboardConfig,
+    pub statuses: Vec<String>,
 }
 
 impl AppConfig {
     pub fn from_args(
-        _args: ConfigArgs,
+        args: ConfigArgs,
         profile_path: &Path,
         profile: Option<&Profile>,
         profile_name: &str,
@@ -35,6 +64,16 @@ impl AppConfig {
 
         let default_tags = profile.map(|p| p.default_tags.clone()).unwrap_or_default();
 
+        let defaults = NoteLimits::default();
+        let note_limits = NoteLimits {
+            max_content_bytes: profile
+                .and_then(|p| p.max_content_bytes)
+                .unwrap_or(defaults.max_content_bytes),
+            max_tags: profile
+                .and_then(|p| p.max_tags)
+                .unwrap_or(defaults.max_tags),
+        };
+
         AppConfig {
             profile_name: profile_name.to_string(),
             profile_exists: profile.is_some(),
@@ -44,6 +83,61 @@ impl AppConfig {
                 .unwrap_or_else(|| "./".to_string()),
             db_path,
             default_tags,
+            ephemeral: args.ephemeral,
+            note_limits,
+            retention: profile.map(|p| p.retention.clone()).unwrap_or_default(),
+            inbox_on_down: profile.is_some_and(|p| p.inbox_on_down),
+            suggest_tags: profile.is_some_and(|p| p.suggest_tags),
+            capture_context: profile.is_some_and(|p| p.capture_context),
+            encrypt: profile.is_some_and(|p| p.encrypt),
+            server_url: profile.and_then(|p| p.server_url.clone()),
+            server_cert_sha256: profile.and_then(|p| p.server_cert_sha256.clone()),
+            server_cert_url: profile.and_then(|p| p.server_cert_url.clone()),
+            shell_aliases: profile.map(|p| p.shell_aliases.clone()).unwrap_or_default(),
+            timezone: profile.and_then(|p| p.timezone.clone()),
+            date_format: profile.and_then(|p| p.date_format.clone()),
+            week_start: profile.and_then(|p| p.week_start.clone()),
+            journal_dir: profile.and_then(|p| p.journal_dir.clone()),
+            usage_history: profile.is_some_and(|p| p.usage_history),
+            plain_structure: args.plain_structure || profile.is_some_and(|p| p.plain_structure),
+            hooks: profile.and_then(|p| p.hooks.clone()).unwrap_or_default(),
+            confirm_delete: profile.and_then(|p| p.confirm_delete).unwrap_or(true),
+            confirm_dedupe: profile.and_then(|p| p.confirm_dedupe).unwrap_or(true),
+            confirm_expire: profile.and_then(|p| p.confirm_expire).unwrap_or(true),
+            confirm_prune: profile.and_then(|p| p.confirm_prune).unwrap_or(true),
+            searches: profile.map(|p| p.searches.clone()).unwrap_or_default(),
+            templates: profile.map(|p| p.templates.clone()).unwrap_or_default(),
+            dashboard: profile
+                .and_then(|p| p.dashboard.clone())
+                .unwrap_or_default(),
+            statuses: profile
+                .map(|p| p.statuses.clone())
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(profile::default_statuses),
+        }
+    }
+
+    /// The certificate fingerprint pinned for the server at `base`, if any;
+    /// a pin never applies to a server other than the one it was recorded for
+    pub fn pinned_cert(&self, base: &str) -> Option<&str> {
+        let pinned_url = self.server_cert_url.as_ref().or(self.server_url.as_ref())?;
+        (pinned_url.trim_end_matches('/') == base.trim_end_matches('/'))
+            .then_some(self.server_cert_sha256.as_deref())
+            .flatten()
+    }
+
+    /// Fail unless `status` is one of the profile's `statuses`
+    pub fn check_status(&self, status: &str) -> Result<(), CliError> {
+        if self.statuses.iter().any(|s| s == status) {
+            return Ok(());
         }
+        Err(CliError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Unknown status '{}' (use {})",
+                status,
+                self.statuses.join(", ")
+            ),
+        ))
     }
 }
@@ -1,7 +1,10 @@
-use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
 
-use crate::utils::{date_source::DateSource, date_target::DateTarget};
+use crate::utils::{
+    date_source::DateSource, date_target::DateTarget, interval::Interval,
+    retention::RetentionPeriod,
+};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -16,6 +19,30 @@ pub struct CliArgs {
 
     #[command(flatten)]
     pub config: ConfigArgs,
+
+    /// Error output format (`json` reports failures as `{"error": {"kind", "message"}}` on stderr)
+    #[arg(long, value_enum, env = "JOT_OUTPUT")]
+    pub output: Option<OutputFormat>,
+}
+
+impl CliArgs {
+    /// Whether failures should be reported as JSON: requested globally, or by
+    /// a subcommand that was asked for JSON output
+    pub fn json_errors(&self) -> bool {
+        let command_output = match &self.command {
+            Some(Command::Note(NoteCommand::Search(args))) | Some(Command::List(args)) => {
+                Some(&args.output)
+            }
+            Some(Command::Note(NoteCommand::Last(args))) => Some(&args.output),
+            Some(Command::Sync(args)) => Some(&args.output),
+            Some(Command::Note(NoteCommand::Show(args))) | Some(Command::Show(args)) => {
+                Some(&args.output)
+            }
+            _ => None,
+        };
+
+        self.output.as_ref().or(command_output) == Some(&OutputFormat::Json)
+    }
 }
 
 #[derive(Debug, Args, Serialize)]
@@ -23,6 +50,15 @@ pub struct ConfigArgs {
     /// Profile name to use
     #[arg(long, short, env = "JOT_PROFILE")]
     pub profile: Option<String>,
+
+    /// Use a throwaway in-memory database (nothing is saved)
+    #[arg(long, global = true)]
+    pub ephemeral: bool,
+
+    /// Screen-reader-friendly output: one labelled field per line, no
+    /// symbols, colors or aligned columns
+    #[arg(long, global = true)]
+    pub plain_structure: bool,
 }
 
 #[derive(Debug, Subcommand, PartialEq)]
@@ -39,29 +75,480 @@ pub enum Command {
     Note(NoteCommand),
     /// Creates a new note. Alias for 'note add'.
     Down(NoteAddArgs),
+    /// Turn each line (or delimited block) of a stream into a note
+    Capture(CaptureArgs),
     /// Search notes. Alias for 'note search'.
     #[clap(name = "ls")]
     List(NoteSearchArgs),
     /// Show a note with full details. Alias for 'note show'.
     Show(NoteShowArgs),
+    /// Manage saved searches (run one with `jot ls @name`)
+    Search {
+        #[clap(subcommand)]
+        command: Option<SearchCommand>,
+    },
+    /// List notes tagged #inbox (or clear them once triaged)
+    Inbox {
+        #[clap(subcommand)]
+        command: Option<InboxCommand>,
+    },
+    /// Deleted notes: list them, restore them or remove them for good
+    #[clap(subcommand)]
+    Trash(TrashCommand),
+    /// Tag tools
+    #[clap(subcommand)]
+    Tag(TagCommand),
+    /// Pinned notes, today's notes and open tasks at a glance (sections set in the profile)
+    Dashboard,
+    /// Notes grouped by status in columns (kanban board)
+    Board(BoardArgs),
+    /// Month calendar with the number of notes per day; pick a day with the
+    /// arrow keys (or --day) to list its notes
+    Cal(CalArgs),
+    /// Local usage statistics: most used commands, notes per week, tag trends
+    Usage(UsageArgs),
+    /// Track time spent on notes
+    #[clap(subcommand)]
+    Track(TrackCommand),
+    /// Notes created on a schedule (e.g. every monday)
+    #[clap(subcommand)]
+    Recur(RecurCommand),
+    /// Import notes from other apps
+    #[clap(subcommand)]
+    Import(ImportCommand),
+    /// Write every note to a directory as a Markdown file
+    Export(ExportArgs),
+    /// Local database maintenance
+    #[clap(subcommand)]
+    Db(DbCommand),
+    /// Developer tools (seed data, diagnostics)
+    #[clap(subcommand, hide = true)]
+    Dev(DevCommand),
+    /// Print suggested shell aliases (e.g. `eval "$(jot alias --shell zsh)"`)
+    Alias {
+        /// Shell syntax to generate
+        #[arg(long, value_enum)]
+        shell: AliasShell,
+    },
+    /// Accounts on the sync server
+    #[clap(subcommand)]
+    Auth(AuthCommand),
+    /// Send local changes to the sync server and apply the server's changes
+    Sync(SyncArgs),
+    /// Sync server tools
+    #[clap(subcommand)]
+    Server(ServerCommand),
     /// Generate shell completion scripts
     Completion {
         /// Shell type
         #[arg(value_enum)]
         shell: clap_complete::Shell,
     },
+    /// Any other name runs the `jot-<name>` plugin from PATH
+    #[command(external_subcommand)]
+    External(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq)]
+pub enum AliasShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum ServerCommand {
+    /// Check the server's health, version and whether the stored credential is accepted
+    Ping(ServerPingArgs),
+    /// Fill a fresh local database with a full snapshot of the server's notes
+    Bootstrap(ServerBootstrapArgs),
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct ServerBootstrapArgs {
+    /// Server URL (defaults to the profile's `server_url`)
+    #[arg(long)]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct BoardArgs {
+    /// Only show notes with these tags (can be specified multiple times or comma-separated)
+    #[arg(long, short = 't', value_name = "TAGS", value_delimiter = ',')]
+    pub tag: Vec<String>,
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct CalArgs {
+    /// Month to show (e.g. 2025-01, "next month"; default this month)
+    #[arg(value_parser = parse_date_target, conflicts_with = "day")]
+    pub month: Option<DateTarget>,
+    /// List the notes of this day below its month instead of picking one
+    /// interactively
+    #[arg(long, value_parser = parse_date_source)]
+    pub day: Option<DateSource>,
+    /// Output format of the listed notes (pretty, plain, json, id or markdown)
+    #[arg(long, value_enum, default_value_t = OutputFormat::Pretty)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum TrackCommand {
+    /// Start tracking time on a note (stops whatever is running)
+    Start {
+        /// Note ID or alias
+        id: String,
+    },
+    /// Stop the running time entry
+    Stop,
+    /// Hours per note and per tag, for today unless --week is given
+    Report(TrackReportArgs),
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct TrackReportArgs {
+    /// Report the current week (starting on the profile's `week_start`)
+    #[arg(long)]
+    pub week: bool,
+}
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum RecurCommand {
+    /// Add a rule creating a note on a schedule
+    Add(RecurAddArgs),
+    /// List the rules and their next occurrence
+    List,
+    /// Remove a rule (notes it already created stay)
+    Remove {
+        /// Rule number from `jot recur list`
+        id: i64,
+    },
+    /// Create the notes of every occurrence up to today that doesn't exist yet
+    /// (safe to run from cron)
+    Run,
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct RecurAddArgs {
+    /// Note content
+    #[arg(trailing_var_arg = true, required = true)]
+    pub content: Vec<String>,
+    /// Interval: day, weekday (Monday to Friday), week, month, or a day name like monday
+    #[arg(long, value_name = "INTERVAL")]
+    pub every: String,
+    /// Add tags to the notes (can be specified multiple times or comma-separated)
+    #[arg(long, short = 't', value_name = "TAGS", value_delimiter = ',')]
+    pub tag: Vec<String>,
+    /// First day the rule applies (default today)
+    #[arg(long, value_parser = parse_date_source, default_value_t = DateSource::Today)]
+    pub from: DateSource,
+}
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum AuthCommand {
+    /// Create an account on the sync server and store its credential in the profile
+    Register(AuthRegisterArgs),
+    /// Log in to the sync server and store the credential in the profile
+    Login(AuthLoginArgs),
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct AuthLoginArgs {
+    /// Email of the account
+    #[arg(long)]
+    pub email: String,
+
+    /// Read the password from the first line of stdin instead of prompting
+    #[arg(long)]
+    pub password_stdin: bool,
+
+    /// Server URL (defaults to the profile's `server_url`)
+    #[arg(long)]
+    pub url: Option<String>,
+
+    /// Trust the server's certificate without a CA (e.g. self-signed) and pin
+    /// its fingerprint in the profile, so no other certificate is accepted later.
+    /// The fingerprint is shown and has to be confirmed
+    #[arg(long)]
+    pub insecure_pin: bool,
+
+    /// Pin the server's certificate only if its SHA-256 fingerprint is this
+    /// one (e.g. from `openssl x509 -noout -fingerprint -sha256`), without asking
+    #[arg(long, value_name = "SHA256")]
+    pub fingerprint: Option<String>,
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct AuthRegisterArgs {
+    /// Email to sign up with
+    #[arg(long)]
+    pub email: String,
+
+    /// Display name (defaults to the email's local part)
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Read the password from the first line of stdin instead of prompting
+    #[arg(long)]
+    pub password_stdin: bool,
+
+    /// Server URL (defaults to the profile's `server_url`)
+    #[arg(long)]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct SyncArgs {
+    /// Server URL (defaults to the profile's `server_url`)
+    #[arg(long)]
+    pub url: Option<String>,
+
+    /// `json` prints a summary with pushed, pulled and conflict counts
+    #[arg(long, value_enum, default_value_t = OutputFormat::Pretty)]
+    pub output: OutputFormat,
+
+    #[command(subcommand)]
+    pub command: Option<SyncCommand>,
+}
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum SyncCommand {
+    /// Keep syncing: right after local changes, every --interval otherwise
+    Daemon(SyncDaemonArgs),
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct SyncDaemonArgs {
+    /// Time between syncs when nothing changes locally, e.g. 30s, 5m, 1h
+    #[arg(long, value_name = "INTERVAL", value_parser = parse_interval, default_value = "5m")]
+    pub interval: Interval,
+
+    /// Run in the background, logging to a file next to the profile database
+    #[arg(long)]
+    pub detach: bool,
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct ServerPingArgs {
+    /// Server URL (defaults to the profile's `server_url`)
+    #[arg(long)]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum SearchCommand {
+    /// Save a filter combination under a name (replaces an existing one)
+    Save(SearchSaveArgs),
+    /// List saved searches
+    List,
+    /// Delete a saved search
+    #[clap(visible_alias = "rm")]
+    Remove { name: String },
+}
+
+#[derive(Debug, Args, PartialEq)]
+pub struct SearchSaveArgs {
+    /// Name to run the search by, as `jot ls @NAME`
+    pub name: String,
+
+    /// Search term to filter notes
+    pub term: Option<String>,
+
+    /// Filter by tags (can be specified multiple times or comma-separated)
+    #[arg(long, short = 't', value_name = "TAGS", value_delimiter = ',')]
+    pub tag: Vec<String>,
+
+    /// Filter by date (e.g., "today", "this week", "week 42", "2025-01", "2024-03-16"), resolved each time the search runs
+    #[arg(long, value_name = "DATE", value_parser = parse_date_target)]
+    pub date: Option<DateTarget>,
+
+    /// Maximum number of results to return
+    #[arg(long, short = 'n')]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Subcommand, Serialize, PartialEq)]
+pub enum InboxCommand {
+    /// Remove the #inbox tag from notes (the notes are kept)
+    Clear {
+        /// Note ID(s) or aliases
+        #[arg(value_name = "ID", required = true)]
+        ids: Vec<String>,
+    },
+}
+
+#[derive(Debug, Subcommand, Serialize, PartialEq)]
+pub enum TrashCommand {
+    /// List deleted notes, most recently deleted first
+    List {
+        /// Output format (pretty, plain, json, id or markdown)
+        #[arg(long, value_enum, default_value_t = OutputFormat::Pretty)]
+        output: OutputFormat,
+    },
+    /// Bring deleted notes back
+    Restore {
+        /// Note ID(s), full or a unique prefix
+        #[arg(value_name = "ID", required = true)]
+        ids: Vec<String>,
+    },
+    /// Permanently remove deleted notes (ones this device hasn't synced yet are kept)
+    Empty(TrashEmptyArgs),
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+pub struct TrashEmptyArgs {
+    /// Only remove notes deleted longer ago than this, e.g. 30d, 12w, 6m, 1y
+    #[arg(long, value_name = "PERIOD", value_parser = parse_retention_period)]
+    pub older_than: Option<RetentionPeriod>,
+
+    /// Skip confirmation prompt
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+}
+
+#[derive(Debug, Subcommand, Serialize, PartialEq)]
+pub enum ImportCommand {
+    /// Decrypted Standard Notes backup (the .zip or the .txt inside it), with tags and trash
+    #[clap(name = "standardnotes")]
+    StandardNotes {
+        /// Path to the backup
+        path: std::path::PathBuf,
+    },
+    /// Folder of HTML or Markdown files from an Apple Notes exporter; subfolders become tags
+    AppleNotes {
+        /// Path to the exported folder
+        dir: std::path::PathBuf,
+    },
+    /// Folder of Markdown files, `jot export` output or an Obsidian vault; frontmatter tags
+    /// and dates are kept and subfolders become tags
+    #[clap(visible_alias = "obsidian")]
+    Markdown {
+        /// Path to the folder
+        dir: std::path::PathBuf,
+    },
+    /// JSON list of notes as printed by `jot ls --output json`
+    Json {
+        /// Path to the JSON file
+        path: std::path::PathBuf,
+    },
+}
+
+#[derive(Args, Debug, Serialize, PartialEq)]
+pub struct ExportArgs {
+    /// Directory to write to (created if missing)
+    pub dir: std::path::PathBuf,
+
+    /// Only write notes changed since the last export to this directory, and remove deleted ones
+    #[arg(long)]
+    pub incremental: bool,
+}
+
+#[derive(Args, Debug, Serialize, PartialEq)]
+pub struct UsageArgs {
+    /// Number of weeks shown (tag trends compare them with the weeks before)
+    #[arg(long, value_name = "N", default_value_t = 8)]
+    pub weeks: u32,
+}
+
+#[derive(Debug, Subcommand, Serialize, PartialEq)]
+pub enum TagCommand {
+    /// Recommend tags for some text or an existing note, based on how past notes were tagged
+    Suggest(TagSuggestArgs),
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+pub struct TagSuggestArgs {
+    /// Note ID or alias, or the text to find tags for
+    #[arg(value_name = "CONTENT|ID", required = true)]
+    pub input: Vec<String>,
+
+    /// Tags already chosen (suggestions then include tags often used with them)
+    #[arg(long, short = 't', value_name = "TAGS", value_delimiter = ',')]
+    pub tag: Vec<String>,
+
+    /// Maximum number of suggestions
+    #[arg(long, short = 'n', value_name = "N", default_value_t = 5)]
+    pub limit: usize,
 }
 
 #[derive(Debug, Subcommand, Serialize, PartialEq)]
 pub enum ProfileCommand {
     /// Switch to a profile (creates it if it doesn't exist)
-    Use { name: String },
+    Use {
+        name: String,
+        /// Only for this shell: print `export JOT_PROFILE=...` to eval
+        /// (e.g. `eval "$(jot profile use --session work)"`) instead of
+        /// changing the profile everywhere
+        #[arg(long)]
+        session: bool,
+    },
     /// List all available profiles
     List,
     /// Show current active profile
     Current,
 }
 
+#[derive(Debug, Subcommand, Serialize, PartialEq)]
+pub enum DbCommand {
+    /// Apply pending schema migrations (a backup is taken first)
+    Migrate(DbMigrateArgs),
+    /// Permanently remove old deleted notes (tombstones) and shrink the database
+    Compact(DbCompactArgs),
+    /// Maintenance tasks that rewrite stored notes without changing them
+    Maintain(DbMaintainArgs),
+    /// Rebuild the full-text search index from the notes and verify it
+    Reindex(DbReindexArgs),
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+pub struct DbReindexArgs {
+    /// Only compare the index with the notes, failing if they differ
+    #[arg(long)]
+    pub check: bool,
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+pub struct DbCompactArgs {
+    /// Keep tombstones deleted within this window, e.g. 30d, 12w, 6m, 1y
+    #[arg(long, value_name = "PERIOD", value_parser = parse_retention_period, default_value = "90d")]
+    pub keep: RetentionPeriod,
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+#[command(group(ArgGroup::new("task").required(true).multiple(true).args(["recompress", "encrypt"])))]
+pub struct DbMaintainArgs {
+    /// Compress large note bodies (and expand small ones) per the current threshold
+    #[arg(long)]
+    pub recompress: bool,
+    /// Encrypt notes stored before the profile turned on `encrypt`
+    #[arg(long)]
+    pub encrypt: bool,
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+pub struct DbMigrateArgs {
+    /// Only report the schema version and pending migrations
+    #[arg(long)]
+    pub status: bool,
+}
+
+#[derive(Debug, Subcommand, Serialize, PartialEq)]
+pub enum DevCommand {
+    /// Fill the database with generated notes for performance testing
+    Seed(DevSeedArgs),
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+pub struct DevSeedArgs {
+    /// Number of notes to generate
+    #[arg(long, short = 'n', default_value = "1000")]
+    pub count: usize,
+
+    /// Random seed (the same seed produces the same notes)
+    #[arg(long, default_value = "1")]
+    pub seed: u64,
+}
+
 #[derive(Debug, Subcommand, Serialize, PartialEq)]
 pub enum NoteCommand {
     /// Creates a new note.
@@ -69,17 +556,95 @@ pub enum NoteCommand {
     /// Search notes.
     #[clap(visible_alias = "ls")]
     Search(NoteSearchArgs),
-    /// Get latest note.
+    /// Get latest note(s).
     #[clap(visible_alias = "latest")]
     Last(NoteLatestArgs),
     /// Show a note with full details.
     Show(NoteShowArgs),
     /// Edit a note.
     Edit(NoteEditArgs),
+    /// Append text to an existing note.
+    Append(NoteAppendArgs),
+    /// Open a link from a note in the browser.
+    Open(NoteOpenArgs),
+    /// Hand a note to another device, e.g. as a QR code to scan with a phone.
+    Share(NoteShareArgs),
+    /// Attach a file to a note (replaces an attached file with the same name).
+    Attach(NoteAttachArgs),
+    /// Remove an attached file from a note.
+    Detach(NoteDetachArgs),
+    /// Pin notes to the top of listings and `jot dashboard`.
+    Pin {
+        /// Note ID(s) or aliases
+        #[arg(value_name = "ID", required = true)]
+        ids: Vec<String>,
+    },
+    /// Stop listing notes first and drop them from the dashboard's pinned section.
+    Unpin {
+        /// Note ID(s) or aliases
+        #[arg(value_name = "ID", required = true)]
+        ids: Vec<String>,
+    },
+    /// Archive notes: hide them from listings and search without deleting them.
+    Archive {
+        /// Note ID(s) or aliases
+        #[arg(value_name = "ID", required = true)]
+        ids: Vec<String>,
+    },
+    /// Bring archived notes back into listings and search.
+    Unarchive {
+        /// Note ID(s) or aliases
+        #[arg(value_name = "ID", required = true)]
+        ids: Vec<String>,
+    },
     /// Delete a note (soft delete).
     Delete(NoteDeleteArgs),
     /// Interactive cleanup of notes.
     Prune(NotePruneArgs),
+    /// List notes with identical content (optionally removing the copies).
+    Dedupe(NoteDedupeArgs),
+    /// Delete notes older than a retention window (soft delete).
+    Expire(NoteExpireArgs),
+    /// Friendly names for notes, usable wherever an ID is accepted (defaults to listing them).
+    Alias {
+        #[clap(subcommand)]
+        command: Option<AliasCommand>,
+    },
+}
+
+#[derive(Debug, Subcommand, Serialize, PartialEq)]
+pub enum AliasCommand {
+    /// Point an alias at a note (replaces an existing alias with the same name)
+    Set {
+        name: String,
+        #[arg(value_name = "ID")]
+        id: String,
+    },
+    /// Remove an alias (the note itself is kept)
+    #[clap(visible_alias = "rm")]
+    Remove { name: String },
+    /// List all aliases
+    List,
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+#[command(group(ArgGroup::new("source").required(true).args(["watch", "stdin_stream"])))]
+pub struct CaptureArgs {
+    /// Read from a file or named pipe; a pipe is reopened after each writer closes it
+    #[arg(long, value_name = "PATH")]
+    pub watch: Option<std::path::PathBuf>,
+    /// Read from standard input until it closes
+    #[arg(long)]
+    pub stdin_stream: bool,
+    /// Line separating notes (e.g. `---`); without it every line is a note
+    #[arg(long, value_name = "LINE", allow_hyphen_values = true)]
+    pub delimiter: Option<String>,
+    /// Tags for every captured note (can be specified multiple times or comma-separated)
+    #[arg(long, short = 't', value_name = "TAGS", value_delimiter = ',')]
+    pub tag: Vec<String>,
+    /// Quiet mode: only output the note IDs
+    #[arg(long, short = 'q', default_value_t = false)]
+    pub quiet: bool,
 }
 
 #[derive(Debug, Args, Serialize, PartialEq)]
@@ -93,14 +658,44 @@ pub struct NoteAddArgs {
     /// Open in external editor for interactive editing
     #[arg(long, short = 'e', default_value_t = false)]
     pub editor: bool,
+    /// Run a capture command (e.g. a dictation script) and save its output,
+    /// tagged #voice
+    #[arg(long, value_name = "COMMAND", conflicts_with_all = ["content", "editor"])]
+    pub exec: Option<String>,
     /// Add tags to note (can be specified multiple times or comma-separated)
     #[arg(long, short = 't', value_name = "TAGS", value_delimiter = ',')]
     pub tag: Vec<String>,
+    /// Workflow status, one of the profile's `statuses` (default todo, doing, done)
+    #[arg(long, value_name = "STATUS")]
+    pub status: Option<String>,
+    /// Start from one of the profile's `templates` in the editor, asking
+    /// for its variables first
+    #[arg(long, value_name = "NAME", conflicts_with = "exec")]
+    pub template: Option<String>,
+    /// Value of a template variable instead of asking for it, e.g.
+    /// `--var project=Apollo` (repeatable)
+    #[arg(long, value_name = "NAME=VALUE", requires = "template")]
+    pub var: Vec<String>,
+    /// What to do when a note added today has nearly the same text
+    /// (asked on a terminal; added with a warning otherwise)
+    #[arg(long, value_enum, value_name = "ACTION")]
+    pub if_similar: Option<IfSimilar>,
     /// Quiet mode: only output the note ID
     #[arg(long, short = 'q', default_value_t = false)]
     pub quiet: bool,
 }
 
+/// What `note add` does with a note nearly the same as one added today
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Serialize)]
+pub enum IfSimilar {
+    /// Add it anyway
+    Add,
+    /// Append its text to the earlier note instead
+    Append,
+    /// Add nothing
+    Skip,
+}
+
 #[derive(Debug, Clone, ValueEnum, PartialEq, Serialize, Deserialize, Default)]
 pub enum OutputFormat {
     #[default]
@@ -109,12 +704,14 @@ pub enum OutputFormat {
     Json,
     /// Output only note IDs (one per line)
     Id,
+    /// Markdown with the metadata in TOML frontmatter, as `jot export` writes it
+    Markdown,
 }
 
 #[derive(Debug, clap::Args, PartialEq, Serialize, Deserialize)]
 #[command(about = "Search and list notes")]
 pub struct NoteSearchArgs {
-    /// Search term to filter notes
+    /// Search term to filter notes, or `@name` to run a saved search
     #[arg(default_value = None)]
     pub term: Option<String>,
 
@@ -122,11 +719,23 @@ pub struct NoteSearchArgs {
     #[arg(long, short = 't', value_name = "TAGS", value_delimiter = ',')]
     pub tag: Vec<String>,
 
-    /// Filter by date (e.g., "today", "last week", "2024-03-16")
+    /// Filter by date (e.g., "today", "this week", "week 42", "2025-01", "2024-03-16")
     #[arg(long, value_name = "DATE", value_parser = parse_date_target)]
     pub date: Option<DateTarget>,
 
-    /// Number of lines to display for each note (default: full content)
+    /// Filter by status (e.g. "doing")
+    #[arg(long, value_name = "STATUS")]
+    pub status: Option<String>,
+
+    /// Filter by where notes were captured, e.g. `repo=jot` (can be specified multiple times)
+    #[arg(long, value_name = "KEY=VALUE")]
+    pub meta: Vec<String>,
+
+    /// Include archived notes
+    #[arg(long)]
+    pub archived: bool,
+
+    /// Number of lines to display for each note (default: full content, 0 shows metadata only)
     #[arg(long, short = 'L', value_name = "N")]
     pub lines: Option<usize>,
 
@@ -134,7 +743,19 @@ pub struct NoteSearchArgs {
     #[arg(long, short = 'n')]
     pub limit: Option<i64>,
 
-    /// Output format (pretty, plain, or json)
+    /// Show this page of results (starting at 1, see --per-page)
+    #[arg(long, value_name = "N", conflicts_with = "limit", value_parser = clap::value_parser!(u64).range(1..))]
+    pub page: Option<u64>,
+
+    /// Results per page with --page, or per batch with --all (default: 50)
+    #[arg(long, value_name = "N", conflicts_with = "limit", value_parser = clap::value_parser!(u64).range(1..))]
+    pub per_page: Option<u64>,
+
+    /// Print every result, loading them in batches rather than all at once
+    #[arg(long, conflicts_with_all = ["limit", "page"])]
+    pub all: bool,
+
+    /// Output format (pretty, plain, json, id or markdown)
     #[arg(long, value_enum, default_value_t = OutputFormat::Pretty)]
     pub output: OutputFormat,
 }
@@ -150,9 +771,37 @@ pub struct NoteLatestArgs {
     #[arg(long, short = 't', value_name = "TAGS", value_delimiter = ',')]
     pub tag: Vec<String>,
 
-    /// Output format (pretty, plain, or json)
+    /// Number of most recent notes to show
+    #[arg(long, short = 'n', value_name = "N", default_value_t = 1)]
+    pub count: usize,
+
+    /// Number of lines to display for each note (0 shows metadata only)
+    #[arg(long, short = 'L', value_name = "N")]
+    pub lines: Option<usize>,
+
+    /// Output format (pretty, plain, json, id or markdown)
     #[arg(long, value_enum, default_value_t = OutputFormat::Pretty)]
     pub output: OutputFormat,
+
+    /// Open the latest matching note in the editor instead of showing it
+    #[arg(long, conflicts_with_all = ["count", "delete", "yank"])]
+    pub edit: bool,
+
+    /// Delete the latest matching note instead of showing it
+    #[arg(long, conflicts_with_all = ["count", "yank"])]
+    pub delete: bool,
+
+    /// Copy the latest matching note's content to the clipboard
+    #[arg(long, conflicts_with = "count")]
+    pub yank: bool,
+
+    /// Skip the confirmation prompt of --delete
+    #[arg(long, short = 'y', requires = "delete")]
+    pub yes: bool,
+
+    /// Print only this field of the latest matching note, as it is
+    #[arg(long, value_enum, value_name = "FIELD", conflicts_with_all = ["count", "lines", "output", "edit", "delete", "yank"])]
+    pub field: Option<NoteField>,
 }
 
 impl Default for NoteSearchArgs {
@@ -161,8 +810,14 @@ impl Default for NoteSearchArgs {
             term: None,
             tag: vec![],
             date: None,
+            status: None,
+            meta: vec![],
+            archived: false,
             lines: None,
             limit: None,
+            page: None,
+            per_page: None,
+            all: false,
             output: OutputFormat::Pretty,
         }
     }
@@ -176,15 +831,72 @@ pub fn parse_date_source(s: &str) -> anyhow::Result<DateSource> {
     s.parse()
 }
 
+pub fn parse_retention_period(s: &str) -> anyhow::Result<RetentionPeriod> {
+    s.parse()
+}
+
+pub fn parse_interval(s: &str) -> anyhow::Result<Interval> {
+    s.parse()
+}
+
 #[derive(Debug, Args, Serialize, PartialEq)]
 pub struct NoteShowArgs {
     /// Note ID to show (if not provided, shows the most recent note)
     #[arg(value_name = "ID")]
     pub id: Option<String>,
 
-    /// Output format (pretty, plain, or json)
+    /// Number of content lines to display (default: full content, 0 shows metadata only)
+    #[arg(long, short = 'L', value_name = "N")]
+    pub lines: Option<usize>,
+
+    /// Output format (pretty, plain, json, id or markdown)
     #[arg(long, value_enum, default_value_t = OutputFormat::Pretty)]
     pub output: OutputFormat,
+
+    /// List the files attached to the note
+    #[arg(long)]
+    pub attachments: bool,
+
+    /// Also write the attached files into DIR (those stored on this device)
+    #[arg(long, value_name = "DIR", requires = "attachments")]
+    pub save_to: Option<std::path::PathBuf>,
+
+    /// Print only this field of the note, as it is
+    #[arg(long, value_enum, value_name = "FIELD", conflicts_with_all = ["lines", "output", "attachments"])]
+    pub field: Option<NoteField>,
+}
+
+/// A note field printed on its own by `--field`, for scripts
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Serialize, Deserialize)]
+pub enum NoteField {
+    /// The full content
+    Content,
+    /// The note ID
+    Id,
+    /// The subject date, or the day it was created (YYYY-MM-DD)
+    Date,
+    /// The tags, comma-separated
+    Tags,
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+pub struct NoteAttachArgs {
+    /// Note ID or alias
+    #[arg(value_name = "ID")]
+    pub id: String,
+    /// File to attach
+    #[arg(value_name = "FILE")]
+    pub file: std::path::PathBuf,
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+pub struct NoteDetachArgs {
+    /// Note ID or alias
+    #[arg(value_name = "ID")]
+    pub id: String,
+    /// File name (or attachment ID) to remove
+    #[arg(value_name = "NAME")]
+    pub name: String,
 }
 
 #[derive(Debug, Args, Serialize, PartialEq)]
@@ -192,6 +904,50 @@ pub struct NoteEditArgs {
     /// Note ID to edit (if not provided, edits the most recent note)
     #[arg(value_name = "ID")]
     pub id: Option<String>,
+    /// Set the status instead of opening the editor
+    #[arg(long, value_name = "STATUS")]
+    pub status: Option<String>,
+    /// Clear the status instead of opening the editor
+    #[arg(long, conflicts_with = "status")]
+    pub no_status: bool,
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+pub struct NoteOpenArgs {
+    /// Note ID or alias
+    #[arg(value_name = "ID")]
+    pub id: String,
+
+    /// Which link to open, as numbered by `jot note show`
+    #[arg(value_name = "N", default_value_t = 1)]
+    pub number: usize,
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+#[command(group(ArgGroup::new("method").required(true).args(["qr"])))]
+pub struct NoteShareArgs {
+    /// Note ID or alias
+    #[arg(value_name = "ID")]
+    pub id: String,
+
+    /// Print a QR code of the note's content in the terminal
+    #[arg(long)]
+    pub qr: bool,
+
+    /// Draw the light modules instead, for terminals with light text on a dark background
+    #[arg(long, requires = "qr")]
+    pub invert: bool,
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+pub struct NoteAppendArgs {
+    /// Note ID or alias to append to
+    #[arg(value_name = "ID")]
+    pub id: String,
+
+    /// Text to append (added on a new line)
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+    pub content: Vec<String>,
 }
 
 #[derive(Debug, Args, Serialize, PartialEq)]
@@ -201,8 +957,69 @@ pub struct NoteDeleteArgs {
     pub ids: Vec<String>,
 
     /// Skip confirmation prompt
-    #[arg(long, short = 'y')]
+    #[arg(long, short = 'y', visible_alias = "no-confirm")]
     pub yes: bool,
+
+    /// Ask for confirmation even if the profile turned it off
+    #[arg(long, conflicts_with = "yes")]
+    pub confirm: bool,
+
+    /// Show which notes would be deleted without deleting them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Confirm each note separately instead of all at once
+    #[arg(long, conflicts_with = "yes")]
+    pub step: bool,
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+pub struct NoteDedupeArgs {
+    /// Soft delete duplicates, keeping the oldest note of each group
+    #[arg(long)]
+    pub delete: bool,
+
+    /// Like --delete, but first copy the duplicates' tags onto the kept note
+    #[arg(long, conflicts_with = "delete")]
+    pub merge: bool,
+
+    /// Skip confirmation prompt
+    #[arg(long, short = 'y', visible_alias = "no-confirm")]
+    pub yes: bool,
+
+    /// Ask for confirmation even if the profile turned it off
+    #[arg(long, conflicts_with = "yes")]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Args, Serialize, PartialEq)]
+pub struct NoteExpireArgs {
+    /// Keep notes created within this window, e.g. 30d, 12w, 6m, 1y
+    /// (defaults to the profile's retention policies)
+    #[arg(long, value_name = "PERIOD", value_parser = parse_retention_period)]
+    pub keep: Option<RetentionPeriod>,
+
+    /// Only expire notes with these tags (can be specified multiple times or comma-separated)
+    #[arg(
+        long,
+        short = 't',
+        value_name = "TAGS",
+        value_delimiter = ',',
+        requires = "keep"
+    )]
+    pub tag: Vec<String>,
+
+    /// Only list the notes that would be deleted
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Skip confirmation prompt
+    #[arg(long, short = 'y', visible_alias = "no-confirm")]
+    pub yes: bool,
+
+    /// Ask for confirmation even if the profile turned it off
+    #[arg(long, conflicts_with = "yes")]
+    pub confirm: bool,
 }
 
 #[derive(Debug, Args, Serialize, PartialEq)]
@@ -219,11 +1036,19 @@ pub struct NotePruneArgs {
     #[arg(long, short = 't', value_name = "TAGS", value_delimiter = ',')]
     pub tag: Vec<String>,
 
-    /// Filter by date (e.g., "today", "last week", "2024-03-16")
+    /// Filter by date (e.g., "today", "this week", "week 42", "2025-01", "2024-03-16")
     #[arg(long, value_name = "DATE", value_parser = parse_date_target)]
     pub date: Option<DateTarget>,
 
     /// Search term to filter notes
     #[arg(value_name = "TERM")]
     pub term: Option<String>,
+
+    /// Delete without the final confirmation prompt
+    #[arg(long, short = 'y', visible_alias = "no-confirm")]
+    pub yes: bool,
+
+    /// Ask for confirmation even if the profile turned it off
+    #[arg(long, conflicts_with = "yes")]
+    pub confirm: bool,
 }
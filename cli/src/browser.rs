@@ -0,0 +1,24 @@
+use std::process::Command;
+
+use anyhow::Context;
+
+use crate::editor::split_editor_command;
+
+/// Open `url` with the `BROWSER` command if set, otherwise the system's default browser
+pub fn open_url(url: &str) -> anyhow::Result<()> {
+    let browser = std::env::var("BROWSER").unwrap_or_default();
+    let parts = split_editor_command(&browser);
+    let Some((program, args)) = parts.split_first() else {
+        return webbrowser::open(url).with_context(|| format!("Failed to open '{}'", url));
+    };
+
+    let status = Command::new(program)
+        .args(args)
+        .arg(url)
+        .status()
+        .with_context(|| format!("Failed to open '{}' with '{}'", url, browser))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("Browser returned non-zero exit code"));
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+use anyhow::Context;
+
+use crate::editor::split_editor_command;
+
+/// Clipboard tools tried in order when `JOT_CLIPBOARD` is unset
+#[cfg(target_os = "macos")]
+const CLIPBOARD_TOOLS: &[&[&str]] = &[&["pbcopy"]];
+#[cfg(windows)]
+const CLIPBOARD_TOOLS: &[&[&str]] = &[&["clip"]];
+#[cfg(not(any(target_os = "macos", windows)))]
+const CLIPBOARD_TOOLS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+];
+
+/// Copy `text` to the system clipboard by piping it into a clipboard tool
+pub fn copy_to_clipboard(text: &str) -> anyhow::Result<()> {
+    if let Some(tool) = std::env::var("JOT_CLIPBOARD")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+    {
+        let parts = split_editor_command(&tool);
+        return pipe_into(&parts, text)
+            .with_context(|| format!("Failed to run clipboard command '{}'", tool))?
+            .then_some(())
+            .ok_or_else(|| anyhow::anyhow!("Clipboard command '{}' failed", tool));
+    }
+
+    for tool in CLIPBOARD_TOOLS {
+        let parts: Vec<String> = tool.iter().map(|s| s.to_string()).collect();
+        match pipe_into(&parts, text) {
+            Ok(true) => return Ok(()),
+            // Installed but unusable (e.g. wl-copy outside Wayland), try the next one
+            Ok(false) => continue,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e).with_context(|| format!("Failed to run '{}'", tool[0])),
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "No clipboard tool found (install wl-copy, xclip or xsel, or set JOT_CLIPBOARD)"
+    ))
+}
+
+/// Run `command` with `text` on stdin, returning whether it exited successfully
+fn pipe_into(command: &[String], text: &str) -> io::Result<bool> {
+    let Some((program, args)) = command.split_first() else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "empty command"));
+    };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    Ok(child.wait()?.success())
+}
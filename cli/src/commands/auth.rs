@@ -0,0 +1,246 @@
+use std::{
+    io::{self, BufRead, IsTerminal, Write},
+    time::Duration,
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_config::AppConfig,
+    args::{AuthCommand, AuthLoginArgs, AuthRegisterArgs},
+    commands::{
+        search::update_profile,
+        server::{client, server_base, server_client, ServerError},
+    },
+    error::{CliError, ErrorKind},
+    profile, tls,
+};
+
+const REGISTER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Body of the server's `POST /auth/login`
+#[derive(Debug, Serialize)]
+struct LoginBody {
+    username: String,
+    password: String,
+}
+
+#[derive(Debug, Serialize)]
+struct RegisterBody {
+    email: String,
+    name: String,
+    password: String,
+}
+
+/// Body of the server's login and register responses
+#[derive(Debug, Deserialize)]
+struct LoginReply {
+    token: String,
+}
+
+pub fn auth_cmd(command: AuthCommand, config: &AppConfig) -> anyhow::Result<()> {
+    match command {
+        AuthCommand::Register(args) => register_cmd(args, config),
+        AuthCommand::Login(args) => login_cmd(args, config),
+    }
+}
+
+/// Log in and keep the returned token; with `--insecure-pin` or
+/// `--fingerprint`, first check the certificate the server presents and talk
+/// to it only through that. A login without them verifies the server against
+/// CAs and drops a certificate pinned for it before. Either way the profile
+/// then syncs with this server.
+fn login_cmd(args: AuthLoginArgs, config: &AppConfig) -> anyhow::Result<()> {
+    let base = server_base(args.url, config)?;
+    let pin = if args.insecure_pin || args.fingerprint.is_some() {
+        Some(pin_certificate(&base, args.fingerprint.as_deref())?)
+    } else {
+        None
+    };
+    let password = read_password(args.password_stdin, false)?;
+
+    let client = match pin {
+        Some(ref pin) => tls::pinned_client(pin, REGISTER_TIMEOUT)?,
+        None => client(false, REGISTER_TIMEOUT)?,
+    };
+    let response = client
+        .post(format!("{}/auth/login", base))
+        .json(&LoginBody {
+            username: args.email.clone(),
+            password,
+        })
+        .send()
+        .with_context(|| format!("Server {} is unreachable", base))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let reason = response
+            .json::<ServerError>()
+            .map(|e| e.error)
+            .unwrap_or_else(|_| status.to_string());
+        return Err(CliError::new(
+            ErrorKind::InvalidInput,
+            format!("Login to {} failed: {}", base, reason),
+        )
+        .into());
+    }
+    let reply: LoginReply = response
+        .json()
+        .context("Server sent an unreadable login response")?;
+
+    profile::save_profile_token(&config.profile_name, &reply.token)?;
+    // Only a pin recorded for this server is replaced or dropped
+    let drop_pin = pin.is_none() && config.pinned_cert(&base).is_some();
+    update_profile(config, |profile| {
+        profile.server_url = Some(base.clone());
+        if let Some(ref pin) = pin {
+            profile.server_cert_sha256 = Some(pin.clone());
+            profile.server_cert_url = Some(base.clone());
+        } else if drop_pin {
+            profile.server_cert_sha256 = None;
+            profile.server_cert_url = None;
+        }
+        Ok(())
+    })?;
+    if let Some(pin) = pin {
+        println!("Pinned the certificate of {} (SHA-256 {})", base, pin);
+    } else if drop_pin {
+        println!(
+            "Removed the pinned certificate of {} from profile '{}'",
+            base, config.profile_name
+        );
+    }
+    println!(
+        "Logged in as {} on {}; credential stored for profile '{}'",
+        args.email, base, config.profile_name
+    );
+    Ok(())
+}
+
+/// Fingerprint of the certificate `base` presents, once it matches
+/// `expected` or, without one, the user has confirmed it at a prompt
+fn pin_certificate(base: &str, expected: Option<&str>) -> anyhow::Result<String> {
+    if !base.starts_with("https://") {
+        return Err(CliError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Pinning a certificate needs an https:// server, not {}",
+                base
+            ),
+        )
+        .into());
+    }
+    if expected.is_none() && !io::stdin().is_terminal() {
+        return Err(CliError::new(
+            ErrorKind::InvalidInput,
+            "No terminal to confirm the server's certificate (use --fingerprint)",
+        )
+        .into());
+    }
+
+    let presented = tls::fetch_fingerprint(base, REGISTER_TIMEOUT)?;
+    match expected {
+        Some(expected) if !tls::same_fingerprint(expected, &presented) => Err(CliError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Server {} presented a certificate with SHA-256 fingerprint {}, not {}",
+                base, presented, expected
+            ),
+        )
+        .into()),
+        Some(_) => Ok(presented),
+        None => {
+            println!(
+                "Server {} presented a certificate with SHA-256 fingerprint",
+                base
+            );
+            println!("  {}", presented);
+            print!("Check it against the server's. Trust and pin it? [y/N]: ");
+            io::stdout().flush()?;
+            let mut input = String::new();
+            io::stdin().read_line(&mut input)?;
+            if !input.trim().eq_ignore_ascii_case("y") {
+                return Err(CliError::new(
+                    ErrorKind::InvalidInput,
+                    format!("Certificate of {} not trusted; nothing was changed", base),
+                )
+                .into());
+            }
+            Ok(presented)
+        }
+    }
+}
+
+/// Sign up on the server and keep the returned token, so `jot sync` works
+/// right away
+fn register_cmd(args: AuthRegisterArgs, config: &AppConfig) -> anyhow::Result<()> {
+    let base = server_base(args.url, config)?;
+    let name = args
+        .name
+        .unwrap_or_else(|| args.email.split('@').next().unwrap_or_default().to_string());
+    let password = read_password(args.password_stdin, true)?;
+
+    let response = server_client(config, &base, REGISTER_TIMEOUT)?
+        .post(format!("{}/auth/register", base))
+        .json(&RegisterBody {
+            email: args.email.clone(),
+            name,
+            password,
+        })
+        .send()
+        .with_context(|| format!("Server {} is unreachable", base))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let reason = response
+            .json::<ServerError>()
+            .map(|e| e.error)
+            .unwrap_or_else(|_| status.to_string());
+        return Err(CliError::new(
+            ErrorKind::InvalidInput,
+            format!("Registration on {} failed: {}", base, reason),
+        )
+        .into());
+    }
+    let reply: LoginReply = response
+        .json()
+        .context("Server sent an unreadable registration response")?;
+
+    profile::save_profile_token(&config.profile_name, &reply.token)?;
+    println!(
+        "Registered {} on {}; credential stored for profile '{}'",
+        args.email, base, config.profile_name
+    );
+    Ok(())
+}
+
+/// The password from stdin or a hidden prompt, typed twice when `confirm`
+fn read_password(from_stdin: bool, confirm: bool) -> anyhow::Result<String> {
+    if from_stdin {
+        let mut line = String::new();
+        io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .context("Failed to read password from stdin")?;
+        return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    if !io::stdin().is_terminal() {
+        return Err(CliError::new(
+            ErrorKind::InvalidInput,
+            "No terminal to prompt for a password (use --password-stdin)",
+        )
+        .into());
+    }
+    let password = rpassword::prompt_password("Password: ").context("Failed to read password")?;
+    if !confirm {
+        return Ok(password);
+    }
+    let repeated =
+        rpassword::prompt_password("Repeat password: ").context("Failed to read password")?;
+    if password != repeated {
+        return Err(CliError::new(ErrorKind::InvalidInput, "Passwords don't match").into());
+    }
+    Ok(password)
+}
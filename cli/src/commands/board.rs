@@ -0,0 +1,81 @@
+use std::path::Path;
+
+use jot_core::{Note, SearchQuery};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    app_config::AppConfig, args::BoardArgs, db::LocalDb, formatters::short_id,
+    utils::text::preview_line,
+};
+
+/// Width of one column, separator excluded
+const COLUMN_WIDTH: usize = 32;
+
+/// Columns between two board columns
+const SEPARATOR: &str = "  ";
+
+/// Notes with a status, one column per configured status
+///
+/// Statuses outside the configured set (synced from a device with another
+/// profile) get a column of their own after the configured ones; notes
+/// without a status are left off the board.
+pub fn board_cmd(db_path: &Path, args: BoardArgs, config: &AppConfig) -> anyhow::Result<()> {
+    let db = LocalDb::open_for(db_path, config)?;
+    let notes = db.search_notes(&SearchQuery {
+        tags: args.tag,
+        ..Default::default()
+    })?;
+
+    let mut columns: Vec<(String, Vec<&Note>)> = config
+        .statuses
+        .iter()
+        .map(|s| (s.clone(), Vec::new()))
+        .collect();
+    for note in &notes {
+        let Some(ref status) = note.status else {
+            continue;
+        };
+        match columns.iter_mut().find(|(name, _)| name == status) {
+            Some((_, column)) => column.push(note),
+            None => columns.push((status.clone(), vec![note])),
+        }
+    }
+
+    let headers: Vec<String> = columns
+        .iter()
+        .map(|(name, notes)| format!("{} ({})", name, notes.len()))
+        .collect();
+    print_row(&headers);
+    print_row(&vec!["-".repeat(COLUMN_WIDTH); columns.len()]);
+
+    let rows = columns.iter().map(|(_, n)| n.len()).max().unwrap_or(0);
+    // Room for the short ID, a space and the "..." of a cut preview
+    let preview_width = COLUMN_WIDTH - 12;
+    for row in 0..rows {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|(_, notes)| match notes.get(row) {
+                Some(note) => format!(
+                    "{} {}",
+                    short_id(&note.id),
+                    preview_line(&note.content, preview_width)
+                ),
+                None => String::new(),
+            })
+            .collect();
+        print_row(&cells);
+    }
+    Ok(())
+}
+
+fn print_row(cells: &[String]) {
+    let line = cells
+        .iter()
+        .map(|cell| {
+            let padding = COLUMN_WIDTH.saturating_sub(cell.width());
+            format!("{}{}", cell, " ".repeat(padding))
+        })
+        .collect::<Vec<_>>()
+        .join(SEPARATOR);
+    println!("{}", line.trim_end());
+}
@@ -0,0 +1,343 @@
+use std::{
+    collections::HashMap,
+    io::{self, IsTerminal, Write},
+    path::Path,
+};
+
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute, terminal,
+};
+use jot_core::{Note, SearchQuery};
+use termcolor::{Buffer, BufferWriter, Color, ColorChoice, ColorSpec, WriteColor};
+
+use crate::{
+    app_config::AppConfig,
+    args::{CalArgs, NoteSearchArgs, OutputFormat},
+    db::LocalDb,
+    formatters::{short_id, NoteSearchFormatter},
+    utils::{
+        date_target::{parse_week_start, start_of_week, DateTarget},
+        text::preview_line,
+        time_display::TimeDisplay,
+    },
+};
+
+/// Columns per day: the day of the month, its note count and a gap
+const CELL_WIDTH: usize = 7;
+
+/// Notes previewed under the calendar while picking a day
+const PREVIEW_NOTES: usize = 5;
+
+/// Width of those previews
+const LINE_WIDTH: usize = 50;
+
+/// Greens from faint to bright for days with more and more notes
+const INTENSITY: [u8; 4] = [22, 28, 34, 40];
+
+pub fn cal_cmd(db_path: &Path, args: CalArgs, config: &AppConfig) -> anyhow::Result<()> {
+    let db = LocalDb::open_for(db_path, config)?;
+    let week_start = parse_week_start(config.week_start.as_deref())?;
+    let today = TimeDisplay::from_config(config)?.today();
+
+    if let Some(day) = args.day {
+        let day = day.to_date(today);
+        // Machine-readable listings stay parseable without the calendar
+        if matches!(args.output, OutputFormat::Pretty | OutputFormat::Plain) {
+            print_month(&db, day, Some(day), week_start)?;
+            println!();
+        }
+        return print_day(&db, day, args.output, config);
+    }
+
+    let shown = match args.month {
+        Some(target) => {
+            let (from, to) = target.to_date_range(today, week_start);
+            from.or(to).unwrap_or(today)
+        }
+        None => today,
+    };
+    let selected = match first_of_month(shown) == first_of_month(today) {
+        true => today,
+        false => shown,
+    };
+
+    if io::stdin().is_terminal() && io::stdout().is_terminal() {
+        if let Some(day) = pick_day(&db, selected, week_start)? {
+            print_day(&db, day, args.output, config)?;
+        }
+        return Ok(());
+    }
+    let highlight = (first_of_month(shown) == first_of_month(today)).then_some(today);
+    print_month(&db, shown, highlight, week_start)
+}
+
+/// The month containing `date`, with `highlight` marked
+fn print_month(
+    db: &LocalDb,
+    date: NaiveDate,
+    highlight: Option<NaiveDate>,
+    week_start: Weekday,
+) -> anyhow::Result<()> {
+    let writer = stdout_writer();
+    let mut buffer = writer.buffer();
+    let counts = month_counts(db, first_of_month(date))?;
+    render_month(
+        &mut buffer,
+        first_of_month(date),
+        &counts,
+        highlight,
+        week_start,
+        "\n",
+    )?;
+    writer.print(&buffer)?;
+    Ok(())
+}
+
+/// Notes whose subject date is `day`, in the usual listing format
+fn print_day(
+    db: &LocalDb,
+    day: NaiveDate,
+    output: OutputFormat,
+    config: &AppConfig,
+) -> anyhow::Result<()> {
+    let notes = db.search_notes(&day_query(day))?;
+    let mut formatter = NoteSearchFormatter::new(
+        NoteSearchArgs {
+            date: Some(DateTarget::Specific(day)),
+            output,
+            ..Default::default()
+        },
+        TimeDisplay::from_config(config)?,
+    )
+    .with_plain_structure(config.plain_structure);
+    formatter.print_notes(&notes)?;
+    Ok(())
+}
+
+/// Let the user move around the calendar until they pick a day (Enter) or
+/// give up (q, Esc)
+fn pick_day(
+    db: &LocalDb,
+    mut selected: NaiveDate,
+    week_start: Weekday,
+) -> anyhow::Result<Option<NaiveDate>> {
+    let writer = stdout_writer();
+    let _screen = RawScreen::enter()?;
+    let mut month = first_of_month(selected);
+    let mut counts = month_counts(db, month)?;
+
+    loop {
+        let first = first_of_month(selected);
+        if first != month {
+            month = first;
+            counts = month_counts(db, month)?;
+        }
+        let notes = db.search_notes(&day_query(selected))?;
+
+        let mut buffer = writer.buffer();
+        render_month(
+            &mut buffer,
+            first,
+            &counts,
+            Some(selected),
+            week_start,
+            "\r\n",
+        )?;
+        write!(buffer, "\r\n")?;
+        render_previews(&mut buffer, selected, &notes)?;
+        write!(
+            buffer,
+            "\r\narrows move, PgUp/PgDn change month, Enter lists the day, q quits\r\n"
+        )?;
+
+        let mut stdout = io::stdout();
+        execute!(
+            stdout,
+            cursor::MoveTo(0, 0),
+            terminal::Clear(terminal::ClearType::All)
+        )?;
+        stdout.write_all(buffer.as_slice())?;
+        stdout.flush()?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let moved = match key.code {
+            KeyCode::Enter => return Ok(Some(selected)),
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(None),
+            KeyCode::Left | KeyCode::Char('h') => selected.pred_opt(),
+            KeyCode::Right | KeyCode::Char('l') => selected.succ_opt(),
+            KeyCode::Up | KeyCode::Char('k') => selected.checked_sub_days(Days::new(7)),
+            KeyCode::Down | KeyCode::Char('j') => selected.checked_add_days(Days::new(7)),
+            KeyCode::PageUp | KeyCode::Char('p') => selected.checked_sub_months(Months::new(1)),
+            KeyCode::PageDown | KeyCode::Char('n') => selected.checked_add_months(Months::new(1)),
+            _ => None,
+        };
+        selected = moved.unwrap_or(selected);
+    }
+}
+
+/// Alternate screen in raw mode, restored when dropped (errors included)
+struct RawScreen;
+
+impl RawScreen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let screen = Self;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(screen)
+    }
+}
+
+impl Drop for RawScreen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Month grid, one row per week, each day followed by its note count
+///
+/// `newline` is "\r\n" in raw mode.
+fn render_month(
+    buffer: &mut Buffer,
+    first: NaiveDate,
+    counts: &HashMap<NaiveDate, i64>,
+    highlight: Option<NaiveDate>,
+    week_start: Weekday,
+    newline: &str,
+) -> io::Result<()> {
+    let total: i64 = counts.values().sum();
+    let max = counts.values().copied().max().unwrap_or(0);
+
+    buffer.set_color(ColorSpec::new().set_bold(true))?;
+    write!(buffer, "{} ({} notes)", first.format("%B %Y"), total)?;
+    buffer.reset()?;
+    write!(buffer, "{}", newline)?;
+
+    let names: Vec<String> = std::iter::successors(Some(week_start), |d| Some(d.succ()))
+        .take(7)
+        .map(|d| d.to_string()[..2].to_string())
+        .collect();
+    write!(
+        buffer,
+        "{}{}",
+        names.join(&" ".repeat(CELL_WIDTH - 2)),
+        newline
+    )?;
+
+    let mut date = start_of_week(first, week_start);
+    while date.month() == first.month() || date < first {
+        // Blank columns are only written when a day follows, so no line ends in spaces
+        let mut gap = 0;
+        for _ in 0..7 {
+            if date.month() == first.month() {
+                let count = counts.get(&date).copied().unwrap_or(0);
+                let mut spec = ColorSpec::new();
+                if count > 0 {
+                    spec.set_fg(Some(Color::Ansi256(intensity(count, max))));
+                }
+                let mut cell = match count {
+                    0 => format!("{:>2}", date.day()),
+                    n => format!("{:>2} {}", date.day(), n),
+                };
+                if Some(date) == highlight {
+                    spec.set_fg(Some(Color::Black))
+                        .set_bg(Some(Color::Cyan))
+                        .set_bold(true);
+                    // The background shows across the whole cell
+                    if buffer.supports_color() {
+                        cell = format!("{:<width$}", cell, width = CELL_WIDTH - 1);
+                    }
+                }
+                write!(buffer, "{:gap$}", "")?;
+                match spec.is_none() {
+                    true => write!(buffer, "{}", cell)?,
+                    false => {
+                        buffer.set_color(&spec)?;
+                        write!(buffer, "{}", cell)?;
+                        buffer.reset()?;
+                    }
+                }
+                gap = CELL_WIDTH - cell.len();
+            } else {
+                gap += CELL_WIDTH;
+            }
+            date = date.succ_opt().unwrap_or(NaiveDate::MAX);
+        }
+        write!(buffer, "{}", newline)?;
+    }
+    Ok(())
+}
+
+/// First few notes of the selected day, shown under the calendar
+fn render_previews(buffer: &mut Buffer, day: NaiveDate, notes: &[Note]) -> io::Result<()> {
+    write!(buffer, "{}: ", day.format("%a %-d %B %Y"))?;
+    match notes.len() {
+        0 => write!(buffer, "no notes\r\n")?,
+        1 => write!(buffer, "1 note\r\n")?,
+        n => write!(buffer, "{} notes\r\n", n)?,
+    }
+    for note in notes.iter().take(PREVIEW_NOTES) {
+        write!(
+            buffer,
+            "  {} {}\r\n",
+            short_id(&note.id),
+            preview_line(&note.content, LINE_WIDTH)
+        )?;
+    }
+    if notes.len() > PREVIEW_NOTES {
+        write!(buffer, "  ... and {} more\r\n", notes.len() - PREVIEW_NOTES)?;
+    }
+    Ok(())
+}
+
+/// Color for a day with `count` notes when the busiest day has `max`
+fn intensity(count: i64, max: i64) -> u8 {
+    let levels = INTENSITY.len() as i64;
+    let level = (count * levels + max - 1) / max.max(1);
+    INTENSITY[(level.clamp(1, levels) - 1) as usize]
+}
+
+fn month_counts(db: &LocalDb, first: NaiveDate) -> anyhow::Result<HashMap<NaiveDate, i64>> {
+    let last = first
+        .checked_add_months(Months::new(1))
+        .and_then(|d| d.pred_opt())
+        .unwrap_or(first);
+    Ok(db
+        .count_notes_per_day(first, last)?
+        .into_iter()
+        .filter_map(|(date, count)| {
+            NaiveDate::parse_from_str(&date, jot_core::schema::DATE_FORMAT)
+                .ok()
+                .map(|date| (date, count))
+        })
+        .collect())
+}
+
+fn day_query(day: NaiveDate) -> SearchQuery {
+    SearchQuery {
+        date_from: Some(day),
+        date_to: Some(day),
+        ..Default::default()
+    }
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+/// Colors on a terminal, plain text when piped
+fn stdout_writer() -> BufferWriter {
+    match io::stdout().is_terminal() {
+        true => BufferWriter::stdout(ColorChoice::Auto),
+        false => BufferWriter::stdout(ColorChoice::Never),
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use crate::{
+    app_config::AppConfig,
+    args::CaptureArgs,
+    db::LocalDb,
+    utils::{capture_context::capture_context, time_display::TimeDisplay},
+};
+use anyhow::Context;
+
+pub fn capture_cmd(db_path: &Path, args: CaptureArgs, config: &AppConfig) -> anyhow::Result<()> {
+    let db = LocalDb::open_for(db_path, config)?;
+
+    let mut tags = args.tag;
+    // Add default tags from profile
+    for default_tag in &config.default_tags {
+        if !tags.contains(default_tag) {
+            tags.push(default_tag.clone());
+        }
+    }
+    let mut capture = Capture {
+        db: &db,
+        tags,
+        meta: capture_context(config),
+        time: TimeDisplay::from_config(config)?,
+        delimiter: args.delimiter,
+        quiet: args.quiet,
+        block: Vec::new(),
+    };
+
+    match args.watch {
+        Some(path) => watch(&path, &mut capture),
+        None => capture.read(io::stdin().lock()),
+    }
+}
+
+/// Capture from `path`; a named pipe is reopened whenever its writer closes
+/// it, so this keeps going until interrupted
+fn watch(path: &Path, capture: &mut Capture) -> anyhow::Result<()> {
+    let reopen = is_fifo(path);
+    loop {
+        // Blocks until a writer opens the pipe
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        capture.read(BufReader::new(file))?;
+        if !reopen {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).is_ok_and(|m| m.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_path: &Path) -> bool {
+    false
+}
+
+struct Capture<'a> {
+    db: &'a LocalDb,
+    tags: Vec<String>,
+    /// Where the stream is captured, the same for all its notes
+    meta: BTreeMap<String, String>,
+    /// Notes are dated today in the profile's timezone
+    time: TimeDisplay,
+    delimiter: Option<String>,
+    quiet: bool,
+    /// Lines of the block being collected (with a delimiter)
+    block: Vec<String>,
+}
+
+impl Capture<'_> {
+    /// Save notes from `reader` until it ends; a pending block is saved at the end
+    fn read(&mut self, reader: impl BufRead) -> anyhow::Result<()> {
+        for line in reader.lines() {
+            let line = line.context("Failed to read input")?;
+            match self.delimiter {
+                Some(ref delimiter) if line.trim_end() == delimiter => self.save_block(),
+                Some(_) => self.block.push(line),
+                None => self.save(&line),
+            }
+        }
+        self.save_block();
+        Ok(())
+    }
+
+    fn save_block(&mut self) {
+        let block = std::mem::take(&mut self.block).join("\n");
+        self.save(&block);
+    }
+
+    /// Store one note; a failure is reported without ending the stream
+    fn save(&self, content: &str) {
+        let content = content.trim_start_matches(['\n', '\r']).trim_end();
+        if content.trim().is_empty() {
+            return;
+        }
+        let date = self.time.today().format("%Y-%m-%d").to_string();
+        match self.db.create_note(
+            content.to_string(),
+            self.tags.clone(),
+            Some(date),
+            None,
+            self.meta.clone(),
+        ) {
+            Ok(note) if self.quiet => println!("{}", note.id),
+            Ok(note) => println!("Note added successfully ({})", note.id),
+            Err(e) => eprintln!("Failed to capture note: {:#}", e),
+        }
+    }
+}
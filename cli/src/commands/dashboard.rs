@@ -0,0 +1,182 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use chrono::NaiveDate;
+use jot_core::{Note, SearchQuery};
+use termcolor::{BufferWriter, ColorChoice, ColorSpec, WriteColor};
+
+use crate::{
+    app_config::AppConfig,
+    args::NoteSearchArgs,
+    commands::{note::build_search_query, search::expand_saved_search},
+    db::LocalDb,
+    error::{CliError, ErrorKind},
+    formatters::short_id,
+    utils::{tasks::open_tasks, text::preview_line, time_display::TimeDisplay},
+};
+
+/// Width of note previews and task texts
+const LINE_WIDTH: usize = 60;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Section {
+    Pinned,
+    Today,
+    /// Open tasks that are not overdue
+    Tasks,
+    /// Open tasks whose due date has passed
+    Overdue,
+    /// A saved search, by name
+    Search(String),
+}
+
+impl FromStr for Section {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pinned" => Ok(Self::Pinned),
+            "today" => Ok(Self::Today),
+            "tasks" => Ok(Self::Tasks),
+            "overdue" => Ok(Self::Overdue),
+            _ => match s.strip_prefix('@') {
+                Some(name) => Ok(Self::Search(name.to_string())),
+                None => Err(CliError::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                    "Unknown dashboard section '{}' (use pinned, today, tasks, overdue or @search)",
+                    s
+                ),
+                )
+                .into()),
+            },
+        }
+    }
+}
+
+pub fn dashboard_cmd(db_path: &Path, config: &AppConfig) -> anyhow::Result<()> {
+    let sections = config
+        .dashboard
+        .sections
+        .iter()
+        .map(|s| s.parse::<Section>())
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let db = LocalDb::open_for(db_path, config)?;
+    let notes = db.search_notes(&SearchQuery::default())?;
+    let today = TimeDisplay::from_config(config)?.today();
+
+    // Plain when captured by a status bar or a file
+    let color_choice = if io::stdout().is_terminal() {
+        ColorChoice::Auto
+    } else {
+        ColorChoice::Never
+    };
+    let writer = BufferWriter::stdout(color_choice);
+    let mut buffer = writer.buffer();
+    for (i, section) in sections.iter().enumerate() {
+        if i > 0 {
+            writeln!(buffer)?;
+        }
+        let (title, lines) = match section {
+            Section::Pinned => (
+                "Pinned".to_string(),
+                note_lines(notes.iter().filter(|n| n.pinned)),
+            ),
+            Section::Today => {
+                let today = today.to_string();
+                (
+                    "Today".to_string(),
+                    note_lines(
+                        notes
+                            .iter()
+                            .filter(|n| n.subject_date.as_deref() == Some(today.as_str())),
+                    ),
+                )
+            }
+            Section::Tasks => (
+                "Tasks".to_string(),
+                task_lines(&notes, |due| due.is_none_or(|d| d >= today)),
+            ),
+            Section::Overdue => (
+                "Overdue".to_string(),
+                task_lines(&notes, |due| due.is_some_and(|d| d < today)),
+            ),
+            Section::Search(name) => {
+                let args = expand_saved_search(
+                    NoteSearchArgs {
+                        term: Some(format!("@{}", name)),
+                        ..Default::default()
+                    },
+                    config,
+                )?;
+                let found = db.search_notes(&build_search_query(&args, config)?)?;
+                (format!("@{}", name), note_lines(found.iter()))
+            }
+        };
+        print_section(&mut buffer, &title, &lines, config.dashboard.limit)?;
+    }
+    writer.print(&buffer)?;
+
+    Ok(())
+}
+
+fn note_lines<'a>(notes: impl Iterator<Item = &'a Note>) -> Vec<String> {
+    notes
+        .map(|note| {
+            format!(
+                "{}  {}",
+                short_id(&note.id),
+                preview_line(&note.content, LINE_WIDTH)
+            )
+        })
+        .collect()
+}
+
+/// Open tasks from all notes whose due date passes `include`, soonest due first
+fn task_lines(notes: &[Note], include: impl Fn(Option<NaiveDate>) -> bool) -> Vec<String> {
+    let mut tasks: Vec<(Option<NaiveDate>, String)> = notes
+        .iter()
+        .flat_map(|note| {
+            open_tasks(&note.content)
+                .into_iter()
+                .filter(|task| include(task.due))
+                .map(|task| {
+                    let line = format!(
+                        "{}  {}",
+                        short_id(&note.id),
+                        preview_line(task.text, LINE_WIDTH)
+                    );
+                    (task.due, line)
+                })
+        })
+        .collect();
+    // Undated tasks after dated ones; stable, so note order is kept otherwise
+    tasks.sort_by_key(|(due, _)| (due.is_none(), *due));
+    tasks.into_iter().map(|(_, line)| line).collect()
+}
+
+fn print_section(
+    buffer: &mut termcolor::Buffer,
+    title: &str,
+    lines: &[String],
+    limit: usize,
+) -> io::Result<()> {
+    buffer.set_color(ColorSpec::new().set_bold(true))?;
+    writeln!(buffer, "{} ({})", title, lines.len())?;
+    buffer.reset()?;
+
+    if lines.is_empty() {
+        writeln!(buffer, "  (none)")?;
+    }
+    for line in lines.iter().take(limit) {
+        writeln!(buffer, "  {}", line)?;
+    }
+    if lines.len() > limit {
+        writeln!(buffer, "  ... and {} more", lines.len() - limit)?;
+    }
+    Ok(())
+}
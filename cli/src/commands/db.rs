@@ -0,0 +1,135 @@
+use std::path::Path;
+
+use jot_core::{schema, SearchIndexCheck};
+
+use crate::{
+    app_config::AppConfig,
+    args::DbCommand,
+    db::LocalDb,
+    error::{CliError, ErrorKind},
+    lock::OperationLock,
+};
+
+pub fn db_cmd(
+    db_path: &Path,
+    subcommand: DbCommand,
+    config: &AppConfig,
+) -> Result<(), anyhow::Error> {
+    match subcommand {
+        DbCommand::Migrate(args) => {
+            if args.status {
+                let status = LocalDb::migration_status(db_path)?;
+
+                println!(
+                    "Schema version: {} (latest: {})",
+                    status.version,
+                    schema::LATEST_VERSION
+                );
+
+                if status.pending.is_empty() {
+                    println!("Database is up to date.");
+                } else {
+                    println!("Pending migrations:");
+                    for migration in &status.pending {
+                        println!("  v{}: {}", migration.version, migration.description);
+                    }
+                }
+            } else {
+                // Opening the database applies pending migrations
+                LocalDb::open(db_path)?;
+                println!(
+                    "Database is up to date (schema v{}).",
+                    schema::LATEST_VERSION
+                );
+            }
+        }
+        DbCommand::Compact(args) => {
+            let cutoff = args
+                .keep
+                .cutoff(chrono::Local::now())
+                .ok_or_else(|| anyhow::anyhow!("Retention period '{}' is too long", args.keep))?;
+
+            let db = LocalDb::open(db_path)?;
+            let _lock = OperationLock::acquire(db_path)?;
+            let result = db.compact(cutoff.timestamp_millis())?;
+            println!(
+                "Removed {} deleted note(s) older than {}, reclaimed {} bytes.",
+                result.purged, args.keep, result.reclaimed_bytes
+            );
+        }
+        DbCommand::Maintain(args) => {
+            if args.recompress {
+                let db = LocalDb::open(db_path)?;
+                let _lock = OperationLock::acquire(db_path)?;
+                let result = db.recompress()?;
+                println!(
+                    "Compressed {} note(s), expanded {} note(s), reclaimed {} bytes.",
+                    result.compressed, result.expanded, result.reclaimed_bytes
+                );
+            }
+            if args.encrypt {
+                if !config.encrypt {
+                    return Err(CliError::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Profile '{}' doesn't encrypt its notes (set `encrypt = true` in its profile first)",
+                            config.profile_name
+                        ),
+                    )
+                    .into());
+                }
+                let db = LocalDb::open_for(db_path, config)?;
+                let _lock = OperationLock::acquire(db_path)?;
+                let encrypted = db.encrypt_existing()?;
+                println!("Encrypted {} note(s).", encrypted);
+            }
+        }
+        DbCommand::Reindex(args) => {
+            let db = LocalDb::open(db_path)?;
+            if args.check {
+                let check = db.check_search_index()?;
+                if !check.is_consistent() {
+                    return Err(CliError::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Search index differs from the notes: {}; run `jot db reindex` to rebuild it",
+                            describe_drift(&check)
+                        ),
+                    )
+                    .into());
+                }
+                println!("Search index matches all {} note(s).", check.notes);
+                return Ok(());
+            }
+
+            let _lock = OperationLock::acquire(db_path)?;
+            let result = db.reindex()?;
+            if !result.before.is_consistent() {
+                println!(
+                    "Search index differed from the notes: {}",
+                    describe_drift(&result.before)
+                );
+            }
+            if !result.after.is_consistent() {
+                anyhow::bail!(
+                    "Search index still differs from the notes after rebuilding: {}",
+                    describe_drift(&result.after)
+                );
+            }
+            println!(
+                "Rebuilt the search index of {} note(s).",
+                result.after.notes
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Counts of a search index check that found differences
+fn describe_drift(check: &SearchIndexCheck) -> String {
+    format!(
+        "{} note(s), {} index row(s); {} missing, {} stale, {} orphaned",
+        check.notes, check.indexed, check.missing, check.stale, check.orphaned
+    )
+}
@@ -0,0 +1,25 @@
+use std::{path::Path, time::Instant};
+
+use crate::{app_config::AppConfig, args::DevCommand, db::LocalDb};
+
+pub fn dev_cmd(
+    db_path: &Path,
+    subcommand: DevCommand,
+    config: &AppConfig,
+) -> Result<(), anyhow::Error> {
+    let db = LocalDb::open_for(db_path, config)?;
+
+    match subcommand {
+        DevCommand::Seed(args) => {
+            let started = Instant::now();
+            let count = db.seed_notes(args.count, args.seed)?;
+            println!(
+                "Seeded {} notes in {:.2}s",
+                count,
+                started.elapsed().as_secs_f64()
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use std::{collections::HashSet, path::Path};
+
+use anyhow::Context;
+use jot_core::Note;
+
+use crate::{
+    app_config::AppConfig,
+    args::ExportArgs,
+    db::LocalDb,
+    export::{markdown, Manifest, ATTACHMENTS_DIR},
+};
+
+pub fn export_cmd(db_path: &Path, args: ExportArgs, config: &AppConfig) -> anyhow::Result<()> {
+    std::fs::create_dir_all(&args.dir)
+        .with_context(|| format!("Failed to create {}", args.dir.display()))?;
+    // The state key is the absolute path, so `./backup` and `~/backup` match
+    let dir = args
+        .dir
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", args.dir.display()))?;
+    let target = dir.to_string_lossy();
+
+    let db = LocalDb::open_for(db_path, config)?;
+    let since = match args.incremental {
+        true => db.last_export(&target)?.unwrap_or(i64::MIN),
+        false => i64::MIN,
+    };
+    // A note changed in the same millisecond is exported again next time
+    // rather than missed
+    let started = chrono::Utc::now().timestamp_millis() - 1;
+
+    let mut manifest = Manifest::load(&dir)?;
+    let mut exported = Export::default();
+    let result = exported.run(&db, &dir, since, &mut manifest);
+    if !args.incremental && result.is_ok() {
+        exported.remove_stale(&dir, &mut manifest)?;
+    }
+    // Saved even after a failure, so files already written are not
+    // mistaken for someone else's on the next run
+    manifest.save(&dir)?;
+    result?;
+    db.set_last_export(&target, started)?;
+
+    println!("Exported {} note(s) to {}", exported.written, dir.display());
+    if exported.removed > 0 {
+        println!("Removed {} deleted note(s)", exported.removed);
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct Export {
+    written: usize,
+    removed: usize,
+    /// Live notes seen, to find manifest entries of notes gone entirely
+    seen: HashSet<String>,
+}
+
+impl Export {
+    fn run(
+        &mut self,
+        db: &LocalDb,
+        dir: &Path,
+        since: i64,
+        manifest: &mut Manifest,
+    ) -> anyhow::Result<()> {
+        for note in db.get_notes_since(since)? {
+            if note.deleted_at.is_some() {
+                if let Some(name) = manifest.notes.remove(&note.id) {
+                    remove_file(&dir.join(name))?;
+                    self.removed += 1;
+                }
+                continue;
+            }
+
+            let name = manifest.file_name(dir, &note, markdown::EXTENSION);
+            let path = dir.join(&name);
+            std::fs::write(&path, markdown::render(&note))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            // The first line or date changed: drop the file under the old name
+            if let Some(old) = manifest.notes.insert(note.id.clone(), name.clone()) {
+                if old != name {
+                    remove_file(&dir.join(old))?;
+                }
+            }
+            write_attachments(db, dir, &note)?;
+            self.seen.insert(note.id);
+            self.written += 1;
+        }
+        Ok(())
+    }
+
+    /// Remove files of notes that no longer exist at all (compacted away)
+    fn remove_stale(&mut self, dir: &Path, manifest: &mut Manifest) -> anyhow::Result<()> {
+        let stale: Vec<String> = manifest
+            .notes
+            .keys()
+            .filter(|id| !self.seen.contains(*id))
+            .cloned()
+            .collect();
+        for id in stale {
+            if let Some(name) = manifest.notes.remove(&id) {
+                remove_file(&dir.join(name))?;
+                self.removed += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write the contents of the note's attachments stored on this device to
+/// [`ATTACHMENTS_DIR`], once per hash
+fn write_attachments(db: &LocalDb, dir: &Path, note: &Note) -> anyhow::Result<()> {
+    let attachments_dir = dir.join(ATTACHMENTS_DIR);
+    for attachment in &note.attachments {
+        let path = attachments_dir.join(&attachment.hash);
+        if path.exists() {
+            continue;
+        }
+        let Some(data) = db.attachment_data(attachment)? else {
+            continue;
+        };
+        std::fs::create_dir_all(&attachments_dir)
+            .with_context(|| format!("Failed to create {}", attachments_dir.display()))?;
+        std::fs::write(&path, data)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn remove_file(path: &Path) -> anyhow::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,135 @@
+use std::path::Path;
+
+use anyhow::Context;
+use jot_core::Attachment;
+
+use crate::{
+    app_config::AppConfig,
+    args::ImportCommand,
+    db::LocalDb,
+    import::{apple_notes, json, markdown, standard_notes, ParsedImport},
+    lock::OperationLock,
+};
+
+pub fn import_cmd(
+    db_path: &Path,
+    command: ImportCommand,
+    config: &AppConfig,
+) -> anyhow::Result<()> {
+    let parsed = match command {
+        ImportCommand::StandardNotes { path } => {
+            standard_notes::parse_backup(&standard_notes::read_backup(&path)?)?
+        }
+        ImportCommand::AppleNotes { dir } => apple_notes::read_export(&dir)?,
+        ImportCommand::Markdown { dir } => markdown::read_dir(&dir)?,
+        ImportCommand::Json { path } => json::read_dump(&path)?,
+    };
+
+    let db = LocalDb::open_for(db_path, config)?;
+    let _lock = (!config.ephemeral)
+        .then(|| OperationLock::acquire(db_path))
+        .transpose()?;
+    store(&db, parsed)
+}
+
+/// Insert the notes, reporting (but not stopping at) ones that fail validation
+fn store(db: &LocalDb, parsed: ParsedImport) -> anyhow::Result<()> {
+    let (mut imported, mut duplicates, mut failed) = (0, 0, 0);
+    let mut attachments = RestoredAttachments::default();
+    for imported_note in parsed.notes {
+        match db.import_note(imported_note.note) {
+            Ok(Some(note)) => {
+                imported += 1;
+                attachments.restore(
+                    db,
+                    &note.id,
+                    &imported_note.title,
+                    &imported_note.attachments,
+                    parsed.attachments_dir.as_deref(),
+                )?;
+            }
+            Ok(None) => duplicates += 1,
+            Err(e) => {
+                failed += 1;
+                eprintln!("Skipped note '{}': {}", imported_note.title, e);
+            }
+        }
+    }
+
+    println!("Imported {} note(s)", imported);
+    if attachments.restored > 0 {
+        println!("Restored {} attachment(s)", attachments.restored);
+    }
+    if duplicates > 0 {
+        println!("{} note(s) were already present", duplicates);
+    }
+    if parsed.skipped > 0 {
+        println!(
+            "{} item(s) could not be read and were skipped",
+            parsed.skipped
+        );
+    }
+    if failed > 0 {
+        println!("{} note(s) failed to import", failed);
+    }
+    if attachments.missing + attachments.corrupted > 0 {
+        println!(
+            "{} attachment(s) were missing and {} corrupted; their notes were imported without them",
+            attachments.missing, attachments.corrupted
+        );
+    }
+    Ok(())
+}
+
+/// Attachment contents restored into the profile, and those left out
+#[derive(Default)]
+struct RestoredAttachments {
+    restored: usize,
+    missing: usize,
+    corrupted: usize,
+}
+
+impl RestoredAttachments {
+    /// Attach each file to the imported note from `dir` after checking its
+    /// hash, reporting files that are missing or don't match instead of
+    /// keeping a reference to contents the profile doesn't have
+    fn restore(
+        &mut self,
+        db: &LocalDb,
+        note_id: &str,
+        title: &str,
+        attachments: &[Attachment],
+        dir: Option<&Path>,
+    ) -> anyhow::Result<()> {
+        for attachment in attachments {
+            // The hash names the file, so only accept one that can't leave `dir`
+            let path = dir
+                .filter(|_| attachment.hash.chars().all(|c| c.is_ascii_hexdigit()))
+                .map(|dir| dir.join(&attachment.hash))
+                .filter(|path| path.is_file());
+            let Some(path) = path else {
+                self.missing += 1;
+                eprintln!(
+                    "Note '{}': attachment {} is missing",
+                    title, attachment.filename
+                );
+                continue;
+            };
+            let data = std::fs::read(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            match db.restore_attachment(note_id, attachment, &data)? {
+                Some(_) => self.restored += 1,
+                None => {
+                    self.corrupted += 1;
+                    eprintln!(
+                        "Note '{}': attachment {} is corrupted ({} doesn't match its hash)",
+                        title,
+                        attachment.filename,
+                        path.display()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
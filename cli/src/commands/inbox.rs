@@ -0,0 +1,58 @@
+use std::path::Path;
+
+use crate::{
+    app_config::AppConfig,
+    args::{InboxCommand, NoteSearchArgs},
+    commands::note::build_search_query,
+    db::LocalDb,
+    formatters::NoteSearchFormatter,
+    utils::time_display::TimeDisplay,
+};
+
+/// Tag marking notes that still need triage
+pub const INBOX_TAG: &str = "inbox";
+
+pub fn inbox_cmd(
+    db_path: &Path,
+    command: Option<InboxCommand>,
+    config: &AppConfig,
+) -> Result<(), anyhow::Error> {
+    let db = LocalDb::open_for(db_path, config)?;
+
+    match command {
+        None => {
+            let args = NoteSearchArgs {
+                tag: vec![INBOX_TAG.to_string()],
+                ..Default::default()
+            };
+
+            // The tag filter matches prefixes, so keep only exact #inbox notes
+            let mut notes = db.search_notes(&build_search_query(&args, config)?)?;
+            notes.retain(|note| note.tags.iter().any(|t| t == INBOX_TAG));
+
+            let mut formatter = NoteSearchFormatter::new(args, TimeDisplay::from_config(config)?)
+                .with_plain_structure(config.plain_structure);
+            formatter
+                .print_notes(&notes)
+                .map_err(|e| anyhow::anyhow!("Error while formatting notes: {}", e))?;
+        }
+        Some(InboxCommand::Clear { ids }) => {
+            for id in &ids {
+                let note = db
+                    .get_note_by_id(&db.resolve_id(id)?)?
+                    .ok_or_else(|| db.note_not_found(id))?;
+
+                if !note.tags.iter().any(|t| t == INBOX_TAG) {
+                    println!("Note {} is not in the inbox", note.id);
+                    continue;
+                }
+
+                let tags = note.tags.into_iter().filter(|t| t != INBOX_TAG).collect();
+                db.update_note(&note.id, note.content, tags, note.subject_date)?;
+                println!("Cleared note {} from the inbox", note.id);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,3 +1,22 @@
+pub mod auth;
+pub mod board;
+pub mod cal;
+pub mod capture;
 pub mod config;
+pub mod dashboard;
+pub mod db;
+pub mod dev;
+pub mod export;
+pub mod import;
+pub mod inbox;
 pub mod note;
 pub mod profile;
+pub mod recur;
+pub mod search;
+pub mod server;
+pub mod shell;
+pub mod sync;
+pub mod tag;
+pub mod track;
+pub mod trash;
+pub mod usage;
@@ -1,14 +1,36 @@
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, io::IsTerminal, path::Path, process::Stdio};
 
-use jot_core::SearchQuery;
+use anyhow::Context;
+
+use jot_core::{Note, SearchQuery};
 
 use crate::{
     app_config::AppConfig,
-    args::{NoteCommand, NoteSearchArgs},
+    args::{
+        AliasCommand, IfSimilar, NoteAddArgs, NoteCommand, NoteDeleteArgs, NoteEditArgs, NoteField,
+        NoteSearchArgs, OutputFormat,
+    },
+    browser::open_url,
+    clipboard::copy_to_clipboard,
+    commands::search::expand_saved_search,
     db::LocalDb,
     editor::Editor,
-    formatters::{NoteSearchFormatter, NoteShowFormatter},
+    error::{CliError, ErrorKind},
+    formatters::{short_id, NoteSearchFormatter, NoteShowFormatter},
+    lock::OperationLock,
     prune::{self, PruneAction},
+    utils::{
+        capture_context::capture_context,
+        date_target::parse_week_start,
+        process::shell_command,
+        qr::render_qr,
+        query::parse_query,
+        retention::RetentionPolicy,
+        tag_suggest::{popular_tags, suggest_tags},
+        template::{fill_template, template_variables},
+        text::{find_urls, preview_line},
+        time_display::TimeDisplay,
+    },
 };
 
 const TEMPLATE: &str = r#"tags = ["work", "important"]
@@ -16,40 +38,107 @@ const TEMPLATE: &str = r#"tags = ["work", "important"]
 #date = "YYYY-MM-DD"
 +++"#;
 
+/// Tags suggested in the `note add --editor` template
+const TAG_HINT_COUNT: usize = 5;
+
+/// How alike (see [`jot_core::similarity`]) a new note and one added earlier
+/// the same day must be for `note add` to flag it as a double capture
+const SIMILAR_THRESHOLD: f64 = 0.95;
+
+/// Notes listed individually before `jot note expire` asks for confirmation
+const EXPIRE_PREVIEW_LIMIT: usize = 20;
+
+/// Results per page of `--page`, and per batch of `--all`, without `--per-page`
+const DEFAULT_PER_PAGE: usize = 50;
+
+/// Tag on notes captured with `note add --exec`
+pub const VOICE_TAG: &str = "voice";
+
 pub fn note_cmd(
     db_path: &Path,
     subcommand: NoteCommand,
     config: &AppConfig,
 ) -> Result<(), anyhow::Error> {
-    let db = LocalDb::open(db_path)?;
+    let db = LocalDb::open_for(db_path, config)?;
 
     match subcommand {
         NoteCommand::Add(args) => {
-            let note = if args.editor {
-                let editor = Editor::new(TEMPLATE);
+            if let Some(ref status) = args.status {
+                config.check_status(status)?;
+            }
+            let time = TimeDisplay::from_config(config)?;
+            let (content, mut tags, date) = if args.editor || args.template.is_some() {
+                let frontmatter = match args.template {
+                    Some(ref name) => note_template(name, &args, &time, config)?,
+                    None => TEMPLATE.to_string(),
+                };
+                let template = if config.suggest_tags {
+                    format!("{}{}", tag_hint(&db, &args)?, frontmatter)
+                } else {
+                    frontmatter
+                };
+                let editor = Editor::new(&template);
                 let result = editor.open(&args)?;
 
-                let mut tags: Vec<String> = result.tags.iter().map(|t| t.to_string()).collect();
-                // Add default tags from profile
-                for default_tag in &config.default_tags {
-                    if !tags.contains(default_tag) {
-                        tags.push(default_tag.clone());
+                let tags: Vec<String> = result.tags.iter().map(|t| t.to_string()).collect();
+                let date = result
+                    .date
+                    .to_date(time.today())
+                    .format("%Y-%m-%d")
+                    .to_string();
+                (result.content, tags, date)
+            } else {
+                let date = args
+                    .date
+                    .to_date(time.today())
+                    .format("%Y-%m-%d")
+                    .to_string();
+                let mut tags = args.tag.clone();
+                let content = match args.exec {
+                    Some(ref command) => {
+                        if !tags.iter().any(|t| t == VOICE_TAG) {
+                            tags.push(VOICE_TAG.to_string());
+                        }
+                        run_capture_command(command)?
                     }
+                    None => args.content.join(" "),
+                };
+                (content, tags, date)
+            };
+            // Add default tags from profile
+            for default_tag in &config.default_tags {
+                if !tags.contains(default_tag) {
+                    tags.push(default_tag.clone());
                 }
-                let date = result.date.to_date().format("%Y-%m-%d").to_string();
+            }
 
-                db.create_note(result.content, tags, Some(date))?
-            } else {
-                let date = args.date.to_date().format("%Y-%m-%d").to_string();
-                let mut tags = args.tag.clone();
-                // Add default tags from profile
-                for default_tag in &config.default_tags {
-                    if !tags.contains(default_tag) {
-                        tags.push(default_tag.clone());
+            if let Some((similar, score)) = similar_note_today(&db, &content, &time)? {
+                match if_similar(&similar, score, args.if_similar)? {
+                    IfSimilar::Add => {}
+                    IfSimilar::Append => {
+                        append_to_note(&db, &similar, &content, tags)?;
+                        match args.quiet {
+                            true => println!("{}", similar.id),
+                            false => println!("Appended to note {}", similar.id),
+                        }
+                        return Ok(());
+                    }
+                    IfSimilar::Skip => {
+                        match args.quiet {
+                            true => println!("{}", similar.id),
+                            false => println!("Nothing added, kept note {}", similar.id),
+                        }
+                        return Ok(());
                     }
                 }
-                db.create_note(args.content.join(" "), tags, Some(date))?
-            };
+            }
+            let note = db.create_note(
+                content,
+                tags,
+                Some(date),
+                args.status.clone(),
+                capture_context(config),
+            )?;
 
             if args.quiet {
                 println!("{}", note.id);
@@ -58,10 +147,39 @@ pub fn note_cmd(
             }
         }
         NoteCommand::Search(args) => {
-            let query = build_search_query(&args);
+            let args = expand_saved_search(args, config)?;
+            let query = build_search_query(&args, config)?;
+
+            if args.all {
+                // Fetch one batch at a time so memory use doesn't grow with the result count
+                let batch_size = query.limit.unwrap_or(DEFAULT_PER_PAGE);
+                let mut offset = 0;
+                let mut done = false;
+                let batches = std::iter::from_fn(|| {
+                    if done {
+                        return None;
+                    }
+                    let batch = db.search_notes(&SearchQuery {
+                        offset: Some(offset),
+                        ..query.clone()
+                    });
+                    if let Ok(ref notes) = batch {
+                        offset += notes.len();
+                        done = notes.len() < batch_size;
+                    }
+                    Some(batch)
+                });
+
+                let mut formatter =
+                    NoteSearchFormatter::new(args, TimeDisplay::from_config(config)?)
+                        .with_plain_structure(config.plain_structure);
+                return formatter.print_batches(batches);
+            }
+
             let notes = db.search_notes(&query)?;
 
-            let mut formatter = NoteSearchFormatter::new(args);
+            let mut formatter = NoteSearchFormatter::new(args, TimeDisplay::from_config(config)?)
+                .with_plain_structure(config.plain_structure);
             formatter
                 .print_notes(&notes)
                 .map_err(|e| anyhow::anyhow!("Error while formatting notes: {}", e))?;
@@ -71,15 +189,65 @@ pub fn note_cmd(
                 term: args.term,
                 tag: args.tag,
                 date: None,
-                lines: None,
-                limit: Some(1),
+                status: None,
+                meta: vec![],
+                archived: false,
+                lines: args.lines,
+                limit: Some(args.count as i64),
+                page: None,
+                per_page: None,
+                all: false,
                 output: args.output,
             };
 
-            let query = build_search_query(&search_args);
+            let query = SearchQuery {
+                chronological: true,
+                ..build_search_query(&search_args, config)?
+            };
             let notes = db.search_notes(&query)?;
 
-            let mut formatter = NoteSearchFormatter::new(search_args);
+            if let Some(field) = args.field {
+                let note = notes
+                    .first()
+                    .ok_or_else(|| CliError::new(ErrorKind::NotFound, "No matching note found"))?;
+                return print_field(note, field, config);
+            }
+
+            // Follow-up actions on the latest note reuse the regular commands
+            if args.edit || args.delete || args.yank {
+                let note = notes
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| CliError::new(ErrorKind::NotFound, "No matching note found"))?;
+
+                if args.yank {
+                    copy_to_clipboard(&note.content)?;
+                    println!("Copied note {} to the clipboard", note.id);
+                    return Ok(());
+                }
+
+                let subcommand = if args.edit {
+                    NoteCommand::Edit(NoteEditArgs {
+                        id: Some(note.id),
+                        status: None,
+                        no_status: false,
+                    })
+                } else {
+                    NoteCommand::Delete(NoteDeleteArgs {
+                        ids: vec![note.id],
+                        yes: args.yes,
+                        confirm: false,
+                        dry_run: false,
+                        step: false,
+                    })
+                };
+                drop(db);
+                return note_cmd(db_path, subcommand, config);
+            }
+
+            let mut formatter =
+                NoteSearchFormatter::new(search_args, TimeDisplay::from_config(config)?)
+                    .with_plain_structure(config.plain_structure);
             formatter
                 .print_notes(&notes)
                 .map_err(|e| anyhow::anyhow!("Error while formatting notes: {}", e))?;
@@ -87,20 +255,15 @@ pub fn note_cmd(
         NoteCommand::Show(args) => {
             // Get the note to show
             let note = if let Some(ref id) = args.id {
-                // Show specific note by ID
-                db.get_note_by_id(id)?
-                    .ok_or_else(|| anyhow::anyhow!("Note with ID '{}' not found", id))?
+                // Show specific note by ID (or alias)
+                db.get_note_by_id(&db.resolve_id(id)?)?
+                    .ok_or_else(|| db.note_not_found(id))?
             } else {
                 // Show most recent note
                 let query = SearchQuery {
-                    text: None,
-                    tags: vec![],
-                    date_from: None,
-                    date_to: None,
-                    created_from: None,
-                    created_to: None,
-                    include_deleted: false,
+                    chronological: true,
                     limit: Some(1),
+                    ..Default::default()
                 };
                 let notes = db.search_notes(&query)?;
                 notes
@@ -109,28 +272,31 @@ pub fn note_cmd(
                     .ok_or_else(|| anyhow::anyhow!("No notes found to show"))?
             };
 
-            let mut formatter = NoteShowFormatter::new(&args);
+            if let Some(field) = args.field {
+                return print_field(&note, field, config);
+            }
+
+            let mut formatter = NoteShowFormatter::new(&args, TimeDisplay::from_config(config)?)
+                .with_plain_structure(config.plain_structure);
             formatter
                 .print_note(&note)
                 .map_err(|e| anyhow::anyhow!("Error while formatting note: {}", e))?;
+            if let Some(ref dir) = args.save_to {
+                save_attachments(&db, &note, dir, args.output == OutputFormat::Json)?;
+            }
         }
         NoteCommand::Edit(args) => {
             // Get the note to edit
             let note = if let Some(id) = args.id {
-                // Edit specific note by ID
-                db.get_note_by_id(&id)?
-                    .ok_or_else(|| anyhow::anyhow!("Note with ID '{}' not found", id))?
+                // Edit specific note by ID (or alias)
+                db.get_note_by_id(&db.resolve_id(&id)?)?
+                    .ok_or_else(|| db.note_not_found(&id))?
             } else {
                 // Edit most recent note
                 let query = SearchQuery {
-                    text: None,
-                    tags: vec![],
-                    date_from: None,
-                    date_to: None,
-                    created_from: None,
-                    created_to: None,
-                    include_deleted: false,
+                    chronological: true,
                     limit: Some(1),
+                    ..Default::default()
                 };
                 let notes = db.search_notes(&query)?;
                 notes
@@ -139,6 +305,21 @@ pub fn note_cmd(
                     .ok_or_else(|| anyhow::anyhow!("No notes found to edit"))?
             };
 
+            if args.status.is_some() || args.no_status {
+                if let Some(ref status) = args.status {
+                    config.check_status(status)?;
+                }
+                match (
+                    db.set_status(&note.id, args.status.as_deref())?,
+                    args.status,
+                ) {
+                    (true, Some(status)) => println!("Note {} is now {}", note.id, status),
+                    (true, None) => println!("Cleared the status of note {}", note.id),
+                    (false, _) => println!("Note {} is unchanged", note.id),
+                }
+                return Ok(());
+            }
+
             // Create template with existing note data
             let tags_str = note
                 .tags
@@ -159,25 +340,161 @@ pub fn note_cmd(
 
             // Update the note
             let tags = parsed.tags.iter().map(|t| t.to_string()).collect();
-            let date = parsed.date.to_date().format("%Y-%m-%d").to_string();
+            let today = TimeDisplay::from_config(config)?.today();
+            let date = parsed.date.to_date(today).format("%Y-%m-%d").to_string();
 
             db.update_note(&note.id, parsed.content, tags, Some(date))?;
 
             println!("Note updated successfully ({})", note.id);
         }
+        NoteCommand::Append(args) => {
+            let note = db
+                .get_note_by_id(&db.resolve_id(&args.id)?)?
+                .ok_or_else(|| db.note_not_found(&args.id))?;
+
+            let content = format!("{}\n{}", note.content, args.content.join(" "));
+            db.update_note(&note.id, content, note.tags, note.subject_date)?;
+
+            println!("Appended to note {}", note.id);
+        }
+        NoteCommand::Attach(args) => {
+            let note = live_note(&db, &args.id)?;
+            let attachment = db
+                .attach_file(&note.id, &args.file)?
+                .ok_or_else(|| deleted_note(&note))?;
+            println!(
+                "Attached {} ({} bytes) to note {}",
+                attachment.filename, attachment.size, note.id
+            );
+        }
+        NoteCommand::Detach(args) => {
+            let note = live_note(&db, &args.id)?;
+            match db.detach_file(&note.id, &args.name)? {
+                Some(attachment) => {
+                    println!("Removed {} from note {}", attachment.filename, note.id)
+                }
+                None => {
+                    let names: Vec<&str> = note
+                        .attachments
+                        .iter()
+                        .map(|a| a.filename.as_str())
+                        .collect();
+                    let hint = match names.is_empty() {
+                        true => "it has no attachments".to_string(),
+                        false => format!("it has {}", names.join(", ")),
+                    };
+                    return Err(CliError::new(
+                        ErrorKind::NotFound,
+                        format!(
+                            "Note {} has no attachment '{}' ({})",
+                            note.id, args.name, hint
+                        ),
+                    )
+                    .into());
+                }
+            }
+        }
+        NoteCommand::Pin { ids } => {
+            for id in &ids {
+                let note = db
+                    .get_note_by_id(&db.resolve_id(id)?)?
+                    .ok_or_else(|| db.note_not_found(id))?;
+
+                if db.pin_note(&note.id)? {
+                    println!("Pinned note {}", note.id);
+                } else {
+                    println!("Note {} is already pinned", note.id);
+                }
+            }
+        }
+        NoteCommand::Unpin { ids } => {
+            for id in &ids {
+                let note = db
+                    .get_note_by_id(&db.resolve_id(id)?)?
+                    .ok_or_else(|| db.note_not_found(id))?;
+
+                if db.unpin_note(&note.id)? {
+                    println!("Unpinned note {}", note.id);
+                } else {
+                    println!("Note {} is not pinned", note.id);
+                }
+            }
+        }
+        NoteCommand::Archive { ids } => {
+            for id in &ids {
+                let note = db
+                    .get_note_by_id(&db.resolve_id(id)?)?
+                    .ok_or_else(|| db.note_not_found(id))?;
+
+                if db.archive_note(&note.id)? {
+                    println!("Archived note {}", note.id);
+                } else {
+                    println!("Note {} is already archived", note.id);
+                }
+            }
+        }
+        NoteCommand::Unarchive { ids } => {
+            for id in &ids {
+                let note = db
+                    .get_note_by_id(&db.resolve_id(id)?)?
+                    .ok_or_else(|| db.note_not_found(id))?;
+
+                if db.unarchive_note(&note.id)? {
+                    println!("Unarchived note {}", note.id);
+                } else {
+                    println!("Note {} is not archived", note.id);
+                }
+            }
+        }
+        NoteCommand::Open(args) => {
+            let note = db
+                .get_note_by_id(&db.resolve_id(&args.id)?)?
+                .ok_or_else(|| db.note_not_found(&args.id))?;
+
+            let urls = find_urls(&note.content);
+            let url = match args.number.checked_sub(1).and_then(|i| urls.get(i)) {
+                Some(url) => url,
+                None if urls.is_empty() => {
+                    return Err(CliError::new(
+                        ErrorKind::NotFound,
+                        format!("Note {} contains no links", note.id),
+                    )
+                    .into());
+                }
+                None => {
+                    return Err(CliError::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Note {} has {} link(s); pick a number from 1 to {}",
+                            note.id,
+                            urls.len(),
+                            urls.len()
+                        ),
+                    )
+                    .into());
+                }
+            };
+
+            open_url(url)?;
+        }
+        NoteCommand::Share(args) => {
+            let note = db
+                .get_note_by_id(&db.resolve_id(&args.id)?)?
+                .ok_or_else(|| db.note_not_found(&args.id))?;
+
+            // The server has no share links yet, so the code carries the text itself
+            if args.qr {
+                println!("{}", render_qr(&note.content, args.invert)?);
+            }
+        }
         NoteCommand::Delete(args) => {
             // Get note IDs to delete
             let ids_to_delete: Vec<String> = if args.ids.is_empty() {
                 // Delete most recent note
                 let query = SearchQuery {
-                    text: None,
-                    tags: vec![],
-                    date_from: None,
-                    date_to: None,
-                    created_from: None,
-                    created_to: None,
-                    include_deleted: false,
+                    chronological: true,
                     limit: Some(1),
+                    ..Default::default()
                 };
                 let notes = db.search_notes(&query)?;
                 if notes.is_empty() {
@@ -186,20 +503,30 @@ pub fn note_cmd(
                 vec![notes[0].id.clone()]
             } else {
                 args.ids
+                    .iter()
+                    .map(|id| db.resolve_id(id))
+                    .collect::<Result<_, _>>()?
             };
 
-            // Confirm deletion unless --yes flag is provided
-            if !args.yes {
+            // Delete without confirmation (--step always prompts per note)
+            let confirm = needs_confirmation(args.yes, args.confirm, config.confirm_delete);
+            if !confirm && !args.step && !args.dry_run {
                 for id in &ids_to_delete {
-                    let note = db
-                        .get_note_by_id(id)?
-                        .ok_or_else(|| anyhow::anyhow!("Note with ID '{}' not found", id))?;
-
-                    let preview = if note.content.len() > 60 {
-                        format!("{}...", &note.content[..60])
-                    } else {
-                        note.content.clone()
-                    };
+                    db.soft_delete_note(id)?;
+                    println!("Deleted note {}", id);
+                }
+                return Ok(());
+            }
+
+            let notes = ids_to_delete
+                .iter()
+                .map(|id| db.get_note_by_id(id)?.ok_or_else(|| db.note_not_found(id)))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // Opt-in per-note prompting
+            if args.step && !args.dry_run {
+                for note in &notes {
+                    let preview = preview_line(&note.content, 60);
 
                     print!("Delete note \"{}\"? [y/N]: ", preview);
                     std::io::Write::flush(&mut std::io::stdout())?;
@@ -208,35 +535,223 @@ pub fn note_cmd(
                     std::io::stdin().read_line(&mut input)?;
 
                     if !input.trim().eq_ignore_ascii_case("y") {
-                        println!("Skipped deleting note {}", id);
+                        println!("Skipped deleting note {}", note.id);
                         continue;
                     }
 
-                    db.soft_delete_note(id)?;
-                    println!("Deleted note {}", id);
+                    db.soft_delete_note(&note.id)?;
+                    println!("Deleted note {}", note.id);
                 }
+                return Ok(());
+            }
+
+            if args.dry_run {
+                println!("Would delete {} note(s):", notes.len());
             } else {
-                // Delete without confirmation
-                for id in &ids_to_delete {
-                    db.soft_delete_note(id)?;
-                    println!("Deleted note {}", id);
+                println!("About to delete {} note(s):", notes.len());
+            }
+            for note in &notes {
+                println!("  {} {}", note.id, preview_line(&note.content, 60));
+            }
+
+            if args.dry_run {
+                return Ok(());
+            }
+
+            print!("\nDelete {} note(s)? [y/N]: ", notes.len());
+            std::io::Write::flush(&mut std::io::stdout())?;
+
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+
+            if !input.trim().eq_ignore_ascii_case("y") {
+                println!("Aborted. No notes were deleted.");
+                return Ok(());
+            }
+
+            let ids: Vec<&str> = notes.iter().map(|n| n.id.as_str()).collect();
+            db.soft_delete_notes(&ids)?;
+            for id in ids {
+                println!("Deleted note {}", id);
+            }
+        }
+        NoteCommand::Dedupe(args) => {
+            let groups = db.find_duplicate_notes()?;
+
+            if groups.is_empty() {
+                println!("No duplicate notes found.");
+                return Ok(());
+            }
+
+            for group in &groups {
+                let preview = preview_line(&group[0].content, 60);
+                println!("\"{}\" ({} copies)", preview, group.len());
+                for (i, note) in group.iter().enumerate() {
+                    let marker = if i == 0 { " (oldest)" } else { "" };
+                    println!("  {}{}", note.id, marker);
+                }
+            }
+
+            if !args.delete && !args.merge {
+                return Ok(());
+            }
+
+            let duplicate_count: usize = groups.iter().map(|g| g.len() - 1).sum();
+            if needs_confirmation(args.yes, args.confirm, config.confirm_dedupe) {
+                print!("\nDelete {} duplicate note(s)? [y/N]: ", duplicate_count);
+                std::io::Write::flush(&mut std::io::stdout())?;
+
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input)?;
+
+                if !input.trim().eq_ignore_ascii_case("y") {
+                    println!("Aborted. No notes were deleted.");
+                    return Ok(());
+                }
+            }
+
+            for group in &groups {
+                let (keep, duplicates) = group.split_at(1);
+                let keep = &keep[0];
+
+                if args.merge {
+                    let mut tags = keep.tags.clone();
+                    for tag in duplicates.iter().flat_map(|n| &n.tags) {
+                        if !tags.contains(tag) {
+                            tags.push(tag.clone());
+                        }
+                    }
+                    if tags != keep.tags {
+                        db.update_note(
+                            &keep.id,
+                            keep.content.clone(),
+                            tags,
+                            keep.subject_date.clone(),
+                        )?;
+                    }
+                }
+
+                let ids: Vec<&str> = duplicates.iter().map(|n| n.id.as_str()).collect();
+                db.soft_delete_notes(&ids)?;
+            }
+            println!("Deleted {} duplicate note(s).", duplicate_count);
+        }
+        NoteCommand::Expire(args) => {
+            let policies = match args.keep {
+                Some(keep) => vec![RetentionPolicy {
+                    keep,
+                    tags: args.tag,
+                }],
+                None => config.retention.clone(),
+            };
+
+            if policies.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "No retention window given. Use --keep <PERIOD> or add a [[retention]] policy to the profile."
+                ));
+            }
+
+            // Collect notes past any policy's window (a note may match several)
+            let now = chrono::Local::now();
+            let mut expired: Vec<Note> = Vec::new();
+            for policy in &policies {
+                let Some(cutoff) = policy.keep.cutoff(now) else {
+                    continue;
+                };
+                let query = SearchQuery {
+                    tags: policy.tags.clone(),
+                    created_to: Some(cutoff.timestamp_millis()),
+                    ..Default::default()
+                };
+                for note in db.search_notes(&query)? {
+                    if !expired.iter().any(|n| n.id == note.id) {
+                        expired.push(note);
+                    }
+                }
+            }
+
+            if expired.is_empty() {
+                println!("No notes are past their retention window.");
+                return Ok(());
+            }
+
+            let time = TimeDisplay::from_config(config)?;
+            println!("{} note(s) past their retention window:", expired.len());
+            for note in expired.iter().take(EXPIRE_PREVIEW_LIMIT) {
+                let created = time
+                    .date_of(note.created_at)
+                    .map(|date| date.to_string())
+                    .unwrap_or_default();
+                println!("  {} {}", created, preview_line(&note.content, 60));
+            }
+            if expired.len() > EXPIRE_PREVIEW_LIMIT {
+                println!("  ... and {} more", expired.len() - EXPIRE_PREVIEW_LIMIT);
+            }
+
+            if args.dry_run {
+                return Ok(());
+            }
+
+            if needs_confirmation(args.yes, args.confirm, config.confirm_expire) {
+                print!("\nDelete {} note(s)? [y/N]: ", expired.len());
+                std::io::Write::flush(&mut std::io::stdout())?;
+
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input)?;
+
+                if !input.trim().eq_ignore_ascii_case("y") {
+                    println!("Aborted. No notes were deleted.");
+                    return Ok(());
                 }
             }
+
+            let ids: Vec<&str> = expired.iter().map(|n| n.id.as_str()).collect();
+            db.soft_delete_notes(&ids)?;
+            println!("Deleted {} note(s).", expired.len());
         }
+        NoteCommand::Alias { command } => match command.unwrap_or(AliasCommand::List) {
+            AliasCommand::Set { name, id } => {
+                validate_alias_name(&name)?;
+                let note = db
+                    .get_note_by_id(&db.resolve_id(&id)?)?
+                    .ok_or_else(|| db.note_not_found(&id))?;
+                db.set_alias(&name, &note.id)?;
+                println!("Alias '{}' now points to {}", name, note.id);
+            }
+            AliasCommand::Remove { name } => {
+                if !db.remove_alias(&name)? {
+                    return Err(CliError::new(
+                        ErrorKind::NotFound,
+                        format!("Alias '{}' not found", name),
+                    )
+                    .into());
+                }
+                println!("Removed alias '{}'", name);
+            }
+            AliasCommand::List => {
+                let aliases = db.list_aliases()?;
+                if aliases.is_empty() {
+                    println!("No aliases defined.");
+                }
+                for alias in aliases {
+                    let preview = db
+                        .get_note_by_id(&alias.note_id)?
+                        .map(|note| preview_line(&note.content, 50))
+                        .unwrap_or_else(|| "(note not found)".to_string());
+                    println!("{}\t{}\t{}", alias.name, alias.note_id, preview);
+                }
+            }
+        },
         NoteCommand::Prune(args) => {
             // Build search query based on args
             let limit = if args.all { None } else { Some(args.limit) };
 
+            let week_start = parse_week_start(config.week_start.as_deref())?;
+            let today = TimeDisplay::from_config(config)?.today();
             let (date_from, date_to) = args
                 .date
                 .as_ref()
-                .map(|d| {
-                    let (from, to) = d.to_date_range();
-                    (
-                        from.map(|d| d.format("%Y-%m-%d").to_string()),
-                        to.map(|d| d.format("%Y-%m-%d").to_string()),
-                    )
-                })
+                .map(|d| d.to_date_range(today, week_start))
                 .unwrap_or((None, None));
 
             let query = SearchQuery {
@@ -244,10 +759,8 @@ pub fn note_cmd(
                 tags: args.tag,
                 date_from,
                 date_to,
-                created_from: None,
-                created_to: None,
-                include_deleted: false,
                 limit: limit.map(|l| l as usize),
+                ..Default::default()
             };
 
             // Get notes to prune
@@ -261,11 +774,11 @@ pub fn note_cmd(
             // Generate prune file content
             let prune_content = prune::generate_prune_file(&notes);
 
-            // Open editor
-            let edited_content = prune::open_prune_editor(&prune_content)?;
-
-            // Parse decisions
-            let decisions = prune::parse_prune_file(&edited_content)?;
+            // Open editor until the file parses (re-opening with the error on failure)
+            let Some(decisions) = prune::edit_prune_file(&prune_content, &notes)? else {
+                println!("Aborted. No notes were deleted.");
+                return Ok(());
+            };
 
             // Create a map of note IDs to notes for quick lookup
             let note_map: HashMap<String, _> =
@@ -279,11 +792,21 @@ pub fn note_cmd(
                 .collect();
 
             // Show summary and confirm
-            if prune::confirm_deletions(&notes_to_delete)? {
+            let proceed = if needs_confirmation(args.yes, args.confirm, config.confirm_prune) {
+                prune::confirm_deletions(&notes_to_delete)?
+            } else if notes_to_delete.is_empty() {
+                println!("No notes to delete.");
+                false
+            } else {
+                true
+            };
+            if proceed {
+                let _lock = (!config.ephemeral)
+                    .then(|| OperationLock::acquire(db_path))
+                    .transpose()?;
                 // Delete the notes
-                for note in &notes_to_delete {
-                    db.soft_delete_note(&note.id)?;
-                }
+                let ids: Vec<&str> = notes_to_delete.iter().map(|n| n.id.as_str()).collect();
+                db.soft_delete_notes(&ids)?;
                 println!("Deleted {} note(s).", notes_to_delete.len());
             } else {
                 println!("Aborted. No notes were deleted.");
@@ -294,28 +817,354 @@ pub fn note_cmd(
     Ok(())
 }
 
-fn build_search_query(args: &NoteSearchArgs) -> SearchQuery {
-    let (date_from, date_to) = args
+/// Whether a destructive command should prompt: `--yes` skips it,
+/// `--confirm` forces it, otherwise the profile setting decides
+fn needs_confirmation(yes: bool, confirm: bool, profile_default: bool) -> bool {
+    !yes && (confirm || profile_default)
+}
+
+/// Alias names are short identifiers that can't be mistaken for a note ID
+fn validate_alias_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(CliError::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid alias '{}': use letters, digits, '-' or '_'", name),
+        )
+        .into());
+    }
+
+    if ulid::Ulid::from_string(name).is_ok() {
+        return Err(CliError::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid alias '{}': it looks like a note ID", name),
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// A note that isn't deleted, by ID or alias
+fn live_note(db: &LocalDb, id: &str) -> anyhow::Result<Note> {
+    let note = db
+        .get_note_by_id(&db.resolve_id(id)?)?
+        .ok_or_else(|| db.note_not_found(id))?;
+    match note.deleted_at {
+        Some(_) => Err(deleted_note(&note).into()),
+        None => Ok(note),
+    }
+}
+
+fn deleted_note(note: &Note) -> CliError {
+    CliError::new(
+        ErrorKind::InvalidInput,
+        format!("Note {} is deleted", note.id),
+    )
+}
+
+/// Print one field of `note` with nothing around it (`--field`)
+fn print_field(note: &Note, field: NoteField, config: &AppConfig) -> anyhow::Result<()> {
+    let value = match field {
+        NoteField::Content => note.content.clone(),
+        NoteField::Id => note.id.clone(),
+        NoteField::Date => match note.subject_date {
+            Some(ref date) => date.clone(),
+            None => TimeDisplay::from_config(config)?
+                .date_of(note.created_at)
+                .map(|date| date.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+        },
+        NoteField::Tags => note.tags.join(","),
+    };
+    if value.ends_with('\n') {
+        print!("{}", value);
+    } else {
+        println!("{}", value);
+    }
+    Ok(())
+}
+
+/// Write the attachments of `note` stored on this device into `dir`
+///
+/// Only the file name of each attachment is used, so names synced from
+/// another device can't point outside `dir`. Progress goes to stderr when
+/// stdout carries JSON.
+fn save_attachments(db: &LocalDb, note: &Note, dir: &Path, json: bool) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    for attachment in &note.attachments {
+        let Some(name) = Path::new(&attachment.filename).file_name() else {
+            continue;
+        };
+        let Some(data) = db.attachment_data(attachment)? else {
+            eprintln!(
+                "Skipped {}: it was attached on another device",
+                attachment.filename
+            );
+            continue;
+        };
+        let path = dir.join(name);
+        std::fs::write(&path, data)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        match json {
+            true => eprintln!("Saved {}", path.display()),
+            false => println!("Saved {}", path.display()),
+        }
+    }
+    Ok(())
+}
+
+/// Run the `note add --exec` command and return what it printed, trimmed
+///
+/// The command keeps the terminal's stdin and stderr, so a recorder can show
+/// its progress and be stopped with a key press.
+fn run_capture_command(command: &str) -> anyhow::Result<String> {
+    let output = shell_command(command)
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| format!("Failed to run capture command '{}'", command))?;
+    if !output.status.success() {
+        anyhow::bail!("Capture command '{}' failed ({})", command, output.status);
+    }
+
+    let content = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if content.is_empty() {
+        return Err(CliError::new(
+            ErrorKind::InvalidInput,
+            format!("Capture command '{}' printed nothing to save", command),
+        )
+        .into());
+    }
+    Ok(content)
+}
+
+/// The note added today (in `time`'s timezone) whose text is most like
+/// `content`, with how alike they are, when that reaches [`SIMILAR_THRESHOLD`]
+fn similar_note_today(
+    db: &LocalDb,
+    content: &str,
+    time: &TimeDisplay,
+) -> anyhow::Result<Option<(Note, f64)>> {
+    let today = db.search_notes(&SearchQuery {
+        created_from: Some(time.start_of_day(time.today())),
+        ..Default::default()
+    })?;
+    Ok(today
+        .into_iter()
+        .map(|note| {
+            let score = jot_core::similarity(&note.content, content);
+            (note, score)
+        })
+        .filter(|(_, score)| *score >= SIMILAR_THRESHOLD)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b)))
+}
+
+/// Warn about a note nearly the same as the one being added and settle what
+/// to do: `choice` when given, otherwise ask on a terminal and add if not
+fn if_similar(similar: &Note, score: f64, choice: Option<IfSimilar>) -> anyhow::Result<IfSimilar> {
+    eprintln!(
+        "Warning: this is {:.0}% the same as note {} added today: {}",
+        score * 100.0,
+        short_id(&similar.id),
+        preview_line(&similar.content, 60)
+    );
+    if let Some(choice) = choice {
+        return Ok(choice);
+    }
+    if !std::io::stdin().is_terminal() {
+        return Ok(IfSimilar::Add);
+    }
+
+    eprint!("Append to it instead? [y/N]: ");
+    std::io::Write::flush(&mut std::io::stderr())?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(match input.trim().eq_ignore_ascii_case("y") {
+        true => IfSimilar::Append,
+        false => IfSimilar::Add,
+    })
+}
+
+/// Add `content` to the end of `note`, along with any tags it lacks
+fn append_to_note(
+    db: &LocalDb,
+    note: &Note,
+    content: &str,
+    tags: Vec<String>,
+) -> anyhow::Result<()> {
+    let mut merged = note.tags.clone();
+    for tag in tags {
+        if !merged.contains(&tag) {
+            merged.push(tag);
+        }
+    }
+    db.update_note(
+        &note.id,
+        format!("{}\n\n{}", note.content.trim_end(), content),
+        merged,
+        note.subject_date.clone(),
+    )
+}
+
+/// Editor text for `note add --template`: the template's tags and any given
+/// with `-t` as frontmatter, then its body with the variables filled in
+fn note_template(
+    name: &str,
+    args: &NoteAddArgs,
+    time: &TimeDisplay,
+    config: &AppConfig,
+) -> anyhow::Result<String> {
+    let Some(template) = config.templates.get(name) else {
+        let known: Vec<&str> = config.templates.keys().map(String::as_str).collect();
+        let message = match known.is_empty() {
+            true => format!(
+                "Unknown template '{}': the profile has no [templates.<name>] tables",
+                name
+            ),
+            false => format!(
+                "Unknown template '{}' (profile templates: {})",
+                name,
+                known.join(", ")
+            ),
+        };
+        return Err(CliError::new(ErrorKind::InvalidInput, message).into());
+    };
+
+    let date = args
         .date
-        .as_ref()
-        .map(|d| {
-            let (from, to) = d.to_date_range();
-            (
-                from.map(|d| d.format("%Y-%m-%d").to_string()),
-                to.map(|d| d.format("%Y-%m-%d").to_string()),
+        .to_date(time.today())
+        .format("%Y-%m-%d")
+        .to_string();
+    let mut values: HashMap<String, String> = HashMap::new();
+    values.insert("date".to_string(), date.clone());
+    values.insert("time".to_string(), time.format_now("%H:%M"));
+    for var in &args.var {
+        let (key, value) = var.split_once('=').ok_or_else(|| {
+            CliError::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Invalid --var '{}' (use name=value, e.g. project=Apollo)",
+                    var
+                ),
             )
-        })
-        .unwrap_or((None, None));
+        })?;
+        values.insert(key.trim().to_string(), value.to_string());
+    }
+
+    let missing: Vec<String> = template_variables(&template.body)
+        .into_iter()
+        .filter(|name| !values.contains_key(name))
+        .collect();
+    if !missing.is_empty() {
+        if !std::io::stdin().is_terminal() {
+            return Err(CliError::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Template '{}' needs a value for {} (pass --var name=value)",
+                    name,
+                    missing.join(", ")
+                ),
+            )
+            .into());
+        }
+        cliclack::intro(format!("New note from template '{}'", name))?;
+        for variable in missing {
+            let prompt = template
+                .prompts
+                .get(&variable)
+                .cloned()
+                .unwrap_or_else(|| variable.clone());
+            let value: String = cliclack::input(prompt).required(false).interact()?;
+            values.insert(variable, value);
+        }
+        cliclack::outro("Opening the editor")?;
+    }
+
+    let mut tags = template.tags.clone();
+    for tag in &args.tag {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+    let tags = toml::Value::Array(tags.into_iter().map(toml::Value::String).collect());
+    Ok(format!(
+        "tags = {}\ndate = \"{}\"\n+++\n{}",
+        tags,
+        date,
+        fill_template(&template.body, &values)
+    ))
+}
+
+/// Comment line for the editor template listing tags suggested from the
+/// content and tags given on the command line (the most used tags otherwise)
+fn tag_hint(db: &LocalDb, args: &NoteAddArgs) -> anyhow::Result<String> {
+    let history = db.search_notes(&SearchQuery::default())?;
+    let content = args.content.join(" ");
 
+    let tags = if content.is_empty() && args.tag.is_empty() {
+        popular_tags(&history, TAG_HINT_COUNT)
+    } else {
+        suggest_tags(&history, &content, &args.tag, TAG_HINT_COUNT)
+            .into_iter()
+            .map(|s| s.tag)
+            .collect()
+    };
+
+    if tags.is_empty() {
+        return Ok(String::new());
+    }
+    let tags: Vec<String> = tags.iter().map(|t| format!("\"{}\"", t)).collect();
+    Ok(format!("# Suggested tags: {}\n", tags.join(", ")))
+}
+
+/// Build the query from the term's filters plus the flags; `--tag` adds to
+/// the term's tags and `--date` replaces its date range
+pub fn build_search_query(
+    args: &NoteSearchArgs,
+    config: &AppConfig,
+) -> anyhow::Result<SearchQuery> {
+    let week_start = parse_week_start(config.week_start.as_deref())?;
+    let today = TimeDisplay::from_config(config)?.today();
+    let mut query = match args.term {
+        Some(ref term) => parse_query(term, today, week_start)?,
+        None => SearchQuery::default(),
+    };
+
+    for tag in &args.tag {
+        if !query.tags.contains(tag) {
+            query.tags.push(tag.clone());
+        }
+    }
+    if let Some(ref date) = args.date {
+        (query.date_from, query.date_to) = date.to_date_range(today, week_start);
+    }
+    if let Some(ref status) = args.status {
+        query.status = Some(status.clone());
+    }
+    for pair in &args.meta {
+        let Some((key, value)) = pair.split_once('=') else {
+            return Err(CliError::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid --meta '{}' (use key=value, e.g. repo=jot)", pair),
+            )
+            .into());
+        };
+        query
+            .meta
+            .push((key.trim().to_string(), value.trim().to_string()));
+    }
+    query.include_archived = args.archived;
     // TODO: Add created_from and created_to from args when --created flag is implemented
-    SearchQuery {
-        text: args.term.clone(),
-        tags: args.tag.clone(),
-        date_from,
-        date_to,
-        created_from: None,
-        created_to: None,
-        include_deleted: false,
-        limit: args.limit.map(|l| l as usize),
+    query.limit = args.limit.map(|l| l as usize);
+    if args.page.is_some() || args.per_page.is_some() || args.all {
+        let per_page = args.per_page.map_or(DEFAULT_PER_PAGE, |n| n as usize);
+        let page = args.page.unwrap_or(1) as usize;
+        query.limit = Some(per_page);
+        query.offset = Some((page - 1).saturating_mul(per_page)).filter(|&o| o > 0);
     }
+
+    Ok(query)
 }
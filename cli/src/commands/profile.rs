@@ -1,13 +1,24 @@
 use crate::{
+    app_config::AppConfig,
     args::ProfileCommand,
+    db::LocalDb,
     profile::{self, Profile},
+    utils::time_display::TimeDisplay,
 };
 
-pub fn profile_cmd(subcommand: Option<ProfileCommand>) -> Result<(), anyhow::Error> {
+/// `config` is for the profile this invocation runs with, which `--profile`
+/// or `JOT_PROFILE` (e.g. from `profile use --session`) may set
+pub fn profile_cmd(
+    subcommand: Option<ProfileCommand>,
+    config: &AppConfig,
+) -> Result<(), anyhow::Error> {
+    let active = config.profile_name.as_str();
     match subcommand.unwrap_or(ProfileCommand::Current) {
-        ProfileCommand::Use { name } => {
+        ProfileCommand::Use { name, session } => {
             // Set as current profile
-            profile::set_current_profile_name(&name)?;
+            if !session {
+                profile::set_current_profile_name(&name)?;
+            }
 
             // Create profile config if it doesn't exist
             let config_path = profile::get_profile_config_path(&name);
@@ -18,29 +29,58 @@ pub fn profile_cmd(subcommand: Option<ProfileCommand>) -> Result<(), anyhow::Err
 
                 let new_profile = Profile::default();
                 new_profile.save(&config_path)?;
-                println!("Created new profile: {}", name);
+                // stdout is eval'd in session mode
+                match session {
+                    true => eprintln!("Created new profile: {}", name),
+                    false => println!("Created new profile: {}", name),
+                }
             }
 
-            println!("Switched to profile: {}", name);
+            if session {
+                println!("export JOT_PROFILE={}", shell_quote(&name));
+            } else {
+                println!("Switched to profile: {}", name);
+            }
         }
         ProfileCommand::List => {
             let profiles = profile::list_profiles()?;
-            let current =
-                profile::get_current_profile_name().unwrap_or_else(|_| "default".to_string());
+            let time = TimeDisplay::from_config(config)?;
 
             println!("Available profiles:");
             for profile_name in profiles {
-                let marker = if profile_name == current { "*" } else { " " };
-                let db_path = profile::get_profile_db_path(&profile_name);
-                println!("{} {} ({})", marker, profile_name, db_path.display());
+                let marker = if profile_name == active { "*" } else { " " };
+                let db_path = profile::resolve_profile_db_path(&profile_name)?;
+                // One unreadable database shouldn't hide the other profiles
+                let summary = match LocalDb::summary(&db_path) {
+                    Ok(Some(summary)) => match summary.last_modified {
+                        Some(at) => format!(
+                            "{} note(s), last modified {}",
+                            summary.notes,
+                            time.format_timestamp(at)
+                        ),
+                        None => "no notes".to_string(),
+                    },
+                    Ok(None) => "no database yet".to_string(),
+                    Err(e) => format!("unreadable: {:#}", e),
+                };
+                println!(
+                    "{} {} ({}) - {}",
+                    marker,
+                    profile_name,
+                    db_path.display(),
+                    summary
+                );
             }
         }
         ProfileCommand::Current => {
-            let current = profile::get_current_profile_name()?;
-            let db_path = profile::get_profile_db_path(&current);
-            println!("Current profile: {} ({})", current, db_path.display());
+            println!("Current profile: {} ({})", active, config.db_path);
         }
     }
 
     Ok(())
 }
+
+/// Quote `value` as a single shell word (POSIX shells and fish)
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
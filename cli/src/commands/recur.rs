@@ -0,0 +1,123 @@
+use std::path::Path;
+
+use chrono::NaiveDate;
+use jot_core::{schema::DATE_FORMAT, Recurrence};
+
+use crate::{
+    app_config::AppConfig,
+    args::{RecurAddArgs, RecurCommand},
+    db::LocalDb,
+    error::{CliError, ErrorKind},
+    utils::{recurrence::Every, text::preview_line, time_display::TimeDisplay},
+};
+
+/// Width of the content previews in `jot recur list`
+const LINE_WIDTH: usize = 50;
+
+pub fn recur_cmd(db_path: &Path, command: RecurCommand, config: &AppConfig) -> anyhow::Result<()> {
+    let db = LocalDb::open_for(db_path, config)?;
+
+    match command {
+        RecurCommand::Add(args) => add(&db, args, config)?,
+        RecurCommand::List => {
+            let rules = db.list_recurrences()?;
+            if rules.is_empty() {
+                println!("No recurring notes");
+            }
+            for rule in rules {
+                let tags: Vec<String> = rule.tags.iter().map(|t| format!("#{}", t)).collect();
+                println!(
+                    "{:>3}  every {:<9}  next {}  {} {}",
+                    rule.id,
+                    rule.every,
+                    rule.next_date,
+                    preview_line(&rule.content, LINE_WIDTH),
+                    tags.join(" ")
+                );
+            }
+        }
+        RecurCommand::Remove { id } => match db.remove_recurrence(id)? {
+            true => println!("Removed recurring note {}", id),
+            false => {
+                return Err(CliError::new(
+                    ErrorKind::NotFound,
+                    format!("No recurring note {} (see jot recur list)", id),
+                )
+                .into())
+            }
+        },
+        RecurCommand::Run => {
+            let created = run(&db, TimeDisplay::from_config(config)?.today())?;
+            println!("Created {} recurring note(s)", created);
+        }
+    }
+    Ok(())
+}
+
+fn add(db: &LocalDb, args: RecurAddArgs, config: &AppConfig) -> anyhow::Result<()> {
+    let every: Every = args.every.parse()?;
+    let mut tags = args.tag;
+    for default_tag in &config.default_tags {
+        if !tags.contains(default_tag) {
+            tags.push(default_tag.clone());
+        }
+    }
+    let start =
+        every.first_on_or_after(args.from.to_date(TimeDisplay::from_config(config)?.today()));
+
+    let rule = db.add_recurrence(
+        &args.content.join(" "),
+        &tags,
+        &every.to_string(),
+        &start.format(DATE_FORMAT).to_string(),
+    )?;
+    println!(
+        "Added recurring note {} (every {}, first on {}); create the notes with `jot recur run`",
+        rule.id, rule.every, rule.next_date
+    );
+    Ok(())
+}
+
+/// Create a note for every due occurrence up to `today`, returning how many
+///
+/// Each rule moves past an occurrence together with creating its note, so
+/// an interrupted run picks up where it stopped, and a rule that another
+/// run already moved on is left to that run.
+fn run(db: &LocalDb, today: NaiveDate) -> anyhow::Result<usize> {
+    let mut created = 0;
+    for rule in db.due_recurrences(&today.format(DATE_FORMAT).to_string())? {
+        let every: Every = rule.every.parse()?;
+        let start = parse_rule_date(&rule, &rule.start_date)?;
+        let mut date = parse_rule_date(&rule, &rule.next_date)?;
+        while date <= today {
+            let next = every.next_after(start, date);
+            let Some(note) = db.create_occurrence(
+                &rule,
+                &date.format(DATE_FORMAT).to_string(),
+                &next.format(DATE_FORMAT).to_string(),
+            )?
+            else {
+                break;
+            };
+            println!(
+                "{} {} ({})",
+                note.id,
+                preview_line(&note.content, LINE_WIDTH),
+                date
+            );
+            created += 1;
+            date = next;
+        }
+    }
+    Ok(created)
+}
+
+fn parse_rule_date(rule: &Recurrence, date: &str) -> anyhow::Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT).map_err(|_| {
+        CliError::new(
+            ErrorKind::InvalidInput,
+            format!("Recurring note {} has an invalid date '{}'", rule.id, date),
+        )
+        .into()
+    })
+}
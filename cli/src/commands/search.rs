@@ -0,0 +1,144 @@
+use std::path::Path;
+
+use crate::{
+    app_config::AppConfig,
+    args::{NoteSearchArgs, SearchCommand, SearchSaveArgs},
+    error::{CliError, ErrorKind},
+    profile::{Profile, SavedSearch},
+};
+
+pub fn search_cmd(command: Option<SearchCommand>, config: &AppConfig) -> anyhow::Result<()> {
+    match command.unwrap_or(SearchCommand::List) {
+        SearchCommand::Save(args) => {
+            validate_search_name(&args.name)?;
+            let name = args.name.clone();
+            update_profile(config, |profile| {
+                profile.searches.insert(name.clone(), saved_search(args));
+                Ok(())
+            })?;
+            println!("Saved search '{}' (run it with `jot ls @{}`)", name, name);
+        }
+        SearchCommand::List => {
+            if config.searches.is_empty() {
+                println!("No saved searches.");
+            }
+            for (name, search) in &config.searches {
+                println!("@{}\t{}", name, describe(search));
+            }
+        }
+        SearchCommand::Remove { name } => {
+            update_profile(config, |profile| match profile.searches.remove(&name) {
+                Some(_) => Ok(()),
+                None => Err(unknown_search(&name)),
+            })?;
+            println!("Deleted saved search '{}'", name);
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace an `@name` term with the saved search's filters
+///
+/// Tags given on the command line are added to the saved ones, while an
+/// explicit `--date` or `--limit` replaces the saved value.
+pub fn expand_saved_search(
+    mut args: NoteSearchArgs,
+    config: &AppConfig,
+) -> anyhow::Result<NoteSearchArgs> {
+    let Some(name) = args.term.as_deref().and_then(|t| t.strip_prefix('@')) else {
+        return Ok(args);
+    };
+    let search = config
+        .searches
+        .get(name)
+        .ok_or_else(|| unknown_search(name))?;
+
+    let mut tags = search.tags.clone();
+    for tag in args.tag {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    args.tag = tags;
+    args.term = search.term.clone();
+    if args.date.is_none() {
+        args.date = search.date.as_deref().map(str::parse).transpose()?;
+    }
+    if args.limit.is_none() {
+        args.limit = search.limit;
+    }
+    Ok(args)
+}
+
+fn saved_search(args: SearchSaveArgs) -> SavedSearch {
+    SavedSearch {
+        term: args.term,
+        tags: args.tag,
+        date: args.date.map(|d| d.to_string()),
+        limit: args.limit,
+    }
+}
+
+/// The saved filters written as `jot ls` arguments
+fn describe(search: &SavedSearch) -> String {
+    let mut parts = Vec::new();
+    if let Some(ref term) = search.term {
+        parts.push(format!("{:?}", term));
+    }
+    if !search.tags.is_empty() {
+        parts.push(format!("--tag {}", search.tags.join(",")));
+    }
+    if let Some(ref date) = search.date {
+        parts.push(format!("--date {:?}", date));
+    }
+    if let Some(limit) = search.limit {
+        parts.push(format!("--limit {}", limit));
+    }
+    if parts.is_empty() {
+        "(all notes)".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Load the profile file (or start a new one), apply `change` and save it
+pub fn update_profile(
+    config: &AppConfig,
+    change: impl FnOnce(&mut Profile) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let path = Path::new(&config.profile_path);
+    let mut profile = Profile::from_path(path)?.unwrap_or_default();
+    change(&mut profile)?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    profile.save(path)
+}
+
+fn validate_search_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(CliError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Invalid search name '{}': use letters, digits, '-' or '_'",
+                name
+            ),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn unknown_search(name: &str) -> anyhow::Error {
+    CliError::new(
+        ErrorKind::NotFound,
+        format!("No saved search named '{}' (see `jot search list`)", name),
+    )
+    .into()
+}
@@ -0,0 +1,463 @@
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use flate2::read::GzDecoder;
+use reqwest::{blocking::Client, StatusCode};
+use serde::Deserialize;
+
+use crate::{
+    app_config::AppConfig,
+    args::{ServerBootstrapArgs, ServerCommand, ServerPingArgs},
+    db::LocalDb,
+    error::{CliError, ErrorKind},
+    profile, tls,
+};
+
+/// Access tokens with less than this left are refreshed before use, so a
+/// sync doesn't fail halfway and the daemon outlives the token lifetime
+const REFRESH_WITHIN_SECS: i64 = 24 * 60 * 60;
+
+/// Response header the server reports its version in
+const VERSION_HEADER: &str = "x-jot-version";
+
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Downloading a large account's snapshot can take a while
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Response header with the server time (ms) a snapshot was taken at
+const SNAPSHOT_AT_HEADER: &str = "x-jot-snapshot-at";
+
+/// Body of the server's `GET /version`
+#[derive(Debug, Deserialize)]
+pub struct ServerVersion {
+    pub version: String,
+    pub sync_protocols: Vec<u32>,
+    #[serde(default)]
+    pub features: ServerFeatures,
+}
+
+/// Body of the server's error responses
+#[derive(Debug, Deserialize)]
+pub struct ServerError {
+    pub error: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ServerFeatures {
+    #[serde(default)]
+    pub attachments: bool,
+    #[serde(default)]
+    pub sharing: bool,
+    #[serde(default)]
+    pub e2ee: bool,
+}
+
+impl ServerFeatures {
+    fn enabled(&self) -> Vec<&'static str> {
+        [
+            ("attachments", self.attachments),
+            ("sharing", self.sharing),
+            ("e2ee", self.e2ee),
+        ]
+        .into_iter()
+        .filter_map(|(name, on)| on.then_some(name))
+        .collect()
+    }
+}
+
+pub fn server_cmd(
+    db_path: &Path,
+    command: ServerCommand,
+    config: &AppConfig,
+) -> Result<(), anyhow::Error> {
+    match command {
+        ServerCommand::Ping(args) => ping_cmd(args, config),
+        ServerCommand::Bootstrap(args) => bootstrap_cmd(db_path, args, config),
+    }
+}
+
+/// `--url`, or the profile's `server_url`, without a trailing slash
+pub fn server_base(url: Option<String>, config: &AppConfig) -> Result<String, anyhow::Error> {
+    let url = url.or_else(|| config.server_url.clone()).ok_or_else(|| {
+        CliError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "No server configured for profile '{}' (set server_url or pass --url)",
+                config.profile_name
+            ),
+        )
+    })?;
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+fn ping_cmd(args: ServerPingArgs, config: &AppConfig) -> Result<(), anyhow::Error> {
+    let base = server_base(args.url, config)?;
+    let base = base.as_str();
+    let token = profile::read_profile_token(&config.profile_name)?;
+
+    let client = server_client(config, base, PING_TIMEOUT)?;
+    let start = Instant::now();
+    let response = client.get(format!("{}/health/ping", base)).send();
+    let latency = start.elapsed();
+
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            // A pin mismatch is already explained by the error itself
+            if config.pinned_cert(base).is_none()
+                && base.starts_with("https://")
+                && certificate_is_invalid(base)
+            {
+                println!("Server:     {}", base);
+                println!("TLS:        invalid certificate");
+                anyhow::bail!("Server {} presented an invalid TLS certificate", base);
+            }
+            return Err(e).with_context(|| format!("Server {} is unreachable", base));
+        }
+    };
+    if !response.status().is_success() {
+        anyhow::bail!("Server {} answered ping with {}", base, response.status());
+    }
+
+    let header_version = response
+        .headers()
+        .get(VERSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let server_version = fetch_server_version(&client, base)?;
+
+    println!("Server:     {}", base);
+    println!("Latency:    {} ms", latency.as_millis());
+    println!(
+        "TLS:        {}",
+        if config.pinned_cert(base).is_some() {
+            "pinned certificate"
+        } else if base.starts_with("https://") {
+            "valid"
+        } else {
+            "not used"
+        }
+    );
+    let compatibility = match &server_version {
+        Some(server_version) => {
+            println!("Version:    {}", server_version.version);
+            let features = server_version.features.enabled();
+            println!(
+                "Features:   {}",
+                if features.is_empty() {
+                    "none".to_string()
+                } else {
+                    features.join(", ")
+                }
+            );
+            check_sync_protocol(server_version)
+        }
+        None => {
+            println!(
+                "Version:    {}",
+                header_version.as_deref().unwrap_or("unknown")
+            );
+            Err(server_too_old(base))
+        }
+    };
+    println!(
+        "Protocol:   {}",
+        if compatibility.is_ok() {
+            "compatible"
+        } else {
+            "incompatible"
+        }
+    );
+    println!("Credential: {}", credential_status(&client, base, token)?);
+
+    compatibility
+}
+
+/// Fetch `GET /version`; `None` when the server predates the endpoint
+pub fn fetch_server_version(
+    client: &Client,
+    base: &str,
+) -> Result<Option<ServerVersion>, anyhow::Error> {
+    let response = client
+        .get(format!("{}/version", base))
+        .send()
+        .context("Failed to fetch server version")?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    let version = response
+        .error_for_status()
+        .context("Failed to fetch server version")?
+        .json()
+        .context("Server sent an unreadable version response")?;
+    Ok(Some(version))
+}
+
+/// Refuse to sync unless the server accepts our sync protocol version,
+/// telling the user which side needs upgrading
+pub fn check_sync_protocol(server: &ServerVersion) -> Result<(), anyhow::Error> {
+    let ours = jot_core::SYNC_PROTOCOL_VERSION;
+    if server.sync_protocols.contains(&ours) {
+        return Ok(());
+    }
+
+    let message = if server.sync_protocols.iter().all(|&v| v < ours) {
+        format!(
+            "Server {} is too old for this jot (sync protocol {} needed, server speaks {}); upgrade the server",
+            server.version,
+            ours,
+            protocol_list(&server.sync_protocols)
+        )
+    } else {
+        format!(
+            "Server {} is too new for this jot (sync protocol {} unsupported, server speaks {}); upgrade jot",
+            server.version,
+            ours,
+            protocol_list(&server.sync_protocols)
+        )
+    };
+    Err(CliError::new(ErrorKind::IncompatibleServer, message).into())
+}
+
+pub fn server_too_old(base: &str) -> anyhow::Error {
+    CliError::new(
+        ErrorKind::IncompatibleServer,
+        format!(
+            "Server {} is too old for this jot (no /version endpoint); upgrade the server",
+            base
+        ),
+    )
+    .into()
+}
+
+fn protocol_list(versions: &[u32]) -> String {
+    if versions.is_empty() {
+        return "none".to_string();
+    }
+    versions
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The profile's stored credential, refreshed through `POST /auth/refresh`
+/// first when it's about to expire
+pub fn require_token(
+    client: &Client,
+    base: &str,
+    config: &AppConfig,
+) -> Result<String, anyhow::Error> {
+    let token = stored_token(config)?;
+    match token_expiry(&token) {
+        Some(exp) if exp - chrono::Utc::now().timestamp() < REFRESH_WITHIN_SECS => {
+            refresh_token(client, base, token, exp, config)
+        }
+        _ => Ok(token),
+    }
+}
+
+/// Trade `token`, which expires at `exp`, for one with a full lifetime and
+/// store it. A still valid token is kept when the server can't refresh it
+/// (e.g. it predates `/auth/refresh`); one the server rejects or that has
+/// already expired can only be replaced by logging in again.
+fn refresh_token(
+    client: &Client,
+    base: &str,
+    token: String,
+    exp: i64,
+    config: &AppConfig,
+) -> Result<String, anyhow::Error> {
+    #[derive(Deserialize)]
+    struct RefreshReply {
+        token: String,
+    }
+
+    let response = client
+        .post(format!("{}/auth/refresh", base))
+        .bearer_auth(&token)
+        .send()
+        .with_context(|| format!("Server {} is unreachable", base))?;
+    let status = response.status();
+    if !status.is_success() {
+        let rejected = status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN;
+        if rejected || exp <= chrono::Utc::now().timestamp() {
+            return Err(CliError::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "The stored credential for profile '{}' has expired; run `jot auth login` to log in to {} again",
+                    config.profile_name, base
+                ),
+            )
+            .into());
+        }
+        return Ok(token);
+    }
+    let reply: RefreshReply = response
+        .json()
+        .context("Server sent an unreadable refresh response")?;
+    profile::save_profile_token(&config.profile_name, &reply.token)?;
+    Ok(reply.token)
+}
+
+/// `exp` claim of a JWT access token, read without checking its signature;
+/// None for credentials that aren't JWTs
+fn token_expiry(token: &str) -> Option<i64> {
+    let payload = URL_SAFE_NO_PAD.decode(token.split('.').nth(1)?).ok()?;
+    serde_json::from_slice::<serde_json::Value>(&payload)
+        .ok()?
+        .get("exp")?
+        .as_i64()
+}
+
+/// The profile's stored credential, failing when there is none
+pub fn stored_token(config: &AppConfig) -> Result<String, anyhow::Error> {
+    profile::read_profile_token(&config.profile_name)?.ok_or_else(|| {
+        CliError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "No credential stored for profile '{}' (expected in {})",
+                config.profile_name,
+                profile::get_profile_token_path(&config.profile_name).display()
+            ),
+        )
+        .into()
+    })
+}
+
+/// First sync of a fresh device: download the server's snapshot and import it
+fn bootstrap_cmd(
+    db_path: &Path,
+    args: ServerBootstrapArgs,
+    config: &AppConfig,
+) -> Result<(), anyhow::Error> {
+    let base = server_base(args.url, config)?;
+    stored_token(config)?;
+    let db = LocalDb::open_for(db_path, config)?;
+    db.ensure_fresh()?;
+
+    let client = server_client(config, &base, SNAPSHOT_TIMEOUT)?;
+    match fetch_server_version(&client, &base)? {
+        Some(version) => check_sync_protocol(&version)?,
+        None => return Err(server_too_old(&base)),
+    }
+    let token = require_token(&client, &base, config)?;
+
+    let response = client
+        .get(format!("{}/sync/snapshot", base))
+        .bearer_auth(token)
+        .send()
+        .with_context(|| format!("Server {} is unreachable", base))?
+        .error_for_status()
+        .context("Failed to download snapshot")?;
+    let snapshot_at: i64 = response
+        .headers()
+        .get(SNAPSHOT_AT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .context("Server sent a snapshot without a timestamp")?;
+
+    // Decompress straight to disk instead of holding the export in memory
+    let mut snapshot = tempfile::NamedTempFile::new().context("Failed to create temp file")?;
+    std::io::copy(&mut GzDecoder::new(response), &mut snapshot)
+        .context("Failed to download snapshot")?;
+
+    let imported = db.import_snapshot(snapshot.path(), snapshot_at)?;
+    println!("Imported {} note(s) from {}", imported, base);
+    Ok(())
+}
+
+/// Client for the server at `base`: only the pinned certificate is accepted
+/// when the profile pinned one for that server, CA-verified ones otherwise
+pub fn server_client(
+    config: &AppConfig,
+    base: &str,
+    timeout: Duration,
+) -> Result<Client, anyhow::Error> {
+    match config.pinned_cert(base) {
+        Some(pin) => tls::pinned_client(pin, timeout),
+        None => client(false, timeout),
+    }
+}
+
+pub fn client(accept_invalid_certs: bool, timeout: Duration) -> Result<Client, anyhow::Error> {
+    Client::builder()
+        .timeout(timeout)
+        .danger_accept_invalid_certs(accept_invalid_certs)
+        .build()
+        .context("Failed to build HTTP client")
+}
+
+/// Whether the server is reachable once certificate checks are turned off,
+/// i.e. the original failure was the certificate itself
+fn certificate_is_invalid(base: &str) -> bool {
+    client(true, PING_TIMEOUT)
+        .and_then(|client| Ok(client.get(format!("{}/health/ping", base)).send()?))
+        .is_ok()
+}
+
+fn credential_status(
+    client: &Client,
+    base: &str,
+    token: Option<String>,
+) -> Result<&'static str, anyhow::Error> {
+    let Some(token) = token else {
+        return Ok("none stored");
+    };
+
+    let response = client
+        .get(format!("{}/health/auth", base))
+        .bearer_auth(token)
+        .send()
+        .context("Failed to check credential")?;
+
+    Ok(if response.status().is_success() {
+        "accepted"
+    } else {
+        "rejected"
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    fn server(sync_protocols: Vec<u32>) -> ServerVersion {
+        ServerVersion {
+            version: "1.2.3".to_string(),
+            sync_protocols,
+            features: ServerFeatures::default(),
+        }
+    }
+
+    #[test]
+    fn test_token_expiry() {
+        let payload = URL_SAFE_NO_PAD.encode(r#"{"sub":"user-1","exp":1700000000}"#);
+        assert_eq!(
+            token_expiry(&format!("header.{}.signature", payload)),
+            Some(1700000000)
+        );
+        assert_eq!(token_expiry("secret-token"), None);
+        assert_eq!(token_expiry("header.not-base64!.signature"), None);
+    }
+
+    #[test]
+    fn test_check_sync_protocol() {
+        let ours = jot_core::SYNC_PROTOCOL_VERSION;
+        assert!(check_sync_protocol(&server(vec![ours])).is_ok());
+
+        let too_old = check_sync_protocol(&server(vec![ours - 1])).unwrap_err();
+        assert!(too_old.to_string().contains("too old"));
+
+        let too_new = check_sync_protocol(&server(vec![ours + 1])).unwrap_err();
+        assert!(too_new.to_string().contains("too new"));
+    }
+}
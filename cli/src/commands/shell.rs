@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    app_config::AppConfig,
+    args::AliasShell,
+    error::{CliError, ErrorKind},
+};
+
+/// Built-in suggestions: alias name and the jot arguments it expands to
+const DEFAULT_SHELL_ALIASES: &[(&str, &str)] = &[
+    ("jt", "down"),
+    ("jl", "ls --date today"),
+    ("jla", "last"),
+    ("js", "show"),
+    ("ji", "inbox"),
+];
+
+pub fn shell_alias_cmd(shell: AliasShell, config: &AppConfig) -> Result<(), anyhow::Error> {
+    print!("{}", render_aliases(shell, &shell_aliases(config)?));
+    Ok(())
+}
+
+/// Built-in aliases merged with the profile's `[shell_aliases]` table, where
+/// an empty value drops a built-in one
+fn shell_aliases(config: &AppConfig) -> Result<BTreeMap<String, String>, CliError> {
+    let mut aliases: BTreeMap<String, String> = DEFAULT_SHELL_ALIASES
+        .iter()
+        .map(|(name, args)| (name.to_string(), args.to_string()))
+        .collect();
+
+    for (name, args) in &config.shell_aliases {
+        if args.trim().is_empty() {
+            aliases.remove(name);
+        } else {
+            check_alias_name(name)?;
+            aliases.insert(name.clone(), args.trim().to_string());
+        }
+    }
+    Ok(aliases)
+}
+
+/// Alias names are printed unquoted into shell code, so only letters,
+/// digits, `_` and `-` are allowed
+fn check_alias_name(name: &str) -> Result<(), CliError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(CliError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Invalid shell alias name '{}': use only letters, digits, '_' and '-'",
+                name
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn render_aliases(shell: AliasShell, aliases: &BTreeMap<String, String>) -> String {
+    let mut out = String::new();
+    for (name, args) in aliases {
+        let command = format!("jot {}", args);
+        let line = match shell {
+            AliasShell::Bash | AliasShell::Zsh => {
+                format!("alias {}='{}'\n", name, command.replace('\'', r"'\''"))
+            }
+            AliasShell::Fish => format!(
+                "alias {} '{}'\n",
+                name,
+                command.replace('\\', r"\\").replace('\'', r"\'")
+            ),
+        };
+        out.push_str(&line);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_quotes_per_shell() {
+        let aliases = BTreeMap::from([("jq".to_string(), "ls it's".to_string())]);
+
+        assert_eq!(
+            render_aliases(AliasShell::Zsh, &aliases),
+            "alias jq='jot ls it'\\''s'\n"
+        );
+        assert_eq!(
+            render_aliases(AliasShell::Fish, &aliases),
+            "alias jq 'jot ls it\\'s'\n"
+        );
+    }
+}
@@ -0,0 +1,430 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use jot_core::{Attachment, Note, SyncReport, SyncSide};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_config::AppConfig,
+    args::{OutputFormat, SyncArgs, SyncCommand, SyncDaemonArgs},
+    commands::server::{
+        check_sync_protocol, fetch_server_version, require_token, server_base, server_client,
+        server_too_old, stored_token, ServerError,
+    },
+    db::LocalDb,
+    error::{error_kind, CliError, ErrorKind},
+    formatters::short_id,
+    lock::{OperationLock, SyncDaemonLock},
+};
+
+/// Generous, since a first sync may carry every note of the account
+const SYNC_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Share of the server's storage quota past which a sync warns
+const QUOTA_WARNING_PERCENT: u64 = 90;
+
+/// How often `sync daemon` looks for local changes between syncs
+const DAEMON_POLL: Duration = Duration::from_secs(2);
+
+/// First retry delay of `sync daemon` after a failed sync, doubled on every
+/// further failure up to [`BACKOFF_MAX`]
+const BACKOFF_START: Duration = Duration::from_secs(10);
+
+const BACKOFF_MAX: Duration = Duration::from_secs(30 * 60);
+
+/// A note as the server's `/sync` endpoint sends and expects it
+#[derive(Debug, Serialize, Deserialize)]
+struct WireNote {
+    id: String,
+    content: String,
+    tags: Vec<String>,
+    date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date_tz: Option<String>,
+    created_at: i64,
+    updated_at: i64,
+    deleted_at: Option<i64>,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    meta: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attachments: Vec<Attachment>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    archived_at: Option<i64>,
+}
+
+impl From<Note> for WireNote {
+    fn from(note: Note) -> Self {
+        WireNote {
+            id: note.id,
+            content: note.content,
+            tags: note.tags,
+            date: note.subject_date,
+            date_tz: note.subject_tz,
+            created_at: note.created_at,
+            updated_at: note.updated_at,
+            deleted_at: note.deleted_at,
+            pinned: note.pinned,
+            status: note.status,
+            meta: note.meta,
+            attachments: note.attachments,
+            archived_at: note.archived_at,
+        }
+    }
+}
+
+impl From<WireNote> for Note {
+    fn from(wire: WireNote) -> Self {
+        Note {
+            id: wire.id,
+            content: wire.content,
+            tags: wire.tags,
+            subject_date: wire.date,
+            subject_tz: wire.date_tz,
+            created_at: wire.created_at,
+            updated_at: wire.updated_at,
+            deleted_at: wire.deleted_at,
+            pinned: wire.pinned,
+            status: wire.status,
+            meta: wire.meta,
+            attachments: wire.attachments,
+            archived_at: wire.archived_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SyncBody {
+    notes: Vec<WireNote>,
+    last_sync: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SyncReply {
+    notes: Vec<WireNote>,
+    #[serde(default)]
+    conflicts: Vec<WireConflict>,
+    /// Sent only by servers with a storage quota
+    #[serde(default)]
+    usage: Option<WireUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WireUsage {
+    used_bytes: u64,
+    quota_bytes: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WireConflict {
+    note_id: String,
+    winner: SyncSide,
+    losing_version: WireNote,
+}
+
+pub fn sync_cmd(db_path: &Path, mut args: SyncArgs, config: &AppConfig) -> anyhow::Result<()> {
+    if let Some(SyncCommand::Daemon(daemon)) = args.command.take() {
+        return sync_daemon(db_path, args.url, daemon, config);
+    }
+
+    let started = Instant::now();
+    let json = args.output == OutputFormat::Json;
+
+    match sync(db_path, args, config) {
+        Ok(mut report) => {
+            report.duration_ms = started.elapsed().as_millis() as u64;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            }
+            Ok(())
+        }
+        Err(e) => {
+            if json {
+                let report = SyncReport {
+                    duration_ms: started.elapsed().as_millis() as u64,
+                    errors: vec![format!("{:#}", e)],
+                    ..SyncReport::default()
+                };
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Push local changes, apply the server's and move `last_sync` forward;
+/// prints the human-readable summary unless JSON was asked for
+fn sync(db_path: &Path, args: SyncArgs, config: &AppConfig) -> anyhow::Result<SyncReport> {
+    if config.ephemeral {
+        return Err(CliError::new(
+            ErrorKind::InvalidInput,
+            "jot sync needs the profile database and doesn't work with --ephemeral",
+        )
+        .into());
+    }
+    let pretty = args.output != OutputFormat::Json;
+    let base = server_base(args.url, config)?;
+    stored_token(config)?;
+
+    let client = server_client(config, &base, SYNC_TIMEOUT)?;
+    match fetch_server_version(&client, &base)
+        .with_context(|| format!("Server {} is unreachable", base))?
+    {
+        Some(version) => check_sync_protocol(&version)?,
+        None => return Err(server_too_old(&base)),
+    }
+    let token = require_token(&client, &base, config)?;
+
+    let _lock = OperationLock::acquire(db_path)?;
+    let db = LocalDb::open_for(db_path, config)?;
+
+    // Taken before collecting changes, so anything written during the sync
+    // is newer and goes out next time
+    let synced_at = chrono::Utc::now().timestamp_millis();
+    let last_sync = db.get_last_sync()?;
+    let pending = db.pending_changes()?;
+    let pushed: Vec<String> = pending.iter().map(|note| note.id.clone()).collect();
+
+    let response = client
+        .post(format!("{}/sync", base))
+        .bearer_auth(token)
+        .json(&SyncBody {
+            notes: pending.into_iter().map(WireNote::from).collect(),
+            last_sync,
+        })
+        .send()
+        .with_context(|| format!("Server {} is unreachable", base))?;
+
+    let status = response.status();
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        return Err(CliError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Server {} rejected the stored credential for profile '{}'",
+                base, config.profile_name
+            ),
+        )
+        .into());
+    }
+    if !status.is_success() {
+        let reason = response
+            .json::<ServerError>()
+            .map(|e| e.error)
+            .unwrap_or_else(|_| status.to_string());
+        anyhow::bail!("Sync with {} failed: {}", base, reason);
+    }
+    let reply: SyncReply = response
+        .json()
+        .context("Server sent an unreadable sync response")?;
+
+    let pulled = reply.notes.len();
+    db.apply_sync(
+        reply.notes.into_iter().map(Note::from).collect(),
+        &pushed,
+        synced_at.max(last_sync),
+    )?;
+
+    let warnings: Vec<String> = reply
+        .usage
+        .as_ref()
+        .and_then(quota_warning)
+        .into_iter()
+        .collect();
+    if pretty {
+        println!(
+            "Synced with {}: pushed {}, pulled {} note(s)",
+            base,
+            pushed.len(),
+            pulled
+        );
+        for conflict in &reply.conflicts {
+            print_conflict(&db, conflict)?;
+        }
+        for warning in &warnings {
+            eprintln!("Warning: {}", warning);
+        }
+    }
+
+    Ok(SyncReport {
+        pushed: pushed.len(),
+        pulled,
+        conflicts: reply.conflicts.len(),
+        warnings,
+        ..SyncReport::default()
+    })
+}
+
+/// Sync now, then again whenever the local `updated_at` watermark moves or
+/// the interval passes, backing off while the server can't be reached
+fn sync_daemon(
+    db_path: &Path,
+    url: Option<String>,
+    args: SyncDaemonArgs,
+    config: &AppConfig,
+) -> anyhow::Result<()> {
+    if args.detach {
+        return detach_daemon(db_path);
+    }
+
+    let _lock = SyncDaemonLock::acquire(db_path)?;
+    stored_token(config)?;
+    let interval = args.interval.0;
+    println!(
+        "Sync daemon for profile '{}' started (every {}, pid {})",
+        config.profile_name,
+        args.interval,
+        std::process::id()
+    );
+
+    let mut watermark = None;
+    let mut next_sync = Instant::now();
+    let mut failures = 0;
+    loop {
+        let latest = local_watermark(db_path);
+        if Instant::now() >= next_sync || (failures == 0 && latest != watermark) {
+            let args = SyncArgs {
+                url: url.clone(),
+                output: OutputFormat::Pretty,
+                command: None,
+            };
+            match sync(db_path, args, config) {
+                Ok(_) => {
+                    failures = 0;
+                    // Read before syncing, so changes made during the sync go
+                    // out next; pulled notes cost one extra, empty round trip
+                    watermark = latest;
+                    next_sync = Instant::now() + interval;
+                }
+                Err(e) if is_retryable(&e) => {
+                    failures += 1;
+                    let delay = backoff(failures);
+                    eprintln!(
+                        "{} Sync failed: {:#}; retrying in {}s",
+                        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                        e,
+                        delay.as_secs()
+                    );
+                    next_sync = Instant::now() + delay;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let until_sync = next_sync.saturating_duration_since(Instant::now());
+        std::thread::sleep(until_sync.min(DAEMON_POLL));
+    }
+}
+
+/// Start `jot sync daemon` again as a background process, its output going
+/// to `<db>.sync-daemon.log`
+fn detach_daemon(db_path: &Path) -> anyhow::Result<()> {
+    // Fail here rather than in the background when a daemon already runs
+    drop(SyncDaemonLock::acquire(db_path)?);
+
+    let log_path = daemon_log_path(db_path);
+    let log = File::options()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .with_context(|| format!("Failed to open {:?}", log_path))?;
+    let exe = std::env::current_exe().context("Failed to locate the jot executable")?;
+    let child = std::process::Command::new(exe)
+        .args(std::env::args_os().skip(1).filter(|arg| arg != "--detach"))
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .spawn()
+        .context("Failed to start the sync daemon")?;
+
+    println!(
+        "Sync daemon started in the background (pid {}); logging to {}",
+        child.id(),
+        log_path.display()
+    );
+    Ok(())
+}
+
+/// Log of a detached daemon, e.g. `notes.db.sync-daemon.log`
+fn daemon_log_path(db_path: &Path) -> PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".sync-daemon.log");
+    db_path.with_file_name(name)
+}
+
+/// Newest `updated_at` in the profile database; an unreadable database
+/// counts as unchanged until the next scheduled sync reports the error
+fn local_watermark(db_path: &Path) -> Option<i64> {
+    LocalDb::summary(db_path)
+        .ok()
+        .flatten()
+        .and_then(|summary| summary.last_modified)
+}
+
+/// Whether the daemon should keep trying after `e`: unreachable servers,
+/// server errors and a busy database pass, while a missing credential or an
+/// incompatible server need the user
+fn is_retryable(e: &anyhow::Error) -> bool {
+    !matches!(
+        error_kind(e),
+        ErrorKind::InvalidInput | ErrorKind::IncompatibleServer | ErrorKind::SchemaTooNew
+    )
+}
+
+/// Delay before retrying after `failures` failed syncs in a row
+fn backoff(failures: u32) -> Duration {
+    BACKOFF_START
+        .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(BACKOFF_MAX)
+}
+
+/// Warning for an account past [`QUOTA_WARNING_PERCENT`] of its quota
+fn quota_warning(usage: &WireUsage) -> Option<String> {
+    let quota = usage.quota_bytes.filter(|q| *q > 0)?;
+    let percent = usage.used_bytes.saturating_mul(100) / quota;
+    (percent >= QUOTA_WARNING_PERCENT).then(|| {
+        format!(
+            "this account uses {} of its {} storage quota on the server ({}%)",
+            format_size(usage.used_bytes),
+            format_size(quota),
+            percent
+        )
+    })
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MiB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.1} KiB", b as f64 / 1024.0),
+        b => format!("{} B", b),
+    }
+}
+
+/// Tell the user which edit last-write-wins discarded, so it can be redone
+fn print_conflict(db: &LocalDb, conflict: &WireConflict) -> anyhow::Result<()> {
+    let kept = match conflict.winner {
+        SyncSide::Client => "this device's",
+        SyncSide::Server => "the server's",
+    };
+    println!(
+        "Conflict on note {}: kept {} newer version; the discarded edit was:",
+        short_id(&conflict.note_id),
+        kept
+    );
+    for line in db
+        .decrypt_content(&conflict.losing_version.content)?
+        .lines()
+    {
+        println!("  | {}", line);
+    }
+    Ok(())
+}
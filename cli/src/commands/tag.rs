@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use jot_core::SearchQuery;
+
+use crate::{
+    app_config::AppConfig,
+    args::{TagCommand, TagSuggestArgs},
+    db::LocalDb,
+    utils::tag_suggest::suggest_tags,
+};
+
+pub fn tag_cmd(db_path: &Path, command: TagCommand, config: &AppConfig) -> anyhow::Result<()> {
+    let db = LocalDb::open_for(db_path, config)?;
+
+    match command {
+        TagCommand::Suggest(args) => suggest_cmd(&db, args),
+    }
+}
+
+fn suggest_cmd(db: &LocalDb, args: TagSuggestArgs) -> anyhow::Result<()> {
+    let mut history = db.search_notes(&SearchQuery::default())?;
+
+    // A single word naming a note suggests tags for that note
+    let note = match args.input.as_slice() {
+        [word] if !word.contains(char::is_whitespace) => {
+            db.get_note_by_id(&db.resolve_id(word)?)?
+        }
+        _ => None,
+    };
+    let (content, mut current_tags) = match note {
+        Some(note) => {
+            history.retain(|n| n.id != note.id);
+            (note.content, note.tags)
+        }
+        None => (args.input.join(" "), vec![]),
+    };
+    for tag in args.tag {
+        if !current_tags.contains(&tag) {
+            current_tags.push(tag);
+        }
+    }
+
+    let suggestions = suggest_tags(&history, &content, &current_tags, args.limit);
+    if suggestions.is_empty() {
+        println!("No tag suggestions.");
+    }
+    for suggestion in suggestions {
+        println!("{}\t{}", suggestion.tag, suggestion.reason);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use std::{collections::BTreeMap, path::Path};
+
+use chrono::Days;
+
+use crate::{
+    app_config::AppConfig,
+    args::{TrackCommand, TrackReportArgs},
+    db::LocalDb,
+    error::{CliError, ErrorKind},
+    formatters::short_id,
+    utils::{
+        date_target::{parse_week_start, start_of_week},
+        text::preview_line,
+        time_display::TimeDisplay,
+    },
+};
+
+/// Width of the note previews in the report
+const LINE_WIDTH: usize = 50;
+
+/// Shown for time on notes without tags
+const UNTAGGED: &str = "(untagged)";
+
+pub fn track_cmd(db_path: &Path, command: TrackCommand, config: &AppConfig) -> anyhow::Result<()> {
+    let db = LocalDb::open_for(db_path, config)?;
+
+    match command {
+        TrackCommand::Start { id } => {
+            let note = db
+                .get_note_by_id(&db.resolve_id(&id)?)?
+                .ok_or_else(|| db.note_not_found(&id))?;
+            if note.deleted_at.is_some() {
+                return Err(CliError::new(
+                    ErrorKind::InvalidInput,
+                    format!("Note {} is deleted", note.id),
+                )
+                .into());
+            }
+
+            if let Some(stopped) = db.start_tracking(&note.id)? {
+                print_stopped(&stopped);
+            }
+            println!("Tracking note {}", note.id);
+        }
+        TrackCommand::Stop => match db.stop_tracking()? {
+            Some(stopped) => print_stopped(&stopped),
+            None => println!("Nothing is being tracked"),
+        },
+        TrackCommand::Report(args) => report(&db, args, config)?,
+    }
+    Ok(())
+}
+
+fn print_stopped(entry: &jot_core::TimeEntry) {
+    let stopped_at = entry.stopped_at.unwrap_or(entry.started_at);
+    println!(
+        "Stopped note {} after {}",
+        short_id(&entry.note_id),
+        format_duration(stopped_at - entry.started_at)
+    );
+}
+
+/// Hours per note and per tag for today or the current week
+fn report(db: &LocalDb, args: TrackReportArgs, config: &AppConfig) -> anyhow::Result<()> {
+    let time = TimeDisplay::from_config(config)?;
+    let today = time.today();
+    let (first_day, days) = match args.week {
+        true => (
+            start_of_week(today, parse_week_start(config.week_start.as_deref())?),
+            7,
+        ),
+        false => (today, 1),
+    };
+    let last_day = first_day
+        .checked_add_days(Days::new(days - 1))
+        .unwrap_or(first_day);
+    let from = time.start_of_day(first_day);
+    let to = time.start_of_day(last_day.succ_opt().unwrap_or(last_day));
+    let now = chrono::Utc::now().timestamp_millis();
+
+    let mut per_note: BTreeMap<String, i64> = BTreeMap::new();
+    for entry in db.time_entries_between(from, to)? {
+        *per_note.entry(entry.note_id.clone()).or_default() += entry.overlap_ms(from, to, now);
+    }
+    let total: i64 = per_note.values().sum();
+
+    match args.week {
+        true => println!("Week of {}: {}", first_day, format_duration(total)),
+        false => println!("{}: {}", first_day, format_duration(total)),
+    }
+    if per_note.is_empty() {
+        println!("  (nothing tracked)");
+        return Ok(());
+    }
+
+    let mut notes: Vec<(i64, String, String)> = Vec::new();
+    let mut per_tag: BTreeMap<String, i64> = BTreeMap::new();
+    for (id, ms) in per_note {
+        let note = db.get_note_by_id(&id)?;
+        let preview = note
+            .as_ref()
+            .map(|n| preview_line(&n.content, LINE_WIDTH))
+            .unwrap_or_default();
+        let tags = note.map(|n| n.tags).unwrap_or_default();
+        if tags.is_empty() {
+            *per_tag.entry(UNTAGGED.to_string()).or_default() += ms;
+        }
+        for tag in tags {
+            *per_tag.entry(format!("#{}", tag)).or_default() += ms;
+        }
+        notes.push((ms, id, preview));
+    }
+    notes.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    let mut tags: Vec<(String, i64)> = per_tag.into_iter().collect();
+    tags.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    println!();
+    println!("By note");
+    for (ms, id, preview) in &notes {
+        println!(
+            "  {}  {:>8}  {}",
+            short_id(id),
+            format_duration(*ms),
+            preview
+        );
+    }
+    println!();
+    // A note with several tags counts towards each of them
+    println!("By tag");
+    for (tag, ms) in &tags {
+        println!("  {:>8}  {}", format_duration(*ms), tag);
+    }
+    Ok(())
+}
+
+/// `2h 05m`, rounded down to the minute
+fn format_duration(ms: i64) -> String {
+    let minutes = ms.max(0) / 60_000;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use jot_core::Note;
+
+use crate::{
+    app_config::AppConfig,
+    args::{NoteSearchArgs, OutputFormat, TrashCommand},
+    db::LocalDb,
+    error::{CliError, ErrorKind},
+    formatters::NoteSearchFormatter,
+    lock::OperationLock,
+    utils::time_display::TimeDisplay,
+};
+
+pub fn trash_cmd(
+    db_path: &Path,
+    command: TrashCommand,
+    config: &AppConfig,
+) -> Result<(), anyhow::Error> {
+    let db = LocalDb::open_for(db_path, config)?;
+
+    match command {
+        TrashCommand::List { output } => {
+            let notes = db.trashed_notes()?;
+            if notes.is_empty() && matches!(output, OutputFormat::Pretty | OutputFormat::Plain) {
+                println!("Trash is empty.");
+                return Ok(());
+            }
+
+            let mut formatter = NoteSearchFormatter::new(
+                NoteSearchArgs {
+                    output,
+                    ..Default::default()
+                },
+                TimeDisplay::from_config(config)?,
+            )
+            .with_plain_structure(config.plain_structure);
+            formatter
+                .print_notes(&notes)
+                .map_err(|e| anyhow::anyhow!("Error while formatting notes: {}", e))?;
+        }
+        TrashCommand::Restore { ids } => {
+            let trashed = db.trashed_notes()?;
+            for id in &ids {
+                let note = find_trashed(&trashed, id)?;
+                if db.restore_note(&note.id)? {
+                    println!("Restored note {}", note.id);
+                } else {
+                    println!("Note {} is already restored", note.id);
+                }
+            }
+        }
+        TrashCommand::Empty(args) => {
+            let now = chrono::Local::now();
+            let cutoff = match args.older_than {
+                Some(period) => period
+                    .cutoff(now)
+                    .ok_or_else(|| anyhow::anyhow!("Retention period '{}' is too long", period))?,
+                None => now,
+            }
+            .timestamp_millis();
+
+            let due = db
+                .trashed_notes()?
+                .iter()
+                .filter(|note| note.deleted_at.is_some_and(|at| at < cutoff))
+                .count();
+            if due == 0 {
+                println!("No deleted notes to remove.");
+                return Ok(());
+            }
+
+            if !args.yes {
+                print!("Permanently remove {} deleted note(s)? [y/N]: ", due);
+                std::io::Write::flush(&mut std::io::stdout())?;
+
+                let mut input = String::new();
+                std::io::stdin().read_line(&mut input)?;
+
+                if !input.trim().eq_ignore_ascii_case("y") {
+                    println!("Aborted. No notes were removed.");
+                    return Ok(());
+                }
+            }
+
+            let _lock = OperationLock::acquire(db_path)?;
+            let removed = db.empty_trash(cutoff)?;
+            println!("Removed {} deleted note(s).", removed);
+            if removed < due {
+                println!(
+                    "Kept {} deleted after the last sync; run `jot sync` first to remove them too.",
+                    due - removed
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The deleted note with this ID or unique ID prefix
+fn find_trashed<'a>(trashed: &'a [Note], id: &str) -> anyhow::Result<&'a Note> {
+    let prefix = id
+        .trim()
+        .trim_matches(|c| matches!(c, '"' | '\'' | '`'))
+        .to_ascii_uppercase();
+    if let Some(note) = trashed.iter().find(|note| note.id == prefix) {
+        return Ok(note);
+    }
+
+    let matches: Vec<&Note> = trashed
+        .iter()
+        .filter(|note| !prefix.is_empty() && note.id.starts_with(&prefix))
+        .collect();
+    match matches.as_slice() {
+        [note] => Ok(note),
+        [] => Err(CliError::new(
+            ErrorKind::NotFound,
+            format!("No deleted note with ID '{}' (see `jot trash list`)", id),
+        )
+        .into()),
+        _ => Err(CliError::new(
+            ErrorKind::AmbiguousId,
+            format!(
+                "Ambiguous ID '{}': matches {} deleted notes. Please provide more characters.",
+                id,
+                matches.len()
+            ),
+        )
+        .into()),
+    }
+}
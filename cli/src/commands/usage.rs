@@ -0,0 +1,185 @@
+use std::{collections::HashMap, path::Path};
+
+use chrono::{Days, NaiveDate};
+use jot_core::Note;
+
+use crate::{
+    app_config::AppConfig,
+    args::UsageArgs,
+    db::LocalDb,
+    profile, usage,
+    utils::{
+        date_target::{parse_week_start, start_of_week},
+        time_display::TimeDisplay,
+    },
+};
+
+/// Commands listed under "Most used commands"
+const TOP_COMMANDS: usize = 10;
+
+/// Tags listed under "Top tags"
+const TOP_TAGS: usize = 5;
+
+/// Widest bar in the notes-per-week chart
+const BAR_WIDTH: usize = 30;
+
+pub fn usage_cmd(db_path: &Path, args: UsageArgs, config: &AppConfig) -> anyhow::Result<()> {
+    let db = LocalDb::open_for(db_path, config)?;
+    // Deleted notes were still created, so they count towards the weeks
+    let notes = db.get_notes_since(i64::MIN)?;
+    let time = TimeDisplay::from_config(config)?;
+
+    let weeks = args.weeks.max(1);
+    let this_week = start_of_week(
+        time.today(),
+        parse_week_start(config.week_start.as_deref())?,
+    );
+    let first_week = this_week
+        .checked_sub_days(Days::new(7 * u64::from(weeks - 1)))
+        .unwrap_or(this_week);
+    let before = first_week
+        .checked_sub_days(Days::new(7 * u64::from(weeks)))
+        .unwrap_or(first_week);
+
+    print_commands(config, &time)?;
+    println!();
+    print_weeks(&notes, config, &time, first_week, weeks);
+    println!();
+    print_tags(&notes, config, &time, before, first_week, weeks);
+    Ok(())
+}
+
+fn print_commands(config: &AppConfig, time: &TimeDisplay) -> anyhow::Result<()> {
+    let usage::CommandCounts { counts, since } =
+        usage::command_counts(&profile::get_profile_usage_log_path(&config.profile_name))?;
+    match since {
+        Some(since) => println!(
+            "Most used commands (since {})",
+            time.format_timestamp(since)
+        ),
+        None => println!("Most used commands"),
+    }
+
+    if counts.is_empty() {
+        match config.usage_history {
+            true => println!("  (nothing recorded yet)"),
+            false => println!(
+                "  (history is off; set `usage_history = true` in the profile to record command names locally)"
+            ),
+        }
+    }
+    let width = counts
+        .iter()
+        .take(TOP_COMMANDS)
+        .map(|(command, _)| command.len())
+        .max()
+        .unwrap_or_default();
+    for (command, count) in counts.iter().take(TOP_COMMANDS) {
+        match config.plain_structure {
+            true => println!("{}: used {} time(s)", command, count),
+            false => println!("  {:<width$}  {}", command, count, width = width),
+        }
+    }
+    Ok(())
+}
+
+/// Notes created in each of the `weeks` weeks starting at `first_week`
+fn print_weeks(
+    notes: &[Note],
+    config: &AppConfig,
+    time: &TimeDisplay,
+    first_week: NaiveDate,
+    weeks: u32,
+) {
+    let mut counts = vec![0usize; weeks as usize];
+    for date in notes.iter().filter_map(|n| time.date_of(n.created_at)) {
+        let days = (date - first_week).num_days();
+        if days >= 0 {
+            if let Some(count) = counts.get_mut((days / 7) as usize) {
+                *count += 1;
+            }
+        }
+    }
+
+    println!("Notes created per week");
+    let max = counts.iter().copied().max().unwrap_or_default().max(1);
+    let count_width = max.to_string().len();
+    for (i, count) in counts.iter().enumerate() {
+        let week = first_week
+            .checked_add_days(Days::new(7 * i as u64))
+            .unwrap_or(first_week);
+        match config.plain_structure {
+            true => println!("Week of {}: {} note(s)", week, count),
+            false => println!(
+                "  {}  {:>width$}  {}",
+                week,
+                count,
+                "#".repeat(count * BAR_WIDTH / max),
+                width = count_width
+            ),
+        }
+    }
+}
+
+/// Most used tags on live notes created since `first_week`, with the change
+/// from the same number of weeks before (starting at `before`)
+fn print_tags(
+    notes: &[Note],
+    config: &AppConfig,
+    time: &TimeDisplay,
+    before: NaiveDate,
+    first_week: NaiveDate,
+    weeks: u32,
+) {
+    let mut recent: HashMap<&str, i64> = HashMap::new();
+    let mut earlier: HashMap<&str, i64> = HashMap::new();
+    for note in notes.iter().filter(|n| n.deleted_at.is_none()) {
+        let Some(date) = time.date_of(note.created_at) else {
+            continue;
+        };
+        let counts = match date {
+            d if d >= first_week => &mut recent,
+            d if d >= before => &mut earlier,
+            _ => continue,
+        };
+        for tag in &note.tags {
+            *counts.entry(tag.as_str()).or_default() += 1;
+        }
+    }
+
+    println!(
+        "Top tags (last {} week(s), change from the {} before)",
+        weeks, weeks
+    );
+    let mut top: Vec<(&str, i64)> = recent.into_iter().collect();
+    top.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    top.truncate(TOP_TAGS);
+    if top.is_empty() {
+        println!("  (none)");
+    }
+
+    let width = top
+        .iter()
+        .map(|(tag, _)| tag.chars().count() + 1)
+        .max()
+        .unwrap_or_default();
+    for (tag, count) in top {
+        let change = count - earlier.get(tag).copied().unwrap_or_default();
+        if config.plain_structure {
+            let change = match change {
+                0 => "same as before".to_string(),
+                c if c > 0 => format!("{} more than before", c),
+                c => format!("{} fewer than before", -c),
+            };
+            println!("{}: {} note(s), {}", tag, count, change);
+            continue;
+        }
+        println!(
+            "  {:<width$}  {}  ({:+})",
+            format!("#{}", tag),
+            count,
+            change,
+            width = width
+        );
+    }
+}
@@ -1,11 +1,56 @@
 use anyhow::{Context, Result};
-use jot_core::{Note, SearchQuery};
-use rusqlite::Connection;
-use std::path::Path;
+use chrono::NaiveDate;
+use jot_core::{
+    schema::{self, Migration},
+    validation::as_validation_error,
+    Attachment, Note, NoteAlias, NoteBuilder, NoteLimits, NoteObserver, NoteValidationError,
+    ObserverRegistry, Recurrence, SearchCache, SearchQuery, TimeEntry,
+};
+use rusqlite::{Connection, OpenFlags};
+
+use crate::{
+    app_config::AppConfig,
+    crypto::{self, NoteCipher},
+    error::{note_not_found, CliError, ErrorKind},
+    hooks::NoteHooks,
+    journal::JournalMirror,
+    lock::OperationLock,
+    utils::{text::edit_distance, time_display::TimeDisplay},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    sync::Arc,
+};
+
+/// Schema version of a database and the migrations it still needs
+pub struct MigrationStatus {
+    pub version: i32,
+    pub pending: Vec<Migration>,
+}
+
+/// Note count and latest change of a database (for `jot profile list`)
+pub struct DbSummary {
+    /// Notes that aren't deleted
+    pub notes: i64,
+    /// Latest `updated_at` of any note, deletions included (milliseconds)
+    pub last_modified: Option<i64>,
+}
+
+/// Most IDs listed after "Did you mean" in a not-found error
+const MAX_ID_SUGGESTIONS: usize = 3;
 
 /// Local database for offline note storage
 pub struct LocalDb {
     conn: Connection,
+    observers: ObserverRegistry,
+    limits: NoteLimits,
+    /// Repeated searches (the dashboard's sections) reuse results until a write
+    search_cache: SearchCache,
+    /// Set when the profile encrypts note content and tags
+    cipher: Option<NoteCipher>,
+    /// Timezone whose UTC offset is recorded with a note's date
+    time: TimeDisplay,
 }
 
 impl LocalDb {
@@ -17,63 +62,278 @@ impl LocalDb {
                 .with_context(|| format!("Failed to create database directory at {:?}", parent))?;
         }
 
-        let conn = jot_core::open_db(path)
-            .with_context(|| format!("Failed to open local database at {:?}", path))?;
+        // Keep other long operations out while the schema is rewritten
+        let _lock = match Self::needs_migration(path)? {
+            true => Some(OperationLock::acquire(path)?),
+            false => None,
+        };
+        let conn = jot_core::open_db(path).map_err(|e| open_error(e, path))?;
+
+        Ok(Self {
+            conn,
+            observers: ObserverRegistry::new(),
+            limits: NoteLimits::default(),
+            search_cache: SearchCache::default(),
+            cipher: None,
+            time: TimeDisplay::default(),
+        })
+    }
+
+    /// Open the database a command should use: the profile database, or an
+    /// in-memory one with `--ephemeral`, with the profile's note limits,
+    /// timezone, encryption, journal mirror and hooks applied
+    pub fn open_for(path: &Path, config: &AppConfig) -> Result<Self> {
+        let time = TimeDisplay::new(config.timezone.as_deref(), None)?;
+        if config.ephemeral {
+            return Ok(Self::open_in_memory()?
+                .with_limits(config.note_limits)
+                .with_time(time));
+        }
+        let mut db = Self::open(path)?
+            .with_limits(config.note_limits)
+            .with_time(time);
+        if config.encrypt {
+            let set_up = NoteCipher::is_set_up(&db.conn)?;
+            let passphrase = crypto::read_passphrase(!set_up)?;
+            db.cipher = Some(NoteCipher::unlock(&db.conn, passphrase)?);
+        }
+        if let Some(journal) = JournalMirror::from_config(config)? {
+            db.register_observer(Arc::new(journal));
+        }
+        if let Some(hooks) = NoteHooks::from_config(&config.hooks) {
+            db.register_observer(Arc::new(hooks));
+        }
+        Ok(db)
+    }
+
+    /// Use custom content limits for notes created or updated through this handle
+    pub fn with_limits(mut self, limits: NoteLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Record note dates with the UTC offset of `time`'s timezone
+    pub fn with_time(mut self, time: TimeDisplay) -> Self {
+        self.time = time;
+        self
+    }
+
+    /// Register an observer for note create/update/delete events
+    pub fn register_observer(&mut self, observer: Arc<dyn NoteObserver>) {
+        self.observers.register(observer);
+    }
+
+    /// Open a throwaway in-memory database
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = jot_core::open_db_in_memory().context("Failed to open in-memory database")?;
+
+        Ok(Self {
+            conn,
+            observers: ObserverRegistry::new(),
+            limits: NoteLimits::default(),
+            search_cache: SearchCache::default(),
+            cipher: None,
+            time: TimeDisplay::default(),
+        })
+    }
+
+    /// Whether an existing database at `path` is on an older schema
+    fn needs_migration(path: &Path) -> Result<bool> {
+        if !path.exists() {
+            return Ok(false);
+        }
+        let conn = jot_core::open_db_unmigrated(path).map_err(|e| open_error(e, path))?;
+        let version = schema::get_schema_version(&conn).context("Failed to read schema version")?;
+        Ok(version < schema::LATEST_VERSION)
+    }
+
+    /// Inspect schema version and pending migrations without applying them
+    pub fn migration_status(path: &Path) -> Result<MigrationStatus> {
+        if !path.exists() {
+            return Ok(MigrationStatus {
+                version: 0,
+                pending: schema::MIGRATIONS.to_vec(),
+            });
+        }
+
+        let conn = jot_core::open_db_unmigrated(path).map_err(|e| open_error(e, path))?;
+        let version = schema::get_schema_version(&conn).context("Failed to read schema version")?;
+        let pending = schema::migrate_dry_run(&conn).map_err(|e| open_error(e, path))?;
+
+        Ok(MigrationStatus { version, pending })
+    }
+
+    /// Summarize the database at `path` (`None` if it doesn't exist yet)
+    ///
+    /// The file is opened read-only and not migrated, so listing profiles
+    /// never changes another profile's database.
+    pub fn summary(path: &Path) -> Result<Option<DbSummary>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| open_error(e, path))?;
+        conn.busy_timeout(jot_core::db::BUSY_TIMEOUT)?;
+        let (notes, last_modified) = conn
+            .query_row(
+                "SELECT COUNT(*) FILTER (WHERE deleted_at IS NULL), MAX(updated_at) FROM notes",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .with_context(|| format!("Failed to read notes from {:?}", path))?;
 
-        Ok(Self { conn })
+        Ok(Some(DbSummary {
+            notes,
+            last_modified,
+        }))
     }
 
-    /// Create a new note
+    /// Create a new note, optionally with a workflow status (see `jot board`)
+    /// and where it was captured (profile `capture_context`)
     pub fn create_note(
         &self,
         content: String,
         tags: Vec<String>,
         date: Option<String>,
+        status: Option<String>,
+        meta: BTreeMap<String, String>,
     ) -> Result<Note> {
-        jot_core::create_note(&self.conn, &content, tags, date).context("Failed to create note")
+        let (stored, stored_tags) = self.seal(&content, &tags)?;
+        let mut note = jot_core::create_note_with_limits(
+            &self.conn,
+            &stored,
+            stored_tags,
+            date,
+            &self.time.current_offset(),
+            &self.stored_limits(),
+        )
+        .map_err(|e| write_error(e, "Failed to create note"))?;
+        note.content = content;
+        note.tags = tags;
+        if let Some(status) = status {
+            jot_core::set_status(&self.conn, &note.id, Some(&status))
+                .context("Failed to set status")?;
+            note.status = Some(status);
+        }
+        if !meta.is_empty() {
+            jot_core::set_meta(&self.conn, &note.id, &meta).context("Failed to set metadata")?;
+            note.meta = meta;
+        }
+        self.queue_for_sync(&note.id)?;
+        self.observers.notify_create(&note);
+        Ok(note)
     }
 
     /// Search for notes
+    ///
+    /// On an encrypted profile the text and tag filters run here on the
+    /// decrypted notes, and full-text queries aren't available.
     pub fn search_notes(&self, query: &SearchQuery) -> Result<Vec<Note>> {
-        jot_core::search_notes(&self.conn, query).context("Failed to search notes")
+        let Some(cipher) = &self.cipher else {
+            return self.search_stored(query);
+        };
+        if query.match_query.is_some() {
+            return Err(CliError::new(
+                ErrorKind::InvalidInput,
+                "Search operators aren't available on an encrypted profile; search with plain words instead",
+            )
+            .into());
+        }
+
+        let stored = SearchQuery {
+            text: None,
+            tags: vec![],
+            exclude_tags: vec![],
+            limit: None,
+            offset: None,
+            ..query.clone()
+        };
+        let text = query.text.as_ref().map(|t| t.to_lowercase());
+
+        let mut skip = query.offset.unwrap_or(0);
+        let mut notes = Vec::new();
+        for note in self.search_stored(&stored)? {
+            let note = cipher.open_note(note)?;
+            let matches = text
+                .as_ref()
+                .is_none_or(|text| note.content.to_lowercase().contains(text))
+                && query.matches_tags(&note.tags);
+            if matches && skip > 0 {
+                skip -= 1;
+            } else if matches {
+                notes.push(note);
+            }
+            if query.limit.is_some_and(|limit| notes.len() >= limit) {
+                break;
+            }
+        }
+        Ok(notes)
+    }
+
+    /// Search the notes as stored, without decrypting them
+    fn search_stored(&self, query: &SearchQuery) -> Result<Vec<Note>> {
+        self.search_cache
+            .search(&self.conn, query)
+            .map_err(|e| match e {
+                // Mistakes in a full-text query (e.g. `AND` with nothing before it)
+                rusqlite::Error::SqliteFailure(_, Some(ref message))
+                    if query.match_query.is_some() && message.starts_with("fts5:") =>
+                {
+                    CliError::new(
+                        ErrorKind::InvalidInput,
+                        format!("Invalid search query: {}", message),
+                    )
+                    .into()
+                }
+                e => anyhow::Error::new(e).context("Failed to search notes"),
+            })
     }
 
     /// Get a note by ID (supports partial IDs - finds notes starting with the given prefix)
+    ///
+    /// Surrounding whitespace and quotes are ignored and the prefix match is
+    /// case-insensitive, so a lowercase ULID pasted from elsewhere still works.
     pub fn get_note_by_id(&self, id: &str) -> Result<Option<Note>> {
+        let id = normalize_id(id);
+
         // First try exact match
         if let Some(note) =
             jot_core::get_note_by_id(&self.conn, id).context("Failed to get note by ID")?
         {
-            return Ok(Some(note));
+            return self.reveal(note).map(Some);
         }
 
         // If not found, try partial match (ID starts with the given prefix)
         let query = SearchQuery {
-            text: None,
-            tags: vec![],
-            date_from: None,
-            date_to: None,
-            created_from: None,
-            created_to: None,
-            include_deleted: false,
-            limit: None,
+            include_archived: true,
+            ..Default::default()
         };
         let all_notes =
             jot_core::search_notes(&self.conn, &query).context("Failed to search notes")?;
 
+        let prefix = id.to_ascii_uppercase();
         let matches: Vec<Note> = all_notes
             .into_iter()
-            .filter(|note| note.id.starts_with(id))
+            .filter(|note| note.id.to_ascii_uppercase().starts_with(&prefix))
             .collect();
 
         match matches.len() {
             0 => Ok(None),
-            1 => Ok(matches.into_iter().next()),
-            _ => Err(anyhow::anyhow!(
-                "Ambiguous ID '{}': matches {} notes. Please provide more characters.",
-                id,
-                matches.len()
-            )),
+            1 => matches
+                .into_iter()
+                .next()
+                .map(|n| self.reveal(n))
+                .transpose(),
+            _ => Err(CliError::new(
+                ErrorKind::AmbiguousId,
+                format!(
+                    "Ambiguous ID '{}': matches {} notes. Please provide more characters.",
+                    id,
+                    matches.len()
+                ),
+            )
+            .into()),
         }
     }
 
@@ -85,29 +345,555 @@ impl LocalDb {
         tags: Vec<String>,
         date: Option<String>,
     ) -> Result<()> {
-        jot_core::update_note(&self.conn, id, &content, tags, date).context("Failed to update note")
+        let (stored, stored_tags) = self.seal(&content, &tags)?;
+        jot_core::update_note_with_limits(
+            &self.conn,
+            id,
+            &stored,
+            stored_tags,
+            date,
+            &self.time.current_offset(),
+            &self.stored_limits(),
+        )
+        .map_err(|e| write_error(e, "Failed to update note"))?;
+        self.queue_for_sync(id)?;
+        self.notify_stored(id, ObserverRegistry::notify_update)
+    }
+
+    /// Set or clear a note's status, returning whether it changed
+    pub fn set_status(&self, id: &str, status: Option<&str>) -> Result<bool> {
+        let changed =
+            jot_core::set_status(&self.conn, id, status).context("Failed to set status")?;
+        if changed {
+            self.queue_for_sync(id)?;
+            self.notify_stored(id, ObserverRegistry::notify_update)?;
+        }
+        Ok(changed)
+    }
+
+    /// Pin a note, returning whether it wasn't pinned already
+    pub fn pin_note(&self, id: &str) -> Result<bool> {
+        self.set_flag(id, jot_core::pin_note)
+    }
+
+    /// Unpin a note, returning whether it was pinned
+    pub fn unpin_note(&self, id: &str) -> Result<bool> {
+        self.set_flag(id, jot_core::unpin_note)
+    }
+
+    /// Archive a note, returning whether it wasn't archived already
+    pub fn archive_note(&self, id: &str) -> Result<bool> {
+        self.set_flag(id, jot_core::archive_note)
+    }
+
+    /// Unarchive a note, returning whether it was archived
+    pub fn unarchive_note(&self, id: &str) -> Result<bool> {
+        self.set_flag(id, jot_core::unarchive_note)
+    }
+
+    fn set_flag(
+        &self,
+        id: &str,
+        set: fn(&rusqlite::Connection, &str) -> rusqlite::Result<bool>,
+    ) -> Result<bool> {
+        let changed = set(&self.conn, id).context("Failed to update note")?;
+        if changed {
+            self.queue_for_sync(id)?;
+            self.notify_stored(id, ObserverRegistry::notify_update)?;
+        }
+        Ok(changed)
+    }
+
+    /// Insert `count` generated notes (see [`jot_core::seed`])
+    pub fn seed_notes(&self, count: usize, seed: u64) -> Result<usize> {
+        jot_core::seed::seed_notes(&self.conn, count, seed).context("Failed to seed notes")
+    }
+
+    /// Insert an imported note unless a live note with the same content exists
+    ///
+    /// Returns `None` for such duplicates, so importing the same file twice
+    /// adds nothing the second time.
+    pub fn import_note(&self, note: NoteBuilder) -> Result<Option<Note>> {
+        let Some(note) = (match self.cipher {
+            Some(_) => self.import_sealed(note)?,
+            None => note
+                .insert_if_new(&self.conn)
+                .map_err(|e| write_error(e, "Failed to import note"))?,
+        }) else {
+            return Ok(None);
+        };
+        self.queue_for_sync(&note.id)?;
+        self.observers.notify_create(&note);
+        Ok(Some(note))
+    }
+
+    /// [`LocalDb::import_note`] on an encrypted profile, where duplicates can
+    /// only be told apart once decrypted
+    fn import_sealed(&self, note: NoteBuilder) -> Result<Option<Note>> {
+        let note = note.build().map_err(|e| {
+            write_error(
+                rusqlite::Error::ToSqlConversionFailure(Box::new(e)),
+                "Failed to import note",
+            )
+        })?;
+        let existing = self.search_notes(&SearchQuery {
+            include_deleted: note.deleted_at.is_some(),
+            include_archived: true,
+            ..Default::default()
+        })?;
+        // A live note with the same content, or a deleted one for a deleted import
+        if existing.iter().any(|n| {
+            n.content == note.content && (n.deleted_at.is_none() || note.deleted_at.is_some())
+        }) {
+            return Ok(None);
+        }
+
+        let (content, tags) = self.seal(&note.content, &note.tags)?;
+        let stored = Note {
+            content,
+            tags,
+            ..note.clone()
+        };
+        jot_core::insert_note(&self.conn, &stored)
+            .map_err(|e| write_error(e, "Failed to import note"))?;
+        Ok(Some(note))
+    }
+
+    /// Map an alias, or a partial or lowercase ID, to the full note ID
+    ///
+    /// Anything that matches no note is returned trimmed but otherwise unchanged.
+    pub fn resolve_id(&self, id_or_alias: &str) -> Result<String> {
+        let id_or_alias = normalize_id(id_or_alias);
+        if let Some(id) = jot_core::alias::resolve_alias(&self.conn, id_or_alias)
+            .context("Failed to resolve alias")?
+        {
+            return Ok(id);
+        }
+        Ok(match self.get_note_by_id(id_or_alias)? {
+            Some(note) => note.id,
+            None => id_or_alias.to_string(),
+        })
+    }
+
+    /// "Note not found" error suggesting the closest existing IDs, by shared
+    /// prefix or by edit distance for typos
+    pub fn note_not_found(&self, id: &str) -> anyhow::Error {
+        let id = normalize_id(id);
+        let suggestions = self.similar_ids(id).unwrap_or_default();
+        note_not_found(id, &suggestions)
+    }
+
+    fn similar_ids(&self, id: &str) -> Result<Vec<String>> {
+        if id.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM notes WHERE deleted_at IS NULL")
+            .context("Failed to list note IDs")?;
+        let ids = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .context("Failed to list note IDs")?;
+
+        // Compare against the same number of leading characters, so a short
+        // partial ID isn't penalised for the part it left out
+        let max_distance = (id.chars().count() / 4).clamp(1, 3);
+        let mut scored: Vec<(usize, String)> = ids
+            .into_iter()
+            .filter_map(|candidate| {
+                let head: String = candidate.chars().take(id.chars().count()).collect();
+                let distance = edit_distance(id, &head);
+                (distance <= max_distance).then_some((distance, candidate))
+            })
+            .collect();
+        scored.sort();
+
+        Ok(scored
+            .into_iter()
+            .take(MAX_ID_SUGGESTIONS)
+            .map(|(_, id)| id)
+            .collect())
+    }
+
+    pub fn set_alias(&self, name: &str, note_id: &str) -> Result<()> {
+        jot_core::alias::set_alias(&self.conn, name, note_id).context("Failed to set alias")
+    }
+
+    pub fn remove_alias(&self, name: &str) -> Result<bool> {
+        jot_core::alias::remove_alias(&self.conn, name).context("Failed to remove alias")
+    }
+
+    pub fn list_aliases(&self) -> Result<Vec<NoteAlias>> {
+        jot_core::alias::list_aliases(&self.conn).context("Failed to list aliases")
+    }
+
+    /// Start tracking time on a note, returning the entry that was running before
+    pub fn start_tracking(&self, note_id: &str) -> Result<Option<TimeEntry>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        jot_core::time_entry::start_entry(&self.conn, note_id, now)
+            .context("Failed to start tracking")
+    }
+
+    /// Stop the running time entry, returning it
+    pub fn stop_tracking(&self) -> Result<Option<TimeEntry>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        jot_core::time_entry::stop_entry(&self.conn, now).context("Failed to stop tracking")
+    }
+
+    /// Time entries overlapping `[from, to)` (milliseconds)
+    pub fn time_entries_between(&self, from: i64, to: i64) -> Result<Vec<TimeEntry>> {
+        jot_core::time_entry::entries_between(&self.conn, from, to)
+            .context("Failed to read time entries")
+    }
+
+    /// Store a recurring note rule whose first occurrence is `start_date`
+    pub fn add_recurrence(
+        &self,
+        content: &str,
+        tags: &[String],
+        every: &str,
+        start_date: &str,
+    ) -> Result<Recurrence> {
+        let (stored, stored_tags) = self.seal(content, tags)?;
+        let rule = jot_core::recurrence::add_recurrence(
+            &self.conn,
+            &stored,
+            &stored_tags,
+            every,
+            start_date,
+        )
+        .context("Failed to add recurring note")?;
+        self.reveal_rule(rule)
+    }
+
+    pub fn list_recurrences(&self) -> Result<Vec<Recurrence>> {
+        jot_core::recurrence::list_recurrences(&self.conn)
+            .context("Failed to list recurring notes")?
+            .into_iter()
+            .map(|rule| self.reveal_rule(rule))
+            .collect()
+    }
+
+    pub fn remove_recurrence(&self, id: i64) -> Result<bool> {
+        jot_core::recurrence::remove_recurrence(&self.conn, id)
+            .context("Failed to remove recurring note")
+    }
+
+    /// Rules with an occurrence on or before `date` still to create
+    pub fn due_recurrences(&self, date: &str) -> Result<Vec<Recurrence>> {
+        jot_core::recurrence::due_recurrences(&self.conn, date)
+            .context("Failed to read recurring notes")?
+            .into_iter()
+            .map(|rule| self.reveal_rule(rule))
+            .collect()
+    }
+
+    /// Create the note for the occurrence of `rule` on `date` and move the
+    /// rule on to `next_date`, both or neither; None when another run already
+    /// moved the rule past `date`
+    pub fn create_occurrence(
+        &self,
+        rule: &Recurrence,
+        date: &str,
+        next_date: &str,
+    ) -> Result<Option<Note>> {
+        let (stored, stored_tags) = self.seal(&rule.content, &rule.tags)?;
+        let note = jot_core::with_transaction(&self.conn, |conn| {
+            if !jot_core::recurrence::set_next_date(conn, rule.id, date, next_date)? {
+                return Ok(None);
+            }
+            let note = jot_core::create_note_with_limits(
+                conn,
+                &stored,
+                stored_tags,
+                Some(date.to_string()),
+                &self.time.current_offset(),
+                &self.stored_limits(),
+            )?;
+            jot_core::sync_queue::enqueue(conn, &note.id)?;
+            Ok(Some(note))
+        })
+        .map_err(|e| write_error(e, "Failed to create recurring note"))?;
+        let Some(mut note) = note else {
+            return Ok(None);
+        };
+        note.content = rule.content.clone();
+        note.tags = rule.tags.clone();
+        self.observers.notify_create(&note);
+        Ok(Some(note))
+    }
+
+    /// Attach the file at `path` to a live note as its file name, replacing
+    /// an attachment of the same name (None when the note is deleted)
+    pub fn attach_file(&self, id: &str, path: &Path) -> Result<Option<Attachment>> {
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| {
+                CliError::new(
+                    ErrorKind::InvalidInput,
+                    format!("{} is not a file", path.display()),
+                )
+            })?;
+        let data =
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let attachment = jot_core::with_transaction(&self.conn, |conn| {
+            let attachment = jot_core::attach_file(conn, id, &filename, &data)?;
+            if attachment.is_some() {
+                jot_core::sync_queue::enqueue(conn, id)?;
+            }
+            Ok(attachment)
+        })
+        .context("Failed to attach file")?;
+        if attachment.is_some() {
+            self.notify_stored(id, ObserverRegistry::notify_update)?;
+        }
+        Ok(attachment)
+    }
+
+    /// Remove the attachment named `name` (file name or ID) from a live note
+    pub fn detach_file(&self, id: &str, name: &str) -> Result<Option<Attachment>> {
+        let attachment = jot_core::with_transaction(&self.conn, |conn| {
+            let attachment = jot_core::detach_file(conn, id, name)?;
+            if attachment.is_some() {
+                jot_core::sync_queue::enqueue(conn, id)?;
+            }
+            Ok(attachment)
+        })
+        .context("Failed to detach file")?;
+        if attachment.is_some() {
+            self.notify_stored(id, ObserverRegistry::notify_update)?;
+        }
+        Ok(attachment)
+    }
+
+    /// Attach the contents of an imported attachment to a note, None when
+    /// they don't match its hash
+    pub fn restore_attachment(
+        &self,
+        id: &str,
+        attachment: &Attachment,
+        data: &[u8],
+    ) -> Result<Option<Attachment>> {
+        jot_core::restore_attachment(&self.conn, id, attachment, data)
+            .context("Failed to restore attachment")
+    }
+
+    /// Contents of an attachment, None when they were attached on another device
+    pub fn attachment_data(&self, attachment: &Attachment) -> Result<Option<Vec<u8>>> {
+        jot_core::attachment_data(&self.conn, attachment).context("Failed to read attachment")
+    }
+
+    /// Live notes per subject date in `[from, to]` (days without notes left out)
+    pub fn count_notes_per_day(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<(String, i64)>> {
+        jot_core::count_notes_per_day(&self.conn, from, to).context("Failed to count notes")
+    }
+
+    /// Groups of live notes with identical content, oldest note first
+    pub fn find_duplicate_notes(&self) -> Result<Vec<Vec<Note>>> {
+        if self.cipher.is_none() {
+            return jot_core::find_duplicate_notes(&self.conn)
+                .context("Failed to find duplicate notes");
+        }
+
+        // Sealed copies differ, so group by the decrypted content
+        let mut notes = self.search_notes(&SearchQuery::default())?;
+        notes.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        let mut groups: Vec<Vec<Note>> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for note in notes {
+            match index.get(&note.content) {
+                Some(&i) => groups[i].push(note),
+                None => {
+                    index.insert(note.content.clone(), groups.len());
+                    groups.push(vec![note]);
+                }
+            }
+        }
+        groups.retain(|group| group.len() > 1);
+        Ok(groups)
     }
 
     /// Soft delete a note
     pub fn soft_delete_note(&self, id: &str) -> Result<()> {
-        jot_core::soft_delete_note(&self.conn, id).context("Failed to soft delete note")
+        jot_core::soft_delete_note(&self.conn, id).context("Failed to soft delete note")?;
+        self.queue_for_sync(id)?;
+        self.notify_stored(id, ObserverRegistry::notify_delete)
     }
 
-    /// Get all notes modified since a timestamp (for sync)
-    #[allow(dead_code)]
+    /// Soft delete several notes atomically
+    pub fn soft_delete_notes(&self, ids: &[&str]) -> Result<()> {
+        jot_core::with_transaction(&self.conn, |tx| {
+            for id in ids {
+                jot_core::soft_delete_note(tx, id)?;
+                jot_core::sync_queue::enqueue(tx, id)?;
+            }
+            Ok(())
+        })
+        .context("Failed to soft delete notes")?;
+
+        for id in ids {
+            self.notify_stored(id, ObserverRegistry::notify_delete)?;
+        }
+        Ok(())
+    }
+
+    /// Soft-deleted notes, most recently deleted first
+    pub fn trashed_notes(&self) -> Result<Vec<Note>> {
+        let mut notes = self.search_notes(&SearchQuery {
+            include_deleted: true,
+            include_archived: true,
+            ..Default::default()
+        })?;
+        notes.retain(|note| note.deleted_at.is_some());
+        notes.sort_by_key(|note| std::cmp::Reverse(note.deleted_at));
+        Ok(notes)
+    }
+
+    /// Bring back a soft-deleted note, returning whether it was deleted
+    pub fn restore_note(&self, id: &str) -> Result<bool> {
+        self.set_flag(id, jot_core::restore_note)
+    }
+
+    /// Permanently remove notes deleted before `deleted_before` (milliseconds)
+    ///
+    /// Like [`LocalDb::compact`], deletions the server hasn't seen yet are kept.
+    pub fn empty_trash(&self, deleted_before: i64) -> Result<usize> {
+        jot_core::purge_deleted_before(&self.conn, self.synced_cutoff(deleted_before)?)
+            .context("Failed to empty the trash")
+    }
+
+    /// Remember a locally changed note so the next sync sends it
+    fn queue_for_sync(&self, id: &str) -> Result<()> {
+        jot_core::sync_queue::enqueue(&self.conn, id).context("Failed to queue note for sync")
+    }
+
+    /// Notes to send on the next sync: everything queued locally plus
+    /// anything changed after `last_sync`
+    ///
+    /// The queue catches edits whose `updated_at` went backwards with the
+    /// system clock; the timestamp scan catches writes made before the queue
+    /// existed.
+    pub fn pending_changes(&self) -> Result<Vec<Note>> {
+        // Sent as stored, with metadata and attachment names sealed as well, so
+        // an encrypted profile's server never sees plain text
+        let mut notes = jot_core::get_notes_since(&self.conn, self.get_last_sync()?)
+            .context("Failed to get notes since timestamp")?;
+        let queued =
+            jot_core::sync_queue::queued_ids(&self.conn).context("Failed to read sync queue")?;
+        for id in queued {
+            if notes.iter().any(|note| note.id == id) {
+                continue;
+            }
+            if let Some(note) =
+                jot_core::get_note_by_id(&self.conn, &id).context("Failed to get note by ID")?
+            {
+                notes.push(note);
+            }
+        }
+        match &self.cipher {
+            Some(cipher) => notes
+                .into_iter()
+                .map(|note| cipher.seal_context(note))
+                .collect(),
+            None => Ok(notes),
+        }
+    }
+
+    /// Reload a changed note and hand it to the observers (skipped when none are registered)
+    fn notify_stored(&self, id: &str, notify: fn(&ObserverRegistry, &Note)) -> Result<()> {
+        if self.observers.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(note) =
+            jot_core::get_note_by_id(&self.conn, id).context("Failed to get note by ID")?
+        {
+            notify(&self.observers, &self.reveal(note)?);
+        }
+        Ok(())
+    }
+
+    /// Get all notes modified since a timestamp (for sync and exports)
     pub fn get_notes_since(&self, timestamp: i64) -> Result<Vec<Note>> {
         jot_core::get_notes_since(&self.conn, timestamp)
-            .context("Failed to get notes since timestamp")
+            .context("Failed to get notes since timestamp")?
+            .into_iter()
+            .map(|note| self.reveal(note))
+            .collect()
+    }
+
+    /// Seal live notes stored in plain text before the profile turned on
+    /// encryption, returning how many there were
+    ///
+    /// They are queued like edits, so the server's plain copies get replaced.
+    pub fn encrypt_existing(&self) -> Result<usize> {
+        let plain: Vec<Note> = self
+            .search_stored(&SearchQuery::default())?
+            .into_iter()
+            .filter(|note| !NoteCipher::is_sealed(&note.content))
+            .collect();
+        if self.cipher.is_none() || plain.is_empty() {
+            return Ok(0);
+        }
+        let sealed = plain
+            .iter()
+            .map(|note| Ok((note, self.seal(&note.content, &note.tags)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        jot_core::with_transaction(&self.conn, |conn| {
+            for (note, (content, tags)) in &sealed {
+                jot_core::update_note_with_limits(
+                    conn,
+                    &note.id,
+                    content,
+                    tags.clone(),
+                    note.subject_date.clone(),
+                    &self.time.current_offset(),
+                    &self.stored_limits(),
+                )?;
+                jot_core::sync_queue::enqueue(conn, &note.id)?;
+            }
+            Ok(())
+        })
+        .context("Failed to encrypt notes")?;
+        Ok(plain.len())
     }
 
-    /// Update or insert a note (for sync)
-    #[allow(dead_code)]
-    pub fn upsert_note(&self, note: &Note) -> Result<()> {
-        jot_core::upsert_note(&self.conn, note).context("Failed to upsert note")
+    /// Decrypt note content as it came from sync (plain text passes through)
+    pub fn decrypt_content(&self, content: &str) -> Result<String> {
+        match &self.cipher {
+            Some(cipher) => cipher.decrypt(content),
+            None => Ok(content.to_string()),
+        }
+    }
+
+    /// Store the outcome of a sync, all or nothing: upsert the notes pulled
+    /// from the server, forget the queued ones it accepted and move
+    /// `last_sync` on
+    pub fn apply_sync(&self, pulled: Vec<Note>, pushed: &[String], last_sync: i64) -> Result<()> {
+        let pulled = pulled
+            .into_iter()
+            .map(|note| self.open_context(note))
+            .collect::<Result<Vec<_>>>()?;
+
+        jot_core::with_transaction(&self.conn, |tx| {
+            for note in &pulled {
+                jot_core::upsert_note(tx, note)?;
+            }
+            jot_core::sync_queue::dequeue(tx, pushed)?;
+            jot_core::set_sync_state(tx, "last_sync", &last_sync.to_string())
+        })
+        .context("Failed to store sync results")
     }
 
     /// Get the last sync timestamp
-    #[allow(dead_code)]
     pub fn get_last_sync(&self) -> Result<i64> {
         match jot_core::get_sync_state(&self.conn, "last_sync") {
             Ok(Some(s)) => Ok(s.parse::<i64>().unwrap_or(0)),
@@ -116,10 +902,184 @@ impl LocalDb {
         }
     }
 
-    /// Set the last sync timestamp
-    #[allow(dead_code)]
-    pub fn set_last_sync(&self, timestamp: i64) -> Result<()> {
-        jot_core::set_sync_state(&self.conn, "last_sync", &timestamp.to_string())
-            .context("Failed to set last sync timestamp")
+    /// When `jot export` last wrote to `target` (an absolute directory path)
+    pub fn last_export(&self, target: &str) -> Result<Option<i64>> {
+        let value = jot_core::get_sync_state(&self.conn, &export_state_key(target))
+            .context("Failed to get last export timestamp")?;
+        Ok(value.and_then(|s| s.parse::<i64>().ok()))
+    }
+
+    pub fn set_last_export(&self, target: &str, timestamp: i64) -> Result<()> {
+        jot_core::set_sync_state(
+            &self.conn,
+            &export_state_key(target),
+            &timestamp.to_string(),
+        )
+        .context("Failed to set last export timestamp")
+    }
+
+    /// Purge tombstones deleted before `deleted_before` (milliseconds)
+    ///
+    /// Tombstones changed after the last sync are kept even if they are old,
+    /// since the server hasn't seen the deletion yet.
+    pub fn compact(&self, deleted_before: i64) -> Result<jot_core::CompactResult> {
+        jot_core::compact_db(&self.conn, self.synced_cutoff(deleted_before)?)
+            .context("Failed to compact database")
+    }
+
+    /// Compare the full-text search index with the notes
+    pub fn check_search_index(&self) -> Result<jot_core::SearchIndexCheck> {
+        jot_core::check_search_index(&self.conn).context("Failed to check search index")
+    }
+
+    /// Rebuild the full-text search index from the notes
+    pub fn reindex(&self) -> Result<jot_core::ReindexResult> {
+        jot_core::reindex_db(&self.conn).context("Failed to rebuild search index")
+    }
+
+    /// `deleted_before` moved back to the last sync, so tombstones the
+    /// server hasn't seen yet survive a purge
+    fn synced_cutoff(&self, deleted_before: i64) -> Result<i64> {
+        let last_sync = self.get_last_sync()?;
+        Ok(if last_sync > 0 {
+            deleted_before.min(last_sync + 1)
+        } else {
+            deleted_before
+        })
+    }
+
+    /// Move note bodies to or from compressed storage per the current threshold
+    pub fn recompress(&self) -> Result<jot_core::RecompressResult> {
+        jot_core::recompress(&self.conn).context("Failed to recompress notes")
+    }
+
+    /// Load every note (tombstones included) from a server snapshot and
+    /// continue syncing from `snapshot_at`
+    ///
+    /// Only allowed on an empty database: local notes newer than the old
+    /// `last_sync` would otherwise never be pushed.
+    pub fn import_snapshot(&self, snapshot: &Path, snapshot_at: i64) -> Result<usize> {
+        self.ensure_fresh()?;
+
+        let source = jot_core::open_db(snapshot).context("Failed to open snapshot")?;
+        let notes = jot_core::get_notes_since(&source, i64::MIN)
+            .context("Failed to read notes from snapshot")?
+            .into_iter()
+            .map(|note| self.open_context(note))
+            .collect::<Result<Vec<_>>>()?;
+
+        jot_core::with_transaction(&self.conn, |tx| {
+            for note in &notes {
+                jot_core::insert_note(tx, note)?;
+            }
+            jot_core::set_sync_state(tx, "last_sync", &snapshot_at.to_string())
+        })
+        .context("Failed to import snapshot")?;
+
+        Ok(notes.len())
+    }
+
+    /// Fail unless the database has no notes at all (tombstones included)
+    pub fn ensure_fresh(&self) -> Result<()> {
+        let existing: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM notes", [], |row| row.get(0))
+            .context("Failed to count notes")?;
+        if existing > 0 {
+            return Err(CliError::new(
+                ErrorKind::InvalidInput,
+                "The local database already has notes; bootstrapping only works on a fresh device",
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Content and tags as they should be stored: checked against the note
+    /// limits and sealed when the profile encrypts
+    fn seal(&self, content: &str, tags: &[String]) -> Result<(String, Vec<String>)> {
+        let Some(cipher) = &self.cipher else {
+            return Ok((content.to_string(), tags.to_vec()));
+        };
+        self.limits
+            .validate(content, tags)
+            .map_err(|e| write_error(e.into(), "Invalid note"))?;
+        Ok((cipher.encrypt(content)?, cipher.seal_tags(tags)?))
+    }
+
+    /// Metadata values and attachment names sealed by another device's sync
+    /// opened again; they are kept in plain text locally, so `--meta` and
+    /// attachment names work as without encryption
+    fn open_context(&self, note: Note) -> Result<Note> {
+        match &self.cipher {
+            Some(cipher) => cipher.open_context(note),
+            None => Ok(note),
+        }
+    }
+
+    /// Limits checked on stored values, which [`LocalDb::seal`] has already
+    /// checked in plain text when they are sealed
+    fn stored_limits(&self) -> NoteLimits {
+        match self.cipher {
+            Some(_) => NoteLimits {
+                max_content_bytes: usize::MAX,
+                max_tags: usize::MAX,
+            },
+            None => self.limits,
+        }
+    }
+
+    /// A note as read from the database, decrypted when the profile encrypts
+    fn reveal(&self, note: Note) -> Result<Note> {
+        match &self.cipher {
+            Some(cipher) => cipher.open_note(note),
+            None => Ok(note),
+        }
+    }
+
+    fn reveal_rule(&self, rule: Recurrence) -> Result<Recurrence> {
+        match &self.cipher {
+            Some(cipher) => cipher.open_recurrence(rule),
+            None => Ok(rule),
+        }
+    }
+}
+
+fn export_state_key(target: &str) -> String {
+    format!("last_export:{}", target)
+}
+
+/// Strip whitespace and quotes copied along with an ID
+fn normalize_id(id: &str) -> &str {
+    id.trim()
+        .trim_matches(|c| matches!(c, '"' | '\'' | '`'))
+        .trim()
+}
+
+/// Turn a database open error into a user-facing message
+fn open_error(err: rusqlite::Error, path: &Path) -> anyhow::Error {
+    match schema::as_newer_schema_version(&err) {
+        // Already actionable on its own, no need for the generic wrapper
+        Some(newer) => {
+            CliError::new(ErrorKind::SchemaTooNew, format!("{} ({:?})", newer, path)).into()
+        }
+        None => {
+            anyhow::Error::new(err).context(format!("Failed to open local database at {:?}", path))
+        }
+    }
+}
+
+/// Turn a rejected write into a user-facing message
+fn write_error(err: rusqlite::Error, action: &str) -> anyhow::Error {
+    match as_validation_error(&err) {
+        Some(NoteValidationError::ContentTooLong { .. }) => CliError::new(
+            ErrorKind::InvalidNote,
+            format!(
+                "{}. Large pastes are better kept as a file attachment, with a short note pointing to it.",
+                err
+            ),
+        ).into(),
+        Some(_) => CliError::new(ErrorKind::InvalidNote, err.to_string()).into(),
+        None => anyhow::Error::new(err).context(action.to_string()),
     }
 }
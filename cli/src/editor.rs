@@ -1,6 +1,9 @@
 use std::{
     collections::HashSet,
+    ffi::OsStr,
     io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::Command,
 };
 
 use anyhow::Context;
@@ -8,6 +11,130 @@ use serde::{Deserialize, Serialize};
 
 use crate::{args::NoteAddArgs, utils::date_source::DateSource};
 
+/// Editor used when neither `VISUAL` nor `EDITOR` is set
+#[cfg(windows)]
+const DEFAULT_EDITOR: &str = "notepad";
+#[cfg(not(windows))]
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Extensions tried when `PATHEXT` is unset
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+/// Open `path` in the user's editor and wait for it to exit
+pub fn open_in_editor(path: &Path) -> anyhow::Result<()> {
+    let editor = editor_from_env();
+    let status = editor_command(&editor, path)
+        .status()
+        .with_context(|| format!("Failed to open editor '{}'", editor))?;
+
+    if !status.success() {
+        return Err(anyhow::anyhow!("Editor returned non-zero exit code"));
+    }
+    Ok(())
+}
+
+/// `VISUAL`, then `EDITOR`, then the platform default
+fn editor_from_env() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string())
+}
+
+fn editor_command(editor: &str, path: &Path) -> Command {
+    let parts = split_editor_command(editor);
+    let (program, args) = match parts.split_first() {
+        Some((program, args)) => (program.as_str(), args),
+        None => (DEFAULT_EDITOR, &[] as &[String]),
+    };
+
+    let mut command = if cfg!(windows) {
+        // `Command` only appends `.exe` when searching PATH, so `code`
+        // (really `code.cmd`) wouldn't be found. Batch files resolved here
+        // are wrapped in cmd.exe by std with proper argument escaping;
+        // anything unresolvable (shell built-ins, doskey macros) goes
+        // through `cmd /C`.
+        let path_var = std::env::var_os("PATH").unwrap_or_default();
+        let pathext = std::env::var("PATHEXT").unwrap_or_else(|_| DEFAULT_PATHEXT.to_string());
+        match resolve_program(program, &path_var, &pathext) {
+            Some(resolved) => Command::new(resolved),
+            None => {
+                let mut command = Command::new("cmd");
+                command.arg("/C").arg(program);
+                command
+            }
+        }
+    } else {
+        Command::new(program)
+    };
+    command.args(args).arg(path);
+    command
+}
+
+/// Split an editor setting into program and arguments
+///
+/// Double quotes group words (`"C:\Program Files\Vim\gvim.exe" -f`), and a
+/// setting naming an existing file is taken whole, so unquoted paths with
+/// spaces work too.
+pub fn split_editor_command(editor: &str) -> Vec<String> {
+    let editor = editor.trim();
+    if !editor.contains('"') && Path::new(editor).is_file() {
+        return vec![editor.to_string()];
+    }
+
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_part = false;
+    for c in editor.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_part = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_part {
+                    parts.push(std::mem::take(&mut current));
+                    has_part = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_part = true;
+            }
+        }
+    }
+    if has_part {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Find `program` the way `cmd.exe` would: as given when it has a path or an
+/// extension that exists, otherwise by trying each `PATHEXT` extension in
+/// every `PATH` directory
+fn resolve_program(program: &str, path_var: &OsStr, pathext: &str) -> Option<PathBuf> {
+    let extensions: Vec<&str> = pathext.split(';').filter(|e| !e.is_empty()).collect();
+    let with_extensions = |base: &Path| -> Option<PathBuf> {
+        if base.extension().is_some() && base.is_file() {
+            return Some(base.to_path_buf());
+        }
+        extensions.iter().find_map(|ext| {
+            let mut candidate = base.as_os_str().to_owned();
+            candidate.push(ext);
+            let candidate = PathBuf::from(candidate);
+            candidate.is_file().then_some(candidate)
+        })
+    };
+
+    let program = Path::new(program);
+    if program.components().count() > 1 || program.is_absolute() {
+        return with_extensions(program);
+    }
+    std::env::split_paths(path_var).find_map(|dir| with_extensions(&dir.join(program)))
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct EditorTemplate {
     #[serde(default)]
@@ -59,20 +186,7 @@ impl Editor {
     }
 
     fn read_from_file(&self, tempfile: tempfile::NamedTempFile) -> anyhow::Result<String> {
-        // Read VISUAL or EDITOR environment variable
-        let editor = std::env::var("VISUAL")
-            .unwrap_or_else(|_| std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string()));
-
-        let mut child = std::process::Command::new(editor)
-            .arg(tempfile.path())
-            .spawn()
-            .context("Failed to open editor")?;
-
-        let status = child.wait().context("Failed to wait for editor")?;
-
-        if !status.success() {
-            return Err(anyhow::anyhow!("Editor returned non-zero exit code"));
-        }
+        open_in_editor(tempfile.path())?;
 
         // Read content of the tempfile
         let mut content = String::new();
@@ -442,6 +556,62 @@ My content"#;
         assert!(formatted.contains("# invalid key"));
     }
 
+    #[test]
+    fn test_split_editor_command() {
+        assert_eq!(split_editor_command("vim"), vec!["vim"]);
+        assert_eq!(split_editor_command("code --wait"), vec!["code", "--wait"]);
+        assert_eq!(
+            split_editor_command(r#""C:\Program Files\Vim\gvim.exe" -f"#),
+            vec![r"C:\Program Files\Vim\gvim.exe", "-f"]
+        );
+        assert!(split_editor_command("   ").is_empty());
+    }
+
+    #[test]
+    fn test_split_editor_command_keeps_existing_path_with_spaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let editor = dir.path().join("my editor");
+        std::fs::write(&editor, "").unwrap();
+        let editor = editor.to_str().unwrap();
+
+        assert_eq!(split_editor_command(editor), vec![editor]);
+    }
+
+    #[test]
+    fn test_resolve_program_tries_pathext() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("code.cmd"), "").unwrap();
+        std::fs::write(dir.path().join("notepad.exe"), "").unwrap();
+        let path_var = std::env::join_paths([dir.path()]).unwrap();
+        // Lowercase so the lookup also matches on case-sensitive filesystems
+        let pathext = ".exe;.cmd";
+
+        assert_eq!(
+            resolve_program("code", &path_var, pathext),
+            Some(dir.path().join("code.cmd"))
+        );
+        assert_eq!(
+            resolve_program("notepad.exe", &path_var, pathext),
+            Some(dir.path().join("notepad.exe"))
+        );
+        assert_eq!(resolve_program("missing", &path_var, pathext), None);
+
+        let absolute = dir.path().join("code");
+        assert_eq!(
+            resolve_program(absolute.to_str().unwrap(), &path_var, pathext),
+            Some(dir.path().join("code.cmd"))
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_editor_command_falls_back_to_cmd() {
+        let command = editor_command("surely-not-an-editor", Path::new("note.txt"));
+        assert_eq!(command.get_program(), "cmd");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args, ["/C", "surely-not-an-editor", "note.txt"]);
+    }
+
     #[test]
     fn test_format_error_header_multiline_error() {
         let error = anyhow::anyhow!("Line 1 error\nLine 2 error\nLine 3 error");
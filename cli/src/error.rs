@@ -0,0 +1,131 @@
+use std::fmt;
+
+use jot_core::{schema, validation::as_validation_error};
+use rusqlite::ErrorCode;
+use serde::Serialize;
+use serde_json::json;
+
+/// Machine-readable category of a failure, reported in JSON error output
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    NotFound,
+    AmbiguousId,
+    InvalidInput,
+    InvalidNote,
+    DatabaseLocked,
+    SchemaTooNew,
+    IncompatibleServer,
+    Database,
+    Io,
+    Other,
+}
+
+/// An error whose kind is known where it is raised (e.g. a missing note)
+#[derive(Debug)]
+pub struct CliError {
+    pub kind: ErrorKind,
+    message: String,
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Shorthand for the common "no note with this ID" failure, listing similar
+/// existing IDs when there are any
+pub fn note_not_found(id: &str, suggestions: &[String]) -> anyhow::Error {
+    let mut message = format!("Note with ID '{}' not found", id);
+    if !suggestions.is_empty() {
+        message.push_str(&format!(". Did you mean: {}?", suggestions.join(", ")));
+    }
+    CliError::new(ErrorKind::NotFound, message).into()
+}
+
+/// Classify an error by the first cause in its chain that we recognize
+pub fn error_kind(err: &anyhow::Error) -> ErrorKind {
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<CliError>() {
+            return err.kind;
+        }
+        if let Some(err) = cause.downcast_ref::<rusqlite::Error>() {
+            if schema::as_newer_schema_version(err).is_some() {
+                return ErrorKind::SchemaTooNew;
+            }
+            if as_validation_error(err).is_some() {
+                return ErrorKind::InvalidNote;
+            }
+            return match err.sqlite_error_code() {
+                Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => {
+                    ErrorKind::DatabaseLocked
+                }
+                _ => ErrorKind::Database,
+            };
+        }
+        if cause.is::<std::io::Error>() {
+            return ErrorKind::Io;
+        }
+    }
+    ErrorKind::Other
+}
+
+/// Render an error as `{"error": {"kind": ..., "message": ...}}`
+pub fn error_json(err: &anyhow::Error) -> serde_json::Value {
+    json!({
+        "error": {
+            "kind": error_kind(err),
+            "message": format!("{:#}", err),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_error_kind_follows_context_chain() {
+        let err = Err::<(), _>(note_not_found("abc", &[]))
+            .context("Failed to show note")
+            .unwrap_err();
+        assert_eq!(error_kind(&err), ErrorKind::NotFound);
+
+        let busy = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            None,
+        );
+        let err = anyhow::Error::new(busy).context("Failed to create note");
+        assert_eq!(error_kind(&err), ErrorKind::DatabaseLocked);
+
+        assert_eq!(error_kind(&anyhow::anyhow!("boom")), ErrorKind::Other);
+    }
+
+    #[test]
+    fn test_error_json_shape() {
+        let value = error_json(&note_not_found("abc", &[]));
+        assert_eq!(value["error"]["kind"], "not_found");
+        assert_eq!(value["error"]["message"], "Note with ID 'abc' not found");
+
+        let value = error_json(&note_not_found("abd", &["ABC".to_string()]));
+        assert_eq!(
+            value["error"]["message"],
+            "Note with ID 'abd' not found. Did you mean: ABC?"
+        );
+    }
+}
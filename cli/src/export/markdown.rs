@@ -0,0 +1,45 @@
+//! One Markdown file per note, with the metadata in TOML frontmatter
+//!
+//! The frontmatter uses the same `tags` and `date` keys as the
+//! `note add --editor` template, between `+++` lines as static site
+//! generators expect. Attachments are listed as `attachments`, with their
+//! contents written separately to [`super::ATTACHMENTS_DIR`].
+
+use chrono::{DateTime, SecondsFormat};
+use jot_core::Note;
+
+pub const EXTENSION: &str = "md";
+
+pub fn render(note: &Note) -> String {
+    let mut out = String::from("+++\n");
+    out.push_str(&format!("id = {:?}\n", note.id));
+    out.push_str(&format!("tags = {:?}\n", note.tags));
+    if let Some(ref date) = note.subject_date {
+        out.push_str(&format!("date = {:?}\n", date));
+    }
+    out.push_str(&format!("created_at = {:?}\n", timestamp(note.created_at)));
+    out.push_str(&format!("updated_at = {:?}\n", timestamp(note.updated_at)));
+    if !note.attachments.is_empty() {
+        out.push_str("attachments = [\n");
+        for a in &note.attachments {
+            out.push_str(&format!(
+                "    {{ id = {:?}, filename = {:?}, hash = {:?}, size = {}, created_at = {} }},\n",
+                a.id, a.filename, a.hash, a.size, a.created_at
+            ));
+        }
+        out.push_str("]\n");
+    }
+    out.push_str("+++\n\n");
+    out.push_str(&note.content);
+    if !note.content.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+/// Milliseconds as RFC 3339 in UTC
+fn timestamp(millis: i64) -> String {
+    DateTime::from_timestamp_millis(millis)
+        .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_default()
+}
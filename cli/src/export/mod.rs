@@ -0,0 +1,91 @@
+//! Writers for notes exported to plain files
+//!
+//! Exported files are named `<date>-<slug>-<short id>.<ext>` and recorded in a
+//! manifest in the target directory, so a re-export overwrites (or renames)
+//! the note's existing file instead of adding another one.
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::Context;
+use chrono::DateTime;
+use jot_core::Note;
+use serde::{Deserialize, Serialize};
+
+use crate::{formatters::short_id, utils::text::slugify};
+
+pub mod markdown;
+
+/// Manifest file, kept next to the exported notes
+pub const MANIFEST_FILE: &str = ".jot-export.json";
+
+/// Folder within the export directory holding attachment contents, as files
+/// named by their SHA-256
+pub const ATTACHMENTS_DIR: &str = "attachments";
+
+/// Longest slug taken from a note's first line
+const SLUG_LENGTH: usize = 50;
+
+/// Which file each exported note was written to
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Note ID -> file name within the export directory
+    pub notes: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    /// Read the directory's manifest, or start an empty one
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self, dir: &Path) -> anyhow::Result<()> {
+        let path = dir.join(MANIFEST_FILE);
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(&path, json + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// File name for `note` in `dir`
+    ///
+    /// The name comes from the note's date, first line and short ID. When
+    /// another note (or a file the export didn't write) already has it, the
+    /// full ID is used instead, which cannot collide.
+    pub fn file_name(&self, dir: &Path, note: &Note, extension: &str) -> String {
+        let date = note.subject_date.clone().unwrap_or_else(|| {
+            DateTime::from_timestamp_millis(note.created_at)
+                .map(|t| t.format("%Y-%m-%d").to_string())
+                .unwrap_or_default()
+        });
+        let slug = match slugify(&note.content, SLUG_LENGTH) {
+            slug if slug.is_empty() => "note".to_string(),
+            slug => slug,
+        };
+
+        let name = format!("{}-{}-{}.{}", date, slug, short_id(&note.id), extension);
+        if self.is_taken(dir, &note.id, &name) {
+            format!("{}-{}-{}.{}", date, slug, note.id, extension)
+        } else {
+            name
+        }
+    }
+
+    /// Whether `name` belongs to anything but the note `id`; compared
+    /// case-insensitively for case-insensitive file systems
+    fn is_taken(&self, dir: &Path, id: &str, name: &str) -> bool {
+        let mut owners = self
+            .notes
+            .iter()
+            .filter(|(_, file)| file.eq_ignore_ascii_case(name))
+            .map(|(owner, _)| owner);
+        match owners.next() {
+            Some(owner) => owner != id,
+            None => dir.join(name).exists(),
+        }
+    }
+}
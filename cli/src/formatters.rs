@@ -1,28 +1,48 @@
 use crate::args::{NoteSearchArgs, NoteShowArgs, OutputFormat};
+use crate::export::markdown;
+use crate::utils::{
+    text::{find_urls, truncate_to_width},
+    time_display::TimeDisplay,
+};
 use jot_core::Note;
 use std::io::{self, Write};
 use termcolor::{BufferWriter, Color, ColorChoice, ColorSpec, WriteColor};
 
 pub struct NoteSearchFormatter {
     args: NoteSearchArgs,
+    plain_structure: bool,
+    time: TimeDisplay,
     writer: BufferWriter,
 }
 
 impl NoteSearchFormatter {
-    pub fn new(args: NoteSearchArgs) -> Self {
+    pub fn new(args: NoteSearchArgs, time: TimeDisplay) -> Self {
         let color_choice = match args.output {
             OutputFormat::Plain => ColorChoice::Never,
             OutputFormat::Json => ColorChoice::Never,
             OutputFormat::Id => ColorChoice::Never,
+            OutputFormat::Markdown => ColorChoice::Never,
             OutputFormat::Pretty => ColorChoice::Auto,
         };
 
         Self {
             args,
+            plain_structure: false,
+            time,
             writer: BufferWriter::stdout(color_choice),
         }
     }
 
+    /// Screen-reader-friendly output instead of pretty/plain: one labelled
+    /// field per line, no symbols or colors
+    pub fn with_plain_structure(mut self, plain_structure: bool) -> Self {
+        if plain_structure {
+            self.plain_structure = true;
+            self.writer = BufferWriter::stdout(ColorChoice::Never);
+        }
+        self
+    }
+
     pub fn print_notes(&mut self, notes: &[Note]) -> io::Result<()> {
         let mut buffer = self.writer.buffer();
 
@@ -35,6 +55,17 @@ impl NoteSearchFormatter {
                     writeln!(buffer, "{}", note.id)?;
                 }
             }
+            OutputFormat::Markdown => {
+                for (i, note) in notes.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(buffer)?;
+                    }
+                    write!(buffer, "{}", markdown::render(note))?;
+                }
+            }
+            _ if self.plain_structure => {
+                self.print_structured(&mut buffer, notes)?;
+            }
             _ => {
                 if notes.is_empty() {
                     writeln!(buffer, "No notes found")?;
@@ -54,6 +85,67 @@ impl NoteSearchFormatter {
         Ok(())
     }
 
+    /// Print notes batch by batch as they are fetched, so only one batch is
+    /// held at a time
+    ///
+    /// The screen-reader layout numbers notes against the total, so it still
+    /// collects everything first.
+    pub fn print_batches<I>(&mut self, batches: I) -> anyhow::Result<()>
+    where
+        I: Iterator<Item = anyhow::Result<Vec<Note>>>,
+    {
+        let structured = self.plain_structure
+            && matches!(self.args.output, OutputFormat::Pretty | OutputFormat::Plain);
+        if structured {
+            let notes = batches.collect::<anyhow::Result<Vec<Vec<Note>>>>()?;
+            self.print_notes(&notes.concat())?;
+            return Ok(());
+        }
+
+        let mut total = 0;
+        for batch in batches {
+            let notes = batch?;
+            let mut buffer = self.writer.buffer();
+            for note in &notes {
+                match self.args.output {
+                    OutputFormat::Json => {
+                        // Same layout as one pretty-printed array
+                        write!(buffer, "{}", if total == 0 { "[\n" } else { ",\n" })?;
+                        let json = serde_json::to_string_pretty(note)?;
+                        let indented: Vec<String> =
+                            json.lines().map(|line| format!("  {}", line)).collect();
+                        write!(buffer, "{}", indented.join("\n"))?;
+                    }
+                    OutputFormat::Id => writeln!(buffer, "{}", note.id)?,
+                    OutputFormat::Markdown => {
+                        if total > 0 {
+                            writeln!(buffer)?;
+                        }
+                        write!(buffer, "{}", markdown::render(note))?;
+                    }
+                    OutputFormat::Pretty | OutputFormat::Plain => {
+                        let pretty = self.args.output == OutputFormat::Pretty;
+                        self.print_note(&mut buffer, note, pretty)?;
+                    }
+                }
+                total += 1;
+            }
+            self.writer.print(&buffer)?;
+        }
+
+        let mut buffer = self.writer.buffer();
+        match self.args.output {
+            OutputFormat::Json if total == 0 => writeln!(buffer, "[]")?,
+            OutputFormat::Json => writeln!(buffer, "\n]")?,
+            OutputFormat::Pretty | OutputFormat::Plain if total == 0 => {
+                writeln!(buffer, "No notes found")?
+            }
+            _ => {}
+        }
+        self.writer.print(&buffer)?;
+        Ok(())
+    }
+
     fn print_note(
         &mut self,
         buffer: &mut termcolor::Buffer,
@@ -65,8 +157,18 @@ impl NoteSearchFormatter {
         } else {
             self.print_metadata(buffer, note)?;
         }
-        // Print content
-        self.print_content(buffer, &note.content)?;
+
+        // Print content (`--lines 0` shows metadata only)
+        if self.args.lines == Some(0) {
+            if !do_pretty_print {
+                writeln!(buffer)?;
+            }
+        } else {
+            if !do_pretty_print {
+                write!(buffer, " ")?;
+            }
+            self.print_content(buffer, &note.content)?;
+        }
 
         // Line break if pretty print
         if do_pretty_print {
@@ -83,18 +185,23 @@ impl NoteSearchFormatter {
                 .set_intense(false),
         )?;
 
-        writeln!(buffer, "\u{1F4CB} {}", &note.id[..8])?; // Show first 8 chars of ULID
-
-        // Show note subject date if present
-        if let Some(ref date) = note.subject_date {
-            writeln!(buffer, "\u{1F4C5} {}", date)?;
+        write!(buffer, "\u{1F4CB} {}", short_id(&note.id))?;
+        if note.pinned {
+            write!(buffer, " \u{1F4CC}")?;
         }
+        writeln!(buffer)?;
+
+        writeln!(buffer, "\u{1F4C5} {}", self.note_date(note))?;
 
         if !note.tags.is_empty() {
             write!(buffer, "\u{1F516}")?;
             writeln!(buffer, " {}", note.tags.join(","))?;
         }
 
+        if let Some(ref status) = note.status {
+            writeln!(buffer, "\u{1F6A6} {}", status)?;
+        }
+
         buffer.reset()?;
 
         Ok(())
@@ -103,22 +210,34 @@ impl NoteSearchFormatter {
     fn print_metadata(&self, buffer: &mut termcolor::Buffer, note: &Note) -> io::Result<()> {
         let mut metadata = Vec::new();
 
-        metadata.push(note.id[..8].to_string()); // Show first 8 chars of ULID
+        metadata.push(short_id(&note.id).to_string());
 
-        // Show note subject date if present
-        if let Some(ref date) = note.subject_date {
-            metadata.push(format!("[{}]", date));
-        }
+        metadata.push(format!("[{}]", self.note_date(note)));
 
         if !note.tags.is_empty() {
             metadata.push(format!("[{}]", note.tags.join(",")));
         }
 
-        write!(buffer, "{} ", metadata.join(" "))?;
+        if let Some(ref status) = note.status {
+            metadata.push(format!("({})", status));
+        }
+
+        write!(buffer, "{}", metadata.join(" "))?;
 
         Ok(())
     }
 
+    /// The subject date, or when the note has none its creation time, as the
+    /// profile's `timezone` and `date_format` show it
+    fn note_date(&self, note: &Note) -> String {
+        match note.subject_date {
+            Some(ref date) => self
+                .time
+                .format_subject_date(date, note.subject_tz.as_deref()),
+            None => self.time.format_timestamp(note.created_at),
+        }
+    }
+
     fn print_content(&self, buffer: &mut termcolor::Buffer, content: &str) -> io::Result<()> {
         let content = self.create_preview(content);
 
@@ -127,6 +246,28 @@ impl NoteSearchFormatter {
         Ok(())
     }
 
+    fn print_structured(&self, buffer: &mut termcolor::Buffer, notes: &[Note]) -> io::Result<()> {
+        writeln!(buffer, "Found {} note(s)", notes.len())?;
+        for (i, note) in notes.iter().enumerate() {
+            writeln!(buffer)?;
+            writeln!(buffer, "Note {} of {}", i + 1, notes.len())?;
+            writeln!(buffer, "ID: {}", short_id(&note.id))?;
+            writeln!(buffer, "Date: {}", self.note_date(note))?;
+            if !note.tags.is_empty() {
+                writeln!(buffer, "Tags: {}", note.tags.join(", "))?;
+            }
+            if self.args.lines != Some(0) {
+                writeln!(buffer, "Content:")?;
+                writeln!(
+                    buffer,
+                    "{}",
+                    spelled_out_lines(&note.content, self.args.lines)
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     fn print_json(&mut self, notes: &[Note], buffer: &mut termcolor::Buffer) -> io::Result<()> {
         let json = serde_json::to_string_pretty(notes).map_err(io::Error::other)?;
 
@@ -135,29 +276,24 @@ impl NoteSearchFormatter {
     }
 
     fn create_preview(&self, content: &str) -> String {
-        let max_lines = self.args.lines.unwrap_or(usize::MAX);
-        let preview: String = content
-            .lines()
-            .take(max_lines)
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        if content.lines().count() > max_lines {
-            format!("{}...", preview)
-        } else {
-            preview
-        }
+        limit_lines(content, self.args.lines)
     }
 }
 
 #[test]
 fn test_note_search_formatter_create_preview_one_line() {
-    let formatter = NoteSearchFormatter::new(NoteSearchArgs {
-        lines: Some(1),
-        ..Default::default()
-    });
+    let formatter = NoteSearchFormatter::new(
+        NoteSearchArgs {
+            lines: Some(1),
+            ..Default::default()
+        },
+        TimeDisplay::default(),
+    );
 
-    assert_eq!(formatter.create_preview("One\nTwo\nThree\nFour"), "One...");
+    assert_eq!(
+        formatter.create_preview("One\nTwo\nThree\nFour"),
+        "One \u{2026} (+3 more lines)"
+    );
 
     assert_eq!(formatter.create_preview("One"), "One");
 
@@ -165,20 +301,28 @@ fn test_note_search_formatter_create_preview_one_line() {
 
     assert_eq!(
         formatter.create_preview("Multi-line note\nWith several\nDistinct lines\nTo test preview"),
-        "Multi-line note..."
+        "Multi-line note \u{2026} (+3 more lines)"
     );
 }
 
 #[test]
 fn test_note_search_formatter_create_preview_two_lines() {
-    let formatter = NoteSearchFormatter::new(NoteSearchArgs {
-        lines: Some(2),
-        ..Default::default()
-    });
+    let formatter = NoteSearchFormatter::new(
+        NoteSearchArgs {
+            lines: Some(2),
+            ..Default::default()
+        },
+        TimeDisplay::default(),
+    );
 
     assert_eq!(
         formatter.create_preview("One\nTwo\nThree\nFour"),
-        "One\nTwo..."
+        "One\nTwo \u{2026} (+2 more lines)"
+    );
+
+    assert_eq!(
+        formatter.create_preview("One\nTwo\nThree"),
+        "One\nTwo \u{2026} (+1 more line)"
     );
 
     assert_eq!(formatter.create_preview("One\nTwo"), "One\nTwo");
@@ -186,24 +330,44 @@ fn test_note_search_formatter_create_preview_two_lines() {
 
 pub struct NoteShowFormatter {
     output: OutputFormat,
+    lines: Option<usize>,
+    /// List attached files instead of only counting them
+    attachments: bool,
+    plain_structure: bool,
+    time: TimeDisplay,
     writer: BufferWriter,
 }
 
 impl NoteShowFormatter {
-    pub fn new(args: &NoteShowArgs) -> Self {
+    pub fn new(args: &NoteShowArgs, time: TimeDisplay) -> Self {
         let color_choice = match args.output {
             OutputFormat::Plain => ColorChoice::Never,
             OutputFormat::Json => ColorChoice::Never,
             OutputFormat::Id => ColorChoice::Never,
+            OutputFormat::Markdown => ColorChoice::Never,
             OutputFormat::Pretty => ColorChoice::Auto,
         };
 
         Self {
             output: args.output.clone(),
+            lines: args.lines,
+            attachments: args.attachments,
+            plain_structure: false,
+            time,
             writer: BufferWriter::stdout(color_choice),
         }
     }
 
+    /// Screen-reader-friendly output instead of pretty/plain: one labelled
+    /// field per line, no symbols or colors
+    pub fn with_plain_structure(mut self, plain_structure: bool) -> Self {
+        if plain_structure {
+            self.plain_structure = true;
+            self.writer = BufferWriter::stdout(ColorChoice::Never);
+        }
+        self
+    }
+
     pub fn print_note(&mut self, note: &Note) -> io::Result<()> {
         let mut buffer = self.writer.buffer();
 
@@ -215,6 +379,12 @@ impl NoteShowFormatter {
             OutputFormat::Id => {
                 writeln!(buffer, "{}", note.id)?;
             }
+            OutputFormat::Markdown => {
+                write!(buffer, "{}", markdown::render(note))?;
+            }
+            _ if self.plain_structure => {
+                self.print_plain(&mut buffer, note)?;
+            }
             OutputFormat::Pretty => {
                 self.print_pretty(&mut buffer, note)?;
             }
@@ -230,7 +400,11 @@ impl NoteShowFormatter {
     fn print_pretty(&self, buffer: &mut termcolor::Buffer, note: &Note) -> io::Result<()> {
         // Header with ID
         buffer.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)).set_bold(true))?;
-        writeln!(buffer, "Note: {}", note.id)?;
+        write!(buffer, "Note: {}", note.id)?;
+        if note.pinned {
+            write!(buffer, " \u{1F4CC}")?;
+        }
+        writeln!(buffer)?;
         buffer.reset()?;
 
         // Metadata section
@@ -240,7 +414,12 @@ impl NoteShowFormatter {
         if let Some(ref date) = note.subject_date {
             write!(buffer, "Date:       ")?;
             buffer.reset()?;
-            writeln!(buffer, "{}", date)?;
+            writeln!(
+                buffer,
+                "{}",
+                self.time
+                    .format_subject_date(date, note.subject_tz.as_deref())
+            )?;
             buffer.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
         }
 
@@ -252,32 +431,95 @@ impl NoteShowFormatter {
             buffer.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
         }
 
+        // Status
+        if let Some(ref status) = note.status {
+            write!(buffer, "Status:     ")?;
+            buffer.reset()?;
+            writeln!(buffer, "{}", status)?;
+            buffer.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+        }
+
+        // Where it was captured
+        if !note.meta.is_empty() {
+            write!(buffer, "Context:    ")?;
+            buffer.reset()?;
+            writeln!(buffer, "{}", meta_pairs(note))?;
+            buffer.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+        }
+
+        // Attached files, listed below the content with --attachments
+        if !note.attachments.is_empty() && !self.attachments {
+            write!(buffer, "Files:      ")?;
+            buffer.reset()?;
+            writeln!(buffer, "{}", attachment_count(note))?;
+            buffer.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+        }
+
         // Created at
         write!(buffer, "Created:    ")?;
         buffer.reset()?;
-        writeln!(buffer, "{}", format_timestamp(note.created_at))?;
+        writeln!(buffer, "{}", self.time.format_timestamp(note.created_at))?;
         buffer.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
 
         // Updated at
         write!(buffer, "Updated:    ")?;
         buffer.reset()?;
-        writeln!(buffer, "{}", format_timestamp(note.updated_at))?;
+        writeln!(buffer, "{}", self.time.format_timestamp(note.updated_at))?;
+
+        // Archived at
+        if let Some(archived_at) = note.archived_at {
+            buffer.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+            write!(buffer, "Archived:   ")?;
+            buffer.reset()?;
+            writeln!(buffer, "{}", self.time.format_timestamp(archived_at))?;
+        }
 
         // Deleted at (if soft deleted)
         if let Some(deleted_at) = note.deleted_at {
             buffer.set_color(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(true))?;
             write!(buffer, "Deleted:    ")?;
             buffer.reset()?;
-            writeln!(buffer, "{}", format_timestamp(deleted_at))?;
+            writeln!(buffer, "{}", self.time.format_timestamp(deleted_at))?;
         }
 
         buffer.reset()?;
 
-        // Separator
-        writeln!(buffer)?;
+        self.print_content(buffer, &note.content)?;
 
-        // Content
-        writeln!(buffer, "{}", note.content)?;
+        let urls = find_urls(&note.content);
+        if !urls.is_empty() {
+            writeln!(buffer)?;
+            buffer.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+            writeln!(buffer, "Links:")?;
+            buffer.reset()?;
+            print_links(buffer, &urls)?;
+        }
+
+        if self.attachments {
+            writeln!(buffer)?;
+            buffer.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))?;
+            writeln!(buffer, "Attachments:")?;
+            buffer.reset()?;
+            print_attachments(buffer, note)?;
+        }
+        Ok(())
+    }
+
+    /// Blank separator line followed by the (line-limited) content
+    fn print_content(&self, buffer: &mut termcolor::Buffer, content: &str) -> io::Result<()> {
+        if self.lines == Some(0) {
+            return Ok(());
+        }
+
+        writeln!(buffer)?;
+        match self.plain_structure {
+            true => writeln!(
+                buffer,
+                "Content:\n{}",
+                spelled_out_lines(content, self.lines)
+            )?,
+            false => writeln!(buffer, "{}", limit_lines(content, self.lines))?,
+        }
 
         Ok(())
     }
@@ -286,35 +528,151 @@ impl NoteShowFormatter {
         writeln!(buffer, "ID: {}", note.id)?;
 
         if let Some(ref date) = note.subject_date {
-            writeln!(buffer, "Date: {}", date)?;
+            writeln!(
+                buffer,
+                "Date: {}",
+                self.time
+                    .format_subject_date(date, note.subject_tz.as_deref())
+            )?;
         }
 
         if !note.tags.is_empty() {
             writeln!(buffer, "Tags: {}", note.tags.join(", "))?;
         }
 
-        writeln!(buffer, "Created: {}", format_timestamp(note.created_at))?;
-        writeln!(buffer, "Updated: {}", format_timestamp(note.updated_at))?;
+        if let Some(ref status) = note.status {
+            writeln!(buffer, "Status: {}", status)?;
+        }
+        if !note.meta.is_empty() {
+            writeln!(buffer, "Context: {}", meta_pairs(note))?;
+        }
+        if !note.attachments.is_empty() && !self.attachments {
+            writeln!(buffer, "Files: {}", attachment_count(note))?;
+        }
 
+        writeln!(
+            buffer,
+            "Created: {}",
+            self.time.format_timestamp(note.created_at)
+        )?;
+        writeln!(
+            buffer,
+            "Updated: {}",
+            self.time.format_timestamp(note.updated_at)
+        )?;
+
+        if let Some(archived_at) = note.archived_at {
+            writeln!(
+                buffer,
+                "Archived: {}",
+                self.time.format_timestamp(archived_at)
+            )?;
+        }
         if let Some(deleted_at) = note.deleted_at {
-            writeln!(buffer, "Deleted: {}", format_timestamp(deleted_at))?;
+            writeln!(
+                buffer,
+                "Deleted: {}",
+                self.time.format_timestamp(deleted_at)
+            )?;
         }
 
-        writeln!(buffer)?;
-        writeln!(buffer, "{}", note.content)?;
+        self.print_content(buffer, &note.content)?;
+
+        let urls = find_urls(&note.content);
+        if !urls.is_empty() {
+            writeln!(buffer)?;
+            writeln!(buffer, "Links:")?;
+            print_links(buffer, &urls)?;
+        }
 
+        if self.attachments {
+            writeln!(buffer)?;
+            writeln!(buffer, "Attachments:")?;
+            print_attachments(buffer, note)?;
+        }
         Ok(())
     }
 }
 
-fn format_timestamp(timestamp_ms: i64) -> String {
-    use chrono::{DateTime, Local, TimeZone};
+/// Links numbered for `jot note open <id> <n>`
+fn print_links(buffer: &mut termcolor::Buffer, urls: &[&str]) -> io::Result<()> {
+    for (i, url) in urls.iter().enumerate() {
+        writeln!(buffer, "  [{}] {}", i + 1, url)?;
+    }
+    Ok(())
+}
+
+/// Attached files with their sizes, for `note show --attachments`
+fn print_attachments(buffer: &mut termcolor::Buffer, note: &Note) -> io::Result<()> {
+    if note.attachments.is_empty() {
+        writeln!(buffer, "  (none)")?;
+    }
+    for attachment in &note.attachments {
+        writeln!(
+            buffer,
+            "  {} ({} bytes)",
+            attachment.filename, attachment.size
+        )?;
+    }
+    Ok(())
+}
+
+fn attachment_count(note: &Note) -> String {
+    match note.attachments.len() {
+        1 => "1 attached file (see --attachments)".to_string(),
+        n => format!("{} attached files (see --attachments)", n),
+    }
+}
+
+/// Capture context as `key=value` pairs, e.g. `branch=main repo=jot`
+fn meta_pairs(note: &Note) -> String {
+    note.meta
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// First `max_lines` lines of `content` (all of it for `None`), with a
+/// `… (+K more lines)` marker when lines were left out
+fn limit_lines(content: &str, max_lines: Option<usize>) -> String {
+    let Some(max_lines) = max_lines else {
+        return content.to_string();
+    };
+
+    let shown = content
+        .lines()
+        .take(max_lines)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let hidden = content.lines().count().saturating_sub(max_lines);
+
+    match hidden {
+        0 => shown,
+        1 => format!("{} \u{2026} (+1 more line)", shown),
+        _ => format!("{} \u{2026} (+{} more lines)", shown, hidden),
+    }
+}
 
-    let datetime = Local
-        .timestamp_millis_opt(timestamp_ms)
-        .single()
-        .map(|dt: DateTime<Local>| dt.format("%Y-%m-%d %H:%M:%S").to_string())
-        .unwrap_or_else(|| "Invalid timestamp".to_string());
+/// Like [`limit_lines`], but the left-out lines are reported in words on a
+/// line of their own rather than with an ellipsis symbol
+fn spelled_out_lines(content: &str, max_lines: Option<usize>) -> String {
+    let Some(max_lines) = max_lines else {
+        return content.to_string();
+    };
+
+    let shown = content
+        .lines()
+        .take(max_lines)
+        .collect::<Vec<_>>()
+        .join("\n");
+    match content.lines().count().saturating_sub(max_lines) {
+        0 => shown,
+        hidden => format!("{}\n({} more line(s) not shown)", shown, hidden),
+    }
+}
 
-    datetime
+/// First 8 columns of a note ID (synced IDs aren't guaranteed to be ASCII ULIDs)
+pub fn short_id(id: &str) -> &str {
+    truncate_to_width(id, 8).0
 }
@@ -0,0 +1,88 @@
+//! User commands run after note changes
+//!
+//! The profile's `[hooks]` table maps `post_add`, `post_edit` and
+//! `post_delete` to shell commands. Each runs once per changed note, after
+//! the change is saved, with the note as JSON on stdin and `JOT_HOOK` /
+//! `JOT_NOTE_ID` in its environment. Hook output goes to stderr so it never
+//! mixes with jot's own output, and a failing hook only prints a warning.
+
+use std::{
+    io::{self, Write},
+    process::Stdio,
+};
+
+use jot_core::{Note, NoteObserver};
+
+use crate::{formatters::short_id, profile::HooksConfig, utils::process::shell_command};
+
+pub struct NoteHooks {
+    hooks: HooksConfig,
+}
+
+impl NoteHooks {
+    /// The hooks the profile sets, if any
+    pub fn from_config(hooks: &HooksConfig) -> Option<Self> {
+        (hooks.post_add.is_some() || hooks.post_edit.is_some() || hooks.post_delete.is_some()).then(
+            || Self {
+                hooks: hooks.clone(),
+            },
+        )
+    }
+
+    fn run(&self, name: &str, command: Option<&String>, note: &Note) {
+        let Some(command) = command.filter(|c| !c.trim().is_empty()) else {
+            return;
+        };
+        // The note itself is already saved; a hook failure must not undo that
+        match run_hook(name, command, note) {
+            Ok(true) => {}
+            Ok(false) => eprintln!(
+                "Warning: {} hook failed for note {}: '{}'",
+                name,
+                short_id(&note.id),
+                command
+            ),
+            Err(e) => eprintln!(
+                "Warning: failed to run {} hook for note {}: {}",
+                name,
+                short_id(&note.id),
+                e
+            ),
+        }
+    }
+}
+
+impl NoteObserver for NoteHooks {
+    fn on_create(&self, note: &Note) {
+        self.run("post_add", self.hooks.post_add.as_ref(), note);
+    }
+
+    fn on_update(&self, note: &Note) {
+        self.run("post_edit", self.hooks.post_edit.as_ref(), note);
+    }
+
+    fn on_delete(&self, note: &Note) {
+        self.run("post_delete", self.hooks.post_delete.as_ref(), note);
+    }
+}
+
+/// Run `command` through the shell with `note` on stdin, returning whether
+/// it exited successfully
+fn run_hook(name: &str, command: &str, note: &Note) -> io::Result<bool> {
+    let json = serde_json::to_string(note).map_err(io::Error::other)?;
+
+    let mut child = shell_command(command)
+        .env("JOT_HOOK", name)
+        .env("JOT_NOTE_ID", &note.id)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::from(io::stderr()))
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        match stdin.write_all(json.as_bytes()) {
+            // Hooks that don't read the note may exit before it is written
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+            result => result?,
+        }
+    }
+    Ok(child.wait()?.success())
+}
@@ -0,0 +1,335 @@
+//! Apple Notes exported to a folder
+//!
+//! Apple Notes has no export of its own, so this reads what the common
+//! exporters produce: one HTML or Markdown file per note, in a folder tree
+//! mirroring the Notes folders. HTML is converted to Markdown, and each
+//! folder above a note becomes one of its tags.
+
+use std::path::Path;
+
+use anyhow::Context;
+use jot_core::NoteBuilder;
+
+use crate::import::{
+    collect_note_files, file_times, folder_tag, has_extension, with_timestamps, ImportedNote,
+    ParsedImport,
+};
+
+const HTML_EXTENSIONS: &[&str] = &["html", "htm"];
+const TEXT_EXTENSIONS: &[&str] = &["md", "markdown", "txt"];
+
+/// Notes in an exported folder, dated by the files' creation and
+/// modification times
+///
+/// Other files (attachments, images) are ignored; note files that are not
+/// valid UTF-8 are counted as skipped.
+pub fn read_export(dir: &Path) -> anyhow::Result<ParsedImport> {
+    let extensions = [HTML_EXTENSIONS, TEXT_EXTENSIONS].concat();
+    let files = collect_note_files(dir, &extensions)?;
+
+    let mut parsed = ParsedImport::default();
+    for (path, folders) in files {
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            parsed.skipped += 1;
+            continue;
+        };
+        let content = if has_extension(&path, HTML_EXTENSIONS) {
+            html_to_markdown(&text)
+        } else {
+            text.trim().to_string()
+        };
+        if content.is_empty() {
+            continue;
+        }
+
+        let tags = folders.iter().filter_map(|f| folder_tag(f)).collect();
+        let mut note = NoteBuilder::new(content).tags(tags);
+        let metadata = std::fs::metadata(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if let Some((created_at, updated_at)) = file_times(&metadata) {
+            note = with_timestamps(note, created_at, Some(updated_at));
+        }
+
+        let title = path
+            .strip_prefix(dir)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        parsed.notes.push(ImportedNote {
+            title,
+            note,
+            attachments: Vec::new(),
+        });
+    }
+
+    Ok(parsed)
+}
+
+/// Open list while converting HTML
+struct List {
+    ordered: bool,
+    /// Apple Notes checklists: `<ul class="checklist">`
+    checklist: bool,
+    items: usize,
+}
+
+/// Convert note HTML to Markdown
+///
+/// Handles what note exporters produce: paragraphs and `<div>` lines,
+/// headings, emphasis, links, (check)lists and line breaks. Anything else is
+/// reduced to its text.
+pub fn html_to_markdown(html: &str) -> String {
+    let mut out = String::new();
+    let mut lists: Vec<List> = Vec::new();
+    let mut links: Vec<Option<String>> = Vec::new();
+    // Element whose content is not note text (`<head>`, `<style>`, ...)
+    let mut skipping: Option<String> = None;
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        if skipping.is_none() {
+            push_text(&mut out, &rest[..start]);
+        }
+        let after = &rest[start + 1..];
+        if let Some(comment) = after.strip_prefix("!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(end) = after.find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let raw = after[..end].trim_end_matches('/').trim();
+        rest = &after[end + 1..];
+
+        let closing = raw.starts_with('/');
+        let raw = raw.trim_start_matches('/');
+        let name = raw
+            .split(|c: char| c.is_whitespace())
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        if let Some(ref skipped) = skipping {
+            if closing && *skipped == name {
+                skipping = None;
+            }
+            continue;
+        }
+
+        match (name.as_str(), closing) {
+            ("head" | "style" | "script" | "title", false) => skipping = Some(name),
+            ("br", _) => out.push('\n'),
+            ("p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote" | "pre", true) => {
+                start_line(&mut out);
+                out.push('\n');
+            }
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                start_line(&mut out);
+                let level = name[1..].parse().unwrap_or(1);
+                out.push_str(&"#".repeat(level));
+                out.push(' ');
+            }
+            ("blockquote", false) => {
+                start_line(&mut out);
+                out.push_str("> ");
+            }
+            ("p" | "div" | "pre" | "tr" | "table", _) | ("li", true) => start_line(&mut out),
+            ("ul" | "ol", false) => {
+                start_line(&mut out);
+                lists.push(List {
+                    ordered: name == "ol",
+                    checklist: attribute(raw, "class").is_some_and(|c| c.contains("checklist")),
+                    items: 0,
+                });
+            }
+            ("ul" | "ol", true) => {
+                lists.pop();
+                start_line(&mut out);
+                if lists.is_empty() {
+                    out.push('\n');
+                }
+            }
+            ("li", false) => {
+                start_line(&mut out);
+                let depth = lists.len().saturating_sub(1);
+                out.push_str(&"  ".repeat(depth));
+                let checked = attribute(raw, "class").is_some_and(|c| c.contains("checked"));
+                match lists.last_mut() {
+                    Some(list) if list.checklist => {
+                        out.push_str(if checked { "- [x] " } else { "- [ ] " })
+                    }
+                    Some(list) if list.ordered => {
+                        list.items += 1;
+                        out.push_str(&format!("{}. ", list.items));
+                    }
+                    _ => out.push_str("- "),
+                }
+            }
+            ("b" | "strong", _) => out.push_str("**"),
+            ("i" | "em", _) => out.push('*'),
+            ("s" | "strike" | "del", _) => out.push_str("~~"),
+            ("code" | "tt", _) => out.push('`'),
+            ("a", false) => {
+                let href = attribute(raw, "href").filter(|h| !h.is_empty());
+                if href.is_some() {
+                    out.push('[');
+                }
+                links.push(href);
+            }
+            ("a", true) => {
+                if let Some(Some(href)) = links.pop() {
+                    out.push_str(&format!("]({})", href));
+                }
+            }
+            ("hr", _) => {
+                start_line(&mut out);
+                out.push_str("---\n");
+            }
+            _ => {}
+        }
+    }
+    if skipping.is_none() {
+        push_text(&mut out, rest);
+    }
+
+    tidy_lines(&out)
+}
+
+/// Append text with HTML whitespace rules: runs of whitespace become one
+/// space, and none is kept at the start of a line
+fn push_text(out: &mut String, text: &str) {
+    let text = decode_entities(text);
+    for (i, word) in text.split(|c: char| c.is_ascii_whitespace()).enumerate() {
+        if i > 0 && !out.is_empty() && !out.ends_with([' ', '\n']) {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+}
+
+fn start_line(out: &mut String) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Trim trailing spaces and squeeze runs of blank lines into one
+fn tidy_lines(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// Value of attribute `name` in a tag's source (`a href="..." class=x`)
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find(name) {
+        let start = from + pos;
+        from = start + name.len();
+        let preceded_by_space = lower[..start].ends_with(|c: char| c.is_whitespace());
+        let value = lower[from..].trim_start();
+        if !preceded_by_space || !value.starts_with('=') {
+            continue;
+        }
+        // Index into the original to keep the value's case
+        let value = tag[tag.len() - value.len() + 1..].trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+            _ => value.split(char::is_whitespace).next().unwrap_or_default(),
+        };
+        return Some(decode_entities(value));
+    }
+    None
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn test_html_to_markdown() {
+        let html = r#"<html><head><title>Trip</title><style>div { color: red }</style></head>
+<body>
+<div><h1>Trip &amp; packing</h1></div>
+<div>Book <b>train</b> at <a href="https://example.com/?a=1&amp;b=2">the site</a></div>
+<div><br></div>
+<ul class="checklist"><li class="checked">passport</li><li>charger</li></ul>
+<ol><li>one</li><li>two<ul><li>nested</li></ul></li></ol>
+<!-- exporter comment -->
+<p>Café &#8211; ok&nbsp;then</p>
+</body></html>"#;
+
+        assert_eq!(
+            html_to_markdown(html),
+            "# Trip & packing\n\n\
+             Book **train** at [the site](https://example.com/?a=1&b=2)\n\n\
+             - [x] passport\n\
+             - [ ] charger\n\n\
+             1. one\n\
+             2. two\n  - nested\n\n\
+             Café \u{2013} ok then"
+        );
+    }
+
+    #[test]
+    fn test_folder_tag() {
+        assert_eq!(
+            folder_tag("Work Projects"),
+            Some("work-projects".to_string())
+        );
+        assert_eq!(folder_tag("a, b"), Some("a-b".to_string()));
+        assert_eq!(folder_tag("  "), None);
+    }
+}
@@ -0,0 +1,81 @@
+//! Notes as printed by `jot ls --output json`
+//!
+//! A dump is an array of notes (or a single note, from `jot note show
+//! --output json`). Notes get new IDs and keep their tags, dates, pin,
+//! status, archive state and capture context; deleted notes are left out.
+//! Attachment contents are read from an `attachments` folder next to the
+//! dump, as `jot export` writes it.
+
+use std::path::Path;
+
+use anyhow::Context;
+use jot_core::{Note, NoteBuilder};
+
+use crate::{
+    error::{CliError, ErrorKind},
+    export::ATTACHMENTS_DIR,
+    import::{ImportedNote, ParsedImport},
+    utils::text::preview_line,
+};
+
+/// Width of the note previews used as titles in messages
+const TITLE_WIDTH: usize = 40;
+
+pub fn read_dump(path: &Path) -> anyhow::Result<ParsedImport> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let items = match serde_json::from_str(&text) {
+        Ok(serde_json::Value::Array(items)) => items,
+        Ok(item @ serde_json::Value::Object(_)) => vec![item],
+        _ => {
+            return Err(CliError::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} is not a JSON list of notes (as printed by `jot ls --output json`)",
+                    path.display()
+                ),
+            )
+            .into())
+        }
+    };
+
+    let mut parsed = ParsedImport {
+        attachments_dir: path.parent().map(|dir| dir.join(ATTACHMENTS_DIR)),
+        ..ParsedImport::default()
+    };
+    for item in items {
+        match serde_json::from_value::<Note>(item) {
+            Ok(note) if note.deleted_at.is_some() => {}
+            Ok(note) => parsed.notes.push(imported(note)),
+            Err(_) => parsed.skipped += 1,
+        }
+    }
+    Ok(parsed)
+}
+
+fn imported(note: Note) -> ImportedNote {
+    let title = preview_line(&note.content, TITLE_WIDTH);
+    let mut builder = NoteBuilder::new(note.content)
+        .tags(note.tags)
+        .created_at(note.created_at)
+        .updated_at(note.updated_at.max(note.created_at))
+        .pinned(note.pinned)
+        .meta(note.meta);
+    if let Some(date) = note.subject_date {
+        builder = builder.subject_date(date);
+    }
+    if let Some(offset) = note.subject_tz {
+        builder = builder.subject_tz(offset);
+    }
+    if let Some(status) = note.status {
+        builder = builder.status(status);
+    }
+    if let Some(archived_at) = note.archived_at {
+        builder = builder.archived_at(archived_at);
+    }
+    ImportedNote {
+        title,
+        note: builder,
+        attachments: note.attachments,
+    }
+}
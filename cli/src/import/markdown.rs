@@ -0,0 +1,365 @@
+//! Folders of Markdown files: plain notes, `jot export` output and
+//! Obsidian vaults
+//!
+//! Frontmatter is read when a file starts with it: TOML between `+++` lines
+//! (as `jot export` writes it) or YAML between `---` lines (Obsidian). Its
+//! tags and dates go to the note and the rest is dropped with it. Notes
+//! without a date there are dated by a `YYYY-MM-DD` file name (daily notes),
+//! then by the file times. Each folder above a note becomes one of its tags.
+//! Attachments listed in `jot export` frontmatter are restored from the
+//! folder's `attachments` subfolder.
+
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use jot_core::{Attachment, NoteBuilder};
+use serde::Deserialize;
+
+use crate::{
+    export::ATTACHMENTS_DIR,
+    import::{
+        collect_note_files, file_times, folder_tag, with_timestamps, ImportedNote, ParsedImport,
+    },
+};
+
+const EXTENSIONS: &[&str] = &["md", "markdown", "txt"];
+
+/// Formats of frontmatter dates with a time but no offset, read as local time
+const LOCAL_TIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+];
+
+/// What a note's frontmatter says about it
+#[derive(Debug, Default, PartialEq)]
+struct Frontmatter {
+    tags: Vec<String>,
+    /// Subject date (`date`)
+    date: Option<NaiveDate>,
+    created: Option<DateTime<Utc>>,
+    updated: Option<DateTime<Utc>>,
+    /// Attached files, from `jot export` (TOML only)
+    attachments: Vec<Attachment>,
+}
+
+/// A frontmatter value; anything that isn't a list is kept as text
+#[derive(Debug, PartialEq)]
+enum Value {
+    Text(String),
+    List(Vec<String>),
+}
+
+/// Notes in a folder of Markdown files
+///
+/// Files that are not valid UTF-8 are counted as skipped.
+pub fn read_dir(dir: &Path) -> anyhow::Result<ParsedImport> {
+    let mut parsed = ParsedImport {
+        attachments_dir: Some(dir.join(ATTACHMENTS_DIR)),
+        ..ParsedImport::default()
+    };
+    for (path, folders) in collect_note_files(dir, EXTENSIONS)? {
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            parsed.skipped += 1;
+            continue;
+        };
+        let (frontmatter, body) = split_frontmatter(&text);
+        let content = body.trim();
+        if content.is_empty() {
+            continue;
+        }
+
+        let mut tags: Vec<String> = folders.iter().filter_map(|f| folder_tag(f)).collect();
+        for tag in frontmatter.tags {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        let mut note = NoteBuilder::new(content).tags(tags);
+
+        let metadata = std::fs::metadata(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let times = file_times(&metadata);
+        let created = frontmatter
+            .created
+            .or_else(|| frontmatter.date.map(start_of_day))
+            .or_else(|| date_from_file_name(&path).map(start_of_day))
+            .or(times.map(|(created, _)| created));
+        if let Some(created) = created {
+            let updated = frontmatter.updated.or(times.map(|(_, updated)| updated));
+            note = with_timestamps(note, created, updated);
+        }
+        if let Some(date) = frontmatter.date {
+            note = note.subject_date(date.format("%Y-%m-%d").to_string());
+        }
+
+        let title = path
+            .strip_prefix(dir)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        parsed.notes.push(ImportedNote {
+            title,
+            note,
+            attachments: frontmatter.attachments,
+        });
+    }
+    Ok(parsed)
+}
+
+/// Frontmatter and the rest of the file
+///
+/// A file whose frontmatter can't be read is imported whole.
+fn split_frontmatter(text: &str) -> (Frontmatter, &str) {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let Some(first_line) = text.lines().next() else {
+        return (Frontmatter::default(), text);
+    };
+    let fence = first_line.trim_end();
+    if fence != "+++" && fence != "---" {
+        return (Frontmatter::default(), text);
+    }
+
+    let mut offset = first_line.len() + 1;
+    for line in text[offset.min(text.len())..].split_inclusive('\n') {
+        if line.trim_end() == fence {
+            let raw = &text[first_line.len() + 1..offset];
+            let body = &text[offset + line.len()..];
+            let values = match fence {
+                "+++" => toml_values(raw),
+                _ => yaml_values(raw),
+            };
+            return match values {
+                Some(values) => {
+                    let mut frontmatter = frontmatter(values);
+                    if fence == "+++" {
+                        frontmatter.attachments = toml_attachments(raw);
+                    }
+                    (frontmatter, body)
+                }
+                None => (Frontmatter::default(), text),
+            };
+        }
+        offset += line.len();
+    }
+    (Frontmatter::default(), text)
+}
+
+fn frontmatter(values: Vec<(String, Value)>) -> Frontmatter {
+    let mut frontmatter = Frontmatter::default();
+    for (key, value) in values {
+        match (key.to_lowercase().as_str(), value) {
+            ("tags" | "tag", Value::List(tags)) => {
+                frontmatter.tags = tags.iter().filter_map(|t| clean_tag(t)).collect()
+            }
+            ("tags" | "tag", Value::Text(tags)) => {
+                frontmatter.tags = tags
+                    .split(|c: char| c.is_whitespace() || c == ',')
+                    .filter_map(clean_tag)
+                    .collect()
+            }
+            ("date", Value::Text(date)) => {
+                frontmatter.date = date
+                    .get(..10)
+                    .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+                if frontmatter.created.is_none() && date.len() > 10 {
+                    frontmatter.created = parse_timestamp(&date);
+                }
+            }
+            ("created" | "created_at", Value::Text(created)) => {
+                frontmatter.created = parse_timestamp(&created)
+            }
+            ("updated" | "updated_at" | "modified", Value::Text(updated)) => {
+                frontmatter.updated = parse_timestamp(&updated)
+            }
+            _ => {}
+        }
+    }
+    frontmatter
+}
+
+/// `#Project Alpha` as `project-alpha`; Obsidian's nested `a/b` tags stay as they are
+fn clean_tag(tag: &str) -> Option<String> {
+    folder_tag(tag.trim().trim_start_matches('#'))
+}
+
+fn toml_values(raw: &str) -> Option<Vec<(String, Value)>> {
+    let table: toml::Table = raw.parse().ok()?;
+    Some(
+        table
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    toml::Value::String(text) => Value::Text(text),
+                    toml::Value::Array(items) => Value::List(
+                        items
+                            .into_iter()
+                            .map(|item| match item {
+                                toml::Value::String(text) => text,
+                                other => other.to_string(),
+                            })
+                            .collect(),
+                    ),
+                    other => Value::Text(other.to_string()),
+                };
+                (key, value)
+            })
+            .collect(),
+    )
+}
+
+/// The `attachments` list `jot export` writes; entries that can't be read
+/// are dropped
+fn toml_attachments(raw: &str) -> Vec<Attachment> {
+    #[derive(Deserialize)]
+    struct Exported {
+        #[serde(default)]
+        attachments: Vec<toml::Value>,
+    }
+
+    toml::from_str::<Exported>(raw)
+        .map(|exported| {
+            exported
+                .attachments
+                .into_iter()
+                .filter_map(|value| value.try_into().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The subset of YAML notes apps write: `key: value`, `key: [a, b]` and
+/// `key:` followed by `- item` lines
+///
+/// None when a line is none of these, as the block is then likely note text
+/// between two horizontal rules.
+fn yaml_values(raw: &str) -> Option<Vec<(String, Value)>> {
+    let mut values: Vec<(String, Value)> = Vec::new();
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or((trimmed == "-").then_some(""))
+        {
+            if let Some((_, Value::List(items))) = values.last_mut() {
+                items.push(unquote(item).to_string());
+            }
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) {
+            continue;
+        }
+        let (key, value) = trimmed.split_once(':')?;
+        let value = value.trim();
+        let value = if value.is_empty() {
+            Value::List(Vec::new())
+        } else if let Some(items) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            Value::List(
+                items
+                    .split(',')
+                    .map(|item| unquote(item.trim()).to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect(),
+            )
+        } else {
+            Value::Text(unquote(value).to_string())
+        };
+        values.push((key.trim().to_string(), value));
+    }
+    Some(values)
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|v| v.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+/// RFC 3339, a local date and time, or a date alone (its start in UTC)
+fn parse_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&Utc));
+    }
+    for format in LOCAL_TIME_FORMATS {
+        if let Ok(time) = NaiveDateTime::parse_from_str(text, format) {
+            return time
+                .and_local_timezone(Local)
+                .earliest()
+                .map(|t| t.with_timezone(&Utc));
+        }
+    }
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .ok()
+        .map(start_of_day)
+}
+
+/// Daily notes are named after their day (`2025-01-15.md`, `2025-01-15 Standup.md`)
+fn date_from_file_name(path: &Path) -> Option<NaiveDate> {
+    let stem = path.file_stem()?.to_str()?;
+    NaiveDate::parse_from_str(stem.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Midnight UTC, which keeps the day when the note is dated from it
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_time(NaiveTime::MIN).and_utc()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn test_split_frontmatter() {
+        let yaml = "---\ntitle: Trip\ntags:\n  - Travel\n  - \"#Summer Plans\"\ncreated: 2024-06-01T09:30:00Z\n---\n\n# Trip\n";
+        let (frontmatter, body) = split_frontmatter(yaml);
+        assert_eq!(body, "\n# Trip\n");
+        assert_eq!(frontmatter.tags, vec!["travel", "summer-plans"]);
+        assert_eq!(
+            frontmatter.created,
+            Some(
+                DateTime::parse_from_rfc3339("2024-06-01T09:30:00Z")
+                    .unwrap()
+                    .to_utc()
+            )
+        );
+
+        let inline = "---\ntags: [work, project/alpha]\ndate: 2024-03-02\n---\nbody";
+        let (frontmatter, body) = split_frontmatter(inline);
+        assert_eq!(body, "body");
+        assert_eq!(frontmatter.tags, vec!["work", "project/alpha"]);
+        assert_eq!(frontmatter.date, NaiveDate::from_ymd_opt(2024, 3, 2));
+
+        let toml = "+++\nid = \"01J\"\ntags = [\"work\"]\ndate = \"2024-01-01\"\nupdated_at = \"2024-01-02T10:00:00Z\"\n+++\n\ntext\n";
+        let (frontmatter, body) = split_frontmatter(toml);
+        assert_eq!(body, "\ntext\n");
+        assert_eq!(frontmatter.tags, vec!["work"]);
+        assert_eq!(frontmatter.date, NaiveDate::from_ymd_opt(2024, 1, 1));
+        assert!(frontmatter.updated.is_some());
+
+        let exported = "+++\nattachments = [{ id = \"01A\", filename = \"a.txt\", hash = \"ab\", size = 2, created_at = 1000 }, { filename = \"broken\" }]\n+++\ntext";
+        let (frontmatter, _) = split_frontmatter(exported);
+        assert_eq!(frontmatter.attachments.len(), 1);
+        assert_eq!(frontmatter.attachments[0].filename, "a.txt");
+
+        // Horizontal rules around note text are not frontmatter
+        for text in ["---\nno closing fence", "---\nJust text\n---\nmore"] {
+            let (frontmatter, body) = split_frontmatter(text);
+            assert_eq!(frontmatter, Frontmatter::default());
+            assert_eq!(body, text);
+        }
+    }
+}
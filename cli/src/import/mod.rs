@@ -0,0 +1,131 @@
+//! Readers for notes exported from other apps
+//!
+//! Each reader turns its source format into [`NoteBuilder`]s; storing them
+//! (and skipping notes already imported) is left to `jot import`.
+
+use std::{
+    fs::Metadata,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use jot_core::{Attachment, NoteBuilder};
+
+use crate::error::{CliError, ErrorKind};
+
+pub mod apple_notes;
+pub mod json;
+pub mod markdown;
+pub mod standard_notes;
+
+/// A note read from another app
+pub struct ImportedNote {
+    /// Title in the source app, used in messages about this note
+    pub title: String,
+    pub note: NoteBuilder,
+    /// Files attached to the note in the source, restored from
+    /// [`ParsedImport::attachments_dir`]
+    pub attachments: Vec<Attachment>,
+}
+
+#[derive(Default)]
+pub struct ParsedImport {
+    pub notes: Vec<ImportedNote>,
+    /// Items that could not be read (encrypted or in an unsupported format)
+    pub skipped: usize,
+    /// Folder holding attachment contents as files named by their SHA-256,
+    /// as `jot export` writes them
+    pub attachments_dir: Option<PathBuf>,
+}
+
+/// Keep the source's timestamps, dating the note on the day it was created
+///
+/// A modification time earlier than the creation time is clamped to it.
+fn with_timestamps(
+    note: NoteBuilder,
+    created_at: DateTime<Utc>,
+    updated_at: Option<DateTime<Utc>>,
+) -> NoteBuilder {
+    let updated_at = updated_at.unwrap_or(created_at).max(created_at);
+    note.created_at(created_at.timestamp_millis())
+        .updated_at(updated_at.timestamp_millis())
+        .subject_date(created_at.format("%Y-%m-%d").to_string())
+}
+
+/// Files with one of `extensions` under `dir`, with the folder names leading
+/// to them, in name order; hidden entries (`.obsidian`, `.git`) are skipped
+fn collect_note_files(
+    dir: &Path,
+    extensions: &[&str],
+) -> anyhow::Result<Vec<(PathBuf, Vec<String>)>> {
+    if !dir.is_dir() {
+        return Err(CliError::new(
+            ErrorKind::InvalidInput,
+            format!("{} is not a directory", dir.display()),
+        )
+        .into());
+    }
+    let mut files = Vec::new();
+    collect_files_in(dir, extensions, &mut Vec::new(), &mut files)?;
+    Ok(files)
+}
+
+fn collect_files_in(
+    dir: &Path,
+    extensions: &[&str],
+    folders: &mut Vec<String>,
+    files: &mut Vec<(PathBuf, Vec<String>)>,
+) -> anyhow::Result<()> {
+    let mut entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Failed to read {}", dir.display()))?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            folders.push(name);
+            collect_files_in(&path, extensions, folders, files)?;
+            folders.pop();
+        } else if has_extension(&path, extensions) {
+            files.push((path, folders.clone()));
+        }
+    }
+    Ok(())
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| extensions.contains(&e.to_lowercase().as_str()))
+}
+
+/// Folder name as a tag: lowercase, with spaces and commas turned into `-`
+/// so it can be typed after `-t` or `tag:`
+fn folder_tag(folder: &str) -> Option<String> {
+    let tag = folder
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// Creation and modification time; the earlier of the two counts as
+/// creation, as copying files can leave them the wrong way round
+fn file_times(metadata: &Metadata) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let modified: DateTime<Utc> = metadata.modified().ok()?.into();
+    let created = metadata
+        .created()
+        .ok()
+        .map(DateTime::<Utc>::from)
+        .map_or(modified, |created| created.min(modified));
+    Some((created, modified))
+}
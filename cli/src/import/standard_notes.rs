@@ -0,0 +1,245 @@
+//! Standard Notes decrypted backups
+//!
+//! A backup is a JSON document (`Standard Notes Backup and Import File.txt`,
+//! usually inside a zip) with an `items` array. Notes and tags are separate
+//! items; a tag lists the notes it is attached to in its `references`.
+
+use std::{collections::HashMap, io::Read, path::Path};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use jot_core::NoteBuilder;
+use serde::Deserialize;
+
+use crate::{
+    error::{CliError, ErrorKind},
+    import::{with_timestamps, ImportedNote, ParsedImport},
+};
+
+/// Name of the backup document inside the zip
+const BACKUP_FILE_NAME: &str = "Standard Notes Backup and Import File";
+
+#[derive(Debug, Deserialize)]
+struct Backup {
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Item {
+    uuid: String,
+    content_type: String,
+    #[serde(default)]
+    deleted: bool,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    /// An object when decrypted, an `004:...` string when encrypted
+    #[serde(default)]
+    content: serde_json::Value,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ItemContent {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    trashed: bool,
+    #[serde(default)]
+    references: Vec<Reference>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Reference {
+    uuid: String,
+    content_type: String,
+}
+
+/// Read the backup JSON from a zip or a plain `.txt`/`.json` file
+pub fn read_backup(path: &Path) -> anyhow::Result<String> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if !bytes.starts_with(b"PK\x03\x04") {
+        return String::from_utf8(bytes).context("Backup file is not valid UTF-8");
+    }
+
+    let mut archive =
+        zip::ZipArchive::new(std::io::Cursor::new(bytes)).context("Failed to open backup zip")?;
+    let name = archive
+        .file_names()
+        .filter(|name| !name.contains('/'))
+        .filter(|name| name.ends_with(".txt") || name.ends_with(".json"))
+        .min_by_key(|name| !name.starts_with(BACKUP_FILE_NAME))
+        .map(String::from)
+        .ok_or_else(|| {
+            CliError::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} contains no '{}.txt'; is it a Standard Notes backup?",
+                    path.display(),
+                    BACKUP_FILE_NAME
+                ),
+            )
+        })?;
+
+    let mut json = String::new();
+    archive
+        .by_name(&name)
+        .context("Failed to read backup zip")?
+        .read_to_string(&mut json)
+        .with_context(|| format!("Failed to read '{}' from the backup zip", name))?;
+    Ok(json)
+}
+
+/// Notes in a decrypted backup, tagged with the titles of the tags
+/// referencing them; trashed notes come out soft-deleted
+pub fn parse_backup(json: &str) -> anyhow::Result<ParsedImport> {
+    let backup: Backup = serde_json::from_str(json).map_err(|e| {
+        CliError::new(
+            ErrorKind::InvalidInput,
+            format!("Not a Standard Notes backup: {}", e),
+        )
+    })?;
+
+    let mut parsed = ParsedImport::default();
+    let mut notes = Vec::new();
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+
+    for item in backup.items {
+        if item.deleted || !matches!(item.content_type.as_str(), "Note" | "Tag") {
+            continue;
+        }
+        let content = match item.content {
+            value @ serde_json::Value::Object(_) => {
+                serde_json::from_value::<ItemContent>(value).unwrap_or_default()
+            }
+            _ => {
+                parsed.skipped += 1;
+                continue;
+            }
+        };
+
+        if item.content_type == "Tag" {
+            let title = content.title.trim();
+            if title.is_empty() {
+                continue;
+            }
+            for reference in content
+                .references
+                .iter()
+                .filter(|r| r.content_type == "Note")
+            {
+                tags.entry(reference.uuid.clone())
+                    .or_default()
+                    .push(title.to_string());
+            }
+        } else {
+            notes.push((item.uuid, item.created_at, item.updated_at, content));
+        }
+    }
+
+    if notes.is_empty() && parsed.skipped > 0 {
+        return Err(CliError::new(
+            ErrorKind::InvalidInput,
+            "The backup is encrypted; export a decrypted backup from Standard Notes instead",
+        )
+        .into());
+    }
+
+    for (uuid, created_at, updated_at, content) in notes {
+        let created_at = created_at.as_deref().and_then(parse_timestamp);
+        let updated_at = updated_at.as_deref().and_then(parse_timestamp);
+
+        let body = match (content.title.trim(), content.text.trim_end()) {
+            ("", text) => text.to_string(),
+            (title, "") => title.to_string(),
+            (title, text) => format!("{}\n\n{}", title, text),
+        };
+        if body.trim().is_empty() {
+            continue;
+        }
+
+        let mut note = NoteBuilder::new(body).tags(tags.remove(&uuid).unwrap_or_default());
+        if let Some(created_at) = created_at {
+            note = with_timestamps(note, created_at, updated_at);
+            if content.trashed {
+                let deleted_at = updated_at.unwrap_or(created_at).max(created_at);
+                note = note.deleted_at(deleted_at.timestamp_millis());
+            }
+        }
+        parsed.notes.push(ImportedNote {
+            title: content.title,
+            note,
+            attachments: Vec::new(),
+        });
+    }
+
+    Ok(parsed)
+}
+
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    const BACKUP: &str = r#"{
+        "version": "004",
+        "items": [
+            {
+                "uuid": "n1", "content_type": "Note",
+                "created_at": "2023-05-01T10:00:00.000Z", "updated_at": "2023-05-02T08:30:00.000Z",
+                "content": {"title": "Groceries", "text": "milk\neggs", "references": []}
+            },
+            {
+                "uuid": "n2", "content_type": "Note",
+                "created_at": "2023-06-01T10:00:00.000Z", "updated_at": "2023-06-03T10:00:00.000Z",
+                "content": {"title": "", "text": "old idea", "trashed": true}
+            },
+            {"uuid": "n3", "content_type": "Note", "deleted": true},
+            {
+                "uuid": "t1", "content_type": "Tag",
+                "content": {"title": "home", "references": [{"uuid": "n1", "content_type": "Note"}]}
+            },
+            {"uuid": "x1", "content_type": "SN|Component", "content": {"name": "editor"}},
+            {"uuid": "e1", "content_type": "Note", "content": "004:abc:def"}
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_backup() {
+        let parsed = parse_backup(BACKUP).unwrap();
+        assert_eq!(parsed.skipped, 1);
+
+        let notes: Vec<_> = parsed
+            .notes
+            .into_iter()
+            .map(|n| n.note.build().unwrap())
+            .collect();
+        assert_eq!(notes.len(), 2);
+
+        assert_eq!(notes[0].content, "Groceries\n\nmilk\neggs");
+        assert_eq!(notes[0].tags, vec!["home"]);
+        assert_eq!(notes[0].subject_date.as_deref(), Some("2023-05-01"));
+        assert_eq!(notes[0].created_at, 1_682_935_200_000);
+        assert_eq!(notes[0].updated_at, 1_683_016_200_000);
+        assert_eq!(notes[0].deleted_at, None);
+
+        assert_eq!(notes[1].content, "old idea");
+        assert_eq!(notes[1].deleted_at, Some(notes[1].updated_at));
+    }
+
+    #[test]
+    fn test_encrypted_backup_is_rejected() {
+        let json = r#"{"items": [{"uuid": "e1", "content_type": "Note", "content": "004:abc"}]}"#;
+        let err = parse_backup(json).err().unwrap();
+        assert!(err.to_string().contains("decrypted backup"));
+        assert!(parse_backup("{}").is_err());
+    }
+}
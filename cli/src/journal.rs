@@ -0,0 +1,73 @@
+//! Plain-text mirror of created notes
+//!
+//! With `journal_dir` set in the profile, every note created on this device
+//! is also appended to a Markdown file per month (`2025-03.md`). The files
+//! are never rewritten, so they stay a readable safety net next to SQLite.
+
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use jot_core::{Note, NoteObserver};
+
+use crate::{app_config::AppConfig, formatters::short_id, utils::time_display::TimeDisplay};
+
+pub struct JournalMirror {
+    dir: PathBuf,
+    /// Entry headings, in the profile's timezone and date format
+    time: TimeDisplay,
+    /// File names, in the profile's timezone
+    month: TimeDisplay,
+}
+
+impl JournalMirror {
+    /// The mirror the profile asks for, if any
+    pub fn from_config(config: &AppConfig) -> anyhow::Result<Option<Self>> {
+        let Some(ref dir) = config.journal_dir else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            dir: PathBuf::from(dir),
+            time: TimeDisplay::from_config(config)?,
+            month: TimeDisplay::new(config.timezone.as_deref(), Some("%Y-%m"))?,
+        }))
+    }
+
+    fn append(&self, note: &Note) -> io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!(
+            "{}.md",
+            self.month.format_timestamp(note.created_at)
+        ));
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        let mut entry = format!(
+            "## {} ({})\n\n",
+            self.time.format_timestamp(note.created_at),
+            short_id(&note.id)
+        );
+        if !note.tags.is_empty() {
+            let tags: Vec<String> = note.tags.iter().map(|t| format!("#{}", t)).collect();
+            entry.push_str(&format!("Tags: {}\n\n", tags.join(" ")));
+        }
+        entry.push_str(note.content.trim_end());
+        entry.push_str("\n\n");
+        file.write_all(entry.as_bytes())
+    }
+}
+
+impl NoteObserver for JournalMirror {
+    fn on_create(&self, note: &Note) {
+        // The note itself is already saved; a journal failure must not undo that
+        if let Err(e) = self.append(note) {
+            eprintln!(
+                "Warning: failed to write note {} to the journal in {}: {}",
+                short_id(&note.id),
+                self.dir.display(),
+                e
+            );
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Advisory lock held by long-running mutations of a profile's database
+//!
+//! Imports, prune deletions, migrations and other whole-database rewrites
+//! take it so a second such operation fails straight away with a clear
+//! message, instead of interleaving writes or timing out on `SQLITE_BUSY`
+//! halfway through a transaction. Ordinary commands don't take it; SQLite's
+//! own locking keeps them consistent.
+
+use std::{
+    fs::{File, TryLockError},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+
+use crate::error::{CliError, ErrorKind};
+
+/// Held until dropped (the OS also releases it if the process dies)
+#[derive(Debug)]
+pub struct OperationLock {
+    _file: File,
+}
+
+impl OperationLock {
+    /// Take the lock for the database at `db_path`, failing if another `jot`
+    /// process holds it
+    pub fn acquire(db_path: &Path) -> anyhow::Result<Self> {
+        let path = lock_path(db_path);
+        match try_lock(&path)? {
+            Some(file) => Ok(Self { _file: file }),
+            None => Err(CliError::new(
+                ErrorKind::DatabaseLocked,
+                format!(
+                    "Another jot operation is in progress on {:?}; try again when it has finished",
+                    db_path
+                ),
+            )
+            .into()),
+        }
+    }
+}
+
+/// Held by `jot sync daemon` for as long as it runs, so a profile has at
+/// most one daemon; separate from [`OperationLock`], which each sync takes
+#[derive(Debug)]
+pub struct SyncDaemonLock {
+    _file: File,
+}
+
+impl SyncDaemonLock {
+    /// Take the daemon lock of the profile database at `db_path`, recording
+    /// this process's ID in the lock file
+    pub fn acquire(db_path: &Path) -> anyhow::Result<Self> {
+        let path = daemon_lock_path(db_path);
+        let Some(mut file) = try_lock(&path)? else {
+            let pid = std::fs::read_to_string(&path).unwrap_or_default();
+            let owner = match pid.trim() {
+                "" => String::new(),
+                pid => format!(" (process {})", pid),
+            };
+            return Err(CliError::new(
+                ErrorKind::DatabaseLocked,
+                format!(
+                    "A sync daemon is already running for {:?}{}",
+                    db_path, owner
+                ),
+            )
+            .into());
+        };
+
+        file.set_len(0)
+            .and_then(|()| writeln!(file, "{}", std::process::id()))
+            .with_context(|| format!("Failed to write {:?}", path))?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// Open and lock `path`, or `None` if another process holds it
+fn try_lock(path: &Path) -> anyhow::Result<Option<File>> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open lock file {:?}", path))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(e).with_context(|| format!("Failed to lock {:?}", path)),
+    }
+}
+
+/// Lock file of the database at `db_path`, e.g. `notes.db.lock`
+pub fn lock_path(db_path: &Path) -> PathBuf {
+    with_suffix(db_path, ".lock")
+}
+
+/// Lock file of the profile's sync daemon, e.g. `notes.db.sync-daemon.lock`
+pub fn daemon_lock_path(db_path: &Path) -> PathBuf {
+    with_suffix(db_path, ".sync-daemon.lock")
+}
+
+fn with_suffix(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    db_path.with_file_name(name)
+}
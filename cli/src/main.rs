@@ -3,26 +3,76 @@
 
 use crate::app_config::AppConfig;
 use args::{CliArgs, Command};
-use clap::Parser;
-use commands::{config::config_cmd, note::note_cmd, profile::profile_cmd};
+use clap::{CommandFactory, FromArgMatches, Parser};
+use commands::{
+    auth::auth_cmd, board::board_cmd, cal::cal_cmd, capture::capture_cmd, config::config_cmd,
+    dashboard::dashboard_cmd, db::db_cmd, dev::dev_cmd, export::export_cmd, import::import_cmd,
+    inbox::inbox_cmd, note::note_cmd, profile::profile_cmd, recur::recur_cmd, search::search_cmd,
+    server::server_cmd, shell::shell_alias_cmd, sync::sync_cmd, tag::tag_cmd, track::track_cmd,
+    trash::trash_cmd, usage::usage_cmd,
+};
 use profile::{get_profile_path, Profile};
 
 mod app_config;
 mod args;
+mod browser;
+mod clipboard;
 mod commands;
+mod crypto;
 mod db;
 mod editor;
+mod error;
+mod export;
 mod formatters;
+mod hooks;
+mod import;
+mod journal;
+mod lock;
+mod plugin;
 mod profile;
 mod prune;
+mod suggest;
+mod tls;
+mod usage;
 mod utils;
 
 #[cfg(test)]
 mod test;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = CliArgs::parse();
+    let argv: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    let parsed = CliArgs::command()
+        .try_get_matches_from(&argv)
+        .and_then(|matches| {
+            let args = CliArgs::from_arg_matches(&matches)?;
+            Ok((args, usage::command_path(&matches)))
+        });
+    let (args, command_path) = parsed.unwrap_or_else(|e| {
+        let argv: Vec<String> = argv
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        match suggest::unknown_subcommand_hint(&e, &argv) {
+            Some(hint) => {
+                eprint!("{}", hint);
+                std::process::exit(2);
+            }
+            None => e.exit(),
+        }
+    });
+    let json_errors = args.json_errors();
+
+    match run(args, command_path) {
+        Err(err) if json_errors => {
+            eprintln!("{}", error::error_json(&err));
+            std::process::exit(1);
+        }
+        result => result.map_err(Into::into),
+    }
+}
 
+/// `command_path` is the subcommand as typed, e.g. `note add`
+fn run(args: CliArgs, command_path: Option<String>) -> anyhow::Result<()> {
     // Determine profile name (from arg or current profile)
     let profile_name = if let Some(ref name) = args.config.profile {
         name.clone()
@@ -31,21 +81,41 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     let profile_path = get_profile_path(&args.config.profile);
+    let profile = Profile::from_path(&profile_path)?;
+    let command = match args.command {
+        Some(command) => Some(command),
+        None => default_command(profile.as_ref())?,
+    };
 
-    if let Some(command) = args.command {
-        let profile = Profile::from_path(&profile_path)?;
+    if let Some(command) = command {
         let config =
             AppConfig::from_args(args.config, &profile_path, profile.as_ref(), &profile_name);
+        if config.usage_history && !config.ephemeral {
+            if let Some(ref command_path) = command_path {
+                let log = profile::get_profile_usage_log_path(&profile_name);
+                if let Err(e) = usage::record(&log, command_path) {
+                    eprintln!(
+                        "Warning: failed to record usage in {}: {}",
+                        log.display(),
+                        e
+                    );
+                }
+            }
+        }
 
         match command {
             Command::Config => config_cmd(config)?,
-            Command::Profile { command } => profile_cmd(command)?,
+            Command::Profile { command } => profile_cmd(command, &config)?,
             Command::Note(subcommand) => {
                 let db_path = std::path::Path::new(&config.db_path);
                 note_cmd(db_path, subcommand, &config)?;
             }
-            Command::Down(args) => {
+            Command::Down(mut args) => {
                 let db_path = std::path::Path::new(&config.db_path);
+                if config.inbox_on_down && !args.tag.iter().any(|t| t == commands::inbox::INBOX_TAG)
+                {
+                    args.tag.push(commands::inbox::INBOX_TAG.to_string());
+                }
                 note_cmd(db_path, args::NoteCommand::Add(args), &config)?;
             }
             Command::List(args) => {
@@ -56,17 +126,113 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 let db_path = std::path::Path::new(&config.db_path);
                 note_cmd(db_path, args::NoteCommand::Show(args), &config)?;
             }
+            Command::Db(subcommand) => {
+                let db_path = std::path::Path::new(&config.db_path);
+                db_cmd(db_path, subcommand, &config)?;
+            }
+            Command::Search { command } => search_cmd(command, &config)?,
+            Command::Inbox { command } => {
+                let db_path = std::path::Path::new(&config.db_path);
+                inbox_cmd(db_path, command, &config)?;
+            }
+            Command::Trash(subcommand) => {
+                let db_path = std::path::Path::new(&config.db_path);
+                trash_cmd(db_path, subcommand, &config)?;
+            }
+            Command::Dashboard => {
+                let db_path = std::path::Path::new(&config.db_path);
+                dashboard_cmd(db_path, &config)?;
+            }
+            Command::Usage(args) => {
+                let db_path = std::path::Path::new(&config.db_path);
+                usage_cmd(db_path, args, &config)?;
+            }
+            Command::Board(args) => {
+                let db_path = std::path::Path::new(&config.db_path);
+                board_cmd(db_path, args, &config)?;
+            }
+            Command::Cal(args) => {
+                let db_path = std::path::Path::new(&config.db_path);
+                cal_cmd(db_path, args, &config)?;
+            }
+            Command::Recur(subcommand) => {
+                let db_path = std::path::Path::new(&config.db_path);
+                recur_cmd(db_path, subcommand, &config)?;
+            }
+            Command::Track(subcommand) => {
+                let db_path = std::path::Path::new(&config.db_path);
+                track_cmd(db_path, subcommand, &config)?;
+            }
+            Command::Capture(args) => {
+                let db_path = std::path::Path::new(&config.db_path);
+                capture_cmd(db_path, args, &config)?;
+            }
+            Command::Export(args) => {
+                let db_path = std::path::Path::new(&config.db_path);
+                export_cmd(db_path, args, &config)?;
+            }
+            Command::Import(subcommand) => {
+                let db_path = std::path::Path::new(&config.db_path);
+                import_cmd(db_path, subcommand, &config)?;
+            }
+            Command::Tag(subcommand) => {
+                let db_path = std::path::Path::new(&config.db_path);
+                tag_cmd(db_path, subcommand, &config)?;
+            }
+            Command::Dev(subcommand) => {
+                let db_path = std::path::Path::new(&config.db_path);
+                dev_cmd(db_path, subcommand, &config)?;
+            }
+            Command::Alias { shell } => shell_alias_cmd(shell, &config)?,
+            Command::Auth(subcommand) => auth_cmd(subcommand, &config)?,
+            Command::Sync(args) => {
+                let db_path = std::path::Path::new(&config.db_path);
+                sync_cmd(db_path, args, &config)?;
+            }
+            Command::Server(subcommand) => {
+                let db_path = std::path::Path::new(&config.db_path);
+                server_cmd(db_path, subcommand, &config)?;
+            }
             Command::Completion { shell } => {
-                use clap::CommandFactory;
                 let mut cmd = args::CliArgs::command();
                 clap_complete::generate(shell, &mut cmd, "jot", &mut std::io::stdout());
             }
+            Command::External(words) => plugin::run_plugin(&words, &config)?,
         }
     } else {
         // No command provided, print help
-        use clap::CommandFactory;
         CliArgs::command().print_help()?;
     }
 
     Ok(())
 }
+
+/// The profile's `default_command` for a bare `jot`, if it sets one
+fn default_command(profile: Option<&Profile>) -> anyhow::Result<Option<Command>> {
+    let Some(words) = profile
+        .map(|p| &p.default_command)
+        .filter(|words| !words.is_empty())
+    else {
+        return Ok(None);
+    };
+
+    let invalid = |reason: &str| {
+        error::CliError::new(
+            error::ErrorKind::InvalidInput,
+            format!(
+                "Invalid default_command in profile ({}): {}",
+                words.join(" "),
+                reason.trim_start_matches("error: ")
+            ),
+        )
+    };
+    let args =
+        CliArgs::try_parse_from(std::iter::once("jot").chain(words.iter().map(String::as_str)))
+            .map_err(|e| invalid(e.to_string().lines().next().unwrap_or_default()))?;
+    // Only built-in commands, so a typo is caught here instead of being run as a plugin
+    if let Some(Command::External(words)) = &args.command {
+        let name = words.first().map(String::as_str).unwrap_or_default();
+        return Err(invalid(&format!("unrecognized subcommand '{}'", name)).into());
+    }
+    Ok(args.command)
+}
@@ -0,0 +1,66 @@
+//! Git-style plugins: `jot foo ...` runs `jot-foo ...` from `PATH`
+//!
+//! Any top-level subcommand that isn't built in is looked up as an
+//! executable named `jot-<name>`. It gets the remaining arguments as-is and
+//! the active profile in its environment, so it can read the same database
+//! or call back into `jot` without being told where things are:
+//!
+//! - `JOT_PROFILE` - profile name (also picked up by nested `jot` calls)
+//! - `JOT_PROFILE_PATH` - the profile's TOML file
+//! - `JOT_DB_PATH` - the profile's SQLite database
+//! - `JOT_SERVER_URL` - the profile's `server_url`, when set
+//! - `JOT_BIN` - the running `jot` executable
+
+use std::{io, process::Command};
+
+use anyhow::Context;
+use clap::CommandFactory;
+
+use crate::{app_config::AppConfig, args::CliArgs, suggest};
+
+/// Run the plugin for `words` (its name, then its arguments) and exit with
+/// its status; unknown names fail like any other mistyped subcommand
+pub fn run_plugin(words: &[String], config: &AppConfig) -> anyhow::Result<()> {
+    let Some((name, args)) = words.split_first() else {
+        return Ok(());
+    };
+    // A separator would make the program a path instead of a PATH lookup
+    if name.contains(['/', '\\']) {
+        unknown_subcommand(name);
+    }
+    let program = format!("jot-{}", name);
+
+    let mut command = Command::new(&program);
+    command
+        .args(args)
+        .env("JOT_PROFILE", &config.profile_name)
+        .env("JOT_PROFILE_PATH", &config.profile_path)
+        .env("JOT_DB_PATH", &config.db_path);
+    if let Some(ref url) = config.server_url {
+        command.env("JOT_SERVER_URL", url);
+    }
+    if let Ok(exe) = std::env::current_exe() {
+        command.env("JOT_BIN", exe);
+    }
+
+    match command.status() {
+        Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => unknown_subcommand(name),
+        Err(e) => Err(e).with_context(|| format!("Failed to run plugin '{}'", program)),
+    }
+}
+
+/// Report `name` as neither built in nor a plugin, with "did you mean"
+/// suggestions when something is close
+fn unknown_subcommand(name: &str) -> ! {
+    if let Some(hint) = suggest::hint_for(name, &["jot".to_string(), name.to_string()]) {
+        eprint!("{}", hint);
+        std::process::exit(2);
+    }
+    CliArgs::command()
+        .error(
+            clap::error::ErrorKind::InvalidSubcommand,
+            format!("unrecognized subcommand '{}'", name),
+        )
+        .exit()
+}
@@ -1,13 +1,170 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Ok};
 use serde::{Deserialize, Serialize};
 
+use crate::utils::retention::RetentionPolicy;
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Profile {
     pub db_path: Option<String>,
+    /// Base URL of the sync server, e.g. `https://jot.example.com`
+    pub server_url: Option<String>,
+    /// SHA-256 fingerprint of the sync server's certificate, recorded by
+    /// `jot auth login --insecure-pin`; that certificate is then trusted
+    /// without a CA and no other is accepted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_cert_sha256: Option<String>,
+    /// Server URL `server_cert_sha256` was recorded for; the pin applies to
+    /// requests to that server only (`server_url` when missing)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_cert_url: Option<String>,
     #[serde(default)]
     pub default_tags: Vec<String>,
+    /// Maximum note content size in bytes
+    pub max_content_bytes: Option<usize>,
+    /// Maximum number of tags per note
+    pub max_tags: Option<usize>,
+    /// Tag notes captured with `jot down` as `#inbox`
+    #[serde(default)]
+    pub inbox_on_down: bool,
+    /// Put suggested tags as comments into the `note add --editor` template
+    #[serde(default)]
+    pub suggest_tags: bool,
+    /// Record the working directory, git repo and branch, and hostname with
+    /// each added note (searchable with `--meta repo=jot`)
+    #[serde(default)]
+    pub capture_context: bool,
+    /// Encrypt note content and tags with a key derived from a passphrase
+    /// (`JOT_PASSPHRASE`, or asked for), so neither the database file nor the
+    /// sync server sees them in plain text
+    #[serde(default)]
+    pub encrypt: bool,
+    /// Policies applied by `jot note expire` when no `--keep` is given
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retention: Vec<RetentionPolicy>,
+    /// Extra or replaced aliases for `jot alias` (name = jot arguments, empty removes one)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub shell_aliases: BTreeMap<String, String>,
+    /// Ask before `jot note delete` (default true)
+    pub confirm_delete: Option<bool>,
+    /// Ask before `jot note dedupe --delete/--merge` (default true)
+    pub confirm_dedupe: Option<bool>,
+    /// Ask before `jot note expire` (default true)
+    pub confirm_expire: Option<bool>,
+    /// Ask before deleting the notes marked in `jot note prune` (default true)
+    pub confirm_prune: Option<bool>,
+    /// Named filter combinations for `jot ls @name`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub searches: BTreeMap<String, SavedSearch>,
+    /// Starting points for `jot note add --template <name>` (`[templates.<name>]`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub templates: BTreeMap<String, NoteTemplate>,
+    /// Sections shown by `jot dashboard`
+    pub dashboard: Option<DashboardConfig>,
+    /// Note statuses allowed by `--status`, in `jot board` column order
+    /// (default todo, doing, done)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statuses: Vec<String>,
+    /// IANA timezone timestamps are shown in and "today" is taken from, e.g.
+    /// `Europe/Prague` (system zone if unset)
+    pub timezone: Option<String>,
+    /// strftime pattern for displayed timestamps, e.g. `%d.%m.%Y %H:%M`
+    pub date_format: Option<String>,
+    /// First day of the week for `this week` and `week N` dates, e.g. `sunday` (default monday)
+    pub week_start: Option<String>,
+    /// Directory where created notes are also appended to one Markdown file per month
+    pub journal_dir: Option<String>,
+    /// Arguments run when `jot` is invoked without a subcommand, e.g.
+    /// `["ls", "--date", "today"]` (help is printed if unset)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_command: Vec<String>,
+    /// Record the commands run (names only) in a local log for `jot usage`
+    #[serde(default)]
+    pub usage_history: bool,
+    /// Always use `--plain-structure` output
+    #[serde(default)]
+    pub plain_structure: bool,
+    /// Commands run after notes are added, edited or deleted (`[hooks]`)
+    pub hooks: Option<HooksConfig>,
+}
+
+/// Filters stored by `jot search save`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub term: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Date filter as typed (e.g. "today"), resolved each time the search runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+}
+
+/// Note text and tags `jot note add --template` starts from
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NoteTemplate {
+    /// `{{name}}` placeholders are asked for before the editor opens;
+    /// `{{date}}` (the note's) and `{{time}}` (now) are filled in
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Question asked for a variable instead of its name, e.g.
+    /// `attendees = "Who was there?"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub prompts: BTreeMap<String, String>,
+}
+
+/// Shell commands run after a note changes, with the note as JSON on stdin
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HooksConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_add: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_edit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_delete: Option<String>,
+}
+
+/// Layout of `jot dashboard`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DashboardConfig {
+    /// In display order: "pinned", "today", "tasks", "overdue" or "@search"
+    #[serde(default = "default_dashboard_sections")]
+    pub sections: Vec<String>,
+    /// Entries shown per section before "... and N more"
+    #[serde(default = "default_dashboard_limit")]
+    pub limit: usize,
+}
+
+impl Default for DashboardConfig {
+    fn default() -> Self {
+        Self {
+            sections: default_dashboard_sections(),
+            limit: default_dashboard_limit(),
+        }
+    }
+}
+
+fn default_dashboard_sections() -> Vec<String> {
+    ["pinned", "today", "tasks", "overdue"]
+        .map(String::from)
+        .to_vec()
+}
+
+/// Statuses used when the profile sets none
+pub fn default_statuses() -> Vec<String> {
+    ["todo", "doing", "done"].map(String::from).to_vec()
+}
+
+fn default_dashboard_limit() -> usize {
+    10
 }
 
 impl Profile {
@@ -46,14 +203,29 @@ pub fn get_current_profile_name() -> anyhow::Result<String> {
 }
 
 /// Set the current active profile name
+///
+/// The marker is written to a temporary file and renamed into place, so a
+/// concurrent `jot` never reads a half-written name; writers take a lock on
+/// `current.lock` so simultaneous switches are applied one after another.
 pub fn set_current_profile_name(name: &str) -> anyhow::Result<()> {
     let current_file = get_current_profile_file();
+    let dir = current_file
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    std::fs::create_dir_all(&dir).context("Failed to create config directory")?;
 
-    if let Some(parent) = current_file.parent() {
-        std::fs::create_dir_all(parent).context("Failed to create config directory")?;
-    }
+    let lock = std::fs::File::create(dir.join("current.lock"))
+        .context("Failed to open current profile lock")?;
+    lock.lock().context("Failed to lock current profile")?;
 
-    std::fs::write(&current_file, name).context("Failed to write current profile")?;
+    let mut temp =
+        tempfile::NamedTempFile::new_in(&dir).context("Failed to write current profile")?;
+    temp.write_all(name.as_bytes())
+        .and_then(|_| temp.as_file().sync_all())
+        .context("Failed to write current profile")?;
+    temp.persist(&current_file)
+        .context("Failed to write current profile")?;
 
     Ok(())
 }
@@ -94,6 +266,51 @@ pub fn get_profile_config_path(profile_name: &str) -> PathBuf {
         .join(format!("{}.toml", profile_name))
 }
 
+/// Get path to a profile's stored access token
+pub fn get_profile_token_path(profile_name: &str) -> PathBuf {
+    get_config_dir()
+        .join("profiles")
+        .join(format!("{}.token", profile_name))
+}
+
+/// Read a profile's stored access token, if it has one
+pub fn read_profile_token(profile_name: &str) -> anyhow::Result<Option<String>> {
+    let path = get_profile_token_path(profile_name);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let token = std::fs::read_to_string(&path).context("Failed to read access token")?;
+    let token = token.trim();
+    Ok((!token.is_empty()).then(|| token.to_string()))
+}
+
+/// Store a profile's access token, readable only by the current user
+pub fn save_profile_token(profile_name: &str, token: &str) -> anyhow::Result<()> {
+    let path = get_profile_token_path(profile_name);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create profile directory")?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(&path)
+        .context("Failed to write access token")?;
+    writeln!(file, "{}", token).context("Failed to write access token")?;
+    Ok(())
+}
+
+/// Get path to a profile's command history log (see `usage_history`)
+pub fn get_profile_usage_log_path(profile_name: &str) -> PathBuf {
+    get_data_dir()
+        .join("profiles")
+        .join(profile_name)
+        .join("usage.log")
+}
+
 /// Get path to a profile's database
 pub fn get_profile_db_path(profile_name: &str) -> PathBuf {
     get_data_dir()
@@ -102,6 +319,15 @@ pub fn get_profile_db_path(profile_name: &str) -> PathBuf {
         .join("notes.db")
 }
 
+/// Database a profile uses: its configured `db_path`, or the default location
+pub fn resolve_profile_db_path(profile_name: &str) -> anyhow::Result<PathBuf> {
+    let profile = Profile::from_path(&get_profile_config_path(profile_name))?;
+    Ok(profile
+        .and_then(|p| p.db_path)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| get_profile_db_path(profile_name)))
+}
+
 /// List all available profiles
 pub fn list_profiles() -> anyhow::Result<Vec<String>> {
     let profiles_dir = get_config_dir().join("profiles");
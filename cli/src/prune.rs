@@ -1,7 +1,11 @@
 use anyhow::{Context, Result};
 use jot_core::Note;
+use std::collections::HashSet;
 use std::io::{self, Write};
 
+use crate::editor::open_in_editor;
+use crate::utils::text::preview_line;
+
 #[derive(Debug, PartialEq)]
 pub enum PruneAction {
     Keep,
@@ -12,6 +16,8 @@ pub enum PruneAction {
 pub struct PruneDecision {
     pub note_id: String,
     pub action: PruneAction,
+    /// 1-based line in the prune file, for error messages
+    pub line: usize,
 }
 
 /// Generate the prune file content for editing
@@ -51,24 +57,11 @@ pub fn generate_prune_file(notes: &[Note]) -> String {
         };
 
         // Get first line of content for preview
-        let preview = note
-            .content
-            .lines()
-            .next()
-            .unwrap_or("")
-            .chars()
-            .take(80)
-            .collect::<String>();
-
-        let preview_suffix = if note.content.lines().count() > 1 || preview.len() >= 80 {
-            "..."
-        } else {
-            ""
-        };
+        let preview = preview_line(&note.content, 80);
 
         content.push_str(&format!(
-            "keep {} {}{} {}{}\n",
-            note.id, date_str, tags_str, preview, preview_suffix
+            "keep {} {}{} {}\n",
+            note.id, date_str, tags_str, preview
         ));
     }
 
@@ -111,7 +104,11 @@ pub fn parse_prune_file(content: &str) -> Result<Vec<PruneDecision>> {
 
         let note_id = parts[1].to_string();
 
-        decisions.push(PruneDecision { note_id, action });
+        decisions.push(PruneDecision {
+            note_id,
+            action,
+            line: line_num + 1,
+        });
     }
 
     Ok(decisions)
@@ -119,10 +116,6 @@ pub fn parse_prune_file(content: &str) -> Result<Vec<PruneDecision>> {
 
 /// Open editor with the prune file
 pub fn open_prune_editor(initial_content: &str) -> Result<String> {
-    // Read VISUAL or EDITOR environment variable
-    let editor = std::env::var("VISUAL")
-        .unwrap_or_else(|_| std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string()));
-
     // Create temporary file
     let mut tempfile = tempfile::NamedTempFile::new().context("Failed to create temporary file")?;
 
@@ -134,17 +127,7 @@ pub fn open_prune_editor(initial_content: &str) -> Result<String> {
     // Flush to ensure content is written
     tempfile.flush().context("Failed to flush temp file")?;
 
-    // Open editor
-    let mut child = std::process::Command::new(editor)
-        .arg(tempfile.path())
-        .spawn()
-        .context("Failed to open editor")?;
-
-    let status = child.wait().context("Failed to wait for editor")?;
-
-    if !status.success() {
-        return Err(anyhow::anyhow!("Editor returned non-zero exit code"));
-    }
+    open_in_editor(tempfile.path())?;
 
     // Read edited content
     let edited_content =
@@ -153,6 +136,88 @@ pub fn open_prune_editor(initial_content: &str) -> Result<String> {
     Ok(edited_content)
 }
 
+const ERROR_HEADER_START: &str = "# ===== PRUNE FILE ERROR =====";
+const ERROR_HEADER_END: &str = "# ===== Fix the issue below and save again =====";
+
+/// Check that every decision refers to one of the notes presented in this session
+///
+/// A mistyped ID could otherwise match (and delete) an unrelated note.
+pub fn validate_decisions(decisions: &[PruneDecision], notes: &[Note]) -> Result<()> {
+    let known: HashSet<&str> = notes.iter().map(|n| n.id.as_str()).collect();
+
+    for decision in decisions {
+        if !known.contains(decision.note_id.as_str()) {
+            return Err(anyhow::anyhow!(
+                "Unknown note ID '{}' at line {}. Only IDs listed in this prune file can be used",
+                decision.note_id,
+                decision.line
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Open the prune file until it parses and only references `notes`, re-opening it
+/// with the error on top after a failure
+///
+/// The user's edits are carried over between passes. Returns `None` if the user aborts.
+pub fn edit_prune_file(
+    initial_content: &str,
+    notes: &[Note],
+) -> Result<Option<Vec<PruneDecision>>> {
+    let mut current_content = initial_content.to_string();
+
+    loop {
+        let edited_content = open_prune_editor(&current_content)?;
+
+        let parsed = parse_prune_file(&edited_content).and_then(|decisions| {
+            validate_decisions(&decisions, notes)?;
+            Ok(decisions)
+        });
+
+        match parsed {
+            Ok(decisions) => return Ok(Some(decisions)),
+            Err(e) => {
+                println!("Error in prune file: {}\n", e);
+                print!("[R]etry (re-open editor with your changes) or [A]bort? (R/a): ");
+                io::stdout().flush()?;
+
+                let mut input = String::new();
+                // Treat end of input as abort so a non-interactive run can't loop forever
+                if io::stdin().read_line(&mut input)? == 0 || input.trim().eq_ignore_ascii_case("a")
+                {
+                    return Ok(None);
+                }
+
+                current_content = format_error_header(&e, &edited_content);
+            }
+        }
+    }
+}
+
+/// Prefix the prune file with the error as comments, replacing the header from an earlier pass
+fn format_error_header(error: &anyhow::Error, content: &str) -> String {
+    let content = match (
+        content.find(ERROR_HEADER_START),
+        content.find(ERROR_HEADER_END),
+    ) {
+        (Some(0), Some(end)) => content[end + ERROR_HEADER_END.len()..].trim_start_matches('\n'),
+        _ => content,
+    };
+
+    let error_lines = format!("{}", error)
+        .lines()
+        .map(|line| format!("# {}", line))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        "{}\n{}\n{}\n\n{}",
+        ERROR_HEADER_START, error_lines, ERROR_HEADER_END, content
+    )
+}
+
 /// Show summary and confirm deletion
 pub fn confirm_deletions(notes_to_delete: &[&Note]) -> Result<bool> {
     if notes_to_delete.is_empty() {
@@ -185,22 +250,9 @@ pub fn confirm_deletions(notes_to_delete: &[&Note]) -> Result<bool> {
             )
         };
 
-        let preview = note
-            .content
-            .lines()
-            .next()
-            .unwrap_or("")
-            .chars()
-            .take(60)
-            .collect::<String>();
-
-        let preview_suffix = if note.content.lines().count() > 1 || preview.len() >= 60 {
-            "..."
-        } else {
-            ""
-        };
+        let preview = preview_line(&note.content, 60);
 
-        println!("  {}{} {}{}", date_str, tags_str, preview, preview_suffix);
+        println!("  {}{} {}", date_str, tags_str, preview);
     }
 
     print!("\nProceed? [y/N]: ");
@@ -224,12 +276,46 @@ mod tests {
             content: content.to_string(),
             tags: tags.into_iter().map(|t| t.to_string()).collect(),
             subject_date: date.map(|d| d.to_string()),
+            subject_tz: None,
             created_at: 0,
             updated_at: 0,
             deleted_at: None,
+            pinned: false,
+            status: None,
+            meta: Default::default(),
+            attachments: vec![],
+            archived_at: None,
         }
     }
 
+    #[test]
+    fn test_validate_decisions_rejects_unknown_ids() {
+        let notes = vec![create_test_note("abc123", "note", vec![], None)];
+
+        let decisions = parse_prune_file("delete abc123 note").expect("valid file");
+        assert!(validate_decisions(&decisions, &notes).is_ok());
+
+        let decisions =
+            parse_prune_file("keep abc123 note\ndelete abc124 typo").expect("valid file");
+        let err = validate_decisions(&decisions, &notes).expect_err("unknown ID");
+        assert!(err
+            .to_string()
+            .contains("Unknown note ID 'abc124' at line 2"));
+    }
+
+    #[test]
+    fn test_format_error_header_replaces_previous_header() {
+        let first = format_error_header(&anyhow::anyhow!("first error"), "keep abc123 note");
+        assert!(first.starts_with(ERROR_HEADER_START));
+        assert!(first.contains("# first error"));
+
+        let second = format_error_header(&anyhow::anyhow!("second error"), &first);
+        assert!(!second.contains("first error"));
+        assert!(second.contains("# second error"));
+        assert_eq!(second.matches(ERROR_HEADER_START).count(), 1);
+        assert!(second.ends_with("\n\nkeep abc123 note"));
+    }
+
     #[test]
     fn test_generate_prune_file() {
         let notes = vec![
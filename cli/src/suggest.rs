@@ -0,0 +1,124 @@
+//! "Did you mean" hints for mistyped subcommands
+//!
+//! clap already suggests the closest subcommand at the level of the typo.
+//! This goes further: every close match is listed with its full path and
+//! aliases, and a typo at the top level also matches `note` subcommands,
+//! since several of them have top-level shortcuts (`jot ls`, `jot show`).
+
+use clap::{
+    error::{ContextKind, ContextValue, ErrorKind},
+    CommandFactory,
+};
+
+use crate::{args::CliArgs, utils::text::edit_distance};
+
+/// Largest edit distance still treated as a typo (less for short words,
+/// where two edits turn anything into anything)
+const MAX_DISTANCE: usize = 2;
+
+/// Most suggestions shown
+const MAX_SUGGESTIONS: usize = 4;
+
+struct Candidate {
+    /// Full invocation, e.g. `jot note search`
+    path: String,
+    aliases: Vec<String>,
+    about: String,
+    distance: usize,
+}
+
+/// Error text listing the closest subcommands, for an unknown-subcommand
+/// `error` raised while parsing `args`; `None` leaves the error to clap
+pub fn unknown_subcommand_hint(error: &clap::Error, args: &[String]) -> Option<String> {
+    if error.kind() != ErrorKind::InvalidSubcommand {
+        return None;
+    }
+    let Some(ContextValue::String(typo)) = error.get(ContextKind::InvalidSubcommand) else {
+        return None;
+    };
+    hint_for(typo, args)
+}
+
+/// Error text listing the subcommands close to `typo`, typed as part of
+/// `args`; `None` when nothing is close
+pub fn hint_for(typo: &str, args: &[String]) -> Option<String> {
+    // Follow the subcommands typed before the typo
+    let root = CliArgs::command();
+    let mut command = &root;
+    let mut path = vec!["jot".to_string()];
+    for arg in args.iter().skip(1).take_while(|arg| *arg != typo) {
+        if let Some(sub) = command.find_subcommand(arg) {
+            path.push(sub.get_name().to_string());
+            command = sub;
+        }
+    }
+
+    let mut candidates = candidates_for(command, &path.join(" "), typo);
+    if path.len() == 1 {
+        if let Some(note) = root.find_subcommand("note") {
+            candidates.extend(candidates_for(note, "jot note", typo));
+        }
+    }
+    if candidates.is_empty() {
+        return None;
+    }
+    candidates.sort_by(|a, b| a.distance.cmp(&b.distance).then(a.path.cmp(&b.path)));
+    candidates.truncate(MAX_SUGGESTIONS);
+
+    let labels: Vec<String> = candidates
+        .iter()
+        .map(|c| match c.aliases.is_empty() {
+            true => c.path.clone(),
+            false => format!("{} (alias: {})", c.path, c.aliases.join(", ")),
+        })
+        .collect();
+    let width = labels.iter().map(String::len).max().unwrap_or_default();
+
+    let mut hint = format!(
+        "error: unrecognized subcommand '{}'\n\n  Did you mean:\n",
+        typo
+    );
+    for (label, candidate) in labels.iter().zip(&candidates) {
+        hint.push_str(&format!(
+            "    {:<width$}  {}\n",
+            label,
+            candidate.about,
+            width = width
+        ));
+    }
+    hint.push_str(&format!(
+        "\nFor more information, try '{} --help'.\n",
+        path.join(" ")
+    ));
+    Some(hint)
+}
+
+/// Visible subcommands of `command` whose name or an alias is close to `typo`
+fn candidates_for(command: &clap::Command, path: &str, typo: &str) -> Vec<Candidate> {
+    let max_distance = (typo.len() / 3).clamp(1, MAX_DISTANCE);
+    command
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .filter_map(|sub| {
+            let aliases: Vec<String> = sub.get_visible_aliases().map(String::from).collect();
+            let distance = std::iter::once(sub.get_name())
+                .chain(aliases.iter().map(String::as_str))
+                .map(|name| distance(typo, name))
+                .min()?;
+            (distance <= max_distance).then(|| Candidate {
+                path: format!("{} {}", path, sub.get_name()),
+                aliases,
+                about: sub.get_about().map(|a| a.to_string()).unwrap_or_default(),
+                distance,
+            })
+        })
+        .collect()
+}
+
+/// How far `typo` is from `name`; a prefix of the name counts as close
+fn distance(typo: &str, name: &str) -> usize {
+    match typo.len() >= 2 && name.starts_with(typo) {
+        true => 1,
+        false => edit_distance(typo, name),
+    }
+}
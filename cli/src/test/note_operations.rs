@@ -35,21 +35,15 @@ impl TestDb {
         std::fs::create_dir_all(&profile_data_dir).unwrap();
 
         // Database will be created at the profile data location
-        let db_path = profile_data_dir.join("notes.db");
-
-        // Create a minimal profile config
-        let profile_config_path = profile_config_dir.join(format!("{}.toml", profile_name));
-        let profile = crate::profile::Profile {
-            db_path: Some(db_path.to_str().unwrap().to_string()),
-            default_tags: vec![],
-        };
-        profile.save(&profile_config_path).unwrap();
-
-        Self {
+        let db = Self {
             _temp_dir: temp_dir,
-            db_path,
+            db_path: profile_data_dir.join("notes.db"),
             profile_name,
-        }
+        };
+
+        // Create a minimal profile config
+        db.save_profile(crate::profile::Profile::default());
+        db
     }
 
     fn cmd(&self) -> Command {
@@ -65,20 +59,40 @@ impl TestDb {
         cmd
     }
 
+    /// Config file of the test profile
+    fn profile_path(&self) -> PathBuf {
+        self._temp_dir
+            .path()
+            .join("config/jot/profiles")
+            .join(format!("{}.toml", self.profile_name))
+    }
+
+    /// Replace the test profile's config, pointing it at the test database
+    /// unless it sets a `db_path` of its own
+    fn save_profile(&self, mut profile: crate::profile::Profile) {
+        profile
+            .db_path
+            .get_or_insert_with(|| self.db_path.to_str().unwrap().to_string());
+        profile.save(&self.profile_path()).unwrap();
+    }
+
+    /// Access token file of the test profile
+    fn token_path(&self) -> PathBuf {
+        self._temp_dir
+            .path()
+            .join("config/jot/profiles")
+            .join(format!("{}.token", self.profile_name))
+    }
+
+    /// Store an access token for the test profile
+    fn save_token(&self, token: &str) {
+        std::fs::write(self.token_path(), format!("{}\n", token)).unwrap();
+    }
+
     /// Get all notes from the database
     fn get_notes(&self) -> Vec<jot_core::Note> {
         let conn = jot_core::open_db(&self.db_path).unwrap();
-        let query = jot_core::SearchQuery {
-            text: None,
-            tags: vec![],
-            date_from: None,
-            date_to: None,
-            created_from: None,
-            created_to: None,
-            include_deleted: false,
-            limit: None,
-        };
-        jot_core::search_notes(&conn, &query).unwrap()
+        jot_core::search_notes(&conn, &jot_core::SearchQuery::default()).unwrap()
     }
 
     /// Add a note directly to the database for testing
@@ -257,6 +271,67 @@ fn test_note_search_with_limit() {
     assert_eq!(json.as_array().unwrap().len(), 2);
 }
 
+#[test]
+fn test_note_search_pages() {
+    let db = TestDb::new();
+    for day in 1..=5 {
+        let date = format!("2025-01-0{}", day);
+        db.add_note(&format!("day {}", day), vec![], Some(&date));
+    }
+
+    let contents = |args: &[&str]| -> Vec<String> {
+        let output = db
+            .cmd()
+            .args(["note", "search", "--output", "json"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        json.as_array()
+            .unwrap()
+            .iter()
+            .map(|note| note["content"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    assert_eq!(
+        contents(&["--page", "2", "--per-page", "2"]),
+        vec!["day 3", "day 2"]
+    );
+    assert_eq!(contents(&["--page", "3", "--per-page", "2"]), vec!["day 1"]);
+    assert!(contents(&["--page", "4", "--per-page", "2"]).is_empty());
+
+    // --all fetches in batches of --per-page but prints one JSON array
+    assert_eq!(
+        contents(&["--all", "--per-page", "2"]),
+        vec!["day 5", "day 4", "day 3", "day 2", "day 1"]
+    );
+    db.cmd()
+        .args([
+            "note",
+            "search",
+            "--all",
+            "--per-page",
+            "2",
+            "--output",
+            "id",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::function(|out: &str| out.lines().count() == 5));
+    db.cmd()
+        .args(["note", "search", "nothing-matches", "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No notes found"));
+
+    db.cmd()
+        .args(["note", "search", "--all", "--limit", "2"])
+        .assert()
+        .failure();
+}
+
 #[test]
 fn test_note_last() {
     let db = TestDb::new();
@@ -282,6 +357,72 @@ fn test_note_last() {
         .stdout(predicate::str::contains("latest note"));
 }
 
+#[test]
+fn test_note_last_count() {
+    let db = TestDb::new();
+
+    db.add_note("work one", vec!["work"], Some("2025-01-01"));
+    db.add_note("home one", vec!["home"], Some("2025-01-04"));
+    db.add_note("work two", vec!["work"], Some("2025-01-02"));
+    db.add_note("work three", vec!["work"], Some("2025-01-03"));
+
+    let output = db
+        .cmd()
+        .args([
+            "note", "last", "-n", "2", "--tag", "work", "--output", "json",
+        ])
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let notes = json.as_array().unwrap();
+    assert_eq!(notes.len(), 2);
+    assert_eq!(notes[0]["content"], "work three");
+    assert_eq!(notes[1]["content"], "work two");
+}
+
+#[test]
+fn test_note_field_prints_raw_value() {
+    let db = TestDb::new();
+
+    let older = db.add_note("older", vec!["home"], Some("2025-01-01"));
+    let id = db.add_note(
+        "line one\nline \"two\"",
+        vec!["work", "urgent"],
+        Some("2025-01-02"),
+    );
+
+    let field = |args: &[&str]| {
+        let output = db.cmd().args(args).output().unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(
+        field(&["note", "last", "--field", "content"]),
+        "line one\nline \"two\"\n"
+    );
+    assert_eq!(
+        field(&["note", "last", "--field", "id"]),
+        format!("{}\n", id)
+    );
+    assert_eq!(
+        field(&["note", "last", "--tag", "home", "--field", "date"]),
+        "2025-01-01\n"
+    );
+    assert_eq!(
+        field(&["note", "show", &older, "--field", "tags"]),
+        "home\n"
+    );
+    assert_eq!(field(&["note", "show", "--field", "tags"]), "work,urgent\n");
+
+    db.cmd()
+        .args(["note", "last", "--tag", "missing", "--field", "id"])
+        .assert()
+        .failure();
+}
+
 #[test]
 fn test_note_delete_latest() {
     let db = TestDb::new();
@@ -791,6 +932,26 @@ fn test_note_show_id_output() {
         .stdout(predicate::str::is_match(format!("^{}\\s*$", note_id)).unwrap());
 }
 
+#[test]
+fn test_note_show_markdown_output() {
+    let db = TestDb::new();
+    let id = db.add_note("# Plan\n\n- ship it", vec!["work"], Some("2025-01-02"));
+
+    let output = db
+        .cmd()
+        .args(["note", "show", &id, "--output", "markdown"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with(&format!(
+        "+++\nid = \"{}\"\ntags = [\"work\"]\ndate = \"2025-01-02\"\n",
+        id
+    )));
+    assert!(stdout.contains("created_at = "));
+    assert!(stdout.ends_with("+++\n\n# Plan\n\n- ship it\n"));
+}
+
 #[test]
 fn test_note_show_nonexistent() {
     let db = TestDb::new();
@@ -832,3 +993,3546 @@ fn test_show_alias() {
         .success()
         .stdout(predicate::str::contains("Test for alias"));
 }
+
+#[test]
+fn test_db_migrate_status_and_apply() {
+    let db = TestDb::new();
+
+    // Simulate a database created by an older version of jot
+    let conn = rusqlite::Connection::open(&db.db_path).unwrap();
+    conn.execute_batch(jot_core::schema::SCHEMA_V1).unwrap();
+    drop(conn);
+
+    db.cmd()
+        .args(["db", "migrate", "--status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Schema version: 1"))
+        .stdout(predicate::str::contains("v2:"))
+        .stdout(predicate::str::contains("v3:"));
+
+    db.cmd()
+        .args(["db", "migrate"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("up to date"));
+
+    assert!(
+        jot_core::db::migration_backup_path(&db.db_path, jot_core::schema::LATEST_VERSION).exists()
+    );
+
+    db.cmd()
+        .args(["db", "migrate", "--status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Database is up to date."));
+}
+
+#[test]
+fn test_newer_schema_is_refused() {
+    let db = TestDb::new();
+    db.add_note("existing", vec![], None);
+
+    let conn = rusqlite::Connection::open(&db.db_path).unwrap();
+    jot_core::schema::set_schema_version(&conn, jot_core::schema::LATEST_VERSION + 1).unwrap();
+    drop(conn);
+
+    db.cmd()
+        .args(["note", "search"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "created by a newer version of jot",
+        ));
+}
+
+#[test]
+fn test_ephemeral_does_not_touch_profile_db() {
+    let db = TestDb::new();
+
+    db.cmd()
+        .args(["--ephemeral", "note", "add", "scratch", "note"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Note added successfully"));
+
+    db.cmd()
+        .args(["note", "search", "--ephemeral"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No notes found"));
+
+    assert!(!db.db_path.exists());
+}
+
+#[test]
+fn test_profile_note_limits() {
+    let db = TestDb::new();
+
+    db.save_profile(crate::profile::Profile {
+        max_content_bytes: Some(10),
+        max_tags: Some(2),
+        ..Default::default()
+    });
+
+    db.cmd()
+        .args([
+            "note", "add", "--tag", "a", "--tag", "b", "--tag", "c", "tagged",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("exceeds the limit of 2"));
+
+    db.cmd()
+        .args(["note", "add", "this note is far too long"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("file attachment"));
+
+    assert!(db.get_notes().is_empty());
+}
+
+#[test]
+fn test_note_dedupe_merge() {
+    let db = TestDb::new();
+    db.add_note("same content", vec!["a"], Some("2024-03-01"));
+    db.add_note("same content", vec!["b"], Some("2024-03-02"));
+    db.add_note("other", vec![], Some("2024-03-03"));
+
+    db.cmd()
+        .args(["note", "dedupe"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"same content\" (2 copies)"));
+    assert_eq!(db.get_notes().len(), 3);
+
+    db.cmd()
+        .args(["note", "dedupe", "--merge", "-y"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deleted 1 duplicate note(s)."));
+
+    let notes = db.get_notes();
+    assert_eq!(notes.len(), 2);
+    let kept = notes.iter().find(|n| n.content == "same content").unwrap();
+    let mut tags = kept.tags.clone();
+    tags.sort();
+    assert_eq!(tags, vec!["a", "b"]);
+
+    db.cmd()
+        .args(["note", "dedupe"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No duplicate notes found."));
+}
+
+#[test]
+fn test_dev_seed() {
+    let db = TestDb::new();
+
+    db.cmd()
+        .args(["dev", "seed", "--count", "25"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Seeded 25 notes"));
+
+    assert_eq!(db.get_notes().len(), 25);
+}
+
+#[test]
+fn test_note_delete_preview_multibyte_content() {
+    let db = TestDb::new();
+    let id = db.add_note(&"é".repeat(70), vec![], None);
+
+    db.cmd()
+        .args(["note", "delete", "--step", &id])
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Delete note \"{}...\"?",
+            "é".repeat(60)
+        )))
+        .stdout(predicate::str::contains("Skipped deleting note"));
+}
+
+#[test]
+fn test_note_delete_batch_confirmation() {
+    let db = TestDb::new();
+    let first = db.add_note("First to go", vec![], None);
+    let second = db.add_note("Second to go", vec![], None);
+
+    // A single prompt covers every note; declining keeps them all
+    db.cmd()
+        .args(["note", "delete", &first, &second])
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("About to delete 2 note(s):"))
+        .stdout(predicate::str::contains("First to go"))
+        .stdout(predicate::str::contains("Second to go"))
+        .stdout(predicate::str::contains("Aborted. No notes were deleted."));
+    assert_eq!(db.get_notes().len(), 2);
+
+    db.cmd()
+        .args(["note", "delete", &first, &second])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Deleted note {}", first)))
+        .stdout(predicate::str::contains(format!("Deleted note {}", second)));
+    assert!(db.get_notes().is_empty());
+}
+
+#[test]
+fn test_note_delete_dry_run() {
+    let db = TestDb::new();
+    let id = db.add_note("Keep me around", vec![], None);
+
+    db.cmd()
+        .args(["note", "delete", "--dry-run", "--yes", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would delete 1 note(s):"))
+        .stdout(predicate::str::contains("Keep me around"))
+        .stdout(predicate::str::contains("Deleted note").not());
+    assert_eq!(db.get_notes().len(), 1);
+}
+
+#[test]
+fn test_json_error_output() {
+    let db = TestDb::new();
+
+    let output = db
+        .cmd()
+        .args(["--output", "json", "note", "show", "01NOSUCHNOTE"])
+        .assert()
+        .failure()
+        .get_output()
+        .stderr
+        .clone();
+    let error: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(error["error"]["kind"], "not_found");
+    assert!(error["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("01NOSUCHNOTE"));
+
+    // A subcommand asked for JSON output reports errors the same way
+    db.cmd()
+        .args(["note", "show", "--output", "json", "01NOSUCHNOTE"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(r#""kind":"not_found""#));
+}
+
+#[test]
+fn test_shell_alias_generation() {
+    let db = TestDb::new();
+
+    db.cmd()
+        .args(["alias", "--shell", "zsh"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("alias jt='jot down'"))
+        .stdout(predicate::str::contains("alias jl='jot ls --date today'"));
+
+    // Profile entries replace or remove the built-in suggestions
+    db.save_profile(crate::profile::Profile {
+        shell_aliases: [
+            ("jl".to_string(), "ls --date yesterday".to_string()),
+            ("jt".to_string(), String::new()),
+        ]
+        .into(),
+        ..Default::default()
+    });
+
+    db.cmd()
+        .args(["alias", "--shell", "fish"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "alias jl 'jot ls --date yesterday'",
+        ))
+        .stdout(predicate::str::contains("alias jt ").not());
+
+    // Names end up unquoted in shell code
+    db.save_profile(crate::profile::Profile {
+        shell_aliases: [("x; rm -rf ~".to_string(), "ls".to_string())].into(),
+        ..Default::default()
+    });
+
+    db.cmd()
+        .args(["alias", "--shell", "bash"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains("Invalid shell alias name"));
+}
+
+#[test]
+fn test_server_ping_requires_server_url() {
+    let db = TestDb::new();
+
+    db.cmd()
+        .args(["server", "ping"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No server configured"));
+}
+
+/// Minimal HTTP/1.1 server answering `connections` requests with
+/// `handler(request head and body)`
+fn serve_http<F>(connections: usize, handler: F) -> (String, std::thread::JoinHandle<()>)
+where
+    F: Fn(&str) -> (&'static str, Vec<(&'static str, String)>, Vec<u8>) + Send + 'static,
+{
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        for stream in listener.incoming().take(connections) {
+            let mut stream = stream.unwrap();
+            let mut request = String::new();
+            let mut reader = BufReader::new(&stream);
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                request.push_str(&line);
+            }
+            let length = request
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().ok())?
+                })
+                .unwrap_or_default();
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            request.push_str("\r\n");
+            request.push_str(&String::from_utf8_lossy(&body));
+
+            let (status, headers, body) = handler(&request);
+            let mut head = format!("HTTP/1.1 {}\r\n", status);
+            for (name, value) in headers {
+                head.push_str(&format!("{}: {}\r\n", name, value));
+            }
+            head.push_str(&format!(
+                "content-length: {}\r\nconnection: close\r\n\r\n",
+                body.len()
+            ));
+            stream.write_all(head.as_bytes()).unwrap();
+            stream.write_all(&body).unwrap();
+        }
+    });
+    (url, server)
+}
+
+/// `GET /version` body of a compatible server
+fn version_body() -> Vec<u8> {
+    format!(
+        r#"{{"version":"9.9.9","sync_protocols":[{}],"features":{{"attachments":false,"sharing":true,"e2ee":false}}}}"#,
+        jot_core::SYNC_PROTOCOL_VERSION
+    )
+    .into_bytes()
+}
+
+#[test]
+fn test_sync_pushes_and_pulls_changes() {
+    let db = TestDb::new();
+    db.save_token("secret-token");
+    let local_id = db.add_note("written offline", vec![], None);
+
+    // /version and /sync, twice
+    let (url, server) = serve_http(4, |request| {
+        if request.starts_with("GET /version") {
+            return ("200 OK", vec![], version_body());
+        }
+        assert!(request.starts_with("POST /sync"));
+        assert!(request.contains("Bearer secret-token"));
+        let body = if request.contains("written offline") {
+            r#"{"notes":[{"id":"01ARZ3NDEKTSV4RRFFQ69G5FAV","content":"from the phone","tags":["mobile"],"date":null,"created_at":1000,"updated_at":1000,"deleted_at":null}],"conflicts":[]}"#
+        } else {
+            r#"{"notes":[],"conflicts":[]}"#
+        };
+        (
+            "200 OK",
+            vec![("content-type", "application/json".to_string())],
+            body.as_bytes().to_vec(),
+        )
+    });
+
+    let output = db
+        .cmd()
+        .args(["sync", "--url", &url, "--output", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["pushed"], 1);
+    assert_eq!(report["pulled"], 1);
+    assert_eq!(report["conflicts"], 0);
+    assert_eq!(report["errors"], serde_json::json!([]));
+    for key in ["pushed", "pulled", "conflicts", "duration_ms", "errors"] {
+        assert!(report.get(key).is_some(), "sync report lacks {}", key);
+    }
+    assert!(report["duration_ms"].is_u64());
+
+    let notes = db.get_notes();
+    assert!(notes.iter().any(|n| n.id == local_id));
+    let pulled = notes
+        .iter()
+        .find(|n| n.id == "01ARZ3NDEKTSV4RRFFQ69G5FAV")
+        .unwrap();
+    assert_eq!(pulled.content, "from the phone");
+    assert_eq!(pulled.tags, vec!["mobile"]);
+
+    // Everything was sent, so the next sync has nothing to push
+    db.cmd()
+        .args(["sync", "--url", &url])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("pushed 0, pulled 0 note(s)"));
+    server.join().unwrap();
+}
+
+#[test]
+fn test_sync_stores_results_all_or_nothing() {
+    let db = TestDb::new();
+    db.save_token("secret-token");
+    let local_id = db.add_note("written offline", vec![], None);
+
+    // Storing last_sync fails once the pulled note is already written
+    let conn = rusqlite::Connection::open(&db.db_path).unwrap();
+    conn.execute_batch(
+        "CREATE TRIGGER fail_last_sync BEFORE INSERT ON sync_state
+         BEGIN SELECT RAISE(ABORT, 'disk trouble'); END;",
+    )
+    .unwrap();
+
+    // /version and /sync, twice
+    let (url, server) = serve_http(4, |request| {
+        if request.starts_with("GET /version") {
+            return ("200 OK", vec![], version_body());
+        }
+        let body = r#"{"notes":[{"id":"01ARZ3NDEKTSV4RRFFQ69G5FAV","content":"from the phone","tags":[],"date":null,"created_at":1000,"updated_at":1000,"deleted_at":null}],"conflicts":[]}"#;
+        (
+            "200 OK",
+            vec![("content-type", "application/json".to_string())],
+            body.as_bytes().to_vec(),
+        )
+    });
+
+    db.cmd()
+        .args(["sync", "--url", &url])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to store sync results"));
+    let notes = db.get_notes();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].id, local_id);
+    assert_eq!(jot_core::get_sync_state(&conn, "last_sync").unwrap(), None);
+
+    // Nothing was recorded, so the retry sends the local note again
+    conn.execute_batch("DROP TRIGGER fail_last_sync;").unwrap();
+    db.cmd()
+        .args(["sync", "--url", &url])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("pushed 1, pulled 1 note(s)"));
+    assert_eq!(db.get_notes().len(), 2);
+    server.join().unwrap();
+}
+
+#[test]
+fn test_sync_warns_near_storage_quota() {
+    let db = TestDb::new();
+    db.save_token("secret-token");
+
+    let (url, server) = serve_http(4, |request| {
+        if request.starts_with("GET /version") {
+            return ("200 OK", vec![], version_body());
+        }
+        let body = r#"{"notes":[],"conflicts":[],"usage":{"note_count":3,"db_size":900,"attachment_bytes":50,"used_bytes":950,"quota_bytes":1000}}"#;
+        (
+            "200 OK",
+            vec![("content-type", "application/json".to_string())],
+            body.as_bytes().to_vec(),
+        )
+    });
+
+    db.cmd()
+        .args(["sync", "--url", &url])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Warning: this account uses 950 B of its 1000 B storage quota on the server (95%)",
+        ));
+
+    let output = db
+        .cmd()
+        .args(["sync", "--url", &url, "--output", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["warnings"].as_array().unwrap().len(), 1);
+    server.join().unwrap();
+}
+
+#[test]
+fn test_sync_refreshes_expiring_token() {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    let jwt = |exp: i64| {
+        let claims = format!(r#"{{"sub":"user-1","exp":{}}}"#, exp);
+        format!("header.{}.signature", URL_SAFE_NO_PAD.encode(claims))
+    };
+    let now = chrono::Utc::now().timestamp();
+    let expiring = jwt(now + 60);
+    let fresh = jwt(now + 7 * 24 * 60 * 60);
+
+    let db = TestDb::new();
+    db.save_token(&expiring);
+
+    // /version, /auth/refresh and /sync, then /version and /sync with the
+    // fresh token, which isn't refreshed again
+    let (url, server) = serve_http(5, {
+        let (expiring, fresh) = (expiring.clone(), fresh.clone());
+        move |request| {
+            if request.starts_with("GET /version") {
+                return ("200 OK", vec![], version_body());
+            }
+            let body = if request.starts_with("POST /auth/refresh") {
+                assert!(request.contains(&format!("Bearer {}", expiring)));
+                format!(r#"{{"token":"{}"}}"#, fresh)
+            } else {
+                assert!(request.starts_with("POST /sync"));
+                assert!(request.contains(&format!("Bearer {}", fresh)));
+                r#"{"notes":[],"conflicts":[]}"#.to_string()
+            };
+            (
+                "200 OK",
+                vec![("content-type", "application/json".to_string())],
+                body.into_bytes(),
+            )
+        }
+    });
+
+    for _ in 0..2 {
+        db.cmd().args(["sync", "--url", &url]).assert().success();
+    }
+    server.join().unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(db.token_path()).unwrap().trim(),
+        fresh
+    );
+}
+
+#[test]
+fn test_sync_asks_to_log_in_when_token_expired() {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    let claims = format!(
+        r#"{{"sub":"user-1","exp":{}}}"#,
+        chrono::Utc::now().timestamp() - 60
+    );
+    let db = TestDb::new();
+    db.save_token(&format!(
+        "header.{}.signature",
+        URL_SAFE_NO_PAD.encode(claims)
+    ));
+
+    // /version, then /auth/refresh rejects the expired token; /sync is never
+    // reached
+    let (url, server) = serve_http(2, |request| {
+        if request.starts_with("GET /version") {
+            return ("200 OK", vec![], version_body());
+        }
+        assert!(request.starts_with("POST /auth/refresh"));
+        ("401 Unauthorized", vec![], Vec::new())
+    });
+
+    db.cmd()
+        .args(["sync", "--url", &url])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("run `jot auth login`"));
+    server.join().unwrap();
+}
+
+#[test]
+fn test_sync_daemon_syncs_local_changes() {
+    let db = TestDb::new();
+    db.save_token("secret-token");
+    db.add_note("written offline", vec![], None);
+
+    // Two syncs: one at startup, one after the next local change
+    let (requests, received) = std::sync::mpsc::channel();
+    let requests = std::sync::Mutex::new(requests);
+    let (url, server) = serve_http(4, move |request| {
+        if request.starts_with("GET /version") {
+            return ("200 OK", vec![], version_body());
+        }
+        requests.lock().unwrap().send(request.to_string()).unwrap();
+        ("200 OK", vec![], br#"{"notes":[],"conflicts":[]}"#.to_vec())
+    });
+
+    let temp = db._temp_dir.path();
+    let mut daemon = std::process::Command::new(assert_cmd::cargo::cargo_bin("jot"))
+        .env("XDG_CONFIG_HOME", temp.join("config"))
+        .env("XDG_DATA_HOME", temp.join("data"))
+        .env("JOT_PROFILE", &db.profile_name)
+        .args(["sync", "--url", &url, "daemon", "--interval", "1h"])
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let timeout = std::time::Duration::from_secs(30);
+    let first = received.recv_timeout(timeout);
+    db.add_note("written while running", vec![], None);
+    let second = received.recv_timeout(timeout);
+
+    // Only one daemon per profile
+    let second_daemon = db.cmd().args(["sync", "daemon"]).assert().failure();
+    daemon.kill().unwrap();
+    daemon.wait().unwrap();
+    server.join().unwrap();
+
+    let (first, second) = (first.unwrap(), second.unwrap());
+    assert!(first.contains("written offline"));
+    assert!(second.contains("written while running"));
+    assert!(!second.contains("written offline"));
+    second_daemon.stderr(predicate::str::contains("A sync daemon is already running"));
+}
+
+#[test]
+fn test_auth_register_stores_token() {
+    let db = TestDb::new();
+
+    let (url, server) = serve_http(2, |request| {
+        assert!(request.starts_with("POST /auth/register"));
+        if request.contains("taken@example.com") {
+            return (
+                "400 Bad Request",
+                vec![("content-type", "application/json".to_string())],
+                br#"{"error":"Invalid input: An account with email 'taken@example.com' already exists"}"#.to_vec(),
+            );
+        }
+        assert!(request.contains(r#""name":"ada""#));
+        assert!(request.contains(r#""password":"correct horse""#));
+        (
+            "201 Created",
+            vec![("content-type", "application/json".to_string())],
+            br#"{"token":"fresh-token"}"#.to_vec(),
+        )
+    });
+
+    db.cmd()
+        .args(["auth", "register", "--url", &url, "--password-stdin"])
+        .args(["--email", "taken@example.com"])
+        .write_stdin("correct horse\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+
+    db.cmd()
+        .args(["auth", "register", "--url", &url, "--password-stdin"])
+        .args(["--email", "ada@example.com"])
+        .write_stdin("correct horse\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Registered ada@example.com"));
+    server.join().unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(db.token_path()).unwrap().trim(),
+        "fresh-token"
+    );
+}
+
+#[test]
+fn test_auth_login_stores_token() {
+    let db = TestDb::new();
+    db.save_profile(crate::profile::Profile {
+        server_cert_sha256: Some("AB:CD".to_string()),
+        server_cert_url: Some("https://jot.example.com".to_string()),
+        ..Default::default()
+    });
+
+    let (url, server) = serve_http(2, |request| {
+        assert!(request.starts_with("POST /auth/login"));
+        assert!(request.contains(r#""username":"ada@example.com""#));
+        (
+            "200 OK",
+            vec![("content-type", "application/json".to_string())],
+            br#"{"token":"login-token"}"#.to_vec(),
+        )
+    });
+
+    // Pinning only makes sense for a TLS server
+    db.cmd()
+        .args(["auth", "login", "--url", &url, "--password-stdin"])
+        .args(["--email", "ada@example.com", "--insecure-pin"])
+        .write_stdin("correct horse\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("needs an https:// server"));
+
+    // The fingerprint has to be confirmed, which needs a terminal
+    db.cmd()
+        .args([
+            "auth",
+            "login",
+            "--url",
+            "https://127.0.0.1:9",
+            "--password-stdin",
+        ])
+        .args(["--email", "ada@example.com", "--insecure-pin"])
+        .write_stdin("correct horse\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("use --fingerprint"));
+
+    // Logging in to another server points the profile at it but keeps the
+    // pin of the one it was recorded for
+    db.cmd()
+        .args(["auth", "login", "--url", &url, "--password-stdin"])
+        .args(["--email", "ada@example.com"])
+        .write_stdin("correct horse\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Logged in as ada@example.com"))
+        .stdout(predicate::str::contains("Removed the pinned certificate").not());
+    assert_eq!(
+        std::fs::read_to_string(db.token_path()).unwrap().trim(),
+        "login-token"
+    );
+    let profile = std::fs::read_to_string(db.profile_path()).unwrap();
+    assert!(
+        profile.contains(&format!("server_url = \"{}\"", url)),
+        "{}",
+        profile
+    );
+    assert!(profile.contains("server_cert_sha256"), "{}", profile);
+
+    // A login without pinning drops the pin of the same server
+    db.save_profile(crate::profile::Profile {
+        server_cert_sha256: Some("AB:CD".to_string()),
+        server_cert_url: Some(url.clone()),
+        ..Default::default()
+    });
+    db.cmd()
+        .args(["auth", "login", "--url", &url, "--password-stdin"])
+        .args(["--email", "ada@example.com"])
+        .write_stdin("correct horse\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed the pinned certificate"));
+    server.join().unwrap();
+
+    let profile = std::fs::read_to_string(db.profile_path()).unwrap();
+    assert!(!profile.contains("server_cert"), "{}", profile);
+    assert!(
+        profile.contains(&format!("server_url = \"{}\"", url)),
+        "{}",
+        profile
+    );
+}
+
+#[test]
+fn test_server_ping_reports_version_and_credential() {
+    let db = TestDb::new();
+    db.save_token("secret-token");
+    // Pinned for the profile's server, so it doesn't apply to `--url`
+    db.save_profile(crate::profile::Profile {
+        server_url: Some("https://jot.example.com".to_string()),
+        server_cert_sha256: Some("AB:CD".to_string()),
+        ..Default::default()
+    });
+
+    // One connection each for /health/ping, /version and /health/auth
+    let (url, server) = serve_http(3, |request| {
+        let authorized = request.contains("Bearer secret-token");
+        let status = if !request.contains("/health/auth") || authorized {
+            "200 OK"
+        } else {
+            "403 Forbidden"
+        };
+        let body = if request.starts_with("GET /version") {
+            version_body()
+        } else {
+            vec![]
+        };
+        (status, vec![("x-jot-version", "9.9.9".to_string())], body)
+    });
+
+    db.cmd()
+        .args(["server", "ping", "--url", &url])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Version:    9.9.9"))
+        .stdout(predicate::str::contains("TLS:        not used"))
+        .stdout(predicate::str::contains("Features:   sharing"))
+        .stdout(predicate::str::contains("Protocol:   compatible"))
+        .stdout(predicate::str::contains("Credential: accepted"));
+    server.join().unwrap();
+}
+
+#[test]
+fn test_server_bootstrap_imports_snapshot() {
+    use std::io::Write;
+
+    let db = TestDb::new();
+    db.save_token("secret-token");
+
+    // The server's copy, gzip-compressed like GET /sync/snapshot
+    let remote_dir = TempDir::new().unwrap();
+    let remote_path = remote_dir.path().join("remote.db");
+    let remote = jot_core::open_db(&remote_path).unwrap();
+    jot_core::create_note(&remote, "from the server", vec!["synced".to_string()], None).unwrap();
+    let deleted = jot_core::create_note(&remote, "deleted remotely", vec![], None).unwrap();
+    jot_core::soft_delete_note(&remote, &deleted.id).unwrap();
+    remote
+        .pragma_update(None, "journal_mode", "DELETE")
+        .unwrap();
+    drop(remote);
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder
+        .write_all(&std::fs::read(&remote_path).unwrap())
+        .unwrap();
+    let snapshot = encoder.finish().unwrap();
+
+    // /version, then /sync/snapshot
+    let (url, server) = serve_http(2, move |request| {
+        if request.starts_with("GET /version") {
+            ("200 OK", vec![], version_body())
+        } else if request.contains("Bearer secret-token") {
+            (
+                "200 OK",
+                vec![("x-jot-snapshot-at", "12345".to_string())],
+                snapshot.clone(),
+            )
+        } else {
+            ("403 Forbidden", vec![], vec![])
+        }
+    });
+
+    db.cmd()
+        .args(["server", "bootstrap", "--url", &url])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 2 note(s)"));
+    server.join().unwrap();
+
+    let notes = db.get_notes();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].content, "from the server");
+    let conn = jot_core::open_db(&db.db_path).unwrap();
+    assert_eq!(
+        jot_core::get_sync_state(&conn, "last_sync").unwrap(),
+        Some("12345".to_string())
+    );
+
+    // A device with notes of its own must use a regular sync instead
+    db.cmd()
+        .args(["server", "bootstrap", "--url", &url])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("fresh device"));
+}
+
+#[test]
+fn test_db_compact_purges_old_tombstones() {
+    let db = TestDb::new();
+    db.add_note("keep me", vec![], None);
+    db.add_note("old and deleted", vec![], None);
+    db.add_note("recently deleted", vec![], None);
+
+    let conn = jot_core::open_db(&db.db_path).unwrap();
+    let long_ago = (chrono::Utc::now() - chrono::Duration::days(100)).timestamp_millis();
+    conn.execute(
+        "UPDATE notes SET deleted_at = ?1, updated_at = ?1 WHERE content = 'old and deleted'",
+        [long_ago],
+    )
+    .unwrap();
+    let now = chrono::Utc::now().timestamp_millis();
+    conn.execute(
+        "UPDATE notes SET deleted_at = ?1, updated_at = ?1 WHERE content = 'recently deleted'",
+        [now],
+    )
+    .unwrap();
+
+    // Deletions the server hasn't seen yet are never purged
+    jot_core::set_sync_state(&conn, "last_sync", &(long_ago - 1).to_string()).unwrap();
+    drop(conn);
+    db.cmd()
+        .args(["db", "compact", "--keep", "30d"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 0 deleted note(s)"));
+
+    let conn = jot_core::open_db(&db.db_path).unwrap();
+    jot_core::set_sync_state(&conn, "last_sync", &now.to_string()).unwrap();
+    drop(conn);
+    db.cmd()
+        .args(["db", "compact", "--keep", "30d"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Removed 1 deleted note(s) older than 30d",
+        ));
+
+    let conn = jot_core::open_db(&db.db_path).unwrap();
+    let remaining: i64 = conn
+        .query_row("SELECT COUNT(*) FROM notes", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(remaining, 2);
+}
+
+#[test]
+fn test_db_maintain_recompress() {
+    let db = TestDb::new();
+    let id = db.add_note("placeholder", vec![], None);
+    let body = "2024-03-16 12:00:00 INFO request handled\n".repeat(500);
+
+    // Written as-is, the way notes were stored before compression
+    let conn = jot_core::open_db(&db.db_path).unwrap();
+    conn.execute(
+        "UPDATE notes SET content = ?1 WHERE id = ?2",
+        [body.as_str(), id.as_str()],
+    )
+    .unwrap();
+    drop(conn);
+
+    db.cmd()
+        .args(["db", "maintain", "--recompress"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Compressed 1 note(s)"));
+
+    let note = jot_core::get_note_by_id(&jot_core::open_db(&db.db_path).unwrap(), &id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(note.content, body);
+
+    db.cmd().args(["db", "maintain"]).assert().failure();
+}
+
+#[test]
+fn test_db_reindex_repairs_search_index() {
+    let db = TestDb::new();
+    let id = db.add_note("dropped from the index", vec![], None);
+    db.add_note("still indexed", vec![], None);
+
+    // As if the database had been edited around the triggers
+    let conn = jot_core::open_db(&db.db_path).unwrap();
+    conn.execute(
+        "DELETE FROM notes_fts WHERE rowid = (SELECT rowid FROM notes WHERE id = ?1)",
+        [id.as_str()],
+    )
+    .unwrap();
+    drop(conn);
+
+    db.cmd()
+        .args(["db", "reindex", "--check"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "2 note(s), 1 index row(s); 1 missing, 0 stale, 0 orphaned",
+        ));
+
+    db.cmd()
+        .args(["db", "reindex"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Search index differed from the notes",
+        ))
+        .stdout(predicate::str::contains(
+            "Rebuilt the search index of 2 note(s).",
+        ));
+
+    db.cmd()
+        .args(["db", "reindex", "--check"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Search index matches all 2 note(s).",
+        ));
+}
+
+#[test]
+fn test_local_writes_are_queued_for_sync() {
+    let db = TestDb::new();
+    for content in ["first", "second"] {
+        db.cmd().args(["note", "add", content]).assert().success();
+    }
+    let first = db
+        .get_notes()
+        .into_iter()
+        .find(|note| note.content == "first")
+        .unwrap()
+        .id;
+
+    db.cmd()
+        .args(["note", "delete", "--yes", &first])
+        .assert()
+        .success();
+
+    let conn = rusqlite::Connection::open(&db.db_path).unwrap();
+    let queued = jot_core::sync_queue::queued_ids(&conn).unwrap();
+    assert_eq!(queued.len(), 2);
+    assert!(queued.contains(&first));
+}
+
+#[test]
+fn test_note_show_uses_profile_timezone_and_format() {
+    let db = TestDb::new();
+    // 2024-03-16 22:30:00 UTC
+    let created = 1_710_628_200_000;
+
+    let conn = jot_core::open_db(&db.db_path).unwrap();
+    let note = jot_core::NoteBuilder::new("late night thought")
+        .created_at(created)
+        .updated_at(created)
+        .insert(&conn)
+        .unwrap();
+    drop(conn);
+
+    db.save_profile(crate::profile::Profile {
+        timezone: Some("Asia/Tokyo".to_string()),
+        date_format: Some("%d.%m.%Y %H:%M".to_string()),
+        ..Default::default()
+    });
+
+    db.cmd()
+        .args(["note", "show", "--output", "plain", &note.id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created: 17.03.2024 07:30"));
+
+    // Listings show a note without a subject date by its creation time
+    db.cmd()
+        .args(["ls", "--output", "plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[17.03.2024 07:30] late night thought",
+        ));
+
+    db.save_profile(crate::profile::Profile {
+        timezone: Some("Nowhere/Special".to_string()),
+        ..Default::default()
+    });
+
+    db.cmd()
+        .args(["note", "show", &note.id])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unknown timezone 'Nowhere/Special'",
+        ));
+}
+
+#[test]
+fn test_today_follows_profile_timezone() {
+    let db = TestDb::new();
+    // UTC+14, a day ahead of most system clocks for part of the day
+    db.save_profile(crate::profile::Profile {
+        timezone: Some("Pacific/Kiritimati".to_string()),
+        ..Default::default()
+    });
+    let kiritimati = chrono::FixedOffset::east_opt(14 * 60 * 60).unwrap();
+    let today = chrono::Utc::now()
+        .with_timezone(&kiritimati)
+        .date_naive()
+        .to_string();
+
+    db.cmd()
+        .args(["note", "add", "island time"])
+        .assert()
+        .success();
+
+    let output = db
+        .cmd()
+        .args(["ls", "--date", "today", "--output", "json"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let notes = json.as_array().unwrap();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0]["subject_date"], today.as_str());
+    assert_eq!(notes[0]["subject_tz"], "+14:00");
+}
+
+#[test]
+fn test_profile_confirmation_defaults() {
+    let db = TestDb::new();
+    let first = db.add_note("first", vec![], None);
+    let second = db.add_note("second", vec![], None);
+
+    db.save_profile(crate::profile::Profile {
+        confirm_delete: Some(false),
+        ..Default::default()
+    });
+
+    // No prompt: deleted without reading stdin
+    db.cmd()
+        .args(["note", "delete", &first])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Deleted note {}", first)))
+        .stdout(predicate::str::contains("[y/N]").not());
+
+    // --confirm brings the prompt back for one invocation
+    db.cmd()
+        .args(["note", "delete", "--confirm", &second])
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Aborted. No notes were deleted."));
+
+    assert_eq!(db.get_notes().len(), 1);
+}
+
+#[test]
+fn test_note_ids_are_normalized_and_suggested() {
+    let db = TestDb::new();
+    let id = db.add_note("findable", vec![], None);
+
+    // Lowercase, quoted and padded IDs still resolve
+    db.cmd()
+        .args(["note", "show", &format!(" '{}' ", id.to_lowercase())])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("findable"));
+
+    // A typo in the last character suggests the real ID
+    let mut typo = id[..id.len() - 1].to_string();
+    typo.push(if id.ends_with('0') { '1' } else { '0' });
+    db.cmd()
+        .args(["note", "append", &typo, "more"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Note with ID '{}' not found. Did you mean: {}?",
+            typo, id
+        )));
+
+    // Deleting by lowercase ID removes the note
+    db.cmd()
+        .args(["note", "delete", "--yes", &id.to_lowercase()])
+        .assert()
+        .success();
+    assert!(db.get_notes().is_empty());
+}
+
+#[test]
+fn test_saved_searches() {
+    let db = TestDb::new();
+    db.add_note(
+        "standup: shipped the parser",
+        vec!["work"],
+        Some("2024-03-16"),
+    );
+    db.add_note("standup: groceries", vec!["home"], Some("2024-03-16"));
+    db.add_note("planning", vec!["work", "draft"], Some("2024-03-17"));
+
+    // Keys after the searches table must still serialize to valid TOML
+    db.save_profile(crate::profile::Profile {
+        timezone: Some("UTC".to_string()),
+        ..Default::default()
+    });
+
+    db.cmd()
+        .args(["search", "save", "standup", "standup", "--tag", "work"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Saved search 'standup'"));
+
+    db.cmd()
+        .args(["search", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("@standup\t\"standup\" --tag work"));
+
+    db.cmd()
+        .args(["ls", "@standup", "--output", "plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("shipped the parser"))
+        .stdout(predicate::str::contains("groceries").not())
+        .stdout(predicate::str::contains("planning").not());
+
+    // Extra tags narrow the saved filters
+    db.cmd()
+        .args(["search", "save", "work", "--tag", "work"])
+        .assert()
+        .success();
+    db.cmd()
+        .args(["ls", "@work", "--tag", "draft", "--output", "plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("planning"))
+        .stdout(predicate::str::contains("shipped").not());
+
+    db.cmd()
+        .args(["search", "rm", "standup"])
+        .assert()
+        .success();
+    db.cmd()
+        .args(["ls", "@standup"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No saved search named 'standup'"));
+
+    let saved = crate::profile::Profile::from_path(&db.profile_path())
+        .unwrap()
+        .unwrap();
+    assert_eq!(saved.timezone.as_deref(), Some("UTC"));
+    assert!(saved.searches.contains_key("work"));
+}
+
+#[test]
+fn test_search_term_query_language() {
+    let db = TestDb::new();
+    db.add_note("weekly sync notes", vec!["work"], Some("2025-01-10"));
+    db.add_note(
+        "weekly sync draft",
+        vec!["work", "draft"],
+        Some("2025-01-12"),
+    );
+    db.add_note("weekly sync notes", vec!["work"], Some("2025-02-03"));
+    db.add_note("tag:literal reminder", vec!["home"], Some("2025-01-05"));
+
+    let output = db
+        .cmd()
+        .args([
+            "ls",
+            r#"tag:work -tag:draft date:2025-01 "sync notes""#,
+            "--output",
+            "json",
+        ])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let notes = json.as_array().unwrap();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0]["subject_date"], "2025-01-10");
+
+    // --date replaces the term's date range
+    db.cmd()
+        .args(["ls", "tag:work date:2025-01", "--date", "2025-02-03"])
+        .args(["--output", "plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2025-02-03"))
+        .stdout(predicate::str::contains("2025-01-10").not());
+
+    db.cmd()
+        .args(["ls", r#""tag:literal""#, "--output", "plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("reminder"));
+
+    db.cmd()
+        .args(["ls", "date:someday"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid date 'someday'"));
+}
+
+#[test]
+fn test_calendar_date_targets() {
+    let db = TestDb::new();
+    db.add_note("january kickoff", vec![], Some("2025-01-02"));
+    db.add_note("january review", vec![], Some("2025-01-31"));
+    db.add_note("february plan", vec![], Some("2025-02-01"));
+    let today = chrono::Local::now().date_naive().to_string();
+    db.add_note("this week's note", vec![], Some(&today));
+
+    db.cmd()
+        .args(["ls", "--date", "2025-01", "--output", "plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("kickoff"))
+        .stdout(predicate::str::contains("review"))
+        .stdout(predicate::str::contains("february").not());
+
+    db.cmd()
+        .args(["ls", "--date", "this week", "--output", "plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("this week's note"))
+        .stdout(predicate::str::contains("january").not());
+
+    db.save_profile(crate::profile::Profile {
+        week_start: Some("someday".to_string()),
+        ..Default::default()
+    });
+
+    db.cmd()
+        .args(["ls", "--date", "this week"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid week_start 'someday'"));
+}
+
+#[test]
+fn test_note_last_delete_acts_on_latest_match() {
+    let db = TestDb::new();
+    let keep = db.add_note("work one", vec!["work"], Some("2025-01-01"));
+    db.add_note("work two", vec!["work"], Some("2025-01-02"));
+    let other = db.add_note("home", vec!["home"], None);
+
+    db.cmd()
+        .args(["note", "last", "--tag", "work", "--delete", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deleted note"));
+
+    let ids: Vec<String> = db.get_notes().into_iter().map(|n| n.id).collect();
+    assert_eq!(ids.len(), 2);
+    assert!(ids.contains(&keep));
+    assert!(ids.contains(&other));
+
+    db.cmd()
+        .args(["note", "last", "--tag", "missing", "--delete", "--yes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No matching note found"));
+
+    db.cmd()
+        .args(["note", "last", "-n", "2", "--delete"])
+        .assert()
+        .failure();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_note_last_yank_copies_content() {
+    let db = TestDb::new();
+    db.add_note("older", vec![], Some("2025-01-01"));
+    db.add_note("copy me", vec![], Some("2025-01-02"));
+
+    let out = db._temp_dir.path().join("clipboard.txt");
+    db.cmd()
+        .env("JOT_CLIPBOARD", format!("tee {}", out.display()))
+        .args(["note", "last", "--yank"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("to the clipboard"));
+
+    assert_eq!(std::fs::read_to_string(out).unwrap(), "copy me");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_note_links_are_listed_and_opened() {
+    let db = TestDb::new();
+    let id = db.add_note(
+        "reading list:\n- https://example.com/one.\n- (see http://example.org/two)",
+        vec![],
+        None,
+    );
+
+    db.cmd()
+        .args(["note", "show", &id, "--output", "plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Links:\n  [1] https://example.com/one\n  [2] http://example.org/two\n",
+        ));
+
+    db.cmd()
+        .env("BROWSER", "echo")
+        .args(["note", "open", &id, "2"])
+        .assert()
+        .success()
+        .stdout("http://example.org/two\n");
+
+    db.cmd()
+        .env("BROWSER", "echo")
+        .args(["note", "open", &id, "3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("has 2 link(s)"));
+
+    let plain = db.add_note("no links here", vec![], None);
+    db.cmd()
+        .args(["note", "open", &plain])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("contains no links"));
+}
+
+#[test]
+fn test_tag_suggest() {
+    let db = TestDb::new();
+    db.add_note("standup: fixed login bug", vec!["work", "standup"], None);
+    db.add_note("standup: reviewed PRs", vec!["work", "standup"], None);
+    db.add_note("bought milk", vec!["home"], None);
+    let untagged = db.add_note("standup went long", vec![], None);
+
+    db.cmd()
+        .args(["tag", "suggest", "standup", "moved", "to", "Tuesday"])
+        .assert()
+        .success()
+        .stdout("standup\tmentioned in the note\nwork\tused with \"standup\"\n");
+
+    // An ID suggests tags for that note's content
+    db.cmd()
+        .args(["tag", "suggest", &untagged, "-n", "1"])
+        .assert()
+        .success()
+        .stdout("standup\tmentioned in the note\n");
+
+    db.cmd()
+        .args(["tag", "suggest", "nothing", "relevant"])
+        .assert()
+        .success()
+        .stdout("No tag suggestions.\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_editor_template_lists_suggested_tags() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let db = TestDb::new();
+    db.add_note("standup notes", vec!["standup"], None);
+
+    db.save_profile(crate::profile::Profile {
+        suggest_tags: true,
+        ..Default::default()
+    });
+
+    // Keeps a copy of the template, then writes the note
+    let seen = db._temp_dir.path().join("template.txt");
+    let script = db._temp_dir.path().join("editor.sh");
+    std::fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\ncp \"$1\" '{}'\nprintf 'tags = []\\n+++\\ndone' > \"$1\"\n",
+            seen.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    db.cmd()
+        .env("EDITOR", &script)
+        .env_remove("VISUAL")
+        .args(["note", "add", "--editor", "daily", "standup"])
+        .assert()
+        .success();
+
+    let template = std::fs::read_to_string(seen).unwrap();
+    assert!(template.starts_with("# Suggested tags: \"standup\"\n"));
+}
+
+#[test]
+fn test_note_pin_lists_first() {
+    let db = TestDb::new();
+    let pinned = db.add_note("release checklist", vec![], Some("2024-01-01"));
+    let latest = db.add_note("newer note", vec![], Some("2024-02-01"));
+
+    db.cmd().args(["note", "pin", &pinned]).assert().success();
+    db.cmd()
+        .args(["note", "pin", &pinned])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("already pinned"));
+
+    let output = db.cmd().args(["ls"]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.find("release checklist").unwrap() < stdout.find("newer note").unwrap(),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(&format!("{} \u{1F4CC}", &pinned[..8])),
+        "{}",
+        stdout
+    );
+
+    // The latest note is still the newest one, pinned or not
+    db.cmd()
+        .args(["note", "last"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("newer note"));
+
+    let notes = db.get_notes();
+    assert!(notes.iter().find(|n| n.id == pinned).unwrap().pinned);
+    assert!(!notes.iter().find(|n| n.id == latest).unwrap().pinned);
+    assert!(notes.iter().all(|n| n.tags.is_empty()));
+}
+
+#[test]
+fn test_note_status_and_board() {
+    let db = TestDb::new();
+    db.cmd()
+        .args(["note", "add", "--status", "todo", "write", "report"])
+        .assert()
+        .success();
+    let review = db.add_note("review PR", vec![], None);
+    db.add_note("no status here", vec![], None);
+
+    db.cmd()
+        .args(["note", "edit", &review, "--status", "doing"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("is now doing"));
+    db.cmd()
+        .args(["note", "add", "--status", "blocked", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown status 'blocked'"));
+
+    db.cmd()
+        .args(["ls", "--status", "doing"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("review PR"))
+        .stdout(predicate::str::contains("write report").not());
+
+    let output = db.cmd().args(["board"]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let header = stdout.lines().next().unwrap();
+    assert!(
+        header.find("todo (1)").unwrap() < header.find("doing (1)").unwrap()
+            && header.contains("done (0)"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("write report"), "{}", stdout);
+    assert!(!stdout.contains("no status here"), "{}", stdout);
+
+    db.cmd()
+        .args(["note", "edit", &review, "--no-status"])
+        .assert()
+        .success();
+    let notes = db.get_notes();
+    assert_eq!(notes.iter().find(|n| n.id == review).unwrap().status, None);
+    assert_eq!(
+        notes
+            .iter()
+            .find(|n| n.content == "write report")
+            .unwrap()
+            .status
+            .as_deref(),
+        Some("todo")
+    );
+}
+
+#[test]
+fn test_capture_context_is_searchable() {
+    let db = TestDb::new();
+    db.cmd()
+        .args(["note", "add", "without", "context"])
+        .assert()
+        .success();
+
+    db.save_profile(crate::profile::Profile {
+        capture_context: true,
+        ..Default::default()
+    });
+
+    let repo = db._temp_dir.path().join("myrepo");
+    std::fs::create_dir(&repo).unwrap();
+    for args in [&["init", "-q"][..], &["checkout", "-q", "-b", "feature"]] {
+        let status = std::process::Command::new("git")
+            .args(args)
+            .current_dir(&repo)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+    db.cmd()
+        .current_dir(&repo)
+        .args(["note", "add", "fix", "the", "parser"])
+        .assert()
+        .success();
+
+    let captured = db
+        .get_notes()
+        .into_iter()
+        .find(|n| n.content == "fix the parser")
+        .unwrap();
+    assert_eq!(
+        captured.meta.get("repo").map(String::as_str),
+        Some("myrepo")
+    );
+    assert_eq!(
+        captured.meta.get("branch").map(String::as_str),
+        Some("feature")
+    );
+    assert!(captured.meta.contains_key("cwd"));
+
+    db.cmd()
+        .args(["ls", "--meta", "repo=myrepo", "--meta", "branch=feature"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fix the parser"))
+        .stdout(predicate::str::contains("without context").not());
+    db.cmd()
+        .args(["note", "show", &captured.id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("repo=myrepo"));
+    db.cmd()
+        .args(["ls", "--meta", "repo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("use key=value"));
+}
+
+#[test]
+fn test_note_attachments() {
+    let db = TestDb::new();
+    let id = db.add_note("trip plan", vec![], None);
+    let file = db._temp_dir.path().join("ticket.txt");
+    std::fs::write(&file, "seat 12A").unwrap();
+
+    db.cmd()
+        .args(["note", "attach", &id, file.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Attached ticket.txt (8 bytes)"));
+    db.cmd()
+        .args(["note", "show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 attached file"));
+
+    let out = db._temp_dir.path().join("out");
+    db.cmd()
+        .args(["note", "show", &id, "--attachments", "--save-to"])
+        .arg(&out)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ticket.txt (8 bytes)"));
+    assert_eq!(
+        std::fs::read_to_string(out.join("ticket.txt")).unwrap(),
+        "seat 12A"
+    );
+    let notes = db.get_notes();
+    assert_eq!(notes[0].attachments.len(), 1);
+
+    db.cmd()
+        .args(["note", "detach", &id, "missing.txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("it has ticket.txt"));
+    db.cmd()
+        .args(["note", "detach", &id, "ticket.txt"])
+        .assert()
+        .success();
+    db.cmd()
+        .args(["note", "show", &id, "--attachments"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(none)"));
+}
+
+#[test]
+fn test_encrypted_profile() {
+    let db = TestDb::new();
+    db.cmd()
+        .args(["note", "add", "written", "before"])
+        .assert()
+        .success();
+
+    db.save_profile(crate::profile::Profile {
+        encrypt: true,
+        ..Default::default()
+    });
+
+    // Nothing to ask for the passphrase with
+    db.cmd()
+        .args(["ls"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("JOT_PASSPHRASE"));
+
+    db.cmd()
+        .env("JOT_PASSPHRASE", "correct horse")
+        .args(["note", "add", "-t", "Secret", "launch", "codes"])
+        .assert()
+        .success();
+    db.cmd()
+        .env("JOT_PASSPHRASE", "correct horse")
+        .args(["db", "maintain", "--encrypt"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Encrypted 1 note(s)"));
+
+    // Only sealed text reaches the database (and so the sync server)
+    for note in db.get_notes() {
+        assert!(note.content.starts_with("jotenc1:"), "{}", note.content);
+        assert!(!note.tags.iter().any(|t| t.contains("secret")));
+    }
+
+    db.cmd()
+        .env("JOT_PASSPHRASE", "correct horse")
+        .args(["ls", "-t", "secret", "codes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("launch codes"))
+        .stdout(predicate::str::contains("written before").not());
+    db.cmd()
+        .env("JOT_PASSPHRASE", "correct horse")
+        .args(["ls", "BEFORE"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("written before"));
+
+    db.cmd()
+        .env("JOT_PASSPHRASE", "wrong")
+        .args(["ls"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Wrong passphrase"));
+}
+
+#[test]
+fn test_encrypted_profile_pages_after_filtering() {
+    let db = TestDb::new();
+    db.add_note("day 1", vec!["work"], Some("2025-01-01"));
+    db.add_note("day 2", vec!["workshop"], Some("2025-01-02"));
+    db.add_note("day 3", vec!["work/sub"], Some("2025-01-03"));
+    db.save_profile(crate::profile::Profile {
+        encrypt: true,
+        ..Default::default()
+    });
+    db.cmd()
+        .env("JOT_PASSPHRASE", "correct horse")
+        .args(["db", "maintain", "--encrypt"])
+        .assert()
+        .success();
+    // Written in plain text, e.g. synced from a device that doesn't encrypt
+    db.add_note("day 4", vec!["work"], Some("2025-01-04"));
+    db.add_note("day 5", vec!["workshop"], Some("2025-01-05"));
+
+    let contents = |args: &[&str]| -> Vec<String> {
+        let output = db
+            .cmd()
+            .env("JOT_PASSPHRASE", "correct horse")
+            .args(["note", "search", "--output", "json"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        json.as_array()
+            .unwrap()
+            .iter()
+            .map(|note| note["content"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    // `work` matches nested tags but not `workshop`, and pages count only matches
+    assert_eq!(
+        contents(&["-t", "work", "--page", "1", "--per-page", "2"]),
+        vec!["day 4", "day 3"]
+    );
+    assert_eq!(
+        contents(&["-t", "work", "--page", "2", "--per-page", "2"]),
+        vec!["day 1"]
+    );
+    assert_eq!(
+        contents(&["-t", "WORKSHOP", "--page", "2", "--per-page", "1"]),
+        vec!["day 2"]
+    );
+}
+
+#[test]
+fn test_encrypted_profile_seals_context_for_sync() {
+    let db = TestDb::new();
+    db.save_token("secret-token");
+    db.save_profile(crate::profile::Profile {
+        encrypt: true,
+        capture_context: true,
+        ..Default::default()
+    });
+
+    let workdir = db._temp_dir.path().join("secret-project");
+    std::fs::create_dir(&workdir).unwrap();
+    db.cmd()
+        .env("JOT_PASSPHRASE", "correct horse")
+        .env("HOSTNAME", "secret-host")
+        .current_dir(&workdir)
+        .args(["note", "add", "launch", "plan"])
+        .assert()
+        .success();
+    let id = db.get_notes()[0].id.clone();
+    let file = workdir.join("merger-terms.txt");
+    std::fs::write(&file, "terms").unwrap();
+    db.cmd()
+        .env("JOT_PASSPHRASE", "correct horse")
+        .args(["note", "attach", &id, file.to_str().unwrap()])
+        .assert()
+        .success();
+
+    // The server gets nothing readable, and sends the note back the way it
+    // would to another device
+    let (url, server) = serve_http(2, |request| {
+        if request.starts_with("GET /version") {
+            return ("200 OK", vec![], version_body());
+        }
+        let (_, body) = request.split_once("\r\n\r\n").unwrap();
+        for plain in ["secret-project", "secret-host", "merger-terms", "launch"] {
+            assert!(
+                !body.contains(plain),
+                "{} sent in plain text: {}",
+                plain,
+                body
+            );
+        }
+        let sent: serde_json::Value = serde_json::from_str(body).unwrap();
+        let note = &sent["notes"][0];
+        for sealed in [&note["meta"]["cwd"], &note["attachments"][0]["filename"]] {
+            assert!(
+                sealed.as_str().unwrap().starts_with("jotenc1:"),
+                "{}",
+                sealed
+            );
+        }
+        let reply = serde_json::json!({ "notes": [note], "conflicts": [] });
+        (
+            "200 OK",
+            vec![("content-type", "application/json".to_string())],
+            reply.to_string().into_bytes(),
+        )
+    });
+    db.cmd()
+        .env("JOT_PASSPHRASE", "correct horse")
+        .args(["sync", "--url", &url])
+        .assert()
+        .success();
+    server.join().unwrap();
+
+    // Kept readable locally, so `--meta` and attachment names still work
+    let note = db.get_notes().into_iter().find(|n| n.id == id).unwrap();
+    assert_eq!(
+        note.meta.get("host").map(String::as_str),
+        Some("secret-host")
+    );
+    assert!(note.meta["cwd"].ends_with("secret-project"));
+    assert_eq!(note.attachments[0].filename, "merger-terms.txt");
+}
+
+#[test]
+fn test_note_add_from_template() {
+    let db = TestDb::new();
+    let mut profile = crate::profile::Profile::default();
+    profile.templates.insert(
+        "meeting".to_string(),
+        crate::profile::NoteTemplate {
+            body: "# {{project}} sync ({{date}})\nAttendees: {{attendees}}\n".to_string(),
+            tags: vec!["meeting".to_string()],
+            ..Default::default()
+        },
+    );
+    db.save_profile(profile);
+
+    db.cmd()
+        .args(["note", "add", "--template", "standup"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("profile templates: meeting"));
+    // No terminal to ask on
+    db.cmd()
+        .args([
+            "note",
+            "add",
+            "--template",
+            "meeting",
+            "--var",
+            "project=Apollo",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("needs a value for attendees"));
+
+    // The editor saves the filled-in template as it is
+    db.cmd()
+        .env("EDITOR", "true")
+        .env_remove("VISUAL")
+        .args([
+            "note",
+            "add",
+            "--template",
+            "meeting",
+            "-t",
+            "q3",
+            "--date",
+            "2024-05-06",
+            "--var",
+            "project=Apollo",
+            "--var",
+            "attendees=Ann, Bo",
+        ])
+        .assert()
+        .success();
+
+    let notes = db.get_notes();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(
+        notes[0].content,
+        "# Apollo sync (2024-05-06)\nAttendees: Ann, Bo"
+    );
+    let mut tags = notes[0].tags.clone();
+    tags.sort();
+    assert_eq!(tags, vec!["meeting", "q3"]);
+    assert_eq!(notes[0].subject_date.as_deref(), Some("2024-05-06"));
+}
+
+#[test]
+fn test_note_add_flags_similar_note_from_today() {
+    let db = TestDb::new();
+    let text = "Backup of the production database finished without errors";
+    db.cmd().args(["down", text]).assert().success();
+
+    // Without a terminal it is added, with a warning
+    db.cmd()
+        .args(["down", &format!("{}.", text)])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("same as note"));
+    assert_eq!(db.get_notes().len(), 2);
+
+    db.cmd()
+        .args(["down", "--if-similar", "skip", text])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing added"));
+    assert_eq!(db.get_notes().len(), 2);
+
+    db.cmd()
+        .args(["down", "-t", "ops", "--if-similar", "append", text])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Appended to note"));
+    let notes = db.get_notes();
+    assert_eq!(notes.len(), 2);
+    assert!(notes
+        .iter()
+        .any(|n| n.content == format!("{}\n\n{}", text, text) && n.tags == vec!["ops"]));
+
+    // Different enough text is not flagged
+    db.cmd()
+        .args(["down", "Backup of the staging database failed"])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty());
+
+    // Nor is a similar note from an earlier day
+    db.add_note("weekly report sent", vec![], Some("2020-01-01"));
+    let conn = jot_core::open_db(&db.db_path).unwrap();
+    conn.execute(
+        "UPDATE notes SET created_at = 0 WHERE content = 'weekly report sent'",
+        [],
+    )
+    .unwrap();
+    db.cmd()
+        .args(["down", "weekly report sent"])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn test_recur_run_creates_each_occurrence_once() {
+    let db = TestDb::new();
+    let from = (chrono::Local::now().date_naive() - chrono::Days::new(2))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    db.cmd()
+        .args([
+            "recur", "add", "--every", "day", "--tag", "work", "--from", &from, "daily", "review",
+        ])
+        .assert()
+        .success();
+    db.cmd()
+        .args(["recur", "add", "--every", "fortnight", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid interval 'fortnight'"));
+
+    db.cmd()
+        .args(["recur", "run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created 3 recurring note(s)"));
+    db.cmd()
+        .args(["recur", "run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created 0 recurring note(s)"));
+
+    let notes = db.get_notes();
+    assert_eq!(notes.len(), 3);
+    assert!(notes
+        .iter()
+        .all(|n| n.content == "daily review" && n.tags == vec!["work"]));
+    assert!(notes
+        .iter()
+        .any(|n| n.subject_date.as_deref() == Some(from.as_str())));
+
+    db.cmd()
+        .args(["recur", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("every day"));
+    db.cmd().args(["recur", "remove", "1"]).assert().success();
+    db.cmd().args(["recur", "remove", "1"]).assert().failure();
+    assert_eq!(db.get_notes().len(), 3);
+}
+
+#[test]
+fn test_concurrent_recur_runs_create_each_occurrence_once() {
+    let db = TestDb::new();
+    let from = (chrono::Local::now().date_naive() - chrono::Days::new(99))
+        .format("%Y-%m-%d")
+        .to_string();
+    db.cmd()
+        .args(["recur", "add", "--every", "day", "--from", &from, "daily"])
+        .assert()
+        .success();
+
+    let temp = db._temp_dir.path();
+    let runs: Vec<_> = (0..4)
+        .map(|_| {
+            std::process::Command::new(assert_cmd::cargo::cargo_bin("jot"))
+                .env("XDG_CONFIG_HOME", temp.join("config"))
+                .env("XDG_DATA_HOME", temp.join("data"))
+                .env("JOT_PROFILE", &db.profile_name)
+                .args(["recur", "run"])
+                .stdout(std::process::Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    for mut run in runs {
+        assert!(run.wait().unwrap().success());
+    }
+
+    let notes = db.get_notes();
+    let mut dates: Vec<_> = notes.iter().map(|n| n.subject_date.clone()).collect();
+    dates.sort();
+    dates.dedup();
+    assert_eq!(notes.len(), 100);
+    assert_eq!(dates.len(), 100);
+}
+
+#[test]
+fn test_cal_counts_notes_per_day() {
+    let db = TestDb::new();
+    db.add_note("first", vec![], Some("2024-03-15"));
+    db.add_note("second", vec![], Some("2024-03-15"));
+    db.add_note("other day", vec![], Some("2024-03-02"));
+    db.add_note("next month", vec![], Some("2024-04-15"));
+
+    let output = db.cmd().args(["cal", "2024-03"]).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("March 2024 (3 notes)"), "{}", stdout);
+    assert!(
+        stdout.lines().nth(1).unwrap().starts_with("Mo"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("15 2"), "{}", stdout);
+    assert!(stdout.contains(" 2 1"), "{}", stdout);
+
+    db.cmd()
+        .args(["cal", "--day", "2024-03-15"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("March 2024"))
+        .stdout(predicate::str::contains("second"))
+        .stdout(predicate::str::contains("other day").not());
+
+    let output = db
+        .cmd()
+        .args(["cal", "--day", "2024-03-15", "--output", "json"])
+        .output()
+        .unwrap();
+    let notes: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(notes.len(), 2);
+}
+
+#[test]
+fn test_track_time_on_notes() {
+    let db = TestDb::new();
+    let client = db.add_note("client work", vec!["acme"], None);
+    let chores = db.add_note("admin chores", vec![], None);
+
+    db.cmd()
+        .args(["track", "start", &client])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Tracking note"));
+    db.cmd()
+        .args(["track", "start", &chores])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Stopped note {}",
+            &client[..8]
+        )));
+    db.cmd()
+        .args(["track", "stop"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Stopped note {}",
+            &chores[..8]
+        )));
+    db.cmd()
+        .args(["track", "stop"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing is being tracked"));
+
+    // 90 minutes on the client note earlier today
+    let midnight = chrono::Local::now()
+        .date_naive()
+        .and_hms_opt(0, 1, 0)
+        .unwrap()
+        .and_local_timezone(chrono::Local)
+        .earliest()
+        .unwrap()
+        .timestamp_millis();
+    let conn = jot_core::open_db(&db.db_path).unwrap();
+    conn.execute(
+        "INSERT INTO time_entries (note_id, started_at, stopped_at) VALUES (?1, ?2, ?3)",
+        rusqlite::params![client, midnight, midnight + 90 * 60_000],
+    )
+    .unwrap();
+    drop(conn);
+
+    db.cmd()
+        .args(["track", "report", "--week"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Week of "))
+        .stdout(predicate::str::contains(format!(
+            "{}    1h 30m  client work",
+            &client[..8]
+        )))
+        .stdout(predicate::str::contains("1h 30m  #acme"))
+        .stdout(predicate::str::contains("0h 00m  (untagged)"));
+}
+
+#[test]
+fn test_dashboard_sections() {
+    let db = TestDb::new();
+    let today = chrono::Local::now().date_naive().to_string();
+    let pinned = db.add_note("release checklist", vec![], Some("2024-01-01"));
+    db.add_note("today's log", vec!["work"], Some(&today));
+    db.add_note(
+        "errands\n- [ ] renew passport due:2000-01-01\n- [ ] buy milk\n- [x] post letter",
+        vec![],
+        Some("2024-01-02"),
+    );
+
+    db.cmd()
+        .args(["note", "pin", &pinned])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pinned note"));
+
+    db.cmd()
+        .arg("dashboard")
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("Pinned (1)\n  ")
+                .and(predicate::str::contains("release checklist")),
+        )
+        .stdout(predicate::str::contains("Today (1)"))
+        .stdout(predicate::str::contains("today's log"))
+        .stdout(predicate::str::contains("Tasks (1)"))
+        .stdout(predicate::str::contains("buy milk"))
+        .stdout(predicate::str::contains("Overdue (1)"))
+        .stdout(predicate::str::contains("renew passport due:2000-01-01"))
+        .stdout(predicate::str::contains("post letter").not());
+
+    db.cmd().args(["note", "unpin", &pinned]).assert().success();
+
+    // The layout comes from the profile
+    let work = crate::profile::SavedSearch {
+        tags: vec!["work".to_string()],
+        ..Default::default()
+    };
+    db.save_profile(crate::profile::Profile {
+        searches: [("work".to_string(), work)].into(),
+        dashboard: Some(crate::profile::DashboardConfig {
+            sections: vec!["pinned".to_string(), "@work".to_string()],
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    db.cmd()
+        .arg("dashboard")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Pinned (0)\n  (none)\n\n@work (1)",
+        ))
+        .stdout(predicate::str::contains("Tasks").not());
+
+    db.save_profile(crate::profile::Profile {
+        dashboard: Some(crate::profile::DashboardConfig {
+            sections: vec!["later".to_string()],
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    db.cmd()
+        .arg("dashboard")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unknown dashboard section 'later'",
+        ));
+}
+
+#[test]
+fn test_import_standard_notes_backup() {
+    use std::io::Write;
+
+    let db = TestDb::new();
+    let backup = r#"{"version": "004", "items": [
+        {"uuid": "n1", "content_type": "Note",
+         "created_at": "2023-05-01T10:00:00.000Z", "updated_at": "2023-05-02T08:30:00.000Z",
+         "content": {"title": "Groceries", "text": "milk", "references": []}},
+        {"uuid": "n2", "content_type": "Note",
+         "created_at": "2023-06-01T10:00:00.000Z", "updated_at": "2023-06-03T10:00:00.000Z",
+         "content": {"title": "", "text": "old idea", "trashed": true}},
+        {"uuid": "t1", "content_type": "Tag",
+         "content": {"title": "home", "references": [{"uuid": "n1", "content_type": "Note"}]}},
+        {"uuid": "e1", "content_type": "Note", "content": "004:encrypted"}
+    ]}"#;
+    let zip_path = db._temp_dir.path().join("backup.zip");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+    zip.start_file(
+        "Standard Notes Backup and Import File.txt",
+        zip::write::SimpleFileOptions::default(),
+    )
+    .unwrap();
+    zip.write_all(backup.as_bytes()).unwrap();
+    zip.finish().unwrap();
+
+    db.cmd()
+        .args(["import", "standardnotes", zip_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 2 note(s)"))
+        .stdout(predicate::str::contains("1 item(s) could not be read"));
+
+    // Trashed notes come in deleted, so only one is listed
+    let output = db
+        .cmd()
+        .args(["note", "search", "--output", "json"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let notes = json.as_array().unwrap();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0]["content"], "Groceries\n\nmilk");
+    assert_eq!(notes[0]["tags"][0], "home");
+    assert_eq!(notes[0]["subject_date"], "2023-05-01");
+    assert_eq!(notes[0]["created_at"], 1_682_935_200_000_i64);
+
+    // Importing the same backup again adds nothing
+    db.cmd()
+        .args(["import", "standardnotes", zip_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 0 note(s)"))
+        .stdout(predicate::str::contains("2 note(s) were already present"));
+}
+
+#[test]
+fn test_import_apple_notes_folder() {
+    let db = TestDb::new();
+    let export = db._temp_dir.path().join("apple-notes");
+    std::fs::create_dir_all(export.join("Work Projects/Q3")).unwrap();
+    std::fs::create_dir_all(export.join("Notes/Attachments")).unwrap();
+
+    let plan = export.join("Work Projects/Q3/Plan.html");
+    std::fs::write(
+        &plan,
+        "<html><body><div><h1>Q3 plan</h1></div><div>Ship <b>sync</b></div></body></html>",
+    )
+    .unwrap();
+    // Exporters set the file times to the note's dates
+    let modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_682_935_200);
+    std::fs::File::options()
+        .write(true)
+        .open(&plan)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    std::fs::write(export.join("Notes/Groceries.md"), "Groceries\n\n- milk\n").unwrap();
+    std::fs::write(
+        export.join("Notes/Attachments/photo.png"),
+        [0x89, b'P', b'N', b'G'],
+    )
+    .unwrap();
+
+    db.cmd()
+        .args(["import", "apple-notes", export.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 2 note(s)"));
+
+    let output = db
+        .cmd()
+        .args(["note", "search", "--output", "json"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let notes = json.as_array().unwrap();
+    assert_eq!(notes.len(), 2);
+
+    let plan = notes
+        .iter()
+        .find(|n| n["tags"][0] == "work-projects")
+        .unwrap();
+    assert_eq!(plan["content"], "# Q3 plan\n\nShip **sync**");
+    assert_eq!(plan["tags"][1], "q3");
+    assert_eq!(plan["subject_date"], "2023-05-01");
+    assert_eq!(plan["updated_at"], 1_682_935_200_000_i64);
+
+    let groceries = notes.iter().find(|n| n["tags"][0] == "notes").unwrap();
+    assert_eq!(groceries["content"], "Groceries\n\n- milk");
+}
+
+#[test]
+fn test_import_markdown_and_json() {
+    let db = TestDb::new();
+    db.add_note("exported note", vec!["work"], Some("2024-01-05"));
+    let export = db._temp_dir.path().join("export");
+    db.cmd()
+        .args(["export", export.to_str().unwrap()])
+        .assert()
+        .success();
+
+    let vault = db._temp_dir.path().join("vault");
+    std::fs::create_dir_all(vault.join("Daily")).unwrap();
+    std::fs::create_dir_all(vault.join(".obsidian")).unwrap();
+    std::fs::write(vault.join(".obsidian/app.json"), "{}").unwrap();
+    std::fs::write(
+        vault.join("Trip.md"),
+        "---\ntags: [travel, \"#Summer Plans\"]\ncreated: 2024-06-01T09:30:00Z\n---\n\n# Trip\n",
+    )
+    .unwrap();
+    std::fs::write(vault.join("Daily/2024-03-02.md"), "standup notes\n").unwrap();
+
+    let dump = db._temp_dir.path().join("dump.json");
+    let output = db.cmd().args(["ls", "--output", "json"]).output().unwrap();
+    std::fs::write(&dump, &output.stdout).unwrap();
+
+    let other = TestDb::new();
+    other
+        .cmd()
+        .args(["import", "markdown", export.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 1 note(s)"));
+    // The same note again, from the JSON dump
+    other
+        .cmd()
+        .args(["import", "json", dump.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 0 note(s)"))
+        .stdout(predicate::str::contains("1 note(s) were already present"));
+    other
+        .cmd()
+        .args(["import", "obsidian", vault.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 2 note(s)"));
+
+    let original = db.get_notes();
+    let notes = other.get_notes();
+    assert_eq!(notes.len(), 3);
+    let exported = notes.iter().find(|n| n.content == "exported note").unwrap();
+    assert_ne!(exported.id, original[0].id);
+    assert_eq!(exported.tags, vec!["work"]);
+    assert_eq!(exported.subject_date.as_deref(), Some("2024-01-05"));
+    assert_eq!(exported.created_at / 1000, original[0].created_at / 1000);
+
+    let trip = notes.iter().find(|n| n.content == "# Trip").unwrap();
+    assert_eq!(trip.tags, vec!["travel", "summer-plans"]);
+    assert_eq!(trip.subject_date.as_deref(), Some("2024-06-01"));
+    let daily = notes.iter().find(|n| n.content == "standup notes").unwrap();
+    assert_eq!(daily.tags, vec!["daily"]);
+    assert_eq!(daily.subject_date.as_deref(), Some("2024-03-02"));
+
+    let fresh = TestDb::new();
+    fresh
+        .cmd()
+        .args(["import", "json", dump.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 1 note(s)"));
+    let imported = fresh.get_notes();
+    assert_eq!(imported[0].tags, vec!["work"]);
+    assert_ne!(imported[0].id, original[0].id);
+}
+
+#[test]
+fn test_import_restores_exported_attachments() {
+    let db = TestDb::new();
+    let trip = db.add_note("trip plan", vec![], None);
+    let photos = db.add_note("photos", vec![], None);
+    for (id, name, contents) in [
+        (&trip, "ticket.txt", "seat 12A"),
+        (&trip, "map.txt", "north is up"),
+        (&photos, "photo.txt", "smile"),
+    ] {
+        let file = db._temp_dir.path().join(name);
+        std::fs::write(&file, contents).unwrap();
+        db.cmd()
+            .args(["note", "attach", id, file.to_str().unwrap()])
+            .assert()
+            .success();
+    }
+
+    let export = db._temp_dir.path().join("export");
+    db.cmd()
+        .args(["export", export.to_str().unwrap()])
+        .assert()
+        .success();
+    let output = db.cmd().args(["ls", "--output", "json"]).output().unwrap();
+    std::fs::write(export.join("notes.json"), &output.stdout).unwrap();
+
+    // One file lost and one damaged since the export
+    let hash = |name: &str| {
+        db.get_notes()
+            .iter()
+            .flat_map(|n| n.attachments.clone())
+            .find(|a| a.filename == name)
+            .unwrap()
+            .hash
+    };
+    std::fs::remove_file(export.join("attachments").join(hash("map.txt"))).unwrap();
+    std::fs::write(export.join("attachments").join(hash("photo.txt")), "frown").unwrap();
+
+    for source in [
+        vec!["import", "markdown", export.to_str().unwrap()],
+        vec![
+            "import",
+            "json",
+            export.join("notes.json").to_str().unwrap(),
+        ],
+    ] {
+        let other = TestDb::new();
+        other
+            .cmd()
+            .args(&source)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Imported 2 note(s)"))
+            .stdout(predicate::str::contains("Restored 1 attachment(s)"))
+            .stdout(predicate::str::contains(
+                "1 attachment(s) were missing and 1 corrupted",
+            ))
+            .stderr(predicate::str::contains("attachment map.txt is missing"))
+            .stderr(predicate::str::contains(
+                "attachment photo.txt is corrupted",
+            ));
+
+        let notes = other.get_notes();
+        let imported = notes.iter().find(|n| n.content == "trip plan").unwrap();
+        assert_eq!(imported.attachments.len(), 1);
+        assert_eq!(imported.attachments[0].filename, "ticket.txt");
+        let out = other._temp_dir.path().join("out");
+        other
+            .cmd()
+            .args(["note", "show", &imported.id, "--attachments", "--save-to"])
+            .arg(&out)
+            .assert()
+            .success();
+        assert_eq!(
+            std::fs::read_to_string(out.join("ticket.txt")).unwrap(),
+            "seat 12A"
+        );
+        let photos = notes.iter().find(|n| n.content == "photos").unwrap();
+        assert!(photos.attachments.is_empty());
+    }
+}
+
+#[test]
+fn test_export_incremental() {
+    let db = TestDb::new();
+    let kept = db.add_note("kept as is", vec!["work"], Some("2024-01-01"));
+    let changed = db.add_note("will change", vec![], None);
+    let deleted = db.add_note("will go", vec![], None);
+    let dir = db._temp_dir.path().join("export");
+    let file = |id: &str| dir.join(exported_file_name(&dir, id).unwrap());
+
+    db.cmd()
+        .args(["export", dir.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 3 note(s)"));
+    let deleted_file = file(&deleted);
+    let exported = std::fs::read_to_string(file(&kept)).unwrap();
+    assert!(exported.starts_with(&format!(
+        "+++\nid = \"{}\"\ntags = [\"work\"]\ndate = \"2024-01-01\"\n",
+        kept
+    )));
+    assert!(exported.ends_with("+++\n\nkept as is\n"));
+
+    // Untouched notes are not rewritten by an incremental export
+    std::fs::write(file(&kept), "edited by hand").unwrap();
+    db.cmd()
+        .args(["note", "append", &changed, "more"])
+        .assert()
+        .success();
+    db.cmd()
+        .args(["note", "delete", &deleted, "--yes"])
+        .assert()
+        .success();
+
+    db.cmd()
+        .args(["export", dir.to_str().unwrap(), "--incremental"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 1 note(s)"))
+        .stdout(predicate::str::contains("Removed 1 deleted note(s)"));
+    assert_eq!(
+        std::fs::read_to_string(file(&kept)).unwrap(),
+        "edited by hand"
+    );
+    assert!(std::fs::read_to_string(file(&changed))
+        .unwrap()
+        .ends_with("will change\nmore\n"));
+    assert!(!deleted_file.exists());
+    assert_eq!(exported_file_name(&dir, &deleted), None);
+
+    // A full export rewrites everything
+    db.cmd()
+        .args(["export", dir.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 2 note(s)"));
+    assert!(std::fs::read_to_string(file(&kept))
+        .unwrap()
+        .ends_with("kept as is\n"));
+}
+
+/// File the export manifest in `dir` lists for note `id`
+fn exported_file_name(dir: &std::path::Path, id: &str) -> Option<String> {
+    let manifest = std::fs::read_to_string(dir.join(".jot-export.json")).unwrap();
+    let manifest: serde_json::Value = serde_json::from_str(&manifest).unwrap();
+    manifest["notes"][id].as_str().map(String::from)
+}
+
+#[test]
+fn test_export_file_names() {
+    let db = TestDb::new();
+    let id = db.add_note("# Weekly sync: Q3\nagenda", vec![], Some("2024-03-01"));
+    let dir = db._temp_dir.path().join("export");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // A file the export didn't write keeps its name; the note falls back to its full ID
+    let short_name = format!("2024-03-01-weekly-sync-q3-{}.md", &id[..8]);
+    std::fs::write(dir.join(&short_name), "mine").unwrap();
+    db.cmd()
+        .args(["export", dir.to_str().unwrap()])
+        .assert()
+        .success();
+    let full_name = format!("2024-03-01-weekly-sync-q3-{}.md", id);
+    assert_eq!(exported_file_name(&dir, &id), Some(full_name.clone()));
+    assert_eq!(
+        std::fs::read_to_string(dir.join(&short_name)).unwrap(),
+        "mine"
+    );
+
+    // Re-exporting writes the same file again
+    db.cmd()
+        .args(["export", dir.to_str().unwrap()])
+        .assert()
+        .success();
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 3);
+
+    // A new first line renames the file
+    std::fs::remove_file(dir.join(&short_name)).unwrap();
+    let conn = jot_core::open_db(&db.db_path).unwrap();
+    jot_core::update_note(&conn, &id, "Retro", vec![], Some("2024-03-01".to_string())).unwrap();
+    db.cmd()
+        .args(["export", dir.to_str().unwrap(), "--incremental"])
+        .assert()
+        .success();
+    let renamed = format!("2024-03-01-retro-{}.md", &id[..8]);
+    assert_eq!(exported_file_name(&dir, &id), Some(renamed.clone()));
+    assert!(dir.join(&renamed).exists());
+    assert!(!dir.join(&full_name).exists());
+}
+
+#[test]
+fn test_capture_stream() {
+    let db = TestDb::new();
+
+    db.cmd()
+        .args(["capture", "--stdin-stream", "-t", "log", "-q"])
+        .write_stdin("deploy started\n\n  \ndeploy finished\n")
+        .assert()
+        .success()
+        .stdout(predicate::function(|out: &str| out.lines().count() == 2));
+
+    let blocks = db._temp_dir.path().join("blocks.txt");
+    std::fs::write(&blocks, "first\n  indented\n---\n\n---\nsecond").unwrap();
+    db.cmd()
+        .args(["capture", "--watch", blocks.to_str().unwrap()])
+        .args(["--delimiter", "---", "-t", "block"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Note added successfully").count(2));
+
+    let output = db
+        .cmd()
+        .args(["note", "search", "--output", "json", "-t", "log"])
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let mut contents: Vec<&str> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n["content"].as_str().unwrap())
+        .collect();
+    contents.sort();
+    assert_eq!(contents, vec!["deploy finished", "deploy started"]);
+
+    db.cmd()
+        .args(["note", "search", "-t", "block"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("first"))
+        .stdout(predicate::str::contains("second"));
+
+    db.cmd()
+        .arg("capture")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--watch"));
+}
+
+#[test]
+fn test_journal_mirror() {
+    let db = TestDb::new();
+    let journal = db._temp_dir.path().join("journal");
+    db.save_profile(crate::profile::Profile {
+        journal_dir: Some(journal.to_str().unwrap().to_string()),
+        timezone: Some("UTC".to_string()),
+        ..Default::default()
+    });
+
+    db.cmd()
+        .args(["down", "-t", "work,ops", "first entry"])
+        .assert()
+        .success();
+    db.cmd().args(["down", "second entry"]).assert().success();
+
+    let month = chrono::Utc::now().format("%Y-%m").to_string();
+    let text = std::fs::read_to_string(journal.join(format!("{}.md", month))).unwrap();
+    assert!(text.starts_with(&format!("## {}", month)));
+    assert!(text.contains("Tags: #work #ops\n\nfirst entry\n\n## "));
+    assert!(text.ends_with(")\n\nsecond entry\n\n"));
+
+    // Edits and deletions don't touch what was already written
+    db.cmd()
+        .args(["note", "last", "--delete", "--yes"])
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_to_string(journal.join(format!("{}.md", month))).unwrap(),
+        text
+    );
+}
+
+#[test]
+fn test_profile_use_session() {
+    let db = TestDb::new();
+    let current = db._temp_dir.path().join("config/jot/current");
+
+    db.cmd()
+        .args(["profile", "use", "--session", "o'brien"])
+        .assert()
+        .success()
+        .stdout("export JOT_PROFILE='o'\\''brien'\n")
+        .stderr(predicate::str::contains("Created new profile: o'brien"));
+    assert!(!current.exists());
+
+    db.cmd()
+        .env("JOT_PROFILE", "o'brien")
+        .args(["profile", "current"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Current profile: o'brien"));
+
+    db.cmd()
+        .args(["profile", "use", "work"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Switched to profile: work"));
+    assert_eq!(std::fs::read_to_string(&current).unwrap(), "work");
+}
+
+#[test]
+fn test_bare_jot_runs_default_command() {
+    let db = TestDb::new();
+    db.add_note("remember the milk", vec![], None);
+
+    db.cmd()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Usage:"));
+
+    db.save_profile(crate::profile::Profile {
+        default_command: vec![
+            "ls".to_string(),
+            "--output".to_string(),
+            "plain".to_string(),
+        ],
+        ..Default::default()
+    });
+    db.cmd()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("remember the milk"));
+
+    db.save_profile(crate::profile::Profile {
+        default_command: vec!["lss".to_string()],
+        ..Default::default()
+    });
+    db.cmd().assert().failure().stderr(predicate::str::contains(
+        "Invalid default_command in profile (lss)",
+    ));
+}
+
+#[test]
+fn test_unknown_subcommand_suggestions() {
+    let db = TestDb::new();
+
+    db.cmd()
+        .args(["serach", "meeting"])
+        .assert()
+        .code(2)
+        .stderr(
+            predicate::str::contains("unrecognized subcommand 'serach'")
+                .and(predicate::str::contains("jot note search (alias: ls)"))
+                .and(predicate::str::contains("jot search ")),
+        );
+
+    db.cmd()
+        .args(["note", "ad", "text"])
+        .assert()
+        .code(2)
+        .stderr(
+            predicate::str::contains("jot note add")
+                .and(predicate::str::contains("search").not())
+                .and(predicate::str::contains("try 'jot note --help'")),
+        );
+}
+
+#[test]
+fn test_profile_list_shows_note_counts() {
+    let db = TestDb::new();
+    db.add_note("first", vec![], None);
+    let deleted = db.add_note("second", vec![], None);
+    db.cmd()
+        .args(["note", "delete", "--yes", &deleted])
+        .assert()
+        .success();
+    std::fs::write(
+        db._temp_dir.path().join("config/jot/profiles/empty.toml"),
+        "",
+    )
+    .unwrap();
+
+    db.cmd()
+        .args(["profile", "list"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains(format!("* {} (", db.profile_name))
+                .and(predicate::str::contains("1 note(s), last modified"))
+                .and(predicate::str::contains("  empty ("))
+                .and(predicate::str::contains("no database yet")),
+        );
+    assert!(!db
+        ._temp_dir
+        .path()
+        .join("data/jot/profiles/empty/notes.db")
+        .exists());
+}
+
+#[test]
+fn test_long_operations_refuse_to_run_concurrently() {
+    let db = TestDb::new();
+    let backup = db._temp_dir.path().join("backup.txt");
+    std::fs::write(
+        &backup,
+        r#"{"items": [{"uuid": "1", "content_type": "Note", "created_at": "2024-03-16T10:00:00.000Z", "content": {"title": "T", "text": "imported"}}]}"#,
+    )
+    .unwrap();
+
+    // Another jot process in the middle of an import
+    let lock_path = crate::lock::lock_path(&db.db_path);
+    let held = std::fs::File::create(&lock_path).unwrap();
+    held.lock().unwrap();
+
+    db.cmd()
+        .args(["import", "standardnotes", backup.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Another jot operation is in progress",
+        ));
+    db.cmd()
+        .args(["db", "compact"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Another jot operation is in progress",
+        ));
+    // Ordinary commands aren't affected
+    db.cmd().args(["ls"]).assert().success();
+
+    drop(held);
+    db.cmd()
+        .args(["import", "standardnotes", backup.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 1 note(s)"));
+}
+
+#[test]
+fn test_usage_statistics() {
+    let db = TestDb::new();
+    db.add_note("tagged", vec!["work"], None);
+    db.add_note("also tagged", vec!["work", "idea"], None);
+
+    // History is opt-in
+    db.cmd().args(["usage"]).assert().success().stdout(
+        predicate::str::contains("history is off")
+            .and(predicate::str::contains("Notes created per week"))
+            .and(predicate::str::contains("#work  2  (+2)")),
+    );
+    let log = db
+        ._temp_dir
+        .path()
+        .join("data/jot/profiles")
+        .join(&db.profile_name)
+        .join("usage.log");
+    assert!(!log.exists());
+
+    db.save_profile(crate::profile::Profile {
+        usage_history: true,
+        ..Default::default()
+    });
+    db.cmd().args(["ls"]).assert().success();
+    db.cmd().args(["ls", "secret term"]).assert().success();
+    db.cmd().args(["note", "add", "hello"]).assert().success();
+
+    let recorded = std::fs::read_to_string(&log).unwrap();
+    assert!(!recorded.contains("secret"));
+    db.cmd()
+        .args(["usage", "--weeks", "2"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("ls        2")
+                .and(predicate::str::contains("note add  1"))
+                .and(predicate::str::contains("Top tags (last 2 week(s)")),
+        );
+}
+
+#[test]
+fn test_plain_structure_output() {
+    let db = TestDb::new();
+    let id = db.add_note(
+        "first line\nsecond line\nthird line",
+        vec!["work", "idea"],
+        Some("2024-03-16"),
+    );
+
+    db.cmd()
+        .args(["ls", "--plain-structure", "--lines", "1"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("Found 1 note(s)\n\nNote 1 of 1\n")
+                .and(predicate::str::contains("Date: 2024-03-16\n"))
+                .and(predicate::str::contains("Tags: work, idea\n"))
+                .and(predicate::str::contains(
+                    "Content:\nfirst line\n(2 more line(s) not shown)\n",
+                ))
+                .and(predicate::str::contains("\u{2026}").not())
+                .and(predicate::str::contains("\u{1F4CB}").not()),
+        );
+
+    db.cmd()
+        .args(["--plain-structure", "show", &id])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains(format!("ID: {}\n", id))
+                .and(predicate::str::contains(
+                    "Content:\nfirst line\nsecond line",
+                ))
+                .and(predicate::str::contains("\x1b[").not()),
+        );
+
+    db.cmd()
+        .args(["usage", "--plain-structure", "--weeks", "1"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains(": 1 note(s)\n")
+                .and(predicate::str::contains(
+                    "work: 1 note(s), 1 more than before\n",
+                ))
+                .and(predicate::str::contains("#").not()),
+        );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_note_hooks() {
+    let db = TestDb::new();
+    let added = db._temp_dir.path().join("added.json");
+    let edited = db._temp_dir.path().join("edited.txt");
+    db.save_profile(crate::profile::Profile {
+        hooks: Some(crate::profile::HooksConfig {
+            post_add: Some(format!("cat > '{}'", added.display())),
+            post_edit: Some(format!(
+                "echo \"$JOT_HOOK $JOT_NOTE_ID\" > '{}'",
+                edited.display()
+            )),
+            post_delete: Some("exit 3".to_string()),
+        }),
+        ..Default::default()
+    });
+
+    db.cmd()
+        .args(["down", "-t", "work", "hooked note"])
+        .assert()
+        .success();
+    let note: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&added).unwrap()).unwrap();
+    assert_eq!(note["content"], "hooked note");
+    assert_eq!(note["tags"], serde_json::json!(["work"]));
+    let id = note["id"].as_str().unwrap().to_string();
+
+    db.cmd()
+        .args(["note", "append", &id, "more"])
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_to_string(&edited).unwrap(),
+        format!("post_edit {}\n", id)
+    );
+
+    // A failing hook warns but the deletion stands
+    db.cmd()
+        .args(["note", "delete", "--yes", &id])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Warning: post_delete hook failed"));
+    db.cmd()
+        .args(["note", "show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deleted:"));
+
+    // Nothing runs for ephemeral databases
+    std::fs::remove_file(&added).unwrap();
+    db.cmd()
+        .args(["--ephemeral", "down", "scratch"])
+        .assert()
+        .success();
+    assert!(!added.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_plugin_dispatch() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let db = TestDb::new();
+    let bin = db._temp_dir.path().join("bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    let plugin = bin.join("jot-hello");
+    std::fs::write(
+        &plugin,
+        "#!/bin/sh\necho \"args: $*\"\necho \"profile: $JOT_PROFILE\"\necho \"db: $JOT_DB_PATH\"\nexit 3\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&plugin, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!("{}:{}", bin.display(), std::env::var("PATH").unwrap());
+
+    // Arguments are passed through untouched, and so is the exit code
+    db.cmd()
+        .env("PATH", &path)
+        .args(["hello", "world", "--loud"])
+        .assert()
+        .code(3)
+        .stdout(
+            predicate::str::contains("args: world --loud")
+                .and(predicate::str::contains(format!(
+                    "profile: {}",
+                    db.profile_name
+                )))
+                .and(predicate::str::contains(format!(
+                    "db: {}",
+                    db.db_path.display()
+                ))),
+        );
+
+    db.cmd()
+        .env("PATH", &path)
+        .args(["nosuchplugin"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "unrecognized subcommand 'nosuchplugin'",
+        ));
+}
+
+#[test]
+fn test_full_text_search() {
+    let db = TestDb::new();
+    db.add_note("meeting about the budget", vec![], None);
+    db.add_note("budget budget budget", vec![], None);
+    db.add_note("meeting notes", vec![], None);
+
+    db.cmd()
+        .args(["ls", "meeting AND budget", "--output", "plain"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("meeting about the budget")
+                .and(predicate::str::contains("budget budget budget").not())
+                .and(predicate::str::contains("meeting notes").not()),
+        );
+
+    db.cmd()
+        .args(["ls", "budg* NOT meeting", "--output", "plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("budget budget budget"));
+
+    db.cmd()
+        .args(["ls", "meeting AND"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid search query"));
+}
+
+#[test]
+fn test_note_share_qr() {
+    let db = TestDb::new();
+    let id = db.add_note("call the plumber at 5", vec![], None);
+
+    let output = db
+        .cmd()
+        .args(["note", "share", "--qr", &id])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let qr = String::from_utf8(output.stdout).unwrap();
+    // A square with a quiet zone, two module rows per line
+    let lines: Vec<&str> = qr.lines().collect();
+    let width = lines[0].chars().count();
+    assert!(lines.iter().all(|line| line.chars().count() == width));
+    assert_eq!(lines.len(), width.div_ceil(2));
+    assert!(lines[0].trim().is_empty());
+    assert!(qr.contains('\u{2588}'));
+
+    let long = db.add_note(&"x".repeat(5000), vec![], None);
+    db.cmd()
+        .args(["note", "share", "--qr", &long])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Too long for a QR code"));
+
+    db.cmd()
+        .args(["note", "share", &id])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--qr"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_note_add_exec() {
+    let db = TestDb::new();
+
+    db.cmd()
+        .args([
+            "note",
+            "add",
+            "-t",
+            "idea",
+            "--exec",
+            "echo transcribing >&2; printf '  buy more coffee\\n'",
+        ])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("transcribing"));
+    let notes = db.get_notes();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].content, "buy more coffee");
+    assert_eq!(notes[0].tags, vec!["idea", "voice"]);
+
+    db.cmd()
+        .args(["note", "add", "--exec", "exit 4"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Capture command 'exit 4' failed"));
+    db.cmd()
+        .args(["note", "add", "--exec", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("printed nothing to save"));
+    db.cmd()
+        .args(["note", "add", "--exec", "true", "typed", "text"])
+        .assert()
+        .failure();
+    assert_eq!(db.get_notes().len(), 1);
+}
+
+#[test]
+fn test_lines_option_rendering() {
+    let db = TestDb::new();
+    db.add_note("line one\nline two\nline three", vec!["work"], None);
+
+    db.cmd()
+        .args(["note", "search", "--lines", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "line one \u{2026} (+2 more lines)",
+        ))
+        .stdout(predicate::str::contains("line two").not());
+
+    db.cmd()
+        .args(["note", "last", "--lines", "0", "--output", "plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[work]\n"))
+        .stdout(predicate::str::contains("line one").not());
+
+    db.cmd()
+        .args(["note", "show", "-L", "2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "line one\nline two \u{2026} (+1 more line)",
+        ));
+}
+
+#[test]
+fn test_note_expire() {
+    let db = TestDb::new();
+    let year_ms = 366 * 24 * 60 * 60 * 1000_i64;
+    let old = chrono::Utc::now().timestamp_millis() - year_ms;
+
+    let conn = jot_core::open_db(&db.db_path).unwrap();
+    jot_core::NoteBuilder::new("old journal entry")
+        .tags(vec!["journal".to_string()])
+        .created_at(old)
+        .insert(&conn)
+        .unwrap();
+    jot_core::NoteBuilder::new("old work note")
+        .tags(vec!["work".to_string()])
+        .created_at(old)
+        .insert(&conn)
+        .unwrap();
+    drop(conn);
+    db.add_note("fresh journal entry", vec!["journal"], None);
+
+    db.cmd()
+        .args([
+            "note",
+            "expire",
+            "--keep",
+            "365d",
+            "--tag",
+            "journal",
+            "--dry-run",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "1 note(s) past their retention window",
+        ))
+        .stdout(predicate::str::contains("old journal entry"));
+    assert_eq!(db.get_notes().len(), 3);
+
+    db.cmd()
+        .args(["note", "expire", "--keep", "365d", "--tag", "journal", "-y"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deleted 1 note(s)."));
+
+    let remaining: Vec<_> = db.get_notes().into_iter().map(|n| n.content).collect();
+    assert!(remaining.contains(&"old work note".to_string()));
+    assert!(remaining.contains(&"fresh journal entry".to_string()));
+}
+
+#[test]
+fn test_note_expire_uses_profile_policy() {
+    let db = TestDb::new();
+    let old = chrono::Utc::now().timestamp_millis() - 40 * 24 * 60 * 60 * 1000_i64;
+
+    let conn = jot_core::open_db(&db.db_path).unwrap();
+    jot_core::NoteBuilder::new("stale scratch")
+        .created_at(old)
+        .insert(&conn)
+        .unwrap();
+    drop(conn);
+
+    db.cmd()
+        .args(["note", "expire"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No retention window given"));
+
+    db.save_profile(crate::profile::Profile {
+        retention: vec![crate::utils::retention::RetentionPolicy {
+            keep: crate::utils::retention::RetentionPeriod::Days(30),
+            tags: vec![],
+        }],
+        ..Default::default()
+    });
+
+    db.cmd()
+        .args(["note", "expire", "-y"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deleted 1 note(s)."));
+    assert!(db.get_notes().is_empty());
+}
+
+#[test]
+fn test_note_alias() {
+    let db = TestDb::new();
+    let id = db.add_note("standup notes", vec![], Some("2024-03-16"));
+
+    db.cmd()
+        .args(["note", "alias", "set", "standup", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Alias 'standup' now points to {}",
+            id
+        )));
+
+    db.cmd()
+        .args(["note", "append", "standup", "- shipped aliases"])
+        .assert()
+        .success();
+
+    db.cmd()
+        .args(["note", "show", "standup", "--output", "plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("standup notes\n- shipped aliases"));
+
+    db.cmd()
+        .args(["note", "alias"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("standup\t{}", id)));
+
+    db.cmd()
+        .args(["note", "alias", "set", &id, &id])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("looks like a note ID"));
+
+    db.cmd()
+        .args(["note", "alias", "rm", "standup"])
+        .assert()
+        .success();
+
+    db.cmd()
+        .args(["note", "show", "standup"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Note with ID 'standup' not found"));
+}
+
+#[test]
+fn test_inbox_workflow() {
+    let db = TestDb::new();
+
+    db.save_profile(crate::profile::Profile {
+        inbox_on_down: true,
+        ..Default::default()
+    });
+
+    db.cmd()
+        .args(["down", "call", "the", "plumber"])
+        .assert()
+        .success();
+    // Only `jot down` captures into the inbox
+    db.cmd()
+        .args(["note", "add", "filed", "away"])
+        .assert()
+        .success();
+
+    db.cmd()
+        .args(["inbox"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("call the plumber"))
+        .stdout(predicate::str::contains("filed away").not());
+
+    let id = db
+        .get_notes()
+        .into_iter()
+        .find(|n| n.content == "call the plumber")
+        .unwrap()
+        .id;
+    db.cmd()
+        .args(["inbox", "clear", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cleared note"));
+
+    db.cmd()
+        .args(["inbox"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No notes found"));
+    let note = db.get_notes().into_iter().find(|n| n.id == id).unwrap();
+    assert!(note.tags.is_empty());
+}
+
+/// Write an executable script to use as $EDITOR
+#[cfg(unix)]
+fn write_editor_script(db: &TestDb, body: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = db._temp_dir.path().join("editor.sh");
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[cfg(unix)]
+#[test]
+fn test_prune_reopens_editor_after_invalid_file() {
+    let db = TestDb::new();
+    db.add_note("prune me", vec![], Some("2024-03-16"));
+
+    // First pass writes an unknown action; the second pass (which sees the
+    // error header) fixes it to a delete
+    let marker = db._temp_dir.path().join("first-pass-done");
+    let editor = write_editor_script(
+        &db,
+        &format!(
+            r#"if [ ! -e "{marker}" ]; then
+  touch "{marker}"
+  sed -i 's/^keep /remove /' "$1"
+elif grep -q "PRUNE FILE ERROR" "$1"; then
+  sed -i 's/^remove /delete /' "$1"
+fi"#,
+            marker = marker.display()
+        ),
+    );
+
+    db.cmd()
+        .args(["note", "prune"])
+        .env("VISUAL", &editor)
+        .write_stdin("r\ny\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Invalid action 'remove'"))
+        .stdout(predicate::str::contains("Deleted 1 note(s)."));
+
+    assert!(db.get_notes().is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_prune_rejects_ids_outside_session() {
+    let db = TestDb::new();
+    let shown = db.add_note("shown note", vec!["scratch"], Some("2024-03-16"));
+    let hidden = db.add_note("hidden note", vec!["keep"], Some("2024-03-17"));
+
+    // Point the delete at a note that wasn't part of the prune session
+    let editor = write_editor_script(
+        &db,
+        &format!("sed -i 's/^keep {}/delete {}/' \"$1\"", shown, hidden),
+    );
+
+    db.cmd()
+        .args(["note", "prune", "--tag", "scratch"])
+        .env("VISUAL", &editor)
+        .write_stdin("a\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Unknown note ID '{}'",
+            hidden
+        )))
+        .stdout(predicate::str::contains("Aborted. No notes were deleted."));
+
+    assert_eq!(db.get_notes().len(), 2);
+}
+
+#[cfg(windows)]
+#[test]
+fn test_prune_runs_batch_editor_from_path_with_spaces() {
+    let db = TestDb::new();
+    db.add_note("prune me", vec![], Some("2024-03-16"));
+
+    // An unquoted VISUAL pointing at a .cmd script must be run through cmd.exe
+    let dir = db._temp_dir.path().join("my editor");
+    std::fs::create_dir_all(&dir).unwrap();
+    let editor = dir.join("edit.cmd");
+    std::fs::write(
+        &editor,
+        "@echo off\r\npowershell -NoProfile -Command \"(Get-Content -LiteralPath '%~1') -replace '^keep ','delete ' | Set-Content -LiteralPath '%~1'\"\r\n",
+    )
+    .unwrap();
+
+    db.cmd()
+        .args(["note", "prune"])
+        .env("VISUAL", &editor)
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deleted 1 note(s)."));
+
+    assert!(db.get_notes().is_empty());
+}
+
+#[test]
+fn test_archive_hides_note_from_search() {
+    let db = TestDb::new();
+
+    db.add_note("current plan", vec![], None);
+    let id = db.add_note("old plan", vec![], None);
+
+    db.cmd()
+        .args(["note", "archive", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Archived note {}", id)));
+
+    db.cmd()
+        .args(["note", "search", "plan"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("current plan"))
+        .stdout(predicate::str::contains("old plan").not());
+    db.cmd()
+        .args(["note", "search", "plan", "--archived"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("old plan"));
+    db.cmd()
+        .args(["note", "show", &id, "--output", "plain"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Archived: "));
+
+    db.cmd()
+        .args(["note", "unarchive", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Unarchived note {}", id)));
+    db.cmd()
+        .args(["note", "search", "plan"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("old plan"));
+}
+
+#[test]
+fn test_trash_list_restore_and_empty() {
+    let db = TestDb::new();
+
+    db.add_note("keep me", vec![], None);
+    let restored = db.add_note("deleted by mistake", vec![], None);
+    let purged = db.add_note("really gone", vec![], None);
+
+    db.cmd()
+        .args(["trash", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Trash is empty."));
+
+    db.cmd()
+        .args(["note", "delete", "-y", &restored, &purged])
+        .assert()
+        .success();
+    db.cmd()
+        .args(["trash", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("deleted by mistake"))
+        .stdout(predicate::str::contains("really gone"))
+        .stdout(predicate::str::contains("keep me").not());
+
+    db.cmd()
+        .args(["trash", "restore", &restored[..10]])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Restored note {}",
+            restored
+        )));
+    db.cmd()
+        .args(["trash", "restore", "ZZZZ"])
+        .assert()
+        .failure();
+
+    // Nothing was deleted a year ago
+    db.cmd()
+        .args(["trash", "empty", "--older-than", "1y", "-y"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No deleted notes to remove."));
+    db.cmd()
+        .args(["trash", "empty", "-y"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 1 deleted note(s)."));
+
+    let notes = db.get_notes();
+    assert!(notes.iter().any(|n| n.id == restored));
+    assert!(!notes.iter().any(|n| n.id == purged));
+    db.cmd()
+        .args(["trash", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Trash is empty."));
+}
@@ -0,0 +1,77 @@
+//! Opt-in local history of the commands run, for `jot usage`
+//!
+//! With `usage_history = true` in the profile, each invocation appends a
+//! line `<milliseconds>\t<command>` (e.g. `note add`) to the profile's
+//! `usage.log`. Only subcommand names are recorded, never arguments or note
+//! content, and nothing is sent anywhere.
+
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::{self, Write},
+    path::Path,
+};
+
+use anyhow::Context;
+use clap::ArgMatches;
+
+/// Subcommand path of an invocation, e.g. `note add` (`None` for bare `jot`)
+pub fn command_path(matches: &ArgMatches) -> Option<String> {
+    let mut names = Vec::new();
+    let mut current = matches;
+    while let Some((name, sub)) = current.subcommand() {
+        names.push(name);
+        current = sub;
+    }
+    (!names.is_empty()).then(|| names.join(" "))
+}
+
+/// Append one use of `command` to the log at `path`
+pub fn record(path: &Path, command: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(
+        file,
+        "{}\t{}",
+        chrono::Utc::now().timestamp_millis(),
+        command
+    )
+}
+
+/// What the log at a path says
+#[derive(Debug, Default)]
+pub struct CommandCounts {
+    /// Commands with their use counts, most used first
+    pub counts: Vec<(String, usize)>,
+    /// Time of the first entry (`None` when nothing was recorded yet)
+    pub since: Option<i64>,
+}
+
+/// Count the uses of each command in the log at `path`
+pub fn command_counts(path: &Path) -> anyhow::Result<CommandCounts> {
+    if !path.exists() {
+        return Ok(CommandCounts::default());
+    }
+    let log = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut since = None;
+    // Lines that don't parse (e.g. cut short by a crash) are skipped
+    for (at, command) in log.lines().filter_map(|line| line.split_once('\t')) {
+        let Ok(at) = at.parse::<i64>() else {
+            continue;
+        };
+        since = Some(since.map_or(at, |since: i64| since.min(at)));
+        *counts.entry(command).or_default() += 1;
+    }
+
+    let mut counts: Vec<(String, usize)> = counts
+        .into_iter()
+        .map(|(command, count)| (command.to_string(), count))
+        .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    Ok(CommandCounts { counts, since })
+}
@@ -0,0 +1,51 @@
+use std::{collections::BTreeMap, path::Path, process::Command};
+
+use crate::app_config::AppConfig;
+
+/// Where a note is being captured, when the profile opts in with
+/// `capture_context` (nothing otherwise)
+///
+/// `cwd` and `host` when they can be read; `repo` (the repository folder's
+/// name) and `branch` when the working directory is inside a git repository.
+pub fn capture_context(config: &AppConfig) -> BTreeMap<String, String> {
+    let mut meta = BTreeMap::new();
+    if !config.capture_context {
+        return meta;
+    }
+    if let Ok(cwd) = std::env::current_dir() {
+        meta.insert("cwd".to_string(), cwd.display().to_string());
+    }
+    if let Some(top) = git(&["rev-parse", "--show-toplevel"]) {
+        if let Some(name) = Path::new(&top).file_name() {
+            meta.insert("repo".to_string(), name.to_string_lossy().to_string());
+        }
+        // Fails on a detached HEAD, which has no branch to record
+        if let Some(branch) = git(&["symbolic-ref", "--short", "-q", "HEAD"]) {
+            meta.insert("branch".to_string(), branch);
+        }
+    }
+    if let Some(host) = hostname() {
+        meta.insert("host".to_string(), host);
+    }
+    meta
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    output(Command::new("git").args(args))
+}
+
+fn hostname() -> Option<String> {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|host| !host.is_empty())
+        .or_else(|| output(&mut Command::new("hostname")))
+}
+
+/// Trimmed stdout of a command that succeeded with some output
+fn output(command: &mut Command) -> Option<String> {
+    let output = command.stderr(std::process::Stdio::null()).output().ok()?;
+    let text = String::from_utf8(output.stdout).ok()?;
+    let text = text.trim();
+    (output.status.success() && !text.is_empty()).then(|| text.to_string())
+}
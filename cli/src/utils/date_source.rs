@@ -1,7 +1,7 @@
 use std::fmt;
 use std::str::FromStr;
 
-use chrono::{Days, Local, NaiveDate};
+use chrono::{Days, NaiveDate};
 use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Default)]
@@ -51,22 +51,23 @@ impl<'de> Deserialize<'de> for DateSource {
 
 impl fmt::Display for DateSource {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_date().format("%Y-%m-%d"))
-    }
-}
-
-impl From<DateSource> for NaiveDate {
-    fn from(ds: DateSource) -> Self {
-        ds.to_date()
+        match self {
+            Self::Today => write!(f, "today"),
+            Self::Yesterday => write!(f, "yesterday"),
+            Self::Tomorrow => write!(f, "tomorrow"),
+            Self::Specific(dt) => write!(f, "{}", dt.format("%Y-%m-%d")),
+        }
     }
 }
 
 impl DateSource {
-    pub fn to_date(&self) -> NaiveDate {
+    /// The date this stands for, relative to `today` (the profile's, see
+    /// [`crate::utils::time_display::TimeDisplay::today`])
+    pub fn to_date(&self, today: NaiveDate) -> NaiveDate {
         match self {
-            Self::Today => Local::now().date_naive(),
-            Self::Yesterday => Local::now().date_naive() - Days::new(1),
-            Self::Tomorrow => Local::now().date_naive() + Days::new(1),
+            Self::Today => today,
+            Self::Yesterday => today - Days::new(1),
+            Self::Tomorrow => today + Days::new(1),
             Self::Specific(dt) => *dt,
         }
     }
@@ -93,6 +94,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_relative_dates_follow_given_today() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        assert_eq!(DateSource::Today.to_date(today), today);
+        assert_eq!(
+            DateSource::Yesterday.to_date(today),
+            NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()
+        );
+        assert_eq!(
+            DateSource::Tomorrow.to_date(today).to_string(),
+            "2024-03-02"
+        );
+        assert_eq!(DateSource::Yesterday.to_string(), "yesterday");
+    }
+
     #[test]
     fn test_specific_date_parsing() {
         let date = DateSource::from_str("2024-03-16").unwrap();
@@ -1,7 +1,9 @@
 use std::fmt;
 use std::str::FromStr;
 
-use chrono::{Days, Local, NaiveDate};
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+
+use crate::error::{CliError, ErrorKind};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
@@ -15,15 +17,26 @@ pub enum DateTarget {
     LastMonth,
     NextWeek,
     NextMonth,
+    /// Calendar week containing today, starting on the profile's week start
+    ThisWeek,
+    /// Calendar month containing today
+    ThisMonth,
+    /// Numbered week of the current year; week 1 contains January 4th
+    Week(u32),
+    /// Whole calendar month, stored as its first day
+    Month(NaiveDate),
     Specific(NaiveDate),
 }
 
 impl DateTarget {
-    /// Convert DateTarget to a date range (from, to).
+    /// Convert DateTarget to a date range (from, to), relative to `today`
+    /// (the profile's, see [`crate::utils::time_display::TimeDisplay::today`]).
     /// Returns (None, None) for All, (Some, None) for Past/Future, (Some, Some) for specific ranges.
-    pub fn to_date_range(&self) -> (Option<NaiveDate>, Option<NaiveDate>) {
-        let today = Local::now().date_naive();
-
+    pub fn to_date_range(
+        &self,
+        today: NaiveDate,
+        week_start: Weekday,
+    ) -> (Option<NaiveDate>, Option<NaiveDate>) {
         match self {
             DateTarget::All => (None, None),
             DateTarget::Past => (None, Some(today.pred_opt().unwrap_or(today))),
@@ -57,11 +70,54 @@ impl DateTarget {
                 let end = today.checked_add_days(Days::new(30)).unwrap_or(today);
                 (Some(start), Some(end))
             }
+            DateTarget::ThisWeek => week_range(start_of_week(today, week_start)),
+            DateTarget::ThisMonth => month_range(today.with_day(1).unwrap_or(today)),
+            DateTarget::Week(number) => {
+                let jan4 = NaiveDate::from_ymd_opt(today.year(), 1, 4).unwrap_or(today);
+                let first = start_of_week(jan4, week_start);
+                match first.checked_add_days(Days::new(7 * u64::from(number.saturating_sub(1)))) {
+                    Some(start) => week_range(start),
+                    None => (None, None),
+                }
+            }
+            DateTarget::Month(first) => month_range(*first),
             DateTarget::Specific(date) => (Some(*date), Some(*date)),
         }
     }
 }
 
+/// Parse the profile's `week_start` (e.g. "monday", "sun"); Monday when unset
+pub fn parse_week_start(name: Option<&str>) -> anyhow::Result<Weekday> {
+    match name {
+        None => Ok(Weekday::Mon),
+        Some(name) => name.parse::<Weekday>().map_err(|_| {
+            CliError::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid week_start '{}' (use a day name like monday)", name),
+            )
+            .into()
+        }),
+    }
+}
+
+/// First day of the week `date` is in
+pub fn start_of_week(date: NaiveDate, week_start: Weekday) -> NaiveDate {
+    let offset = date.weekday().days_since(week_start);
+    date.checked_sub_days(Days::new(u64::from(offset)))
+        .unwrap_or(date)
+}
+
+fn week_range(start: NaiveDate) -> (Option<NaiveDate>, Option<NaiveDate>) {
+    (Some(start), start.checked_add_days(Days::new(6)))
+}
+
+fn month_range(first: NaiveDate) -> (Option<NaiveDate>, Option<NaiveDate>) {
+    let last = first
+        .checked_add_months(Months::new(1))
+        .and_then(|d| d.pred_opt());
+    (Some(first), last)
+}
+
 impl FromStr for DateTarget {
     type Err = anyhow::Error;
 
@@ -76,6 +132,18 @@ impl FromStr for DateTarget {
             "last month" => Ok(Self::LastMonth),
             "next week" => Ok(Self::NextWeek),
             "next month" => Ok(Self::NextMonth),
+            "this week" => Ok(Self::ThisWeek),
+            "this month" => Ok(Self::ThisMonth),
+            _ if s.starts_with("week ") => match s["week ".len()..].trim().parse::<u32>() {
+                Ok(number @ 1..=53) => Ok(Self::Week(number)),
+                _ => anyhow::bail!("Invalid date target: week number must be between 1 and 53"),
+            },
+            _ if s.len() == 7 => {
+                match NaiveDate::parse_from_str(&format!("{}-01", s), "%Y-%m-%d") {
+                    Ok(first) => Ok(Self::Month(first)),
+                    Err(e) => anyhow::bail!("Invalid date target: {}", e),
+                }
+            }
             _ => match NaiveDate::parse_from_str(s, "%Y-%m-%d") {
                 Ok(dt) => Ok(Self::Specific(dt)),
                 Err(e) => anyhow::bail!("Invalid date target: {}", e),
@@ -96,6 +164,10 @@ impl fmt::Display for DateTarget {
             DateTarget::LastMonth => write!(f, "last month"),
             DateTarget::NextWeek => write!(f, "next week"),
             DateTarget::NextMonth => write!(f, "next month"),
+            DateTarget::ThisWeek => write!(f, "this week"),
+            DateTarget::ThisMonth => write!(f, "this month"),
+            DateTarget::Week(number) => write!(f, "week {}", number),
+            DateTarget::Month(first) => write!(f, "{}", first.format("%Y-%m")),
             DateTarget::Specific(dt) => write!(f, "{}", dt),
         }
     }
@@ -161,6 +233,50 @@ mod tests {
         assert_eq!(DateTarget::NextMonth.to_string(), "next month");
     }
 
+    #[test]
+    fn test_calendar_targets() {
+        // A Wednesday
+        let today = NaiveDate::from_ymd_opt(2025, 10, 15).unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+
+        let this_week = "this week".parse::<DateTarget>().unwrap();
+        assert_eq!(
+            this_week.to_date_range(today, Weekday::Mon),
+            (date(2025, 10, 13), date(2025, 10, 19))
+        );
+        assert_eq!(
+            this_week.to_date_range(today, Weekday::Sun),
+            (date(2025, 10, 12), date(2025, 10, 18))
+        );
+
+        assert_eq!(
+            DateTarget::ThisMonth.to_date_range(today, Weekday::Mon),
+            (date(2025, 10, 1), date(2025, 10, 31))
+        );
+
+        // ISO week 42 of 2025 runs Monday 13th to Sunday 19th October
+        let week = "week 42".parse::<DateTarget>().unwrap();
+        assert_eq!(week, DateTarget::Week(42));
+        assert_eq!(
+            week.to_date_range(today, Weekday::Mon),
+            (date(2025, 10, 13), date(2025, 10, 19))
+        );
+        assert_eq!(week.to_string(), "week 42");
+
+        let month = "2024-02".parse::<DateTarget>().unwrap();
+        assert_eq!(
+            month.to_date_range(today, Weekday::Mon),
+            (date(2024, 2, 1), date(2024, 2, 29))
+        );
+        assert_eq!(month.to_string(), "2024-02");
+
+        assert!("week 0".parse::<DateTarget>().is_err());
+        assert!("week 54".parse::<DateTarget>().is_err());
+        assert!("2024-13".parse::<DateTarget>().is_err());
+        assert!(parse_week_start(Some("sunday")).is_ok());
+        assert!(parse_week_start(Some("someday")).is_err());
+    }
+
     #[test]
     fn test_specific_date_parsing() {
         let date = DateTarget::from_str("2024-03-16").unwrap();
@@ -0,0 +1,72 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How often something repeats, e.g. `30s`, `5m` or `1h`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval(pub Duration);
+
+impl FromStr for Interval {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(|| anyhow::anyhow!("Missing unit in '{}' (use s, m or h)", s))?;
+        let (amount, unit) = s.split_at(split);
+        let amount: u64 = amount
+            .parse()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| anyhow::anyhow!("Invalid interval '{}' (e.g. 30s, 5m, 1h)", s))?;
+
+        let seconds = match unit {
+            "s" => amount,
+            "m" => amount.saturating_mul(60),
+            "h" => amount.saturating_mul(60 * 60),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Unknown unit '{}' in '{}' (use s, m or h)",
+                    unit,
+                    s
+                ))
+            }
+        };
+        Ok(Interval(Duration::from_secs(seconds)))
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.0.as_secs();
+        if seconds > 0 && seconds.is_multiple_of(3600) {
+            write!(f, "{}h", seconds / 3600)
+        } else if seconds > 0 && seconds.is_multiple_of(60) {
+            write!(f, "{}m", seconds / 60)
+        } else {
+            write!(f, "{}s", seconds)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn test_parse_interval() {
+        let parse = |s: &str| s.parse::<Interval>().map(|i| i.0.as_secs());
+        assert_eq!(parse("30s").unwrap(), 30);
+        assert_eq!(parse("5m").unwrap(), 300);
+        assert_eq!(parse("2h").unwrap(), 7200);
+        assert!(parse("5").is_err());
+        assert!(parse("0m").is_err());
+        assert!(parse("1d").is_err());
+
+        assert_eq!(Interval(Duration::from_secs(300)).to_string(), "5m");
+        assert_eq!(Interval(Duration::from_secs(90)).to_string(), "90s");
+    }
+}
@@ -1,2 +1,14 @@
+pub mod capture_context;
 pub mod date_source;
 pub mod date_target;
+pub mod interval;
+pub mod process;
+pub mod qr;
+pub mod query;
+pub mod recurrence;
+pub mod retention;
+pub mod tag_suggest;
+pub mod tasks;
+pub mod template;
+pub mod text;
+pub mod time_display;
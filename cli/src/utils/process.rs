@@ -0,0 +1,19 @@
+use std::process::Command;
+
+/// `command` run through the platform shell, so pipes, quoting and `~` work
+/// as the user typed them
+#[cfg(windows)]
+pub fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.args(["/C", command]);
+    shell
+}
+
+/// `command` run through the platform shell, so pipes, quoting and `~` work
+/// as the user typed them
+#[cfg(not(windows))]
+pub fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+}
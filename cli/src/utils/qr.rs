@@ -0,0 +1,30 @@
+use qrcode::{render::unicode::Dense1x2, types::QrError, EcLevel, QrCode};
+
+use crate::error::{CliError, ErrorKind};
+
+/// `data` as a QR code drawn with Unicode half blocks (two modules per
+/// character row), quiet zone included
+///
+/// Low error correction leaves the most room for text; a screen is a clean
+/// enough surface to scan from. `invert` swaps which modules are drawn.
+pub fn render_qr(data: &str, invert: bool) -> anyhow::Result<String> {
+    let code = QrCode::with_error_correction_level(data, EcLevel::L).map_err(|e| match e {
+        QrError::DataTooLong => CliError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Too long for a QR code ({} bytes; about 2900 fit)",
+                data.len()
+            ),
+        )
+        .into(),
+        e => anyhow::anyhow!("Failed to build QR code: {}", e),
+    })?;
+
+    let mut renderer = code.render::<Dense1x2>();
+    if invert {
+        renderer
+            .dark_color(Dense1x2::Light)
+            .light_color(Dense1x2::Dark);
+    }
+    Ok(renderer.build())
+}
@@ -0,0 +1,300 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+use jot_core::SearchQuery;
+
+use crate::{
+    error::{CliError, ErrorKind},
+    utils::date_target::DateTarget,
+};
+
+/// Parse a search term written in the mini query language
+///
+/// Words are split on whitespace, and double quotes keep a phrase together.
+/// `tag:x` (or `tag:x,y`) requires tags, `-tag:x` excludes them and `date:`
+/// takes anything `--date` does plus `YYYY` and `from..to` ranges.
+/// Everything else is free text, matched as one phrase, unless it uses
+/// `AND`, `OR`, `NOT` or a `prefix*`: then it becomes a ranked full-text
+/// query, with each word and quoted phrase matched as a whole token.
+/// Relative dates like `date:yesterday` count from `today`.
+pub fn parse_query(
+    input: &str,
+    today: NaiveDate,
+    week_start: Weekday,
+) -> anyhow::Result<SearchQuery> {
+    let mut query = SearchQuery::default();
+    let mut text = Vec::new();
+    let mut seen_date = false;
+
+    for (word, quoted) in split_words(input) {
+        if quoted {
+            text.push((word, quoted));
+            continue;
+        }
+
+        if let Some(tags) = word.strip_prefix("tag:") {
+            query.tags.extend(parse_tags(tags)?);
+        } else if let Some(tags) = word.strip_prefix("-tag:") {
+            query.exclude_tags.extend(parse_tags(tags)?);
+        } else if let Some(date) = word.strip_prefix("date:") {
+            if seen_date {
+                return Err(invalid("only one date: filter is allowed".to_string()));
+            }
+            seen_date = true;
+            (query.date_from, query.date_to) = parse_date_range(date, today, week_start)?;
+        } else {
+            text.push((word, quoted));
+        }
+    }
+
+    if text
+        .iter()
+        .any(|(word, quoted)| !quoted && is_match_syntax(word))
+    {
+        query.match_query = Some(match_expression(&text));
+    } else if !text.is_empty() {
+        let words: Vec<String> = text.into_iter().map(|(word, _)| word).collect();
+        query.text = Some(words.join(" "));
+    }
+    Ok(query)
+}
+
+/// FTS5 operators that switch a search to full-text matching
+const MATCH_OPERATORS: [&str; 3] = ["AND", "OR", "NOT"];
+
+fn is_match_syntax(word: &str) -> bool {
+    MATCH_OPERATORS.contains(&word) || (word.len() > 1 && word.trim_end_matches(')').ends_with('*'))
+}
+
+/// FTS5 query for the free-text words: operators and parentheses are kept,
+/// everything else is quoted so punctuation in it can't break the syntax
+fn match_expression(words: &[(String, bool)]) -> String {
+    let quote = |text: &str| format!("\"{}\"", text.replace('"', "\"\""));
+    words
+        .iter()
+        .map(|(word, quoted)| {
+            if !quoted && MATCH_OPERATORS.contains(&word.as_str()) {
+                return word.clone();
+            }
+            // Parentheses around a phrase group it; as text they'd be dropped
+            // by the tokenizer anyway
+            let inner = word.trim_start_matches('(');
+            let open = &word[..word.len() - inner.len()];
+            let term = inner.trim_end_matches(')');
+            let close = &inner[term.len()..];
+            let prefix = term.strip_suffix('*').filter(|t| !quoted && !t.is_empty());
+            let term = match prefix {
+                Some(prefix) => format!("{}*", quote(prefix)),
+                None if term.is_empty() => String::new(),
+                None => quote(term),
+            };
+            format!("{}{}{}", open, term, close)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Split on whitespace outside double quotes, flagging words that started
+/// with a quote so `"tag:x"` stays plain text
+fn split_words(input: &str) -> Vec<(String, bool)> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut started = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                if !started {
+                    quoted = true;
+                }
+                in_quotes = !in_quotes;
+                started = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if started {
+                    words.push((std::mem::take(&mut current), quoted));
+                }
+                quoted = false;
+                started = false;
+            }
+            c => {
+                current.push(c);
+                started = true;
+            }
+        }
+    }
+    if started {
+        words.push((current, quoted));
+    }
+
+    words.retain(|(word, _)| !word.is_empty());
+    words
+}
+
+fn parse_tags(value: &str) -> anyhow::Result<Vec<String>> {
+    let tags: Vec<String> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect();
+    if tags.is_empty() {
+        return Err(invalid("tag: needs a tag name".to_string()));
+    }
+    Ok(tags)
+}
+
+/// `from..to` with either side optional, or a single date value
+fn parse_date_range(
+    value: &str,
+    today: NaiveDate,
+    week_start: Weekday,
+) -> anyhow::Result<(Option<NaiveDate>, Option<NaiveDate>)> {
+    match value.split_once("..") {
+        Some((from, to)) => {
+            let from = match from {
+                "" => None,
+                from => parse_date(from, today, week_start)?.0,
+            };
+            let to = match to {
+                "" => None,
+                to => parse_date(to, today, week_start)?.1,
+            };
+            Ok((from, to))
+        }
+        None => parse_date(value, today, week_start),
+    }
+}
+
+/// Anything `--date` accepts, or a whole year
+fn parse_date(
+    value: &str,
+    today: NaiveDate,
+    week_start: Weekday,
+) -> anyhow::Result<(Option<NaiveDate>, Option<NaiveDate>)> {
+    if let Ok(target) = value.parse::<DateTarget>() {
+        return Ok(target.to_date_range(today, week_start));
+    }
+
+    let year_start = match value.len() {
+        4 => NaiveDate::parse_from_str(&format!("{}-01-01", value), "%Y-%m-%d").ok(),
+        _ => None,
+    };
+    let Some(start) = year_start else {
+        return Err(invalid(format!(
+            "Invalid date '{}' (use YYYY-MM-DD, YYYY-MM, YYYY or a word like 'today')",
+            value
+        )));
+    };
+    Ok((Some(start), NaiveDate::from_ymd_opt(start.year(), 12, 31)))
+}
+
+fn invalid(message: String) -> anyhow::Error {
+    CliError::new(
+        ErrorKind::InvalidInput,
+        format!("Invalid search term: {}", message),
+    )
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    fn date(s: &str) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 16).unwrap()
+    }
+
+    #[test]
+    fn test_parse_query_filters_and_text() {
+        let query = parse_query(
+            r#"tag:work -tag:draft date:2025-01 "exact phrase""#,
+            today(),
+            Weekday::Mon,
+        )
+        .unwrap();
+        assert_eq!(query.tags, vec!["work"]);
+        assert_eq!(query.exclude_tags, vec!["draft"]);
+        assert_eq!(query.date_from, date("2025-01-01"));
+        assert_eq!(query.date_to, date("2025-01-31"));
+        assert_eq!(query.text.as_deref(), Some("exact phrase"));
+
+        let query = parse_query(
+            r#"meeting tag:a,b "tag:literal" notes"#,
+            today(),
+            Weekday::Mon,
+        )
+        .unwrap();
+        assert_eq!(query.tags, vec!["a", "b"]);
+        assert_eq!(query.text.as_deref(), Some("meeting tag:literal notes"));
+
+        let query = parse_query("plain words only", today(), Weekday::Mon).unwrap();
+        assert_eq!(query.text.as_deref(), Some("plain words only"));
+        assert!(query.tags.is_empty());
+    }
+
+    #[test]
+    fn test_parse_query_full_text() {
+        let query = parse_query("meeting AND budget tag:work", today(), Weekday::Mon).unwrap();
+        assert_eq!(
+            query.match_query.as_deref(),
+            Some(r#""meeting" AND "budget""#)
+        );
+        assert_eq!(query.text, None);
+        assert_eq!(query.tags, vec!["work"]);
+
+        let query = parse_query(r#"(plan* OR "road map") NOT q3"#, today(), Weekday::Mon).unwrap();
+        assert_eq!(
+            query.match_query.as_deref(),
+            Some(r#"("plan"* OR "road map") NOT "q3""#)
+        );
+
+        // Lowercase words and quoted operators stay a plain phrase
+        let query = parse_query(r#"this and "OR" that"#, today(), Weekday::Mon).unwrap();
+        assert_eq!(query.match_query, None);
+        assert_eq!(query.text.as_deref(), Some("this and OR that"));
+    }
+
+    #[test]
+    fn test_parse_query_dates() {
+        let query = parse_query("date:2024", today(), Weekday::Mon).unwrap();
+        assert_eq!(query.date_from, date("2024-01-01"));
+        assert_eq!(query.date_to, date("2024-12-31"));
+
+        let query = parse_query("date:2024-02", today(), Weekday::Mon).unwrap();
+        assert_eq!(query.date_to, date("2024-02-29"));
+
+        let query = parse_query("date:2024-03-16", today(), Weekday::Mon).unwrap();
+        assert_eq!(query.date_from, date("2024-03-16"));
+        assert_eq!(query.date_to, date("2024-03-16"));
+
+        let query = parse_query("date:2024-01..2024-03", today(), Weekday::Mon).unwrap();
+        assert_eq!(query.date_from, date("2024-01-01"));
+        assert_eq!(query.date_to, date("2024-03-31"));
+
+        let query = parse_query("date:2024-06..", today(), Weekday::Mon).unwrap();
+        assert_eq!(query.date_from, date("2024-06-01"));
+        assert_eq!(query.date_to, None);
+
+        let query = parse_query(r#"date:"last week""#, today(), Weekday::Mon).unwrap();
+        assert_eq!(query.date_from, date("2024-03-09"));
+        assert_eq!(query.date_to, date("2024-03-15"));
+
+        let query = parse_query("date:yesterday", today(), Weekday::Mon).unwrap();
+        assert_eq!(query.date_from, date("2024-03-15"));
+    }
+
+    #[test]
+    fn test_parse_query_rejects_bad_filters() {
+        assert!(parse_query("date:someday", today(), Weekday::Mon).is_err());
+        assert!(parse_query("date:2024-13", today(), Weekday::Mon).is_err());
+        assert!(parse_query("tag:", today(), Weekday::Mon).is_err());
+        assert!(parse_query("date:2024 date:2025", today(), Weekday::Mon).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+
+use crate::error::{CliError, ErrorKind};
+
+/// How often a recurring note comes back (`jot recur add --every ...`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Every {
+    Day,
+    /// Monday to Friday
+    Workday,
+    /// Every 7 days from the first occurrence
+    Week,
+    /// On this day of each week
+    Weekday(Weekday),
+    /// On the first occurrence's day of each month (or the month's last day)
+    Month,
+}
+
+impl FromStr for Every {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "day" | "daily" => Ok(Self::Day),
+            "weekday" | "weekdays" | "workday" => Ok(Self::Workday),
+            "week" | "weekly" => Ok(Self::Week),
+            "month" | "monthly" => Ok(Self::Month),
+            name => name.parse::<Weekday>().map(Self::Weekday).map_err(|_| {
+                CliError::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Invalid interval '{}' (use day, weekday, week, month or a day name like monday)",
+                        s
+                    ),
+                )
+                .into()
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Every {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Day => write!(f, "day"),
+            Self::Workday => write!(f, "weekday"),
+            Self::Week => write!(f, "week"),
+            Self::Month => write!(f, "month"),
+            Self::Weekday(day) => write!(f, "{}", day_name(*day)),
+        }
+    }
+}
+
+impl Every {
+    /// First occurrence on or after `date`
+    pub fn first_on_or_after(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Weekday(day) => {
+                let offset = day.days_since(date.weekday());
+                date.checked_add_days(Days::new(u64::from(offset)))
+                    .unwrap_or(date)
+            }
+            Self::Workday => skip_weekend(date),
+            Self::Day | Self::Week | Self::Month => date,
+        }
+    }
+
+    /// Occurrence after `current`, for a rule that started on `start`
+    pub fn next_after(&self, start: NaiveDate, current: NaiveDate) -> NaiveDate {
+        let next = match self {
+            Self::Day => current.succ_opt(),
+            Self::Workday => current.succ_opt().map(skip_weekend),
+            Self::Week | Self::Weekday(_) => current.checked_add_days(Days::new(7)),
+            // Counted from the start so the 31st comes back after a short month
+            Self::Month => {
+                let months = (current.year() - start.year()) * 12 + current.month() as i32
+                    - start.month() as i32
+                    + 1;
+                u32::try_from(months)
+                    .ok()
+                    .and_then(|m| start.checked_add_months(Months::new(m)))
+            }
+        };
+        next.unwrap_or(NaiveDate::MAX)
+    }
+}
+
+fn skip_weekend(date: NaiveDate) -> NaiveDate {
+    let skip = match date.weekday() {
+        Weekday::Sat => 2,
+        Weekday::Sun => 1,
+        _ => 0,
+    };
+    date.checked_add_days(Days::new(skip)).unwrap_or(date)
+}
+
+fn day_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_every_parsing_and_steps() {
+        assert_eq!("Mon".parse::<Every>().unwrap().to_string(), "monday");
+        assert_eq!("weekly".parse::<Every>().unwrap(), Every::Week);
+        assert!("fortnight".parse::<Every>().is_err());
+
+        // 2025-01-08 is a Wednesday
+        let monday = Every::Weekday(Weekday::Mon);
+        assert_eq!(
+            monday.first_on_or_after(date("2025-01-08")),
+            date("2025-01-13")
+        );
+        assert_eq!(
+            monday.first_on_or_after(date("2025-01-13")),
+            date("2025-01-13")
+        );
+        assert_eq!(
+            monday.next_after(date("2025-01-13"), date("2025-01-13")),
+            date("2025-01-20")
+        );
+
+        let workday = Every::Workday;
+        assert_eq!(
+            workday.first_on_or_after(date("2025-01-11")),
+            date("2025-01-13")
+        );
+        assert_eq!(
+            workday.next_after(date("2025-01-06"), date("2025-01-10")),
+            date("2025-01-13")
+        );
+
+        let start = date("2025-01-31");
+        let feb = Every::Month.next_after(start, start);
+        assert_eq!(feb, date("2025-02-28"));
+        assert_eq!(Every::Month.next_after(start, feb), date("2025-03-31"));
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Days, Local, Months};
+use serde::{Deserialize, Serialize};
+
+/// How long notes are kept before `jot note expire` removes them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum RetentionPeriod {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    Years(u32),
+}
+
+impl RetentionPeriod {
+    /// Notes created at or before this instant are past the retention window
+    pub fn cutoff(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        match *self {
+            RetentionPeriod::Days(n) => now.checked_sub_days(Days::new(n.into())),
+            RetentionPeriod::Weeks(n) => now.checked_sub_days(Days::new(u64::from(n) * 7)),
+            RetentionPeriod::Months(n) => now.checked_sub_months(Months::new(n)),
+            RetentionPeriod::Years(n) => now.checked_sub_months(Months::new(n.saturating_mul(12))),
+        }
+    }
+}
+
+impl FromStr for RetentionPeriod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(|| anyhow::anyhow!("Missing unit in '{}' (use d, w, m or y)", s))?;
+        let (amount, unit) = s.split_at(split);
+        let amount: u32 = amount.parse().map_err(|_| {
+            anyhow::anyhow!("Invalid retention period '{}' (e.g. 30d, 12w, 6m, 1y)", s)
+        })?;
+
+        match unit {
+            "d" => Ok(RetentionPeriod::Days(amount)),
+            "w" => Ok(RetentionPeriod::Weeks(amount)),
+            "m" => Ok(RetentionPeriod::Months(amount)),
+            "y" => Ok(RetentionPeriod::Years(amount)),
+            _ => Err(anyhow::anyhow!(
+                "Unknown unit '{}' in '{}' (use d, w, m or y)",
+                unit,
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for RetentionPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RetentionPeriod::Days(n) => write!(f, "{}d", n),
+            RetentionPeriod::Weeks(n) => write!(f, "{}w", n),
+            RetentionPeriod::Months(n) => write!(f, "{}m", n),
+            RetentionPeriod::Years(n) => write!(f, "{}y", n),
+        }
+    }
+}
+
+impl TryFrom<String> for RetentionPeriod {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<RetentionPeriod> for String {
+    fn from(value: RetentionPeriod) -> Self {
+        value.to_string()
+    }
+}
+
+/// A profile-level rule: notes with all of `tags` are kept for `keep`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub keep: RetentionPeriod,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_retention_period() {
+        assert_eq!(
+            "365d".parse::<RetentionPeriod>().unwrap(),
+            RetentionPeriod::Days(365)
+        );
+        assert_eq!(
+            "12w".parse::<RetentionPeriod>().unwrap(),
+            RetentionPeriod::Weeks(12)
+        );
+        assert_eq!(
+            "6m".parse::<RetentionPeriod>().unwrap(),
+            RetentionPeriod::Months(6)
+        );
+        assert_eq!(
+            "1y".parse::<RetentionPeriod>().unwrap(),
+            RetentionPeriod::Years(1)
+        );
+        assert!("365".parse::<RetentionPeriod>().is_err());
+        assert!("d".parse::<RetentionPeriod>().is_err());
+        assert!("3h".parse::<RetentionPeriod>().is_err());
+    }
+
+    #[test]
+    fn test_retention_cutoff() {
+        let now = Local.with_ymd_and_hms(2024, 3, 16, 12, 0, 0).unwrap();
+        let cutoff = |p: &str| {
+            p.parse::<RetentionPeriod>()
+                .unwrap()
+                .cutoff(now)
+                .unwrap()
+                .date_naive()
+                .to_string()
+        };
+
+        assert_eq!(cutoff("10d"), "2024-03-06");
+        assert_eq!(cutoff("2w"), "2024-03-02");
+        assert_eq!(cutoff("1m"), "2024-02-16");
+        assert_eq!(cutoff("1y"), "2023-03-16");
+    }
+
+    #[test]
+    fn test_retention_policy_from_toml() {
+        let policy: RetentionPolicy =
+            toml::from_str("keep = \"365d\"\ntags = [\"journal\"]").unwrap();
+        assert_eq!(policy.keep, RetentionPeriod::Days(365));
+        assert_eq!(policy.tags, vec!["journal"]);
+    }
+}
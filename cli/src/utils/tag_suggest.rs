@@ -0,0 +1,211 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use jot_core::Note;
+
+/// Words too common to say anything about a note's topic
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "with", "that", "this", "from", "have", "has", "was", "are", "but", "not",
+    "you", "your", "our", "all", "any", "can", "will", "just", "about", "into", "out", "then",
+    "than", "them", "they", "what", "when", "who", "how", "why", "also", "some", "more",
+];
+
+/// Suggestions below this score are left out
+const MIN_SCORE: f64 = 0.2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagSuggestion {
+    pub tag: String,
+    pub score: f64,
+    /// Short explanation, e.g. `used with "standup"`
+    pub reason: String,
+}
+
+/// Rank tags for `content` from how past notes were tagged
+///
+/// A tag scores from three signals: it appears as a word in the content,
+/// notes sharing the content's words carried it, and it co-occurs with the
+/// tags already chosen. Tags in `current_tags` are never suggested.
+pub fn suggest_tags(
+    history: &[Note],
+    content: &str,
+    current_tags: &[String],
+    limit: usize,
+) -> Vec<TagSuggestion> {
+    let words = keywords(content);
+
+    // word -> notes containing it, and word -> tag -> notes with both
+    let mut word_notes: HashMap<String, usize> = HashMap::new();
+    let mut word_tags: HashMap<String, HashMap<&str, usize>> = HashMap::new();
+    // tag -> notes, and tag -> other tag -> notes with both
+    let mut tag_notes: HashMap<&str, usize> = HashMap::new();
+    let mut tag_pairs: HashMap<&str, HashMap<&str, usize>> = HashMap::new();
+
+    for note in history.iter().filter(|n| !n.tags.is_empty()) {
+        for word in keywords(&note.content)
+            .into_iter()
+            .filter(|w| words.contains(w))
+        {
+            *word_notes.entry(word.clone()).or_default() += 1;
+            let counts = word_tags.entry(word).or_default();
+            for tag in &note.tags {
+                *counts.entry(tag).or_default() += 1;
+            }
+        }
+        for tag in &note.tags {
+            *tag_notes.entry(tag).or_default() += 1;
+            let pairs = tag_pairs.entry(tag).or_default();
+            for other in note.tags.iter().filter(|t| *t != tag) {
+                *pairs.entry(other).or_default() += 1;
+            }
+        }
+    }
+
+    // tag -> (score, strongest reason)
+    let mut scores: BTreeMap<&str, (f64, f64, String)> = BTreeMap::new();
+    let mut add = |tag: &str, score: f64, reason: String| {
+        if current_tags.iter().any(|t| t == tag) {
+            return;
+        }
+        let Some(tag) = tag_notes.get_key_value(tag).map(|(k, _)| *k) else {
+            return;
+        };
+        let entry = scores.entry(tag).or_insert((0.0, 0.0, String::new()));
+        entry.0 += score;
+        if score > entry.1 {
+            entry.1 = score;
+            entry.2 = reason;
+        }
+    };
+
+    for word in &words {
+        add(word, 1.0, "mentioned in the note".to_string());
+        let (Some(total), Some(counts)) = (word_notes.get(word), word_tags.get(word)) else {
+            continue;
+        };
+        for (tag, count) in counts {
+            add(
+                tag,
+                *count as f64 / *total as f64,
+                format!("used with \"{}\"", word),
+            );
+        }
+    }
+
+    for current in current_tags {
+        let (Some(total), Some(pairs)) = (
+            tag_notes.get(current.as_str()),
+            tag_pairs.get(current.as_str()),
+        ) else {
+            continue;
+        };
+        for (tag, count) in pairs {
+            add(
+                tag,
+                *count as f64 / *total as f64,
+                format!("often tagged together with #{}", current),
+            );
+        }
+    }
+
+    let mut suggestions: Vec<TagSuggestion> = scores
+        .into_iter()
+        .filter(|(_, (score, _, _))| *score >= MIN_SCORE)
+        .map(|(tag, (score, _, reason))| TagSuggestion {
+            tag: tag.to_string(),
+            score,
+            reason,
+        })
+        .collect();
+    suggestions.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.tag.cmp(&b.tag)));
+    suggestions.truncate(limit);
+    suggestions
+}
+
+/// Most used tags, for when there is nothing to base suggestions on
+pub fn popular_tags(history: &[Note], limit: usize) -> Vec<String> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for tag in history.iter().flat_map(|n| &n.tags) {
+        *counts.entry(tag).or_default() += 1;
+    }
+    let mut tags: Vec<(&str, usize)> = counts.into_iter().collect();
+    tags.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    tags.into_iter()
+        .take(limit)
+        .map(|(tag, _)| tag.to_string())
+        .collect()
+}
+
+/// Distinct lowercase words of three or more letters, minus stopwords
+fn keywords(content: &str) -> BTreeSet<String> {
+    content
+        .split(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+        .map(|w| w.trim_matches(['-', '_']).to_lowercase())
+        .filter(|w| w.chars().count() >= 3 && !STOPWORDS.contains(&w.as_str()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    fn note(content: &str, tags: &[&str]) -> Note {
+        Note {
+            id: content.to_string(),
+            content: content.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            subject_date: None,
+            subject_tz: None,
+            created_at: 0,
+            updated_at: 0,
+            deleted_at: None,
+            pinned: false,
+            status: None,
+            meta: Default::default(),
+            attachments: vec![],
+            archived_at: None,
+        }
+    }
+
+    fn tags(suggestions: &[TagSuggestion]) -> Vec<&str> {
+        suggestions.iter().map(|s| s.tag.as_str()).collect()
+    }
+
+    #[test]
+    fn test_suggests_from_keywords_and_co_occurrence() {
+        let history = vec![
+            note("standup: fixed login bug", &["work", "standup"]),
+            note("standup: reviewed PRs", &["work", "standup"]),
+            note("bought milk", &["home"]),
+            note("deploy checklist", &["work", "ops"]),
+        ];
+
+        let suggestions = suggest_tags(&history, "Standup notes for today", &[], 5);
+        assert_eq!(tags(&suggestions), vec!["standup", "work"]);
+        assert_eq!(suggestions[0].reason, "mentioned in the note");
+        assert_eq!(suggestions[1].reason, "used with \"standup\"");
+
+        // #work usually comes with #standup, occasionally with #ops
+        let suggestions = suggest_tags(&history, "", &["work".to_string()], 5);
+        assert_eq!(tags(&suggestions), vec!["standup", "ops"]);
+        assert!(suggestions[0].reason.contains("#work"));
+    }
+
+    #[test]
+    fn test_ignores_unknown_and_current_tags() {
+        let history = vec![note("rust parser", &["rust"])];
+        assert!(suggest_tags(&history, "rust parser", &["rust".to_string()], 5).is_empty());
+        assert!(suggest_tags(&history, "python script", &[], 5).is_empty());
+    }
+
+    #[test]
+    fn test_popular_tags() {
+        let history = vec![
+            note("a", &["work", "ops"]),
+            note("b", &["work"]),
+            note("c", &["home"]),
+        ];
+        assert_eq!(popular_tags(&history, 2), vec!["work", "home"]);
+    }
+}
@@ -0,0 +1,56 @@
+use chrono::NaiveDate;
+
+/// An unchecked Markdown checkbox (`- [ ] call Alice due:2025-03-01`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task<'a> {
+    /// The item text without the checkbox
+    pub text: &'a str,
+    /// Date from a `due:YYYY-MM-DD` word in the text
+    pub due: Option<NaiveDate>,
+}
+
+/// Unchecked `- [ ]` / `* [ ]` items in `content`, in order
+pub fn open_tasks(content: &str) -> Vec<Task<'_>> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let text = line
+                .strip_prefix("- [ ]")
+                .or_else(|| line.strip_prefix("* [ ]"))?
+                .trim();
+            if text.is_empty() {
+                return None;
+            }
+
+            let due = text
+                .split_whitespace()
+                .filter_map(|word| word.strip_prefix("due:"))
+                .find_map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+            Some(Task { text, due })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_tasks() {
+        let content = "Plan\n- [ ] call Alice due:2025-03-01\n- [x] done already\n  * [ ] nested item\n- [ ]\nnot a task [ ]";
+        assert_eq!(
+            open_tasks(content),
+            vec![
+                Task {
+                    text: "call Alice due:2025-03-01",
+                    due: NaiveDate::from_ymd_opt(2025, 3, 1),
+                },
+                Task {
+                    text: "nested item",
+                    due: None,
+                },
+            ]
+        );
+    }
+}
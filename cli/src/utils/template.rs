@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+/// Names of the `{{name}}` placeholders in `body`, in order of first use
+pub fn template_variables(body: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (_, name) in placeholders(body) {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// `body` with each placeholder replaced by its value; placeholders without
+/// one are kept as typed
+pub fn fill_template(body: &str, values: &HashMap<String, String>) -> String {
+    let mut filled = String::with_capacity(body.len());
+    let mut rest = 0;
+    for (range, name) in placeholders(body) {
+        if let Some(value) = values.get(name) {
+            filled.push_str(&body[rest..range.start]);
+            filled.push_str(value);
+            rest = range.end;
+        }
+    }
+    filled.push_str(&body[rest..]);
+    filled
+}
+
+/// Placeholders as their byte range in `body` and the name inside,
+/// surrounding spaces allowed (`{{ project }}`)
+fn placeholders(body: &str) -> Vec<(std::ops::Range<usize>, &str)> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = body[offset..].find("{{").map(|i| offset + i) {
+        let Some(end) = body[start + 2..].find("}}").map(|i| start + 2 + i) else {
+            break;
+        };
+        let name = body[start + 2..end].trim();
+        if is_variable_name(name) {
+            found.push((start..end + 2, name));
+            offset = end + 2;
+        } else {
+            offset = start + 2;
+        }
+    }
+    found
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn test_fill_template() {
+        let body =
+            "# {{project}} sync\nAttendees: {{ attendees }}\n{{project}} {{not a var}} {{missing}}";
+        assert_eq!(
+            template_variables(body),
+            vec!["project", "attendees", "missing"]
+        );
+
+        let values = HashMap::from([
+            ("project".to_string(), "Apollo".to_string()),
+            ("attendees".to_string(), "Ann, Bo".to_string()),
+        ]);
+        assert_eq!(
+            fill_template(body, &values),
+            "# Apollo sync\nAttendees: Ann, Bo\nApollo {{not a var}} {{missing}}"
+        );
+        assert_eq!(fill_template("no {{ end", &values), "no {{ end");
+    }
+}
@@ -0,0 +1,189 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Cut `text` to at most `max_width` terminal columns without splitting a grapheme
+///
+/// Returns the kept prefix and whether anything was cut off.
+pub fn truncate_to_width(text: &str, max_width: usize) -> (&str, bool) {
+    let mut width = 0;
+    for (offset, grapheme) in text.grapheme_indices(true) {
+        width += grapheme.width();
+        if width > max_width {
+            return (&text[..offset], true);
+        }
+    }
+    (text, false)
+}
+
+/// First line of `content`, at most `max_width` columns wide, with "..." appended
+/// when anything (further text or further lines) was left out
+pub fn preview_line(content: &str, max_width: usize) -> String {
+    let mut lines = content.lines();
+    let first = lines.next().unwrap_or("");
+    let (preview, truncated) = truncate_to_width(first, max_width);
+
+    if truncated || lines.next().is_some() {
+        format!("{}...", preview)
+    } else {
+        preview.to_string()
+    }
+}
+
+/// Number of single-character edits turning `a` into `b` (case-insensitive)
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().map(|c| c.to_ascii_uppercase()).collect();
+    let b: Vec<char> = b.chars().map(|c| c.to_ascii_uppercase()).collect();
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// `http(s)://` links in `content`, in order of appearance and without repeats
+///
+/// Trailing sentence punctuation and unbalanced closing brackets (as in
+/// `(see https://example.com)` or Markdown links) are not part of the link.
+pub fn find_urls(content: &str) -> Vec<&str> {
+    let mut urls: Vec<&str> = Vec::new();
+    for word in content.split(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"')) {
+        let Some(start) = word.find("https://").or_else(|| word.find("http://")) else {
+            continue;
+        };
+        let mut url = &word[start..];
+        loop {
+            let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '*']);
+            let trimmed = match trimmed.chars().last() {
+                Some(close @ (')' | ']' | '}')) => {
+                    let open = match close {
+                        ')' => '(',
+                        ']' => '[',
+                        _ => '{',
+                    };
+                    if trimmed.matches(close).count() > trimmed.matches(open).count() {
+                        &trimmed[..trimmed.len() - 1]
+                    } else {
+                        trimmed
+                    }
+                }
+                _ => trimmed,
+            };
+            if trimmed == url {
+                break;
+            }
+            url = trimmed;
+        }
+
+        let has_host = url
+            .split_once("://")
+            .is_some_and(|(_, rest)| !rest.is_empty());
+        if has_host && !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    urls
+}
+
+/// The first non-blank line as lowercase words joined by `-`, for file names
+///
+/// Punctuation (including Markdown heading markers) is dropped, and whole
+/// words are kept up to `max_len` characters.
+pub fn slugify(content: &str, max_len: usize) -> String {
+    let line = content
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("");
+    let mut slug = String::new();
+    for word in line
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        let word = word.to_lowercase();
+        let len = slug.chars().count();
+        if len == 0 {
+            slug = word.chars().take(max_len).collect();
+        } else if len + 1 + word.chars().count() <= max_len {
+            slug.push('-');
+            slug.push_str(&word);
+        } else {
+            break;
+        }
+    }
+    slug
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_urls() {
+        assert_eq!(
+            find_urls("see https://example.com/a, and (http://x.org/b).\nhttps://example.com/a"),
+            vec!["https://example.com/a", "http://x.org/b"]
+        );
+        assert_eq!(
+            find_urls("[docs](https://en.wikipedia.org/wiki/Rust_(language)) <https://a.io>"),
+            vec![
+                "https://en.wikipedia.org/wiki/Rust_(language)",
+                "https://a.io"
+            ]
+        );
+        assert!(find_urls("no links, just https:// and ftp://x").is_empty());
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(
+            slugify("\n# Weekly sync: Q3 plans!\nbody", 40),
+            "weekly-sync-q3-plans"
+        );
+        assert_eq!(slugify("Café über straße", 40), "café-über-straße");
+        assert_eq!(slugify("one two three", 8), "one-two");
+        assert_eq!(slugify("supercalifragilistic", 5), "super");
+        assert_eq!(slugify("--- ...", 40), "");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("01HQ", "01hq"), 0);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_truncate_ascii() {
+        assert_eq!(truncate_to_width("hello", 10), ("hello", false));
+        assert_eq!(truncate_to_width("hello world", 5), ("hello", true));
+    }
+
+    #[test]
+    fn test_truncate_multibyte_does_not_split() {
+        // 'é' is two bytes; a byte slice at 3 would panic
+        assert_eq!(truncate_to_width("aéb", 2), ("aé", true));
+        // Combining sequences stay together
+        assert_eq!(truncate_to_width("e\u{301}x", 1), ("e\u{301}", true));
+    }
+
+    #[test]
+    fn test_truncate_counts_display_width() {
+        // CJK characters and most emoji take two columns
+        assert_eq!(truncate_to_width("日本語", 4), ("日本", true));
+        assert_eq!(truncate_to_width("日本語", 5), ("日本", true));
+        assert_eq!(truncate_to_width("👍👍", 2), ("👍", true));
+    }
+
+    #[test]
+    fn test_preview_line() {
+        assert_eq!(preview_line("short", 10), "short");
+        assert_eq!(preview_line("first\nsecond", 10), "first...");
+        assert_eq!(preview_line("日本語のメモ", 6), "日本語...");
+        assert_eq!(preview_line("", 10), "");
+    }
+}
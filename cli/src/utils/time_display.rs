@@ -0,0 +1,188 @@
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Local, NaiveDate, NaiveTime, Utc,
+};
+use chrono_tz::Tz;
+
+use crate::{
+    app_config::AppConfig,
+    error::{CliError, ErrorKind},
+};
+
+/// Used when the profile sets no `date_format`
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// How stored UTC timestamps are shown: the profile's `timezone` (IANA name,
+/// system zone when unset) and `date_format` (strftime pattern)
+#[derive(Debug, Clone)]
+pub struct TimeDisplay {
+    timezone: Option<Tz>,
+    format: String,
+}
+
+impl Default for TimeDisplay {
+    fn default() -> Self {
+        Self {
+            timezone: None,
+            format: DEFAULT_DATE_FORMAT.to_string(),
+        }
+    }
+}
+
+impl TimeDisplay {
+    pub fn new(timezone: Option<&str>, format: Option<&str>) -> anyhow::Result<Self> {
+        let timezone = timezone
+            .map(|name| {
+                name.parse::<Tz>().map_err(|_| {
+                    CliError::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Unknown timezone '{}' (use an IANA name like Europe/Prague)",
+                            name
+                        ),
+                    )
+                })
+            })
+            .transpose()?;
+
+        let format = format.unwrap_or(DEFAULT_DATE_FORMAT);
+        if StrftimeItems::new(format).any(|item| item == Item::Error) {
+            return Err(CliError::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid date_format '{}'", format),
+            )
+            .into());
+        }
+
+        Ok(Self {
+            timezone,
+            format: format.to_string(),
+        })
+    }
+
+    pub fn from_config(config: &AppConfig) -> anyhow::Result<Self> {
+        Self::new(config.timezone.as_deref(), config.date_format.as_deref())
+    }
+
+    /// Format a timestamp in milliseconds since the epoch
+    pub fn format_timestamp(&self, timestamp_ms: i64) -> String {
+        let Some(utc) = DateTime::<Utc>::from_timestamp_millis(timestamp_ms) else {
+            return "Invalid timestamp".to_string();
+        };
+        match self.timezone {
+            Some(tz) => utc.with_timezone(&tz).format(&self.format).to_string(),
+            None => utc.with_timezone(&Local).format(&self.format).to_string(),
+        }
+    }
+
+    /// A note's subject date, with the offset it was set in when that differs
+    /// from the display timezone's current offset
+    pub fn format_subject_date(&self, date: &str, subject_tz: Option<&str>) -> String {
+        match subject_tz {
+            Some(offset) if offset != self.current_offset() => {
+                format!("{} ({})", date, offset)
+            }
+            _ => date.to_string(),
+        }
+    }
+
+    /// The display timezone's current UTC offset, e.g. `+02:00`
+    pub fn current_offset(&self) -> String {
+        self.format_now("%:z")
+    }
+
+    /// The current time in the display timezone, formatted with `format`
+    pub fn format_now(&self, format: &str) -> String {
+        let now = Utc::now();
+        match self.timezone {
+            Some(tz) => now.with_timezone(&tz).format(format).to_string(),
+            None => now.with_timezone(&Local).format(format).to_string(),
+        }
+    }
+
+    /// Today's date in the display timezone
+    pub fn today(&self) -> NaiveDate {
+        let now = Utc::now();
+        match self.timezone {
+            Some(tz) => now.with_timezone(&tz).date_naive(),
+            None => now.with_timezone(&Local).date_naive(),
+        }
+    }
+
+    /// Start of `date` in the display timezone, in milliseconds
+    pub fn start_of_day(&self, date: NaiveDate) -> i64 {
+        let midnight = date.and_time(NaiveTime::MIN);
+        let start = match self.timezone {
+            Some(tz) => midnight
+                .and_local_timezone(tz)
+                .earliest()
+                .map(|t| t.timestamp_millis()),
+            None => midnight
+                .and_local_timezone(Local)
+                .earliest()
+                .map(|t| t.timestamp_millis()),
+        };
+        start.unwrap_or_else(|| midnight.and_utc().timestamp_millis())
+    }
+
+    /// Calendar date of a timestamp in the display timezone
+    pub fn date_of(&self, timestamp_ms: i64) -> Option<NaiveDate> {
+        let utc = DateTime::<Utc>::from_timestamp_millis(timestamp_ms)?;
+        Some(match self.timezone {
+            Some(tz) => utc.with_timezone(&tz).date_naive(),
+            None => utc.with_timezone(&Local).date_naive(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    // 2024-03-16 22:30:00 UTC
+    const LATE_EVENING_UTC: i64 = 1_710_628_200_000;
+
+    #[test]
+    fn test_timestamps_follow_profile_timezone() {
+        let tokyo = TimeDisplay::new(Some("Asia/Tokyo"), None).unwrap();
+        assert_eq!(
+            tokyo.format_timestamp(LATE_EVENING_UTC),
+            "2024-03-17 07:30:00"
+        );
+        assert_eq!(
+            tokyo.date_of(LATE_EVENING_UTC),
+            NaiveDate::from_ymd_opt(2024, 3, 17)
+        );
+
+        assert_eq!(
+            tokyo.start_of_day(NaiveDate::from_ymd_opt(2024, 3, 17).unwrap()),
+            LATE_EVENING_UTC - 7 * 60 * 60 * 1000 - 30 * 60 * 1000
+        );
+        assert_eq!(tokyo.current_offset(), "+09:00");
+
+        let utc = TimeDisplay::new(Some("UTC"), Some("%d.%m.%Y %H:%M")).unwrap();
+        assert_eq!(utc.format_timestamp(LATE_EVENING_UTC), "16.03.2024 22:30");
+    }
+
+    #[test]
+    fn test_subject_date_shows_foreign_offset() {
+        let utc = TimeDisplay::new(Some("UTC"), None).unwrap();
+        assert_eq!(
+            utc.format_subject_date("2024-03-17", Some("+09:00")),
+            "2024-03-17 (+09:00)"
+        );
+        assert_eq!(
+            utc.format_subject_date("2024-03-17", Some("+00:00")),
+            "2024-03-17"
+        );
+        assert_eq!(utc.format_subject_date("2024-03-17", None), "2024-03-17");
+    }
+
+    #[test]
+    fn test_invalid_settings_are_rejected() {
+        assert!(TimeDisplay::new(Some("Mars/Olympus"), None).is_err());
+        assert!(TimeDisplay::new(None, Some("%Y-%")).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+//! SQL-layer benchmarks against a seeded in-memory database
+//!
+//! Run with `cargo bench -p jot-core`.
+
+#![allow(clippy::unwrap_used)]
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use jot_core::seed::{generate_note, seed_notes, SeedRng};
+use jot_core::{open_db_in_memory, search_notes, SearchQuery, SyncRequest};
+use rusqlite::Connection;
+
+const NOTE_COUNT: usize = 10_000;
+
+fn seeded_db() -> Connection {
+    let conn = open_db_in_memory().unwrap();
+    seed_notes(&conn, NOTE_COUNT, 42).unwrap();
+    conn
+}
+
+fn search_benches(c: &mut Criterion) {
+    let conn = seeded_db();
+
+    c.bench_function("search_recent_50", |b| {
+        let query = SearchQuery {
+            limit: Some(50),
+            ..Default::default()
+        };
+        b.iter(|| search_notes(&conn, &query).unwrap())
+    });
+
+    c.bench_function("search_text", |b| {
+        let query = SearchQuery {
+            text: Some("migration".to_string()),
+            ..Default::default()
+        };
+        b.iter(|| search_notes(&conn, &query).unwrap())
+    });
+
+    c.bench_function("search_tag", |b| {
+        let query = SearchQuery {
+            tags: vec!["backend".to_string()],
+            ..Default::default()
+        };
+        b.iter(|| search_notes(&conn, &query).unwrap())
+    });
+}
+
+fn sync_benches(c: &mut Criterion) {
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut rng = SeedRng::new(7);
+    let incoming: Vec<_> = (0..500)
+        .map(|_| generate_note(&mut rng, now).build().unwrap())
+        .collect();
+
+    c.bench_function("sync_500_into_seeded", |b| {
+        b.iter_batched(
+            seeded_db,
+            |conn| {
+                let request = SyncRequest {
+                    notes: incoming.clone(),
+                    last_sync: 0,
+                };
+                jot_core::process_sync_request(&conn, request).unwrap()
+            },
+            BatchSize::PerIteration,
+        )
+    });
+}
+
+criterion_group!(benches, search_benches, sync_benches);
+criterion_main!(benches);
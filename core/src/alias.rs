@@ -0,0 +1,84 @@
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+
+/// A friendly name pointing at a note (e.g. `inbox`, `standup`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoteAlias {
+    pub name: String,
+    pub note_id: String,
+    /// Milliseconds since the Unix epoch
+    pub created_at: i64,
+}
+
+/// Point `name` at `note_id`, replacing any existing alias with that name
+pub fn set_alias(conn: &Connection, name: &str, note_id: &str) -> Result<()> {
+    let now = chrono::Utc::now().timestamp_millis();
+    conn.execute(
+        "INSERT INTO note_aliases (name, note_id, created_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(name) DO UPDATE SET note_id = excluded.note_id, created_at = excluded.created_at",
+        params![name, note_id, now],
+    )?;
+    Ok(())
+}
+
+/// Remove an alias, returning whether it existed
+pub fn remove_alias(conn: &Connection, name: &str) -> Result<bool> {
+    let removed = conn.execute("DELETE FROM note_aliases WHERE name = ?1", params![name])?;
+    Ok(removed > 0)
+}
+
+/// Look up the note ID an alias points at
+pub fn resolve_alias(conn: &Connection, name: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT note_id FROM note_aliases WHERE name = ?1",
+        params![name],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// All aliases, sorted by name
+pub fn list_aliases(conn: &Connection) -> Result<Vec<NoteAlias>> {
+    let mut stmt =
+        conn.prepare("SELECT name, note_id, created_at FROM note_aliases ORDER BY name")?;
+    let aliases = stmt
+        .query_map([], |row| {
+            Ok(NoteAlias {
+                name: row.get(0)?,
+                note_id: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(aliases)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::{create_note, open_db_in_memory};
+
+    #[test]
+    fn test_alias_roundtrip() {
+        let conn = open_db_in_memory().unwrap();
+        let first = create_note(&conn, "inbox", vec![], None).unwrap();
+        let second = create_note(&conn, "new inbox", vec![], None).unwrap();
+
+        set_alias(&conn, "inbox", &first.id).unwrap();
+        assert_eq!(resolve_alias(&conn, "inbox").unwrap(), Some(first.id));
+
+        // Re-pointing an alias replaces it
+        set_alias(&conn, "inbox", &second.id).unwrap();
+        assert_eq!(
+            resolve_alias(&conn, "inbox").unwrap(),
+            Some(second.id.clone())
+        );
+        assert_eq!(list_aliases(&conn).unwrap().len(), 1);
+
+        assert!(remove_alias(&conn, "inbox").unwrap());
+        assert!(!remove_alias(&conn, "inbox").unwrap());
+        assert_eq!(resolve_alias(&conn, "inbox").unwrap(), None);
+    }
+}
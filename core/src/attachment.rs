@@ -0,0 +1,337 @@
+//! Files attached to notes (`jot note attach`)
+//!
+//! Attachments belong to their note: attaching or detaching bumps the note's
+//! `updated_at`, and their metadata travels with it in [`Note::attachments`],
+//! sync included. File contents are stored once per SHA-256 and stay on the
+//! device they were attached on; other devices list the file without it.
+//!
+//! [`Note::attachments`]: crate::models::Note::attachments
+
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// A file attached to a note
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    /// ULID
+    pub id: String,
+    /// File name without its directory
+    pub filename: String,
+    /// SHA-256 of the contents as lowercase hex
+    pub hash: String,
+    /// Size in bytes
+    pub size: i64,
+    /// Milliseconds since the Unix epoch
+    pub created_at: i64,
+}
+
+/// Column of note queries holding the note's attachments as a JSON array,
+/// read with [`read_attachments`]
+pub(crate) const ATTACHMENTS_COLUMN: &str = "(SELECT json_group_array(json_object('id', a.id, 'filename', a.filename, 'hash', a.hash, 'size', a.size, 'created_at', a.created_at)) FROM (SELECT * FROM attachments WHERE note_id = notes.id ORDER BY created_at, id) a)";
+
+/// Attach `data` to a live note as `filename`, replacing an attachment of the
+/// same name
+///
+/// Returns None when there is no such live note.
+pub fn attach_file(
+    conn: &Connection,
+    note_id: &str,
+    filename: &str,
+    data: &[u8],
+) -> Result<Option<Attachment>> {
+    let now = chrono::Utc::now().timestamp_millis();
+    if !touch_note(conn, note_id, now)? {
+        return Ok(None);
+    }
+
+    let attachment = Attachment {
+        id: ulid::Ulid::new().to_string(),
+        filename: filename.to_string(),
+        hash: blob_hash(data),
+        size: data.len() as i64,
+        created_at: now,
+    };
+    conn.execute(
+        "INSERT OR IGNORE INTO attachment_blobs (hash, data) VALUES (?1, ?2)",
+        params![attachment.hash, data],
+    )?;
+    conn.execute(
+        "DELETE FROM attachments WHERE note_id = ?1 AND filename = ?2",
+        params![note_id, filename],
+    )?;
+    insert_attachment(conn, note_id, &attachment)?;
+    prune_blobs(conn)?;
+    Ok(Some(attachment))
+}
+
+/// Attach the contents of an imported attachment to a note, keeping its
+/// file name and time under a new ID
+///
+/// Returns None, storing nothing, when `data` doesn't match the attachment's
+/// hash and size (a corrupted copy).
+pub fn restore_attachment(
+    conn: &Connection,
+    note_id: &str,
+    attachment: &Attachment,
+    data: &[u8],
+) -> Result<Option<Attachment>> {
+    if blob_hash(data) != attachment.hash.to_ascii_lowercase()
+        || data.len() as i64 != attachment.size
+    {
+        return Ok(None);
+    }
+    let restored = Attachment {
+        id: ulid::Ulid::new().to_string(),
+        hash: blob_hash(data),
+        ..attachment.clone()
+    };
+    conn.execute(
+        "INSERT OR IGNORE INTO attachment_blobs (hash, data) VALUES (?1, ?2)",
+        params![restored.hash, data],
+    )?;
+    conn.execute(
+        "DELETE FROM attachments WHERE note_id = ?1 AND filename = ?2",
+        params![note_id, restored.filename],
+    )?;
+    insert_attachment(conn, note_id, &restored)?;
+    Ok(Some(restored))
+}
+
+/// Attachments of a note, oldest first
+pub fn list_attachments(conn: &Connection, note_id: &str) -> Result<Vec<Attachment>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, filename, hash, size, created_at FROM attachments
+         WHERE note_id = ?1 ORDER BY created_at, id",
+    )?;
+    let attachments = stmt
+        .query_map(params![note_id], |row| {
+            Ok(Attachment {
+                id: row.get(0)?,
+                filename: row.get(1)?,
+                hash: row.get(2)?,
+                size: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(attachments)
+}
+
+/// Remove the attachment named `name` (its file name or ID) from a live note,
+/// returning it
+pub fn detach_file(conn: &Connection, note_id: &str, name: &str) -> Result<Option<Attachment>> {
+    let Some(attachment) = list_attachments(conn, note_id)?
+        .into_iter()
+        .find(|a| a.filename == name || a.id == name)
+    else {
+        return Ok(None);
+    };
+    if !touch_note(conn, note_id, chrono::Utc::now().timestamp_millis())? {
+        return Ok(None);
+    }
+    conn.execute(
+        "DELETE FROM attachments WHERE id = ?1",
+        params![attachment.id],
+    )?;
+    prune_blobs(conn)?;
+    Ok(Some(attachment))
+}
+
+/// Contents of an attachment, None when they are not on this device
+pub fn attachment_data(conn: &Connection, attachment: &Attachment) -> Result<Option<Vec<u8>>> {
+    conn.query_row(
+        "SELECT data FROM attachment_blobs WHERE hash = ?1",
+        params![attachment.hash],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Make a note's attachment metadata match `attachments` (a synced version
+/// of the note); contents already stored are kept
+pub(crate) fn replace_attachments(
+    conn: &Connection,
+    note_id: &str,
+    attachments: &[Attachment],
+) -> Result<()> {
+    conn.execute(
+        "DELETE FROM attachments WHERE note_id = ?1",
+        params![note_id],
+    )?;
+    for attachment in attachments {
+        insert_attachment(conn, note_id, attachment)?;
+    }
+    prune_blobs(conn)?;
+    Ok(())
+}
+
+/// Drop attachments of notes that no longer exist and contents no attachment
+/// refers to, returning how many contents were dropped
+pub(crate) fn prune_blobs(conn: &Connection) -> Result<usize> {
+    conn.execute(
+        "DELETE FROM attachments WHERE note_id NOT IN (SELECT id FROM notes)",
+        [],
+    )?;
+    conn.execute(
+        "DELETE FROM attachment_blobs WHERE hash NOT IN (SELECT hash FROM attachments)",
+        [],
+    )
+}
+
+/// Read the [`ATTACHMENTS_COLUMN`] of a note query
+pub(crate) fn read_attachments(row: &rusqlite::Row, idx: usize) -> Result<Vec<Attachment>> {
+    let json: String = row.get(idx)?;
+    serde_json::from_str(&json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn insert_attachment(conn: &Connection, note_id: &str, attachment: &Attachment) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO attachments (id, note_id, filename, hash, size, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            attachment.id,
+            note_id,
+            attachment.filename,
+            attachment.hash,
+            attachment.size,
+            attachment.created_at
+        ],
+    )?;
+    Ok(())
+}
+
+/// Bump a live note's `updated_at` so the change syncs
+fn touch_note(conn: &Connection, note_id: &str, now: i64) -> Result<bool> {
+    let changed = conn.execute(
+        "UPDATE notes SET updated_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+        params![now, note_id],
+    )?;
+    Ok(changed > 0)
+}
+
+fn blob_hash(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::{create_note, get_note_by_id, open_db_in_memory, upsert_note};
+
+    fn blob_count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM attachment_blobs", [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_attach_list_and_detach() {
+        let conn = open_db_in_memory().unwrap();
+        let note = create_note(&conn, "trip", vec![], None).unwrap();
+        let other = create_note(&conn, "copy", vec![], None).unwrap();
+
+        let ticket = attach_file(&conn, &note.id, "ticket.pdf", b"%PDF")
+            .unwrap()
+            .unwrap();
+        assert_eq!(ticket.size, 4);
+        attach_file(&conn, &other.id, "ticket.pdf", b"%PDF")
+            .unwrap()
+            .unwrap();
+        // Same contents are stored once
+        assert_eq!(blob_count(&conn), 1);
+        assert_eq!(
+            attachment_data(&conn, &ticket).unwrap().as_deref(),
+            Some(&b"%PDF"[..])
+        );
+
+        let stored = get_note_by_id(&conn, &note.id).unwrap().unwrap();
+        assert_eq!(stored.attachments, vec![ticket.clone()]);
+        assert!(stored.updated_at >= note.updated_at);
+        assert_eq!(list_attachments(&conn, &note.id).unwrap(), vec![ticket]);
+
+        // Attaching the same name again replaces the file
+        attach_file(&conn, &note.id, "ticket.pdf", b"%PDF-2")
+            .unwrap()
+            .unwrap();
+        assert_eq!(list_attachments(&conn, &note.id).unwrap().len(), 1);
+        assert_eq!(blob_count(&conn), 2);
+
+        assert!(detach_file(&conn, &note.id, "missing.txt")
+            .unwrap()
+            .is_none());
+        assert!(detach_file(&conn, &note.id, "ticket.pdf")
+            .unwrap()
+            .is_some());
+        assert!(list_attachments(&conn, &note.id).unwrap().is_empty());
+        assert_eq!(blob_count(&conn), 1);
+    }
+
+    #[test]
+    fn test_restore_attachment_checks_contents() {
+        let conn = open_db_in_memory().unwrap();
+        let note = create_note(&conn, "imported", vec![], None).unwrap();
+        let exported = Attachment {
+            id: "01EXPORTED".to_string(),
+            filename: "ticket.pdf".to_string(),
+            hash: blob_hash(b"%PDF"),
+            size: 4,
+            created_at: 1000,
+        };
+
+        assert!(restore_attachment(&conn, &note.id, &exported, b"%PDX")
+            .unwrap()
+            .is_none());
+        assert_eq!(blob_count(&conn), 0);
+
+        let restored = restore_attachment(&conn, &note.id, &exported, b"%PDF")
+            .unwrap()
+            .unwrap();
+        assert_ne!(restored.id, exported.id);
+        assert_eq!(restored.filename, "ticket.pdf");
+        assert_eq!(restored.created_at, 1000);
+        assert_eq!(
+            list_attachments(&conn, &note.id).unwrap(),
+            vec![restored.clone()]
+        );
+        assert_eq!(
+            attachment_data(&conn, &restored).unwrap().as_deref(),
+            Some(&b"%PDF"[..])
+        );
+    }
+
+    #[test]
+    fn test_synced_attachments_keep_local_contents() {
+        let conn = open_db_in_memory().unwrap();
+        let note = create_note(&conn, "trip", vec![], None).unwrap();
+        let photo = attach_file(&conn, &note.id, "photo.jpg", b"jpeg")
+            .unwrap()
+            .unwrap();
+
+        // A newer version from another device that added a file we don't have
+        let mut synced = get_note_by_id(&conn, &note.id).unwrap().unwrap();
+        let remote = Attachment {
+            id: "01REMOTE".to_string(),
+            filename: "map.png".to_string(),
+            hash: blob_hash(b"png"),
+            size: 3,
+            created_at: synced.updated_at + 1,
+        };
+        synced.attachments.push(remote.clone());
+        synced.updated_at += 10;
+        upsert_note(&conn, &synced).unwrap();
+
+        let listed = list_attachments(&conn, &note.id).unwrap();
+        assert_eq!(listed, vec![photo.clone(), remote.clone()]);
+        assert!(attachment_data(&conn, &photo).unwrap().is_some());
+        assert!(attachment_data(&conn, &remote).unwrap().is_none());
+    }
+}
@@ -0,0 +1,298 @@
+use crate::db::insert_note;
+use crate::dedupe::{deleted_note_exists, find_note_by_content};
+use crate::models::Note;
+use crate::schema::DATE_FORMAT;
+use crate::validation::{NoteLimits, NoteValidationError};
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Errors raised when a [`NoteBuilder`] is given inconsistent values
+#[derive(Debug, Error, PartialEq)]
+pub enum NoteBuilderError {
+    #[error("Invalid note ID '{0}': expected a ULID")]
+    InvalidId(String),
+    #[error("Invalid subject date '{0}': expected YYYY-MM-DD")]
+    InvalidSubjectDate(String),
+    #[error("Invalid subject timezone '{0}': expected a UTC offset like +09:00")]
+    InvalidSubjectTz(String),
+    #[error("updated_at ({updated_at}) is earlier than created_at ({created_at})")]
+    UpdatedBeforeCreated { created_at: i64, updated_at: i64 },
+    #[error("deleted_at ({deleted_at}) is earlier than created_at ({created_at})")]
+    DeletedBeforeCreated { created_at: i64, deleted_at: i64 },
+    #[error(transparent)]
+    Invalid(#[from] NoteValidationError),
+}
+
+/// Builder for fully-specified notes (importers, tests, fixtures)
+///
+/// Unlike [`crate::create_note`], every field can be overridden. Missing
+/// values fall back to the same defaults `create_note` would use.
+#[derive(Debug, Clone, Default)]
+pub struct NoteBuilder {
+    id: Option<String>,
+    content: String,
+    tags: Vec<String>,
+    subject_date: Option<String>,
+    subject_tz: Option<String>,
+    created_at: Option<i64>,
+    updated_at: Option<i64>,
+    deleted_at: Option<i64>,
+    pinned: bool,
+    status: Option<String>,
+    meta: BTreeMap<String, String>,
+    archived_at: Option<i64>,
+}
+
+impl NoteBuilder {
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Use a specific ULID instead of generating one
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Subject date (YYYY-MM-DD)
+    pub fn subject_date(mut self, date: impl Into<String>) -> Self {
+        self.subject_date = Some(date.into());
+        self
+    }
+
+    /// UTC offset the subject date was set in (`+09:00`)
+    pub fn subject_tz(mut self, offset: impl Into<String>) -> Self {
+        self.subject_tz = Some(offset.into());
+        self
+    }
+
+    /// Creation time in milliseconds since the Unix epoch
+    pub fn created_at(mut self, timestamp: i64) -> Self {
+        self.created_at = Some(timestamp);
+        self
+    }
+
+    /// Last update time in milliseconds (defaults to created_at)
+    pub fn updated_at(mut self, timestamp: i64) -> Self {
+        self.updated_at = Some(timestamp);
+        self
+    }
+
+    /// Mark the note as soft-deleted at the given time (milliseconds)
+    pub fn deleted_at(mut self, timestamp: i64) -> Self {
+        self.deleted_at = Some(timestamp);
+        self
+    }
+
+    pub fn pinned(mut self, pinned: bool) -> Self {
+        self.pinned = pinned;
+        self
+    }
+
+    pub fn archived_at(mut self, timestamp: i64) -> Self {
+        self.archived_at = Some(timestamp);
+        self
+    }
+
+    /// Workflow status (e.g. `todo`)
+    pub fn status(mut self, status: impl Into<String>) -> Self {
+        self.status = Some(status.into());
+        self
+    }
+
+    /// Capture metadata (see [`Note::meta`])
+    pub fn meta(mut self, meta: BTreeMap<String, String>) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    /// Validate the fields and produce a note without touching the database
+    pub fn build(self) -> Result<Note, NoteBuilderError> {
+        NoteLimits::default().validate(&self.content, &self.tags)?;
+
+        let created_at = self
+            .created_at
+            .unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+        let updated_at = self.updated_at.unwrap_or(created_at);
+
+        let id = match self.id {
+            Some(id) => {
+                ulid::Ulid::from_string(&id)
+                    .map_err(|_| NoteBuilderError::InvalidId(id.clone()))?;
+                id
+            }
+            None => ulid_for_timestamp(created_at).to_string(),
+        };
+
+        if let Some(ref date) = self.subject_date {
+            chrono::NaiveDate::parse_from_str(date, DATE_FORMAT)
+                .map_err(|_| NoteBuilderError::InvalidSubjectDate(date.clone()))?;
+        }
+
+        if let Some(ref offset) = self.subject_tz {
+            offset
+                .parse::<chrono::FixedOffset>()
+                .map_err(|_| NoteBuilderError::InvalidSubjectTz(offset.clone()))?;
+        }
+
+        if updated_at < created_at {
+            return Err(NoteBuilderError::UpdatedBeforeCreated {
+                created_at,
+                updated_at,
+            });
+        }
+
+        if let Some(deleted_at) = self.deleted_at {
+            if deleted_at < created_at {
+                return Err(NoteBuilderError::DeletedBeforeCreated {
+                    created_at,
+                    deleted_at,
+                });
+            }
+        }
+
+        Ok(Note {
+            id,
+            content: self.content,
+            tags: self.tags,
+            subject_date: self.subject_date,
+            subject_tz: self.subject_tz,
+            created_at,
+            updated_at,
+            deleted_at: self.deleted_at,
+            pinned: self.pinned,
+            status: self.status,
+            meta: self.meta,
+            attachments: Vec::new(),
+            archived_at: self.archived_at,
+        })
+    }
+
+    /// Validate and insert the note
+    pub fn insert(self, conn: &Connection) -> rusqlite::Result<Note> {
+        let note = self
+            .build()
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        insert_note(conn, &note)?;
+        Ok(note)
+    }
+
+    /// Insert the note unless a live note with identical content already exists
+    ///
+    /// A note built as deleted is also skipped when a deleted note with the
+    /// same content exists. Returns `None` when the note was skipped as a
+    /// duplicate, so re-running an import does not create copies.
+    pub fn insert_if_new(self, conn: &Connection) -> rusqlite::Result<Option<Note>> {
+        if find_note_by_content(conn, &self.content)?.is_some() {
+            return Ok(None);
+        }
+        if self.deleted_at.is_some() && deleted_note_exists(conn, &self.content)? {
+            return Ok(None);
+        }
+        self.insert(conn).map(Some)
+    }
+}
+
+/// Generate a ULID whose time component matches the note's creation time,
+/// so overridden timestamps keep IDs sortable by creation order
+fn ulid_for_timestamp(timestamp_ms: i64) -> ulid::Ulid {
+    let millis = u64::try_from(timestamp_ms).unwrap_or(0);
+    let time = std::time::UNIX_EPOCH + std::time::Duration::from_millis(millis);
+    ulid::Ulid::from_datetime(time)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::{get_note_by_id, open_db_in_memory};
+
+    #[test]
+    fn test_builder_insert_with_overrides() {
+        let conn = open_db_in_memory().unwrap();
+
+        let note = NoteBuilder::new("imported")
+            .id("01ARZ3NDEKTSV4RRFFQ69G5FAV")
+            .tags(vec!["import".to_string()])
+            .subject_date("2024-03-16")
+            .created_at(1_000)
+            .updated_at(2_000)
+            .insert(&conn)
+            .unwrap();
+
+        let stored = get_note_by_id(&conn, "01ARZ3NDEKTSV4RRFFQ69G5FAV")
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored, note);
+        assert_eq!(stored.created_at, 1_000);
+        assert_eq!(stored.updated_at, 2_000);
+    }
+
+    #[test]
+    fn test_builder_generated_id_matches_created_at() {
+        let note = NoteBuilder::new("old").created_at(1_000).build().unwrap();
+
+        let ulid = ulid::Ulid::from_string(&note.id).unwrap();
+        assert_eq!(ulid.timestamp_ms(), 1_000);
+        assert_eq!(note.updated_at, 1_000);
+    }
+
+    #[test]
+    fn test_builder_insert_if_new_skips_duplicates() {
+        let conn = open_db_in_memory().unwrap();
+
+        let first = NoteBuilder::new("imported").insert_if_new(&conn).unwrap();
+        assert!(first.is_some());
+        assert!(NoteBuilder::new("imported")
+            .insert_if_new(&conn)
+            .unwrap()
+            .is_none());
+
+        // Deleted notes only count against notes that are themselves deleted
+        let trashed = || {
+            NoteBuilder::new("trashed")
+                .created_at(1_000)
+                .deleted_at(2_000)
+        };
+        assert!(trashed().insert_if_new(&conn).unwrap().is_some());
+        assert!(trashed().insert_if_new(&conn).unwrap().is_none());
+        assert!(NoteBuilder::new("trashed")
+            .insert_if_new(&conn)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        assert_eq!(
+            NoteBuilder::new("x").id("not-a-ulid").build(),
+            Err(NoteBuilderError::InvalidId("not-a-ulid".to_string()))
+        );
+        assert_eq!(
+            NoteBuilder::new("x").subject_date("16/03/2024").build(),
+            Err(NoteBuilderError::InvalidSubjectDate(
+                "16/03/2024".to_string()
+            ))
+        );
+        assert_eq!(
+            NoteBuilder::new("x")
+                .created_at(2_000)
+                .updated_at(1_000)
+                .build(),
+            Err(NoteBuilderError::UpdatedBeforeCreated {
+                created_at: 2_000,
+                updated_at: 1_000
+            })
+        );
+    }
+}
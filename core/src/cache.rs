@@ -0,0 +1,134 @@
+use crate::db::search_notes;
+use crate::models::{Note, SearchQuery};
+use rusqlite::{Connection, Result};
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+/// Number of writes to `notes` so far, from any connection
+pub fn data_version(conn: &Connection) -> Result<i64> {
+    conn.query_row("SELECT version FROM data_version WHERE id = 1", [], |row| {
+        row.get(0)
+    })
+}
+
+/// Hit and miss counts of a [`SearchCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    /// Data version the entries were read at
+    version: i64,
+    entries: HashMap<SearchQuery, Vec<Note>>,
+    stats: CacheStats,
+}
+
+/// In-process cache for callers that run the same searches repeatedly
+/// (dashboards, long-running processes)
+///
+/// Results are keyed by the query and valid for one [`data_version`]: any
+/// write to `notes` empties the cache on the next lookup. Checking the
+/// version costs a single-row read instead of a full search.
+pub struct SearchCache {
+    state: Mutex<CacheState>,
+    /// Distinct queries kept before the cache is emptied
+    capacity: usize,
+}
+
+impl SearchCache {
+    pub const DEFAULT_CAPACITY: usize = 32;
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(CacheState::default()),
+            capacity,
+        }
+    }
+
+    /// [`search_notes`], answered from the cache when nothing changed since
+    pub fn search(&self, conn: &Connection, query: &SearchQuery) -> Result<Vec<Note>> {
+        let version = data_version(conn)?;
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.version != version {
+            state.entries.clear();
+            state.version = version;
+        }
+        if let Some(notes) = state.entries.get(query).cloned() {
+            state.stats.hits += 1;
+            return Ok(notes);
+        }
+
+        state.stats.misses += 1;
+        let notes = search_notes(conn, query)?;
+        if state.entries.len() >= self.capacity {
+            state.entries.clear();
+        }
+        state.entries.insert(query.clone(), notes.clone());
+        Ok(notes)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .stats
+    }
+}
+
+impl Default for SearchCache {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::{create_note, open_db_in_memory, soft_delete_note};
+
+    #[test]
+    fn test_cache_hits_until_a_write() {
+        let conn = open_db_in_memory().unwrap();
+        let cache = SearchCache::default();
+        let query = SearchQuery::default();
+        let note = create_note(&conn, "first", vec![], None).unwrap();
+
+        assert_eq!(cache.search(&conn, &query).unwrap().len(), 1);
+        assert_eq!(cache.search(&conn, &query).unwrap().len(), 1);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 1 });
+
+        let version = data_version(&conn).unwrap();
+        create_note(&conn, "second", vec![], None).unwrap();
+        assert!(data_version(&conn).unwrap() > version);
+        assert_eq!(cache.search(&conn, &query).unwrap().len(), 2);
+
+        soft_delete_note(&conn, &note.id).unwrap();
+        assert_eq!(cache.search(&conn, &query).unwrap().len(), 1);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 3 });
+    }
+
+    #[test]
+    fn test_cache_keys_by_query() {
+        let conn = open_db_in_memory().unwrap();
+        let cache = SearchCache::new(1);
+        create_note(&conn, "tagged", vec!["work".to_string()], None).unwrap();
+        create_note(&conn, "plain", vec![], None).unwrap();
+
+        let work = SearchQuery {
+            tags: vec!["work".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(cache.search(&conn, &work).unwrap().len(), 1);
+        assert_eq!(
+            cache.search(&conn, &SearchQuery::default()).unwrap().len(),
+            2
+        );
+        assert_eq!(cache.search(&conn, &work).unwrap().len(), 1);
+        assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 3 });
+    }
+}
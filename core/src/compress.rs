@@ -0,0 +1,231 @@
+//! Compressed storage for large note bodies
+//!
+//! Bodies over [`COMPRESS_THRESHOLD`] bytes are stored zstd-compressed in
+//! `notes.content_zstd`, with `content` left empty. Reading a note decodes
+//! them again, so callers only ever see plain text. SQL that needs the body
+//! (rebuilding the search index) goes through the
+//! `note_content(content, content_zstd)` function registered on every
+//! connection; the index triggers leave compressed bodies to
+//! [`crate::db::index_compressed_body`].
+
+use crate::db::{db_size, rebuild_search_index, with_transaction};
+use rusqlite::functions::FunctionFlags;
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, Result, Row};
+
+/// Bodies larger than this (in bytes) are stored compressed
+pub const COMPRESS_THRESHOLD: usize = 8 * 1024;
+
+/// zstd level; the library default, a good size/speed trade-off for text
+const LEVEL: i32 = 3;
+
+/// A note body as written to the `content` and `content_zstd` columns
+pub(crate) struct StoredContent<'a> {
+    pub text: &'a str,
+    pub zstd: Option<Vec<u8>>,
+}
+
+/// Pick the storage for `content`; compression is skipped when it doesn't
+/// make the body smaller
+pub(crate) fn encode(content: &str) -> Result<StoredContent<'_>> {
+    if content.len() <= COMPRESS_THRESHOLD {
+        return Ok(StoredContent {
+            text: content,
+            zstd: None,
+        });
+    }
+
+    let compressed = zstd::encode_all(content.as_bytes(), LEVEL)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    Ok(match compressed.len() < content.len() {
+        true => StoredContent {
+            text: "",
+            zstd: Some(compressed),
+        },
+        false => StoredContent {
+            text: content,
+            zstd: None,
+        },
+    })
+}
+
+/// The plain body from the two stored columns
+fn decode(text: String, zstd: Option<Vec<u8>>, column: usize) -> Result<String> {
+    let Some(compressed) = zstd else {
+        return Ok(text);
+    };
+    let bytes = zstd::decode_all(compressed.as_slice())
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(column, Type::Blob, Box::new(e)))?;
+    String::from_utf8(bytes)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(column, Type::Blob, Box::new(e)))
+}
+
+/// Read the body from a row that selected `content` at `text` and
+/// `content_zstd` at `zstd`
+pub(crate) fn read_content(row: &Row, text: usize, zstd: usize) -> Result<String> {
+    decode(row.get(text)?, row.get(zstd)?, zstd)
+}
+
+/// Register `note_content(content, content_zstd)`, which returns the plain body
+pub(crate) fn register_functions(conn: &Connection) -> Result<()> {
+    conn.create_scalar_function(
+        "note_content",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let text: Option<String> = ctx.get(0)?;
+            decode(text.unwrap_or_default(), ctx.get(1)?, 1)
+                .map_err(|e| rusqlite::Error::UserFunctionError(Box::new(e)))
+        },
+    )
+}
+
+/// Outcome of [`recompress`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecompressResult {
+    /// Notes moved to compressed storage
+    pub compressed: usize,
+    /// Notes moved back to plain storage (at or under the threshold)
+    pub expanded: usize,
+    /// Bytes the database file shrank by after vacuuming
+    pub reclaimed_bytes: u64,
+}
+
+/// Store every note the way [`encode`] would today and vacuum the file
+///
+/// Notes written before compression existed (or under another threshold)
+/// are converted; `updated_at` is left alone, since the content is the same.
+pub fn recompress(conn: &Connection) -> Result<RecompressResult> {
+    let size_before = db_size(conn)?;
+
+    let ids = {
+        let mut stmt = conn.prepare(
+            "SELECT id FROM notes
+             WHERE content_zstd IS NOT NULL OR length(CAST(content AS BLOB)) > ?1",
+        )?;
+        let ids = stmt
+            .query_map(params![COMPRESS_THRESHOLD as i64], |row| {
+                row.get::<_, String>(0)
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        ids
+    };
+
+    let (compressed, expanded) = with_transaction(conn, |tx| {
+        let (mut compressed, mut expanded) = (0, 0);
+        for id in &ids {
+            let (content, was_compressed) = tx.query_row(
+                "SELECT content, content_zstd FROM notes WHERE id = ?1",
+                params![id],
+                |row| {
+                    Ok((
+                        read_content(row, 0, 1)?,
+                        row.get_ref(1)?.as_blob_or_null()?.is_some(),
+                    ))
+                },
+            )?;
+            let stored = encode(&content)?;
+            if stored.zstd.is_some() == was_compressed {
+                continue;
+            }
+
+            tx.execute(
+                "UPDATE notes SET content = ?1, content_zstd = ?2 WHERE id = ?3",
+                params![stored.text, stored.zstd, id],
+            )?;
+            match was_compressed {
+                true => expanded += 1,
+                false => compressed += 1,
+            }
+        }
+        Ok((compressed, expanded))
+    })?;
+
+    if compressed + expanded > 0 {
+        conn.execute_batch("VACUUM")?;
+        rebuild_search_index(conn)?;
+    }
+
+    Ok(RecompressResult {
+        compressed,
+        expanded,
+        reclaimed_bytes: size_before.saturating_sub(db_size(conn)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::{create_note, get_note_by_id, open_db_in_memory, search_notes, update_note};
+    use crate::models::SearchQuery;
+
+    fn large_body(marker: &str) -> String {
+        format!("{}\n{}", marker, "log line: request handled\n".repeat(1000))
+    }
+
+    fn is_compressed(conn: &Connection, id: &str) -> bool {
+        conn.query_row(
+            "SELECT content_zstd IS NOT NULL FROM notes WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_large_content_round_trips_compressed() {
+        let conn = open_db_in_memory().unwrap();
+        let body = large_body("needle");
+        let note = create_note(&conn, &body, vec![], None).unwrap();
+
+        assert!(is_compressed(&conn, &note.id));
+        assert_eq!(
+            get_note_by_id(&conn, &note.id).unwrap().unwrap().content,
+            body
+        );
+
+        let found = search_notes(
+            &conn,
+            &SearchQuery {
+                text: Some("needle".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].content, body);
+
+        update_note(&conn, &note.id, "short now", vec![], None).unwrap();
+        assert!(!is_compressed(&conn, &note.id));
+        assert_eq!(
+            get_note_by_id(&conn, &note.id).unwrap().unwrap().content,
+            "short now"
+        );
+    }
+
+    #[test]
+    fn test_recompress_converts_plain_rows() {
+        let conn = open_db_in_memory().unwrap();
+        let body = large_body("legacy");
+        let note = create_note(&conn, "placeholder", vec![], None).unwrap();
+        // As written before compression existed
+        conn.execute(
+            "UPDATE notes SET content = ?1 WHERE id = ?2",
+            params![body, note.id],
+        )
+        .unwrap();
+
+        let result = recompress(&conn).unwrap();
+        assert_eq!((result.compressed, result.expanded), (1, 0));
+        assert!(is_compressed(&conn, &note.id));
+
+        let stored = get_note_by_id(&conn, &note.id).unwrap().unwrap();
+        assert_eq!(stored.content, body);
+        assert_eq!(stored.updated_at, note.updated_at);
+
+        let again = recompress(&conn).unwrap();
+        assert_eq!((again.compressed, again.expanded), (0, 0));
+    }
+}
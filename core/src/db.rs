@@ -1,48 +1,158 @@
+use crate::attachment::{read_attachments, replace_attachments, ATTACHMENTS_COLUMN};
+use crate::compress::{self, read_content};
+use crate::dedupe::content_hash;
 use crate::models::{Note, SearchQuery};
-use crate::schema;
-use rusqlite::{params, Connection, Result};
-use std::path::Path;
+use crate::schema::{self, DATE_FORMAT};
+use crate::validation::NoteLimits;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, DatabaseName, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long a connection waits for a competing writer before failing with `database is locked`
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Open or create a notes database at the specified path
+///
+/// Pending migrations are applied automatically. An existing database is first
+/// copied to `<name>.pre-vN.bak` (see [`migration_backup_path`]).
 pub fn open_db(path: &Path) -> Result<Connection> {
-    let conn = Connection::open(path)?;
+    let conn = open_db_unmigrated(path)?;
+
+    let version = schema::get_schema_version(&conn)?;
+    if version > 0 && version < schema::LATEST_VERSION {
+        backup_db(&conn, &migration_backup_path(path, schema::LATEST_VERSION))?;
+    }
+
     schema::migrate(&conn)?;
     Ok(conn)
 }
 
-/// Create a new note
+/// Open a migrated database that lives only in memory (tests, throwaway sessions)
+pub fn open_db_in_memory() -> Result<Connection> {
+    let conn = Connection::open_in_memory()?;
+    configure_connection(&conn)?;
+    schema::migrate(&conn)?;
+    Ok(conn)
+}
+
+/// Open a database without running migrations (for status checks and tooling)
+pub fn open_db_unmigrated(path: &Path) -> Result<Connection> {
+    let conn = Connection::open(path)?;
+    configure_connection(&conn)?;
+    Ok(conn)
+}
+
+/// Where the pre-migration copy of `path` is written, e.g. `notes.db.pre-v2.bak`
+pub fn migration_backup_path(path: &Path, version: i32) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".pre-v{}.bak", version));
+    path.with_file_name(name)
+}
+
+/// Copy the database to `dest` using the SQLite online backup API
+pub fn backup_db(conn: &Connection, dest: &Path) -> Result<()> {
+    conn.backup(DatabaseName::Main, dest, None)
+}
+
+/// Apply connection-level settings so concurrent `jot` processes can share a database
+pub fn configure_connection(conn: &Connection) -> Result<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    // WAL lets readers proceed while a writer holds the lock
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    // NORMAL is durable across application crashes in WAL mode and much faster than FULL
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    conn.pragma_update(None, "foreign_keys", "ON")?;
+    compress::register_functions(conn)?;
+    Ok(())
+}
+
+/// Run `f` inside a transaction, committing on success and rolling back on error
+///
+/// Use this for multi-note operations (import, prune, sync merge) so they are
+/// atomic and avoid one fsync per statement.
+pub fn with_transaction<T, F>(conn: &Connection, f: F) -> Result<T>
+where
+    F: FnOnce(&Connection) -> Result<T>,
+{
+    let tx = conn.unchecked_transaction()?;
+    let result = f(&tx)?;
+    tx.commit()?;
+    Ok(result)
+}
+
+/// Create a new note (enforcing the default [`NoteLimits`], dated at
+/// [`local_offset`])
 pub fn create_note(
     conn: &Connection,
     content: &str,
     tags: Vec<String>,
     date: Option<String>,
 ) -> Result<Note> {
+    create_note_with_limits(
+        conn,
+        content,
+        tags,
+        date,
+        &local_offset(),
+        &NoteLimits::default(),
+    )
+}
+
+/// Create a new note, rejecting content that exceeds `limits`; a `date` is
+/// recorded as set at `utc_offset` (e.g. `+02:00`)
+pub fn create_note_with_limits(
+    conn: &Connection,
+    content: &str,
+    tags: Vec<String>,
+    date: Option<String>,
+    utc_offset: &str,
+    limits: &NoteLimits,
+) -> Result<Note> {
+    limits.validate(content, &tags)?;
+
     let id = ulid::Ulid::new().to_string();
     let now = chrono::Utc::now().timestamp_millis();
     let tags_json = serde_json::to_string(&tags)
         .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    let subject_tz = date.as_ref().map(|_| utc_offset.to_string());
+    let stored = compress::encode(content)?;
 
     conn.execute(
-        "INSERT INTO notes (id, content, tags, subject_date, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![id, content, tags_json, date, now, now],
+        "INSERT INTO notes (id, content, content_zstd, tags, subject_date, subject_tz, created_at, updated_at, content_hash) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![id, stored.text, stored.zstd, tags_json, date, subject_tz, now, now, content_hash(content)],
     )?;
+    index_compressed_body(conn, &id, content, &stored)?;
 
     Ok(Note {
         id,
         content: content.to_string(),
         tags,
         subject_date: date,
+        subject_tz,
         created_at: now,
         updated_at: now,
         deleted_at: None,
+        pinned: false,
+        status: None,
+        meta: BTreeMap::new(),
+        attachments: Vec::new(),
+        archived_at: None,
     })
 }
 
+/// This device's current UTC offset, e.g. `+02:00`
+pub fn local_offset() -> String {
+    chrono::Local::now().format("%:z").to_string()
+}
+
 /// Get a note by ID
 pub fn get_note_by_id(conn: &Connection, id: &str) -> Result<Option<Note>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, content, tags, subject_date, created_at, updated_at, deleted_at FROM notes WHERE id = ?1"
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, content, tags, subject_date, created_at, updated_at, deleted_at, subject_tz, content_zstd, pinned, status, meta, {}, archived_at FROM notes WHERE id = ?1",
+        ATTACHMENTS_COLUMN
+    ))?;
 
     let note = stmt.query_row(params![id], |row| {
         let tags_json: String = row.get(2)?;
@@ -52,12 +162,18 @@ pub fn get_note_by_id(conn: &Connection, id: &str) -> Result<Option<Note>> {
 
         Ok(Note {
             id: row.get(0)?,
-            content: row.get(1)?,
+            content: read_content(row, 1, 8)?,
             tags,
             subject_date: row.get(3)?,
             created_at: row.get(4)?,
             updated_at: row.get(5)?,
             deleted_at: row.get(6)?,
+            subject_tz: row.get(7)?,
+            pinned: row.get(9)?,
+            status: row.get(10)?,
+            meta: read_meta(row, 11)?,
+            attachments: read_attachments(row, 12)?,
+            archived_at: row.get(13)?,
         })
     });
 
@@ -68,104 +184,201 @@ pub fn get_note_by_id(conn: &Connection, id: &str) -> Result<Option<Note>> {
     }
 }
 
+/// Newest first by subject date (creation date when unset), then creation
+/// time; must match the `idx_live_sort` index for the order to come from it
+const SEARCH_ORDER: &str =
+    "COALESCE(subject_date, DATE(created_at/1000, 'unixepoch')) DESC, created_at DESC";
+
+/// Put ahead of [`SEARCH_ORDER`] unless the query is chronological; together
+/// they match `idx_live_pinned_sort`
+const PINNED_ORDER: &str = "pinned DESC, ";
+
+/// Number of live notes per subject date in `[from, to]`, oldest day first
+///
+/// Days without notes are left out.
+pub fn count_notes_per_day(
+    conn: &Connection,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<(String, i64)>> {
+    let mut stmt = conn.prepare(
+        "SELECT subject_date, COUNT(*) FROM notes
+         WHERE deleted_at IS NULL AND subject_date >= ?1 AND subject_date <= ?2
+         GROUP BY subject_date ORDER BY subject_date",
+    )?;
+    let counts = stmt
+        .query_map(
+            params![
+                from.format(DATE_FORMAT).to_string(),
+                to.format(DATE_FORMAT).to_string()
+            ],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(counts)
+}
+
 /// Search notes with various filters
 pub fn search_notes(conn: &Connection, query: &SearchQuery) -> Result<Vec<Note>> {
-    let mut sql = String::from(
-        "SELECT id, content, tags, subject_date, created_at, updated_at, deleted_at FROM notes WHERE 1=1",
-    );
+    let (sql, params) = search_sql(query);
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|b| b.as_ref()).collect();
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params_refs.as_slice(), |row| {
+        let tags_json: String = row.get(2)?;
+        let tags: Vec<String> = serde_json::from_str(&tags_json).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
+        })?;
+
+        Ok(Note {
+            id: row.get(0)?,
+            content: read_content(row, 1, 8)?,
+            tags,
+            subject_date: row.get(3)?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+            deleted_at: row.get(6)?,
+            subject_tz: row.get(7)?,
+            pinned: row.get(9)?,
+            status: row.get(10)?,
+            meta: read_meta(row, 11)?,
+            attachments: read_attachments(row, 12)?,
+            archived_at: row.get(13)?,
+        })
+    })?;
+
+    let mut notes = Vec::new();
+    for note in rows {
+        notes.push(note?);
+    }
+
+    Ok(notes)
+}
+
+/// SQL and parameters for [`search_notes`]
+fn search_sql(query: &SearchQuery) -> (String, Vec<Box<dyn rusqlite::ToSql>>) {
+    let mut sql = String::new();
     let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
+    // Full-text matches, joined on rowid and ranked by bm25
+    if let Some(ref match_query) = query.match_query {
+        sql.push_str(
+            "WITH hits AS (SELECT rowid AS hit_rowid, rank AS hit_rank FROM notes_fts WHERE notes_fts MATCH ?) ",
+        );
+        params.push(Box::new(match_query.clone()));
+    }
+    sql.push_str(&format!(
+        "SELECT id, content, tags, subject_date, created_at, updated_at, deleted_at, subject_tz, content_zstd, pinned, status, meta, {}, archived_at FROM notes",
+        ATTACHMENTS_COLUMN
+    ));
+    if query.match_query.is_some() {
+        sql.push_str(" JOIN hits ON hits.hit_rowid = notes.rowid");
+    }
+    sql.push_str(" WHERE 1=1");
+
     // Filter by deleted status
     if !query.include_deleted {
         sql.push_str(" AND deleted_at IS NULL");
     }
 
-    // Full-text search
+    if !query.include_archived {
+        sql.push_str(" AND archived_at IS NULL");
+    }
+
+    // Substring search; compressed bodies are decoded only when present
     if let Some(ref text) = query.text {
-        sql.push_str(" AND content LIKE ?");
-        params.push(Box::new(format!("%{}%", text)));
+        sql.push_str(
+            " AND (content LIKE ? OR (content_zstd IS NOT NULL AND note_content(content, content_zstd) LIKE ?))",
+        );
+        let pattern = format!("%{}%", text);
+        params.push(Box::new(pattern.clone()));
+        params.push(Box::new(pattern));
     }
 
     // Subject date range filters
-    if let Some(ref date_from) = query.date_from {
+    if let Some(date_from) = query.date_from {
         sql.push_str(" AND subject_date >= ?");
-        params.push(Box::new(date_from.clone()));
+        params.push(Box::new(date_from.format(DATE_FORMAT).to_string()));
     }
 
-    if let Some(ref date_to) = query.date_to {
+    if let Some(date_to) = query.date_to {
         sql.push_str(" AND subject_date <= ?");
-        params.push(Box::new(date_to.clone()));
-    }
-
-    // Created at range filters (convert ISO date strings to milliseconds)
-    if let Some(ref created_from) = query.created_from {
-        // Parse ISO date and convert to start of day timestamp in milliseconds
-        if let Ok(date) = chrono::NaiveDate::parse_from_str(created_from, "%Y-%m-%d") {
-            let timestamp = date
-                .and_hms_opt(0, 0, 0)
-                .map(|dt| dt.and_utc().timestamp_millis())
-                .unwrap_or(0);
-            sql.push_str(" AND created_at >= ?");
-            params.push(Box::new(timestamp));
-        }
+        params.push(Box::new(date_to.format(DATE_FORMAT).to_string()));
     }
 
-    if let Some(ref created_to) = query.created_to {
-        // Parse ISO date and convert to end of day timestamp in milliseconds
-        if let Ok(date) = chrono::NaiveDate::parse_from_str(created_to, "%Y-%m-%d") {
-            let timestamp = date
-                .and_hms_opt(23, 59, 59)
-                .map(|dt| dt.and_utc().timestamp_millis())
-                .unwrap_or(0);
-            sql.push_str(" AND created_at <= ?");
-            params.push(Box::new(timestamp));
-        }
+    // Created at range filters (milliseconds)
+    if let Some(created_from) = query.created_from {
+        sql.push_str(" AND created_at >= ?");
+        params.push(Box::new(created_from));
+    }
+
+    if let Some(created_to) = query.created_to {
+        sql.push_str(" AND created_at <= ?");
+        params.push(Box::new(created_to));
     }
 
-    // Tag filters
+    // Tag filters: the tag itself or one nested under it (`project` matches
+    // `project/alpha`), ignoring case
     for tag in &query.tags {
-        sql.push_str(" AND tags LIKE ?");
-        params.push(Box::new(format!("%\"{}%", tag)));
+        sql.push_str(
+            " AND notes.id IN (SELECT note_tags.note_id FROM note_tags JOIN tags ON tags.id = note_tags.tag_id
+               WHERE tags.name = ? COLLATE NOCASE OR tags.name LIKE ? ESCAPE '\\')",
+        );
+        params.push(Box::new(tag.clone()));
+        params.push(Box::new(format!("{}/%", escape_like(tag))));
     }
 
-    // Order by subject_date (or created_at as fallback), then created_at
-    // COALESCE returns first non-NULL value
-    sql.push_str(" ORDER BY COALESCE(subject_date, DATE(created_at/1000, 'unixepoch')) DESC, created_at DESC");
+    for tag in &query.exclude_tags {
+        sql.push_str(
+            " AND notes.id NOT IN (SELECT note_tags.note_id FROM note_tags JOIN tags ON tags.id = note_tags.tag_id
+               WHERE tags.name = ? COLLATE NOCASE)",
+        );
+        params.push(Box::new(tag.clone()));
+    }
 
-    // Limit
-    if let Some(limit) = query.limit {
-        sql.push_str(" LIMIT ?");
-        params.push(Box::new(limit as i64));
+    if let Some(ref status) = query.status {
+        sql.push_str(" AND status = ?");
+        params.push(Box::new(status.clone()));
     }
 
-    let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|b| b.as_ref()).collect();
-    let mut stmt = conn.prepare(&sql)?;
-    let rows = stmt.query_map(params_refs.as_slice(), |row| {
-        let tags_json: String = row.get(2)?;
-        let tags: Vec<String> = serde_json::from_str(&tags_json).map_err(|e| {
-            rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
-        })?;
+    for (key, value) in &query.meta {
+        sql.push_str(
+            " AND EXISTS (SELECT 1 FROM json_each(notes.meta) WHERE json_each.key = ? AND json_each.value = ?)",
+        );
+        params.push(Box::new(key.clone()));
+        params.push(Box::new(value.clone()));
+    }
 
-        Ok(Note {
-            id: row.get(0)?,
-            content: row.get(1)?,
-            tags,
-            subject_date: row.get(3)?,
-            created_at: row.get(4)?,
-            updated_at: row.get(5)?,
-            deleted_at: row.get(6)?,
-        })
-    })?;
+    sql.push_str(" ORDER BY ");
+    if !query.chronological {
+        sql.push_str(PINNED_ORDER);
+    }
+    if query.match_query.is_some() {
+        sql.push_str("hits.hit_rank, ");
+    }
+    sql.push_str(SEARCH_ORDER);
 
-    let mut notes = Vec::new();
-    for note in rows {
-        notes.push(note?);
+    // Limit and offset (SQLite needs a LIMIT before an OFFSET; -1 is none)
+    if query.limit.is_some() || query.offset.is_some() {
+        sql.push_str(" LIMIT ?");
+        params.push(Box::new(query.limit.map_or(-1, |limit| limit as i64)));
+    }
+    if let Some(offset) = query.offset {
+        sql.push_str(" OFFSET ?");
+        params.push(Box::new(offset as i64));
     }
 
-    Ok(notes)
+    (sql, params)
+}
+
+/// `text` with the LIKE wildcards (and the `\\` escaping them) escaped
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
 }
 
-/// Update note content and/or tags
+/// Update note content and/or tags (enforcing the default [`NoteLimits`],
+/// dated at [`local_offset`])
 pub fn update_note(
     conn: &Connection,
     id: &str,
@@ -173,14 +386,54 @@ pub fn update_note(
     tags: Vec<String>,
     date: Option<String>,
 ) -> Result<()> {
+    update_note_with_limits(
+        conn,
+        id,
+        content,
+        tags,
+        date,
+        &local_offset(),
+        &NoteLimits::default(),
+    )
+}
+
+/// Update note content and/or tags, rejecting content that exceeds `limits`;
+/// a changed `date` is recorded as set at `utc_offset`
+pub fn update_note_with_limits(
+    conn: &Connection,
+    id: &str,
+    content: &str,
+    tags: Vec<String>,
+    date: Option<String>,
+    utc_offset: &str,
+    limits: &NoteLimits,
+) -> Result<()> {
+    limits.validate(content, &tags)?;
+
     let now = chrono::Utc::now().timestamp_millis();
     let tags_json = serde_json::to_string(&tags)
         .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
 
+    // Keep the recorded offset unless the date itself changes
+    let subject_tz = date.as_ref().map(|_| utc_offset.to_string());
+    let stored = compress::encode(content)?;
     conn.execute(
-        "UPDATE notes SET content = ?1, tags = ?2, subject_date = ?3, updated_at = ?4 WHERE id = ?5",
-        params![content, tags_json, date, now, id],
+        "UPDATE notes SET content = ?1, content_zstd = ?8, tags = ?2,
+             subject_tz = CASE WHEN subject_date IS ?3 THEN subject_tz ELSE ?4 END,
+             subject_date = ?3, updated_at = ?5, content_hash = ?6
+         WHERE id = ?7",
+        params![
+            stored.text,
+            tags_json,
+            date,
+            subject_tz,
+            now,
+            content_hash(content),
+            id,
+            stored.zstd
+        ],
     )?;
+    index_compressed_body(conn, id, content, &stored)?;
 
     Ok(())
 }
@@ -197,14 +450,118 @@ pub fn soft_delete_note(conn: &Connection, id: &str) -> Result<()> {
     Ok(())
 }
 
+/// Bring back a soft-deleted note, returning whether it was deleted
+///
+/// Bumps `updated_at`, so the restore reaches other devices on the next sync.
+pub fn restore_note(conn: &Connection, id: &str) -> Result<bool> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let changed = conn.execute(
+        "UPDATE notes SET deleted_at = NULL, updated_at = ?1
+         WHERE id = ?2 AND deleted_at IS NOT NULL",
+        params![now, id],
+    )?;
+    Ok(changed > 0)
+}
+
+/// Pin a live note, returning whether it was unpinned before
+///
+/// Bumps `updated_at`, so the pin reaches other devices on the next sync.
+pub fn pin_note(conn: &Connection, id: &str) -> Result<bool> {
+    set_pinned(conn, id, true)
+}
+
+/// Unpin a live note, returning whether it was pinned before
+pub fn unpin_note(conn: &Connection, id: &str) -> Result<bool> {
+    set_pinned(conn, id, false)
+}
+
+/// Archive a live note, returning whether it wasn't archived before
+///
+/// Archived notes are left out of [`search_notes`] unless
+/// [`SearchQuery::include_archived`] is set. Bumps `updated_at`, so the
+/// change reaches other devices on the next sync.
+pub fn archive_note(conn: &Connection, id: &str) -> Result<bool> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let changed = conn.execute(
+        "UPDATE notes SET archived_at = ?1, updated_at = ?1
+         WHERE id = ?2 AND archived_at IS NULL AND deleted_at IS NULL",
+        params![now, id],
+    )?;
+    Ok(changed > 0)
+}
+
+/// Unarchive a live note, returning whether it was archived before
+pub fn unarchive_note(conn: &Connection, id: &str) -> Result<bool> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let changed = conn.execute(
+        "UPDATE notes SET archived_at = NULL, updated_at = ?1
+         WHERE id = ?2 AND archived_at IS NOT NULL AND deleted_at IS NULL",
+        params![now, id],
+    )?;
+    Ok(changed > 0)
+}
+
+/// Set (or with `None` clear) a live note's status, returning whether it changed
+///
+/// Bumps `updated_at`, so the change reaches other devices on the next sync.
+pub fn set_status(conn: &Connection, id: &str, status: Option<&str>) -> Result<bool> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let changed = conn.execute(
+        "UPDATE notes SET status = ?1, updated_at = ?2
+         WHERE id = ?3 AND status IS NOT ?1 AND deleted_at IS NULL",
+        params![status, now, id],
+    )?;
+    Ok(changed > 0)
+}
+
+/// Replace a note's metadata (see [`Note::meta`]), bumping `updated_at`
+pub fn set_meta(conn: &Connection, id: &str, meta: &BTreeMap<String, String>) -> Result<()> {
+    let now = chrono::Utc::now().timestamp_millis();
+    conn.execute(
+        "UPDATE notes SET meta = ?1, updated_at = ?2 WHERE id = ?3",
+        params![meta_json(meta)?, now, id],
+    )?;
+    Ok(())
+}
+
+/// Read the `meta` column: a JSON object, NULL when the note has none
+fn read_meta(row: &rusqlite::Row, idx: usize) -> Result<BTreeMap<String, String>> {
+    let Some(json) = row.get::<_, Option<String>>(idx)? else {
+        return Ok(BTreeMap::new());
+    };
+    serde_json::from_str(&json).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn meta_json(meta: &BTreeMap<String, String>) -> Result<Option<String>> {
+    if meta.is_empty() {
+        return Ok(None);
+    }
+    serde_json::to_string(meta)
+        .map(Some)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn set_pinned(conn: &Connection, id: &str, pinned: bool) -> Result<bool> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let changed = conn.execute(
+        "UPDATE notes SET pinned = ?1, updated_at = ?2
+         WHERE id = ?3 AND pinned != ?1 AND deleted_at IS NULL",
+        params![pinned, now, id],
+    )?;
+    Ok(changed > 0)
+}
+
 /// Get all notes updated since a specific timestamp (for sync)
 pub fn get_notes_since(conn: &Connection, timestamp: i64) -> Result<Vec<Note>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, content, tags, subject_date, created_at, updated_at, deleted_at
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, content, tags, subject_date, created_at, updated_at, deleted_at, subject_tz, content_zstd, pinned, status, meta, {}, archived_at
          FROM notes
          WHERE updated_at > ?1
          ORDER BY updated_at ASC",
-    )?;
+        ATTACHMENTS_COLUMN
+    ))?;
 
     let rows = stmt.query_map(params![timestamp], |row| {
         let tags_json: String = row.get(2)?;
@@ -214,12 +571,18 @@ pub fn get_notes_since(conn: &Connection, timestamp: i64) -> Result<Vec<Note>> {
 
         Ok(Note {
             id: row.get(0)?,
-            content: row.get(1)?,
+            content: read_content(row, 1, 8)?,
             tags,
             subject_date: row.get(3)?,
             created_at: row.get(4)?,
             updated_at: row.get(5)?,
             deleted_at: row.get(6)?,
+            subject_tz: row.get(7)?,
+            pinned: row.get(9)?,
+            status: row.get(10)?,
+            meta: read_meta(row, 11)?,
+            attachments: read_attachments(row, 12)?,
+            archived_at: row.get(13)?,
         })
     })?;
 
@@ -240,22 +603,260 @@ pub fn upsert_note(conn: &Connection, note: &Note) -> Result<()> {
     if let Some(existing) = get_note_by_id(conn, &note.id)? {
         // Only update if incoming note is newer
         if note.updated_at > existing.updated_at {
+            let stored = compress::encode(&note.content)?;
             conn.execute(
-                "UPDATE notes SET content = ?1, tags = ?2, subject_date = ?3, created_at = ?4, updated_at = ?5, deleted_at = ?6 WHERE id = ?7",
-                params![note.content, tags_json, note.subject_date, note.created_at, note.updated_at, note.deleted_at, note.id],
+                "UPDATE notes SET content = ?1, content_zstd = ?2, tags = ?3, subject_date = ?4, subject_tz = ?5, created_at = ?6, updated_at = ?7, deleted_at = ?8, content_hash = ?9, pinned = ?11, status = ?12, meta = ?13, archived_at = ?14 WHERE id = ?10",
+                params![stored.text, stored.zstd, tags_json, note.subject_date, note.subject_tz, note.created_at, note.updated_at, note.deleted_at, content_hash(&note.content), note.id, note.pinned, note.status, meta_json(&note.meta)?, note.archived_at],
             )?;
+            index_compressed_body(conn, &note.id, &note.content, &stored)?;
+            replace_attachments(conn, &note.id, &note.attachments)?;
         }
     } else {
-        // Insert new note
-        conn.execute(
-            "INSERT INTO notes (id, content, tags, subject_date, created_at, updated_at, deleted_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![note.id, note.content, tags_json, note.subject_date, note.created_at, note.updated_at, note.deleted_at],
-        )?;
+        insert_note(conn, note)?;
+    }
+
+    Ok(())
+}
+
+/// Insert a fully-specified note as-is (ID and timestamps are taken from the note)
+pub fn insert_note(conn: &Connection, note: &Note) -> Result<()> {
+    let tags_json = serde_json::to_string(&note.tags)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    let stored = compress::encode(&note.content)?;
+
+    conn.execute(
+        "INSERT INTO notes (id, content, content_zstd, tags, subject_date, subject_tz, created_at, updated_at, deleted_at, content_hash, pinned, status, meta, archived_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![note.id, stored.text, stored.zstd, tags_json, note.subject_date, note.subject_tz, note.created_at, note.updated_at, note.deleted_at, content_hash(&note.content), note.pinned, note.status, meta_json(&note.meta)?, note.archived_at],
+    )?;
+    index_compressed_body(conn, &note.id, &note.content, &stored)?;
+    if !note.attachments.is_empty() {
+        replace_attachments(conn, &note.id, &note.attachments)?;
+    }
+
+    Ok(())
+}
+
+/// Outcome of [`compact_db`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactResult {
+    /// Tombstones that were hard-deleted
+    pub purged: usize,
+    /// Bytes the database file shrank by after vacuuming
+    pub reclaimed_bytes: u64,
+}
+
+/// Hard-delete tombstones (soft-deleted notes) deleted before `deleted_before`
+/// (milliseconds) with [`purge_deleted_before`], and vacuum the file
+///
+/// A purged tombstone can no longer propagate its deletion, so the horizon
+/// must be longer than any peer is expected to go without syncing.
+pub fn compact_db(conn: &Connection, deleted_before: i64) -> Result<CompactResult> {
+    let size_before = db_size(conn)?;
+
+    let purged = purge_deleted_before(conn, deleted_before)?;
+
+    if purged > 0 {
+        conn.execute_batch("VACUUM")?;
+        rebuild_search_index(conn)?;
     }
 
+    Ok(CompactResult {
+        purged,
+        reclaimed_bytes: size_before.saturating_sub(db_size(conn)?),
+    })
+}
+
+/// Hard-delete notes soft-deleted before `deleted_before` (milliseconds) and
+/// drop the aliases, time entries, attachments and tags only they used,
+/// returning how many notes were removed
+///
+/// Unlike [`compact_db`] the file isn't vacuumed, so it doesn't shrink.
+pub fn purge_deleted_before(conn: &Connection, deleted_before: i64) -> Result<usize> {
+    with_transaction(conn, |tx| {
+        let purged = tx.execute(
+            "DELETE FROM notes WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+            params![deleted_before],
+        )?;
+        tx.execute(
+            "DELETE FROM note_aliases WHERE note_id NOT IN (SELECT id FROM notes)",
+            [],
+        )?;
+        tx.execute(
+            "DELETE FROM time_entries WHERE note_id NOT IN (SELECT id FROM notes)",
+            [],
+        )?;
+        crate::attachment::prune_blobs(tx)?;
+        tx.execute(
+            "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM note_tags)",
+            [],
+        )?;
+        Ok(purged)
+    })
+}
+
+/// Add a note's body to `notes_fts` when it was stored compressed
+///
+/// The `notes_fts` triggers are plain SQL so that other tools can write
+/// notes, which leaves them unable to read compressed bodies; the code that
+/// writes one indexes it right after.
+pub(crate) fn index_compressed_body(
+    conn: &Connection,
+    id: &str,
+    content: &str,
+    stored: &compress::StoredContent,
+) -> Result<()> {
+    if stored.zstd.is_none() {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT INTO notes_fts(rowid, content, tags) SELECT rowid, ?2, tags FROM notes WHERE id = ?1",
+        params![id, content],
+    )?;
     Ok(())
 }
 
+/// Refill `notes_fts` from `notes`
+///
+/// The index is keyed by the notes' rowids, which SQLite may renumber when
+/// vacuuming a table without an `INTEGER PRIMARY KEY`, so this runs after
+/// every `VACUUM`.
+pub(crate) fn rebuild_search_index(conn: &Connection) -> Result<()> {
+    with_transaction(conn, |tx| {
+        tx.execute_batch(
+            "INSERT INTO notes_fts(notes_fts) VALUES ('delete-all');
+             INSERT INTO notes_fts(rowid, content, tags)
+             SELECT rowid, note_content(content, content_zstd), tags FROM notes;",
+        )
+    })
+}
+
+/// How far `notes_fts` is from `notes`, found by [`check_search_index`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchIndexCheck {
+    /// Rows in `notes`
+    pub notes: usize,
+    /// Rows in `notes_fts`
+    pub indexed: usize,
+    /// Notes without an index row
+    pub missing: usize,
+    /// Index rows whose terms differ from their note's body and tags
+    pub stale: usize,
+    /// Index rows without a note
+    pub orphaned: usize,
+}
+
+impl SearchIndexCheck {
+    pub fn is_consistent(&self) -> bool {
+        self.notes == self.indexed && self.missing == 0 && self.stale == 0 && self.orphaned == 0
+    }
+}
+
+/// Outcome of [`reindex_db`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReindexResult {
+    /// The index as found, before rebuilding
+    pub before: SearchIndexCheck,
+    /// The rebuilt index, consistent unless something writes around the triggers
+    pub after: SearchIndexCheck,
+}
+
+/// Compare `notes_fts` with `notes`: row counts, and a hash of the terms
+/// indexed for each note against those its body and tags give
+///
+/// `notes_fts` keeps no text to compare with, so the notes are tokenized
+/// into a scratch index in the `temp` schema and the two are compared
+/// through `fts5vocab` tables.
+pub fn check_search_index(conn: &Connection) -> Result<SearchIndexCheck> {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE temp.notes_fts_expected USING fts5(
+             content,
+             tags,
+             content = '',
+             tokenize = 'unicode61 remove_diacritics 2'
+         );
+         INSERT INTO temp.notes_fts_expected(rowid, content, tags)
+         SELECT rowid, note_content(content, content_zstd), tags FROM main.notes;
+         CREATE VIRTUAL TABLE temp.notes_fts_terms USING fts5vocab(main, notes_fts, instance);
+         CREATE VIRTUAL TABLE temp.notes_fts_expected_terms
+             USING fts5vocab(temp, notes_fts_expected, instance);",
+    )?;
+    let result = compare_search_index(conn);
+    conn.execute_batch(
+        "DROP TABLE temp.notes_fts_expected_terms;
+         DROP TABLE temp.notes_fts_terms;
+         DROP TABLE temp.notes_fts_expected;",
+    )?;
+    result
+}
+
+fn compare_search_index(conn: &Connection) -> Result<SearchIndexCheck> {
+    let notes = row_ids(conn, "SELECT rowid FROM main.notes")?;
+    let index = row_ids(conn, "SELECT rowid FROM main.notes_fts")?;
+    let expected = term_hashes(conn, "temp.notes_fts_expected_terms")?;
+    let indexed = term_hashes(conn, "temp.notes_fts_terms")?;
+
+    let mut check = SearchIndexCheck {
+        notes: notes.len(),
+        indexed: index.len(),
+        ..SearchIndexCheck::default()
+    };
+    for rowid in &notes {
+        if !index.contains(rowid) {
+            check.missing += 1;
+        } else if expected.get(rowid) != indexed.get(rowid) {
+            check.stale += 1;
+        }
+    }
+    check.orphaned = index.iter().filter(|rowid| !notes.contains(rowid)).count();
+    Ok(check)
+}
+
+fn row_ids(conn: &Connection, sql: &str) -> Result<BTreeSet<i64>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Hash of the terms an `fts5vocab` instance table lists for each document
+/// with any, in column and position order
+fn term_hashes(conn: &Connection, vocab: &str) -> Result<BTreeMap<i64, String>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT doc, col, term FROM {} ORDER BY doc, col, offset",
+        vocab
+    ))?;
+    let mut terms: BTreeMap<i64, String> = BTreeMap::new();
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let listed = terms.entry(row.get(0)?).or_default();
+        listed.push_str(&row.get::<_, String>(1)?);
+        listed.push('\0');
+        listed.push_str(&row.get::<_, String>(2)?);
+        listed.push('\0');
+    }
+    Ok(terms
+        .into_iter()
+        .map(|(doc, listed)| (doc, content_hash(&listed)))
+        .collect())
+}
+
+/// Rebuild `notes_fts` from `notes`, e.g. after the database was edited with
+/// another tool, and check the result
+pub fn reindex_db(conn: &Connection) -> Result<ReindexResult> {
+    let before = check_search_index(conn)?;
+    rebuild_search_index(conn)?;
+    Ok(ReindexResult {
+        before,
+        after: check_search_index(conn)?,
+    })
+}
+
+/// Size of the main database in bytes, from the page count
+pub(crate) fn db_size(conn: &Connection) -> Result<u64> {
+    let pages: u64 = conn.pragma_query_value(None, "page_count", |row| row.get(0))?;
+    let page_size: u64 = conn.pragma_query_value(None, "page_size", |row| row.get(0))?;
+    Ok(pages * page_size)
+}
+
 /// Get sync state value
 pub fn get_sync_state(conn: &Connection, key: &str) -> Result<Option<String>> {
     let mut stmt = conn.prepare("SELECT value FROM sync_state WHERE key = ?1")?;
@@ -285,11 +886,102 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_reindex_repairs_drifted_search_index() {
+        let conn = open_db_in_memory().unwrap();
+        let dropped = create_note(&conn, "dropped from the index", vec![], None).unwrap();
+        let edited = create_note(&conn, "edited behind the triggers", vec![], None).unwrap();
+        create_note(&conn, "untouched", vec!["ok".to_string()], None).unwrap();
+        assert!(check_search_index(&conn).unwrap().is_consistent());
+
+        // Drift the index the way a manual edit with another tool would
+        conn.execute_batch(&format!(
+            "DELETE FROM notes_fts WHERE rowid = (SELECT rowid FROM notes WHERE id = '{}');
+             UPDATE notes_fts SET content = 'something else', tags = '[]'
+                 WHERE rowid = (SELECT rowid FROM notes WHERE id = '{}');
+             INSERT INTO notes_fts(rowid, content, tags) VALUES (9999, 'ghost', '[]');",
+            dropped.id, edited.id
+        ))
+        .unwrap();
+
+        let result = reindex_db(&conn).unwrap();
+        assert_eq!(
+            result.before,
+            SearchIndexCheck {
+                notes: 3,
+                indexed: 3,
+                missing: 1,
+                stale: 1,
+                orphaned: 1,
+            }
+        );
+        assert!(!result.before.is_consistent());
+        assert!(result.after.is_consistent());
+        assert_eq!(result.after.indexed, 3);
+
+        let query = SearchQuery {
+            match_query: Some("dropped".to_string()),
+            ..Default::default()
+        };
+        let found = search_notes(&conn, &query).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, dropped.id);
+    }
+
+    #[test]
+    fn test_compact_db_purges_old_tombstones_only() {
+        let conn = open_db_in_memory().unwrap();
+        let kept = create_note(&conn, "kept", vec![], None).unwrap();
+        let old = create_note(&conn, &"old ".repeat(2000), vec![], None).unwrap();
+        let recent = create_note(&conn, "recently deleted", vec![], None).unwrap();
+
+        conn.execute(
+            "UPDATE notes SET deleted_at = 1000, updated_at = 1000 WHERE id = ?1",
+            params![old.id],
+        )
+        .unwrap();
+        soft_delete_note(&conn, &recent.id).unwrap();
+        crate::alias::set_alias(&conn, "gone", &old.id).unwrap();
+
+        let result = compact_db(&conn, 5000).unwrap();
+
+        assert_eq!(result.purged, 1);
+        assert!(result.reclaimed_bytes > 0);
+        assert!(get_note_by_id(&conn, &old.id).unwrap().is_none());
+        assert!(get_note_by_id(&conn, &recent.id).unwrap().is_some());
+        assert!(get_note_by_id(&conn, &kept.id).unwrap().is_some());
+        assert!(crate::alias::resolve_alias(&conn, "gone")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_restore_and_purge_deleted_notes() {
+        let conn = open_db_in_memory().unwrap();
+        let restored = create_note(&conn, "restored", vec![], None).unwrap();
+        let purged = create_note(&conn, "purged", vec![], None).unwrap();
+
+        soft_delete_note(&conn, &restored.id).unwrap();
+        assert!(restore_note(&conn, &restored.id).unwrap());
+        assert!(!restore_note(&conn, &restored.id).unwrap());
+        let note = get_note_by_id(&conn, &restored.id).unwrap().unwrap();
+        assert!(note.deleted_at.is_none());
+        assert!(note.updated_at >= restored.updated_at);
+
+        conn.execute(
+            "UPDATE notes SET deleted_at = 1000, updated_at = 1000 WHERE id = ?1",
+            params![purged.id],
+        )
+        .unwrap();
+        assert_eq!(purge_deleted_before(&conn, 500).unwrap(), 0);
+        assert_eq!(purge_deleted_before(&conn, 5000).unwrap(), 1);
+        assert!(get_note_by_id(&conn, &purged.id).unwrap().is_none());
+        assert!(get_note_by_id(&conn, &restored.id).unwrap().is_some());
+    }
+
     #[test]
     fn test_create_and_get_note() {
-        let dir = TempDir::new().unwrap();
-        let db_path = dir.path().join("test.db");
-        let conn = open_db(&db_path).unwrap();
+        let conn = open_db_in_memory().unwrap();
 
         let note = create_note(&conn, "test content", vec!["tag1".to_string()], None).unwrap();
 
@@ -302,11 +994,39 @@ mod tests {
         assert_eq!(retrieved.content, "test content");
     }
 
+    #[test]
+    fn test_subject_tz_follows_subject_date() {
+        let conn = open_db_in_memory().unwrap();
+        let dated = || Some("2024-03-17".to_string());
+
+        let undated = create_note(&conn, "undated", vec![], None).unwrap();
+        assert_eq!(undated.subject_tz, None);
+
+        let note = create_note(&conn, "dated", vec![], dated()).unwrap();
+        assert_eq!(note.subject_tz, Some(local_offset()));
+        let limits = NoteLimits::default();
+        let tokyo =
+            create_note_with_limits(&conn, "dated", vec![], dated(), "+09:00", &limits).unwrap();
+        assert_eq!(tokyo.subject_tz.as_deref(), Some("+09:00"));
+
+        // Offset recorded on another device survives edits that keep the date
+        conn.execute(
+            "UPDATE notes SET subject_tz = '+09:00' WHERE id = ?1",
+            params![note.id],
+        )
+        .unwrap();
+        update_note(&conn, &note.id, "edited", vec![], dated()).unwrap();
+        let kept = get_note_by_id(&conn, &note.id).unwrap().unwrap();
+        assert_eq!(kept.subject_tz.as_deref(), Some("+09:00"));
+
+        update_note(&conn, &note.id, "edited", vec![], None).unwrap();
+        let cleared = get_note_by_id(&conn, &note.id).unwrap().unwrap();
+        assert_eq!(cleared.subject_tz, None);
+    }
+
     #[test]
     fn test_soft_delete() {
-        let dir = TempDir::new().unwrap();
-        let db_path = dir.path().join("test.db");
-        let conn = open_db(&db_path).unwrap();
+        let conn = open_db_in_memory().unwrap();
 
         let note = create_note(&conn, "test", vec![], None).unwrap();
 
@@ -319,9 +1039,7 @@ mod tests {
 
     #[test]
     fn test_search_notes() {
-        let dir = TempDir::new().unwrap();
-        let db_path = dir.path().join("test.db");
-        let conn = open_db(&db_path).unwrap();
+        let conn = open_db_in_memory().unwrap();
 
         create_note(&conn, "first note", vec!["work".to_string()], None).unwrap();
         create_note(&conn, "second note", vec!["personal".to_string()], None).unwrap();
@@ -335,4 +1053,493 @@ mod tests {
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].content, "first note");
     }
+
+    #[test]
+    fn test_search_notes_paging() {
+        let conn = open_db_in_memory().unwrap();
+        for day in 1..=5 {
+            let date = format!("2024-01-0{}", day);
+            create_note(&conn, &format!("day {}", day), vec![], Some(date)).unwrap();
+        }
+
+        let page = |limit: Option<usize>, offset: Option<usize>| -> Vec<String> {
+            let query = SearchQuery {
+                limit,
+                offset,
+                ..Default::default()
+            };
+            search_notes(&conn, &query)
+                .unwrap()
+                .into_iter()
+                .map(|n| n.content)
+                .collect()
+        };
+
+        assert_eq!(page(Some(2), None), vec!["day 5", "day 4"]);
+        assert_eq!(page(Some(2), Some(2)), vec!["day 3", "day 2"]);
+        assert_eq!(page(Some(2), Some(4)), vec!["day 1"]);
+        // An offset alone skips without limiting
+        assert_eq!(page(None, Some(3)), vec!["day 2", "day 1"]);
+        assert!(page(Some(2), Some(5)).is_empty());
+    }
+
+    #[test]
+    fn test_search_notes_by_tag() {
+        let conn = open_db_in_memory().unwrap();
+        let tagged = |tags: &[&str]| tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        create_note(&conn, "standup", tagged(&["work"]), None).unwrap();
+        create_note(&conn, "pottery", tagged(&["workshop"]), None).unwrap();
+        create_note(&conn, "kickoff", tagged(&["project/alpha", "Work"]), None).unwrap();
+        let retagged = create_note(&conn, "draft", tagged(&["work"]), None).unwrap();
+        update_note(&conn, &retagged.id, "draft", tagged(&["home"]), None).unwrap();
+
+        let matched = |tags: &[&str], exclude: &[&str]| -> Vec<String> {
+            let query = SearchQuery {
+                tags: tagged(tags),
+                exclude_tags: tagged(exclude),
+                ..Default::default()
+            };
+            let mut contents: Vec<String> = search_notes(&conn, &query)
+                .unwrap()
+                .into_iter()
+                .map(|n| n.content)
+                .collect();
+            contents.sort();
+
+            // Matching in memory agrees with the database
+            let mut in_memory: Vec<String> = search_notes(&conn, &SearchQuery::default())
+                .unwrap()
+                .into_iter()
+                .filter(|n| query.matches_tags(&n.tags))
+                .map(|n| n.content)
+                .collect();
+            in_memory.sort();
+            assert_eq!(in_memory, contents);
+            contents
+        };
+
+        // Whole tags only, ignoring case
+        assert_eq!(matched(&["work"], &[]), vec!["kickoff", "standup"]);
+        assert_eq!(matched(&["WORK"], &[]), vec!["kickoff", "standup"]);
+        // Nested tags match their parent
+        assert_eq!(matched(&["project"], &[]), vec!["kickoff"]);
+        assert!(matched(&["proj"], &[]).is_empty());
+        assert_eq!(matched(&[], &["work"]), vec!["draft", "pottery"]);
+    }
+
+    #[test]
+    fn test_search_notes_full_text_match() {
+        let conn = open_db_in_memory().unwrap();
+        let matched = |match_query: &str| -> Vec<String> {
+            let query = SearchQuery {
+                match_query: Some(match_query.to_string()),
+                ..Default::default()
+            };
+            search_notes(&conn, &query)
+                .unwrap()
+                .into_iter()
+                .map(|n| n.id)
+                .collect()
+        };
+
+        let both = create_note(&conn, "meeting about the budget", vec![], None).unwrap();
+        let budget = create_note(&conn, "budget budget budget", vec![], None).unwrap();
+        let meeting = create_note(&conn, "meeting notes", vec!["work".to_string()], None).unwrap();
+        let large = create_note(&conn, &"meeting budget ".repeat(1000), vec![], None).unwrap();
+
+        let mut found = matched("meeting AND budget");
+        found.sort();
+        let mut expected = vec![both.id.clone(), large.id.clone()];
+        expected.sort();
+        assert_eq!(found, expected);
+
+        // Ranked by relevance rather than by date
+        let found = matched("budget NOT meeting");
+        assert_eq!(found, vec![budget.id.clone()]);
+        let found = matched("budget");
+        let position = |id: &str| found.iter().position(|f| f == id).unwrap();
+        assert!(position(&budget.id) < position(&both.id));
+
+        // Tags are indexed, and edits and deletions are followed
+        assert_eq!(matched("tags:work"), vec![meeting.id.clone()]);
+        update_note(&conn, &meeting.id, "budget review", vec![], None).unwrap();
+        assert!(matched("tags:work").is_empty());
+        assert!(matched("budget").contains(&meeting.id));
+        soft_delete_note(&conn, &budget.id).unwrap();
+        assert!(!matched("budget").contains(&budget.id));
+
+        // Still in step after compaction vacuums the table
+        compact_db(&conn, i64::MAX).unwrap();
+        assert_eq!(matched("budget NOT meeting"), vec![meeting.id]);
+    }
+
+    #[test]
+    fn test_pinned_notes_sort_first() {
+        let conn = open_db_in_memory().unwrap();
+        let old = create_note(&conn, "old", vec![], Some("2024-01-01".to_string())).unwrap();
+        let new = create_note(&conn, "new", vec![], Some("2024-02-01".to_string())).unwrap();
+        let ids = |query: &SearchQuery| -> Vec<String> {
+            search_notes(&conn, query)
+                .unwrap()
+                .into_iter()
+                .map(|n| n.id)
+                .collect()
+        };
+
+        assert!(pin_note(&conn, &old.id).unwrap());
+        assert!(!pin_note(&conn, &old.id).unwrap());
+        let pinned = get_note_by_id(&conn, &old.id).unwrap().unwrap();
+        assert!(pinned.pinned);
+        assert!(pinned.updated_at >= old.updated_at);
+
+        assert_eq!(
+            ids(&SearchQuery::default()),
+            vec![old.id.clone(), new.id.clone()]
+        );
+        let chronological = SearchQuery {
+            chronological: true,
+            ..Default::default()
+        };
+        assert_eq!(ids(&chronological), vec![new.id.clone(), old.id.clone()]);
+
+        assert!(unpin_note(&conn, &old.id).unwrap());
+        assert!(!unpin_note(&conn, &old.id).unwrap());
+        assert_eq!(ids(&SearchQuery::default()), vec![new.id, old.id]);
+    }
+
+    #[test]
+    fn test_archived_notes_hidden_from_search() {
+        let conn = open_db_in_memory().unwrap();
+        let kept = create_note(&conn, "kept", vec![], None).unwrap();
+        let old = create_note(&conn, "old", vec![], None).unwrap();
+        let ids = |query: &SearchQuery| -> Vec<String> {
+            search_notes(&conn, query)
+                .unwrap()
+                .into_iter()
+                .map(|n| n.id)
+                .collect()
+        };
+        let with_archived = SearchQuery {
+            include_archived: true,
+            ..Default::default()
+        };
+
+        assert!(archive_note(&conn, &old.id).unwrap());
+        assert!(!archive_note(&conn, &old.id).unwrap());
+        let archived = get_note_by_id(&conn, &old.id).unwrap().unwrap();
+        assert!(archived.archived_at.is_some());
+        assert!(archived.deleted_at.is_none());
+        assert!(archived.updated_at >= old.updated_at);
+
+        assert_eq!(ids(&SearchQuery::default()), vec![kept.id.clone()]);
+        assert_eq!(ids(&with_archived).len(), 2);
+
+        assert!(unarchive_note(&conn, &old.id).unwrap());
+        assert!(!unarchive_note(&conn, &old.id).unwrap());
+        assert_eq!(ids(&SearchQuery::default()).len(), 2);
+    }
+
+    #[test]
+    fn test_note_status() {
+        let conn = open_db_in_memory().unwrap();
+        let task = create_note(&conn, "write report", vec![], None).unwrap();
+        create_note(&conn, "plain note", vec![], None).unwrap();
+
+        assert!(set_status(&conn, &task.id, Some("doing")).unwrap());
+        assert!(!set_status(&conn, &task.id, Some("doing")).unwrap());
+        let doing = SearchQuery {
+            status: Some("doing".to_string()),
+            ..Default::default()
+        };
+        let found = search_notes(&conn, &doing).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].status.as_deref(), Some("doing"));
+
+        assert!(set_status(&conn, &task.id, None).unwrap());
+        assert!(search_notes(&conn, &doing).unwrap().is_empty());
+        assert_eq!(
+            get_note_by_id(&conn, &task.id).unwrap().unwrap().status,
+            None
+        );
+    }
+
+    #[test]
+    fn test_search_notes_by_meta() {
+        let conn = open_db_in_memory().unwrap();
+        let captured = create_note(&conn, "fix the parser", vec![], None).unwrap();
+        create_note(&conn, "no context", vec![], None).unwrap();
+        let meta: BTreeMap<String, String> = [("repo", "jot"), ("branch", "main")]
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .into();
+        set_meta(&conn, &captured.id, &meta).unwrap();
+
+        let query = |pairs: &[(&str, &str)]| SearchQuery {
+            meta: pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+        let found = search_notes(&conn, &query(&[("repo", "jot")])).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].meta, meta);
+        assert_eq!(
+            search_notes(&conn, &query(&[("repo", "jot"), ("branch", "main")]))
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(search_notes(&conn, &query(&[("repo", "other")]))
+            .unwrap()
+            .is_empty());
+        assert!(search_notes(&conn, &query(&[("branch", "jot")]))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_count_notes_per_day() {
+        let conn = open_db_in_memory().unwrap();
+        for date in ["2024-03-01", "2024-03-01", "2024-03-15", "2024-04-01"] {
+            create_note(&conn, date, vec![], Some(date.to_string())).unwrap();
+        }
+        let deleted = create_note(&conn, "gone", vec![], Some("2024-03-15".to_string())).unwrap();
+        soft_delete_note(&conn, &deleted.id).unwrap();
+
+        let counts = count_notes_per_day(
+            &conn,
+            NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 3, 31).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            counts,
+            vec![("2024-03-01".to_string(), 2), ("2024-03-15".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_search_notes_date_ranges() {
+        let conn = open_db_in_memory().unwrap();
+
+        create_note(&conn, "march", vec![], Some("2024-03-16".to_string())).unwrap();
+        create_note(&conn, "april", vec![], Some("2024-04-01".to_string())).unwrap();
+
+        let query = SearchQuery {
+            date_from: chrono::NaiveDate::from_ymd_opt(2024, 3, 1),
+            date_to: chrono::NaiveDate::from_ymd_opt(2024, 3, 31),
+            ..Default::default()
+        };
+        let results = search_notes(&conn, &query).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content, "march");
+
+        let query = SearchQuery {
+            created_to: Some(0),
+            ..Default::default()
+        };
+        assert!(search_notes(&conn, &query).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_with_transaction_rolls_back_on_error() {
+        let conn = open_db_in_memory().unwrap();
+
+        let result: Result<()> = with_transaction(&conn, |tx| {
+            create_note(tx, "doomed", vec![], None)?;
+            Err(rusqlite::Error::InvalidQuery)
+        });
+        assert!(result.is_err());
+        assert!(search_notes(&conn, &SearchQuery::default())
+            .unwrap()
+            .is_empty());
+
+        with_transaction(&conn, |tx| {
+            create_note(tx, "first", vec![], None)?;
+            create_note(tx, "second", vec![], None)
+        })
+        .unwrap();
+        assert_eq!(
+            search_notes(&conn, &SearchQuery::default()).unwrap().len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_open_db_configures_pragmas() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("test.db");
+        let conn = open_db(&db_path).unwrap();
+
+        let journal_mode: String = conn
+            .pragma_query_value(None, "journal_mode", |row| row.get(0))
+            .unwrap();
+        let foreign_keys: i32 = conn
+            .pragma_query_value(None, "foreign_keys", |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(journal_mode, "wal");
+        assert_eq!(foreign_keys, 1);
+    }
+
+    #[test]
+    fn test_open_db_backs_up_before_migrating() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("notes.db");
+
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(schema::SCHEMA_V1).unwrap();
+        drop(conn);
+
+        open_db(&db_path).unwrap();
+
+        let backup_path = dir
+            .path()
+            .join(format!("notes.db.pre-v{}.bak", schema::LATEST_VERSION));
+        assert_eq!(
+            migration_backup_path(&db_path, schema::LATEST_VERSION),
+            backup_path
+        );
+
+        let backup = Connection::open(&backup_path).unwrap();
+        assert_eq!(schema::get_schema_version(&backup).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_search_index_follows_writes_from_other_tools() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("notes.db");
+        let conn = open_db(&db_path).unwrap();
+        let large = create_note(&conn, &"compressed body ".repeat(1000), vec![], None).unwrap();
+        drop(conn);
+
+        // A stock connection, without the functions jot registers
+        let other = Connection::open(&db_path).unwrap();
+        other
+            .execute_batch(
+                "INSERT INTO notes (id, content, tags, subject_date, created_at, updated_at)
+                     VALUES ('01OTHERTOOL', 'written elsewhere', '[\"imported\"]', '2024-01-01', 1, 1);
+                 UPDATE notes SET content = 'edited elsewhere' WHERE id = '01OTHERTOOL';",
+            )
+            .unwrap();
+        drop(other);
+
+        let conn = open_db(&db_path).unwrap();
+        let matched = |match_query: &str| -> Vec<String> {
+            let query = SearchQuery {
+                match_query: Some(match_query.to_string()),
+                ..Default::default()
+            };
+            search_notes(&conn, &query)
+                .unwrap()
+                .into_iter()
+                .map(|n| n.id)
+                .collect()
+        };
+        assert_eq!(matched("edited"), vec!["01OTHERTOOL"]);
+        assert!(matched("written").is_empty());
+        assert_eq!(matched("compressed"), vec![large.id]);
+        assert!(check_search_index(&conn).unwrap().is_consistent());
+
+        // The index keeps no copy of the bodies
+        let stored: i64 = conn
+            .query_row(
+                "SELECT count(*) FROM sqlite_master WHERE name = 'notes_fts_content'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(stored, 0);
+    }
+
+    #[test]
+    fn test_open_db_fresh_database_has_no_backup() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("notes.db");
+
+        open_db(&db_path).unwrap();
+
+        assert!(!migration_backup_path(&db_path, 2).exists());
+    }
+
+    #[test]
+    fn test_create_note_enforces_limits() {
+        let conn = open_db_in_memory().unwrap();
+        let limits = NoteLimits {
+            max_content_bytes: 4,
+            max_tags: 10,
+        };
+
+        let err = create_note_with_limits(&conn, "too long", vec![], None, "+00:00", &limits)
+            .unwrap_err();
+        assert!(matches!(
+            crate::validation::as_validation_error(&err),
+            Some(crate::validation::NoteValidationError::ContentTooLong { size: 8, max: 4 })
+        ));
+        assert!(search_notes(&conn, &SearchQuery::default())
+            .unwrap()
+            .is_empty());
+    }
+
+    /// `EXPLAIN QUERY PLAN` details for the search `query`
+    fn search_plan(conn: &Connection, query: &SearchQuery) -> String {
+        let (sql, params) = search_sql(query);
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|b| b.as_ref()).collect();
+        let mut stmt = conn
+            .prepare(&format!("EXPLAIN QUERY PLAN {}", sql))
+            .unwrap();
+        let details = stmt
+            .query_map(params_refs.as_slice(), |row| row.get::<_, String>(3))
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        details.join("\n")
+    }
+
+    #[test]
+    fn test_search_query_plans_use_indexes() {
+        let conn = open_db_in_memory().unwrap();
+
+        let listing = search_plan(
+            &conn,
+            &SearchQuery {
+                limit: Some(10),
+                ..Default::default()
+            },
+        );
+        assert!(listing.contains("idx_live_pinned_sort"), "{}", listing);
+        assert!(!listing.contains("TEMP B-TREE"), "{}", listing);
+
+        let latest = search_plan(
+            &conn,
+            &SearchQuery {
+                chronological: true,
+                limit: Some(1),
+                offset: None,
+                ..Default::default()
+            },
+        );
+        assert!(latest.contains("idx_live_sort"), "{}", latest);
+        assert!(!latest.contains("TEMP B-TREE"), "{}", latest);
+
+        let range = search_plan(
+            &conn,
+            &SearchQuery {
+                date_from: chrono::NaiveDate::from_ymd_opt(2024, 1, 1),
+                date_to: chrono::NaiveDate::from_ymd_opt(2024, 1, 31),
+                ..Default::default()
+            },
+        );
+        assert!(range.contains("idx_live_subject_date"), "{}", range);
+
+        let filtered = search_plan(
+            &conn,
+            &SearchQuery {
+                text: Some("meeting".to_string()),
+                tags: vec!["work".to_string()],
+                ..Default::default()
+            },
+        );
+        assert!(!filtered.contains("SCAN notes"), "{}", filtered);
+    }
 }
@@ -0,0 +1,184 @@
+use crate::db::get_note_by_id;
+use crate::models::Note;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+/// SHA-256 of the note content as lowercase hex, stored in `notes.content_hash`
+pub fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Fill in `content_hash` for rows written before the column existed
+///
+/// Returns the number of rows updated.
+pub fn backfill_content_hashes(conn: &Connection) -> Result<usize> {
+    let mut stmt = conn.prepare("SELECT id, content FROM notes WHERE content_hash IS NULL")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    for (id, content) in &rows {
+        conn.execute(
+            "UPDATE notes SET content_hash = ?1 WHERE id = ?2",
+            params![content_hash(content), id],
+        )?;
+    }
+
+    Ok(rows.len())
+}
+
+/// Find a live note whose content is byte-for-byte identical to `content`
+pub fn find_note_by_content(conn: &Connection, content: &str) -> Result<Option<Note>> {
+    let id: Option<String> = conn
+        .query_row(
+            "SELECT id FROM notes WHERE content_hash = ?1 AND deleted_at IS NULL ORDER BY created_at, id LIMIT 1",
+            params![content_hash(content)],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match id {
+        Some(id) => get_note_by_id(conn, &id),
+        None => Ok(None),
+    }
+}
+
+/// Whether a deleted note has content identical to `content`
+pub(crate) fn deleted_note_exists(conn: &Connection, content: &str) -> Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM notes WHERE content_hash = ?1 AND deleted_at IS NOT NULL)",
+        params![content_hash(content)],
+        |row| row.get(0),
+    )
+}
+
+/// Group live notes that share identical content
+///
+/// Each group holds at least two notes, oldest first, so the first entry is
+/// the natural one to keep.
+pub fn find_duplicate_notes(conn: &Connection) -> Result<Vec<Vec<Note>>> {
+    let mut stmt = conn.prepare(
+        "SELECT content_hash, id FROM notes
+         WHERE deleted_at IS NULL AND content_hash IN (
+             SELECT content_hash FROM notes
+             WHERE deleted_at IS NULL
+             GROUP BY content_hash
+             HAVING COUNT(*) > 1
+         )
+         ORDER BY content_hash, created_at, id",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut groups: Vec<Vec<Note>> = Vec::new();
+    let mut current_hash: Option<String> = None;
+    for (hash, id) in rows {
+        let Some(note) = get_note_by_id(conn, &id)? else {
+            continue;
+        };
+        if current_hash.as_deref() == Some(hash.as_str()) {
+            if let Some(group) = groups.last_mut() {
+                group.push(note);
+            }
+        } else {
+            groups.push(vec![note]);
+            current_hash = Some(hash);
+        }
+    }
+
+    Ok(groups)
+}
+
+/// How alike two texts are, from 0.0 (nothing in common) to 1.0 (the same
+/// apart from case and spacing)
+///
+/// Jaccard similarity of the texts' three-character shingles, so a small
+/// edit anywhere only lowers it a little.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a = shingles(a);
+    let b = shingles(b);
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let common = a.intersection(&b).count();
+    common as f64 / (a.len() + b.len() - common) as f64
+}
+
+fn shingles(text: &str) -> HashSet<Vec<char>> {
+    let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+    let chars: Vec<char> = words.join(" ").chars().collect();
+    match chars.len() {
+        0 => return HashSet::new(),
+        1 | 2 => return HashSet::from([chars]),
+        _ => {}
+    }
+    chars.windows(3).map(<[char]>::to_vec).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::builder::NoteBuilder;
+    use crate::db::{create_note, open_db_in_memory, soft_delete_note};
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        assert_eq!(
+            content_hash("hello"),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert_ne!(content_hash("hello"), content_hash("hello "));
+    }
+
+    #[test]
+    fn test_find_duplicate_notes() {
+        let conn = open_db_in_memory().unwrap();
+        let first = NoteBuilder::new("same")
+            .created_at(1_000)
+            .insert(&conn)
+            .unwrap();
+        let second = NoteBuilder::new("same")
+            .created_at(2_000)
+            .insert(&conn)
+            .unwrap();
+        let deleted = NoteBuilder::new("same")
+            .created_at(3_000)
+            .insert(&conn)
+            .unwrap();
+        soft_delete_note(&conn, &deleted.id).unwrap();
+        create_note(&conn, "unique", vec![], None).unwrap();
+
+        let groups = find_duplicate_notes(&conn).unwrap();
+        assert_eq!(groups.len(), 1);
+        let ids: Vec<_> = groups[0].iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, vec![first.id.as_str(), second.id.as_str()]);
+
+        assert_eq!(
+            find_note_by_content(&conn, "same").unwrap().unwrap().id,
+            first.id
+        );
+        assert!(find_note_by_content(&conn, "missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("Buy milk", "buy  milk\n"), 1.0);
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("abc", "xyz"), 0.0);
+
+        let long = "Deploy the billing service after the database migration has finished";
+        assert!(similarity(long, &format!("{}.", long)) > 0.95);
+        assert!(similarity(long, "Deploy the billing service tomorrow") < 0.5);
+    }
+}
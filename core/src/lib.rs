@@ -1,14 +1,48 @@
 #![deny(clippy::expect_used, clippy::unwrap_used, clippy::panic)]
 
+pub mod alias;
+pub mod attachment;
+pub mod builder;
+pub mod cache;
+pub mod compress;
 pub mod db;
+pub mod dedupe;
 pub mod models;
+pub mod observer;
+pub mod recurrence;
 pub mod schema;
+pub mod seed;
 pub mod sync;
+pub mod sync_queue;
+pub mod time_entry;
+pub mod validation;
 
 // Re-export commonly used types
+pub use alias::NoteAlias;
+pub use attachment::{
+    attach_file, attachment_data, detach_file, list_attachments, restore_attachment, Attachment,
+};
+pub use builder::{NoteBuilder, NoteBuilderError};
+pub use cache::SearchCache;
+pub use compress::{recompress, RecompressResult};
 pub use db::{
-    create_note, get_note_by_id, get_notes_since, get_sync_state, open_db, search_notes,
-    set_sync_state, soft_delete_note, update_note, upsert_note,
+    archive_note, backup_db, check_search_index, compact_db, count_notes_per_day, create_note,
+    create_note_with_limits, get_note_by_id, get_notes_since, get_sync_state, insert_note,
+    local_offset, open_db, open_db_in_memory, open_db_unmigrated, pin_note, purge_deleted_before,
+    reindex_db, restore_note, search_notes, set_meta, set_status, set_sync_state, soft_delete_note,
+    unarchive_note, unpin_note, update_note, update_note_with_limits, upsert_note,
+    with_transaction,
+};
+pub use db::{CompactResult, ReindexResult, SearchIndexCheck};
+pub use dedupe::{content_hash, find_duplicate_notes, find_note_by_content, similarity};
+pub use models::{
+    Conflict, Note, SearchQuery, SyncReport, SyncRequest, SyncResponse, SyncSide, SEALED_PREFIX,
+};
+pub use observer::{NoteObserver, ObserverRegistry};
+pub use recurrence::Recurrence;
+pub use sync::{
+    merge_notes, merge_notes_with, process_sync_request, process_sync_request_with,
+    SYNC_PROTOCOL_VERSION,
 };
-pub use models::{Note, SearchQuery, SyncRequest, SyncResponse};
-pub use sync::{merge_notes, process_sync_request};
+pub use time_entry::TimeEntry;
+pub use validation::{NoteLimits, NoteValidationError};
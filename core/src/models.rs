@@ -1,4 +1,7 @@
+use crate::attachment::Attachment;
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// A note with all metadata
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -11,33 +14,106 @@ pub struct Note {
     pub tags: Vec<String>,
     /// Optional subject date - what date this note is about (YYYY-MM-DD)
     pub subject_date: Option<String>,
+    /// UTC offset (`+09:00`) of the device that set `subject_date`
+    ///
+    /// The date itself is a calendar date in that zone and is compared as-is
+    /// by date filters on every device.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject_tz: Option<String>,
     /// Unix timestamp in milliseconds
     pub created_at: i64,
     /// Unix timestamp in milliseconds
     pub updated_at: i64,
     /// Unix timestamp in milliseconds (None = active, Some = deleted)
     pub deleted_at: Option<i64>,
+    /// Listed before unpinned notes by [`crate::db::search_notes`]
+    #[serde(default)]
+    pub pinned: bool,
+    /// Workflow state such as `todo`, `doing` or `done`; clients decide the set
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Where the note was captured (`cwd`, `repo`, `branch`, `host`), when
+    /// the client records it
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub meta: BTreeMap<String, String>,
+    /// Files attached with [`crate::attachment::attach_file`] (metadata only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+    /// Unix timestamp in milliseconds the note was archived at (None = not archived)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<i64>,
+}
+
+/// Start of content and tags a client encrypted (profile `encrypt`); the
+/// server stores such notes without being able to read them
+pub const SEALED_PREFIX: &str = "jotenc1:";
+
+impl Note {
+    /// Whether the client encrypted this note's content or tags
+    pub fn is_sealed(&self) -> bool {
+        self.content.starts_with(SEALED_PREFIX)
+            || self.tags.iter().any(|tag| tag.starts_with(SEALED_PREFIX))
+    }
 }
 
 /// Search query parameters
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct SearchQuery {
-    /// Full-text search term
+    /// Substring the body must contain
     pub text: Option<String>,
+    /// FTS5 query (e.g. `meeting AND budget`) matched against bodies and
+    /// tags; matching notes are ordered by relevance first
+    pub match_query: Option<String>,
     /// Filter by tags (must have all specified tags)
     pub tags: Vec<String>,
+    /// Exclude notes having any of these tags
+    pub exclude_tags: Vec<String>,
+    /// Filter by exact status
+    pub status: Option<String>,
+    /// Filter by metadata entries (must match all `(key, value)` pairs)
+    pub meta: Vec<(String, String)>,
     /// Filter by subject_date range (inclusive start)
-    pub date_from: Option<String>,
+    pub date_from: Option<NaiveDate>,
     /// Filter by subject_date range (inclusive end)
-    pub date_to: Option<String>,
-    /// Filter by created_at range (inclusive start, ISO date string)
-    pub created_from: Option<String>,
-    /// Filter by created_at range (inclusive end, ISO date string)
-    pub created_to: Option<String>,
+    pub date_to: Option<NaiveDate>,
+    /// Filter by created_at range (inclusive start, Unix timestamp in milliseconds)
+    pub created_from: Option<i64>,
+    /// Filter by created_at range (inclusive end, Unix timestamp in milliseconds)
+    pub created_to: Option<i64>,
     /// Include soft-deleted notes
     pub include_deleted: bool,
+    /// Include archived notes
+    pub include_archived: bool,
+    /// Order by date only, without listing pinned notes first
+    pub chronological: bool,
     /// Limit number of results
     pub limit: Option<usize>,
+    /// Skip this many results first, for paging through them with `limit`
+    pub offset: Option<usize>,
+}
+
+impl SearchQuery {
+    /// Whether a note with `tags` passes the tag filters, matched as
+    /// [`search_notes`](crate::search_notes) does: a `tags` entry matches the
+    /// tag itself or one nested under it, an `exclude_tags` entry only the tag
+    /// itself, both ignoring ASCII case. For notes whose tags the database
+    /// can't see, e.g. encrypted ones.
+    pub fn matches_tags(&self, tags: &[String]) -> bool {
+        self.tags
+            .iter()
+            .all(|filter| tags.iter().any(|tag| is_tag_or_child(tag, filter)))
+            && !self
+                .exclude_tags
+                .iter()
+                .any(|filter| tags.iter().any(|tag| tag.eq_ignore_ascii_case(filter)))
+    }
+}
+
+/// Whether `tag` is `filter` or nested under it (`project/alpha` under `project`)
+fn is_tag_or_child(tag: &str, filter: &str) -> bool {
+    tag.get(..filter.len())
+        .is_some_and(|head| head.eq_ignore_ascii_case(filter))
+        && tag[filter.len()..].chars().next().is_none_or(|c| c == '/')
 }
 
 /// Sync request from client to server
@@ -54,12 +130,47 @@ pub struct SyncRequest {
 pub struct SyncResponse {
     /// Notes from server that client needs
     pub notes: Vec<Note>,
+    /// Notes edited on both sides since the last sync, where one edit was discarded
+    #[serde(default)]
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Which side of a sync a note version came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncSide {
+    Client,
+    Server,
 }
 
-/// Conflict information (for future use)
+/// A note changed on both sides since the client's last sync; last write wins
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Conflict {
     pub note_id: String,
-    pub client_version: Note,
-    pub server_version: Note,
+    /// Side whose version was kept
+    pub winner: SyncSide,
+    /// The discarded version, so the user can recover it
+    pub losing_version: Note,
+}
+
+/// Outcome of one client sync, as printed by `jot sync --output json`
+///
+/// Kept flat and stable so scripts and status bars can read it without
+/// scraping the human-readable output.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncReport {
+    /// Local changes sent to the server
+    pub pushed: usize,
+    /// Server changes applied locally
+    pub pulled: usize,
+    /// Notes where last-write-wins discarded an edit
+    pub conflicts: usize,
+    pub duration_ms: u64,
+    /// Human-readable failures; empty when the sync succeeded
+    #[serde(default)]
+    pub errors: Vec<String>,
+    /// Human-readable warnings about a sync that succeeded, such as the
+    /// account nearing the server's storage quota
+    #[serde(default)]
+    pub warnings: Vec<String>,
 }
@@ -0,0 +1,70 @@
+use crate::models::Note;
+use std::sync::Arc;
+
+/// Callbacks for note lifecycle events
+///
+/// All methods default to no-ops so implementors only override what they need.
+/// Observers are called after the change has been written.
+pub trait NoteObserver: Send + Sync {
+    fn on_create(&self, _note: &Note) {}
+    fn on_update(&self, _note: &Note) {}
+    /// Called with the soft-deleted note (`deleted_at` is set)
+    fn on_delete(&self, _note: &Note) {}
+}
+
+/// A set of registered observers that are notified in registration order
+#[derive(Clone, Default)]
+pub struct ObserverRegistry {
+    observers: Vec<Arc<dyn NoteObserver>>,
+}
+
+impl ObserverRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, observer: Arc<dyn NoteObserver>) {
+        self.observers.push(observer);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    pub fn notify_create(&self, note: &Note) {
+        for observer in &self.observers {
+            observer.on_create(note);
+        }
+    }
+
+    pub fn notify_update(&self, note: &Note) {
+        for observer in &self.observers {
+            observer.on_update(note);
+        }
+    }
+
+    pub fn notify_delete(&self, note: &Note) {
+        for observer in &self.observers {
+            observer.on_delete(note);
+        }
+    }
+
+    /// Dispatch a change that replaced `previous` (if any) with `note`
+    pub fn notify_change(&self, previous: Option<&Note>, note: &Note) {
+        match previous {
+            None => self.notify_create(note),
+            Some(prev) if prev.deleted_at.is_none() && note.deleted_at.is_some() => {
+                self.notify_delete(note)
+            }
+            Some(_) => self.notify_update(note),
+        }
+    }
+}
+
+impl std::fmt::Debug for ObserverRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ObserverRegistry")
+            .field("observers", &self.observers.len())
+            .finish()
+    }
+}
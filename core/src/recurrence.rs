@@ -0,0 +1,153 @@
+//! Rules for notes created on a schedule (`jot recur`)
+//!
+//! A rule remembers the next occurrence that hasn't become a note yet, so
+//! running the rules twice never creates the same occurrence twice. Rules are
+//! local to the device and don't sync; the notes they create do.
+
+use rusqlite::{params, Connection, Result};
+use serde::{Deserialize, Serialize};
+
+/// A note template and how often to create it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recurrence {
+    pub id: i64,
+    pub content: String,
+    pub tags: Vec<String>,
+    /// Interval as typed by the user, normalized (e.g. `monday`, `day`, `month`)
+    pub every: String,
+    /// First occurrence (YYYY-MM-DD); monthly rules keep its day of the month
+    pub start_date: String,
+    /// Next occurrence still to be created (YYYY-MM-DD)
+    pub next_date: String,
+    /// Milliseconds since the Unix epoch
+    pub created_at: i64,
+}
+
+/// Store a rule whose first occurrence is `start_date`
+pub fn add_recurrence(
+    conn: &Connection,
+    content: &str,
+    tags: &[String],
+    every: &str,
+    start_date: &str,
+) -> Result<Recurrence> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let tags_json = serde_json::to_string(tags)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    conn.execute(
+        "INSERT INTO recurrences (content, tags, every, start_date, next_date, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?4, ?5)",
+        params![content, tags_json, every, start_date, now],
+    )?;
+    Ok(Recurrence {
+        id: conn.last_insert_rowid(),
+        content: content.to_string(),
+        tags: tags.to_vec(),
+        every: every.to_string(),
+        start_date: start_date.to_string(),
+        next_date: start_date.to_string(),
+        created_at: now,
+    })
+}
+
+/// All rules, oldest first
+pub fn list_recurrences(conn: &Connection) -> Result<Vec<Recurrence>> {
+    query_recurrences(
+        conn,
+        "SELECT id, content, tags, every, start_date, next_date, created_at FROM recurrences
+         ORDER BY id",
+        [],
+    )
+}
+
+/// Rules with an occurrence on or before `date` (YYYY-MM-DD) still to create
+pub fn due_recurrences(conn: &Connection, date: &str) -> Result<Vec<Recurrence>> {
+    query_recurrences(
+        conn,
+        "SELECT id, content, tags, every, start_date, next_date, created_at FROM recurrences
+         WHERE next_date <= ?1 ORDER BY id",
+        params![date],
+    )
+}
+
+/// Move a rule from the occurrence on `current` to `next_date`, returning
+/// false when it was no longer at `current` (another run moved it on)
+pub fn set_next_date(conn: &Connection, id: i64, current: &str, next_date: &str) -> Result<bool> {
+    let moved = conn.execute(
+        "UPDATE recurrences SET next_date = ?1 WHERE id = ?2 AND next_date = ?3",
+        params![next_date, id, current],
+    )?;
+    Ok(moved > 0)
+}
+
+/// Remove a rule, returning whether it existed
+///
+/// Notes it already created stay.
+pub fn remove_recurrence(conn: &Connection, id: i64) -> Result<bool> {
+    let removed = conn.execute("DELETE FROM recurrences WHERE id = ?1", params![id])?;
+    Ok(removed > 0)
+}
+
+fn query_recurrences<P: rusqlite::Params>(
+    conn: &Connection,
+    sql: &str,
+    params: P,
+) -> Result<Vec<Recurrence>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rules = stmt
+        .query_map(params, |row| {
+            let tags_json: String = row.get(2)?;
+            let tags: Vec<String> = serde_json::from_str(&tags_json).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    2,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?;
+            Ok(Recurrence {
+                id: row.get(0)?,
+                content: row.get(1)?,
+                tags,
+                every: row.get(3)?,
+                start_date: row.get(4)?,
+                next_date: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::open_db_in_memory;
+
+    #[test]
+    fn test_recurrence_rules_become_due() {
+        let conn = open_db_in_memory().unwrap();
+        let tags = vec!["work".to_string()];
+        let rule = add_recurrence(&conn, "standup prep", &tags, "monday", "2025-01-06").unwrap();
+        assert_eq!(list_recurrences(&conn).unwrap(), vec![rule.clone()]);
+
+        assert!(due_recurrences(&conn, "2025-01-05").unwrap().is_empty());
+        assert_eq!(
+            due_recurrences(&conn, "2025-01-06").unwrap(),
+            vec![rule.clone()]
+        );
+
+        assert!(set_next_date(&conn, rule.id, "2025-01-06", "2025-01-13").unwrap());
+        assert!(!set_next_date(&conn, rule.id, "2025-01-06", "2025-01-13").unwrap());
+        assert!(due_recurrences(&conn, "2025-01-12").unwrap().is_empty());
+        let listed = list_recurrences(&conn).unwrap();
+        assert_eq!(listed[0].next_date, "2025-01-13");
+        assert_eq!(listed[0].start_date, "2025-01-06");
+        assert_eq!(listed[0].tags, tags);
+
+        assert!(remove_recurrence(&conn, rule.id).unwrap());
+        assert!(!remove_recurrence(&conn, rule.id).unwrap());
+        assert!(list_recurrences(&conn).unwrap().is_empty());
+    }
+}
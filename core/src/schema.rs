@@ -1,3 +1,6 @@
+/// Storage format of the `subject_date` column
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
 /// SQL schema for notes database (used by both CLI and server per-user DBs)
 pub const SCHEMA_V1: &str = r#"
 CREATE TABLE IF NOT EXISTS notes (
@@ -39,6 +42,415 @@ CREATE INDEX IF NOT EXISTS idx_created_at ON notes(created_at);
 PRAGMA user_version = 2;
 "#;
 
+/// Migration from V2 to V3: Store a content hash per note for duplicate detection
+///
+/// Existing rows are hashed by [`migrate`] after the column is added.
+pub const MIGRATION_V2_TO_V3: &str = r#"
+ALTER TABLE notes ADD COLUMN content_hash TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_content_hash ON notes(content_hash);
+
+PRAGMA user_version = 3;
+"#;
+
+/// Migration from V3 to V4: Friendly names for frequently-referenced notes
+pub const MIGRATION_V3_TO_V4: &str = r#"
+CREATE TABLE IF NOT EXISTS note_aliases (
+    name TEXT PRIMARY KEY NOT NULL,
+    note_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+PRAGMA user_version = 4;
+"#;
+
+/// Migration from V4 to V5: Durable queue of notes changed since the last sync
+pub const MIGRATION_V4_TO_V5: &str = r#"
+CREATE TABLE IF NOT EXISTS sync_queue (
+    note_id TEXT PRIMARY KEY NOT NULL,
+    queued_at INTEGER NOT NULL
+);
+
+PRAGMA user_version = 5;
+"#;
+
+/// Migration from V5 to V6: UTC offset the subject date was resolved in
+pub const MIGRATION_V5_TO_V6: &str = r#"
+ALTER TABLE notes ADD COLUMN subject_tz TEXT;
+
+PRAGMA user_version = 6;
+"#;
+
+/// Migration from V6 to V7: Counter bumped on every write to `notes`
+///
+/// Lets caches (see [`crate::cache::SearchCache`]) tell whether anything
+/// changed since they were filled, whichever connection did the write.
+pub const MIGRATION_V6_TO_V7: &str = r#"
+CREATE TABLE IF NOT EXISTS data_version (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    version INTEGER NOT NULL
+);
+
+INSERT OR IGNORE INTO data_version (id, version) VALUES (1, 0);
+
+CREATE TRIGGER IF NOT EXISTS notes_insert_version AFTER INSERT ON notes
+BEGIN
+    UPDATE data_version SET version = version + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS notes_update_version AFTER UPDATE ON notes
+BEGIN
+    UPDATE data_version SET version = version + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS notes_delete_version AFTER DELETE ON notes
+BEGIN
+    UPDATE data_version SET version = version + 1;
+END;
+
+PRAGMA user_version = 7;
+"#;
+
+/// Migration from V7 to V8: Composite indexes for searching live notes
+///
+/// `idx_live_sort` matches the ORDER BY of [`crate::db::search_notes`], so
+/// listing the newest notes reads them in order instead of sorting every row.
+/// It starts with `deleted_at`, which makes the old single-column index
+/// redundant.
+pub const MIGRATION_V7_TO_V8: &str = r#"
+DROP INDEX IF EXISTS idx_deleted_at;
+
+CREATE INDEX IF NOT EXISTS idx_live_updated_at ON notes(deleted_at, updated_at);
+CREATE INDEX IF NOT EXISTS idx_live_subject_date ON notes(deleted_at, subject_date);
+CREATE INDEX IF NOT EXISTS idx_live_sort ON notes(
+    deleted_at,
+    COALESCE(subject_date, DATE(created_at/1000, 'unixepoch')),
+    created_at
+);
+
+PRAGMA user_version = 8;
+"#;
+
+/// Migration from V8 to V9: Compressed storage for large note bodies
+///
+/// A note whose `content_zstd` is set keeps its body there (zstd-compressed)
+/// and an empty `content`; see [`crate::compress`].
+pub const MIGRATION_V8_TO_V9: &str = r#"
+ALTER TABLE notes ADD COLUMN content_zstd BLOB;
+
+PRAGMA user_version = 9;
+"#;
+
+/// Migration from V9 to V10: Full-text index of note bodies and tags
+///
+/// `notes_fts` is a contentless FTS5 table (`content = ''`) whose rowid is the
+/// note's rowid, so it stores only the index, not a second copy of the
+/// bodies. Its triggers are plain SQL, so a stock `sqlite3` can still write
+/// notes: they index plain bodies, while bodies stored compressed are indexed
+/// by jot when it writes them (see [`crate::db::index_compressed_body`]). A
+/// compressed note edited with another tool drops out of the index until
+/// `jot db reindex`.
+pub const MIGRATION_V9_TO_V10: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
+    content,
+    tags,
+    content = '',
+    contentless_delete = 1,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO notes_fts(rowid, content, tags)
+SELECT rowid, note_content(content, content_zstd), tags FROM notes;
+
+CREATE TRIGGER IF NOT EXISTS notes_fts_insert AFTER INSERT ON notes
+BEGIN
+    INSERT INTO notes_fts(rowid, content, tags)
+    SELECT new.rowid, new.content, new.tags WHERE new.content_zstd IS NULL;
+END;
+
+CREATE TRIGGER IF NOT EXISTS notes_fts_update AFTER UPDATE OF content, content_zstd, tags ON notes
+BEGIN
+    DELETE FROM notes_fts WHERE rowid = old.rowid;
+    INSERT INTO notes_fts(rowid, content, tags)
+    SELECT new.rowid, new.content, new.tags WHERE new.content_zstd IS NULL;
+END;
+
+CREATE TRIGGER IF NOT EXISTS notes_fts_delete AFTER DELETE ON notes
+BEGIN
+    DELETE FROM notes_fts WHERE rowid = old.rowid;
+END;
+
+PRAGMA user_version = 10;
+"#;
+
+/// Migration from V10 to V11: Pinned notes
+///
+/// Pinning used to be the `pinned` tag; tagged notes are carried over to the
+/// column and lose the tag. `idx_live_pinned_sort` matches the default ORDER
+/// BY of [`crate::db::search_notes`], `idx_live_sort` still serves
+/// chronological listings.
+pub const MIGRATION_V10_TO_V11: &str = r#"
+ALTER TABLE notes ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+
+UPDATE notes
+SET pinned = 1,
+    tags = (SELECT json_group_array(value) FROM json_each(notes.tags) WHERE value != 'pinned')
+WHERE EXISTS (SELECT 1 FROM json_each(notes.tags) WHERE value = 'pinned');
+
+CREATE INDEX IF NOT EXISTS idx_live_pinned_sort ON notes(
+    deleted_at,
+    pinned,
+    COALESCE(subject_date, DATE(created_at/1000, 'unixepoch')),
+    created_at
+);
+
+PRAGMA user_version = 11;
+"#;
+
+/// Migration from V11 to V12: Time tracked against notes
+///
+/// See [`crate::time_entry`]; `stopped_at` is NULL while an entry runs.
+pub const MIGRATION_V11_TO_V12: &str = r#"
+CREATE TABLE IF NOT EXISTS time_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    note_id TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    stopped_at INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_time_entries_started_at ON time_entries(started_at);
+
+PRAGMA user_version = 12;
+"#;
+
+/// Migration from V12 to V13: Workflow status of notes (`jot board`)
+pub const MIGRATION_V12_TO_V13: &str = r#"
+ALTER TABLE notes ADD COLUMN status TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_live_status ON notes(deleted_at, status);
+
+PRAGMA user_version = 13;
+"#;
+
+/// Migration from V13 to V14: Rules for recurring notes (`jot recur`)
+///
+/// See [`crate::recurrence`]; `next_date` is the first occurrence not
+/// turned into a note yet.
+pub const MIGRATION_V13_TO_V14: &str = r#"
+CREATE TABLE IF NOT EXISTS recurrences (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    content TEXT NOT NULL,
+    tags TEXT NOT NULL DEFAULT '[]',
+    every TEXT NOT NULL,
+    start_date TEXT NOT NULL,
+    next_date TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+PRAGMA user_version = 14;
+"#;
+
+/// Migration from V14 to V15: Capture metadata of notes
+///
+/// A JSON object of strings (`{"repo": "jot", "branch": "main"}`), NULL
+/// when the note has none.
+pub const MIGRATION_V14_TO_V15: &str = r#"
+ALTER TABLE notes ADD COLUMN meta TEXT;
+
+PRAGMA user_version = 15;
+"#;
+
+/// Migration from V15 to V16: Files attached to notes
+///
+/// See [`crate::attachment`]; file contents are stored once per SHA-256 in
+/// `attachment_blobs`, however many notes carry them.
+pub const MIGRATION_V15_TO_V16: &str = r#"
+CREATE TABLE IF NOT EXISTS attachments (
+    id TEXT PRIMARY KEY,
+    note_id TEXT NOT NULL,
+    filename TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_attachments_note_id ON attachments(note_id, created_at, id);
+
+CREATE TABLE IF NOT EXISTS attachment_blobs (
+    hash TEXT PRIMARY KEY,
+    data BLOB NOT NULL
+);
+
+PRAGMA user_version = 16;
+"#;
+
+/// Migration from V16 to V17: Tags as rows
+///
+/// `notes.tags` stays the JSON list notes are read and synced with; the
+/// triggers mirror it into `note_tags` so tag filters are exact lookups
+/// instead of pattern matches on the JSON text. Tags no note uses any more
+/// are dropped by [`crate::db::compact_db`].
+pub const MIGRATION_V16_TO_V17: &str = r#"
+CREATE TABLE IF NOT EXISTS tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS note_tags (
+    note_id TEXT NOT NULL,
+    tag_id INTEGER NOT NULL,
+    PRIMARY KEY (note_id, tag_id)
+) WITHOUT ROWID;
+
+CREATE INDEX IF NOT EXISTS idx_note_tags_tag_id ON note_tags(tag_id, note_id);
+
+INSERT OR IGNORE INTO tags(name)
+SELECT DISTINCT json_each.value FROM notes, json_each(notes.tags);
+
+INSERT OR IGNORE INTO note_tags(note_id, tag_id)
+SELECT notes.id, tags.id FROM notes, json_each(notes.tags)
+JOIN tags ON tags.name = json_each.value;
+
+CREATE TRIGGER IF NOT EXISTS notes_tags_insert AFTER INSERT ON notes
+BEGIN
+    INSERT OR IGNORE INTO tags(name) SELECT value FROM json_each(new.tags);
+    INSERT OR IGNORE INTO note_tags(note_id, tag_id)
+    SELECT new.id, tags.id FROM json_each(new.tags) JOIN tags ON tags.name = json_each.value;
+END;
+
+CREATE TRIGGER IF NOT EXISTS notes_tags_update AFTER UPDATE OF tags ON notes
+BEGIN
+    DELETE FROM note_tags WHERE note_id = old.id;
+    INSERT OR IGNORE INTO tags(name) SELECT value FROM json_each(new.tags);
+    INSERT OR IGNORE INTO note_tags(note_id, tag_id)
+    SELECT new.id, tags.id FROM json_each(new.tags) JOIN tags ON tags.name = json_each.value;
+END;
+
+CREATE TRIGGER IF NOT EXISTS notes_tags_delete AFTER DELETE ON notes
+BEGIN
+    DELETE FROM note_tags WHERE note_id = old.id;
+END;
+
+PRAGMA user_version = 17;
+"#;
+
+/// Migration from V17 to V18: Archiving
+///
+/// Archived notes are not deleted: they still sync and come back with
+/// unarchive, but searches leave them out unless asked for.
+pub const MIGRATION_V17_TO_V18: &str = r#"
+ALTER TABLE notes ADD COLUMN archived_at INTEGER;
+
+PRAGMA user_version = 18;
+"#;
+
+/// A single schema upgrade step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Migration {
+    /// Schema version after this step is applied
+    pub version: i32,
+    /// Human-readable summary shown by `jot db migrate --status`
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+/// All migrations in application order
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Create notes and sync_state tables",
+        sql: SCHEMA_V1,
+    },
+    Migration {
+        version: 2,
+        description: "Rename date column to subject_date and index created_at",
+        sql: MIGRATION_V1_TO_V2,
+    },
+    Migration {
+        version: 3,
+        description: "Add content_hash column for duplicate detection",
+        sql: MIGRATION_V2_TO_V3,
+    },
+    Migration {
+        version: 4,
+        description: "Add note_aliases table",
+        sql: MIGRATION_V3_TO_V4,
+    },
+    Migration {
+        version: 5,
+        description: "Add sync_queue table",
+        sql: MIGRATION_V4_TO_V5,
+    },
+    Migration {
+        version: 6,
+        description: "Add subject_tz column",
+        sql: MIGRATION_V5_TO_V6,
+    },
+    Migration {
+        version: 7,
+        description: "Add data_version counter",
+        sql: MIGRATION_V6_TO_V7,
+    },
+    Migration {
+        version: 8,
+        description: "Add composite indexes for search",
+        sql: MIGRATION_V7_TO_V8,
+    },
+    Migration {
+        version: 9,
+        description: "Add content_zstd column for compressed note bodies",
+        sql: MIGRATION_V8_TO_V9,
+    },
+    Migration {
+        version: 10,
+        description: "Add notes_fts full-text index",
+        sql: MIGRATION_V9_TO_V10,
+    },
+    Migration {
+        version: 11,
+        description: "Add pinned column",
+        sql: MIGRATION_V10_TO_V11,
+    },
+    Migration {
+        version: 12,
+        description: "Add time_entries table",
+        sql: MIGRATION_V11_TO_V12,
+    },
+    Migration {
+        version: 13,
+        description: "Add status column",
+        sql: MIGRATION_V12_TO_V13,
+    },
+    Migration {
+        version: 14,
+        description: "Add recurrences table",
+        sql: MIGRATION_V13_TO_V14,
+    },
+    Migration {
+        version: 15,
+        description: "Add meta column",
+        sql: MIGRATION_V14_TO_V15,
+    },
+    Migration {
+        version: 16,
+        description: "Add attachments tables",
+        sql: MIGRATION_V15_TO_V16,
+    },
+    Migration {
+        version: 17,
+        description: "Add tags and note_tags tables",
+        sql: MIGRATION_V16_TO_V17,
+    },
+    Migration {
+        version: 18,
+        description: "Add archived_at column",
+        sql: MIGRATION_V17_TO_V18,
+    },
+];
+
+/// Schema version produced by running every migration
+pub const LATEST_VERSION: i32 = 18;
+
 /// Get current schema version from database
 pub fn get_schema_version(conn: &rusqlite::Connection) -> Result<i32, rusqlite::Error> {
     conn.pragma_query_value(None, "user_version", |row| row.get(0))
@@ -52,27 +464,238 @@ pub fn set_schema_version(
     conn.pragma_update(None, "user_version", version)
 }
 
+/// Subject date that doesn't match [`DATE_FORMAT`]
+#[derive(Debug, thiserror::Error)]
+#[error("Note {note_id} has invalid subject_date '{value}' (expected YYYY-MM-DD)")]
+pub struct InvalidSubjectDate {
+    pub note_id: String,
+    pub value: String,
+}
+
+/// Check that every stored subject_date is a valid YYYY-MM-DD date
+pub fn validate_subject_dates(conn: &rusqlite::Connection) -> Result<(), rusqlite::Error> {
+    let mut stmt =
+        conn.prepare("SELECT id, subject_date FROM notes WHERE subject_date IS NOT NULL")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    for row in rows {
+        let (note_id, value) = row?;
+        if chrono::NaiveDate::parse_from_str(&value, DATE_FORMAT).is_err() {
+            return Err(rusqlite::Error::FromSqlConversionFailure(
+                1,
+                rusqlite::types::Type::Text,
+                Box::new(InvalidSubjectDate { note_id, value }),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Database was written by a build of jot that knows a later schema
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[error("Database schema v{found} was created by a newer version of jot (this version supports up to v{supported}). Please upgrade jot.")]
+pub struct NewerSchemaVersion {
+    pub found: i32,
+    pub supported: i32,
+}
+
+/// Extract a [`NewerSchemaVersion`] error, if that is what `err` wraps
+pub fn as_newer_schema_version(err: &rusqlite::Error) -> Option<&NewerSchemaVersion> {
+    match err {
+        rusqlite::Error::ToSqlConversionFailure(inner) => inner.downcast_ref(),
+        _ => None,
+    }
+}
+
+fn check_not_newer(version: i32) -> Result<(), rusqlite::Error> {
+    if version > LATEST_VERSION {
+        return Err(rusqlite::Error::ToSqlConversionFailure(Box::new(
+            NewerSchemaVersion {
+                found: version,
+                supported: LATEST_VERSION,
+            },
+        )));
+    }
+    Ok(())
+}
+
+/// List migrations that [`migrate`] would apply, without changing the database
+pub fn migrate_dry_run(conn: &rusqlite::Connection) -> Result<Vec<Migration>, rusqlite::Error> {
+    let version = get_schema_version(conn)?;
+    check_not_newer(version)?;
+    Ok(MIGRATIONS
+        .iter()
+        .filter(|m| m.version > version)
+        .copied()
+        .collect())
+}
+
 /// Run migrations to bring database to current schema version
 pub fn migrate(conn: &rusqlite::Connection) -> Result<(), rusqlite::Error> {
-    let mut version = get_schema_version(conn)?;
+    // Steps that fill the full-text index decode bodies with `note_content`
+    crate::compress::register_functions(conn)?;
+
+    // Apply all steps atomically so a failed validation leaves the database untouched
+    let tx = conn.unchecked_transaction()?;
+    let pending = migrate_dry_run(&tx)?;
 
     // Apply migrations sequentially
-    if version == 0 {
-        // Fresh database - apply v1 schema
-        conn.execute_batch(SCHEMA_V1)?;
-        version = 1;
+    for migration in &pending {
+        tx.execute_batch(migration.sql)?;
     }
 
-    if version == 1 {
-        // Migrate from v1 to v2
-        conn.execute_batch(MIGRATION_V1_TO_V2)?;
-        version = 2;
+    // Date filters compare subject_date as text, so migrated rows must use the canonical format
+    if !pending.is_empty() {
+        validate_subject_dates(&tx)?;
+        crate::dedupe::backfill_content_hashes(&tx)?;
     }
 
-    // Version 2 is current
-    if version == 2 {
-        Ok(())
+    if get_schema_version(&tx)? == LATEST_VERSION {
+        tx.commit()
     } else {
         Err(rusqlite::Error::InvalidQuery)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn test_migration_rejects_malformed_subject_date() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA_V1).unwrap();
+        conn.execute(
+            "INSERT INTO notes (id, content, tags, date, created_at, updated_at) VALUES ('a', 'x', '[]', '16/03/2024', 0, 0)",
+            [],
+        )
+        .unwrap();
+
+        let err = migrate(&conn).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("invalid subject_date '16/03/2024'"));
+        assert_eq!(get_schema_version(&conn).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_migration_accepts_valid_subject_date() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA_V1).unwrap();
+        conn.execute(
+            "INSERT INTO notes (id, content, tags, date, created_at, updated_at) VALUES ('a', 'x', '[]', '2024-03-16', 0, 0)",
+            [],
+        )
+        .unwrap();
+
+        migrate(&conn).unwrap();
+        assert_eq!(get_schema_version(&conn).unwrap(), LATEST_VERSION);
+
+        let hash: String = conn
+            .query_row("SELECT content_hash FROM notes WHERE id = 'a'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(hash, crate::dedupe::content_hash("x"));
+    }
+
+    #[test]
+    fn test_migration_moves_pinned_tag_to_column() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA_V1).unwrap();
+        conn.execute(
+            "INSERT INTO notes (id, content, tags, created_at, updated_at) VALUES ('a', 'x', '[\"work\",\"pinned\"]', 0, 0), ('b', 'y', '[\"pinned-ish\"]', 0, 0)",
+            [],
+        )
+        .unwrap();
+
+        migrate(&conn).unwrap();
+
+        let rows: Vec<(String, String, bool)> = conn
+            .prepare("SELECT id, tags, pinned FROM notes ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                ("a".to_string(), "[\"work\"]".to_string(), true),
+                ("b".to_string(), "[\"pinned-ish\"]".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_migration_backfills_note_tags() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA_V1).unwrap();
+        conn.execute(
+            "INSERT INTO notes (id, content, tags, created_at, updated_at) VALUES ('a', 'x', '[\"work\",\"urgent\"]', 0, 0), ('b', 'y', '[\"work\"]', 0, 0), ('c', 'z', '[]', 0, 0)",
+            [],
+        )
+        .unwrap();
+
+        migrate(&conn).unwrap();
+
+        let rows: Vec<(String, String)> = conn
+            .prepare(
+                "SELECT note_tags.note_id, tags.name FROM note_tags
+                 JOIN tags ON tags.id = note_tags.tag_id ORDER BY 1, 2",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let expected = [("a", "urgent"), ("a", "work"), ("b", "work")];
+        assert_eq!(
+            rows,
+            expected
+                .iter()
+                .map(|(id, tag)| (id.to_string(), tag.to_string()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_migrate_dry_run_lists_pending() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        assert_eq!(migrate_dry_run(&conn).unwrap().len(), MIGRATIONS.len());
+
+        conn.execute_batch(SCHEMA_V1).unwrap();
+        let pending = migrate_dry_run(&conn).unwrap();
+        let versions: Vec<i32> = pending.iter().map(|m| m.version).collect();
+        assert_eq!(
+            versions,
+            vec![2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18]
+        );
+        // Dry run must not touch the schema
+        assert_eq!(get_schema_version(&conn).unwrap(), 1);
+
+        migrate(&conn).unwrap();
+        assert!(migrate_dry_run(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migrate_refuses_newer_schema() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        set_schema_version(&conn, LATEST_VERSION + 1).unwrap();
+
+        let err = migrate(&conn).unwrap_err();
+        assert_eq!(
+            as_newer_schema_version(&err),
+            Some(&NewerSchemaVersion {
+                found: LATEST_VERSION + 1,
+                supported: LATEST_VERSION
+            })
+        );
+        assert!(err.to_string().contains("newer version of jot"));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::builder::NoteBuilder;
+use crate::db::with_transaction;
+use rusqlite::{Connection, Result};
+
+const WORDS: &[&str] = &[
+    "meeting",
+    "review",
+    "deploy",
+    "bug",
+    "idea",
+    "follow",
+    "up",
+    "with",
+    "team",
+    "about",
+    "the",
+    "release",
+    "notes",
+    "for",
+    "customer",
+    "call",
+    "draft",
+    "plan",
+    "sprint",
+    "fix",
+    "database",
+    "migration",
+    "search",
+    "sync",
+    "server",
+    "client",
+    "backlog",
+    "estimate",
+    "design",
+    "doc",
+    "question",
+    "answer",
+    "todo",
+    "later",
+    "today",
+    "tomorrow",
+    "blocked",
+    "waiting",
+    "on",
+    "feedback",
+    "and",
+    "check",
+    "logs",
+    "metrics",
+    "latency",
+    "budget",
+];
+
+const TAGS: &[&str] = &[
+    "work", "personal", "ideas", "meeting", "todo", "backend", "frontend", "ops", "reading",
+    "health", "finance", "travel",
+];
+
+/// Milliseconds in a day
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// How far back generated notes are spread
+const SPAN_DAYS: i64 = 2 * 365;
+
+/// Small deterministic xorshift generator, so seeded databases are reproducible
+#[derive(Debug, Clone)]
+pub struct SeedRng(u64);
+
+impl SeedRng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on zero
+        Self(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// Uniform value in `0..bound` (`bound` must be non-zero)
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+/// Generate a plausible note created within the two years before `now`
+pub fn generate_note(rng: &mut SeedRng, now: i64) -> NoteBuilder {
+    let sentences = 1 + rng.below(4);
+    let content = (0..sentences)
+        .map(|_| {
+            let words = 4 + rng.below(12);
+            (0..words)
+                .map(|_| rng.pick(WORDS))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join(".\n");
+
+    let mut tags: Vec<String> = Vec::new();
+    for _ in 0..rng.below(4) {
+        let tag = rng.pick(TAGS).to_string();
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    let created_at = now - rng.below((SPAN_DAYS * DAY_MS) as usize) as i64;
+    let mut builder = NoteBuilder::new(content).tags(tags).created_at(created_at);
+
+    // Roughly a third of notes are about a specific day near their creation
+    if rng.below(3) == 0 {
+        let offset_days = rng.below(14) as i64 - 7;
+        if let Some(date) =
+            chrono::DateTime::from_timestamp_millis(created_at + offset_days * DAY_MS)
+        {
+            builder = builder.subject_date(date.date_naive().to_string());
+        }
+    }
+
+    builder
+}
+
+/// Insert `count` generated notes in a single transaction
+pub fn seed_notes(conn: &Connection, count: usize, seed: u64) -> Result<usize> {
+    let mut rng = SeedRng::new(seed);
+    let now = chrono::Utc::now().timestamp_millis();
+
+    with_transaction(conn, |tx| {
+        for _ in 0..count {
+            generate_note(&mut rng, now).insert(tx)?;
+        }
+        Ok(count)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::{open_db_in_memory, search_notes};
+    use crate::models::SearchQuery;
+
+    #[test]
+    fn test_seed_is_deterministic() {
+        let a = generate_note(&mut SeedRng::new(7), 1_700_000_000_000)
+            .build()
+            .unwrap();
+        let b = generate_note(&mut SeedRng::new(7), 1_700_000_000_000)
+            .build()
+            .unwrap();
+        assert_eq!(a.content, b.content);
+        assert_eq!(a.tags, b.tags);
+        assert_eq!(a.subject_date, b.subject_date);
+    }
+
+    #[test]
+    fn test_seed_notes_inserts_count() {
+        let conn = open_db_in_memory().unwrap();
+        assert_eq!(seed_notes(&conn, 50, 1).unwrap(), 50);
+
+        let notes = search_notes(&conn, &SearchQuery::default()).unwrap();
+        assert_eq!(notes.len(), 50);
+    }
+}
@@ -1,15 +1,61 @@
-use crate::db::{get_note_by_id, get_notes_since, upsert_note};
-use crate::models::{Note, SyncRequest, SyncResponse};
+use crate::db::{get_note_by_id, get_notes_since, upsert_note, with_transaction};
+use crate::models::{Conflict, Note, SyncRequest, SyncResponse, SyncSide};
+use crate::observer::ObserverRegistry;
 use rusqlite::{Connection, Result};
 
+/// Version of the `/sync` wire format spoken by this build
+///
+/// Bump it whenever a change to the request or response shape would break
+/// an older peer; servers advertise every version they still accept.
+pub const SYNC_PROTOCOL_VERSION: u32 = 1;
+
 /// Merge notes from client into server database
-/// Returns notes that client needs to update
+/// Returns notes that client needs to update, plus the conflicts that
+/// last-write-wins resolved
+///
+/// The whole merge runs in a single transaction.
 pub fn merge_notes(
     conn: &Connection,
     client_notes: Vec<Note>,
     client_last_sync: i64,
-) -> Result<Vec<Note>> {
+) -> Result<SyncResponse> {
+    merge_notes_with(
+        conn,
+        client_notes,
+        client_last_sync,
+        &ObserverRegistry::new(),
+    )
+}
+
+/// Same as [`merge_notes`], notifying `observers` about every client change that was applied
+///
+/// Observers run only after the merge transaction has committed.
+pub fn merge_notes_with(
+    conn: &Connection,
+    client_notes: Vec<Note>,
+    client_last_sync: i64,
+    observers: &ObserverRegistry,
+) -> Result<SyncResponse> {
+    let mut applied = Vec::new();
+    let response = with_transaction(conn, |tx| {
+        merge_notes_in(tx, client_notes, client_last_sync, &mut applied)
+    })?;
+
+    for (previous, note) in &applied {
+        observers.notify_change(previous.as_ref(), note);
+    }
+
+    Ok(response)
+}
+
+fn merge_notes_in(
+    conn: &Connection,
+    client_notes: Vec<Note>,
+    client_last_sync: i64,
+    applied: &mut Vec<(Option<Note>, Note)>,
+) -> Result<SyncResponse> {
     let mut notes_to_send = Vec::new();
+    let mut conflicts = Vec::new();
     let mut client_note_ids: Vec<String> = Vec::new();
 
     // Process each incoming note from client
@@ -21,14 +67,36 @@ pub fn merge_notes(
             None => {
                 // New note from client - insert it
                 upsert_note(conn, &client_note)?;
+                applied.push((None, client_note));
             }
             Some(server_note) => {
                 // Conflict resolution: Last-Write-Wins
                 if client_note.updated_at > server_note.updated_at {
                     // Client version is newer
+                    if server_note.updated_at > client_last_sync
+                        && !same_version(&server_note, &client_note)
+                    {
+                        // The client never saw this server edit
+                        conflicts.push(Conflict {
+                            note_id: server_note.id.clone(),
+                            winner: SyncSide::Client,
+                            losing_version: server_note.clone(),
+                        });
+                    }
                     upsert_note(conn, &client_note)?;
+                    applied.push((Some(server_note), client_note));
                 } else if server_note.updated_at > client_note.updated_at {
                     // Server version is newer - send to client
+                    if client_note.updated_at > client_last_sync
+                        && !same_version(&server_note, &client_note)
+                    {
+                        // The client's local edit is overwritten
+                        conflicts.push(Conflict {
+                            note_id: client_note.id.clone(),
+                            winner: SyncSide::Server,
+                            losing_version: client_note,
+                        });
+                    }
                     notes_to_send.push(server_note);
                 }
                 // If timestamps equal, no action needed
@@ -46,13 +114,33 @@ pub fn merge_notes(
         }
     }
 
-    Ok(notes_to_send)
+    Ok(SyncResponse {
+        notes: notes_to_send,
+        conflicts,
+    })
+}
+
+/// Whether two versions carry the same user-visible data (timestamps aside)
+fn same_version(a: &Note, b: &Note) -> bool {
+    a.content == b.content
+        && a.tags == b.tags
+        && a.subject_date == b.subject_date
+        && a.deleted_at.is_some() == b.deleted_at.is_some()
+        && a.attachments == b.attachments
 }
 
 /// Process sync request (server-side logic)
 pub fn process_sync_request(conn: &Connection, request: SyncRequest) -> Result<SyncResponse> {
-    let notes = merge_notes(conn, request.notes, request.last_sync)?;
-    Ok(SyncResponse { notes })
+    process_sync_request_with(conn, request, &ObserverRegistry::new())
+}
+
+/// Process sync request, notifying `observers` about applied client changes
+pub fn process_sync_request_with(
+    conn: &Connection,
+    request: SyncRequest,
+    observers: &ObserverRegistry,
+) -> Result<SyncResponse> {
+    merge_notes_with(conn, request.notes, request.last_sync, observers)
 }
 
 #[cfg(test)]
@@ -60,31 +148,35 @@ mod tests {
     #![allow(clippy::unwrap_used)]
 
     use super::*;
-    use crate::db::{create_note, open_db};
+    use crate::db::{create_note, open_db_in_memory};
     use std::thread;
     use std::time::Duration;
-    use tempfile::TempDir;
 
     #[test]
     fn test_merge_new_note_from_client() {
-        let dir = TempDir::new().unwrap();
-        let db_path = dir.path().join("test.db");
-        let conn = open_db(&db_path).unwrap();
+        let conn = open_db_in_memory().unwrap();
 
         let client_note = Note {
             id: "01ARZ3NDEKTSV4RRFFQ69G5FAV".to_string(),
             content: "client note".to_string(),
             tags: vec![],
             subject_date: None,
+            subject_tz: None,
             created_at: 1000,
             updated_at: 1000,
             deleted_at: None,
+            pinned: false,
+            status: None,
+            meta: Default::default(),
+            attachments: vec![],
+            archived_at: None,
         };
 
         let result = merge_notes(&conn, vec![client_note.clone()], 0).unwrap();
 
         // Should return empty since server has no newer notes
-        assert_eq!(result.len(), 0);
+        assert_eq!(result.notes.len(), 0);
+        assert!(result.conflicts.is_empty());
 
         // Verify note was inserted
         let note = get_note_by_id(&conn, &client_note.id).unwrap().unwrap();
@@ -94,9 +186,7 @@ mod tests {
 
     #[test]
     fn test_merge_conflict_last_write_wins() {
-        let dir = TempDir::new().unwrap();
-        let db_path = dir.path().join("test.db");
-        let conn = open_db(&db_path).unwrap();
+        let conn = open_db_in_memory().unwrap();
 
         // Create server note
         let note = create_note(&conn, "server version", vec![], None).unwrap();
@@ -109,19 +199,132 @@ mod tests {
             content: "client version (newer)".to_string(),
             tags: vec![],
             subject_date: None,
+            subject_tz: None,
             created_at: note.created_at,
             updated_at: chrono::Utc::now().timestamp_millis(),
             deleted_at: None,
+            pinned: false,
+            status: None,
+            meta: Default::default(),
+            attachments: vec![],
+            archived_at: None,
         };
 
         let result = merge_notes(&conn, vec![client_note.clone()], 0).unwrap();
 
         // Server should not send anything back (client version wins)
-        assert_eq!(result.len(), 0);
+        assert_eq!(result.notes.len(), 0);
+
+        // The client never saw the server version, so it is reported as lost
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].winner, SyncSide::Client);
+        assert_eq!(result.conflicts[0].losing_version.content, "server version");
 
         // Verify client version was saved
         let updated = get_note_by_id(&conn, &note.id).unwrap().unwrap();
 
         assert_eq!(updated.content, "client version (newer)");
     }
+
+    #[test]
+    fn test_merge_reports_overwritten_client_edit() {
+        let conn = open_db_in_memory().unwrap();
+        let note = create_note(&conn, "original", vec![], None).unwrap();
+        let last_sync = note.updated_at;
+
+        // Edited on the server after the client's last sync...
+        let server_edit = Note {
+            content: "server edit".to_string(),
+            updated_at: last_sync + 20,
+            ..note.clone()
+        };
+        upsert_note(&conn, &server_edit).unwrap();
+
+        // ...and, earlier, on the client
+        let client_edit = Note {
+            content: "client edit".to_string(),
+            updated_at: last_sync + 10,
+            ..note.clone()
+        };
+        let result = merge_notes(&conn, vec![client_edit], last_sync).unwrap();
+
+        assert_eq!(result.notes.len(), 1);
+        assert_eq!(result.notes[0].content, "server edit");
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].winner, SyncSide::Server);
+        assert_eq!(result.conflicts[0].losing_version.content, "client edit");
+
+        // An unchanged client copy that is merely stale is not a conflict
+        let stale = Note {
+            updated_at: last_sync,
+            ..note
+        };
+        let result = merge_notes(&conn, vec![stale], last_sync).unwrap();
+        assert!(result.conflicts.is_empty());
+    }
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        events: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl crate::observer::NoteObserver for RecordingObserver {
+        fn on_create(&self, note: &Note) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("create {}", note.content));
+        }
+        fn on_update(&self, note: &Note) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("update {}", note.content));
+        }
+        fn on_delete(&self, note: &Note) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("delete {}", note.content));
+        }
+    }
+
+    #[test]
+    fn test_merge_notifies_observers() {
+        let conn = open_db_in_memory().unwrap();
+
+        let existing = create_note(&conn, "existing", vec![], None).unwrap();
+
+        let recorder = std::sync::Arc::new(RecordingObserver::default());
+        let mut observers = ObserverRegistry::new();
+        observers.register(recorder.clone());
+
+        let new_note = Note {
+            id: "01ARZ3NDEKTSV4RRFFQ69G5FAV".to_string(),
+            content: "new".to_string(),
+            tags: vec![],
+            subject_date: None,
+            subject_tz: None,
+            created_at: 1000,
+            updated_at: 1000,
+            deleted_at: None,
+            pinned: false,
+            status: None,
+            meta: Default::default(),
+            attachments: vec![],
+            archived_at: None,
+        };
+        let deleted = Note {
+            updated_at: existing.updated_at + 1,
+            deleted_at: Some(existing.updated_at + 1),
+            ..existing
+        };
+
+        merge_notes_with(&conn, vec![new_note, deleted], 0, &observers).unwrap();
+
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec!["create new".to_string(), "delete existing".to_string()]
+        );
+    }
 }
@@ -0,0 +1,57 @@
+//! Notes changed locally that the server hasn't received yet
+//!
+//! Clients record every note they write here, so a sync sends exactly the
+//! changed set even when the system clock moved backwards and an edit got an
+//! `updated_at` older than the last sync.
+
+use rusqlite::{params, Connection, Result};
+
+/// Remember that `note_id` changed and must be sent on the next sync
+pub fn enqueue(conn: &Connection, note_id: &str) -> Result<()> {
+    let now = chrono::Utc::now().timestamp_millis();
+    conn.execute(
+        "INSERT INTO sync_queue (note_id, queued_at) VALUES (?1, ?2)
+         ON CONFLICT(note_id) DO NOTHING",
+        params![note_id, now],
+    )?;
+    Ok(())
+}
+
+/// IDs waiting to be synced, oldest first
+pub fn queued_ids(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT note_id FROM sync_queue ORDER BY queued_at, note_id")?;
+    let ids = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>>>()?;
+    Ok(ids)
+}
+
+/// Drop IDs the server has acknowledged
+pub fn dequeue(conn: &Connection, note_ids: &[String]) -> Result<()> {
+    let mut stmt = conn.prepare("DELETE FROM sync_queue WHERE note_id = ?1")?;
+    for id in note_ids {
+        stmt.execute(params![id])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::open_db_in_memory;
+
+    #[test]
+    fn test_queue_deduplicates_and_dequeues() {
+        let conn = open_db_in_memory().unwrap();
+
+        enqueue(&conn, "a").unwrap();
+        enqueue(&conn, "b").unwrap();
+        enqueue(&conn, "a").unwrap();
+        assert_eq!(queued_ids(&conn).unwrap(), vec!["a", "b"]);
+
+        dequeue(&conn, &["a".to_string()]).unwrap();
+        assert_eq!(queued_ids(&conn).unwrap(), vec!["b"]);
+    }
+}
@@ -0,0 +1,122 @@
+//! Time tracked against notes (`jot track`)
+//!
+//! At most one entry runs at a time; it has no `stopped_at` yet. Entries are
+//! local to the device and don't sync.
+
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+
+/// One interval of work on a note
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub id: i64,
+    pub note_id: String,
+    /// Milliseconds since the Unix epoch
+    pub started_at: i64,
+    /// Milliseconds since the Unix epoch (None while the entry is running)
+    pub stopped_at: Option<i64>,
+}
+
+impl TimeEntry {
+    /// Milliseconds of the entry inside `[from, to)`; a running entry counts up to `now`
+    pub fn overlap_ms(&self, from: i64, to: i64, now: i64) -> i64 {
+        let end = self.stopped_at.unwrap_or(now).min(to);
+        (end - self.started_at.max(from)).max(0)
+    }
+}
+
+/// The entry being tracked right now, if any
+pub fn running_entry(conn: &Connection) -> Result<Option<TimeEntry>> {
+    conn.query_row(
+        "SELECT id, note_id, started_at, stopped_at FROM time_entries WHERE stopped_at IS NULL",
+        [],
+        read_entry,
+    )
+    .optional()
+}
+
+/// Start tracking `note_id` at `now`, stopping the running entry first
+///
+/// Returns the stopped entry, if there was one.
+pub fn start_entry(conn: &Connection, note_id: &str, now: i64) -> Result<Option<TimeEntry>> {
+    let stopped = stop_entry(conn, now)?;
+    conn.execute(
+        "INSERT INTO time_entries (note_id, started_at) VALUES (?1, ?2)",
+        params![note_id, now],
+    )?;
+    Ok(stopped)
+}
+
+/// Stop the running entry at `now`, returning it
+pub fn stop_entry(conn: &Connection, now: i64) -> Result<Option<TimeEntry>> {
+    let Some(mut entry) = running_entry(conn)? else {
+        return Ok(None);
+    };
+    // A clock that went backwards must not produce a negative interval
+    let stopped_at = now.max(entry.started_at);
+    conn.execute(
+        "UPDATE time_entries SET stopped_at = ?1 WHERE id = ?2",
+        params![stopped_at, entry.id],
+    )?;
+    entry.stopped_at = Some(stopped_at);
+    Ok(Some(entry))
+}
+
+/// Entries overlapping `[from, to)`, running one included, oldest first
+pub fn entries_between(conn: &Connection, from: i64, to: i64) -> Result<Vec<TimeEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, note_id, started_at, stopped_at FROM time_entries
+         WHERE started_at < ?2 AND (stopped_at IS NULL OR stopped_at > ?1)
+         ORDER BY started_at, id",
+    )?;
+    let entries = stmt
+        .query_map(params![from, to], read_entry)?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(entries)
+}
+
+fn read_entry(row: &rusqlite::Row) -> Result<TimeEntry> {
+    Ok(TimeEntry {
+        id: row.get(0)?,
+        note_id: row.get(1)?,
+        started_at: row.get(2)?,
+        stopped_at: row.get(3)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::{create_note, open_db_in_memory};
+
+    #[test]
+    fn test_track_start_stop_and_report_window() {
+        let conn = open_db_in_memory().unwrap();
+        let first = create_note(&conn, "client A", vec![], None).unwrap();
+        let second = create_note(&conn, "client B", vec![], None).unwrap();
+
+        assert_eq!(start_entry(&conn, &first.id, 1_000).unwrap(), None);
+        // Switching notes closes the running entry
+        let stopped = start_entry(&conn, &second.id, 5_000).unwrap().unwrap();
+        assert_eq!(stopped.note_id, first.id);
+        assert_eq!(stopped.stopped_at, Some(5_000));
+        assert_eq!(running_entry(&conn).unwrap().unwrap().note_id, second.id);
+
+        let stopped = stop_entry(&conn, 9_000).unwrap().unwrap();
+        assert_eq!(stopped.note_id, second.id);
+        assert_eq!(stop_entry(&conn, 10_000).unwrap(), None);
+
+        let entries = entries_between(&conn, 3_000, 6_000).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].overlap_ms(3_000, 6_000, 10_000), 2_000);
+        assert_eq!(entries[1].overlap_ms(3_000, 6_000, 10_000), 1_000);
+        assert!(entries_between(&conn, 9_000, 12_000).unwrap().is_empty());
+
+        // Running entries count up to now
+        start_entry(&conn, &first.id, 20_000).unwrap();
+        let entries = entries_between(&conn, 0, 100_000).unwrap();
+        assert_eq!(entries[2].overlap_ms(0, 100_000, 25_000), 5_000);
+    }
+}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Size limits enforced before a note is written
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NoteLimits {
+    /// Maximum content size in bytes
+    pub max_content_bytes: usize,
+    /// Maximum number of tags per note
+    pub max_tags: usize,
+}
+
+impl NoteLimits {
+    pub const DEFAULT_MAX_CONTENT_BYTES: usize = 1024 * 1024;
+    pub const DEFAULT_MAX_TAGS: usize = 50;
+
+    /// Check content and tags against these limits
+    pub fn validate(&self, content: &str, tags: &[String]) -> Result<(), NoteValidationError> {
+        if content.len() > self.max_content_bytes {
+            return Err(NoteValidationError::ContentTooLong {
+                size: content.len(),
+                max: self.max_content_bytes,
+            });
+        }
+
+        if tags.len() > self.max_tags {
+            return Err(NoteValidationError::TooManyTags {
+                count: tags.len(),
+                max: self.max_tags,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for NoteLimits {
+    fn default() -> Self {
+        Self {
+            max_content_bytes: Self::DEFAULT_MAX_CONTENT_BYTES,
+            max_tags: Self::DEFAULT_MAX_TAGS,
+        }
+    }
+}
+
+/// A note was rejected because it exceeds [`NoteLimits`]
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum NoteValidationError {
+    #[error("Note content is {size} bytes, which exceeds the limit of {max} bytes")]
+    ContentTooLong { size: usize, max: usize },
+    #[error("Note has {count} tags, which exceeds the limit of {max}")]
+    TooManyTags { count: usize, max: usize },
+}
+
+impl From<NoteValidationError> for rusqlite::Error {
+    fn from(err: NoteValidationError) -> Self {
+        rusqlite::Error::ToSqlConversionFailure(Box::new(err))
+    }
+}
+
+/// Extract a [`NoteValidationError`], if that is what `err` wraps
+pub fn as_validation_error(err: &rusqlite::Error) -> Option<&NoteValidationError> {
+    match err {
+        rusqlite::Error::ToSqlConversionFailure(inner) => inner.downcast_ref(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_limits() {
+        let limits = NoteLimits {
+            max_content_bytes: 5,
+            max_tags: 1,
+        };
+
+        assert_eq!(limits.validate("short", &[]), Ok(()));
+        assert_eq!(
+            limits.validate("too long", &[]),
+            Err(NoteValidationError::ContentTooLong { size: 8, max: 5 })
+        );
+        assert_eq!(
+            limits.validate("ok", &["a".to_string(), "b".to_string()]),
+            Err(NoteValidationError::TooManyTags { count: 2, max: 1 })
+        );
+    }
+}
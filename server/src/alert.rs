@@ -0,0 +1,306 @@
+//! Alerts for whoever runs the server: repeated 5xx responses, failed
+//! migrations and a nearly full data disk, posted to a webhook (Slack,
+//! Discord or a Matrix hookshot) and/or emailed
+
+use std::{
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tracing::{info, warn};
+
+use crate::{
+    digest::{Mailer, SendmailMailer},
+    health,
+};
+
+/// 5xx responses within [`ERROR_WINDOW`] that raise an alert when
+/// `JOT_ALERT_ERROR_THRESHOLD` is not set
+pub const DEFAULT_ERROR_THRESHOLD: usize = 5;
+
+/// Disk usage (percent) that raises an alert when `JOT_ALERT_DISK_PERCENT` is not set
+pub const DEFAULT_DISK_PERCENT: u8 = 90;
+
+/// Window in which server errors are counted
+const ERROR_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// The same kind of alert is sent at most once per this long
+const COOLDOWN: Duration = Duration::from_secs(60 * 60);
+
+/// How often the monitor checks the data disk
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Gives up on a webhook that doesn't answer
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlertKind {
+    ServerErrors,
+    MigrationFailed,
+    DiskNearlyFull,
+}
+
+impl AlertKind {
+    fn subject(self) -> &'static str {
+        match self {
+            AlertKind::ServerErrors => "jot server: repeated server errors",
+            AlertKind::MigrationFailed => "jot server: database migration failed",
+            AlertKind::DiskNearlyFull => "jot server: data disk nearly full",
+        }
+    }
+}
+
+/// Delivers alerts
+pub trait AlertSink: Send + Sync {
+    fn send(&self, subject: &str, body: &str) -> Result<(), String>;
+}
+
+/// Posts `{"text", "content"}` JSON, which Slack and Matrix hookshot
+/// webhooks read as `text` and Discord as `content`
+pub struct WebhookSink {
+    pub url: String,
+}
+
+impl AlertSink for WebhookSink {
+    fn send(&self, subject: &str, body: &str) -> Result<(), String> {
+        let message = format!("{}\n{}", subject, body);
+        let response = reqwest::blocking::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?
+            .post(&self.url)
+            .json(&serde_json::json!({ "text": message, "content": message }))
+            .send()
+            .map_err(|e| format!("Failed to call webhook: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Webhook answered {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+/// Emails alerts to one address
+pub struct EmailSink {
+    pub mailer: Arc<dyn Mailer>,
+    pub to: String,
+}
+
+impl AlertSink for EmailSink {
+    fn send(&self, subject: &str, body: &str) -> Result<(), String> {
+        self.mailer.send(&self.to, subject, body)
+    }
+}
+
+/// Decides when something is worth an alert and sends it to every sink
+pub struct Alerter {
+    sinks: Vec<Arc<dyn AlertSink>>,
+    error_threshold: usize,
+    disk_percent: u8,
+    recent: Mutex<RecentAlerts>,
+}
+
+#[derive(Default)]
+struct RecentAlerts {
+    /// When the server errors of the current window happened
+    errors: VecDeque<Instant>,
+    /// When each kind of alert was last sent
+    sent: HashMap<AlertKind, Instant>,
+}
+
+impl Alerter {
+    pub fn new(sinks: Vec<Arc<dyn AlertSink>>, error_threshold: usize, disk_percent: u8) -> Self {
+        Self {
+            sinks,
+            error_threshold: error_threshold.max(1),
+            disk_percent,
+            recent: Mutex::new(RecentAlerts::default()),
+        }
+    }
+
+    /// Alerts are enabled by setting `JOT_ALERT_WEBHOOK` and/or
+    /// `JOT_ALERT_EMAIL`; returns `None` otherwise
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        let mut sinks: Vec<Arc<dyn AlertSink>> = Vec::new();
+        if let Some(url) = var("JOT_ALERT_WEBHOOK") {
+            sinks.push(Arc::new(WebhookSink { url }));
+        }
+        if let Some(to) = var("JOT_ALERT_EMAIL") {
+            let mailer = SendmailMailer {
+                command: var("JOT_ALERT_SENDMAIL").unwrap_or_else(|| "sendmail".to_string()),
+                from: var("JOT_ALERT_FROM").unwrap_or_else(|| "jot@localhost".to_string()),
+            };
+            sinks.push(Arc::new(EmailSink {
+                mailer: Arc::new(mailer),
+                to,
+            }));
+        }
+        if sinks.is_empty() {
+            return None;
+        }
+
+        let error_threshold = var("JOT_ALERT_ERROR_THRESHOLD")
+            .and_then(|n| n.parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_ERROR_THRESHOLD);
+        let disk_percent = var("JOT_ALERT_DISK_PERCENT")
+            .and_then(|p| p.parse().ok())
+            .filter(|p| (1..=100).contains(p))
+            .unwrap_or(DEFAULT_DISK_PERCENT);
+        Some(Self::new(sinks, error_threshold, disk_percent))
+    }
+
+    /// Count a 5xx response, alerting once enough of them happen close together
+    pub fn record_server_error(&self, detail: &str) {
+        if self.is_error_burst(Instant::now()) {
+            self.alert(
+                AlertKind::ServerErrors,
+                format!(
+                    "{} or more requests failed with a server error in the last {} minutes; the latest was {}.",
+                    self.error_threshold,
+                    ERROR_WINDOW.as_secs() / 60,
+                    detail
+                ),
+            );
+        }
+    }
+
+    /// Send an alert to every sink unless one of the same kind went out recently
+    ///
+    /// Delivery happens on a blocking thread, so a slow webhook or mail
+    /// command never holds up the caller.
+    pub fn alert(&self, kind: AlertKind, body: String) {
+        if !self.should_send(kind, Instant::now()) {
+            return;
+        }
+        info!("Sending alert: {}", kind.subject());
+
+        let sinks = self.sinks.clone();
+        let send = move || {
+            for sink in &sinks {
+                if let Err(e) = sink.send(kind.subject(), &body) {
+                    warn!("Failed to send alert: {}", e);
+                }
+            }
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(send);
+            }
+            Err(_) => send(),
+        }
+    }
+
+    /// Record an error at `now`, returning whether the window now holds the threshold
+    fn is_error_burst(&self, now: Instant) -> bool {
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        recent.errors.push_back(now);
+        while recent
+            .errors
+            .front()
+            .is_some_and(|at| now.duration_since(*at) > ERROR_WINDOW)
+        {
+            recent.errors.pop_front();
+        }
+        recent.errors.len() >= self.error_threshold
+    }
+
+    /// Whether an alert of `kind` may go out at `now`, remembering it if so
+    fn should_send(&self, kind: AlertKind, now: Instant) -> bool {
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        if recent
+            .sent
+            .get(&kind)
+            .is_some_and(|at| now.duration_since(*at) < COOLDOWN)
+        {
+            return false;
+        }
+        recent.sent.insert(kind, now);
+        true
+    }
+}
+
+/// Periodically check the disk holding `data_dir`, alerting when it passes
+/// the configured usage
+pub fn spawn_health_monitor(alerter: Arc<Alerter>, data_dir: PathBuf) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(DISK_CHECK_INTERVAL);
+        loop {
+            ticker.tick().await;
+
+            let dir = data_dir.clone();
+            let usage = tokio::task::spawn_blocking(move || health::disk_usage(&dir))
+                .await
+                .ok()
+                .flatten();
+            let Some(usage) = usage else {
+                continue;
+            };
+            if usage.used_percent() >= alerter.disk_percent {
+                alerter.alert(
+                    AlertKind::DiskNearlyFull,
+                    format!(
+                        "The disk holding {} is {}% full ({} MiB free).",
+                        data_dir.display(),
+                        usage.used_percent(),
+                        usage.available_bytes / (1024 * 1024)
+                    ),
+                );
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingSink(Mutex<Vec<String>>);
+
+    impl AlertSink for RecordingSink {
+        fn send(&self, subject: &str, _body: &str) -> Result<(), String> {
+            self.0.lock().unwrap().push(subject.to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_alerts_on_error_burst_once_per_cooldown() {
+        let sink = Arc::new(RecordingSink::default());
+        let alerter = Alerter::new(vec![sink.clone()], 3, DEFAULT_DISK_PERCENT);
+        let start = Instant::now();
+
+        // Errors older than the window don't count towards the threshold
+        let after = |secs| start + Duration::from_secs(secs);
+        assert!(!alerter.is_error_burst(start));
+        assert!(!alerter.is_error_burst(after(1)));
+        let later = ERROR_WINDOW.as_secs() + 2;
+        assert!(!alerter.is_error_burst(after(later)));
+        assert!(!alerter.is_error_burst(after(later + 1)));
+        assert!(alerter.is_error_burst(after(later + 2)));
+
+        assert!(alerter.should_send(AlertKind::ServerErrors, start));
+        assert!(!alerter.should_send(AlertKind::ServerErrors, start + Duration::from_secs(60)));
+        assert!(alerter.should_send(AlertKind::DiskNearlyFull, start));
+        assert!(alerter.should_send(AlertKind::ServerErrors, start + COOLDOWN));
+
+        // Outside a runtime alerts are sent right away
+        alerter.alert(AlertKind::MigrationFailed, "v18".to_string());
+        alerter.alert(AlertKind::MigrationFailed, "v18".to_string());
+        assert_eq!(
+            *sink.0.lock().unwrap(),
+            vec!["jot server: database migration failed"]
+        );
+    }
+}
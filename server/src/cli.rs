@@ -0,0 +1,328 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use rusqlite::Connection;
+
+use crate::{
+    db::{
+        fsck,
+        migrate::{migrate_all, MigrationOutcome},
+        open_auth_db, remove_user_db, stats, user,
+    },
+    errors::ApplicationError,
+    import::{import_users, parse_users_csv, ImportMode, ImportOutcome},
+    jwt::{generate_password, hash_password, GENERATED_PASSWORD_LEN},
+    model::{
+        stats::ServerStats,
+        user::{Role, User},
+    },
+    state,
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "jot-server", version, about = "Sync server for jot")]
+pub struct ServerArgs {
+    /// Runs the server when omitted
+    #[command(subcommand)]
+    pub command: Option<ServerCommand>,
+
+    /// Migrate every user's notes database before serving, instead of on
+    /// each user's next sync
+    #[arg(long)]
+    pub migrate_all: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ServerCommand {
+    /// Administrative tasks run directly against the data directory
+    #[command(subcommand)]
+    Admin(AdminCommand),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AdminCommand {
+    /// Show user, storage and sync statistics
+    Stats {
+        /// Print the same JSON as `GET /admin/stats`
+        #[arg(long)]
+        json: bool,
+    },
+    /// List accounts with their role and status
+    Users,
+    /// Delete an account and its synced notes
+    Delete {
+        /// Email of the user to delete
+        email: String,
+    },
+    /// Replace a user's password with a generated one and print it
+    ResetPassword {
+        /// Email of the user whose password to reset
+        email: String,
+    },
+    /// Grant or revoke admin access
+    Role {
+        /// Email of the user to change
+        email: String,
+        #[arg(value_enum)]
+        role: RoleArg,
+    },
+    /// Suspend an account (login and sync are refused, data is kept)
+    Suspend {
+        /// Email of the user to suspend
+        email: String,
+    },
+    /// Reactivate a suspended account
+    Reactivate {
+        /// Email of the user to reactivate
+        email: String,
+    },
+    /// Migrate every user's notes database to the latest schema now
+    MigrateAll,
+    /// Cross-check notes databases on disk against the accounts
+    ///
+    /// Reports databases whose user no longer exists and users without a
+    /// database.
+    Fsck {
+        /// Move orphaned databases into `orphaned/` and create the missing ones
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Create accounts in bulk from a CSV file of `email,name` lines
+    ///
+    /// Prints `email<TAB>password` (or `email<TAB>invite link`) per created account.
+    ImportUsers {
+        /// CSV file; a header row starting with `email` is optional
+        file: PathBuf,
+        /// Send invite links under this public server URL instead of generating passwords
+        #[arg(long, value_name = "BASE_URL")]
+        invite: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum RoleArg {
+    User,
+    Admin,
+}
+
+impl From<RoleArg> for Role {
+    fn from(role: RoleArg) -> Self {
+        match role {
+            RoleArg::User => Role::User,
+            RoleArg::Admin => Role::Admin,
+        }
+    }
+}
+
+pub fn admin_cmd(command: AdminCommand, data_dir: &Path) -> Result<(), ApplicationError> {
+    let conn = open_auth_db(&state::auth_db_path(data_dir))
+        .map_err(|e| ApplicationError::Internal(format!("Failed to open auth database: {}", e)))?;
+
+    match command {
+        AdminCommand::Stats { json } => {
+            let stats = stats::collect_stats(&conn, data_dir, chrono::Utc::now().timestamp())
+                .map_err(|e| ApplicationError::Internal(e.to_string()))?;
+
+            if json {
+                let json = serde_json::to_string_pretty(&stats)
+                    .map_err(|e| ApplicationError::Internal(e.to_string()))?;
+                println!("{}", json);
+            } else {
+                print_stats(&stats);
+            }
+        }
+        AdminCommand::Users => {
+            let users =
+                user::list_users(&conn).map_err(|e| ApplicationError::Internal(e.to_string()))?;
+            println!("{:<32} {:<6} {:<9} NAME", "EMAIL", "ROLE", "STATUS");
+            for user in users {
+                println!(
+                    "{:<32} {:<6} {:<9} {}",
+                    user.email,
+                    user.role.as_str(),
+                    if user.is_active {
+                        "active"
+                    } else {
+                        "suspended"
+                    },
+                    user.name
+                );
+            }
+        }
+        AdminCommand::Delete { email } => {
+            let user = find_user(&conn, &email)?;
+            user::delete_user(&conn, &user.id)
+                .map_err(|e| ApplicationError::Internal(e.to_string()))?;
+            remove_user_db(&state::user_db_path(data_dir, &user.id)).map_err(|e| {
+                ApplicationError::Internal(format!("Failed to remove notes database: {}", e))
+            })?;
+            println!("Deleted {}", email);
+        }
+        AdminCommand::ResetPassword { email } => {
+            let user = find_user(&conn, &email)?;
+            let password = generate_password(GENERATED_PASSWORD_LEN);
+            let hash =
+                hash_password(&password).map_err(|e| ApplicationError::Internal(e.to_string()))?;
+            user::set_user_password(&conn, &user.id, &hash)
+                .map_err(|e| ApplicationError::Internal(e.to_string()))?;
+            println!("{}\t{}", email, password);
+        }
+        AdminCommand::Role { email, role } => {
+            let user = find_user(&conn, &email)?;
+            let role = Role::from(role);
+            user::set_user_role(&conn, &user.id, role)
+                .map_err(|e| ApplicationError::Internal(e.to_string()))?;
+            println!("{} is now {}", email, role.as_str());
+        }
+        AdminCommand::Suspend { email } => {
+            let user = find_user(&conn, &email)?;
+            user::set_user_active(&conn, &user.id, false)
+                .map_err(|e| ApplicationError::Internal(e.to_string()))?;
+            println!("Suspended {}", email);
+        }
+        AdminCommand::Reactivate { email } => {
+            let user = find_user(&conn, &email)?;
+            user::set_user_active(&conn, &user.id, true)
+                .map_err(|e| ApplicationError::Internal(e.to_string()))?;
+            println!("Reactivated {}", email);
+        }
+        AdminCommand::MigrateAll => {
+            let failed = migrate_all(data_dir, |done, total, user_id, outcome| match outcome {
+                MigrationOutcome::UpToDate => {
+                    println!("[{}/{}] {}: up to date", done, total, user_id)
+                }
+                MigrationOutcome::Migrated { from } => {
+                    println!("[{}/{}] {}: migrated from v{}", done, total, user_id, from)
+                }
+                MigrationOutcome::Failed(e) => {
+                    eprintln!("[{}/{}] {}: failed: {}", done, total, user_id, e)
+                }
+            })
+            .map_err(|e| {
+                ApplicationError::Internal(format!("Failed to list user databases: {}", e))
+            })?;
+            if failed > 0 {
+                return Err(ApplicationError::Internal(format!(
+                    "{} database(s) could not be migrated",
+                    failed
+                )));
+            }
+        }
+        AdminCommand::Fsck { fix } => {
+            let report = fsck::check(&conn, data_dir)
+                .map_err(|e| ApplicationError::Internal(e.to_string()))?;
+            let now = chrono::Utc::now().timestamp();
+
+            for path in &report.orphaned {
+                println!("Orphaned database: {}", path.display());
+                if fix {
+                    let archived = fsck::archive_orphan(data_dir, path, now).map_err(|e| {
+                        ApplicationError::Internal(format!(
+                            "Failed to archive {}: {}",
+                            path.display(),
+                            e
+                        ))
+                    })?;
+                    println!("  moved to {}", archived.display());
+                }
+            }
+            for user in &report.missing {
+                println!("No database for {} ({})", user.email, user.id);
+                if fix {
+                    fsck::create_missing(data_dir, user)
+                        .map_err(|e| ApplicationError::Internal(e.to_string()))?;
+                    println!("  created");
+                }
+            }
+
+            if report.orphaned.is_empty() && report.missing.is_empty() {
+                println!("Every database belongs to a user and every user has one");
+            } else if !fix {
+                println!(
+                    "Run again with --fix to archive orphaned databases and create missing ones"
+                );
+            }
+        }
+        AdminCommand::ImportUsers { file, invite } => {
+            let input = std::fs::read_to_string(&file).map_err(|e| {
+                ApplicationError::Internal(format!("Failed to read {}: {}", file.display(), e))
+            })?;
+            let rows = parse_users_csv(&input).map_err(ApplicationError::Internal)?;
+            let mode = match invite {
+                Some(base_url) => ImportMode::Invite { base_url },
+                None => ImportMode::Password,
+            };
+
+            let outcomes = import_users(&conn, &rows, &mode, chrono::Utc::now().timestamp())?;
+            let mut created = 0;
+            for (row, outcome) in rows.iter().zip(&outcomes) {
+                match outcome {
+                    ImportOutcome::Password(secret) | ImportOutcome::InviteLink(secret) => {
+                        created += 1;
+                        println!("{}\t{}", row.email, secret);
+                    }
+                    ImportOutcome::Exists => eprintln!("Skipped {}: already exists", row.email),
+                }
+            }
+            eprintln!("Created {} of {} account(s)", created, rows.len());
+        }
+    }
+
+    Ok(())
+}
+
+fn find_user(conn: &Connection, email: &str) -> Result<User, ApplicationError> {
+    user::read_user_by_email(conn, email)
+        .map_err(|e| ApplicationError::Internal(e.to_string()))?
+        .ok_or_else(|| ApplicationError::Internal(format!("No user with email {}", email)))
+}
+
+fn print_stats(stats: &ServerStats) {
+    println!("Users:        {}", stats.user_count);
+    println!("Auth DB size: {}", format_size(stats.auth_db_size));
+
+    if !stats.users.is_empty() {
+        println!();
+        println!(
+            "{:<32} {:>8} {:>10} {:>10}  LAST ACTIVE",
+            "EMAIL", "NOTES", "DB SIZE", "FILES"
+        );
+        for user in &stats.users {
+            let last_active = user
+                .last_active_at
+                .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+                .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "never".to_string());
+            println!(
+                "{:<32} {:>8} {:>10} {:>10}  {}",
+                user.email,
+                user.note_count,
+                format_size(user.db_size),
+                format_size(user.attachment_bytes),
+                last_active
+            );
+        }
+    }
+
+    if !stats.sync_volume.is_empty() {
+        println!();
+        println!(
+            "{:<10} {:>6} {:>9} {:>9}",
+            "DAY", "SYNCS", "RECEIVED", "SENT"
+        );
+        for day in &stats.sync_volume {
+            println!(
+                "{:<10} {:>6} {:>9} {:>9}",
+                day.day, day.syncs, day.notes_received, day.notes_sent
+            );
+        }
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MiB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.1} KiB", b as f64 / 1024.0),
+        b => format!("{} B", b),
+    }
+}
@@ -1,11 +1,12 @@
 use rusqlite::{params, Connection, Result};
 
 use crate::{
+    db::user,
     errors::{AuthError, DbError},
-    jwt::verify_password,
+    jwt::{constant_time_eq, verify_password},
     model::{
         auth::ChallengeResult,
-        user::{User, UserEntity},
+        user::{Role, User, UserEntity},
     },
 };
 
@@ -15,24 +16,28 @@ pub fn check_email_password(
     conn: &Connection,
 ) -> Result<User, AuthError> {
     let mut stmt = conn
-        .prepare("SELECT id, name, email, password FROM users WHERE email = ?")
+        .prepare("SELECT id, name, email, password, role, is_active FROM users WHERE email = ?")
         .map_err(|_| AuthError::DatabaseError)?;
 
-    let user = stmt.query_row(params![email], |row| {
+    let user = stmt.query_row(params![user::normalize_email(email)], |row| {
         Ok(UserEntity {
             id: row.get(0)?,
             name: row.get(1)?,
             email: row.get(2)?,
             password: row.get(3)?,
+            role: Role::parse(&row.get::<_, String>(4)?),
+            is_active: row.get(5)?,
         })
     });
 
     match user {
         Ok(user) => {
-            if verify_password(&password, &user.password) {
-                Ok(user.into())
-            } else {
+            if !verify_password(&password, &user.password) {
                 Err(AuthError::PasswordIncorrect)
+            } else if !user.is_active {
+                Err(AuthError::AccountSuspended)
+            } else {
+                Ok(user.into())
             }
         }
         Err(rusqlite::Error::QueryReturnedNoRows) => Err(AuthError::UserNotFound),
@@ -56,15 +61,67 @@ pub fn create_device_challenge(
     Ok(())
 }
 
+/// A challenge as stored; the token is set once a user approves the device
+struct DeviceChallenge {
+    device_code: String,
+    token: Option<String>,
+    consumed: bool,
+}
+
+/// The unexpired challenge for `device_code`
+///
+/// Every unexpired row is compared in constant time instead of looking the
+/// code up by index, so timing doesn't help guess a pending code. Expired
+/// rows are removed by [`delete_expired_device_challenges`], which keeps the
+/// scan short.
+fn find_device_challenge(
+    device_code: &str,
+    now: i64,
+    conn: &Connection,
+) -> Result<Option<DeviceChallenge>, DbError> {
+    let mut stmt = conn
+        .prepare("SELECT device_code, token, consumed_at FROM device_auth WHERE expires_at > ?")
+        .map_err(|e| DbError::Unknown(e.to_string()))?;
+    let challenges = stmt
+        .query_map(params![now], |row| {
+            Ok(DeviceChallenge {
+                device_code: row.get(0)?,
+                token: row.get(1)?,
+                consumed: row.get::<_, Option<i64>>(2)?.is_some(),
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>>>())
+        .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    let mut found = None;
+    for challenge in challenges {
+        if constant_time_eq(challenge.device_code.as_bytes(), device_code.as_bytes()) {
+            found = Some(challenge);
+        }
+    }
+    Ok(found)
+}
+
+/// Attach the approving user and their token to a pending challenge; false
+/// when the code is unknown, expired, already approved or consumed
 pub fn add_token_to_device_challenge(
     device_code: &str,
-    user_id: String,
+    user_id: &str,
+    token: String,
     conn: &Connection,
 ) -> Result<bool, DbError> {
+    let now = chrono::Utc::now().timestamp();
+    let Some(challenge) = find_device_challenge(device_code, now, conn)? else {
+        return Ok(false);
+    };
+    if challenge.token.is_some() || challenge.consumed {
+        return Ok(false);
+    }
+
     let rows = conn
         .execute(
-            "UPDATE device_auth SET user_id = ? WHERE device_code = ?",
-            params![user_id, device_code],
+            "UPDATE device_auth SET user_id = ?, token = ? WHERE device_code = ? AND token IS NULL AND consumed_at IS NULL",
+            params![user_id, token, challenge.device_code],
         )
         .map_err(|e| DbError::Unknown(e.to_string()))?;
 
@@ -82,26 +139,88 @@ pub fn delete_device_challenge(device_code: String, conn: &Connection) -> Result
     Ok(rows > 0)
 }
 
+/// Poll a challenge; an approved one hands out its token exactly once and is
+/// then consumed, with the stored token cleared
 pub fn get_token_from_device_challenge(
     device_code: String,
     conn: &Connection,
 ) -> Result<ChallengeResult, DbError> {
-    let current_time = chrono::Utc::now().timestamp();
-
-    let mut stmt = conn
-        .prepare("SELECT user_id FROM device_auth WHERE device_code = ? AND expires_at > ?")
-        .map_err(|e| DbError::Unknown(e.to_string()))?;
-
-    let user_id = stmt.query_row(params![device_code, current_time], |row| {
-        row.get::<_, Option<String>>(0)
-    });
+    let now = chrono::Utc::now().timestamp();
 
-    let challenge_result = match user_id {
-        Err(rusqlite::Error::QueryReturnedNoRows) => ChallengeResult::NoChallenge,
-        Ok(None) => ChallengeResult::Pending,
-        Ok(Some(user_id)) => ChallengeResult::Success(user_id),
-        Err(e) => return Err(DbError::Unknown(e.to_string())),
+    let challenge_result = match find_device_challenge(&device_code, now, conn)? {
+        None => ChallengeResult::NoChallenge,
+        Some(challenge) if challenge.consumed => ChallengeResult::Consumed,
+        Some(DeviceChallenge { token: None, .. }) => ChallengeResult::Pending,
+        Some(DeviceChallenge {
+            device_code,
+            token: Some(token),
+            ..
+        }) => {
+            let rows = conn
+                .execute(
+                    "UPDATE device_auth SET consumed_at = ?, token = NULL WHERE device_code = ? AND consumed_at IS NULL",
+                    params![now, device_code],
+                )
+                .map_err(|e| DbError::Unknown(e.to_string()))?;
+            if rows > 0 {
+                ChallengeResult::Success(token)
+            } else {
+                ChallengeResult::Consumed
+            }
+        }
     };
 
     Ok(challenge_result)
 }
+
+/// Remove challenges that expired at or before `now`, consumed or not,
+/// returning how many were removed
+pub fn delete_expired_device_challenges(conn: &Connection, now: i64) -> Result<usize, DbError> {
+    conn.execute(
+        "DELETE FROM device_auth WHERE expires_at <= ?",
+        params![now],
+    )
+    .map_err(|e| DbError::Unknown(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::{testing::temp_auth_db, user::create_user};
+
+    #[test]
+    fn test_device_code_is_single_use() {
+        let (_data_dir, conn) = temp_auth_db();
+        let status = |code: &str| get_token_from_device_challenge(code.to_string(), &conn).unwrap();
+        let user_id = create_user(&conn, "Ada", "ada@example.com", "hash").unwrap();
+        let approve = |code: &str, token: &str| {
+            add_token_to_device_challenge(code, &user_id, token.to_string(), &conn).unwrap()
+        };
+
+        create_device_challenge("device-1".to_string(), "USER-1".to_string(), &conn).unwrap();
+        assert!(matches!(status("device-1"), ChallengeResult::Pending));
+        assert!(matches!(status("device-2"), ChallengeResult::NoChallenge));
+        assert!(matches!(status("device-"), ChallengeResult::NoChallenge));
+
+        assert!(approve("device-1", "token"));
+        // Approving again can't swap in another token
+        assert!(!approve("device-1", "other"));
+        assert!(!approve("device-2", "other"));
+
+        assert!(matches!(status("device-1"), ChallengeResult::Success(ref t) if t == "token"));
+        assert!(matches!(status("device-1"), ChallengeResult::Consumed));
+        assert!(!approve("device-1", "again"));
+
+        // Consumed and pending challenges alike go once expired
+        create_device_challenge("device-3".to_string(), "USER-3".to_string(), &conn).unwrap();
+        let now = chrono::Utc::now().timestamp();
+        assert_eq!(delete_expired_device_challenges(&conn, now).unwrap(), 0);
+        assert_eq!(
+            delete_expired_device_challenges(&conn, now + 24 * 60 * 60).unwrap(),
+            2
+        );
+        assert!(matches!(status("device-1"), ChallengeResult::NoChallenge));
+    }
+}
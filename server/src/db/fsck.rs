@@ -0,0 +1,109 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+
+use crate::{
+    db::{open_user_db, user, user_db_paths},
+    errors::DbError,
+    model::user::User,
+    state::user_db_path,
+};
+
+/// Where `jot-server admin fsck --fix` moves notes databases nobody owns
+pub fn orphaned_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("orphaned")
+}
+
+/// Mismatches between the auth database and the notes databases on disk
+#[derive(Debug, Default)]
+pub struct FsckReport {
+    /// Notes databases whose user no longer exists
+    pub orphaned: Vec<PathBuf>,
+    /// Users without a notes database (normal for accounts that never synced)
+    pub missing: Vec<User>,
+}
+
+/// Cross-check `users/*.db` in `data_dir` against the accounts in `conn`
+pub fn check(conn: &Connection, data_dir: &Path) -> Result<FsckReport, DbError> {
+    let users = user::list_users(conn).map_err(|e| DbError::Unknown(e.to_string()))?;
+    let paths = user_db_paths(data_dir).map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    let orphaned = paths
+        .into_iter()
+        .filter(|path| {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            !users.iter().any(|user| user.id == stem)
+        })
+        .collect();
+    let missing = users
+        .into_iter()
+        .filter(|user| !user_db_path(data_dir, &user.id).exists())
+        .collect();
+    Ok(FsckReport { orphaned, missing })
+}
+
+/// Move an orphaned notes database (and its WAL files) into
+/// [`orphaned_dir`], returning its new path
+///
+/// The name gets the time it was moved, so archiving never overwrites.
+pub fn archive_orphan(data_dir: &Path, path: &Path, now: i64) -> std::io::Result<PathBuf> {
+    let dir = orphaned_dir(data_dir);
+    std::fs::create_dir_all(&dir)?;
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let target = dir.join(format!("{}-{}.db", stem, now));
+    for suffix in ["", "-wal", "-shm"] {
+        let mut from = path.as_os_str().to_owned();
+        from.push(suffix);
+        let mut to = target.as_os_str().to_owned();
+        to.push(suffix);
+        match std::fs::rename(&from, &to) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    Ok(target)
+}
+
+/// Create an empty, migrated notes database for `user`
+pub fn create_missing(data_dir: &Path, user: &User) -> Result<(), DbError> {
+    open_user_db(&user_db_path(data_dir, &user.id)).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::{testing::temp_auth_db, user::create_user};
+
+    #[test]
+    fn test_fsck_finds_and_fixes_mismatches() {
+        let (dir, conn) = temp_auth_db();
+        let data_dir = dir.path();
+        let synced = create_user(&conn, "Ada", "ada@example.com", "x").unwrap();
+        let never_synced = create_user(&conn, "Bob", "bob@example.com", "x").unwrap();
+        open_user_db(&user_db_path(data_dir, &synced)).unwrap();
+        open_user_db(&user_db_path(data_dir, "deleted-user")).unwrap();
+
+        let report = check(&conn, data_dir).unwrap();
+        assert_eq!(
+            report.orphaned,
+            vec![user_db_path(data_dir, "deleted-user")]
+        );
+        let missing: Vec<&str> = report.missing.iter().map(|u| u.id.as_str()).collect();
+        assert_eq!(missing, vec![never_synced.as_str()]);
+
+        let archived = archive_orphan(data_dir, &report.orphaned[0], 1_700_000_000).unwrap();
+        assert_eq!(
+            archived,
+            orphaned_dir(data_dir).join("deleted-user-1700000000.db")
+        );
+        assert!(archived.exists());
+        create_missing(data_dir, &report.missing[0]).unwrap();
+
+        let report = check(&conn, data_dir).unwrap();
+        assert!(report.orphaned.is_empty());
+        assert!(report.missing.is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+use crate::jwt::{generate_token, hash_token};
+
+/// How long an invite link stays valid
+pub const INVITE_TTL_DAYS: i64 = 7;
+
+/// Create an invite for `user_id`, returning the raw token for the link
+pub fn create_invite(conn: &Connection, user_id: &str, now: i64) -> Result<String> {
+    let token = generate_token();
+    conn.execute(
+        "INSERT INTO invites (token_hash, user_id, expires_at, created_at) VALUES (?, ?, ?, ?)",
+        params![
+            hash_token(&token),
+            user_id,
+            now + INVITE_TTL_DAYS * 24 * 60 * 60,
+            now
+        ],
+    )?;
+    Ok(token)
+}
+
+/// The user an unexpired invite belongs to
+pub fn find_invite(conn: &Connection, token: &str, now: i64) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT user_id FROM invites WHERE token_hash = ? AND expires_at > ?",
+        params![hash_token(token), now],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Set the invited user's password and consume the invite, returning the
+/// user ID, or `None` when the invite is unknown or expired
+pub fn redeem_invite(
+    conn: &Connection,
+    token: &str,
+    password_hash: &str,
+    now: i64,
+) -> Result<Option<String>> {
+    let Some(user_id) = find_invite(conn, token, now)? else {
+        return Ok(None);
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE users SET password = ? WHERE id = ?",
+        params![password_hash, user_id],
+    )?;
+    tx.execute("DELETE FROM invites WHERE user_id = ?", params![user_id])?;
+    tx.commit()?;
+
+    Ok(Some(user_id))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::{
+        testing::temp_auth_db,
+        user::{create_user, read_user_by_id, NO_PASSWORD},
+    };
+
+    #[test]
+    fn test_invite_is_single_use_and_expires() {
+        let (_data_dir, conn) = temp_auth_db();
+        let user_id = create_user(&conn, "Ada", "ada@example.com", NO_PASSWORD).unwrap();
+
+        let now = 1_700_000_000;
+        let token = create_invite(&conn, &user_id, now).unwrap();
+        let expired = now + INVITE_TTL_DAYS * 24 * 60 * 60;
+        assert_eq!(find_invite(&conn, &token, expired).unwrap(), None);
+        assert_eq!(find_invite(&conn, "bogus", now).unwrap(), None);
+
+        assert_eq!(
+            redeem_invite(&conn, &token, "hash", now + 60).unwrap(),
+            Some(user_id.clone())
+        );
+        assert_eq!(
+            redeem_invite(&conn, &token, "other", now + 60).unwrap(),
+            None
+        );
+
+        let password: String = conn
+            .query_row(
+                "SELECT password FROM users WHERE id = ?",
+                params![user_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(password, "hash");
+        assert!(read_user_by_id(&conn, &user_id).unwrap().is_some());
+    }
+}
@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use crate::{
+    db::{open_user_db, user_db_paths},
+    errors::DbError,
+};
+
+/// What migrating one user's notes database did
+#[derive(Debug, PartialEq)]
+pub enum MigrationOutcome {
+    /// Already at the latest schema
+    UpToDate,
+    /// Brought from schema version `from` to the latest one
+    Migrated {
+        from: i32,
+    },
+    Failed(String),
+}
+
+/// Migrate every user's notes database, passing each one's position (from
+/// 1), the total, its user ID and the outcome to `report` as it finishes
+///
+/// Returns how many failed.
+pub fn migrate_all(
+    data_dir: &Path,
+    mut report: impl FnMut(usize, usize, &str, &MigrationOutcome),
+) -> std::io::Result<usize> {
+    let paths = user_db_paths(data_dir)?;
+    let mut failed = 0;
+    for (i, path) in paths.iter().enumerate() {
+        let outcome = migrate_user_db(path);
+        if matches!(outcome, MigrationOutcome::Failed(_)) {
+            failed += 1;
+        }
+        let user_id = path.file_stem().unwrap_or_default().to_string_lossy();
+        report(i + 1, paths.len(), &user_id, &outcome);
+    }
+    Ok(failed)
+}
+
+/// Bring a user's notes database to the latest schema, the way the first sync
+/// after an upgrade would (including the pre-migration backup)
+pub fn migrate_user_db(path: &Path) -> MigrationOutcome {
+    match pending_from(path) {
+        Ok(None) => MigrationOutcome::UpToDate,
+        Ok(Some(from)) => match open_user_db(path) {
+            Ok(_) => MigrationOutcome::Migrated { from },
+            Err(e) => MigrationOutcome::Failed(e.to_string()),
+        },
+        Err(e) => MigrationOutcome::Failed(e.to_string()),
+    }
+}
+
+/// Schema version of a database that has migrations pending
+fn pending_from(path: &Path) -> Result<Option<i32>, DbError> {
+    let db_err = |e: rusqlite::Error| match jot_core::schema::as_newer_schema_version(&e) {
+        Some(newer) => DbError::NewerSchema(newer.to_string()),
+        None => DbError::Unknown(e.to_string()),
+    };
+    let conn = jot_core::open_db_unmigrated(path).map_err(db_err)?;
+    let version = jot_core::schema::get_schema_version(&conn).map_err(db_err)?;
+    let pending = jot_core::schema::migrate_dry_run(&conn).map_err(db_err)?;
+    Ok((!pending.is_empty()).then_some(version))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn test_migrate_user_dbs() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path();
+        let users_dir = data_dir.join("users");
+        std::fs::create_dir_all(&users_dir).unwrap();
+
+        jot_core::open_db(&users_dir.join("current.db")).unwrap();
+        rusqlite::Connection::open(users_dir.join("fresh.db"))
+            .unwrap()
+            .execute_batch("PRAGMA user_version = 0;")
+            .unwrap();
+        std::fs::write(users_dir.join("broken.db"), b"not a database").unwrap();
+        std::fs::write(users_dir.join("current.db.pre-v2.bak"), b"").unwrap();
+
+        let paths = user_db_paths(data_dir).unwrap();
+        let names: Vec<_> = paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["broken.db", "current.db", "fresh.db"]);
+
+        assert!(matches!(
+            migrate_user_db(&paths[0]),
+            MigrationOutcome::Failed(_)
+        ));
+        assert_eq!(migrate_user_db(&paths[1]), MigrationOutcome::UpToDate);
+        assert_eq!(
+            migrate_user_db(&paths[2]),
+            MigrationOutcome::Migrated { from: 0 }
+        );
+        assert_eq!(migrate_user_db(&paths[2]), MigrationOutcome::UpToDate);
+    }
+}
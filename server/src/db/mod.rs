@@ -1,8 +1,17 @@
 use rusqlite::Connection;
-use std::path::Path;
-use tracing::info;
+use std::path::{Path, PathBuf};
+use tracing::{error, info};
+
+use crate::errors::DbError;
 
 pub mod auth;
+pub mod fsck;
+pub mod invite;
+pub mod migrate;
+pub mod preferences;
+pub mod snapshot;
+pub mod stats;
+pub mod user;
 
 /// Auth database schema
 const AUTH_SCHEMA: &str = r#"
@@ -25,6 +34,111 @@ CREATE TABLE IF NOT EXISTS device_auth (
 PRAGMA user_version = 1;
 "#;
 
+/// Per-user settings, currently the digest email schedule
+const PREFERENCES_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS user_preferences (
+    user_id TEXT PRIMARY KEY NOT NULL,
+    digest TEXT NOT NULL DEFAULT 'off',
+    last_digest_at INTEGER,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+PRAGMA user_version = 2;
+"#;
+
+/// One row per sync request, for admin statistics
+const SYNC_LOG_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS sync_log (
+    user_id TEXT NOT NULL,
+    synced_at INTEGER NOT NULL,
+    notes_received INTEGER NOT NULL,
+    notes_sent INTEGER NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_sync_log_user ON sync_log (user_id, synced_at);
+
+PRAGMA user_version = 3;
+"#;
+
+/// Admin access is granted per user instead of out of band
+const ROLES_SCHEMA: &str = r#"
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'user';
+
+PRAGMA user_version = 4;
+"#;
+
+/// Suspended accounts keep their data but can't log in or sync
+const SUSPENSION_SCHEMA: &str = r#"
+ALTER TABLE users ADD COLUMN is_active INTEGER NOT NULL DEFAULT 1;
+
+PRAGMA user_version = 5;
+"#;
+
+/// One-time links that let operator-created accounts choose a password
+const INVITES_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS invites (
+    token_hash TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL,
+    expires_at INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+PRAGMA user_version = 6;
+"#;
+
+/// Per-user retention applied by the maintenance job
+const RETENTION_SCHEMA: &str = r#"
+ALTER TABLE user_preferences ADD COLUMN purge_tombstones_after_days INTEGER;
+ALTER TABLE user_preferences ADD COLUMN expire_notes_after_days INTEGER;
+
+PRAGMA user_version = 7;
+"#;
+
+/// Device codes are single-use: the token is handed out once, then the
+/// challenge is marked consumed until the cleanup job removes it. The token
+/// gets its own column; `user_id` references the approving user.
+const DEVICE_AUTH_CONSUMED_SCHEMA: &str = r#"
+ALTER TABLE device_auth ADD COLUMN token TEXT;
+ALTER TABLE device_auth ADD COLUMN consumed_at INTEGER;
+
+PRAGMA user_version = 8;
+"#;
+
+/// Emails are unique regardless of case and stored lowercased. SQLite can't
+/// change a column's collation, so the table is rebuilt; foreign keys aren't
+/// enforced on the auth database, so the referencing rows are kept.
+const EMAIL_NOCASE_SCHEMA: &str = r#"
+BEGIN;
+
+CREATE TABLE users_nocase (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    password TEXT NOT NULL,
+    role TEXT NOT NULL DEFAULT 'user',
+    is_active INTEGER NOT NULL DEFAULT 1
+);
+
+INSERT INTO users_nocase (id, name, email, password, role, is_active)
+    SELECT id, name, lower(trim(email)), password, role, is_active FROM users;
+
+DROP TABLE users;
+ALTER TABLE users_nocase RENAME TO users;
+
+PRAGMA user_version = 9;
+
+COMMIT;
+"#;
+
+/// Retention archives old notes instead of deleting them
+const RETENTION_ARCHIVE_SCHEMA: &str = r#"
+ALTER TABLE user_preferences RENAME COLUMN expire_notes_after_days TO archive_notes_after_days;
+
+PRAGMA user_version = 10;
+"#;
+
 /// Open or create auth database
 pub fn open_auth_db(path: &Path) -> Result<Connection, rusqlite::Error> {
     info!("Setting up auth database at {:?}", path);
@@ -38,6 +152,207 @@ pub fn open_auth_db(path: &Path) -> Result<Connection, rusqlite::Error> {
         conn.execute_batch(AUTH_SCHEMA)?;
     }
 
+    if version < 2 {
+        info!("Adding user preferences to auth database");
+        conn.execute_batch(PREFERENCES_SCHEMA)?;
+    }
+
+    if version < 3 {
+        info!("Adding sync log to auth database");
+        conn.execute_batch(SYNC_LOG_SCHEMA)?;
+    }
+
+    if version < 4 {
+        info!("Adding user roles to auth database");
+        conn.execute_batch(ROLES_SCHEMA)?;
+    }
+
+    if version < 5 {
+        info!("Adding account suspension to auth database");
+        conn.execute_batch(SUSPENSION_SCHEMA)?;
+    }
+
+    if version < 6 {
+        info!("Adding invites to auth database");
+        conn.execute_batch(INVITES_SCHEMA)?;
+    }
+
+    if version < 7 {
+        info!("Adding retention policies to auth database");
+        conn.execute_batch(RETENTION_SCHEMA)?;
+    }
+
+    if version < 8 {
+        info!("Adding single-use device codes to auth database");
+        conn.execute_batch(DEVICE_AUTH_CONSUMED_SCHEMA)?;
+    }
+
+    if version < 9 {
+        info!("Making emails case-insensitive in auth database");
+        refuse_case_variant_emails(&conn)?;
+        conn.execute_batch(EMAIL_NOCASE_SCHEMA)?;
+    }
+
+    if version < 10 {
+        info!("Making retention archive old notes in auth database");
+        conn.execute_batch(RETENTION_ARCHIVE_SCHEMA)?;
+    }
+
     info!("Auth database ready");
     Ok(conn)
 }
+
+/// Fail the email migration while accounts differ only by the case of their
+/// email, so the operator decides which one to keep
+fn refuse_case_variant_emails(conn: &Connection) -> Result<(), rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT group_concat(email, ', ') FROM users
+         GROUP BY lower(trim(email)) HAVING COUNT(*) > 1",
+    )?;
+    let variants = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    if variants.is_empty() {
+        return Ok(());
+    }
+    Err(rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE),
+        Some(format!(
+            "Emails must be unique regardless of case, but these accounts differ only by case: {}. Delete or rename all but one of each before upgrading",
+            variants.join("; ")
+        )),
+    ))
+}
+
+/// Open (and migrate) a user's notes database
+pub fn open_user_db(path: &Path) -> Result<Connection, DbError> {
+    jot_core::open_db(path).map_err(|e| {
+        error!("Failed to open user database at {:?}: {}", path, e);
+        match jot_core::schema::as_newer_schema_version(&e) {
+            Some(newer) => DbError::NewerSchema(newer.to_string()),
+            None => DbError::Unknown(e.to_string()),
+        }
+    })
+}
+
+/// Delete a user's notes database along with its WAL files
+pub fn remove_user_db(path: &Path) -> std::io::Result<()> {
+    for suffix in ["", "-wal", "-shm"] {
+        let mut file = path.as_os_str().to_owned();
+        file.push(suffix);
+        match std::fs::remove_file(&file) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Notes databases in `data_dir`'s users folder, sorted by file name
+pub fn user_db_paths(data_dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let users_dir = data_dir.join("users");
+    if !users_dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(users_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "db"))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::state::auth_db_path;
+
+    /// An auth database as it was before emails became case-insensitive
+    fn version_8_auth_db(path: &Path, emails: &[&str]) {
+        let conn = Connection::open(path).unwrap();
+        for schema in [
+            AUTH_SCHEMA,
+            PREFERENCES_SCHEMA,
+            SYNC_LOG_SCHEMA,
+            ROLES_SCHEMA,
+            SUSPENSION_SCHEMA,
+            INVITES_SCHEMA,
+            RETENTION_SCHEMA,
+            DEVICE_AUTH_CONSUMED_SCHEMA,
+        ] {
+            conn.execute_batch(schema).unwrap();
+        }
+        for (i, email) in emails.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO users (id, name, email, password, role) VALUES (?, 'Ada', ?, 'x', 'admin')",
+                rusqlite::params![format!("u{}", i), email],
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_email_migration_lowercases_and_enforces_nocase() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = auth_db_path(dir.path());
+        version_8_auth_db(&path, &[" Ada@Example.com", "bob@example.com"]);
+
+        let conn = open_auth_db(&path).unwrap();
+        let (email, role): (String, String) = conn
+            .query_row("SELECT email, role FROM users WHERE id = 'u0'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(
+            (email.as_str(), role.as_str()),
+            ("ada@example.com", "admin")
+        );
+        assert!(conn
+            .execute(
+                "INSERT INTO users (id, name, email, password) VALUES ('u9', 'Eve', 'ADA@example.com', 'x')",
+                [],
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_email_migration_refuses_case_variants() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = auth_db_path(dir.path());
+        version_8_auth_db(&path, &["ada@example.com", "ADA@example.com"]);
+
+        let err = open_auth_db(&path).unwrap_err().to_string();
+        assert!(
+            err.contains("ada@example.com") && err.contains("ADA@example.com"),
+            "{}",
+            err
+        );
+        let conn = Connection::open(&path).unwrap();
+        let version: i32 = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, 8);
+    }
+}
+
+#[cfg(test)]
+pub mod testing {
+    #![allow(clippy::unwrap_used)]
+
+    use rusqlite::Connection;
+    use tempfile::TempDir;
+
+    use super::open_auth_db;
+    use crate::state::auth_db_path;
+
+    /// Auth database in a fresh data dir with an empty users folder. The dir
+    /// is removed when the `TempDir` drops, even if the test panics.
+    pub fn temp_auth_db() -> (TempDir, Connection) {
+        let data_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(data_dir.path().join("users")).unwrap();
+        let conn = open_auth_db(&auth_db_path(data_dir.path())).unwrap();
+        (data_dir, conn)
+    }
+}
@@ -0,0 +1,130 @@
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{
+    errors::DbError,
+    model::{
+        preferences::{DigestFrequency, RetentionPolicy, UserPreferences},
+        user::{Role, User},
+    },
+};
+
+/// A user whose digest is enabled, with the time the last one went out
+#[derive(Debug)]
+pub struct DigestSubscription {
+    pub user: User,
+    pub frequency: DigestFrequency,
+    /// Unix timestamp in seconds
+    pub last_digest_at: Option<i64>,
+}
+
+pub fn get_preferences(conn: &Connection, user_id: &str) -> Result<UserPreferences, DbError> {
+    let preferences = conn
+        .query_row(
+            "SELECT digest, purge_tombstones_after_days, archive_notes_after_days
+             FROM user_preferences WHERE user_id = ?",
+            params![user_id],
+            |row| {
+                Ok(UserPreferences {
+                    digest: DigestFrequency::parse(&row.get::<_, String>(0)?),
+                    retention: RetentionPolicy {
+                        purge_tombstones_after_days: row.get(1)?,
+                        archive_notes_after_days: row.get(2)?,
+                    },
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    Ok(preferences.unwrap_or_default())
+}
+
+pub fn set_preferences(
+    conn: &Connection,
+    user_id: &str,
+    preferences: &UserPreferences,
+) -> Result<(), DbError> {
+    conn.execute(
+        "INSERT INTO user_preferences (user_id, digest, purge_tombstones_after_days, archive_notes_after_days)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(user_id) DO UPDATE SET digest = excluded.digest,
+             purge_tombstones_after_days = excluded.purge_tombstones_after_days,
+             archive_notes_after_days = excluded.archive_notes_after_days",
+        params![
+            user_id,
+            preferences.digest.as_str(),
+            preferences.retention.purge_tombstones_after_days,
+            preferences.retention.archive_notes_after_days
+        ],
+    )
+    .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    Ok(())
+}
+
+/// All users with a digest schedule other than "off"
+pub fn digest_subscriptions(conn: &Connection) -> Result<Vec<DigestSubscription>, DbError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT u.id, u.name, u.email, u.role, p.digest, p.last_digest_at
+             FROM user_preferences p JOIN users u ON u.id = p.user_id
+             WHERE p.digest != 'off' AND u.is_active = 1",
+        )
+        .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    let subscriptions = stmt
+        .query_map([], |row| {
+            Ok(DigestSubscription {
+                user: User {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    email: row.get(2)?,
+                    role: Role::parse(&row.get::<_, String>(3)?),
+                    is_active: true,
+                },
+                frequency: DigestFrequency::parse(&row.get::<_, String>(4)?),
+                last_digest_at: row.get(5)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    Ok(subscriptions)
+}
+
+pub fn mark_digest_sent(conn: &Connection, user_id: &str, sent_at: i64) -> Result<(), DbError> {
+    conn.execute(
+        "UPDATE user_preferences SET last_digest_at = ? WHERE user_id = ?",
+        params![sent_at, user_id],
+    )
+    .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Active users with a retention policy, by user ID
+pub fn retention_policies(conn: &Connection) -> Result<Vec<(String, RetentionPolicy)>, DbError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT p.user_id, p.purge_tombstones_after_days, p.archive_notes_after_days
+             FROM user_preferences p JOIN users u ON u.id = p.user_id
+             WHERE u.is_active = 1
+               AND (p.purge_tombstones_after_days IS NOT NULL OR p.archive_notes_after_days IS NOT NULL)",
+        )
+        .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    let policies = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                RetentionPolicy {
+                    purge_tombstones_after_days: row.get(1)?,
+                    archive_notes_after_days: row.get(2)?,
+                },
+            ))
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    Ok(policies)
+}
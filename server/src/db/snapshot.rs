@@ -0,0 +1,80 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+};
+
+use flate2::{write::GzEncoder, Compression};
+use rusqlite::Connection;
+
+use crate::errors::DbError;
+
+/// A consistent copy of a user's notes database in `scratch`
+///
+/// The copy is taken with the SQLite backup API and switched out of WAL mode
+/// so it is a single self-contained file. `scratch` is removed if that fails.
+pub fn backup_user_db(conn: &Connection, scratch: &Path) -> Result<(), DbError> {
+    let result = write_backup(conn, scratch);
+    if result.is_err() {
+        let _ = std::fs::remove_file(scratch);
+    }
+    result
+}
+
+fn write_backup(conn: &Connection, scratch: &Path) -> Result<(), DbError> {
+    let db_err = |e: rusqlite::Error| DbError::Unknown(e.to_string());
+
+    jot_core::backup_db(conn, scratch).map_err(db_err)?;
+    let copy = Connection::open(scratch).map_err(db_err)?;
+    copy.pragma_update(None, "journal_mode", "DELETE")
+        .map_err(db_err)
+}
+
+/// Gzip the copy in `scratch` into `out` a chunk at a time, then remove it
+///
+/// Blocking; run it off the async workers.
+pub fn compress_snapshot(scratch: &Path, out: impl Write) -> io::Result<()> {
+    let result = File::open(scratch).and_then(|mut copy| {
+        let mut encoder = GzEncoder::new(out, Compression::default());
+        io::copy(&mut copy, &mut encoder)?;
+        encoder.finish()?.flush()
+    });
+    let _ = std::fs::remove_file(scratch);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    #[test]
+    fn test_snapshot_round_trips_notes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let conn = jot_core::open_db(&dir.join("u1.db")).unwrap();
+        jot_core::create_note(&conn, "hello", vec!["a".to_string()], None).unwrap();
+
+        let scratch = dir.join("u1.snapshot");
+        backup_user_db(&conn, &scratch).unwrap();
+        let mut gz = Vec::new();
+        compress_snapshot(&scratch, &mut gz).unwrap();
+        assert!(!scratch.exists());
+
+        let mut bytes = Vec::new();
+        GzDecoder::new(&gz[..]).read_to_end(&mut bytes).unwrap();
+        let restored = dir.join("restored.db");
+        std::fs::write(&restored, bytes).unwrap();
+        let copy = jot_core::open_db(&restored).unwrap();
+        let notes = jot_core::get_notes_since(&copy, 0).unwrap();
+        drop(copy);
+
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].content, "hello");
+    }
+}
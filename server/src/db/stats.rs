@@ -0,0 +1,211 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::{
+    errors::DbError,
+    model::stats::{ServerStats, SyncVolume, UserStats, UserUsage},
+    state::{auth_db_path, user_db_path},
+};
+
+/// Days of sync history included in [`ServerStats::sync_volume`]
+const SYNC_VOLUME_DAYS: i64 = 30;
+
+pub fn record_sync(
+    conn: &Connection,
+    user_id: &str,
+    synced_at: i64,
+    notes_received: usize,
+    notes_sent: usize,
+) -> Result<(), DbError> {
+    conn.execute(
+        "INSERT INTO sync_log (user_id, synced_at, notes_received, notes_sent) VALUES (?, ?, ?, ?)",
+        params![user_id, synced_at, notes_received, notes_sent],
+    )
+    .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Gather usage statistics from the auth database and every user's notes database
+pub fn collect_stats(conn: &Connection, data_dir: &Path, now: i64) -> Result<ServerStats, DbError> {
+    add_user_usage(read_auth_stats(conn, now)?, data_dir)
+}
+
+/// The part of [`ServerStats`] kept in the auth database
+pub struct AuthStats {
+    users: Vec<(String, String, Option<i64>)>,
+    sync_volume: Vec<SyncVolume>,
+}
+
+/// Read the accounts and sync history; the auth database isn't needed after this
+pub fn read_auth_stats(conn: &Connection, now: i64) -> Result<AuthStats, DbError> {
+    Ok(AuthStats {
+        users: read_users(conn)?,
+        sync_volume: sync_volume(conn, now - SYNC_VOLUME_DAYS * 24 * 60 * 60)?,
+    })
+}
+
+/// Complete `auth` with each user's notes database
+pub fn add_user_usage(auth: AuthStats, data_dir: &Path) -> Result<ServerStats, DbError> {
+    let users = auth
+        .users
+        .into_iter()
+        .map(|(id, email, last_active_at)| {
+            let usage = user_usage(&user_db_path(data_dir, &id), None)?;
+            Ok(UserStats {
+                note_count: usage.note_count,
+                db_size: usage.db_size,
+                attachment_bytes: usage.attachment_bytes,
+                id,
+                email,
+                last_active_at,
+            })
+        })
+        .collect::<Result<Vec<_>, DbError>>()?;
+
+    Ok(ServerStats {
+        user_count: users.len(),
+        auth_db_size: file_size(&auth_db_path(data_dir)),
+        users,
+        sync_volume: auth.sync_volume,
+    })
+}
+
+/// Storage used by the account whose notes database is at `path`, read
+/// without migrating it
+pub fn user_usage(path: &Path, quota_bytes: Option<u64>) -> Result<UserUsage, DbError> {
+    let (note_count, attachment_bytes) = count_notes(path)?;
+    let db_size = file_size(path);
+    Ok(UserUsage {
+        note_count,
+        db_size,
+        attachment_bytes,
+        used_bytes: db_size + attachment_bytes,
+        quota_bytes,
+    })
+}
+
+fn read_users(conn: &Connection) -> Result<Vec<(String, String, Option<i64>)>, DbError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT u.id, u.email, (SELECT MAX(s.synced_at) FROM sync_log s WHERE s.user_id = u.id)
+             FROM users u ORDER BY u.email",
+        )
+        .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    let users = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    Ok(users)
+}
+
+fn sync_volume(conn: &Connection, since: i64) -> Result<Vec<SyncVolume>, DbError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT date(synced_at, 'unixepoch') AS day, COUNT(*), SUM(notes_received), SUM(notes_sent)
+             FROM sync_log WHERE synced_at >= ? GROUP BY day ORDER BY day",
+        )
+        .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    let volume = stmt
+        .query_map(params![since], |row| {
+            Ok(SyncVolume {
+                day: row.get(0)?,
+                syncs: row.get(1)?,
+                notes_received: row.get(2)?,
+                notes_sent: row.get(3)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    Ok(volume)
+}
+
+/// Live notes and attached bytes in a user's database
+fn count_notes(path: &Path) -> Result<(i64, u64), DbError> {
+    if !path.exists() {
+        return Ok((0, 0));
+    }
+
+    let conn = jot_core::open_db_unmigrated(path).map_err(|e| DbError::Unknown(e.to_string()))?;
+    let notes = conn
+        .query_row(
+            "SELECT COUNT(*) FROM notes WHERE deleted_at IS NULL",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    // Databases from before attachments existed have no table for them
+    let has_attachments: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'attachments')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| DbError::Unknown(e.to_string()))?;
+    if !has_attachments {
+        return Ok((notes, 0));
+    }
+    let bytes: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(size), 0) FROM attachments",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| DbError::Unknown(e.to_string()))?;
+
+    Ok((notes, bytes.max(0) as u64))
+}
+
+fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::testing::temp_auth_db;
+
+    #[test]
+    fn test_collect_stats() {
+        let (dir, conn) = temp_auth_db();
+        let data_dir = dir.path();
+        conn.execute(
+            "INSERT INTO users (id, name, email, password) VALUES ('u1', 'Ada', 'ada@example.com', 'x')",
+            [],
+        )
+        .unwrap();
+
+        let notes = jot_core::open_db(&user_db_path(data_dir, "u1")).unwrap();
+        let note = jot_core::create_note(&notes, "hello", vec![], None).unwrap();
+        jot_core::attach_file(&notes, &note.id, "a.txt", b"12345").unwrap();
+
+        let day = 86_400 * 20_000; // 2024-10-04 00:00 UTC
+        record_sync(&conn, "u1", day + 60, 1, 0).unwrap();
+        record_sync(&conn, "u1", day + 120, 2, 3).unwrap();
+
+        let stats = collect_stats(&conn, data_dir, day + 3600).unwrap();
+        let usage = user_usage(&user_db_path(data_dir, "u1"), Some(1 << 20)).unwrap();
+
+        assert_eq!(stats.user_count, 1);
+        assert_eq!(stats.users[0].note_count, 1);
+        assert!(stats.users[0].db_size > 0);
+        assert_eq!(stats.users[0].attachment_bytes, 5);
+        assert_eq!(usage.note_count, 1);
+        assert_eq!(usage.used_bytes, usage.db_size + 5);
+        assert_eq!(usage.quota_bytes, Some(1 << 20));
+        assert_eq!(stats.users[0].last_active_at, Some(day + 120));
+        assert_eq!(stats.sync_volume.len(), 1);
+        assert_eq!(stats.sync_volume[0].day, "2024-10-04");
+        assert_eq!(stats.sync_volume[0].syncs, 2);
+        assert_eq!(stats.sync_volume[0].notes_received, 3);
+        assert_eq!(stats.sync_volume[0].notes_sent, 3);
+    }
+}
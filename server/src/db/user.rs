@@ -1,27 +1,292 @@
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, OptionalExtension, Result};
 
-use crate::model::user::{User, UserEntity};
+use crate::model::user::{Role, User, UserEntity};
 
-pub fn read_user_by_id(
+pub fn read_user_by_id(conn: &Connection, user_id: &str) -> Result<Option<User>> {
+    conn.query_row(
+        "SELECT id, name, email, password, role, is_active FROM users WHERE id = ?",
+        params![user_id],
+        read_user_entity,
+    )
+    .optional()
+    .map(|entity| entity.map(Into::into))
+}
+
+pub fn read_user_by_email(conn: &Connection, email: &str) -> Result<Option<User>> {
+    conn.query_row(
+        "SELECT id, name, email, password, role, is_active FROM users WHERE email = ?",
+        params![normalize_email(email)],
+        read_user_entity,
+    )
+    .optional()
+    .map(|entity| entity.map(Into::into))
+}
+
+/// Emails are stored and looked up trimmed and lowercased, so case variants
+/// of an address are the same account
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// Placeholder password hash for invited accounts; it never verifies, so
+/// the account can't log in until the invite is redeemed
+pub const NO_PASSWORD: &str = "!";
+
+/// Create an account, returning its ID
+pub fn create_user(
     conn: &Connection,
-    user_id: &str,
-) -> Result<Option<User>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, name, email, password FROM users WHERE id = ?"
+    name: &str,
+    email: &str,
+    password_hash: &str,
+) -> Result<String> {
+    let id = ulid::Ulid::new().to_string();
+    conn.execute(
+        "INSERT INTO users (id, name, email, password) VALUES (?, ?, ?, ?)",
+        params![id, name, normalize_email(email), password_hash],
+    )?;
+    Ok(id)
+}
+
+/// Change a user's role, returning whether the user exists
+pub fn set_user_role(conn: &Connection, user_id: &str, role: Role) -> Result<bool> {
+    let updated = conn.execute(
+        "UPDATE users SET role = ? WHERE id = ?",
+        params![role.as_str(), user_id],
+    )?;
+    Ok(updated > 0)
+}
+
+/// Suspend (`false`) or reactivate (`true`) an account, returning whether the user exists
+pub fn set_user_active(conn: &Connection, user_id: &str, is_active: bool) -> Result<bool> {
+    let updated = conn.execute(
+        "UPDATE users SET is_active = ? WHERE id = ?",
+        params![is_active, user_id],
+    )?;
+    Ok(updated > 0)
+}
+
+/// All accounts, ordered by email
+pub fn list_users(conn: &Connection) -> Result<Vec<User>> {
+    let mut stmt = conn
+        .prepare("SELECT id, name, email, password, role, is_active FROM users ORDER BY email")?;
+    let users = stmt
+        .query_map([], read_user_entity)?
+        .map(|entity| entity.map(Into::into))
+        .collect();
+    users
+}
+
+/// Replace a user's password hash, returning whether the user exists
+pub fn set_user_password(conn: &Connection, user_id: &str, password_hash: &str) -> Result<bool> {
+    let updated = conn.execute(
+        "UPDATE users SET password = ? WHERE id = ?",
+        params![password_hash, user_id],
+    )?;
+    Ok(updated > 0)
+}
+
+/// Delete an account and everything the auth database keeps about it,
+/// returning whether the user existed
+///
+/// The user's notes database is a separate file the caller removes.
+pub fn delete_user(conn: &Connection, user_id: &str) -> Result<bool> {
+    let tx = conn.unchecked_transaction()?;
+    for table in ["device_auth", "user_preferences", "sync_log", "invites"] {
+        tx.execute(
+            &format!("DELETE FROM {} WHERE user_id = ?", table),
+            params![user_id],
+        )?;
+    }
+    let deleted = tx.execute("DELETE FROM users WHERE id = ?", params![user_id])?;
+    tx.commit()?;
+    Ok(deleted > 0)
+}
+
+/// What [`bootstrap_admin`] did
+#[derive(Debug, PartialEq, Eq)]
+pub enum AdminBootstrap {
+    Promoted,
+    /// There already is an admin, so nobody was promoted
+    AdminExists,
+    NoAccount,
+    /// Several accounts differ from the email only by case
+    Ambiguous(usize),
+}
+
+/// Make the account registered with `email` an admin (`JOT_ADMIN_EMAIL`)
+/// while the server has no admin yet
+///
+/// Once there is an admin this does nothing, so a later demotion sticks.
+/// Only an exact match of the normalized email is promoted, and only when no
+/// other account differs from it by case.
+pub fn bootstrap_admin(conn: &Connection, email: &str) -> Result<AdminBootstrap> {
+    let admins: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM users WHERE role = ?",
+        params![Role::Admin.as_str()],
+        |row| row.get(0),
     )?;
+    if admins > 0 {
+        return Ok(AdminBootstrap::AdminExists);
+    }
+
+    let email = normalize_email(email);
+    let variants: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM users WHERE email = ? COLLATE NOCASE",
+        params![email],
+        |row| row.get(0),
+    )?;
+    if variants > 1 {
+        return Ok(AdminBootstrap::Ambiguous(variants as usize));
+    }
+
+    let updated = conn.execute(
+        "UPDATE users SET role = ? WHERE email = ?",
+        params![Role::Admin.as_str(), email],
+    )?;
+    Ok(if updated > 0 {
+        AdminBootstrap::Promoted
+    } else {
+        AdminBootstrap::NoAccount
+    })
+}
+
+fn read_user_entity(row: &rusqlite::Row) -> Result<UserEntity> {
+    Ok(UserEntity {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        email: row.get(2)?,
+        password: row.get(3)?,
+        role: Role::parse(&row.get::<_, String>(4)?),
+        is_active: row.get(5)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::testing::temp_auth_db;
+
+    #[test]
+    fn test_user_role_defaults_and_updates() {
+        let (_data_dir, conn) = temp_auth_db();
+        conn.execute(
+            "INSERT INTO users (id, name, email, password) VALUES ('u1', 'Ada', 'ada@example.com', 'x')",
+            [],
+        )
+        .unwrap();
+
+        let user = read_user_by_email(&conn, "ada@example.com")
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.role, Role::User);
+
+        assert!(set_user_role(&conn, "u1", Role::Admin).unwrap());
+        assert!(!set_user_role(&conn, "missing", Role::Admin).unwrap());
+        assert_eq!(
+            read_user_by_id(&conn, "u1").unwrap().unwrap().role,
+            Role::Admin
+        );
+
+        assert!(user.is_active);
+        assert!(set_user_active(&conn, "u1", false).unwrap());
+        assert!(!read_user_by_id(&conn, "u1").unwrap().unwrap().is_active);
+    }
+
+    #[test]
+    fn test_bootstrap_list_and_delete_users() {
+        let (_data_dir, conn) = temp_auth_db();
+        let ada = create_user(&conn, "Ada", "ada@example.com", "x").unwrap();
+        let bob = create_user(&conn, "Bob", "bob@example.com", "x").unwrap();
+        conn.execute(
+            "INSERT INTO sync_log (user_id, synced_at, notes_received, notes_sent) VALUES (?, 0, 1, 1)",
+            params![bob],
+        )
+        .unwrap();
+
+        assert_eq!(
+            bootstrap_admin(&conn, "eve@example.com").unwrap(),
+            AdminBootstrap::NoAccount
+        );
+        assert_eq!(
+            bootstrap_admin(&conn, " ADA@example.com").unwrap(),
+            AdminBootstrap::Promoted
+        );
+        let users = list_users(&conn).unwrap();
+        let roles: Vec<(&str, Role)> = users.iter().map(|u| (u.email.as_str(), u.role)).collect();
+        assert_eq!(
+            roles,
+            vec![
+                ("ada@example.com", Role::Admin),
+                ("bob@example.com", Role::User)
+            ]
+        );
+
+        assert!(set_user_password(&conn, &ada, "new-hash").unwrap());
+        assert!(!set_user_password(&conn, "missing", "new-hash").unwrap());
+
+        assert!(delete_user(&conn, &bob).unwrap());
+        assert!(!delete_user(&conn, &bob).unwrap());
+        assert_eq!(list_users(&conn).unwrap().len(), 1);
+        let sync_rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM sync_log", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sync_rows, 0);
+    }
+
+    #[test]
+    fn test_bootstrap_admin_is_exact_and_one_time() {
+        let (_data_dir, conn) = temp_auth_db();
+        let ada = create_user(&conn, "Ada", " Ada@Example.com", "x").unwrap();
+        assert_eq!(
+            read_user_by_email(&conn, "ADA@example.com")
+                .unwrap()
+                .unwrap()
+                .email,
+            "ada@example.com"
+        );
+
+        assert_eq!(
+            bootstrap_admin(&conn, "ada@example.com").unwrap(),
+            AdminBootstrap::Promoted
+        );
+
+        // A demotion survives the next startup
+        set_user_role(&conn, &ada, Role::User).unwrap();
+        create_user(&conn, "Bob", "bob@example.com", "x").unwrap();
+        let bob = read_user_by_email(&conn, "bob@example.com")
+            .unwrap()
+            .unwrap();
+        set_user_role(&conn, &bob.id, Role::Admin).unwrap();
+        assert_eq!(
+            bootstrap_admin(&conn, "ada@example.com").unwrap(),
+            AdminBootstrap::AdminExists
+        );
+        assert_eq!(
+            read_user_by_id(&conn, &ada).unwrap().unwrap().role,
+            Role::User
+        );
+    }
+
+    #[test]
+    fn test_bootstrap_admin_refuses_case_variants() {
+        // Case variants can only exist in a users table older than the
+        // case-insensitive email column
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE users (id TEXT, name TEXT, email TEXT, password TEXT, role TEXT NOT NULL DEFAULT 'user', is_active INTEGER NOT NULL DEFAULT 1);
+             INSERT INTO users (id, name, email, password) VALUES ('u1', 'Ada', 'ada@example.com', 'x'), ('u2', 'Eve', 'ADA@example.com', 'x');",
+        )
+        .unwrap();
 
-    let user = stmt.query_row(params![user_id], |row| {
-        Ok(UserEntity {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            email: row.get(2)?,
-            password: row.get(3)?,
-        })
-    });
-
-    match user {
-        Ok(entity) => Ok(Some(entity.into())),
-        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-        Err(e) => Err(e),
+        assert_eq!(
+            bootstrap_admin(&conn, "ada@example.com").unwrap(),
+            AdminBootstrap::Ambiguous(2)
+        );
+        assert!(list_users(&conn)
+            .unwrap()
+            .iter()
+            .all(|u| u.role == Role::User));
     }
 }
@@ -0,0 +1,354 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use jot_core::{Note, SearchQuery};
+use tracing::{error, info, warn};
+
+use crate::{
+    db::{
+        open_user_db,
+        preferences::{self, DigestSubscription},
+    },
+    errors::DbError,
+    model::{preferences::DigestFrequency, user::User},
+    state::AppState,
+};
+
+/// Delivers rendered digest emails
+pub trait Mailer: Send + Sync {
+    fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), String>;
+}
+
+/// Hands messages to a local `sendmail`-compatible binary
+pub struct SendmailMailer {
+    pub command: String,
+    pub from: String,
+}
+
+impl Mailer for SendmailMailer {
+    fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), String> {
+        let message = format_message(&self.from, to, subject, body)?;
+        let mut child = Command::new(&self.command)
+            .arg("-t")
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", self.command, e))?;
+
+        child
+            .stdin
+            .take()
+            .ok_or_else(|| "sendmail stdin unavailable".to_string())?
+            .write_all(message.as_bytes())
+            .map_err(|e| format!("Failed to write message: {}", e))?;
+
+        let status = child
+            .wait()
+            .map_err(|e| format!("Failed to wait for {}: {}", self.command, e))?;
+        if !status.success() {
+            return Err(format!("{} exited with {}", self.command, status));
+        }
+        Ok(())
+    }
+}
+
+/// RFC 5322 message with CRLF line endings; header values containing a line
+/// break are refused, so they can't add headers or recipients for `sendmail -t`
+fn format_message(from: &str, to: &str, subject: &str, body: &str) -> Result<String, String> {
+    for (name, value) in [("From", from), ("To", to), ("Subject", subject)] {
+        if value.contains(['\r', '\n']) {
+            return Err(format!("{} header contains a line break", name));
+        }
+    }
+    let body = body.replace("\r\n", "\n").replace('\n', "\r\n");
+    Ok(format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}",
+        from, to, subject, body
+    ))
+}
+
+/// Settings for the scheduled digest job
+pub struct DigestConfig {
+    /// How often to look for users whose digest is due
+    pub interval: Duration,
+    pub mailer: Arc<dyn Mailer>,
+}
+
+impl DigestConfig {
+    /// Digests are enabled by setting `JOT_DIGEST_SENDMAIL`; returns `None` otherwise
+    pub fn from_env() -> Option<Self> {
+        let command = std::env::var("JOT_DIGEST_SENDMAIL").ok()?;
+        let from = std::env::var("JOT_DIGEST_FROM").unwrap_or_else(|_| "jot@localhost".to_string());
+        let interval = std::env::var("JOT_DIGEST_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(3600);
+
+        Some(Self {
+            interval: Duration::from_secs(interval),
+            mailer: Arc::new(SendmailMailer { command, from }),
+        })
+    }
+}
+
+/// Periodically email digests to users whose schedule is due
+pub fn spawn_digest_job(state: AppState, config: DigestConfig) {
+    info!(
+        "Digest emails enabled, checking every {}s",
+        config.interval.as_secs()
+    );
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(config.interval);
+        loop {
+            ticker.tick().await;
+
+            match send_due_digests(&state, config.mailer.clone(), Utc::now()).await {
+                Ok(sent) if sent > 0 => info!("Sent {} digest email(s)", sent),
+                Ok(_) => {}
+                Err(e) => error!("Digest job failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Send every digest that is due at `now`, returning how many emails went out
+pub async fn send_due_digests(
+    state: &AppState,
+    mailer: Arc<dyn Mailer>,
+    now: DateTime<Utc>,
+) -> Result<usize, DbError> {
+    let subscriptions = {
+        let conn = state
+            .auth_db
+            .lock()
+            .map_err(|_| DbError::Unknown("Failed to lock auth database".to_string()))?;
+        preferences::digest_subscriptions(&conn)?
+    };
+
+    let mut sent = 0;
+    for subscription in subscriptions.into_iter().filter(|s| is_due(s, now)) {
+        let Some(since) = subscription.frequency.period_start(now) else {
+            continue;
+        };
+
+        let notes = {
+            // Read under the user's lock, like sync and the maintenance job
+            let _user_lock = state.user_locks.lock(&subscription.user.id).await;
+            let (state, user) = (state.clone(), subscription.user.clone());
+            tokio::task::spawn_blocking(move || notes_created_since(&state, &user, since))
+                .await
+                .map_err(|e| DbError::Unknown(e.to_string()))??
+        };
+        if !notes.is_empty() {
+            let (subject, body) = render_digest(&subscription.user, subscription.frequency, &notes);
+            let (mailer, to) = (mailer.clone(), subscription.user.email.clone());
+            let result = tokio::task::spawn_blocking(move || mailer.send(&to, &subject, &body))
+                .await
+                .map_err(|e| DbError::Unknown(e.to_string()))?;
+            if let Err(e) = result {
+                // Leave it unmarked so the next run retries
+                warn!("Failed to send digest to {}: {}", subscription.user.id, e);
+                continue;
+            }
+            sent += 1;
+        }
+
+        let conn = state
+            .auth_db
+            .lock()
+            .map_err(|_| DbError::Unknown("Failed to lock auth database".to_string()))?;
+        preferences::mark_digest_sent(&conn, &subscription.user.id, now.timestamp())?;
+    }
+
+    Ok(sent)
+}
+
+fn is_due(subscription: &DigestSubscription, now: DateTime<Utc>) -> bool {
+    match (
+        subscription.frequency.period_start(now),
+        subscription.last_digest_at,
+    ) {
+        (None, _) => false,
+        (Some(_), None) => true,
+        (Some(start), Some(last)) => last <= start.timestamp(),
+    }
+}
+
+fn notes_created_since(
+    state: &AppState,
+    user: &User,
+    since: DateTime<Utc>,
+) -> Result<Vec<Note>, DbError> {
+    let path = state.user_db_path(&user.id);
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let conn = open_user_db(&path)?;
+    let query = SearchQuery {
+        created_from: Some(since.timestamp_millis()),
+        chronological: true,
+        ..Default::default()
+    };
+    jot_core::search_notes(&conn, &query).map_err(|e| DbError::Unknown(e.to_string()))
+}
+
+/// Plain-text digest: subject line and body listing each note's first line;
+/// end-to-end encrypted notes are only counted, as the server can't read them
+fn render_digest(user: &User, frequency: DigestFrequency, notes: &[Note]) -> (String, String) {
+    let period = match frequency {
+        DigestFrequency::Monthly => "month",
+        _ => "week",
+    };
+    let subject = format!(
+        "Your {} jot digest: {} note(s)",
+        frequency.as_str(),
+        notes.len()
+    );
+
+    let mut body = format!(
+        "Hi {},\n\nHere are the {} note(s) you jotted down in the past {}:\n\n",
+        user.name,
+        notes.len(),
+        period
+    );
+    for note in notes {
+        let created = DateTime::from_timestamp_millis(note.created_at)
+            .map(|dt| dt.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        if note.is_sealed() {
+            body.push_str(&format!("- {} (encrypted note)\n", created));
+            continue;
+        }
+        let first_line = note.content.lines().next().unwrap_or("").trim();
+        let preview: String = first_line.chars().take(80).collect();
+        let ellipsis = if preview.len() < first_line.len() {
+            "..."
+        } else {
+            ""
+        };
+
+        body.push_str(&format!("- {} {}{}", created, preview, ellipsis));
+        for tag in &note.tags {
+            body.push_str(&format!(" #{}", tag));
+        }
+        body.push('\n');
+    }
+
+    (subject, body)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    fn user() -> User {
+        User {
+            id: "u1".to_string(),
+            name: "Ada".to_string(),
+            email: "ada@example.com".to_string(),
+            role: Default::default(),
+            is_active: true,
+        }
+    }
+
+    #[test]
+    fn test_digest_due_after_period() {
+        let now = Utc::now();
+        let subscription = |frequency, last_digest_at| DigestSubscription {
+            user: user(),
+            frequency,
+            last_digest_at,
+        };
+
+        assert!(is_due(&subscription(DigestFrequency::Weekly, None), now));
+        assert!(!is_due(&subscription(DigestFrequency::Off, None), now));
+
+        let six_days_ago = (now - chrono::Duration::days(6)).timestamp();
+        assert!(!is_due(
+            &subscription(DigestFrequency::Weekly, Some(six_days_ago)),
+            now
+        ));
+        let eight_days_ago = (now - chrono::Duration::days(8)).timestamp();
+        assert!(is_due(
+            &subscription(DigestFrequency::Weekly, Some(eight_days_ago)),
+            now
+        ));
+        assert!(!is_due(
+            &subscription(DigestFrequency::Monthly, Some(eight_days_ago)),
+            now
+        ));
+
+        // Monthly digests follow calendar months: February is 28 days long
+        let march_first = "2026-03-01T08:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap().timestamp();
+        assert!(is_due(
+            &subscription(DigestFrequency::Monthly, Some(at("2026-02-01T08:00:00Z"))),
+            march_first
+        ));
+        assert!(!is_due(
+            &subscription(DigestFrequency::Monthly, Some(at("2026-02-02T08:00:00Z"))),
+            march_first
+        ));
+    }
+
+    #[test]
+    fn test_format_message_uses_crlf_and_refuses_line_breaks_in_headers() {
+        let message = format_message(
+            "jot@localhost",
+            "ada@example.com",
+            "Your weekly jot digest",
+            "Hi Ada,\n\n- note\n",
+        )
+        .unwrap();
+        assert_eq!(
+            message,
+            "From: jot@localhost\r\nTo: ada@example.com\r\nSubject: Your weekly jot digest\r\n\
+             Content-Type: text/plain; charset=utf-8\r\n\r\nHi Ada,\r\n\r\n- note\r\n"
+        );
+
+        for to in ["ada@example.com\nBcc: eve@example.com", "ada@example.com\r"] {
+            assert!(format_message("jot@localhost", to, "Digest", "").is_err());
+        }
+        assert!(
+            format_message("jot@localhost", "ada@example.com", "Digest\r\nBcc: eve", "").is_err()
+        );
+    }
+
+    #[test]
+    fn test_render_digest_lists_first_lines() {
+        let conn = jot_core::open_db_in_memory().unwrap();
+        let note = jot_core::create_note(
+            &conn,
+            "Ship the release\nwith notes",
+            vec!["work".to_string()],
+            None,
+        )
+        .unwrap();
+
+        let (subject, body) = render_digest(&user(), DigestFrequency::Weekly, &[note]);
+        assert_eq!(subject, "Your weekly jot digest: 1 note(s)");
+        assert!(body.starts_with("Hi Ada,"));
+        assert!(body.contains("Ship the release #work\n"));
+        assert!(!body.contains("with notes"));
+    }
+
+    #[test]
+    fn test_render_digest_hides_encrypted_notes() {
+        let conn = jot_core::open_db_in_memory().unwrap();
+        let sealed = format!("{}c2VhbGVk", jot_core::SEALED_PREFIX);
+        let note = jot_core::create_note(&conn, &sealed, vec![sealed.clone()], None).unwrap();
+
+        let (subject, body) = render_digest(&user(), DigestFrequency::Weekly, &[note]);
+        assert_eq!(subject, "Your weekly jot digest: 1 note(s)");
+        assert!(body.contains("(encrypted note)\n"));
+        assert!(!body.contains(jot_core::SEALED_PREFIX));
+    }
+}
@@ -42,6 +42,8 @@ pub enum RestError {
 pub enum DbError {
     #[error("Error communicating with database: {0}")]
     Unknown(String),
+    #[error("{0}")]
+    NewerSchema(String),
 }
 
 #[derive(Error, Debug, Clone)]
@@ -52,12 +54,20 @@ pub enum AuthError {
     UserNotFound,
     #[error("Token was not found")]
     TokenNotFound,
+    #[error("Admin access required")]
+    NotAdmin,
+    #[error("Registration is closed on this server")]
+    RegistrationClosed,
     #[error("Error while connecting to the database.")]
     DatabaseError,
     #[error("Error while creating a token.")]
     TokenCreation(String),
     #[error("Error while hashing password.")]
     PasswordHash(String),
+    #[error("Token is invalid or expired")]
+    InvalidToken,
+    #[error("Account is suspended")]
+    AccountSuspended,
 }
 
 // Implementation to convert AppError into a Response
@@ -68,7 +78,12 @@ impl IntoResponse for RestError {
                 StatusCode::BAD_REQUEST,
                 Json(AppErrorDto::new(&self.to_string()).with_status(StatusCode::BAD_REQUEST)),
             ),
-            RestError::Authorization(AuthError::TokenNotFound) => (
+            RestError::Authorization(
+                AuthError::TokenNotFound
+                | AuthError::NotAdmin
+                | AuthError::AccountSuspended
+                | AuthError::RegistrationClosed,
+            ) => (
                 StatusCode::FORBIDDEN,
                 Json(AppErrorDto::new(&self.to_string()).with_status(StatusCode::FORBIDDEN)),
             ),
@@ -76,6 +91,10 @@ impl IntoResponse for RestError {
                 StatusCode::UNAUTHORIZED,
                 Json(AppErrorDto::new(&self.to_string()).with_status(StatusCode::UNAUTHORIZED)),
             ),
+            RestError::Database(DbError::NewerSchema(_)) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(AppErrorDto::new(&self.to_string())),
+            ),
             RestError::Database(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(AppErrorDto::new("Internal server error")),
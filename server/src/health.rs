@@ -0,0 +1,64 @@
+//! Checks of the server's own health, read by the alert monitor
+
+use std::{path::Path, process::Command};
+
+/// Space on the filesystem holding a directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+impl DiskUsage {
+    /// Share of the filesystem in use, 0 to 100
+    pub fn used_percent(&self) -> u8 {
+        if self.total_bytes == 0 {
+            return 0;
+        }
+        let used = self.total_bytes.saturating_sub(self.available_bytes);
+        (used.saturating_mul(100) / self.total_bytes).min(100) as u8
+    }
+}
+
+/// Space on the filesystem holding `path`, as reported by `df`; None when
+/// it can't be read
+pub fn disk_usage(path: &Path) -> Option<DiskUsage> {
+    let output = Command::new("df").arg("-Pk").arg(path).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_df(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Read the POSIX output of `df -Pk`: a header, then the filesystem with its
+/// size, used and available space in KiB
+fn parse_df(output: &str) -> Option<DiskUsage> {
+    let line = output.lines().nth(1)?;
+    let mut fields = line.split_whitespace().skip(1);
+    let total: u64 = fields.next()?.parse().ok()?;
+    let _used = fields.next()?;
+    let available: u64 = fields.next()?.parse().ok()?;
+    Some(DiskUsage {
+        total_bytes: total * 1024,
+        available_bytes: available * 1024,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn test_parse_df() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/sda1         1000000    920000     80000      92% /var/lib/jot\n";
+        let usage = parse_df(output).unwrap();
+        assert_eq!(usage.total_bytes, 1_024_000_000);
+        assert_eq!(usage.available_bytes, 81_920_000);
+        assert_eq!(usage.used_percent(), 92);
+
+        assert_eq!(parse_df("Filesystem 1024-blocks\n"), None);
+    }
+}
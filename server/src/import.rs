@@ -0,0 +1,212 @@
+use rusqlite::Connection;
+
+use crate::{
+    db::{invite, user},
+    errors::ApplicationError,
+    jwt::{generate_password, hash_password, GENERATED_PASSWORD_LEN},
+};
+
+/// One account to create, from a `email,name` CSV line
+#[derive(Debug, PartialEq)]
+pub struct ImportRow {
+    pub email: String,
+    pub name: String,
+}
+
+/// How imported users get their first credential
+pub enum ImportMode {
+    /// Generate a password and print it for the operator to hand out
+    Password,
+    /// Create an invite link under this public base URL; users choose their own password
+    Invite { base_url: String },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ImportOutcome {
+    Password(String),
+    InviteLink(String),
+    /// An account with this email already exists and was left untouched
+    Exists,
+}
+
+/// Parse `email,name` lines; a leading `email,...` header row and blank
+/// lines are skipped, and a missing name defaults to the email's local part
+pub fn parse_users_csv(input: &str) -> Result<Vec<ImportRow>, String> {
+    let mut rows = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let fields = split_csv_line(line).map_err(|e| format!("Line {}: {}", index + 1, e))?;
+        let email = fields.first().map(|f| f.trim()).unwrap_or_default();
+        if index == 0 && email.eq_ignore_ascii_case("email") {
+            continue;
+        }
+        if !email.contains('@') {
+            return Err(format!("Line {}: '{}' is not an email", index + 1, email));
+        }
+
+        let name = fields
+            .get(1)
+            .map(|f| f.trim())
+            .filter(|f| !f.is_empty())
+            .unwrap_or_else(|| email.split('@').next().unwrap_or(email));
+        rows.push(ImportRow {
+            email: email.to_string(),
+            name: name.to_string(),
+        });
+    }
+    Ok(rows)
+}
+
+/// Split one CSV line, honouring double-quoted fields with `""` escapes
+fn split_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("unterminated quoted field".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// Create an account per row; existing emails are reported, not changed.
+/// The import is all or nothing: if any row fails, no account is created.
+pub fn import_users(
+    conn: &Connection,
+    rows: &[ImportRow],
+    mode: &ImportMode,
+    now: i64,
+) -> Result<Vec<ImportOutcome>, ApplicationError> {
+    let db_err = |e: rusqlite::Error| ApplicationError::Internal(e.to_string());
+    let tx = conn.unchecked_transaction().map_err(db_err)?;
+
+    let outcomes = rows
+        .iter()
+        .map(|row| {
+            if user::read_user_by_email(&tx, &row.email)
+                .map_err(db_err)?
+                .is_some()
+            {
+                return Ok(ImportOutcome::Exists);
+            }
+
+            match mode {
+                ImportMode::Password => {
+                    let password = generate_password(GENERATED_PASSWORD_LEN);
+                    let hash = hash_password(&password)
+                        .map_err(|e| ApplicationError::Internal(e.to_string()))?;
+                    user::create_user(&tx, &row.name, &row.email, &hash).map_err(db_err)?;
+                    Ok(ImportOutcome::Password(password))
+                }
+                ImportMode::Invite { base_url } => {
+                    let user_id = user::create_user(&tx, &row.name, &row.email, user::NO_PASSWORD)
+                        .map_err(db_err)?;
+                    let token = invite::create_invite(&tx, &user_id, now).map_err(db_err)?;
+                    Ok(ImportOutcome::InviteLink(format!(
+                        "{}/auth/invite/{}",
+                        base_url.trim_end_matches('/'),
+                        token
+                    )))
+                }
+            }
+        })
+        .collect::<Result<Vec<_>, ApplicationError>>()?;
+
+    tx.commit().map_err(db_err)?;
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::{auth::check_email_password, testing::temp_auth_db};
+
+    #[test]
+    fn test_parse_users_csv() {
+        let rows =
+            parse_users_csv("email,name\nada@example.com,\"Lovelace, Ada\"\n\nbob@example.com\n")
+                .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                ImportRow {
+                    email: "ada@example.com".to_string(),
+                    name: "Lovelace, Ada".to_string(),
+                },
+                ImportRow {
+                    email: "bob@example.com".to_string(),
+                    name: "bob".to_string(),
+                },
+            ]
+        );
+
+        assert!(parse_users_csv("not-an-email,Bob")
+            .unwrap_err()
+            .starts_with("Line 1"));
+    }
+
+    #[test]
+    fn test_import_users_creates_accounts_once() {
+        let (_data_dir, conn) = temp_auth_db();
+        let rows = parse_users_csv("ada@example.com,Ada\nbob@example.com,Bob").unwrap();
+
+        let outcomes = import_users(&conn, &rows[..1], &ImportMode::Password, 0).unwrap();
+        let password = match &outcomes[0] {
+            ImportOutcome::Password(password) => Some(password.clone()),
+            _ => None,
+        }
+        .unwrap();
+        assert!(check_email_password("ada@example.com", password, &conn).is_ok());
+
+        let mode = ImportMode::Invite {
+            base_url: "https://jot.example.com/".to_string(),
+        };
+        let outcomes = import_users(&conn, &rows, &mode, 0).unwrap();
+        assert_eq!(outcomes[0], ImportOutcome::Exists);
+        let link = match &outcomes[1] {
+            ImportOutcome::InviteLink(link) => Some(link),
+            _ => None,
+        }
+        .unwrap();
+        assert!(link.starts_with("https://jot.example.com/auth/invite/"));
+        // Invited accounts can't log in until they pick a password
+        assert!(check_email_password("bob@example.com", "".to_string(), &conn).is_err());
+    }
+
+    #[test]
+    fn test_import_users_is_all_or_nothing() {
+        let (_data_dir, conn) = temp_auth_db();
+        conn.execute_batch(
+            "CREATE TRIGGER fail_bob BEFORE INSERT ON users WHEN new.email = 'bob@example.com'
+             BEGIN SELECT RAISE(ABORT, 'no bob'); END;",
+        )
+        .unwrap();
+
+        let rows = parse_users_csv("ada@example.com,Ada\nbob@example.com,Bob").unwrap();
+        let mode = ImportMode::Invite {
+            base_url: "https://jot.example.com".to_string(),
+        };
+        assert!(import_users(&conn, &rows, &mode, 0).is_err());
+        assert!(user::read_user_by_email(&conn, "ada@example.com")
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,9 +1,13 @@
 use argon2::{
-    password_hash::{rand_core::OsRng, SaltString},
+    password_hash::{
+        rand_core::{OsRng, RngCore},
+        SaltString,
+    },
     Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
 };
-use jsonwebtoken::{EncodingKey, Header};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tracing::error;
 
 use crate::errors::AuthError;
@@ -16,7 +20,6 @@ pub struct TokenClaims {
     pub exp: usize,
 }
 
-#[allow(dead_code)]
 pub fn hash_password(password: &str) -> Result<String, AuthError> {
     // Create an instance of the Argon2 hasher
     let argon2 = Argon2::default();
@@ -47,20 +50,124 @@ pub fn verify_password(password: &str, hashed_password: &str) -> bool {
     is_valid
 }
 
-pub fn create_token(user_id: &str, secret: &[u8]) -> Result<String, AuthError> {
+/// Characters used in generated passwords (no look-alikes such as `0`/`O`, `1`/`l`)
+const PASSWORD_ALPHABET: &[u8] = b"abcdefghijkmnopqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Length of passwords generated for imported accounts and password resets
+pub const GENERATED_PASSWORD_LEN: usize = 16;
+
+/// Random password for accounts created by an operator
+pub fn generate_password(len: usize) -> String {
+    let mut password = String::with_capacity(len);
+    // Rejection sampling keeps every character equally likely
+    let limit = 256 - 256 % PASSWORD_ALPHABET.len();
+    while password.len() < len {
+        let byte = (OsRng.next_u32() & 0xff) as usize;
+        if byte < limit {
+            password.push(PASSWORD_ALPHABET[byte % PASSWORD_ALPHABET.len()] as char);
+        }
+    }
+    password
+}
+
+/// Random URL-safe token (hex encoded) for one-time links
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    to_hex(&bytes)
+}
+
+/// Tokens are stored hashed so a leaked database can't be used to redeem them
+pub fn hash_token(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+/// Compare secrets in time that depends only on their length, so response
+/// times don't reveal how much of a guess was right
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A signing secret and the key ID (`kid` header) that identifies it
+#[derive(Clone)]
+pub struct JwtKey {
+    pub id: String,
+    secret: Vec<u8>,
+}
+
+impl JwtKey {
+    /// The key ID is derived from the secret, so operators only manage secrets
+    pub fn new(secret: &str) -> Self {
+        let digest = Sha256::digest(secret.as_bytes());
+        let id = to_hex(&digest[..8]);
+        Self {
+            id,
+            secret: secret.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// The current signing key plus retired keys that still verify
+///
+/// Rotating `JOT_JWT_SECRET` while listing the old value in
+/// `JOT_JWT_PREVIOUS_SECRETS` keeps already issued tokens valid until they expire.
+#[derive(Clone)]
+pub struct JwtKeys {
+    signing: JwtKey,
+    previous: Vec<JwtKey>,
+}
+
+impl JwtKeys {
+    pub fn new(signing_secret: &str, previous_secrets: &[String]) -> Self {
+        Self {
+            signing: JwtKey::new(signing_secret),
+            previous: previous_secrets.iter().map(|s| JwtKey::new(s)).collect(),
+        }
+    }
+
+    fn all(&self) -> impl Iterator<Item = &JwtKey> {
+        std::iter::once(&self.signing).chain(&self.previous)
+    }
+}
+
+/// Access token lifetime when `JOT_TOKEN_TTL_HOURS` is not set
+pub const DEFAULT_TOKEN_TTL_HOURS: i64 = 7 * 24;
+
+/// Longest access token lifetime `JOT_TOKEN_TTL_HOURS` may ask for (five years)
+pub const MAX_TOKEN_TTL_HOURS: i64 = 5 * 365 * 24;
+
+/// Tolerated clock difference between client and server, in seconds
+const CLOCK_LEEWAY_SECS: u64 = 60;
+
+pub fn create_token(
+    user_id: &str,
+    keys: &JwtKeys,
+    ttl: chrono::Duration,
+) -> Result<String, AuthError> {
     let now = chrono::Utc::now();
     let iat = now.timestamp() as usize;
-    let exp = (now + chrono::Duration::days(7)).timestamp() as usize;
+    let exp = now
+        .checked_add_signed(ttl)
+        .ok_or_else(|| AuthError::TokenCreation("Token lifetime is out of range".to_string()))?
+        .timestamp() as usize;
     let claims = TokenClaims {
         sub: user_id.to_string(),
         exp,
         iat,
     };
 
+    let header = Header {
+        kid: Some(keys.signing.id.clone()),
+        ..Header::default()
+    };
     let token = jsonwebtoken::encode(
-        &Header::default(),
+        &header,
         &claims,
-        &EncodingKey::from_secret(secret),
+        &EncodingKey::from_secret(&keys.signing.secret),
     )
     .map_err(|e| {
         error!("{}", e);
@@ -70,10 +177,51 @@ pub fn create_token(user_id: &str, secret: &[u8]) -> Result<String, AuthError> {
     Ok(token)
 }
 
+/// Check a token's signature, expiry (`exp`) and issue time (`iat`) and return its claims
+///
+/// Tokens name their key in the `kid` header; tokens issued before key IDs
+/// existed are tried against every known key.
+pub fn decode_token(token: &str, keys: &JwtKeys) -> Result<TokenClaims, AuthError> {
+    let header = jsonwebtoken::decode_header(token).map_err(|_| AuthError::InvalidToken)?;
+
+    let candidates: Vec<&JwtKey> = match header.kid {
+        Some(kid) => keys.all().filter(|key| key.id == kid).collect(),
+        None => keys.all().collect(),
+    };
+
+    let mut validation = Validation::default();
+    validation.leeway = CLOCK_LEEWAY_SECS;
+    validation.set_required_spec_claims(&["exp", "sub"]);
+
+    let claims = candidates
+        .into_iter()
+        .find_map(|key| {
+            jsonwebtoken::decode::<TokenClaims>(
+                token,
+                &DecodingKey::from_secret(&key.secret),
+                &validation,
+            )
+            .ok()
+        })
+        .map(|data| data.claims)
+        .ok_or(AuthError::InvalidToken)?;
+
+    // A token issued in the future was not issued by us
+    let now = chrono::Utc::now().timestamp() as usize;
+    if claims.iat > now + CLOCK_LEEWAY_SECS as usize {
+        return Err(AuthError::InvalidToken);
+    }
+    Ok(claims)
+}
+
 #[cfg(test)]
 mod test {
 
-    use crate::jwt::{hash_password, verify_password};
+    use crate::errors::AuthError;
+    use crate::jwt::{
+        create_token, decode_token, hash_password, verify_password, JwtKeys,
+        DEFAULT_TOKEN_TTL_HOURS,
+    };
 
     #[test]
     fn test_token_claims() -> Result<(), Box<dyn std::error::Error>> {
@@ -83,4 +231,52 @@ mod test {
         assert!(valid);
         Ok(())
     }
+
+    fn ttl() -> chrono::Duration {
+        chrono::Duration::hours(DEFAULT_TOKEN_TTL_HOURS)
+    }
+
+    #[test]
+    fn test_decode_token() -> Result<(), Box<dyn std::error::Error>> {
+        let keys = JwtKeys::new("secret", &[]);
+        let token = create_token("user-1", &keys, ttl())?;
+        assert_eq!(decode_token(&token, &keys)?.sub, "user-1");
+        assert!(decode_token(&token, &JwtKeys::new("other secret", &[])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rotated_key_still_verifies() -> Result<(), Box<dyn std::error::Error>> {
+        let old = JwtKeys::new("old secret", &[]);
+        let token = create_token("user-1", &old, ttl())?;
+
+        let rotated = JwtKeys::new("new secret", &["old secret".to_string()]);
+        assert_eq!(decode_token(&token, &rotated)?.sub, "user-1");
+
+        // New tokens are signed with the new key only
+        let fresh = create_token("user-1", &rotated, ttl())?;
+        assert!(decode_token(&fresh, &old).is_err());
+
+        // Once the old key is dropped its tokens stop working
+        let retired = JwtKeys::new("new secret", &[]);
+        assert!(decode_token(&token, &retired).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_expired_token_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+        let keys = JwtKeys::new("secret", &[]);
+        let expired = create_token("user-1", &keys, chrono::Duration::hours(-1))?;
+        assert!(decode_token(&expired, &keys).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_out_of_range_ttl_fails_to_create_token() {
+        let keys = JwtKeys::new("secret", &[]);
+        assert!(matches!(
+            create_token("user-1", &keys, chrono::Duration::MAX),
+            Err(AuthError::TokenCreation(_))
+        ));
+    }
 }
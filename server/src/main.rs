@@ -1,18 +1,31 @@
 #![deny(clippy::expect_used, clippy::unwrap_used, clippy::panic)]
 #![warn(clippy::expect_used)]
 
-use db::open_auth_db;
+use alert::{spawn_health_monitor, AlertKind, Alerter};
+use clap::Parser;
+use cli::{admin_cmd, ServerArgs, ServerCommand};
+use db::{migrate::MigrationOutcome, open_auth_db, user::AdminBootstrap};
+use digest::{spawn_digest_job, DigestConfig};
 use dotenvy::dotenv;
 use errors::ApplicationError;
+use jwt::JwtKeys;
+use maintenance::spawn_maintenance_job;
 use router::setup_router;
-use std::env;
+use state::AppState;
+use std::{env, sync::Arc};
 use tokio::net::TcpListener;
-use tracing::info;
+use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod alert;
+mod cli;
 mod db;
+mod digest;
 mod errors;
+mod health;
+mod import;
 mod jwt;
+mod maintenance;
 mod model;
 mod router;
 mod state;
@@ -29,6 +42,14 @@ async fn main() -> Result<(), ApplicationError> {
 }
 
 async fn run() -> Result<(), ApplicationError> {
+    let args = ServerArgs::parse();
+
+    // Admin commands print their own output, so they run without the log subscriber
+    if let Some(ServerCommand::Admin(command)) = args.command {
+        dotenv().ok();
+        return admin_cmd(command, &data_dir_from_env());
+    }
+
     setup_tracing();
 
     let (host, port, jwt_secret, data_dir) = setup_env()?;
@@ -43,12 +64,69 @@ async fn run() -> Result<(), ApplicationError> {
         ApplicationError::Internal(format!("Failed to create users directory: {}", e))
     })?;
 
+    // Optional alerts about server errors, failed migrations and disk space
+    let alerter = Alerter::from_env().map(Arc::new);
+
+    if args.migrate_all {
+        migrate_user_dbs(&data_dir, alerter.as_deref())?;
+    }
+
     // Open auth database
-    let auth_db_path = data_dir.join("auth.db");
+    let auth_db_path = state::auth_db_path(&data_dir);
     let auth_db = open_auth_db(&auth_db_path)
         .map_err(|e| ApplicationError::Internal(format!("Failed to open auth database: {}", e)))?;
 
-    let app = setup_router(auth_db, &jwt_secret, data_dir);
+    if env::var_os("JOT_ADMIN_EMAILS").is_some() {
+        warn!(
+            "JOT_ADMIN_EMAILS is no longer read: admin access is a role stored per account. \
+             Grant it with `jot-server admin role <email> admin`, or set JOT_ADMIN_EMAIL for the first admin"
+        );
+    }
+
+    // Only an existing account is promoted: registration doesn't verify
+    // emails, so the address could otherwise be claimed by anyone
+    if let Some(ref email) = admin_email_from_env() {
+        match db::user::bootstrap_admin(&auth_db, email) {
+            Ok(AdminBootstrap::Promoted) => info!("{} is now an admin (JOT_ADMIN_EMAIL)", email),
+            Ok(AdminBootstrap::AdminExists) => {}
+            Ok(AdminBootstrap::NoAccount) => warn!(
+                "No account with email {}; register it and restart to make it an admin (JOT_ADMIN_EMAIL)",
+                email
+            ),
+            Ok(AdminBootstrap::Ambiguous(count)) => warn!(
+                "{} accounts match {} when ignoring case; not promoting any of them (JOT_ADMIN_EMAIL)",
+                count, email
+            ),
+            Err(e) => {
+                return Err(ApplicationError::Internal(format!(
+                    "Failed to grant admin access to {}: {}",
+                    email, e
+                )))
+            }
+        }
+    }
+
+    let app_state = AppState::new(auth_db, jwt_keys_from_env(&jwt_secret), data_dir)
+        .with_token_ttl(token_ttl_from_env())
+        .with_note_limits(note_limits_from_env())
+        .with_tombstone_horizon(tombstone_horizon_from_env())
+        .with_storage_quota(storage_quota_from_env())
+        .with_alerter(alerter.clone())
+        .with_registration(registration_from_env());
+
+    // Optional scheduled digest emails
+    if let Some(digest_config) = DigestConfig::from_env() {
+        spawn_digest_job(app_state.clone(), digest_config);
+    }
+
+    spawn_maintenance_job(app_state.clone(), maintenance_interval_from_env());
+
+    if let Some(alerter) = alerter {
+        info!("Alerts enabled");
+        spawn_health_monitor(alerter, app_state.data_dir.clone());
+    }
+
+    let app = setup_router(app_state);
 
     let address = format!("{}:{}", host, port);
     info!("Starting server on {}", address);
@@ -68,6 +146,38 @@ async fn run() -> Result<(), ApplicationError> {
     Ok(())
 }
 
+/// Migrate every user's notes database up front (`--migrate-all`), logging
+/// progress; databases that fail are left for their next sync to report,
+/// and raise an alert
+fn migrate_user_dbs(
+    data_dir: &std::path::Path,
+    alerter: Option<&Alerter>,
+) -> Result<(), ApplicationError> {
+    info!("Migrating user databases");
+    let failed =
+        db::migrate::migrate_all(data_dir, |done, total, user_id, outcome| match outcome {
+            MigrationOutcome::UpToDate => {}
+            MigrationOutcome::Migrated { from } => {
+                info!("[{}/{}] Migrated {} from v{}", done, total, user_id, from)
+            }
+            MigrationOutcome::Failed(e) => {
+                error!("[{}/{}] Failed to migrate {}: {}", done, total, user_id, e)
+            }
+        })
+        .map_err(|e| ApplicationError::Internal(format!("Failed to list user databases: {}", e)))?;
+    info!("User databases migrated ({} failed)", failed);
+    if let (Some(alerter), true) = (alerter, failed > 0) {
+        alerter.alert(
+            AlertKind::MigrationFailed,
+            format!(
+                "{} user database(s) failed to migrate at startup; see the server log.",
+                failed
+            ),
+        );
+    }
+    Ok(())
+}
+
 fn setup_tracing() {
     tracing_subscriber::registry()
         .with(
@@ -92,7 +202,100 @@ fn setup_env() -> Result<(String, String, String, std::path::PathBuf), Applicati
         .map_err(|e| ApplicationError::EnvError(e, "JOT_PORT".to_string()))?;
     let jwt_secret = std::env::var("JOT_JWT_SECRET")
         .map_err(|e| ApplicationError::EnvError(e, "JOT_JWT_SECRET".to_string()))?;
-    let data_dir = env::var("JOT_DATA_DIR").unwrap_or_else(|_| "./data".to_string());
 
-    Ok((host, port, jwt_secret, std::path::PathBuf::from(data_dir)))
+    Ok((host, port, jwt_secret, data_dir_from_env()))
+}
+
+/// Signing key from `JOT_JWT_SECRET`, plus retired keys from the comma-separated
+/// `JOT_JWT_PREVIOUS_SECRETS` that still verify existing tokens
+fn jwt_keys_from_env(jwt_secret: &str) -> JwtKeys {
+    let previous: Vec<String> = env::var("JOT_JWT_PREVIOUS_SECRETS")
+        .map(|secrets| {
+            secrets
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    JwtKeys::new(jwt_secret, &previous)
+}
+
+/// Access token lifetime from `JOT_TOKEN_TTL_HOURS`
+fn token_ttl_from_env() -> chrono::Duration {
+    let hours = env::var("JOT_TOKEN_TTL_HOURS")
+        .ok()
+        .and_then(|h| h.parse().ok())
+        .filter(|h| *h > 0)
+        .unwrap_or(jwt::DEFAULT_TOKEN_TTL_HOURS)
+        .min(jwt::MAX_TOKEN_TTL_HOURS);
+    chrono::Duration::hours(hours)
+}
+
+/// Limits on synced notes from `JOT_MAX_CONTENT_BYTES` and `JOT_MAX_TAGS`,
+/// each falling back to the core default
+fn note_limits_from_env() -> jot_core::NoteLimits {
+    let limit = |name: &str, default: usize| {
+        env::var(name)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(default)
+    };
+    jot_core::NoteLimits {
+        max_content_bytes: limit(
+            "JOT_MAX_CONTENT_BYTES",
+            jot_core::NoteLimits::DEFAULT_MAX_CONTENT_BYTES,
+        ),
+        max_tags: limit("JOT_MAX_TAGS", jot_core::NoteLimits::DEFAULT_MAX_TAGS),
+    }
+}
+
+fn tombstone_horizon_from_env() -> chrono::Duration {
+    let days = env::var("JOT_TOMBSTONE_HORIZON_DAYS")
+        .ok()
+        .and_then(|d| d.parse().ok())
+        .filter(|d| *d > 0)
+        .unwrap_or(state::DEFAULT_TOMBSTONE_HORIZON_DAYS);
+    chrono::Duration::days(days)
+}
+
+/// Per-account storage quota in bytes from `JOT_STORAGE_QUOTA_MB`, unlimited when unset
+fn storage_quota_from_env() -> Option<u64> {
+    env::var("JOT_STORAGE_QUOTA_MB")
+        .ok()
+        .and_then(|mb| mb.parse::<u64>().ok())
+        .filter(|mb| *mb > 0)
+        .map(|mb| mb * 1024 * 1024)
+}
+
+/// Self-service sign-up from `JOT_ALLOW_REGISTRATION`, closed unless set to
+/// `true` or `1`
+fn registration_from_env() -> bool {
+    env::var("JOT_ALLOW_REGISTRATION")
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1"))
+        .unwrap_or(false)
+}
+
+/// How often retention policies are applied, from `JOT_MAINTENANCE_INTERVAL_SECS`
+fn maintenance_interval_from_env() -> std::time::Duration {
+    let secs = env::var("JOT_MAINTENANCE_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|s| *s > 0)
+        .unwrap_or(maintenance::DEFAULT_MAINTENANCE_INTERVAL_SECS);
+    std::time::Duration::from_secs(secs)
+}
+
+/// Email of the account to make an admin, from `JOT_ADMIN_EMAIL`
+fn admin_email_from_env() -> Option<String> {
+    env::var("JOT_ADMIN_EMAIL")
+        .ok()
+        .map(|email| email.trim().to_string())
+        .filter(|email| !email.is_empty())
+}
+
+fn data_dir_from_env() -> std::path::PathBuf {
+    std::path::PathBuf::from(env::var("JOT_DATA_DIR").unwrap_or_else(|_| "./data".to_string()))
 }
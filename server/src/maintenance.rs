@@ -0,0 +1,194 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use jot_core::SearchQuery;
+use rusqlite::Connection;
+use tracing::{error, info};
+
+use crate::{
+    db::{auth, open_user_db, preferences},
+    errors::DbError,
+    model::preferences::RetentionPolicy,
+    state::AppState,
+};
+
+/// How often the maintenance job runs when `JOT_MAINTENANCE_INTERVAL_SECS` is not set
+pub const DEFAULT_MAINTENANCE_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+/// What a retention policy changed in one notes database
+#[derive(Debug, Default, PartialEq)]
+pub struct RetentionOutcome {
+    /// Notes archived for being older than the policy allows
+    pub archived: usize,
+    /// Tombstones hard-deleted
+    pub purged: usize,
+}
+
+/// Periodically apply every user's retention policy to their notes database
+/// and clear out expired device codes
+pub fn spawn_maintenance_job(state: AppState, interval: Duration) {
+    info!("Retention maintenance runs every {}s", interval.as_secs());
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = purge_expired_device_challenges(&state) {
+                error!("Device code cleanup failed: {}", e);
+            }
+            if let Err(e) = apply_retention_policies(&state).await {
+                error!("Maintenance job failed: {}", e);
+            }
+        }
+    });
+}
+
+/// Drop expired device authorization challenges, including consumed ones
+/// kept so far to turn replays away
+fn purge_expired_device_challenges(state: &AppState) -> Result<(), DbError> {
+    let conn = state
+        .auth_db
+        .lock()
+        .map_err(|_| DbError::Unknown("Failed to lock auth database".to_string()))?;
+    let removed = auth::delete_expired_device_challenges(&conn, Utc::now().timestamp())?;
+    if removed > 0 {
+        info!("Removed {} expired device code(s)", removed);
+    }
+    Ok(())
+}
+
+/// Apply the retention policy of each user that has one
+async fn apply_retention_policies(state: &AppState) -> Result<(), DbError> {
+    let policies = {
+        let conn = state
+            .auth_db
+            .lock()
+            .map_err(|_| DbError::Unknown("Failed to lock auth database".to_string()))?;
+        preferences::retention_policies(&conn)?
+    };
+
+    for (user_id, policy) in policies {
+        let path = state.user_db_path(&user_id);
+        if !path.exists() {
+            continue;
+        }
+
+        // Never remove notes while a sync of the same account is merging
+        let _user_lock = state.user_locks.lock(&user_id).await;
+        let result = tokio::task::spawn_blocking(move || {
+            let conn = open_user_db(&path)?;
+            apply_retention(&conn, &policy, Utc::now())
+        })
+        .await;
+
+        match result {
+            Ok(Ok(outcome)) if outcome != RetentionOutcome::default() => info!(
+                "Retention for user {}: {} note(s) archived, {} tombstone(s) purged",
+                user_id, outcome.archived, outcome.purged
+            ),
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => error!("Retention for user {} failed: {}", user_id, e),
+            Err(e) => error!("Retention for user {} panicked: {}", user_id, e),
+        }
+    }
+    Ok(())
+}
+
+/// Archive old notes and purge old tombstones from a notes database as
+/// `policy` asks
+///
+/// Old notes are archived rather than deleted, so devices move them out of
+/// their listings at the next sync and they can still be unarchived; pinned
+/// notes are left alone.
+pub fn apply_retention(
+    conn: &Connection,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> Result<RetentionOutcome, DbError> {
+    let db_err = |e: rusqlite::Error| DbError::Unknown(e.to_string());
+    let mut outcome = RetentionOutcome::default();
+
+    if let Some(days) = policy.archive_notes_after_days {
+        let query = SearchQuery {
+            created_to: Some((now - chrono::Duration::days(days.into())).timestamp_millis()),
+            ..Default::default()
+        };
+        let old: Vec<String> = jot_core::search_notes(conn, &query)
+            .map_err(db_err)?
+            .into_iter()
+            .filter(|note| !note.pinned)
+            .map(|note| note.id)
+            .collect();
+        outcome.archived = jot_core::with_transaction(conn, |tx| {
+            old.iter().try_fold(0, |archived, id| {
+                Ok(archived + usize::from(jot_core::archive_note(tx, id)?))
+            })
+        })
+        .map_err(db_err)?;
+    }
+
+    if let Some(days) = policy.purge_tombstones_after_days {
+        let deleted_before = (now - chrono::Duration::days(days.into())).timestamp_millis();
+        outcome.purged = jot_core::compact_db(conn, deleted_before)
+            .map_err(db_err)?
+            .purged;
+    }
+
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn test_apply_retention() {
+        let conn = jot_core::open_db_in_memory().unwrap();
+        let old = jot_core::create_note(&conn, "old", vec![], None).unwrap();
+        let pinned = jot_core::create_note(&conn, "old but pinned", vec![], None).unwrap();
+        jot_core::pin_note(&conn, &pinned.id).unwrap();
+
+        let archive_only = RetentionPolicy {
+            archive_notes_after_days: Some(365),
+            ..Default::default()
+        };
+        let outcome = apply_retention(&conn, &archive_only, Utc::now()).unwrap();
+        assert_eq!(outcome, RetentionOutcome::default());
+
+        // A year and a bit later only the unpinned note is archived, and only once
+        let later = Utc::now() + chrono::Duration::days(400);
+        let outcome = apply_retention(&conn, &archive_only, later).unwrap();
+        assert_eq!(
+            outcome,
+            RetentionOutcome {
+                archived: 1,
+                purged: 0
+            }
+        );
+        let note = jot_core::get_note_by_id(&conn, &old.id).unwrap().unwrap();
+        assert!(note.archived_at.is_some());
+        assert!(note.deleted_at.is_none());
+        assert_eq!(
+            apply_retention(&conn, &archive_only, later).unwrap(),
+            RetentionOutcome::default()
+        );
+
+        // Once deleted, its tombstone is purged when older than the policy allows
+        jot_core::soft_delete_note(&conn, &old.id).unwrap();
+        let purge = |days| RetentionPolicy {
+            purge_tombstones_after_days: Some(days),
+            ..Default::default()
+        };
+        assert_eq!(
+            apply_retention(&conn, &purge(500), later).unwrap().purged,
+            0
+        );
+        assert_eq!(apply_retention(&conn, &purge(90), later).unwrap().purged, 1);
+        assert!(jot_core::get_note_by_id(&conn, &old.id).unwrap().is_none());
+        assert!(jot_core::get_note_by_id(&conn, &pinned.id)
+            .unwrap()
+            .is_some());
+    }
+}
@@ -12,6 +12,21 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+/// A new account, created by `POST /auth/register`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RegisterRequest {
+    pub email: String,
+    pub name: String,
+    pub password: String,
+}
+
+/// Password chosen on the invite page
+#[derive(Debug, Clone, Deserialize)]
+pub struct InviteRequest {
+    pub password: String,
+    pub confirm: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct DeviceCodeRequest {
     pub device_code: String,
@@ -27,6 +42,8 @@ pub enum ChallengeResult {
     Success(String),
     NoChallenge,
     Pending,
+    /// The token was already handed out; the code can't be used again
+    Consumed,
 }
 
 use chrono::{DateTime, Utc};
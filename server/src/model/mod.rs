@@ -2,7 +2,10 @@ use schemars::JsonSchema;
 use serde::Deserialize;
 
 pub mod auth;
+pub mod preferences;
+pub mod stats;
 pub mod user;
+pub mod version;
 
 /// Struct for holding data from the user login form.
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -0,0 +1,91 @@
+use chrono::{DateTime, Months, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How often a user receives the digest of their recent notes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestFrequency {
+    #[default]
+    Off,
+    Weekly,
+    Monthly,
+}
+
+impl DigestFrequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DigestFrequency::Off => "off",
+            DigestFrequency::Weekly => "weekly",
+            DigestFrequency::Monthly => "monthly",
+        }
+    }
+
+    /// Stored values we don't recognize are treated as "off"
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "weekly" => DigestFrequency::Weekly,
+            "monthly" => DigestFrequency::Monthly,
+            _ => DigestFrequency::Off,
+        }
+    }
+
+    /// Start of the period a digest sent at `now` covers (a week, or a
+    /// calendar month), `None` when disabled
+    pub fn period_start(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            DigestFrequency::Off => None,
+            DigestFrequency::Weekly => Some(now - chrono::Duration::days(7)),
+            DigestFrequency::Monthly => now.checked_sub_months(Months::new(1)),
+        }
+    }
+}
+
+/// Clean-up the server's maintenance job applies to a user's notes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RetentionPolicy {
+    /// Hard-delete deleted notes this many days after they were deleted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purge_tombstones_after_days: Option<u32>,
+    /// Archive unpinned notes created more than this many days ago; devices
+    /// move them to their archive at the next sync (`expire_notes_after_days`
+    /// is still accepted from older clients)
+    #[serde(
+        default,
+        alias = "expire_notes_after_days",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub archive_notes_after_days: Option<u32>,
+}
+
+/// Account preferences exposed via `/me/preferences`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct UserPreferences {
+    /// Digest email schedule
+    #[serde(default)]
+    pub digest: DigestFrequency,
+    /// Server-side retention (nothing is removed when unset)
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn test_retention_accepts_the_old_expire_name() {
+        let policy: RetentionPolicy =
+            serde_json::from_str(r#"{"expire_notes_after_days": 30}"#).unwrap();
+        assert_eq!(policy.archive_notes_after_days, Some(30));
+
+        let json = serde_json::to_value(policy).unwrap();
+        assert_eq!(json, serde_json::json!({ "archive_notes_after_days": 30 }));
+        assert_eq!(
+            serde_json::from_value::<RetentionPolicy>(json).unwrap(),
+            policy
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Server-wide usage numbers for `/admin/stats`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ServerStats {
+    pub user_count: usize,
+    /// Size of the auth database in bytes
+    pub auth_db_size: u64,
+    pub users: Vec<UserStats>,
+    /// Daily sync totals for the last 30 days, oldest first
+    pub sync_volume: Vec<SyncVolume>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UserStats {
+    pub id: String,
+    pub email: String,
+    /// Live (not deleted) notes
+    pub note_count: i64,
+    /// Size of the user's notes database in bytes (0 before the first sync)
+    pub db_size: u64,
+    /// Total size of the files attached to the user's notes
+    pub attachment_bytes: u64,
+    /// Unix timestamp (seconds) of the user's last sync
+    pub last_active_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SyncVolume {
+    /// Day in `YYYY-MM-DD` (UTC)
+    pub day: String,
+    pub syncs: i64,
+    pub notes_received: i64,
+    pub notes_sent: i64,
+}
+
+/// Storage used by one account, for `/me/usage`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UserUsage {
+    /// Live (not deleted) notes
+    pub note_count: i64,
+    /// Size of the notes database in bytes (0 before the first sync)
+    pub db_size: u64,
+    /// Total size of the files attached to the notes
+    pub attachment_bytes: u64,
+    /// Bytes counted against the quota: `db_size` plus `attachment_bytes`
+    pub used_bytes: u64,
+    /// Per-account quota in bytes (`JOT_STORAGE_QUOTA_MB`), None when unlimited
+    pub quota_bytes: Option<u64>,
+}
@@ -1,10 +1,40 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What a user is allowed to do on the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    User,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Admin => "admin",
+        }
+    }
+
+    /// Stored values we don't recognize get no extra privileges
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "admin" => Role::Admin,
+            _ => Role::User,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct User {
     pub id: String,
     pub name: String,
     pub email: String,
+    pub role: Role,
+    /// `false` while the account is suspended
+    pub is_active: bool,
 }
 
 #[derive(Debug)]
@@ -13,6 +43,8 @@ pub struct UserEntity {
     pub name: String,
     pub email: String,
     pub password: String,
+    pub role: Role,
+    pub is_active: bool,
 }
 
 impl From<UserEntity> for User {
@@ -21,6 +53,8 @@ impl From<UserEntity> for User {
             id: val.id,
             name: val.name,
             email: val.email,
+            role: val.role,
+            is_active: val.is_active,
         }
     }
 }
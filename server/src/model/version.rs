@@ -0,0 +1,37 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What this server speaks, so clients can refuse to sync with a server
+/// that is too old or too new for them
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ServerVersion {
+    /// Server release (semver)
+    pub version: String,
+    /// Sync protocol versions accepted by `/sync`
+    pub sync_protocols: Vec<u32>,
+    pub features: ServerFeatures,
+}
+
+/// Optional capabilities enabled on this server
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ServerFeatures {
+    /// Attachment metadata is synced with notes
+    pub attachments: bool,
+    pub sharing: bool,
+    /// End-to-end encrypted notes
+    pub e2ee: bool,
+}
+
+impl ServerVersion {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            sync_protocols: vec![jot_core::SYNC_PROTOCOL_VERSION],
+            features: ServerFeatures {
+                attachments: true,
+                sharing: false,
+                e2ee: true,
+            },
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use aide::{
+    axum::{
+        routing::{delete_with, get_with, post_with, put_with},
+        ApiRouter, IntoApiResponse,
+    },
+    transform::TransformOperation,
+    OperationInput,
+};
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, State},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    db::{remove_user_db, stats, user},
+    errors::{AuthError, DbError, RestError, RestResult},
+    jwt::{generate_password, hash_password, GENERATED_PASSWORD_LEN},
+    model::{
+        stats::ServerStats,
+        user::{Role, User},
+    },
+    state::AppState,
+};
+
+/// An authenticated user whose role is [`Role::Admin`]
+///
+/// The role is re-read from the auth database on every request, so demoting
+/// an admin takes effect immediately.
+pub struct AdminUser(pub User);
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = RestError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> RestResult<Self> {
+        let user = parts
+            .extensions
+            .get::<User>()
+            .cloned()
+            .ok_or(RestError::Authorization(AuthError::TokenNotFound))?;
+
+        let conn = state
+            .auth_db
+            .lock()
+            .map_err(|_| RestError::Internal("Failed to lock auth database".to_string()))?;
+        let current = user::read_user_by_id(&conn, &user.id)
+            .map_err(|e| DbError::Unknown(e.to_string()))?
+            .ok_or(RestError::Authorization(AuthError::UserNotFound))?;
+
+        if !current.is_active {
+            return Err(RestError::Authorization(AuthError::AccountSuspended));
+        }
+        if current.role != Role::Admin {
+            return Err(RestError::Authorization(AuthError::NotAdmin));
+        }
+        Ok(AdminUser(current))
+    }
+}
+
+impl OperationInput for AdminUser {}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RoleRequest {
+    pub role: Role,
+}
+
+/// A freshly generated password, shown once
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ResetPasswordResponse {
+    pub password: String,
+}
+
+pub fn admin_routes(_app_state: AppState) -> ApiRouter<AppState> {
+    ApiRouter::new()
+        .api_route("/admin/stats", get_with(stats_get, stats_get_docs))
+        .api_route("/admin/users", get_with(users_get, users_get_docs))
+        .api_route(
+            "/admin/users/:id",
+            delete_with(user_delete, user_delete_docs),
+        )
+        .api_route(
+            "/admin/users/:id/reset-password",
+            post_with(user_reset_password_post, user_reset_password_post_docs),
+        )
+        .api_route(
+            "/admin/users/:id/role",
+            put_with(user_role_put, user_role_put_docs),
+        )
+        .api_route(
+            "/admin/users/:id/suspend",
+            post_with(user_suspend_post, user_suspend_post_docs),
+        )
+        .api_route(
+            "/admin/users/:id/reactivate",
+            post_with(user_reactivate_post, user_reactivate_post_docs),
+        )
+}
+
+async fn stats_get(State(state): State<AppState>, _admin: AdminUser) -> impl IntoApiResponse {
+    // The auth database is only locked while reading accounts, not while
+    // every user's notes database is opened
+    let result = state
+        .auth_db
+        .lock()
+        .map_err(|_| RestError::Internal("Failed to lock auth database".to_string()))
+        .and_then(|conn| {
+            Ok(stats::read_auth_stats(
+                &conn,
+                chrono::Utc::now().timestamp(),
+            )?)
+        })
+        .and_then(|auth| Ok(stats::add_user_usage(auth, &state.data_dir)?));
+
+    match result {
+        Ok(stats) => (StatusCode::OK, Json(stats)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn users_get(State(state): State<AppState>, _admin: AdminUser) -> impl IntoApiResponse {
+    let result = state
+        .auth_db
+        .lock()
+        .map_err(|_| RestError::Internal("Failed to lock auth database".to_string()))
+        .and_then(|conn| {
+            user::list_users(&conn)
+                .map_err(|e| RestError::Database(DbError::Unknown(e.to_string())))
+        });
+
+    match result {
+        Ok(users) => (StatusCode::OK, Json(users)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn user_delete(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    Path(user_id): Path<String>,
+) -> impl IntoApiResponse {
+    if admin.id == user_id {
+        return RestError::InvalidInput("Admins can't delete themselves".to_string())
+            .into_response();
+    }
+
+    // Wait for a sync in progress before removing the notes database under it
+    let _guard = state.user_locks.lock(&user_id).await;
+    let result = state
+        .auth_db
+        .lock()
+        .map_err(|_| RestError::Internal("Failed to lock auth database".to_string()))
+        .and_then(|conn| {
+            user::delete_user(&conn, &user_id)
+                .map_err(|e| RestError::Database(DbError::Unknown(e.to_string())))
+        })
+        .and_then(|deleted| {
+            if deleted {
+                remove_user_db(&state.user_db_path(&user_id)).map_err(|e| {
+                    RestError::Internal(format!("Failed to remove notes database: {}", e))
+                })?;
+            }
+            Ok(deleted)
+        });
+
+    match result {
+        Ok(true) => {
+            info!("{} deleted user {}", admin.email, user_id);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => {
+            RestError::InvalidInput(format!("User '{}' not found", user_id)).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn user_reset_password_post(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    Path(user_id): Path<String>,
+) -> impl IntoApiResponse {
+    let password = generate_password(GENERATED_PASSWORD_LEN);
+    let result = hash_password(&password)
+        .map_err(RestError::Authorization)
+        .and_then(|hash| {
+            let conn = state
+                .auth_db
+                .lock()
+                .map_err(|_| RestError::Internal("Failed to lock auth database".to_string()))?;
+            user::set_user_password(&conn, &user_id, &hash)
+                .map_err(|e| RestError::Database(DbError::Unknown(e.to_string())))
+        });
+
+    match result {
+        Ok(true) => {
+            info!("{} reset the password of user {}", admin.email, user_id);
+            (StatusCode::OK, Json(ResetPasswordResponse { password })).into_response()
+        }
+        Ok(false) => {
+            RestError::InvalidInput(format!("User '{}' not found", user_id)).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn user_role_put(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    Path(user_id): Path<String>,
+    Json(request): Json<RoleRequest>,
+) -> impl IntoApiResponse {
+    let result = state
+        .auth_db
+        .lock()
+        .map_err(|_| RestError::Internal("Failed to lock auth database".to_string()))
+        .and_then(|conn| {
+            user::set_user_role(&conn, &user_id, request.role)
+                .map_err(|e| RestError::Database(DbError::Unknown(e.to_string())))
+        });
+
+    match result {
+        Ok(true) => {
+            info!(
+                "{} set role of user {} to {}",
+                admin.email,
+                user_id,
+                request.role.as_str()
+            );
+            (StatusCode::OK, Json(request)).into_response()
+        }
+        Ok(false) => {
+            RestError::InvalidInput(format!("User '{}' not found", user_id)).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn user_suspend_post(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    Path(user_id): Path<String>,
+) -> impl IntoApiResponse {
+    set_active(&state, &admin, &user_id, false)
+}
+
+async fn user_reactivate_post(
+    State(state): State<AppState>,
+    AdminUser(admin): AdminUser,
+    Path(user_id): Path<String>,
+) -> impl IntoApiResponse {
+    set_active(&state, &admin, &user_id, true)
+}
+
+fn set_active(state: &AppState, admin: &User, user_id: &str, is_active: bool) -> Response {
+    if !is_active && admin.id == user_id {
+        return RestError::InvalidInput("Admins can't suspend themselves".to_string())
+            .into_response();
+    }
+
+    let result = state
+        .auth_db
+        .lock()
+        .map_err(|_| RestError::Internal("Failed to lock auth database".to_string()))
+        .and_then(|conn| {
+            user::set_user_active(&conn, user_id, is_active)
+                .map_err(|e| RestError::Database(DbError::Unknown(e.to_string())))
+        });
+
+    match result {
+        Ok(true) => {
+            info!(
+                "{} {} user {}",
+                admin.email,
+                if is_active {
+                    "reactivated"
+                } else {
+                    "suspended"
+                },
+                user_id
+            );
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => {
+            RestError::InvalidInput(format!("User '{}' not found", user_id)).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+fn stats_get_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Server usage statistics (admin only)")
+        .tag("admin")
+        .response::<200, Json<ServerStats>>()
+        .response_with::<403, (), _>(|res| res.description("Not an admin"))
+}
+
+fn users_get_docs(op: TransformOperation) -> TransformOperation {
+    op.description("List all accounts with their role and status (admin only)")
+        .tag("admin")
+        .response::<200, Json<Vec<User>>>()
+        .response_with::<403, (), _>(|res| res.description("Not an admin"))
+}
+
+fn user_delete_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Delete an account and its synced notes (admin only)")
+        .tag("admin")
+        .response_with::<204, (), _>(|res| res.description("Account deleted"))
+        .response_with::<403, (), _>(|res| res.description("Not an admin"))
+}
+
+fn user_reset_password_post_docs(op: TransformOperation) -> TransformOperation {
+    op.description(
+        "Replace a user's password with a generated one, returned once (admin only). Tokens already issued stay valid",
+    )
+    .tag("admin")
+    .response::<200, Json<ResetPasswordResponse>>()
+    .response_with::<403, (), _>(|res| res.description("Not an admin"))
+}
+
+fn user_role_put_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Change a user's role (admin only)")
+        .tag("admin")
+        .response::<200, Json<RoleRequest>>()
+        .response_with::<403, (), _>(|res| res.description("Not an admin"))
+}
+
+fn user_suspend_post_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Suspend an account: login and sync are refused, data is kept (admin only)")
+        .tag("admin")
+        .response_with::<204, (), _>(|res| res.description("Account suspended"))
+        .response_with::<403, (), _>(|res| res.description("Not an admin"))
+}
+
+fn user_reactivate_post_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Reactivate a suspended account (admin only)")
+        .tag("admin")
+        .response_with::<204, (), _>(|res| res.description("Account reactivated"))
+        .response_with::<403, (), _>(|res| res.description("Not an admin"))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::router::testing::{create_user, test_server};
+
+    #[tokio::test]
+    async fn test_admin_routes_require_the_admin_role() {
+        let (_data_dir, state, server) = test_server();
+        let (user_id, token) = create_user(&state, "ada@example.com");
+
+        for path in ["/admin/stats", "/admin/users"] {
+            server
+                .get(path)
+                .authorization_bearer(&token)
+                .await
+                .assert_status(StatusCode::FORBIDDEN);
+        }
+        server
+            .put(&format!("/admin/users/{}/role", user_id))
+            .authorization_bearer(&token)
+            .json(&json!({ "role": "admin" }))
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+
+        user::set_user_role(&state.auth_db.lock().unwrap(), &user_id, Role::Admin).unwrap();
+        server
+            .get("/admin/users")
+            .authorization_bearer(&token)
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    async fn test_suspended_user_is_rejected() {
+        let (_data_dir, state, server) = test_server();
+        let (user_id, token) = create_user(&state, "ada@example.com");
+        {
+            let conn = state.auth_db.lock().unwrap();
+            user::set_user_role(&conn, &user_id, Role::Admin).unwrap();
+            user::set_user_active(&conn, &user_id, false).unwrap();
+        }
+
+        for path in ["/admin/users", "/me/usage"] {
+            server
+                .get(path)
+                .authorization_bearer(&token)
+                .await
+                .assert_status(StatusCode::FORBIDDEN);
+        }
+        server
+            .post("/auth/refresh")
+            .authorization_bearer(&token)
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+    }
+}
@@ -10,7 +10,7 @@ use axum::{
     http::{header::SET_COOKIE, StatusCode},
     response::{AppendHeaders, IntoResponse},
     routing::get,
-    Form,
+    Extension, Form,
 };
 
 use axum_extra::response::Html;
@@ -18,13 +18,15 @@ use tower_sessions::cookie::{time::Duration, Cookie, SameSite};
 use tracing::error;
 
 use crate::{
-    db::auth,
-    errors::RestError,
-    jwt::create_token,
+    db::{auth, invite, user},
+    errors::{AuthError, DbError, RestError},
+    jwt::{create_token, hash_password},
     model::{
         auth::{
-            ChallengeResult, DeviceCodeRequest, DeviceStatusResponse, LoginRequest, LoginResponse,
+            ChallengeResult, DeviceCodeRequest, DeviceStatusResponse, InviteRequest, LoginRequest,
+            LoginResponse, RegisterRequest,
         },
+        user::User,
         LoginUserSchema,
     },
     state::AppState,
@@ -40,6 +42,10 @@ pub fn auth_routes_public() -> ApiRouter<AppState> {
     ApiRouter::new()
         .api_route("/auth/login", post_with(login_post, login_post_docs))
         .api_route("/auth/logout", post_with(logout_post, logout_post_docs))
+        .api_route(
+            "/auth/register",
+            post_with(register_post, register_post_docs),
+        )
         .api_route("/auth/device", post_with(device_post, device_post_docs))
         .api_route(
             "/auth/status/:code",
@@ -50,13 +56,16 @@ pub fn auth_routes_public() -> ApiRouter<AppState> {
             "/auth/page/:code",
             get(device_auth_get).post(device_auth_post),
         )
+        .route("/auth/invite/:token", get(invite_get).post(invite_post))
 }
 
 pub fn auth_routes_private(_app_state: AppState) -> ApiRouter<AppState> {
-    ApiRouter::new().api_route(
-        "/auth/device/:code",
-        delete_with(device_delete, device_delete_docs),
-    )
+    ApiRouter::new()
+        .api_route(
+            "/auth/device/:code",
+            delete_with(device_delete, device_delete_docs),
+        )
+        .api_route("/auth/refresh", post_with(refresh_post, refresh_post_docs))
 }
 
 pub async fn login_post(
@@ -80,7 +89,7 @@ pub async fn login_post(
         auth::check_email_password(&form_data.username, form_data.password.clone(), &auth_db);
 
     result
-        .and_then(|user| create_token(&user.id, state.jwt_secret.as_ref()))
+        .and_then(|user| create_token(&user.id, &state.jwt_keys, state.token_ttl))
         .map(|token| Json(LoginResponse { token }))
         .map_err(RestError::Authorization)
         .into_response()
@@ -99,6 +108,127 @@ pub fn login_post_docs(op: TransformOperation) -> TransformOperation {
         })
 }
 
+/// Self-service sign-up when `JOT_ALLOW_REGISTRATION` is set; the new
+/// account is logged in right away
+pub async fn register_post(
+    State(state): State<AppState>,
+    Json(request): Json<RegisterRequest>,
+) -> impl IntoApiResponse {
+    if !state.allow_registration {
+        return RestError::Authorization(AuthError::RegistrationClosed).into_response();
+    }
+
+    if let Err(e) = check_registration(&request) {
+        return e.into_response();
+    }
+
+    // Hashing is slow on purpose, so it runs before the auth database is locked
+    let password = request.password.clone();
+    let hash = match tokio::task::spawn_blocking(move || hash_password(&password)).await {
+        Ok(Ok(hash)) => hash,
+        Ok(Err(e)) => return RestError::Internal(e.to_string()).into_response(),
+        Err(e) => return RestError::Internal(e.to_string()).into_response(),
+    };
+
+    let auth_db = match state.auth_db.lock() {
+        Ok(db) => db,
+        Err(_) => {
+            return RestError::Internal("Failed to lock auth database".to_string()).into_response()
+        }
+    };
+
+    let result = register_user(&auth_db, &request, &hash).and_then(|user_id| {
+        create_token(&user_id, &state.jwt_keys, state.token_ttl).map_err(RestError::Authorization)
+    });
+
+    match result {
+        Ok(token) => (StatusCode::CREATED, Json(LoginResponse { token })).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub fn register_post_docs(op: TransformOperation) -> TransformOperation {
+    op.summary("Register user")
+        .description("Create an account and receive a session token for it")
+        .tag("Authentication")
+        .response_with::<201, Json<LoginResponse>, _>(|res| res.description("Account created"))
+        .response_with::<400, (), _>(|res| {
+            res.description(
+                "Invalid request - bad email, name or password, or email already registered",
+            )
+        })
+        .response_with::<403, (), _>(|res| {
+            res.description("Registration is closed (JOT_ALLOW_REGISTRATION is unset)")
+        })
+}
+
+/// Check the email, name and password of `request`
+fn check_registration(request: &RegisterRequest) -> Result<(), RestError> {
+    let email = user::normalize_email(&request.email);
+    if !email.contains('@') {
+        return Err(RestError::InvalidInput(format!(
+            "'{}' is not an email",
+            email
+        )));
+    }
+    if request.name.trim().is_empty() {
+        return Err(RestError::InvalidInput("Name is required".to_string()));
+    }
+    if request.password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(RestError::InvalidInput(format!(
+            "Password must have at least {} characters",
+            MIN_PASSWORD_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// Create the account for a checked `request` with its password already
+/// hashed, returning its ID
+fn register_user(
+    conn: &rusqlite::Connection,
+    request: &RegisterRequest,
+    password_hash: &str,
+) -> Result<String, RestError> {
+    let email = &user::normalize_email(&request.email);
+    let name = request.name.trim();
+    let db_err = |e: rusqlite::Error| RestError::Database(DbError::Unknown(e.to_string()));
+    if user::read_user_by_email(conn, email)
+        .map_err(db_err)?
+        .is_some()
+    {
+        return Err(RestError::InvalidInput(format!(
+            "An account with email '{}' already exists",
+            email
+        )));
+    }
+
+    user::create_user(conn, name, email, password_hash).map_err(db_err)
+}
+
+/// Sliding expiry: trade a still-valid token for a fresh one
+pub async fn refresh_post(
+    State(state): State<AppState>,
+    user_opt: Option<Extension<User>>,
+) -> impl IntoApiResponse {
+    let Some(Extension(user)) = user_opt else {
+        return RestError::Authorization(AuthError::TokenNotFound).into_response();
+    };
+
+    create_token(&user.id, &state.jwt_keys, state.token_ttl)
+        .map(|token| Json(LoginResponse { token }))
+        .map_err(RestError::Authorization)
+        .into_response()
+}
+
+pub fn refresh_post_docs(op: TransformOperation) -> TransformOperation {
+    op.summary("Refresh access token")
+        .description("Issue a new token with a full lifetime in exchange for a valid one")
+        .tag("Authentication")
+        .response::<200, Json<LoginResponse>>()
+        .response_with::<403, (), _>(|res| res.description("No valid token"))
+}
+
 pub async fn logout_post() -> impl IntoApiResponse {
     let cookie = Cookie::build(("token", ""))
         .path("/")
@@ -215,6 +345,7 @@ pub async fn device_status_get(
         }
         ChallengeResult::NoChallenge => StatusCode::NOT_FOUND.into_response(),
         ChallengeResult::Pending => StatusCode::ACCEPTED.into_response(),
+        ChallengeResult::Consumed => StatusCode::GONE.into_response(),
     }
 }
 
@@ -234,6 +365,9 @@ pub fn device_status_get_docs(op: TransformOperation) -> TransformOperation {
 			.response_with::<404, (), _>(|res|
 					res.description("No device challenge found for the provided code")
 			)
+			.response_with::<410, (), _>(|res|
+					res.description("The token for this code was already handed out")
+			)
 			.response_with::<500, (), _>(|res|
 					res.description("Database error occurred while checking device status")
 			)
@@ -263,7 +397,7 @@ pub async fn device_auth_post(
 
     match result {
         Ok(user) => {
-            let token = match create_token(&user.id, state.jwt_secret.as_ref()) {
+            let token = match create_token(&user.id, &state.jwt_keys, state.token_ttl) {
                 Ok(token) => token,
                 Err(err) => {
                     error!("{}", err);
@@ -272,7 +406,7 @@ pub async fn device_auth_post(
                 }
             };
 
-            let done = auth::add_token_to_device_challenge(&code, token, &auth_db);
+            let done = auth::add_token_to_device_challenge(&code, &user.id, token, &auth_db);
 
             match done {
                 Ok(true) => {
@@ -291,6 +425,10 @@ pub async fn device_auth_post(
                 }
             }
         }
+        Err(AuthError::AccountSuspended) => {
+            let html = handle_auth_error("This account is suspended");
+            Html(html)
+        }
         Err(_) => {
             let html = handle_auth_error("Invalid username or password");
             Html(html)
@@ -304,3 +442,152 @@ fn generate_error_page(code: &str, error: &str, email: &str) -> String {
         .replace("<<ERROR>>", error)
         .replace("<<EMAIL>>", email)
 }
+
+/// Shortest password accepted on sign-up and the invite page
+const MIN_PASSWORD_LEN: usize = 8;
+
+pub async fn invite_get(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> impl IntoResponse {
+    let valid = match state.auth_db.lock() {
+        Ok(auth_db) => invite::find_invite(&auth_db, &token, chrono::Utc::now().timestamp())
+            .map(|user| user.is_some())
+            .unwrap_or_else(|err| {
+                error!("{}", err);
+                false
+            }),
+        Err(_) => false,
+    };
+
+    let content = if valid {
+        invite_form(&token, None)
+    } else {
+        include_str!("../../static/invite_invalid.html").to_string()
+    };
+    Html(include_str!("../../static/invite.html").replace("<<CONTENT>>", &content))
+}
+
+pub async fn invite_post(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+    Form(data): Form<InviteRequest>,
+) -> impl IntoResponse {
+    // The token is echoed back into the form, so never render anything but hex
+    if !token.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Html(include_str!("../../static/invite_invalid.html").to_string());
+    }
+    if data.password.chars().count() < MIN_PASSWORD_LEN {
+        let error = format!("Use at least {} characters", MIN_PASSWORD_LEN);
+        return Html(invite_form(&token, Some(&error)));
+    }
+    if data.password != data.confirm {
+        return Html(invite_form(&token, Some("Passwords don't match")));
+    }
+
+    let hash = match hash_password(&data.password) {
+        Ok(hash) => hash,
+        Err(err) => {
+            error!("{}", err);
+            return Html(invite_form(&token, Some("Failed to set password")));
+        }
+    };
+
+    let auth_db = match state.auth_db.lock() {
+        Ok(db) => db,
+        Err(_) => return Html(invite_form(&token, Some("Failed to access database"))),
+    };
+
+    match invite::redeem_invite(&auth_db, &token, &hash, chrono::Utc::now().timestamp()) {
+        Ok(Some(_)) => Html(include_str!("../../static/invite_success.html").to_string()),
+        Ok(None) => Html(include_str!("../../static/invite_invalid.html").to_string()),
+        Err(err) => {
+            error!("{}", err);
+            Html(invite_form(&token, Some("Database error occurred")))
+        }
+    }
+}
+
+fn invite_form(token: &str, error: Option<&str>) -> String {
+    let error = error
+        .map(|e| format!(r#"<div class="error-message">{}</div>"#, e))
+        .unwrap_or_default();
+    include_str!("../../static/invite_form.html")
+        .replace("<<TOKEN>>", token)
+        .replace("<<ERROR>>", &error)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::db::testing::temp_auth_db;
+    use crate::jwt::decode_token;
+    use crate::router::testing::{create_user, test_server};
+
+    #[test]
+    fn test_register_user_validates_and_rejects_duplicates() {
+        let (_data_dir, conn) = temp_auth_db();
+        let request = |email: &str, name: &str, password: &str| RegisterRequest {
+            email: email.to_string(),
+            name: name.to_string(),
+            password: password.to_string(),
+        };
+
+        for bad in [
+            request("ada.example.com", "Ada", "correct horse"),
+            request("ada@example.com", " ", "correct horse"),
+            request("ada@example.com", "Ada", "short"),
+        ] {
+            assert!(matches!(
+                check_registration(&bad),
+                Err(RestError::InvalidInput(_))
+            ));
+        }
+
+        let hash = hash_password("correct horse").unwrap();
+        let ada = request(" ada@example.com ", "Ada", "correct horse");
+        check_registration(&ada).unwrap();
+        let user_id = register_user(&conn, &ada, &hash).unwrap();
+        let user =
+            auth::check_email_password("ada@example.com", "correct horse".to_string(), &conn)
+                .unwrap();
+        assert_eq!(user.id, user_id);
+        assert_eq!(user.name, "Ada");
+
+        for email in ["ada@example.com", " ADA@Example.com"] {
+            let duplicate = register_user(&conn, &request(email, "Ada 2", "another one"), &hash);
+            assert!(
+                matches!(duplicate, Err(RestError::InvalidInput(ref msg)) if msg.contains("already exists"))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_trades_a_valid_token_for_a_new_one() {
+        let (_data_dir, state, server) = test_server();
+        let (user_id, token) = create_user(&state, "ada@example.com");
+
+        let response = server
+            .post("/auth/refresh")
+            .authorization_bearer(&token)
+            .await;
+        response.assert_status_ok();
+        let refreshed = response.json::<LoginResponse>().token;
+        assert_eq!(
+            decode_token(&refreshed, &state.jwt_keys).unwrap().sub,
+            user_id
+        );
+
+        server
+            .post("/auth/refresh")
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+        server
+            .post("/auth/refresh")
+            .authorization_bearer("not a token")
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
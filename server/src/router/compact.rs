@@ -0,0 +1,77 @@
+use aide::{
+    axum::{routing::post_with, ApiRouter, IntoApiResponse},
+    transform::TransformOperation,
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Extension, Json};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::open_user_db,
+    errors::{AuthError, RestError, RestResult},
+    model::user::User,
+    state::AppState,
+};
+
+/// Result of compacting the caller's notes database
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct CompactResponse {
+    /// Tombstones that were hard-deleted
+    pub purged: usize,
+    /// Bytes the database shrank by
+    pub reclaimed_bytes: u64,
+    /// Tombstones younger than this many days are kept
+    pub horizon_days: i64,
+}
+
+pub fn compact_routes(_app_state: AppState) -> ApiRouter<AppState> {
+    ApiRouter::new().api_route("/me/compact", post_with(compact_post, compact_post_docs))
+}
+
+async fn compact_post(
+    State(state): State<AppState>,
+    user_opt: Option<Extension<User>>,
+) -> impl IntoApiResponse {
+    let Some(Extension(user)) = user_opt else {
+        return RestError::Authorization(AuthError::TokenNotFound).into_response();
+    };
+
+    match compact(&state, &user).await {
+        Ok(response) => (StatusCode::OK, Json(response)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn compact(state: &AppState, user: &User) -> RestResult<CompactResponse> {
+    // Never purge while a sync of the same account is merging
+    let _user_lock = state.user_locks.lock(&user.id).await;
+
+    let horizon = state.tombstone_horizon;
+    let path = state.user_db_path(&user.id);
+    if !path.exists() {
+        return Ok(CompactResponse {
+            purged: 0,
+            reclaimed_bytes: 0,
+            horizon_days: horizon.num_days(),
+        });
+    }
+
+    let conn = open_user_db(&path)?;
+    let deleted_before = (chrono::Utc::now() - horizon).timestamp_millis();
+    let result = jot_core::compact_db(&conn, deleted_before)
+        .map_err(|e| RestError::Internal(format!("Failed to compact database: {}", e)))?;
+
+    Ok(CompactResponse {
+        purged: result.purged,
+        reclaimed_bytes: result.reclaimed_bytes,
+        horizon_days: horizon.num_days(),
+    })
+}
+
+fn compact_post_docs(op: TransformOperation) -> TransformOperation {
+    op.description(
+        "Hard-delete tombstones older than the server's horizon (JOT_TOMBSTONE_HORIZON_DAYS) and report reclaimed space",
+    )
+    .tag("sync")
+    .response::<200, Json<CompactResponse>>()
+}
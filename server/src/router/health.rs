@@ -2,7 +2,11 @@ use aide::{
     axum::{routing::get_with, ApiRouter, IntoApiResponse},
     transform::TransformOperation,
 };
-use axum::{http::StatusCode, response::IntoResponse, Extension};
+use axum::{
+    http::StatusCode,
+    response::{AppendHeaders, IntoResponse},
+    Extension,
+};
 
 use crate::{
     errors::{AuthError, RestError},
@@ -10,6 +14,9 @@ use crate::{
     state::AppState,
 };
 
+/// Response header carrying the server's version
+const VERSION_HEADER: &str = "x-jot-version";
+
 fn health_routes_public() -> ApiRouter<AppState> {
     ApiRouter::new().api_route("/health/ping", get_with(ping, ping_docs))
 }
@@ -23,7 +30,8 @@ pub fn health_routes(app_state: AppState) -> ApiRouter<AppState> {
 }
 
 pub async fn ping() -> impl IntoApiResponse {
-    StatusCode::OK
+    let headers = AppendHeaders([(VERSION_HEADER, env!("CARGO_PKG_VERSION"))]);
+    (headers, StatusCode::OK)
 }
 
 pub fn ping_docs(op: TransformOperation) -> TransformOperation {
@@ -0,0 +1,78 @@
+use axum::{
+    extract::{Request, State},
+    http::header::AUTHORIZATION,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    db::user::read_user_by_id,
+    errors::{AuthError, RestError},
+    jwt::decode_token,
+    model::user::User,
+    state::AppState,
+};
+
+/// Tell the alerter about responses that failed with a server error
+pub async fn report_server_errors(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(alerter) = state.alerter.clone() else {
+        return next.run(request).await;
+    };
+
+    let route = format!("{} {}", request.method(), request.uri().path());
+    let response = next.run(request).await;
+    if response.status().is_server_error() {
+        alerter.record_server_error(&format!("{} ({})", route, response.status()));
+    }
+    response
+}
+
+/// Resolve a `Authorization: Bearer` token to an `Extension<User>`
+///
+/// Requests without a token pass through untouched (endpoints that need a user
+/// reject them); invalid tokens and suspended accounts are refused here.
+pub async fn authenticate(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(token) = bearer_token(&request) else {
+        return next.run(request).await;
+    };
+
+    match verify_user(&state, &token) {
+        Ok(user) => {
+            request.extensions_mut().insert(user);
+            next.run(request).await
+        }
+        Err(err) => RestError::Authorization(err).into_response(),
+    }
+}
+
+fn bearer_token(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(|token| token.trim().to_string())
+}
+
+fn verify_user(state: &AppState, token: &str) -> Result<User, AuthError> {
+    let claims = decode_token(token, &state.jwt_keys)?;
+
+    let conn = state.auth_db.lock().map_err(|_| AuthError::DatabaseError)?;
+    let user = read_user_by_id(&conn, &claims.sub)
+        .map_err(|_| AuthError::DatabaseError)?
+        .ok_or(AuthError::UserNotFound)?;
+
+    if !user.is_active {
+        return Err(AuthError::AccountSuspended);
+    }
+    Ok(user)
+}
@@ -3,20 +3,24 @@ use auth::auth_routes;
 use axum::{Extension, Router};
 use health::health_routes;
 use openapi::{api_docs, docs_routes};
-use rusqlite::Connection;
-use std::path::PathBuf;
 use std::sync::Arc;
 use tower_http::trace::TraceLayer;
 use tower_sessions::{MemoryStore, SessionManagerLayer};
 
 use crate::state::AppState;
 
+pub mod admin;
 pub mod auth;
+pub mod compact;
 pub mod health;
+pub mod middleware;
 pub mod openapi;
+pub mod preferences;
 pub mod sync;
+pub mod usage;
+pub mod version;
 
-pub fn setup_router(auth_db: Connection, jwt_secret: &str, data_dir: PathBuf) -> Router {
+pub fn setup_router(app_state: AppState) -> Router {
     aide::gen::on_error(|error| {
         println!("{error}");
     });
@@ -26,21 +30,69 @@ pub fn setup_router(auth_db: Connection, jwt_secret: &str, data_dir: PathBuf) ->
 
     let session_store = MemoryStore::default();
     let session_layer = SessionManagerLayer::new(session_store);
-    let app_state = AppState::new(auth_db, jwt_secret, data_dir);
     aide::gen::infer_responses(true);
 
     aide::gen::infer_responses(false);
 
-    // Note: Authentication is handled at the endpoint level by checking for Extension<User>
-    // No global auth middleware is applied - public endpoints simply don't require the extension
+    // Bearer tokens are resolved to an Extension<User> by middleware::authenticate;
+    // endpoints that need a user require the extension, public ones don't
     ApiRouter::new()
         .merge(health_routes(app_state.clone()))
+        .merge(version::version_routes())
+        .merge(admin::admin_routes(app_state.clone()))
         .merge(auth_routes(app_state.clone()))
         .merge(sync::sync_routes(app_state.clone()))
+        .merge(preferences::preferences_routes(app_state.clone()))
+        .merge(compact::compact_routes(app_state.clone()))
+        .merge(usage::usage_routes(app_state.clone()))
         .merge(docs_routes())
         .finish_api_with(&mut api, api_docs)
         .layer(Extension(Arc::new(api)))
+        .layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            middleware::authenticate,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            middleware::report_server_errors,
+        ))
         .layer(session_layer)
         .layer(TraceLayer::new_for_http())
         .with_state(app_state)
 }
+
+#[cfg(test)]
+pub mod testing {
+    #![allow(clippy::unwrap_used)]
+
+    use axum_test::TestServer;
+    use tempfile::TempDir;
+
+    use super::setup_router;
+    use crate::{
+        db::{testing::temp_auth_db, user},
+        jwt::{create_token, JwtKeys},
+        state::AppState,
+    };
+
+    /// The full router over a fresh data dir. The state shares the auth
+    /// database with the server, so tests can add accounts through it.
+    pub fn test_server() -> (TempDir, AppState, TestServer) {
+        let (data_dir, conn) = temp_auth_db();
+        let state = AppState::new(
+            conn,
+            JwtKeys::new("test secret", &[]),
+            data_dir.path().to_path_buf(),
+        );
+        let server = TestServer::new(setup_router(state.clone())).unwrap();
+        (data_dir, state, server)
+    }
+
+    /// Create an account and return its ID with a token for it
+    pub fn create_user(state: &AppState, email: &str) -> (String, String) {
+        let conn = state.auth_db.lock().unwrap();
+        let user_id = user::create_user(&conn, "Test", email, user::NO_PASSWORD).unwrap();
+        let token = create_token(&user_id, &state.jwt_keys, state.token_ttl).unwrap();
+        (user_id, token)
+    }
+}
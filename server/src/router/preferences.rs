@@ -0,0 +1,91 @@
+use aide::{
+    axum::{routing::get_with, ApiRouter, IntoApiResponse},
+    transform::TransformOperation,
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Extension, Json};
+
+use crate::{
+    db::preferences,
+    errors::{AuthError, RestError, RestResult},
+    model::{preferences::UserPreferences, user::User},
+    state::AppState,
+};
+
+pub fn preferences_routes(_app_state: AppState) -> ApiRouter<AppState> {
+    ApiRouter::new().api_route(
+        "/me/preferences",
+        get_with(preferences_get, preferences_get_docs)
+            .put_with(preferences_put, preferences_put_docs),
+    )
+}
+
+async fn preferences_get(
+    State(state): State<AppState>,
+    user_opt: Option<Extension<User>>,
+) -> impl IntoApiResponse {
+    let Some(Extension(user)) = user_opt else {
+        return RestError::Authorization(AuthError::TokenNotFound).into_response();
+    };
+
+    let result = with_auth_db(&state, |conn| {
+        Ok(preferences::get_preferences(conn, &user.id)?)
+    });
+
+    match result {
+        Ok(prefs) => (StatusCode::OK, Json(prefs)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn preferences_put(
+    State(state): State<AppState>,
+    user_opt: Option<Extension<User>>,
+    Json(prefs): Json<UserPreferences>,
+) -> impl IntoApiResponse {
+    let Some(Extension(user)) = user_opt else {
+        return RestError::Authorization(AuthError::TokenNotFound).into_response();
+    };
+
+    let retention = prefs.retention;
+    if retention.purge_tombstones_after_days == Some(0)
+        || retention.archive_notes_after_days == Some(0)
+    {
+        return RestError::InvalidInput("Retention periods must be at least one day".to_string())
+            .into_response();
+    }
+
+    let result = with_auth_db(&state, |conn| {
+        preferences::set_preferences(conn, &user.id, &prefs)?;
+        Ok(())
+    });
+
+    match result {
+        Ok(()) => (StatusCode::OK, Json(prefs)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+fn with_auth_db<T>(
+    state: &AppState,
+    f: impl FnOnce(&rusqlite::Connection) -> RestResult<T>,
+) -> RestResult<T> {
+    let conn = state
+        .auth_db
+        .lock()
+        .map_err(|_| RestError::Internal("Failed to lock auth database".to_string()))?;
+    f(&conn)
+}
+
+fn preferences_get_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Get account preferences")
+        .tag("preferences")
+        .response::<200, Json<UserPreferences>>()
+}
+
+fn preferences_put_docs(op: TransformOperation) -> TransformOperation {
+    op.description(
+        "Update account preferences (the weekly/monthly digest email, server-side retention)",
+    )
+    .tag("preferences")
+    .response::<200, Json<UserPreferences>>()
+}
@@ -1,13 +1,26 @@
 use aide::{
-    axum::{routing::post_with, ApiRouter, IntoApiResponse},
+    axum::{
+        routing::{get_with, post_with},
+        ApiRouter, IntoApiResponse,
+    },
     transform::TransformOperation,
 };
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Extension, Json};
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, StatusCode},
+    response::{AppendHeaders, IntoResponse},
+    Extension, Json,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio_util::io::{ReaderStream, SyncIoBridge};
+use tracing::warn;
 
 use crate::{
+    db::{open_user_db, snapshot, stats},
     errors::{RestError, RestResult},
-    model::user::User,
+    model::{stats::UserUsage, user::User},
     state::AppState,
 };
 
@@ -22,6 +35,47 @@ pub struct SyncRequestDto {
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct SyncResponseDto {
     pub notes: Vec<NoteDto>,
+    /// Notes edited on both sides since `last_sync`; the losing edit is included so it can be recovered
+    #[serde(default)]
+    pub conflicts: Vec<ConflictDto>,
+    /// Storage used after this sync, sent only when the server has a quota
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<UserUsage>,
+}
+
+/// Side of the sync whose version of a note was kept
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncSideDto {
+    Client,
+    Server,
+}
+
+impl From<jot_core::SyncSide> for SyncSideDto {
+    fn from(side: jot_core::SyncSide) -> Self {
+        match side {
+            jot_core::SyncSide::Client => SyncSideDto::Client,
+            jot_core::SyncSide::Server => SyncSideDto::Server,
+        }
+    }
+}
+
+/// A note whose concurrent edit was discarded by last-write-wins
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ConflictDto {
+    pub note_id: String,
+    pub winner: SyncSideDto,
+    pub losing_version: NoteDto,
+}
+
+impl From<jot_core::Conflict> for ConflictDto {
+    fn from(conflict: jot_core::Conflict) -> Self {
+        ConflictDto {
+            note_id: conflict.note_id,
+            winner: conflict.winner.into(),
+            losing_version: conflict.losing_version.into(),
+        }
+    }
 }
 
 /// Note DTO for API
@@ -31,9 +85,61 @@ pub struct NoteDto {
     pub content: String,
     pub tags: Vec<String>,
     pub date: Option<String>,
+    /// UTC offset the date was set in; older clients omit it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_tz: Option<String>,
     pub created_at: i64,
     pub updated_at: i64,
     pub deleted_at: Option<i64>,
+    /// Older clients omit it, which reads as unpinned
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Where the note was captured; older clients omit it
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub meta: BTreeMap<String, String>,
+    /// Metadata of attached files; their contents stay on the client
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<AttachmentDto>,
+    /// When the note was archived; older clients omit it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<i64>,
+}
+
+/// Attachment metadata DTO for API
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct AttachmentDto {
+    pub id: String,
+    pub filename: String,
+    /// SHA-256 of the contents as lowercase hex
+    pub hash: String,
+    pub size: i64,
+    pub created_at: i64,
+}
+
+impl From<jot_core::Attachment> for AttachmentDto {
+    fn from(attachment: jot_core::Attachment) -> Self {
+        AttachmentDto {
+            id: attachment.id,
+            filename: attachment.filename,
+            hash: attachment.hash,
+            size: attachment.size,
+            created_at: attachment.created_at,
+        }
+    }
+}
+
+impl From<AttachmentDto> for jot_core::Attachment {
+    fn from(dto: AttachmentDto) -> Self {
+        jot_core::Attachment {
+            id: dto.id,
+            filename: dto.filename,
+            hash: dto.hash,
+            size: dto.size,
+            created_at: dto.created_at,
+        }
+    }
 }
 
 impl From<jot_core::Note> for NoteDto {
@@ -43,9 +149,15 @@ impl From<jot_core::Note> for NoteDto {
             content: note.content,
             tags: note.tags,
             date: note.subject_date,
+            date_tz: note.subject_tz,
             created_at: note.created_at,
             updated_at: note.updated_at,
             deleted_at: note.deleted_at,
+            pinned: note.pinned,
+            status: note.status,
+            meta: note.meta,
+            attachments: note.attachments.into_iter().map(Into::into).collect(),
+            archived_at: note.archived_at,
         }
     }
 }
@@ -57,9 +169,15 @@ impl From<NoteDto> for jot_core::Note {
             content: dto.content,
             tags: dto.tags,
             subject_date: dto.date,
+            subject_tz: dto.date_tz,
             created_at: dto.created_at,
             updated_at: dto.updated_at,
             deleted_at: dto.deleted_at,
+            pinned: dto.pinned,
+            status: dto.status,
+            meta: dto.meta,
+            attachments: dto.attachments.into_iter().map(Into::into).collect(),
+            archived_at: dto.archived_at,
         }
     }
 }
@@ -92,15 +210,26 @@ async fn perform_sync(
     user: &User,
     request: SyncRequestDto,
 ) -> RestResult<SyncResponseDto> {
+    // Held until the response is built, so two syncs of one account never interleave
+    let _user_lock = state.user_locks.lock(&user.id).await;
+
     // Get user's database path
     let user_db_path = state.user_db_path(&user.id.to_string());
 
     // Open user's database
-    let conn = jot_core::open_db(&user_db_path)
-        .map_err(|e| RestError::Internal(format!("Failed to open user database: {}", e)))?;
+    let conn = open_user_db(&user_db_path)?;
 
     // Convert DTOs to core Note types
     let client_notes: Vec<jot_core::Note> = request.notes.into_iter().map(|n| n.into()).collect();
+    let received = client_notes.len();
+
+    // Reject oversized notes before touching the database
+    for note in &client_notes {
+        state
+            .note_limits
+            .validate(&note.content, &note.tags)
+            .map_err(|e| RestError::InvalidInput(format!("Note {}: {}", note.id, e)))?;
+    }
 
     // Process sync using core library
     let sync_request = jot_core::SyncRequest {
@@ -108,14 +237,49 @@ async fn perform_sync(
         last_sync: request.last_sync,
     };
 
-    let sync_response = jot_core::process_sync_request(&conn, sync_request)
+    let sync_response = jot_core::process_sync_request_with(&conn, sync_request, &state.observers)
         .map_err(|e| RestError::Internal(format!("Failed to process sync: {}", e)))?;
 
     // Convert back to DTOs
     let response_notes: Vec<NoteDto> = sync_response.notes.into_iter().map(|n| n.into()).collect();
+    let conflicts: Vec<ConflictDto> = sync_response
+        .conflicts
+        .into_iter()
+        .map(|c| c.into())
+        .collect();
+
+    // Usage statistics are best effort and never fail a sync
+    match state.auth_db.lock() {
+        Ok(auth_db) => {
+            if let Err(e) = stats::record_sync(
+                &auth_db,
+                &user.id,
+                chrono::Utc::now().timestamp(),
+                received,
+                response_notes.len(),
+            ) {
+                warn!("Failed to record sync for {}: {}", user.id, e);
+            }
+        }
+        Err(_) => warn!("Failed to lock auth database to record sync"),
+    }
+
+    // Lets the client warn before the account runs out of space
+    let usage = match state.storage_quota {
+        Some(quota) => match stats::user_usage(&user_db_path, Some(quota)) {
+            Ok(usage) => Some(usage),
+            Err(e) => {
+                warn!("Failed to read storage usage of {}: {}", user.id, e);
+                None
+            }
+        },
+        None => None,
+    };
 
     Ok(SyncResponseDto {
         notes: response_notes,
+        conflicts,
+        usage,
     })
 }
 
@@ -123,10 +287,94 @@ fn sync_notes_docs(op: TransformOperation) -> TransformOperation {
     op.description("Sync notes with server")
         .tag("sync")
         .response_with::<200, Json<SyncResponseDto>, _>(|res| {
-            res.example(SyncResponseDto { notes: vec![] })
+            res.example(SyncResponseDto {
+                notes: vec![],
+                conflicts: vec![],
+                usage: None,
+            })
         })
 }
 
+/// Response header with the server time (ms) the snapshot was taken at; use it as `last_sync`
+pub const SNAPSHOT_AT_HEADER: &str = "x-jot-snapshot-at";
+
+/// Bytes of compressed snapshot buffered between the compressing task and the response
+const SNAPSHOT_BUFFER: usize = 64 * 1024;
+
+/// Full copy of the caller's notes database for bootstrapping a fresh device
+///
+/// The copy goes to a scratch file and is gzipped while it is sent, so neither
+/// the database nor its compressed copy is held in memory.
+async fn sync_snapshot(
+    State(state): State<AppState>,
+    user_opt: Option<Extension<User>>,
+) -> impl IntoApiResponse {
+    let Some(Extension(user)) = user_opt else {
+        return RestError::Authorization(crate::errors::AuthError::TokenNotFound).into_response();
+    };
+
+    // Per request, as the copy is still being sent after the user lock is released
+    let scratch =
+        state
+            .data_dir
+            .join("users")
+            .join(format!("{}.{}.snapshot", user.id, ulid::Ulid::new()));
+    // Taken before the copy, so notes changed while copying are synced again
+    let snapshot_at = chrono::Utc::now().timestamp_millis();
+    let result = {
+        let _user_lock = state.user_locks.lock(&user.id).await;
+        let db_path = state.user_db_path(&user.id);
+        let scratch = scratch.clone();
+        tokio::task::spawn_blocking(move || {
+            open_user_db(&db_path).and_then(|conn| snapshot::backup_user_db(&conn, &scratch))
+        })
+        .await
+    };
+
+    match result {
+        Ok(Ok(())) => {
+            let (writer, reader) = tokio::io::duplex(SNAPSHOT_BUFFER);
+            let writer = SyncIoBridge::new(writer);
+            tokio::task::spawn_blocking(move || {
+                // Also fails when the client goes away mid-download
+                if let Err(e) = snapshot::compress_snapshot(&scratch, writer) {
+                    warn!("Snapshot for {} was cut short: {}", user.id, e);
+                }
+            });
+
+            let headers = AppendHeaders([
+                (header::CONTENT_TYPE, "application/gzip".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"notes.db.gz\"".to_string(),
+                ),
+                (
+                    header::HeaderName::from_static(SNAPSHOT_AT_HEADER),
+                    snapshot_at.to_string(),
+                ),
+            ]);
+            let body = Body::from_stream(ReaderStream::new(reader));
+            (StatusCode::OK, headers, body).into_response()
+        }
+        Ok(Err(e)) => RestError::Database(e).into_response(),
+        Err(e) => RestError::Internal(e.to_string()).into_response(),
+    }
+}
+
+fn sync_snapshot_docs(op: TransformOperation) -> TransformOperation {
+    op.description(
+        "Gzip-compressed SQLite copy of all notes (including tombstones) for a first sync; \
+         the x-jot-snapshot-at header is the last_sync to continue from",
+    )
+    .tag("sync")
+    .response_with::<200, (), _>(|res| res.description("notes.db.gz"))
+}
+
 pub fn sync_routes(_app_state: AppState) -> ApiRouter<AppState> {
-    ApiRouter::new().api_route("/sync", post_with(sync_notes, sync_notes_docs))
+    ApiRouter::new()
+        .api_route("/sync", post_with(sync_notes, sync_notes_docs))
+        .api_route(
+            "/sync/snapshot",
+            get_with(sync_snapshot, sync_snapshot_docs),
+        )
 }
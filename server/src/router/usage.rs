@@ -0,0 +1,36 @@
+use aide::{
+    axum::{routing::get_with, ApiRouter, IntoApiResponse},
+    transform::TransformOperation,
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Extension, Json};
+
+use crate::{
+    db::stats,
+    errors::{AuthError, RestError},
+    model::{stats::UserUsage, user::User},
+    state::AppState,
+};
+
+pub fn usage_routes(_app_state: AppState) -> ApiRouter<AppState> {
+    ApiRouter::new().api_route("/me/usage", get_with(usage_get, usage_get_docs))
+}
+
+async fn usage_get(
+    State(state): State<AppState>,
+    user_opt: Option<Extension<User>>,
+) -> impl IntoApiResponse {
+    let Some(Extension(user)) = user_opt else {
+        return RestError::Authorization(AuthError::TokenNotFound).into_response();
+    };
+
+    match stats::user_usage(&state.user_db_path(&user.id), state.storage_quota) {
+        Ok(usage) => (StatusCode::OK, Json(usage)).into_response(),
+        Err(e) => RestError::Database(e).into_response(),
+    }
+}
+
+fn usage_get_docs(op: TransformOperation) -> TransformOperation {
+    op.description("Storage used by the caller's notes and attachments, and the server's quota")
+        .tag("sync")
+        .response::<200, Json<UserUsage>>()
+}
@@ -0,0 +1,22 @@
+use aide::{
+    axum::{routing::get_with, ApiRouter, IntoApiResponse},
+    transform::TransformOperation,
+};
+use axum::{http::StatusCode, Json};
+
+use crate::{model::version::ServerVersion, state::AppState};
+
+pub fn version_routes() -> ApiRouter<AppState> {
+    ApiRouter::new().api_route("/version", get_with(version_get, version_get_docs))
+}
+
+async fn version_get() -> impl IntoApiResponse {
+    (StatusCode::OK, Json(ServerVersion::current()))
+}
+
+fn version_get_docs(op: TransformOperation) -> TransformOperation {
+    op.summary("Server version")
+        .description("Server release, accepted sync protocol versions and enabled features")
+        .tag("Health")
+        .response::<200, Json<ServerVersion>>()
+}
@@ -1,25 +1,141 @@
+use jot_core::{NoteLimits, ObserverRegistry};
 use rusqlite::Connection;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+
+use crate::alert::Alerter;
+use crate::jwt::{JwtKeys, DEFAULT_TOKEN_TTL_HOURS};
+
+/// Tombstone horizon when `JOT_TOMBSTONE_HORIZON_DAYS` is not set
+pub const DEFAULT_TOMBSTONE_HORIZON_DAYS: i64 = 90;
 
 #[derive(Clone)]
 pub struct AppState {
     pub auth_db: Arc<Mutex<Connection>>, // Auth database (users, device_auth)
-    pub jwt_secret: String,
-    pub data_dir: PathBuf, // Directory for per-user note databases
+    pub jwt_keys: JwtKeys,
+    pub token_ttl: chrono::Duration, // Lifetime of issued access tokens
+    pub data_dir: PathBuf,           // Directory for per-user note databases
+    pub observers: ObserverRegistry, // Notified about note changes applied during sync
+    pub note_limits: NoteLimits,     // Size limits applied to incoming notes
+    pub user_locks: UserLocks,       // Serializes writes to each user's notes database
+    pub tombstone_horizon: chrono::Duration, // `/me/compact` keeps tombstones younger than this
+    pub storage_quota: Option<u64>, // Bytes each account may use before sync warns, None for unlimited
+    pub alerter: Option<Arc<Alerter>>, // Tells the operator about repeated server errors, if configured
+    pub allow_registration: bool,      // Whether `/auth/register` creates accounts
 }
 
 impl AppState {
-    pub fn new(auth_db: Connection, jwt_secret: &str, data_dir: PathBuf) -> Self {
+    pub fn new(auth_db: Connection, jwt_keys: JwtKeys, data_dir: PathBuf) -> Self {
         Self {
             auth_db: Arc::new(Mutex::new(auth_db)),
-            jwt_secret: jwt_secret.to_string(),
+            jwt_keys,
+            token_ttl: chrono::Duration::hours(DEFAULT_TOKEN_TTL_HOURS),
             data_dir,
+            observers: ObserverRegistry::new(),
+            note_limits: NoteLimits::default(),
+            user_locks: UserLocks::default(),
+            tombstone_horizon: chrono::Duration::days(DEFAULT_TOMBSTONE_HORIZON_DAYS),
+            storage_quota: None,
+            alerter: None,
+            allow_registration: false,
         }
     }
 
+    /// Issue access tokens valid for `ttl`
+    pub fn with_token_ttl(mut self, ttl: chrono::Duration) -> Self {
+        self.token_ttl = ttl;
+        self
+    }
+
+    /// Reject synced notes over `limits`
+    pub fn with_note_limits(mut self, limits: NoteLimits) -> Self {
+        self.note_limits = limits;
+        self
+    }
+
+    /// Keep tombstones for `horizon` before `/me/compact` may purge them
+    pub fn with_tombstone_horizon(mut self, horizon: chrono::Duration) -> Self {
+        self.tombstone_horizon = horizon;
+        self
+    }
+
+    /// Report `quota` bytes per account in `/me/usage` and sync responses
+    /// (`JOT_STORAGE_QUOTA_MB`)
+    pub fn with_storage_quota(mut self, quota: Option<u64>) -> Self {
+        self.storage_quota = quota;
+        self
+    }
+
+    /// Send alerts about repeated server errors through `alerter`
+    pub fn with_alerter(mut self, alerter: Option<Arc<Alerter>>) -> Self {
+        self.alerter = alerter;
+        self
+    }
+
+    /// Let anyone create an account via `/auth/register`
+    /// (`JOT_ALLOW_REGISTRATION`)
+    pub fn with_registration(mut self, allow: bool) -> Self {
+        self.allow_registration = allow;
+        self
+    }
+
     /// Get path to a user's notes database
     pub fn user_db_path(&self, user_id: &str) -> PathBuf {
-        self.data_dir.join("users").join(format!("{}.db", user_id))
+        user_db_path(&self.data_dir, user_id)
+    }
+}
+
+/// One async lock per user, so concurrent syncs from the same account merge
+/// into its SQLite file one after another instead of interleaving
+#[derive(Clone, Default)]
+pub struct UserLocks(Arc<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>);
+
+impl UserLocks {
+    /// Wait until no other request holds `user_id`'s lock
+    pub async fn lock(&self, user_id: &str) -> OwnedMutexGuard<()> {
+        let lock = {
+            // The map is never left half-updated, so a poisoned lock is still usable
+            let mut locks = self.0.lock().unwrap_or_else(|e| e.into_inner());
+            // Forget locks nobody is holding or waiting on
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks.entry(user_id.to_string()).or_default().clone()
+        };
+        lock.lock_owned().await
+    }
+}
+
+/// Path to a user's notes database inside `data_dir`
+pub fn user_db_path(data_dir: &Path, user_id: &str) -> PathBuf {
+    data_dir.join("users").join(format!("{}.db", user_id))
+}
+
+/// Path to the auth database (users, device codes, preferences) inside `data_dir`
+pub fn auth_db_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("auth.db")
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_user_locks_serialize_per_user() {
+        let locks = UserLocks::default();
+        let guard = locks.lock("u1").await;
+
+        let same_user = tokio::time::timeout(Duration::from_millis(50), locks.lock("u1")).await;
+        assert!(same_user.is_err(), "second lock for u1 must wait");
+
+        let other_user = tokio::time::timeout(Duration::from_millis(50), locks.lock("u2")).await;
+        assert!(other_user.is_ok());
+
+        drop(guard);
+        let after_release = tokio::time::timeout(Duration::from_millis(50), locks.lock("u1")).await;
+        assert!(after_release.is_ok());
     }
 }